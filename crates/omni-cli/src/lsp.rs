@@ -0,0 +1,572 @@
+//! Language Server Protocol mode for `OmniContext`.
+//!
+//! `omnicontext lsp` serves a small, index-backed subset of LSP over stdio so
+//! any LSP-capable editor gets navigation without a bespoke extension:
+//!
+//! - `workspace/symbol` -- prefix search over the symbols table
+//! - `textDocument/definition` -- identifier under the cursor → symbol definitions
+//! - `textDocument/references` -- incoming dependency-graph edges of that symbol
+//!
+//! The server is read-only: it answers from the existing index and never
+//! re-indexes. Run `omnicontext index .` (or the daemon) to keep it fresh.
+//!
+//! Framing follows the LSP base protocol (`Content-Length` headers + JSON-RPC
+//! 2.0 bodies) and is implemented directly on `serde_json` to avoid pulling a
+//! full LSP framework into the CLI.
+
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use omni_core::types::{ChunkKind, Symbol};
+use serde_json::{json, Value};
+
+/// Maximum symbols returned for a `workspace/symbol` query.
+const MAX_WORKSPACE_SYMBOLS: usize = 100;
+
+/// Maximum candidate definitions considered for one identifier.
+const MAX_DEFINITIONS: usize = 20;
+
+/// JSON-RPC error code: the body is not valid JSON.
+const PARSE_ERROR: i64 = -32700;
+
+/// JSON-RPC error code: method not found.
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// JSON-RPC error code: server not initialized / request after shutdown.
+const INVALID_REQUEST: i64 = -32600;
+
+/// Run the LSP server over stdin/stdout until the client sends `exit`.
+pub fn run(repo_path: &Path) -> Result<()> {
+    let engine = omni_core::Engine::new(repo_path)?;
+    let mut server = LspServer {
+        engine,
+        repo_path: repo_path.to_path_buf(),
+        shutdown_requested: false,
+    };
+
+    let stdin = std::io::stdin();
+    let stdout = std::io::stdout();
+    let mut reader = stdin.lock();
+    let mut writer = stdout.lock();
+
+    while let Some(body) = read_message(&mut reader)? {
+        match server.respond(&body) {
+            Reply::Exit => break,
+            Reply::Silent => {}
+            Reply::Send(response) => write_message(&mut writer, &response)?,
+        }
+    }
+
+    Ok(())
+}
+
+/// What to do after one incoming message.
+enum Reply {
+    /// The client sent `exit`.
+    Exit,
+    /// A notification; nothing is sent back.
+    Silent,
+    /// Send this response.
+    Send(Value),
+}
+
+/// Request dispatcher over an opened engine.
+struct LspServer {
+    engine: omni_core::Engine,
+    repo_path: PathBuf,
+    shutdown_requested: bool,
+}
+
+impl LspServer {
+    /// Answer one message body.
+    ///
+    /// A body that is not valid JSON gets a parse error with a null id, as
+    /// JSON-RPC requires, and the server keeps reading: the framing is
+    /// intact, so the next message can still be served.
+    fn respond(&mut self, body: &[u8]) -> Reply {
+        let message: Value = match serde_json::from_slice(body) {
+            Ok(message) => message,
+            Err(e) => {
+                return Reply::Send(error_response(
+                    &Value::Null,
+                    PARSE_ERROR,
+                    &format!("invalid JSON-RPC body: {e}"),
+                ))
+            }
+        };
+        let method = message.get("method").and_then(Value::as_str).unwrap_or("");
+        if method == "exit" {
+            return Reply::Exit;
+        }
+
+        // Notifications (no id) never get a response.
+        let Some(id) = message.get("id").cloned() else {
+            return Reply::Silent;
+        };
+
+        Reply::Send(if self.shutdown_requested {
+            error_response(&id, INVALID_REQUEST, "server is shutting down")
+        } else if method == "shutdown" {
+            self.shutdown_requested = true;
+            json!({ "jsonrpc": "2.0", "id": id, "result": null })
+        } else {
+            let params = message.get("params").cloned().unwrap_or(Value::Null);
+            match self.handle(method, &params) {
+                Some(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+                None => error_response(&id, METHOD_NOT_FOUND, &format!("unsupported: {method}")),
+            }
+        })
+    }
+
+    /// Handle a request. Returns `None` for unsupported methods.
+    fn handle(&self, method: &str, params: &Value) -> Option<Value> {
+        match method {
+            "initialize" => Some(initialize_result()),
+            "workspace/symbol" => Some(self.workspace_symbol(params)),
+            "textDocument/definition" => Some(self.definition(params)),
+            "textDocument/references" => Some(self.references(params)),
+            _ => None,
+        }
+    }
+
+    fn workspace_symbol(&self, params: &Value) -> Value {
+        let query = params.get("query").and_then(Value::as_str).unwrap_or("");
+        let index = self.engine.metadata_index();
+        let symbols = index
+            .search_symbols_by_name(query, MAX_WORKSPACE_SYMBOLS)
+            .unwrap_or_default();
+
+        Value::Array(
+            symbols
+                .iter()
+                .filter_map(|sym| {
+                    let location = self.location_for(sym)?;
+                    let container = sym
+                        .fqn
+                        .rsplit_once(['.', ':', '/'])
+                        .map(|(parent, _)| parent.trim_end_matches(':'));
+                    Some(json!({
+                        "name": sym.name,
+                        "kind": symbol_kind(sym.kind),
+                        "location": location,
+                        "containerName": container,
+                    }))
+                })
+                .collect(),
+        )
+    }
+
+    fn definition(&self, params: &Value) -> Value {
+        let Some((path, name)) = self.identifier_at(params) else {
+            return Value::Null;
+        };
+        let targets = self.resolve_definitions(&path, &name);
+        Value::Array(
+            targets
+                .iter()
+                .filter_map(|s| self.location_for(s))
+                .collect(),
+        )
+    }
+
+    fn references(&self, params: &Value) -> Value {
+        let Some((path, name)) = self.identifier_at(params) else {
+            return Value::Null;
+        };
+        let include_declaration = params
+            .pointer("/context/includeDeclaration")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+
+        let index = self.engine.metadata_index();
+        let graph = self.engine.dep_graph();
+        let mut seen = std::collections::HashSet::new();
+        let mut locations = Vec::new();
+
+        for target in self.resolve_definitions(&path, &name) {
+            if include_declaration && seen.insert(target.id) {
+                locations.extend(self.location_for(&target));
+            }
+            let edges = graph.get_edges_for_symbol(target.id).unwrap_or_default();
+            for (source_id, _kind, direction) in edges {
+                if direction != "incoming" || !seen.insert(source_id) {
+                    continue;
+                }
+                if let Ok(Some(source)) = index.get_symbol_by_id(source_id) {
+                    locations.extend(self.location_for(&source));
+                }
+            }
+        }
+
+        Value::Array(locations)
+    }
+
+    /// Extract `(relative file path, identifier)` from a text-document position.
+    fn identifier_at(&self, params: &Value) -> Option<(PathBuf, String)> {
+        let uri = params.pointer("/textDocument/uri")?.as_str()?;
+        let line = params.pointer("/position/line")?.as_u64()?;
+        let character = params.pointer("/position/character")?.as_u64()?;
+
+        let abs_path = uri_to_path(uri)?;
+        let content = std::fs::read_to_string(&abs_path).ok()?;
+        let line_text = content.lines().nth(usize::try_from(line).ok()?)?;
+        let name = identifier_at(line_text, usize::try_from(character).ok()?)?;
+
+        let rel_path = abs_path
+            .strip_prefix(&self.repo_path)
+            .map_or_else(|_| abs_path.clone(), Path::to_path_buf);
+        Some((rel_path, name))
+    }
+
+    /// Symbols named exactly `name`, preferring ones defined in `from_file`.
+    fn resolve_definitions(&self, from_file: &Path, name: &str) -> Vec<Symbol> {
+        let index = self.engine.metadata_index();
+        let mut matches: Vec<Symbol> = index
+            .search_symbols_by_name(name, MAX_DEFINITIONS)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|s| s.name == name)
+            .collect();
+
+        let local_file_id = index
+            .get_file_by_path(from_file)
+            .ok()
+            .flatten()
+            .map(|f| f.id);
+        if let Some(file_id) = local_file_id {
+            if matches.iter().any(|s| s.file_id == file_id) {
+                matches.retain(|s| s.file_id == file_id);
+            }
+        }
+        matches
+    }
+
+    /// LSP `Location` for a symbol's definition line.
+    fn location_for(&self, sym: &Symbol) -> Option<Value> {
+        let file = self
+            .engine
+            .metadata_index()
+            .get_file_by_id(sym.file_id)
+            .ok()
+            .flatten()?;
+        let line = sym.line.saturating_sub(1);
+        Some(json!({
+            "uri": path_to_uri(&self.repo_path.join(&file.path)),
+            "range": {
+                "start": { "line": line, "character": 0 },
+                "end": { "line": line, "character": 0 },
+            },
+        }))
+    }
+}
+
+/// Capabilities advertised in the `initialize` response.
+fn initialize_result() -> Value {
+    json!({
+        "capabilities": {
+            "workspaceSymbolProvider": true,
+            "definitionProvider": true,
+            "referencesProvider": true,
+        },
+        "serverInfo": {
+            "name": "omnicontext",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+    })
+}
+
+/// Map an `OmniContext` chunk kind to an LSP `SymbolKind` number.
+fn symbol_kind(kind: ChunkKind) -> u32 {
+    match kind {
+        ChunkKind::Module | ChunkKind::Summary => 2,
+        ChunkKind::Class | ChunkKind::Impl => 5,
+        ChunkKind::Trait => 11,
+        ChunkKind::Function | ChunkKind::Test => 12,
+        ChunkKind::TopLevel => 13,
+        ChunkKind::Const => 14,
+        ChunkKind::TypeDef => 23,
+    }
+}
+
+/// The identifier (`[A-Za-z0-9_$]+`) touching LSP column `character`.
+///
+/// LSP positions count UTF-16 code units, so an emoji or other character
+/// outside the Basic Multilingual Plane earlier on the line takes two.
+fn identifier_at(line: &str, character: usize) -> Option<String> {
+    let chars: Vec<char> = line.chars().collect();
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_' || c == '$';
+
+    let mut pos = utf16_to_char_index(line, character);
+    // Cursor just past the end of an identifier still counts.
+    if (pos == chars.len() || !is_ident(chars[pos])) && pos > 0 && is_ident(chars[pos - 1]) {
+        pos -= 1;
+    }
+    if pos >= chars.len() || !is_ident(chars[pos]) {
+        return None;
+    }
+
+    let start = (0..pos)
+        .rev()
+        .take_while(|&i| is_ident(chars[i]))
+        .last()
+        .unwrap_or(pos);
+    let end = (pos..chars.len())
+        .take_while(|&i| is_ident(chars[i]))
+        .last()
+        .unwrap_or(pos);
+    Some(chars[start..=end].iter().collect())
+}
+
+/// Index into `line.chars()` of UTF-16 offset `utf16`, clamped to the line
+/// length. An offset inside a surrogate pair maps to the next character.
+fn utf16_to_char_index(line: &str, utf16: usize) -> usize {
+    let mut units = 0;
+    for (index, c) in line.chars().enumerate() {
+        if units >= utf16 {
+            return index;
+        }
+        units += c.len_utf16();
+    }
+    line.chars().count()
+}
+
+/// Convert an absolute path into a `file://` URI.
+fn path_to_uri(path: &Path) -> String {
+    let raw = path.to_string_lossy().replace('\\', "/");
+    let mut encoded = String::with_capacity(raw.len());
+    for b in raw.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' | b':' => {
+                encoded.push(b as char);
+            }
+            _ => {
+                const HEX: &[u8; 16] = b"0123456789ABCDEF";
+                encoded.push('%');
+                encoded.push(HEX[usize::from(b >> 4)] as char);
+                encoded.push(HEX[usize::from(b & 0x0F)] as char);
+            }
+        }
+    }
+    if encoded.starts_with('/') {
+        format!("file://{encoded}")
+    } else {
+        // Windows drive paths (`C:/...`) need the extra slash.
+        format!("file:///{encoded}")
+    }
+}
+
+/// Convert a `file://` URI back into a filesystem path.
+fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let rest = uri.strip_prefix("file://")?;
+    let bytes = rest.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok()?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    let mut path = String::from_utf8(decoded).ok()?;
+    // `file:///C:/x` → `C:/x` on Windows-style URIs.
+    if path.len() > 2 && path.as_bytes()[2] == b':' && path.starts_with('/') {
+        path.remove(0);
+    }
+    Some(PathBuf::from(path))
+}
+
+/// Read one LSP message body. Returns `None` on clean EOF.
+fn read_message(reader: &mut impl BufRead) -> Result<Option<Vec<u8>>> {
+    let mut content_length: Option<usize> = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            if content_length.is_some() {
+                break;
+            }
+            continue;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = Some(value.trim().parse().context("invalid Content-Length")?);
+            }
+        }
+    }
+
+    let len = content_length.unwrap_or(0);
+    let mut body = vec![0u8; len];
+    reader.read_exact(&mut body)?;
+    Ok(Some(body))
+}
+
+/// Write one LSP message with its `Content-Length` header.
+fn write_message(writer: &mut impl Write, message: &Value) -> Result<()> {
+    let body = serde_json::to_string(message)?;
+    write!(writer, "Content-Length: {}\r\n\r\n{body}", body.len())?;
+    writer.flush()?;
+    Ok(())
+}
+
+fn error_response(id: &Value, code: i64, message: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message },
+    })
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identifier_at() {
+        let line = "    let cfg = load_config(path);";
+        assert_eq!(identifier_at(line, 14).as_deref(), Some("load_config"));
+        assert_eq!(identifier_at(line, 20).as_deref(), Some("load_config"));
+        // Just past the end of an identifier
+        assert_eq!(identifier_at(line, 25).as_deref(), Some("load_config"));
+        assert_eq!(identifier_at(line, 0), None);
+        assert_eq!(identifier_at("", 3), None);
+    }
+
+    #[test]
+    fn test_identifier_at_counts_utf16_units() {
+        // Each emoji is two UTF-16 units: `ab` ends at unit 7, `cd` starts at 8.
+        let line = "\u{1F600}\u{1F600} ab cd";
+        assert_eq!(identifier_at(line, 7).as_deref(), Some("ab"));
+        assert_eq!(identifier_at(line, 8).as_deref(), Some("cd"));
+        assert_eq!(utf16_to_char_index(line, 100), 8);
+    }
+
+    #[test]
+    fn test_uri_round_trip() {
+        let path = Path::new("/repo/src/my file.rs");
+        let uri = path_to_uri(path);
+        assert_eq!(uri, "file:///repo/src/my%20file.rs");
+        assert_eq!(uri_to_path(&uri).unwrap(), path);
+        assert_eq!(
+            uri_to_path("file:///C:/repo/lib.rs").unwrap(),
+            PathBuf::from("C:/repo/lib.rs")
+        );
+        assert!(uri_to_path("https://example.com").is_none());
+    }
+
+    #[test]
+    fn test_message_framing_round_trip() {
+        let msg = json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize" });
+        let mut buf = Vec::new();
+        write_message(&mut buf, &msg).unwrap();
+
+        let mut reader = std::io::Cursor::new(buf);
+        let decoded = read_message(&mut reader).unwrap().unwrap();
+        assert_eq!(serde_json::from_slice::<Value>(&decoded).unwrap(), msg);
+        assert!(read_message(&mut reader).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_initialize_advertises_capabilities() {
+        let caps = initialize_result();
+        assert_eq!(caps["capabilities"]["definitionProvider"], true);
+        assert_eq!(caps["capabilities"]["referencesProvider"], true);
+        assert_eq!(caps["capabilities"]["workspaceSymbolProvider"], true);
+    }
+
+    /// A server over a fresh index of `dir` holding one symbol,
+    /// `load_config`, defined on line 1 of `src/lib.rs`.
+    fn server_with_symbol(dir: &Path) -> LspServer {
+        use omni_core::types::{FileInfo, Language};
+
+        std::env::set_var("OMNI_SKIP_MODEL_DOWNLOAD", "1");
+        std::env::set_var("OMNI_DISABLE_RERANKER", "1");
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        std::fs::write(
+            dir.join("src/lib.rs"),
+            "fn load_config() {}\nfn main() { load_config(); }\n",
+        )
+        .unwrap();
+
+        let engine = omni_core::Engine::with_config(omni_core::Config::defaults(dir)).unwrap();
+        let index = engine.metadata_index();
+        let file_id = index
+            .upsert_file(&FileInfo {
+                id: 0,
+                path: PathBuf::from("src/lib.rs"),
+                language: Language::Rust,
+                content_hash: "hash".to_string(),
+                size_bytes: 48,
+            })
+            .unwrap();
+        index
+            .insert_symbol(&Symbol {
+                id: 0,
+                name: "load_config".to_string(),
+                fqn: "lib::load_config".to_string(),
+                kind: ChunkKind::Function,
+                file_id,
+                line: 1,
+                chunk_id: None,
+            })
+            .unwrap();
+        LspServer {
+            engine,
+            repo_path: dir.to_path_buf(),
+            shutdown_requested: false,
+        }
+    }
+
+    #[test]
+    fn test_handle_initialize() {
+        let dir = tempfile::tempdir().unwrap();
+        let server = server_with_symbol(dir.path());
+        let result = server.handle("initialize", &Value::Null).unwrap();
+        assert_eq!(result, initialize_result());
+        assert!(server.handle("textDocument/hover", &Value::Null).is_none());
+    }
+
+    #[test]
+    fn test_handle_definition() {
+        let dir = tempfile::tempdir().unwrap();
+        let server = server_with_symbol(dir.path());
+        let uri = path_to_uri(&dir.path().join("src/lib.rs"));
+        let params = json!({
+            "textDocument": { "uri": uri },
+            "position": { "line": 1, "character": 14 },
+        });
+
+        let result = server.handle("textDocument/definition", &params).unwrap();
+        let locations = result.as_array().unwrap();
+        assert_eq!(locations.len(), 1);
+        assert_eq!(locations[0]["uri"], uri);
+        assert_eq!(locations[0]["range"]["start"]["line"], 0);
+    }
+
+    #[test]
+    fn test_invalid_message_gets_parse_error_and_server_continues() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut server = server_with_symbol(dir.path());
+
+        let Reply::Send(error) = server.respond(b"{\"jsonrpc\": \"2.0\", \"id\": 1,") else {
+            panic!("expected an error response");
+        };
+        assert_eq!(error["error"]["code"], PARSE_ERROR);
+        assert_eq!(error["id"], Value::Null);
+
+        let request = json!({ "jsonrpc": "2.0", "id": 2, "method": "initialize" });
+        let Reply::Send(response) = server.respond(request.to_string().as_bytes()) else {
+            panic!("expected a response");
+        };
+        assert_eq!(response["id"], 2);
+        assert_eq!(response["result"], initialize_result());
+    }
+}
@@ -3,6 +3,7 @@
 //! Command-line interface for indexing, searching, and managing
 //! `OmniContext` indexes.
 
+mod lsp;
 mod orchestrator;
 
 use std::time::Instant;
@@ -17,6 +18,48 @@ enum ExportFormat {
     Scip,
 }
 
+/// Output format for `omnicontext context`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ContextOutputFormat {
+    /// Markdown with file headers, fenced code, and line numbers.
+    Markdown,
+    /// XML tags grouping chunks by file and priority.
+    Xml,
+    /// Compact single-line JSON.
+    Json,
+    /// Raw code with minimal file dividers.
+    Compact,
+}
+
+impl From<ContextOutputFormat> for omni_core::search::ContextFormat {
+    fn from(format: ContextOutputFormat) -> Self {
+        match format {
+            ContextOutputFormat::Markdown => Self::AnnotatedMarkdown,
+            ContextOutputFormat::Xml => Self::StructuredXml,
+            ContextOutputFormat::Json => Self::Json,
+            ContextOutputFormat::Compact => Self::Compact,
+        }
+    }
+}
+
+/// Aggregation level for `omnicontext module-graph`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum GraphLevel {
+    /// One node per file.
+    File,
+    /// One node per directory.
+    Dir,
+}
+
+impl From<GraphLevel> for omni_core::graph::modules::ModuleLevel {
+    fn from(level: GraphLevel) -> Self {
+        match level {
+            GraphLevel::File => Self::File,
+            GraphLevel::Dir => Self::Directory,
+        }
+    }
+}
+
 /// Indexing mode for `omnicontext index`.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Default)]
 enum IndexMode {
@@ -50,6 +93,65 @@ struct Cli {
     /// Output results as JSON (for scripting and CI/CD).
     #[arg(long, global = true)]
     json: bool,
+
+    /// Stream results as newline-delimited JSON, one object per line, for
+    /// piping into `jq` or `fzf`. Supported by `search`, `status`, `api`,
+    /// and `hotspots`; elsewhere it behaves like `--json`.
+    #[arg(long, global = true)]
+    json_lines: bool,
+}
+
+impl Cli {
+    fn output(&self) -> Output {
+        if self.json_lines {
+            Output::JsonLines
+        } else if self.json {
+            Output::Json
+        } else {
+            Output::Text
+        }
+    }
+}
+
+/// How a command prints its results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Output {
+    /// Human-readable text.
+    Text,
+    /// One pretty-printed JSON document.
+    Json,
+    /// Newline-delimited JSON: one compact object per result.
+    JsonLines,
+}
+
+/// Newline-delimited JSON writer over stdout.
+///
+/// Each record is flushed as soon as it is written so downstream tools see
+/// results while the command is still running. A closed pipe (`| head`)
+/// ends the process quietly.
+struct JsonLines {
+    out: std::io::StdoutLock<'static>,
+}
+
+impl JsonLines {
+    fn new() -> Self {
+        Self {
+            out: std::io::stdout().lock(),
+        }
+    }
+
+    fn write(&mut self, record: &serde_json::Value) -> Result<()> {
+        use std::io::Write;
+
+        let written = serde_json::to_writer(&mut self.out, record)
+            .map_err(std::io::Error::from)
+            .and_then(|()| self.out.write_all(b"\n"))
+            .and_then(|()| self.out.flush());
+        match written {
+            Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => std::process::exit(0),
+            other => Ok(other?),
+        }
+    }
 }
 
 #[derive(Subcommand, Debug)]
@@ -81,9 +183,15 @@ enum Commands {
         /// Produces an error and exits if the key is absent.
         #[arg(long)]
         cloud: bool,
+
+        /// Wait for the daemon or another indexer to release the index
+        /// instead of failing.
+        #[arg(long)]
+        wait: bool,
     },
 
     /// Search the indexed codebase.
+    #[command(group(clap::ArgGroup::new("file_filters").multiple(true)))]
     Search {
         /// Search query (natural language or keywords).
         query: String,
@@ -93,12 +201,100 @@ enum Commands {
         limit: usize,
 
         /// Filter by programming language.
-        #[arg(long)]
+        #[arg(long, group = "file_filters")]
         language: Option<String>,
 
         /// Filter by code kind (function, class, trait, etc.).
-        #[arg(long)]
+        #[arg(long, group = "file_filters")]
         kind: Option<String>,
+
+        /// Only search files whose path matches this glob (e.g. `src/auth/**`).
+        #[arg(long = "path", value_name = "GLOB", group = "file_filters")]
+        path_glob: Option<String>,
+
+        /// Match the query literally with exact case.
+        #[arg(long, conflicts_with = "file_filters")]
+        case_sensitive: bool,
+
+        /// Match the query literally as a whole identifier.
+        #[arg(long, conflicts_with = "file_filters")]
+        whole_word: bool,
+
+        /// Also return results from `indexing.vendored_patterns` trees
+        /// (ranked below project code).
+        #[arg(long, conflicts_with = "file_filters")]
+        include_vendored: bool,
+
+        /// Also return generated code (files marked "DO NOT EDIT" or
+        /// `@generated`), which is left out by default.
+        #[arg(long, conflicts_with = "include_vendored")]
+        include_generated: bool,
+
+        /// Group results by file: one entry per file with aggregate scores
+        /// and its top matching chunks nested.
+        #[arg(long)]
+        group_by_file: bool,
+
+        /// Only search files whose content changed at or after this ISO 8601
+        /// date or datetime (UTC).
+        #[arg(long, group = "file_filters")]
+        modified_after: Option<String>,
+
+        /// Only search files whose content changed before this ISO 8601 date
+        /// or datetime (UTC).
+        #[arg(long, group = "file_filters")]
+        modified_before: Option<String>,
+
+        /// Only search files of at least this many bytes.
+        #[arg(long, group = "file_filters")]
+        min_size: Option<u64>,
+
+        /// Only search files of at most this many bytes.
+        #[arg(long, group = "file_filters")]
+        max_size: Option<u64>,
+
+        /// Only search this file (repeatable).
+        #[arg(long = "file", group = "file_filters")]
+        files: Vec<std::path::PathBuf>,
+
+        /// Candidates fetched from each retrieval signal before ranking
+        /// (default: 100).
+        #[arg(long)]
+        candidates: Option<usize>,
+
+        /// Push down results about this (repeatable), e.g.
+        /// `search serialization --not tests --not protobuf`.
+        #[arg(long = "not", value_name = "QUERY")]
+        negative: Vec<String>,
+    },
+
+    /// Assemble a token-budgeted context window for a query, ready to paste
+    /// into a prompt.
+    Context {
+        /// Search query (natural language or keywords).
+        query: String,
+
+        /// Maximum number of search results to consider.
+        #[arg(short, long, default_value_t = 20)]
+        limit: usize,
+
+        /// Token budget (default: the model's `[search] model_budgets`
+        /// entry, else `[search] token_budget`).
+        #[arg(long)]
+        budget: Option<u32>,
+
+        /// LLM that will read the context (e.g. `gpt-4o`); the budget is
+        /// counted in its tokens. Default: `[search] target_model`.
+        #[arg(long)]
+        model: Option<String>,
+
+        /// Output format. `--json` is shorthand for `--format json`.
+        #[arg(long, value_enum, default_value = "markdown")]
+        format: ContextOutputFormat,
+
+        /// Path to the repository root.
+        #[arg(short, long, default_value = ".")]
+        path: String,
     },
 
     /// Retry embedding chunks that failed during indexing.
@@ -112,6 +308,20 @@ enum Commands {
         retry_failed: bool,
     },
 
+    /// Check the index and repair what the startup health check finds:
+    /// a stale full-text index, vectors out of step with chunks, or a
+    /// `vectors.bin` of the wrong dimensions.
+    Repair {
+        /// Path to the repository root.
+        #[arg(default_value = ".")]
+        path: String,
+
+        /// Delete the index and rebuild it from scratch. Needed when the
+        /// schema has gaps that no migration can fill.
+        #[arg(long)]
+        rebuild: bool,
+    },
+
     /// Show engine status and index statistics.
     Status {
         /// Path to the repository root.
@@ -119,6 +329,17 @@ enum Commands {
         path: String,
     },
 
+    /// Self-test the index and report problems without repairing them.
+    ///
+    /// Runs `PRAGMA integrity_check`, compares the full-text index with the
+    /// chunk table, and cross-checks chunk vector ids against `vectors.bin`.
+    /// Exits with status 1 when anything is wrong.
+    Doctor {
+        /// Path to the repository root.
+        #[arg(default_value = ".")]
+        path: String,
+    },
+
     /// Start the MCP server for AI agent integration.
     Mcp {
         /// Path to the repository root.
@@ -156,6 +377,10 @@ enum Commands {
         /// Host to bind to.
         #[arg(long, default_value = "127.0.0.1")]
         host: String,
+
+        /// Serve an existing index read-only, without indexing or writes.
+        #[arg(long)]
+        read_only: bool,
     },
 
     /// Manage configuration.
@@ -176,6 +401,12 @@ enum Commands {
         action: SetupAction,
     },
 
+    /// List, remove, or verify downloaded models (embedders and reranker).
+    Models {
+        #[command(subcommand)]
+        action: ModelsAction,
+    },
+
     /// Auto-detect installed IDEs and inject MCP server configuration.
     Autopilot {
         /// Only configure a specific IDE (e.g., "cursor", "vscode", "claude").
@@ -226,6 +457,217 @@ enum Commands {
         #[arg(short, long)]
         path: Option<std::path::PathBuf>,
     },
+
+    /// Run a Language Server Protocol server over stdio.
+    ///
+    /// Serves workspace symbols, go-to-definition, and find-references from
+    /// the existing index, for editors without a dedicated extension.
+    Lsp {
+        /// Path to the repository root.
+        #[arg(default_value = ".")]
+        path: String,
+    },
+
+    /// Save a consistent copy of the current index for later diffing.
+    Snapshot {
+        /// Destination file for the snapshot database.
+        output: std::path::PathBuf,
+
+        /// Path to the repository root.
+        #[arg(short, long, default_value = ".")]
+        path: String,
+    },
+
+    /// Semantic diff between an index snapshot and the current index (JSON).
+    ///
+    /// Reports symbols added/removed/changed, dependency edges added/removed,
+    /// and public API changes, for PR review bots.
+    Diff {
+        /// Old snapshot (`index.db` file or a data directory containing one).
+        old: std::path::PathBuf,
+
+        /// Path to the repository root.
+        #[arg(short, long, default_value = ".")]
+        path: String,
+    },
+
+    /// List the public API surface with doc coverage, grouped by module.
+    ///
+    /// With `--since`, report public symbols added, removed, or with a
+    /// changed signature relative to an older index snapshot instead.
+    Api {
+        /// Old snapshot to diff against (`index.db` file or data directory).
+        #[arg(long)]
+        since: Option<std::path::PathBuf>,
+
+        /// Path to the repository root.
+        #[arg(short, long, default_value = ".")]
+        path: String,
+    },
+
+    /// Measure retrieval quality against a golden query set.
+    ///
+    /// Reports Recall@k, MRR, and nDCG@k for the repository's search
+    /// settings and each `[[config]]` variant in the golden set.
+    Eval {
+        /// Golden set (default: `.omnicontext/eval.toml` in the repository).
+        #[arg(short, long)]
+        file: Option<std::path::PathBuf>,
+
+        /// Path to the repository root.
+        #[arg(short, long, default_value = ".")]
+        path: String,
+    },
+
+    /// Report hotspots: files that change often and are complex.
+    ///
+    /// Scores churn from the indexed git history against summed cyclomatic
+    /// complexity, highest first.
+    Hotspots {
+        /// Maximum number of files to report.
+        #[arg(short, long, default_value = "20")]
+        limit: usize,
+
+        /// Path to the repository root.
+        #[arg(short, long, default_value = ".")]
+        path: String,
+    },
+
+    /// Print the file- or directory-level dependency graph as Graphviz DOT.
+    ///
+    /// Symbol edges are summed per file pair (and per directory pair), each
+    /// weighted by how confidently it was resolved. With `--json`, both
+    /// levels are printed as JSON instead.
+    ModuleGraph {
+        /// Aggregation level.
+        #[arg(short, long, value_enum, default_value = "dir")]
+        level: GraphLevel,
+
+        /// Path to the repository root.
+        #[arg(short, long, default_value = ".")]
+        path: String,
+    },
+
+    /// Check indexed dependencies against the `[architecture]` layer rules.
+    ///
+    /// Prints each violating dependency as `file:line`. Exits with status 1
+    /// when any rule is broken, so it can gate CI.
+    CheckArch {
+        /// Path to the repository root.
+        #[arg(short, long, default_value = ".")]
+        path: String,
+    },
+
+    /// List indexed files that match sensitive-path patterns.
+    ///
+    /// Flags files the `[privacy]` policy excludes (purged on the next index
+    /// run) and files matching built-in risky patterns the policy misses.
+    /// Exits with status 1 when anything is found.
+    PrivacyAudit {
+        /// Path to the repository root.
+        #[arg(short, long, default_value = ".")]
+        path: String,
+    },
+
+    /// List the file headers (license blocks, codegen banners) that open
+    /// many files and are left out of chunk content and full-text search.
+    Headers {
+        /// Path to the repository root.
+        #[arg(short, long, default_value = ".")]
+        path: String,
+    },
+
+    /// Export the search telemetry log as JSON lines.
+    ///
+    /// Entries are only recorded when `[search] log_queries = true`.
+    SearchLog {
+        /// Only export entries logged at or after this time (`YYYY-MM-DD[ HH:MM:SS]`, UTC).
+        #[arg(long)]
+        since: Option<String>,
+
+        /// Write to a file instead of stdout.
+        #[arg(short, long)]
+        output: Option<std::path::PathBuf>,
+
+        /// Delete all entries after exporting them.
+        #[arg(long)]
+        clear: bool,
+
+        /// Path to the repository root.
+        #[arg(short, long, default_value = ".")]
+        path: String,
+    },
+
+    /// Distributed indexing: split the repository across worker processes.
+    ///
+    /// Run `plan` once, `work <N>` for every shard (in parallel, on any
+    /// machines sharing the coordination directory), then `merge`.
+    Shard {
+        /// Distributed indexing step.
+        #[command(subcommand)]
+        action: ShardAction,
+    },
+
+    /// Talk to the running daemon for a repository.
+    Daemon {
+        /// Daemon request.
+        #[command(subcommand)]
+        action: DaemonAction,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+enum DaemonAction {
+    /// Ask the daemon to reindex in the background.
+    ///
+    /// Use this instead of `omnicontext index` while the daemon is running;
+    /// the daemon holds the index lock.
+    Reindex {
+        /// Path to the repository root.
+        #[arg(short, long, default_value = ".")]
+        path: String,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+enum ShardAction {
+    /// Assign the repository's files to shards and write the plan.
+    Plan {
+        /// Number of worker shards.
+        #[arg(short, long, default_value_t = 4)]
+        workers: usize,
+
+        /// Coordination directory shared by all workers.
+        #[arg(long)]
+        dir: std::path::PathBuf,
+
+        /// Path to the repository root.
+        #[arg(short, long, default_value = ".")]
+        path: String,
+    },
+    /// Index one shard of the plan into its own partial index.
+    Work {
+        /// Shard number (0-based).
+        shard: usize,
+
+        /// Coordination directory shared by all workers.
+        #[arg(long)]
+        dir: std::path::PathBuf,
+
+        /// Path to the repository root.
+        #[arg(short, long, default_value = ".")]
+        path: String,
+    },
+    /// Merge every finished shard into the repository's index.
+    Merge {
+        /// Coordination directory shared by all workers.
+        #[arg(long)]
+        dir: std::path::PathBuf,
+
+        /// Path to the repository root.
+        #[arg(short, long, default_value = ".")]
+        path: String,
+    },
 }
 
 #[derive(Subcommand, Debug, Clone, Copy)]
@@ -257,42 +699,131 @@ enum SetupAction {
     },
 }
 
+#[derive(Subcommand, Debug, Clone)]
+enum ModelsAction {
+    /// Show every known model, whether it is installed, and its disk usage.
+    List,
+    /// Delete a downloaded model.
+    Remove {
+        /// Model name or Hugging Face repo (see `models list`).
+        name: String,
+    },
+    /// Check installed models against their SHA-256 digests.
+    Verify {
+        /// Model to verify. Defaults to every installed model.
+        name: Option<String>,
+    },
+    /// Pack installed models into a bundle for offline installation.
+    Bundle {
+        /// Bundle file to write.
+        output: std::path::PathBuf,
+        /// Models to include. Defaults to every installed model.
+        names: Vec<String>,
+    },
+    /// Install models from a bundle made by `models bundle`.
+    Install {
+        /// Bundle file to install.
+        bundle: std::path::PathBuf,
+    },
+}
+
 #[tokio::main]
+#[allow(clippy::too_many_lines)] // one arm per subcommand
 async fn main() -> Result<()> {
     let cli = Cli::parse();
 
-    tracing_subscriber::fmt()
-        .with_env_filter(&cli.log_level)
-        .init();
+    // LSP owns stdout for protocol frames, and JSON output must stay
+    // parseable, so their logs go to stderr.
+    if matches!(cli.command, Commands::Lsp { .. }) || cli.output() != Output::Text {
+        tracing_subscriber::fmt()
+            .with_env_filter(&cli.log_level)
+            .with_writer(std::io::stderr)
+            .init();
+    } else {
+        tracing_subscriber::fmt()
+            .with_env_filter(&cli.log_level)
+            .init();
+    }
 
+    let output = cli.output();
+    let json = output != Output::Text;
     match cli.command {
         Commands::Index {
             path,
             force,
             mode,
             cloud,
+            wait,
         } => {
-            cmd_index(&path, force, mode, cloud, cli.json).await?;
+            cmd_index(&path, force, mode, cloud, wait, json).await?;
         }
         Commands::Search {
             query,
             limit,
             language,
             kind,
+            path_glob,
+            case_sensitive,
+            whole_word,
+            include_vendored,
+            include_generated,
+            group_by_file,
+            modified_after,
+            modified_before,
+            min_size,
+            max_size,
+            files,
+            candidates,
+            negative,
         } => {
+            let exact = omni_core::search::exact::ExactMatchOptions {
+                case_sensitive,
+                whole_word,
+            };
+            let options = omni_core::types::SearchOptions {
+                modified_after,
+                modified_before,
+                min_size_bytes: min_size,
+                max_size_bytes: max_size,
+                files,
+                language,
+                kind,
+                path_glob,
+                candidates,
+                token_budget: None,
+                negative_query: (!negative.is_empty()).then(|| negative.join(" ")),
+                include_generated,
+            };
             cmd_search(
-                &query,
-                limit,
-                language.as_deref(),
-                kind.as_deref(),
-                cli.json,
+                &query, limit, exact, include_vendored, group_by_file, &options, output,
             )?;
         }
+        Commands::Context {
+            query,
+            limit,
+            budget,
+            model,
+            format,
+            path,
+        } => {
+            let format = if json {
+                ContextOutputFormat::Json
+            } else {
+                format
+            };
+            cmd_context(&path, &query, limit, budget, model.as_deref(), format)?;
+        }
         Commands::Embed { path, retry_failed } => {
-            cmd_embed(&path, retry_failed, cli.json)?;
+            cmd_embed(&path, retry_failed, json)?;
+        }
+        Commands::Repair { path, rebuild } => {
+            cmd_repair(&path, rebuild, json).await?;
         }
         Commands::Status { path } => {
-            cmd_status(&path, cli.json)?;
+            cmd_status(&path, output)?;
+        }
+        Commands::Doctor { path } => {
+            cmd_doctor(&path, json)?;
         }
         Commands::Mcp {
             repo,
@@ -302,14 +833,22 @@ async fn main() -> Result<()> {
         } => {
             cmd_mcp(&repo, &transport, port, &host).await?;
         }
-        Commands::Serve { path, port, host } => {
-            cmd_serve(&path, port, &host).await?;
+        Commands::Serve {
+            path,
+            port,
+            host,
+            read_only,
+        } => {
+            cmd_serve(&path, port, &host, read_only).await?;
         }
         Commands::Config { show, init } => {
             cmd_config(show, init)?;
         }
         Commands::Setup { action } => {
-            cmd_setup(action, cli.json)?;
+            cmd_setup(action, json)?;
+        }
+        Commands::Models { action } => {
+            cmd_models(action, json)?;
         }
         Commands::Autopilot { ide, dry_run } => {
             cmd_autopilot(ide.as_deref(), dry_run)?;
@@ -319,17 +858,61 @@ async fn main() -> Result<()> {
             write,
             path,
         } => {
-            cmd_manifest(&path, &format, write, cli.json)?;
+            cmd_manifest(&path, &format, write, json)?;
         }
         Commands::Export {
             path,
             format,
             output,
         } => {
-            cmd_export(path.as_deref(), format, output.as_deref(), cli.json)?;
+            cmd_export(path.as_deref(), format, output.as_deref(), json)?;
         }
         Commands::Import { input, path } => {
-            cmd_import(&input, path.as_deref(), cli.json)?;
+            cmd_import(&input, path.as_deref(), json)?;
+        }
+        Commands::Lsp { path } => {
+            cmd_lsp(&path)?;
+        }
+        Commands::Snapshot { output, path } => {
+            cmd_snapshot(&path, &output, json)?;
+        }
+        Commands::Diff { old, path } => {
+            cmd_diff(&path, &old)?;
+        }
+        Commands::Api { since, path } => {
+            cmd_api(&path, since.as_deref(), output)?;
+        }
+        Commands::Eval { file, path } => {
+            cmd_eval(&path, file.as_deref(), json)?;
+        }
+        Commands::Hotspots { limit, path } => {
+            cmd_hotspots(&path, limit, output)?;
+        }
+        Commands::ModuleGraph { level, path } => {
+            cmd_module_graph(&path, level, json)?;
+        }
+        Commands::CheckArch { path } => {
+            cmd_check_arch(&path, json)?;
+        }
+        Commands::PrivacyAudit { path } => {
+            cmd_privacy_audit(&path, json)?;
+        }
+        Commands::Headers { path } => {
+            cmd_headers(&path, json)?;
+        }
+        Commands::SearchLog {
+            since,
+            output,
+            clear,
+            path,
+        } => {
+            cmd_search_log(&path, since.as_deref(), output.as_deref(), clear)?;
+        }
+        Commands::Shard { action } => {
+            cmd_shard(action, json).await?;
+        }
+        Commands::Daemon { action } => {
+            cmd_daemon(action, json).await?;
         }
     }
 
@@ -337,11 +920,13 @@ async fn main() -> Result<()> {
 }
 
 /// Index a repository.
+#[allow(clippy::fn_params_excessive_bools)] // mirrors the `index` flags
 async fn cmd_index(
     path: &str,
     force: bool,
     mode: IndexMode,
     cloud: bool,
+    wait: bool,
     json: bool,
 ) -> Result<()> {
     // Validate cloud flag early: fail fast with a clear error rather than
@@ -373,6 +958,10 @@ async fn cmd_index(
 
     let mut engine = omni_core::Engine::new(&repo_path)?;
 
+    // Hold the index lock for the whole run, including the offline ANN
+    // build, so the daemon cannot start writing in between.
+    engine.hold_writer_lock(omni_core::index_lock::WriterKind::Indexer, wait)?;
+
     // In offline mode, suppress incremental ANN updates so vectors accumulate
     // in the flat map.  The ANN index is built in one batch pass at the end.
     // This matches Sourcegraph's offline SCIP build + load pattern.
@@ -424,43 +1013,100 @@ async fn cmd_index(
 }
 
 /// Search the indexed codebase.
+#[allow(clippy::too_many_arguments)]
+/// Open the engine for a command that runs semantic search.
+///
+/// A re-embed queued after an embedding model change is normally driven by
+/// the daemon. When no daemon holds the index it is finished here first,
+/// since nothing else would, and semantic search would stay empty.
+fn open_query_engine(repo_path: &std::path::Path) -> Result<omni_core::Engine> {
+    let mut engine = omni_core::Engine::new(repo_path)?;
+    let queued = engine
+        .metadata_index()
+        .active_job(omni_core::pipeline::REEMBED_JOB)?;
+    if let Some(job) = queued {
+        eprintln!(
+            "Re-embedding {} chunks after an embedding model change...",
+            job.total.saturating_sub(job.done)
+        );
+        match engine.finish_background_jobs() {
+            Ok(_) => {}
+            Err(omni_core::error::OmniError::IndexLocked { .. }) => {
+                eprintln!("  the daemon is re-embedding; semantic results fill in as it runs");
+            }
+            Err(e) => eprintln!("  re-embedding stopped: {e}"),
+        }
+    }
+    Ok(engine)
+}
+
 fn cmd_search(
     query: &str,
     limit: usize,
-    _language: Option<&str>,
-    _kind: Option<&str>,
-    json: bool,
+    exact: omni_core::search::exact::ExactMatchOptions,
+    include_vendored: bool,
+    group_by_file: bool,
+    options: &omni_core::types::SearchOptions,
+    output: Output,
 ) -> Result<()> {
     let repo_path = std::env::current_dir()?;
-    let engine = omni_core::Engine::new(&repo_path)?;
+    let engine = open_query_engine(&repo_path)?;
 
     let start = Instant::now();
-    let results = engine.search(query, limit)?;
+    let results = if exact.is_active() {
+        let mut results = engine.search_exact(query, limit, exact)?;
+        if !include_vendored {
+            results.retain(|r| !engine.is_vendored(&r.file_path));
+        }
+        results
+    } else if include_vendored {
+        engine.search_including_vendored(query, limit)?
+    } else if !options.is_empty() {
+        engine.search_with_options(query, limit, options)?
+    } else {
+        engine.search(query, limit)?
+    };
     let elapsed = start.elapsed();
+    let diagnostics = if results.is_empty() && !exact.is_active() {
+        Some(engine.diagnose_no_results(query, options, None)?)
+    } else {
+        None
+    };
 
-    if json {
-        let output = serde_json::json!({
-            "query": query,
-            "elapsed_ms": elapsed.as_millis(),
-            "count": results.len(),
-            "results": results.iter().map(|r| serde_json::json!({
-                "file": r.file_path.display().to_string(),
-                "score": r.score,
-                "kind": format!("{:?}", r.chunk.kind),
-                "symbol": r.chunk.symbol_path,
-                "line_start": r.chunk.line_start,
-                "line_end": r.chunk.line_end,
-                "content": r.chunk.content,
-            })).collect::<Vec<_>>(),
-        });
-        println!("{}", serde_json::to_string_pretty(&output)?);
+    if group_by_file {
+        print_grouped_results(query, results, elapsed, diagnostics.as_ref(), output)?;
         return Ok(());
     }
 
+    match output {
+        Output::JsonLines => {
+            let mut lines = JsonLines::new();
+            for result in &results {
+                lines.write(&search_record(result))?;
+            }
+            return Ok(());
+        }
+        Output::Json => {
+            let mut output = serde_json::json!({
+                "query": query,
+                "elapsed_ms": elapsed.as_millis(),
+                "count": results.len(),
+                "results": results.iter().map(search_record).collect::<Vec<_>>(),
+            });
+            if let Some(diagnostics) = &diagnostics {
+                output["diagnostics"] = serde_json::to_value(diagnostics)?;
+            }
+            println!("{}", serde_json::to_string_pretty(&output)?);
+            return Ok(());
+        }
+        Output::Text => {}
+    }
+
     if results.is_empty() {
         println!("No results found for: \"{query}\"");
-        println!();
-        println!("Tip: Make sure you've run `omnicontext index .` first.");
+        if let Some(diagnostics) = &diagnostics {
+            print_no_results_findings(diagnostics);
+        }
         return Ok(());
     }
 
@@ -479,9 +1125,24 @@ fn cmd_search(
         let symbol = &result.chunk.symbol_path;
         let lines = format!("L{}-L{}", result.chunk.line_start, result.chunk.line_end);
 
-        println!("  {}. {} (score: {:.4})", i + 1, path.display(), score);
-        println!("     {kind} {symbol} [{lines}]");
-
+        let note = if omni_core::search::calibration::is_probably_irrelevant(result.relevance) {
+            ", probably irrelevant"
+        } else {
+            ""
+        };
+        println!(
+            "  {}. {} (relevance: {:.2}, score: {:.4}{note})",
+            i + 1,
+            path.display(),
+            result.relevance,
+            score
+        );
+        println!("     {kind} {symbol} [{lines}]");
+        let provenance = result.provenance();
+        if !provenance.is_empty() {
+            println!("     via {}", provenance.join(", "));
+        }
+
         // Print a preview of the content (first 2 lines)
         let preview: String = result
             .chunk
@@ -500,6 +1161,148 @@ fn cmd_search(
     Ok(())
 }
 
+/// JSON record for one search result.
+fn search_record(r: &omni_core::types::SearchResult) -> serde_json::Value {
+    serde_json::json!({
+        "file": r.file_path.display().to_string(),
+        "score": r.score,
+        "relevance": r.relevance,
+        "probably_irrelevant": omni_core::search::calibration::is_probably_irrelevant(r.relevance),
+        "kind": format!("{:?}", r.chunk.kind),
+        "symbol": r.chunk.symbol_path,
+        "line_start": r.chunk.line_start,
+        "line_end": r.chunk.line_end,
+        "generated": r.chunk.is_generated,
+        "provenance": r.provenance(),
+        "content": r.chunk.content,
+    })
+}
+
+/// Print why a search came back empty and what to try.
+fn print_no_results_findings(diagnostics: &omni_core::search::diagnostics::NoResultsDiagnostics) {
+    println!();
+    for finding in &diagnostics.findings {
+        println!("  - {}.", finding.message);
+        println!("    Try: {}", finding.suggestion);
+    }
+}
+
+/// Print search results grouped by file (`search --group-by-file`).
+fn print_grouped_results(
+    query: &str,
+    results: Vec<omni_core::types::SearchResult>,
+    elapsed: std::time::Duration,
+    diagnostics: Option<&omni_core::search::diagnostics::NoResultsDiagnostics>,
+    output: Output,
+) -> Result<()> {
+    use omni_core::search::grouping::{group_by_file, DEFAULT_CHUNKS_PER_FILE};
+
+    let groups = group_by_file(results, DEFAULT_CHUNKS_PER_FILE);
+    let record = |g: &omni_core::search::FileGroup| {
+        serde_json::json!({
+            "file": g.file_path.display().to_string(),
+            "score": g.score,
+            "relevance": g.relevance,
+            "matches": g.match_count,
+            "chunks": g.chunks.iter().map(search_record).collect::<Vec<_>>(),
+        })
+    };
+    match output {
+        Output::JsonLines => {
+            let mut lines = JsonLines::new();
+            for group in &groups {
+                lines.write(&record(group))?;
+            }
+            return Ok(());
+        }
+        Output::Json => {
+            let mut output = serde_json::json!({
+                "query": query,
+                "elapsed_ms": elapsed.as_millis(),
+                "count": groups.len(),
+                "files": groups.iter().map(record).collect::<Vec<_>>(),
+            });
+            if let Some(diagnostics) = diagnostics {
+                output["diagnostics"] = serde_json::to_value(diagnostics)?;
+            }
+            println!("{}", serde_json::to_string_pretty(&output)?);
+            return Ok(());
+        }
+        Output::Text => {}
+    }
+
+    if groups.is_empty() {
+        println!("No results found for: \"{query}\"");
+        if let Some(diagnostics) = diagnostics {
+            print_no_results_findings(diagnostics);
+        }
+        return Ok(());
+    }
+    println!(
+        "Results for \"{}\" ({} files, {:.1}ms):",
+        query,
+        groups.len(),
+        elapsed.as_secs_f64() * 1000.0
+    );
+    println!();
+    for (i, group) in groups.iter().enumerate() {
+        println!(
+            "  {}. {} (relevance: {:.2}, score: {:.4}, {} match{})",
+            i + 1,
+            group.file_path.display(),
+            group.relevance,
+            group.score,
+            group.match_count,
+            if group.match_count == 1 { "" } else { "es" }
+        );
+        for result in &group.chunks {
+            println!(
+                "     {:?} {} [L{}-L{}] (relevance: {:.2})",
+                result.chunk.kind,
+                result.chunk.symbol_path,
+                result.chunk.line_start,
+                result.chunk.line_end,
+                result.relevance
+            );
+        }
+        println!();
+    }
+    Ok(())
+}
+
+/// Print a token-budgeted context window for a query in the chosen format.
+fn cmd_context(
+    path: &str,
+    query: &str,
+    limit: usize,
+    budget: Option<u32>,
+    model: Option<&str>,
+    format: ContextOutputFormat,
+) -> Result<()> {
+    use omni_core::search::{ContextFormatter, FormatOptions};
+
+    let repo_path = std::path::PathBuf::from(path)
+        .canonicalize()
+        .unwrap_or_else(|_| std::path::PathBuf::from(path));
+    let engine = open_query_engine(&repo_path)?;
+    let window = engine.search_context_window_for_model(query, limit, budget, None, model)?;
+
+    if window.is_empty() && format != ContextOutputFormat::Json {
+        println!("No results found for: \"{query}\"");
+        let options = omni_core::types::SearchOptions::default();
+        print_no_results_findings(&engine.diagnose_no_results(query, &options, None)?);
+        return Ok(());
+    }
+
+    let options = FormatOptions {
+        format: format.into(),
+        query: query.to_string(),
+        ..FormatOptions::default()
+    };
+    println!("{}", ContextFormatter::format(&window, &options));
+    Ok(())
+}
+
 /// Retry embedding chunks that failed during indexing.
 fn cmd_embed(path: &str, retry_failed: bool, json: bool) -> Result<()> {
     let repo_path = std::path::PathBuf::from(path)
@@ -560,8 +1363,68 @@ fn cmd_embed(path: &str, retry_failed: bool, json: bool) -> Result<()> {
     Ok(())
 }
 
+/// Repair the index, or rebuild it from scratch with `rebuild`.
+async fn cmd_repair(path: &str, rebuild: bool, json: bool) -> Result<()> {
+    let repo_path = std::path::PathBuf::from(path)
+        .canonicalize()
+        .unwrap_or_else(|_| std::path::PathBuf::from(path));
+    let mut config = omni_core::Config::load(&repo_path)?;
+    config.storage.auto_repair = true;
+    let data_dir = config.data_dir();
+
+    // The engine skips its health check while another writer holds the
+    // index, so make sure none does.
+    drop(omni_core::index_lock::IndexLock::acquire(
+        &data_dir,
+        omni_core::index_lock::WriterKind::Indexer,
+    )?);
+
+    if rebuild {
+        for name in [
+            "index.db", "index.db-wal", "index.db-shm", "vectors.bin", "vectors.del",
+            "file_hashes.json",
+        ] {
+            match std::fs::remove_file(data_dir.join(name)) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+                _ => {}
+            }
+        }
+        if !json {
+            println!("Deleted the index in {}; rebuilding.", data_dir.display());
+        }
+        return cmd_index(path, true, IndexMode::Incremental, false, false, json).await;
+    }
+
+    let mut engine = omni_core::Engine::with_config(config)?;
+    let repairs = engine.repair()?.to_vec();
+    if json {
+        let output = serde_json::json!({ "repaired": repairs });
+        println!("{}", serde_json::to_string_pretty(&output)?);
+    } else if repairs.is_empty() {
+        println!("No problems found in {}", data_dir.display());
+    } else {
+        println!("Repaired {} problem(s):", repairs.len());
+        for issue in &repairs {
+            println!("  - {issue}");
+        }
+        if repairs.iter().any(|issue| {
+            matches!(
+                issue,
+                omni_core::IndexIssue::DimensionMismatch { .. }
+                    | omni_core::IndexIssue::VectorMismatch { .. }
+            )
+        }) {
+            println!();
+            println!("Run `omnicontext index` to re-embed chunks that lost their vectors.");
+        }
+    }
+    engine.shutdown()?;
+    Ok(())
+}
+
 /// Show engine status and index statistics.
-fn cmd_status(path: &str, json: bool) -> Result<()> {
+#[allow(clippy::too_many_lines)] // one block per status section
+fn cmd_status(path: &str, output: Output) -> Result<()> {
     let repo_path = std::path::PathBuf::from(path)
         .canonicalize()
         .unwrap_or_else(|_| std::path::PathBuf::from(path));
@@ -569,9 +1432,13 @@ fn cmd_status(path: &str, json: bool) -> Result<()> {
     let engine = omni_core::Engine::new(&repo_path)?;
     let status = engine.status()?;
 
-    if json {
-        println!("{}", serde_json::to_string_pretty(&status)?);
-        return Ok(());
+    match output {
+        Output::JsonLines => return JsonLines::new().write(&serde_json::to_value(&status)?),
+        Output::Json => {
+            println!("{}", serde_json::to_string_pretty(&status)?);
+            return Ok(());
+        }
+        Output::Text => {}
     }
 
     println!("OmniContext Status");
@@ -579,11 +1446,15 @@ fn cmd_status(path: &str, json: bool) -> Result<()> {
     println!("  Repository:       {}", status.repo_path);
     println!("  Data directory:   {}", status.data_dir);
     println!("  Search mode:      {}", status.search_mode);
+    print_index_metadata(&status.metadata);
     println!();
     println!("  Files indexed:    {}", status.files_indexed);
     println!("  Chunks indexed:   {}", status.chunks_indexed);
     println!("  Symbols indexed:  {}", status.symbols_indexed);
     println!("  Vectors indexed:  {}", status.vectors_indexed);
+    for (encoding, count) in &status.encoding_distribution {
+        println!("    {count} files decoded from {encoding}");
+    }
     println!();
     println!("  Dep edges (db):   {}", status.dep_edges);
     println!("  Graph nodes:      {}", status.graph_nodes);
@@ -591,10 +1462,142 @@ fn cmd_status(path: &str, json: bool) -> Result<()> {
     if status.has_cycles {
         println!("  [!] Circular dependencies detected");
     }
+    println!();
+    let memory = &status.memory;
+    print!(
+        "  Memory (approx):  {} MB",
+        memory.total_bytes / 1024 / 1024
+    );
+    if let Some(limit) = memory.vector_limit_bytes {
+        print!(" (vector limit {} MB)", limit / 1024 / 1024);
+    }
+    println!();
+    println!(
+        "    vectors {} MB, graph {} MB, parse caches {} MB",
+        memory.vector_index_bytes / 1024 / 1024,
+        memory.dependency_graph_bytes / 1024 / 1024,
+        memory.parse_cache_bytes / 1024 / 1024
+    );
+    if memory.spilled_vectors > 0 {
+        println!("    {} vectors spilled to disk", memory.spilled_vectors);
+    }
+    println!();
+    for (role, health) in [
+        ("Embedder", &status.embedder),
+        ("Reranker", &status.reranker),
+    ] {
+        let state = if health.available {
+            "ready"
+        } else {
+            "unavailable"
+        };
+        print!(
+            "  {:<18}{}@{} ({state}, {} MB",
+            format!("{role}:"),
+            health.name,
+            health.revision,
+            health.size_bytes / 1024 / 1024
+        );
+        if let Some(ms) = health.last_latency_ms {
+            print!(", last {ms:.1} ms");
+        }
+        println!(")");
+    }
+    if !status.background_jobs.is_empty() {
+        println!();
+        println!("  Background jobs:");
+        for job in &status.background_jobs {
+            println!(
+                "    {:<10} {:<8} {}/{} ({:.1}%)",
+                job.kind,
+                job.state.as_str(),
+                job.done,
+                job.total,
+                job.percent()
+            );
+            if let Some(error) = &job.error {
+                println!("      {error}");
+            }
+        }
+    }
+    if !status.degraded_reasons.is_empty() {
+        println!();
+        for reason in &status.degraded_reasons {
+            println!("  [!] {reason}");
+        }
+    }
 
     Ok(())
 }
 
+/// Print the metadata recorded in `index.db`, skipping unrecorded values.
+fn print_index_metadata(metadata: &omni_core::types::IndexMetadata) {
+    let model = metadata.embedding_model.as_ref().map(ToString::to_string);
+    for (label, value) in [
+        ("Index created:", metadata.created_at.as_ref()),
+        ("Last index run:", metadata.last_full_index.as_ref()),
+        ("Indexed by:", metadata.engine_version.as_ref()),
+        ("Vectors from:", model.as_ref()),
+    ] {
+        if let Some(value) = value {
+            println!("  {label:<18}{value}");
+        }
+    }
+}
+
+/// Self-test the index without repairing anything.
+fn cmd_doctor(path: &str, json: bool) -> Result<()> {
+    let repo_path = std::path::PathBuf::from(path)
+        .canonicalize()
+        .unwrap_or_else(|_| std::path::PathBuf::from(path));
+    let config = omni_core::Config::load(&repo_path)?;
+
+    // Diagnose only: open without migrating or repairing anything, so every
+    // problem found is reported as it is on disk.
+    let command = omni_core::pipeline::REPAIR_COMMAND;
+    let engine = match omni_core::Engine::open_for_check(config) {
+        Ok(engine) => engine,
+        Err(omni_core::OmniError::NotFound { entity }) => {
+            anyhow::bail!("nothing to check: no {entity}; run `omnicontext index` first")
+        }
+        Err(e) => return Err(e.into()),
+    };
+    let report = engine.check_integrity()?;
+    let problems = report.problems();
+    let status = engine.status()?;
+
+    if json {
+        let output = serde_json::json!({
+            "metadata": status.metadata,
+            "integrity": report,
+            "problems": problems,
+            "warnings": status.degraded_reasons,
+            "repair_command": (!problems.is_empty()).then_some(command),
+        });
+        println!("{}", serde_json::to_string_pretty(&output)?);
+    } else {
+        print_index_metadata(&status.metadata);
+        for warning in &status.degraded_reasons {
+            println!("  [!] {warning}");
+        }
+        if problems.is_empty() {
+            println!("Index is healthy: database, full-text index, and vectors agree.");
+        } else {
+            println!("Found {} index problem(s):", problems.len());
+            for problem in &problems {
+                println!("  - {problem}");
+            }
+            println!();
+            println!("Run `{command}` to repair them.");
+        }
+    }
+
+    if !problems.is_empty() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
 /// Start the MCP server by launching the dedicated omnicontext-mcp binary.
 async fn cmd_mcp(repo: &str, transport: &str, port: u16, host: &str) -> Result<()> {
     let repo_path = std::path::PathBuf::from(repo)
@@ -641,11 +1644,514 @@ async fn cmd_mcp(repo: &str, transport: &str, port: u16, host: &str) -> Result<(
     }
 }
 
+/// Run the LSP server over stdio (`lsp` subcommand).
+fn cmd_lsp(path: &str) -> Result<()> {
+    let repo_path = std::path::PathBuf::from(path)
+        .canonicalize()
+        .unwrap_or_else(|_| std::path::PathBuf::from(path));
+
+    eprintln!("OmniContext LSP server starting...");
+    eprintln!("  Repository: {}", repo_path.display());
+
+    lsp::run(&repo_path)
+}
+
+fn cmd_snapshot(path: &str, output: &std::path::Path, json: bool) -> Result<()> {
+    let repo_path = std::path::PathBuf::from(path)
+        .canonicalize()
+        .unwrap_or_else(|_| std::path::PathBuf::from(path));
+
+    let engine = omni_core::Engine::new(&repo_path)?;
+    engine.metadata_index().snapshot_to(output)?;
+
+    if json {
+        println!(
+            "{}",
+            serde_json::json!({ "snapshot": output.display().to_string() })
+        );
+    } else {
+        println!("Snapshot written to {}", output.display());
+    }
+    Ok(())
+}
+
+fn cmd_diff(path: &str, old: &std::path::Path) -> Result<()> {
+    let repo_path = std::path::PathBuf::from(path)
+        .canonicalize()
+        .unwrap_or_else(|_| std::path::PathBuf::from(path));
+
+    let engine = omni_core::Engine::new(&repo_path)?;
+    let diff = engine.diff_index(old)?;
+    println!("{}", serde_json::to_string_pretty(&diff)?);
+    Ok(())
+}
+
+fn cmd_api(path: &str, since: Option<&std::path::Path>, output: Output) -> Result<()> {
+    let repo_path = std::path::PathBuf::from(path)
+        .canonicalize()
+        .unwrap_or_else(|_| std::path::PathBuf::from(path));
+
+    let engine = omni_core::Engine::new(&repo_path)?;
+
+    if let Some(old) = since {
+        let diff = engine.public_api_diff(old)?;
+        match output {
+            Output::JsonLines => {
+                let mut lines = JsonLines::new();
+                for (change, symbol) in diff
+                    .added
+                    .iter()
+                    .map(|s| ("added", serde_json::to_value(s)))
+                    .chain(
+                        diff.removed
+                            .iter()
+                            .map(|s| ("removed", serde_json::to_value(s))),
+                    )
+                    .chain(
+                        diff.changed
+                            .iter()
+                            .map(|c| ("changed", serde_json::to_value(c))),
+                    )
+                {
+                    let mut record = symbol?;
+                    record["change"] = change.into();
+                    lines.write(&record)?;
+                }
+                return Ok(());
+            }
+            Output::Json => {
+                println!("{}", serde_json::to_string_pretty(&diff)?);
+                return Ok(());
+            }
+            Output::Text => {}
+        }
+        if diff.is_empty() {
+            println!("No public API changes since {}", old.display());
+            return Ok(());
+        }
+        for symbol in &diff.added {
+            println!("+ {}  {}", symbol.fqn, symbol.signature);
+        }
+        for symbol in &diff.removed {
+            println!("- {}  {}", symbol.fqn, symbol.signature);
+        }
+        for change in &diff.changed {
+            println!("~ {}", change.fqn);
+            println!("    was: {}", change.old);
+            println!("    now: {}", change.new);
+        }
+        return Ok(());
+    }
+
+    let surface = engine.public_api()?;
+    match output {
+        Output::JsonLines => {
+            let mut lines = JsonLines::new();
+            for module in &surface.modules {
+                for symbol in &module.symbols {
+                    let mut record = serde_json::to_value(symbol)?;
+                    record["module"] = module.path.clone().into();
+                    lines.write(&record)?;
+                }
+            }
+            return Ok(());
+        }
+        Output::Json => {
+            println!("{}", serde_json::to_string_pretty(&surface)?);
+            return Ok(());
+        }
+        Output::Text => {}
+    }
+    for module in &surface.modules {
+        println!(
+            "{} ({}/{} documented)",
+            module.path,
+            module.documented(),
+            module.symbols.len()
+        );
+        for symbol in &module.symbols {
+            let marker = if symbol.documented { " " } else { "!" };
+            println!("  {marker} {}", symbol.signature);
+        }
+    }
+    println!();
+    println!(
+        "{} public symbols in {} modules, {:.1}% documented",
+        surface.symbol_count(),
+        surface.modules.len(),
+        surface.doc_coverage() * 100.0
+    );
+    Ok(())
+}
+
+fn cmd_eval(path: &str, file: Option<&std::path::Path>, json: bool) -> Result<()> {
+    let repo_path = std::path::PathBuf::from(path)
+        .canonicalize()
+        .unwrap_or_else(|_| std::path::PathBuf::from(path));
+
+    let eval_path = file.map_or_else(
+        || omni_core::eval::EvalSet::default_path(&repo_path),
+        std::path::Path::to_path_buf,
+    );
+    let set = omni_core::eval::EvalSet::load(&eval_path)?;
+    let mut engine = open_query_engine(&repo_path)?;
+    let report = omni_core::eval::run(&mut engine, &set)?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    let k = report.k;
+    println!(
+        "Evaluated {} queries from {}",
+        set.queries.len(),
+        eval_path.display()
+    );
+    println!();
+    println!(
+        "  {:<24} {:>10} {:>8} {:>9}",
+        "config",
+        format!("Recall@{k}"),
+        "MRR",
+        format!("nDCG@{k}")
+    );
+    for config in &report.configs {
+        println!(
+            "  {:<24} {:>10.4} {:>8.4} {:>9.4}",
+            config.name, config.recall, config.mrr, config.ndcg
+        );
+    }
+    Ok(())
+}
+
+fn cmd_hotspots(path: &str, limit: usize, output: Output) -> Result<()> {
+    let repo_path = std::path::PathBuf::from(path)
+        .canonicalize()
+        .unwrap_or_else(|_| std::path::PathBuf::from(path));
+
+    let engine = omni_core::Engine::new(&repo_path)?;
+    let hotspots = engine.hotspots(limit)?;
+
+    match output {
+        Output::JsonLines => {
+            let mut lines = JsonLines::new();
+            for hotspot in &hotspots {
+                lines.write(&serde_json::to_value(hotspot)?)?;
+            }
+            return Ok(());
+        }
+        Output::Json => {
+            println!("{}", serde_json::to_string_pretty(&hotspots)?);
+            return Ok(());
+        }
+        Output::Text => {}
+    }
+
+    if hotspots.is_empty() {
+        println!("No hotspots: index the repository with git history first.");
+        return Ok(());
+    }
+    println!(
+        "  {:>6} {:>8} {:>10}  path",
+        "score", "commits", "complexity"
+    );
+    for hotspot in &hotspots {
+        println!(
+            "  {:>6.3} {:>8} {:>10}  {}",
+            hotspot.score, hotspot.commits, hotspot.complexity, hotspot.path
+        );
+    }
+    Ok(())
+}
+
+fn cmd_module_graph(path: &str, level: GraphLevel, json: bool) -> Result<()> {
+    let repo_path = std::path::PathBuf::from(path)
+        .canonicalize()
+        .unwrap_or_else(|_| std::path::PathBuf::from(path));
+
+    let engine = omni_core::Engine::new(&repo_path)?;
+    let graph = engine.module_graph()?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&graph)?);
+    } else {
+        print!("{}", graph.to_dot(level.into()));
+    }
+    Ok(())
+}
+
+async fn cmd_shard(action: ShardAction, json: bool) -> Result<()> {
+    let canonical = |path: &str| {
+        std::path::PathBuf::from(path)
+            .canonicalize()
+            .unwrap_or_else(|_| std::path::PathBuf::from(path))
+    };
+
+    match action {
+        ShardAction::Plan { workers, dir, path } => {
+            let engine = omni_core::Engine::new(&canonical(&path))?;
+            let plan = engine.plan_shards(&dir, workers)?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&plan)?);
+            } else {
+                println!(
+                    "Planned {} files across {} shards in {}",
+                    plan.file_count(),
+                    plan.shards.len(),
+                    dir.display()
+                );
+                for (shard, files) in plan.shards.iter().enumerate() {
+                    println!("  shard {shard}: {} files", files.len());
+                }
+            }
+        }
+        ShardAction::Work { shard, dir, path } => {
+            let config = omni_core::Config::load(&canonical(&path))?;
+            let report = omni_core::Engine::index_shard(&config, &dir, shard).await?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                println!(
+                    "Shard {shard}: {} files, {} chunks, {} embeddings ({} failed)",
+                    report.files_processed,
+                    report.chunks_created,
+                    report.embeddings_generated,
+                    report.files_failed
+                );
+            }
+        }
+        ShardAction::Merge { dir, path } => {
+            let mut engine = omni_core::Engine::new(&canonical(&path))?;
+            let report = engine.merge_shards(&dir)?;
+            engine.shutdown()?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                println!(
+                    "Merged {} shards: {} files, {} chunks, {} symbols, {} vectors",
+                    report.shards, report.files, report.chunks, report.symbols, report.vectors
+                );
+                println!("Run `omnicontext index` to link dependencies across shards.");
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn cmd_daemon(action: DaemonAction, json: bool) -> Result<()> {
+    let DaemonAction::Reindex { path } = action;
+    let repo_path = std::path::PathBuf::from(&path)
+        .canonicalize()
+        .unwrap_or_else(|_| std::path::PathBuf::from(&path));
+    let pipe_name = omni_core::config::daemon_pipe_name(&repo_path);
+
+    let result = daemon_request(&pipe_name, "index").await?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&result)?);
+    } else {
+        println!(
+            "{}",
+            result
+                .get("message")
+                .and_then(serde_json::Value::as_str)
+                .unwrap_or("Reindex requested.")
+        );
+    }
+    Ok(())
+}
+
+/// Send one JSON-RPC request to the daemon listening on `pipe_name` and
+/// return its result.
+async fn daemon_request(pipe_name: &str, method: &str) -> Result<serde_json::Value> {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    const REQUEST_ID: u64 = 1;
+
+    #[cfg(unix)]
+    let stream = tokio::net::UnixStream::connect(pipe_name).await;
+    #[cfg(windows)]
+    let stream = tokio::net::windows::named_pipe::ClientOptions::new().open(pipe_name);
+    let stream = stream.map_err(|e| {
+        anyhow::anyhow!("no daemon is listening on {pipe_name} ({e}); run `omnicontext index`")
+    })?;
+
+    let (reader, mut writer) = tokio::io::split(stream);
+    let request = serde_json::json!({ "jsonrpc": "2.0", "id": REQUEST_ID, "method": method });
+    writer.write_all(format!("{request}\n").as_bytes()).await?;
+    writer.flush().await?;
+
+    // The daemon may push event notifications (no id) on the same
+    // connection before it answers, so skip lines until our response.
+    let mut reader = BufReader::new(reader);
+    let mut response = loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).await? == 0 {
+            anyhow::bail!("daemon closed the connection before answering `{method}`");
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+        let message: serde_json::Value = serde_json::from_str(&line)?;
+        if message.get("id").and_then(serde_json::Value::as_u64) == Some(REQUEST_ID) {
+            break message;
+        }
+    };
+    if let Some(error) = response.get("error") {
+        let message = error
+            .get("message")
+            .and_then(serde_json::Value::as_str)
+            .unwrap_or("unknown error");
+        anyhow::bail!("daemon refused `{method}`: {message}");
+    }
+    Ok(response["result"].take())
+}
+
+fn cmd_check_arch(path: &str, json: bool) -> Result<()> {
+    let repo_path = std::path::PathBuf::from(path)
+        .canonicalize()
+        .unwrap_or_else(|_| std::path::PathBuf::from(path));
+
+    let engine = omni_core::Engine::new(&repo_path)?;
+    let violations = engine.check_architecture()?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&violations)?);
+    } else if violations.is_empty() {
+        println!("No architecture rule violations.");
+    } else {
+        for v in &violations {
+            println!(
+                "{}:{}: {} -> {}: {} {} {} ({})",
+                v.path,
+                v.line,
+                v.from_layer,
+                v.to_layer,
+                v.symbol,
+                v.kind.as_str(),
+                v.target,
+                v.target_path
+            );
+            if let Some(reason) = &v.reason {
+                println!("    {reason}");
+            }
+        }
+        println!();
+        println!("{} violation(s) of [architecture] rules.", violations.len());
+    }
+
+    if !violations.is_empty() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+fn cmd_privacy_audit(path: &str, json: bool) -> Result<()> {
+    let repo_path = std::path::PathBuf::from(path)
+        .canonicalize()
+        .unwrap_or_else(|_| std::path::PathBuf::from(path));
+
+    let engine = omni_core::Engine::new(&repo_path)?;
+    let findings = engine.privacy_audit()?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&findings)?);
+    } else if findings.is_empty() {
+        println!("No indexed files match sensitive-path patterns.");
+    } else {
+        for finding in &findings {
+            let status = if finding.excluded {
+                "excluded"
+            } else {
+                "review"
+            };
+            println!("  {status:<9} {}  ({})", finding.path, finding.pattern);
+        }
+        println!();
+        println!(
+            "{} file(s) flagged. Excluded files are purged on the next `omnicontext index`;",
+            findings.len()
+        );
+        println!("add patterns to [privacy] exclude to purge the rest.");
+    }
+
+    if !findings.is_empty() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+fn cmd_headers(path: &str, json: bool) -> Result<()> {
+    let repo_path = std::path::PathBuf::from(path)
+        .canonicalize()
+        .unwrap_or_else(|_| std::path::PathBuf::from(path));
+
+    let engine = omni_core::Engine::new(&repo_path)?;
+    let templates = engine.header_templates();
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(templates)?);
+    } else if templates.is_empty() {
+        println!("No repeated file headers detected.");
+    } else {
+        for template in templates {
+            println!(
+                "{} file(s), {} line(s), e.g. {}",
+                template.file_count,
+                template.lines,
+                template.examples.join(", ")
+            );
+            for line in template.text.lines() {
+                println!("  | {line}");
+            }
+            println!();
+        }
+        println!(
+            "{} template(s) left out of chunk content and full-text search.",
+            templates.len()
+        );
+    }
+    Ok(())
+}
+
+fn cmd_search_log(
+    path: &str,
+    since: Option<&str>,
+    output: Option<&std::path::Path>,
+    clear: bool,
+) -> Result<()> {
+    use std::io::Write;
+
+    let repo_path = std::path::PathBuf::from(path)
+        .canonicalize()
+        .unwrap_or_else(|_| std::path::PathBuf::from(path));
+
+    let engine = omni_core::Engine::new(&repo_path)?;
+    let entries = engine.metadata_index().search_log(since)?;
+
+    let mut out: Box<dyn Write> = match output {
+        Some(file) => Box::new(std::io::BufWriter::new(std::fs::File::create(file)?)),
+        None => Box::new(std::io::stdout().lock()),
+    };
+    for entry in &entries {
+        writeln!(out, "{}", serde_json::to_string(entry)?)?;
+    }
+    out.flush()?;
+
+    if let Some(file) = output {
+        eprintln!("Exported {} entries to {}", entries.len(), file.display());
+    }
+    if clear {
+        let removed = engine.metadata_index().clear_search_log()?;
+        eprintln!("Cleared {removed} entries");
+    }
+    Ok(())
+}
+
 /// Launch the MCP server over HTTP SSE (`serve` subcommand).
 ///
 /// This is a convenience alias for `mcp --transport sse` with a more
 /// discoverable entry point for enterprise/remote deployments.
-async fn cmd_serve(path: &str, port: u16, host: &str) -> Result<()> {
+async fn cmd_serve(path: &str, port: u16, host: &str, read_only: bool) -> Result<()> {
     let repo_path = std::path::PathBuf::from(path)
         .canonicalize()
         .unwrap_or_else(|_| std::path::PathBuf::from(path));
@@ -658,6 +2164,9 @@ async fn cmd_serve(path: &str, port: u16, host: &str) -> Result<()> {
     } else {
         eprintln!("  Auth:       none (set OMNI_SERVER_TOKEN to enable)");
     }
+    if read_only {
+        eprintln!("  Mode:       read-only replica");
+    }
     eprintln!();
 
     let current_exe = std::env::current_exe()?;
@@ -666,7 +2175,8 @@ async fn cmd_serve(path: &str, port: u16, host: &str) -> Result<()> {
         |p| p.join("omnicontext-mcp"),
     );
 
-    let status = tokio::process::Command::new(&mcp_binary)
+    let mut command = tokio::process::Command::new(&mcp_binary);
+    command
         .arg("--repo")
         .arg(&repo_path)
         .arg("--transport")
@@ -674,7 +2184,11 @@ async fn cmd_serve(path: &str, port: u16, host: &str) -> Result<()> {
         .arg("--port")
         .arg(port.to_string())
         .arg("--host")
-        .arg(host)
+        .arg(host);
+    if read_only {
+        command.arg("--read-only");
+    }
+    let status = command
         .stdin(std::process::Stdio::inherit())
         .stdout(std::process::Stdio::inherit())
         .stderr(std::process::Stdio::inherit())
@@ -709,20 +2223,34 @@ fn cmd_config(show: bool, init: bool) -> Result<()> {
 
 [indexing]
 # exclude_patterns = [".git", "node_modules", "target", "__pycache__"]
+# vendored_patterns = ["node_modules", "vendor"]  # indexed, but down-ranked
 # max_file_size = 1048576  # 1 MB
 # max_chunk_tokens = 512
+# min_chunk_tokens = 48  # merge tiny sibling elements (getters, constants)
+
+# [indexing.languages.go]  # per-language chunking overrides
+# max_chunk_tokens = 384
+# split_strategy = "blocks"  # structural | blocks | lines
 
 [search]
 # default_limit = 10
 # rrf_k = 60
 # token_budget = 8192
+# target_model = "gpt-4o"  # count context budgets in this model's tokens
+# max_latency_ms = 150     # skip embedding/reranking when a query would exceed this
 
 [embedding]
 # dimensions = 384
 
 [watcher]
 # debounce_ms = 100
+# mode = "auto"  # native | poll; auto polls on network filesystems
 # poll_interval_secs = 300
+# ignore_patterns = ["*.swp", "*~", "4913", ".#*", "*.tmp"]  # editor temp files
+
+[storage]
+# auto_repair = true  # fix index inconsistencies on startup instead of failing
+# memory_limit_mb = 512  # cap vector memory; excess vectors spill to disk
 "#;
             std::fs::write(&config_file, default_config)?;
             println!("Created: {}", config_file.display());
@@ -745,6 +2273,9 @@ fn cmd_config(show: bool, init: bool) -> Result<()> {
             "  parse_concurrency = {}",
             config.indexing.parse_concurrency
         );
+        for (language, rules) in &config.indexing.languages {
+            println!("  [languages.{language}] {rules:?}");
+        }
         println!();
 
         println!("[search]");
@@ -779,7 +2310,9 @@ fn cmd_setup(action: SetupAction, json: bool) -> Result<()> {
 /// Download and cache the configured embedding model.
 fn cmd_setup_model(force: bool, json: bool) -> Result<()> {
     let spec = omni_core::embedder::model_manager::resolve_model_spec();
-    if !force && omni_core::embedder::model_manager::is_model_ready(spec) {
+    if force {
+        omni_core::embedder::model_manager::remove_model(spec)?;
+    } else if omni_core::embedder::model_manager::is_model_ready(spec) {
         if json {
             println!(
                 "{}",
@@ -812,7 +2345,7 @@ fn cmd_setup_model_status(json: bool) -> Result<()> {
     let ready = omni_core::embedder::model_manager::is_model_ready(spec);
     let path = omni_core::embedder::model_manager::model_path(spec);
     let size = if ready {
-        std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0)
+        std::fs::metadata(&path).map_or(0, |m| m.len())
     } else {
         0
     };
@@ -848,8 +2381,12 @@ fn cmd_setup_model_status(json: bool) -> Result<()> {
 
 /// Download and cache the cross-encoder reranker model (bge-reranker-v2-m3).
 fn cmd_setup_reranker(force: bool, json: bool) -> Result<()> {
-    use omni_core::embedder::model_manager::{ensure_model, is_model_ready, RERANKER_MODEL};
-    if !force && is_model_ready(&RERANKER_MODEL) {
+    use omni_core::embedder::model_manager::{
+        ensure_model, is_model_ready, remove_model, RERANKER_MODEL,
+    };
+    if force {
+        remove_model(&RERANKER_MODEL)?;
+    } else if is_model_ready(&RERANKER_MODEL) {
         if json {
             println!(
                 "{}",
@@ -879,6 +2416,154 @@ fn cmd_setup_reranker(force: bool, json: bool) -> Result<()> {
     Ok(())
 }
 
+/// Handle `models list|remove|verify`.
+#[allow(clippy::too_many_lines)] // one arm per subcommand
+fn cmd_models(action: ModelsAction, json: bool) -> Result<()> {
+    use omni_core::embedder::bundle::{create_bundle, install_bundle};
+    use omni_core::embedder::model_manager::{
+        find_model, list_models, remove_model, verify_model, ModelVerification,
+    };
+
+    let lookup = |name: &str| {
+        find_model(name).ok_or_else(|| {
+            anyhow::anyhow!("unknown model '{name}' (run `omnicontext models list`)")
+        })
+    };
+
+    match action {
+        ModelsAction::List => {
+            let models = list_models();
+            if json {
+                println!("{}", serde_json::to_string_pretty(&models)?);
+                return Ok(());
+            }
+            println!("{:<22} {:<10} {:>10}  PATH", "MODEL", "STATUS", "SIZE");
+            for model in &models {
+                let status = if model.ready {
+                    "installed"
+                } else if model.size_bytes > 0 {
+                    "partial"
+                } else {
+                    "missing"
+                };
+                println!(
+                    "{:<22} {:<10} {:>7} MB  {}",
+                    model.name,
+                    status,
+                    model.size_bytes / 1024 / 1024,
+                    model.path.display()
+                );
+            }
+        }
+        ModelsAction::Remove { name } => {
+            let spec = lookup(&name)?;
+            let freed = remove_model(spec)?;
+            if json {
+                println!(
+                    "{}",
+                    serde_json::json!({"model": spec.name, "removed": freed > 0, "bytes_freed": freed})
+                );
+            } else if freed > 0 {
+                println!(
+                    "Removed '{}' ({} MB freed).",
+                    spec.name,
+                    freed / 1024 / 1024
+                );
+            } else {
+                println!("Model '{}' is not installed.", spec.name);
+            }
+        }
+        ModelsAction::Verify { name } => {
+            let specs = match name {
+                Some(name) => vec![lookup(&name)?],
+                None => list_models()
+                    .iter()
+                    .filter(|m| m.ready)
+                    .filter_map(|m| find_model(m.name))
+                    .collect(),
+            };
+            let mut results = Vec::with_capacity(specs.len());
+            for spec in specs {
+                results.push((spec.name, verify_model(spec)?));
+            }
+            let failed = results
+                .iter()
+                .any(|(_, r)| matches!(r, ModelVerification::Mismatch { .. }));
+            if json {
+                let output: serde_json::Map<String, serde_json::Value> = results
+                    .iter()
+                    .map(|(name, r)| Ok(((*name).to_string(), serde_json::to_value(r)?)))
+                    .collect::<Result<_>>()?;
+                println!("{}", serde_json::to_string_pretty(&output)?);
+            } else if results.is_empty() {
+                println!("No models installed.");
+            } else {
+                for (name, result) in &results {
+                    match result {
+                        ModelVerification::Missing => println!("{name}: not installed"),
+                        ModelVerification::Verified { .. } => println!("{name}: OK"),
+                        ModelVerification::Unpinned { sha256 } => {
+                            println!("{name}: no reference digest (sha256 {sha256})");
+                        }
+                        ModelVerification::Mismatch { expected, actual } => println!(
+                            "{name}: CHECKSUM MISMATCH (expected {expected}, got {actual}); \
+                             run `omnicontext models remove {name}` and download again"
+                        ),
+                    }
+                }
+            }
+            if failed {
+                anyhow::bail!("model verification failed");
+            }
+        }
+        ModelsAction::Bundle { output, names } => {
+            let specs = if names.is_empty() {
+                list_models()
+                    .iter()
+                    .filter(|m| m.ready)
+                    .filter_map(|m| find_model(m.name))
+                    .collect()
+            } else {
+                names
+                    .iter()
+                    .map(|n| lookup(n))
+                    .collect::<Result<Vec<_>>>()?
+            };
+            if specs.is_empty() {
+                anyhow::bail!("no models installed to bundle");
+            }
+            let manifest = create_bundle(&specs, &output)?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&manifest)?);
+            } else {
+                let bytes: u64 = manifest
+                    .models
+                    .iter()
+                    .flat_map(|m| &m.files)
+                    .map(|f| f.size_bytes)
+                    .sum();
+                println!(
+                    "Bundled {} model(s) into {} ({} MB).",
+                    manifest.models.len(),
+                    output.display(),
+                    bytes / 1024 / 1024
+                );
+            }
+        }
+        ModelsAction::Install { bundle } => {
+            let manifest = install_bundle(&bundle)?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&manifest)?);
+            } else {
+                for model in &manifest.models {
+                    println!("Installed '{}'.", model.name);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
 /// Run the Universal IDE Orchestrator (`setup --all`).
 fn cmd_setup_all(dry_run: bool, json: bool) -> Result<()> {
     let result = orchestrator::orchestrate(dry_run)?;
@@ -0,0 +1,318 @@
+//! Public API surface report.
+//!
+//! Lists every public chunk in the index grouped by module (source file),
+//! with its declaration signature and whether it carries a doc comment. Two
+//! surfaces -- typically the live index and an archived snapshot of an older
+//! revision -- can be diffed for release notes: symbols added, removed, and
+//! those whose signature changed.
+//!
+//! Implementation blocks, tests, top-level statements, and summary chunks are
+//! not API items and are left out.
+
+#![allow(clippy::doc_markdown, clippy::missing_errors_doc)]
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+
+use crate::chunker::extract_signature_from_content;
+use crate::error::OmniResult;
+
+/// A public symbol in the API surface.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiSymbol {
+    /// Fully qualified name.
+    pub fqn: String,
+    /// Symbol kind (`function`, `class`, ...).
+    pub kind: String,
+    /// Definition line (1-indexed).
+    pub line: u32,
+    /// Declaration line, without attributes or doc comments.
+    pub signature: String,
+    /// Whether the symbol has a non-empty doc comment.
+    pub documented: bool,
+}
+
+/// Public symbols of one module.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiModule {
+    /// File path relative to the repository root.
+    pub path: String,
+    /// Public symbols in definition order.
+    pub symbols: Vec<ApiSymbol>,
+}
+
+impl ApiModule {
+    /// Number of documented symbols.
+    pub fn documented(&self) -> usize {
+        self.symbols.iter().filter(|s| s.documented).count()
+    }
+}
+
+/// The public API of an index.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ApiSurface {
+    /// Modules with at least one public symbol, in path order.
+    pub modules: Vec<ApiModule>,
+}
+
+impl ApiSurface {
+    /// Load the surface from an open index connection.
+    pub fn load(conn: &Connection) -> OmniResult<Self> {
+        let mut stmt = conn.prepare(
+            "SELECT f.path, c.symbol_path, c.kind, c.line_start, b.content, c.doc_comment
+             FROM chunks c
+             JOIN files f ON c.file_id = f.id
+             JOIN chunk_blobs b ON b.hash = c.blob_hash
+             WHERE c.visibility = 'public'
+               AND c.kind NOT IN ('impl', 'test', 'top_level', 'summary')
+             ORDER BY f.path, c.line_start",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            let content: String = row.get(4)?;
+            let doc: Option<String> = row.get(5)?;
+            Ok((
+                row.get::<_, String>(0)?,
+                ApiSymbol {
+                    fqn: row.get(1)?,
+                    kind: row.get(2)?,
+                    line: row.get(3)?,
+                    signature: extract_signature_from_content(&content),
+                    documented: doc.is_some_and(|d| !d.trim().is_empty()),
+                },
+            ))
+        })?;
+
+        let mut modules: BTreeMap<String, Vec<ApiSymbol>> = BTreeMap::new();
+        for row in rows {
+            let (path, symbol) = row?;
+            modules.entry(path).or_default().push(symbol);
+        }
+        Ok(Self {
+            modules: modules
+                .into_iter()
+                .map(|(path, symbols)| ApiModule { path, symbols })
+                .collect(),
+        })
+    }
+
+    /// Open an index snapshot read-only and load its surface.
+    ///
+    /// `path` may point at the `index.db` file itself or at a data directory
+    /// containing one.
+    pub fn open(path: &Path) -> OmniResult<Self> {
+        Self::load(&crate::index_diff::open_snapshot(path)?)
+    }
+
+    /// Total number of public symbols.
+    pub fn symbol_count(&self) -> usize {
+        self.modules.iter().map(|m| m.symbols.len()).sum()
+    }
+
+    /// Number of documented public symbols.
+    pub fn documented_count(&self) -> usize {
+        self.modules.iter().map(ApiModule::documented).sum()
+    }
+
+    /// Fraction of public symbols with a doc comment (1.0 when empty).
+    pub fn doc_coverage(&self) -> f64 {
+        let total = self.symbol_count();
+        if total == 0 {
+            1.0
+        } else {
+            self.documented_count() as f64 / total as f64
+        }
+    }
+
+    fn by_fqn(&self) -> BTreeMap<&str, &ApiSymbol> {
+        self.modules
+            .iter()
+            .flat_map(|m| m.symbols.iter())
+            .map(|s| (s.fqn.as_str(), s))
+            .collect()
+    }
+}
+
+/// A public symbol whose declaration changed between two surfaces.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignatureChange {
+    /// Fully qualified name.
+    pub fqn: String,
+    /// Signature in the old surface.
+    pub old: String,
+    /// Signature in the new surface.
+    pub new: String,
+}
+
+/// Differences between two API surfaces, each list sorted by FQN.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ApiSurfaceDiff {
+    /// Public symbols only in the new surface.
+    pub added: Vec<ApiSymbol>,
+    /// Public symbols only in the old surface.
+    pub removed: Vec<ApiSymbol>,
+    /// Public symbols in both whose signature differs.
+    pub changed: Vec<SignatureChange>,
+}
+
+impl ApiSurfaceDiff {
+    /// Whether the surfaces are identical.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Compare the API surface `old` to `new`.
+pub fn diff(old: &ApiSurface, new: &ApiSurface) -> ApiSurfaceDiff {
+    let old = old.by_fqn();
+    let new = new.by_fqn();
+    let mut out = ApiSurfaceDiff::default();
+
+    for (fqn, symbol) in &new {
+        match old.get(fqn) {
+            None => out.added.push((*symbol).clone()),
+            Some(prev) if prev.signature != symbol.signature => {
+                out.changed.push(SignatureChange {
+                    fqn: (*fqn).to_string(),
+                    old: prev.signature.clone(),
+                    new: symbol.signature.clone(),
+                });
+            }
+            Some(_) => {}
+        }
+    }
+    out.removed = old
+        .iter()
+        .filter(|(fqn, _)| !new.contains_key(*fqn))
+        .map(|(_, symbol)| (*symbol).clone())
+        .collect();
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::MetadataIndex;
+    use crate::types::{Chunk, ChunkKind, ComplexityMetrics, FileInfo, Language, Visibility};
+
+    fn chunk(
+        file_id: i64,
+        fqn: &str,
+        kind: ChunkKind,
+        vis: Visibility,
+        doc: Option<&str>,
+    ) -> Chunk {
+        Chunk {
+            id: 0,
+            file_id,
+            symbol_path: fqn.to_string(),
+            kind,
+            visibility: vis,
+            line_start: 1,
+            line_end: 3,
+            content: format!("#[inline]\npub fn {fqn}() {{}}"),
+            doc_comment: doc.map(str::to_string),
+            token_count: 4,
+            weight: 1.0,
+            vector_id: None,
+            is_summary: false,
+            is_generated: false,
+            language: None,
+            content_hash: 0,
+            complexity: ComplexityMetrics::default(),
+        }
+    }
+
+    fn api(fqn: &str, signature: &str) -> ApiModule {
+        ApiModule {
+            path: "src/lib.rs".into(),
+            symbols: vec![ApiSymbol {
+                fqn: fqn.into(),
+                kind: "function".into(),
+                line: 1,
+                signature: signature.into(),
+                documented: false,
+            }],
+        }
+    }
+
+    #[test]
+    fn test_load_groups_public_symbols_by_module() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let index = MetadataIndex::open(&dir.path().join("index.db")).expect("open db");
+        let file_id = index
+            .upsert_file(&FileInfo {
+                id: 0,
+                path: "src/auth.rs".into(),
+                language: Language::Rust,
+                content_hash: "h".into(),
+                size_bytes: 1,
+            })
+            .expect("upsert file");
+        for c in [
+            chunk(
+                file_id,
+                "auth_login",
+                ChunkKind::Function,
+                Visibility::Public,
+                Some("Log in."),
+            ),
+            chunk(
+                file_id,
+                "auth_logout",
+                ChunkKind::Function,
+                Visibility::Public,
+                None,
+            ),
+            chunk(
+                file_id,
+                "auth_secret",
+                ChunkKind::Function,
+                Visibility::Private,
+                None,
+            ),
+            chunk(
+                file_id,
+                "auth_tests",
+                ChunkKind::Test,
+                Visibility::Public,
+                None,
+            ),
+        ] {
+            index.insert_chunk(&c).expect("insert chunk");
+        }
+
+        let surface = ApiSurface::load(&index.connection()).expect("load");
+        assert_eq!(surface.modules.len(), 1);
+        assert_eq!(surface.modules[0].path, "src/auth.rs");
+        assert_eq!(surface.symbol_count(), 2);
+        assert_eq!(surface.documented_count(), 1);
+        assert!((surface.doc_coverage() - 0.5).abs() < f64::EPSILON);
+        let login = &surface.modules[0].symbols[0];
+        assert_eq!(login.signature, "pub fn auth_login() {}");
+        assert!(login.documented);
+    }
+
+    #[test]
+    fn test_diff_reports_added_removed_and_changed() {
+        let old = ApiSurface {
+            modules: vec![api("a::keep", "fn keep()"), api("a::gone", "fn gone()")],
+        };
+        let new = ApiSurface {
+            modules: vec![
+                api("a::keep", "fn keep(x: u32)"),
+                api("a::fresh", "fn fresh()"),
+            ],
+        };
+        let d = diff(&old, &new);
+        assert_eq!(d.added.len(), 1);
+        assert_eq!(d.added[0].fqn, "a::fresh");
+        assert_eq!(d.removed.len(), 1);
+        assert_eq!(d.removed[0].fqn, "a::gone");
+        assert_eq!(d.changed.len(), 1);
+        assert_eq!(d.changed[0].new, "fn keep(x: u32)");
+        assert!(diff(&new, &new).is_empty());
+    }
+}
@@ -114,7 +114,9 @@ fn bench_vector_insert(n: usize, dim: usize) -> f64 {
 
 fn bench_index_operations() -> Result<(), Box<dyn std::error::Error>> {
     use omni_core::index::MetadataIndex;
-    use omni_core::types::{Chunk, ChunkKind, FileInfo, Language, Symbol, Visibility};
+    use omni_core::types::{
+        Chunk, ChunkKind, ComplexityMetrics, FileInfo, Language, Symbol, Visibility,
+    };
 
     println!("SQLite Index Performance:");
 
@@ -156,7 +158,10 @@ fn bench_index_operations() -> Result<(), Box<dyn std::error::Error>> {
             weight: 1.0,
             vector_id: None,
             is_summary: false,
+            is_generated: false,
+            language: None,
             content_hash: 0,
+            complexity: ComplexityMetrics::default(),
         });
     }
     let symbols: Vec<Symbol> = chunks
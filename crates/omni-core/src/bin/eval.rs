@@ -145,6 +145,8 @@ fn recall_at_k(result_symbols: &[String], relevance_map: &HashMap<String, u32>,
 // ---------------------------------------------------------------------------
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
+    const K: usize = 10;
+
     let args = parse_args()?;
 
     println!("=== OmniContext NDCG@10 Evaluation ===");
@@ -162,7 +164,6 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let config = Config::load(&args.repo)?;
     let engine = Engine::with_config(config)?;
 
-    const K: usize = 10;
     let mut total_ndcg = 0.0_f64;
     let mut total_mrr = 0.0_f64;
     let mut total_recall = 0.0_f64;
@@ -553,6 +553,7 @@ mod tests {
             references: vec![],
             extends: vec![],
             implements: vec![],
+            complexity: crate::types::ComplexityMetrics::default(),
         }
     }
 
@@ -12,13 +12,16 @@
 //! 4. Large function -> split at block-level statement boundaries
 //! 5. 10-15% token overlap at boundaries for context continuity
 //! 6. Each chunk preserves the parent signature as a header for context
+//! 7. Runs of sibling elements under `min_chunk_tokens` -> one merged chunk
 
 pub mod contextual;
 pub mod token_counter;
 
-use crate::config::Config;
+use std::borrow::Cow;
+
+use crate::config::{Config, SplitStrategy};
 use crate::parser::StructuralElement;
-use crate::types::{Chunk, ChunkKind, FileInfo, ImportStatement};
+use crate::types::{Chunk, ChunkKind, ComplexityMetrics, FileInfo, ImportStatement, Symbol};
 
 use self::token_counter::TokenCounter;
 
@@ -44,22 +47,31 @@ pub fn chunk_elements(
     source_code: &str,
     counter: &dyn TokenCounter,
 ) -> Vec<Chunk> {
-    let max_tokens = config.indexing.max_chunk_tokens;
-    let overlap_fraction = config.indexing.overlap_fraction;
-    let target_overlap_tokens = config.indexing.overlap_tokens;
-    let fallback_overlap_lines = config.indexing.overlap_lines;
+    let rules = config.indexing.chunking_for(file_info.language);
+    let max_tokens = rules.max_chunk_tokens;
+    let overlap_fraction = rules.overlap_fraction;
+    let target_overlap_tokens = rules.overlap_tokens;
+    let fallback_overlap_lines = rules.overlap_lines;
     let include_module_decls = config.indexing.include_module_declarations;
     let mut chunks = Vec::new();
 
     let source_lines: Vec<&str> = source_code.lines().collect();
 
+    let elements = merge_small_elements(
+        elements,
+        &source_lines,
+        |kind| config.indexing.min_chunk_tokens_for(&rules, kind),
+        max_tokens,
+        counter,
+    );
+
     let module_declarations = if include_module_decls {
         extract_module_declarations(&source_lines)
     } else {
         String::new()
     };
 
-    for elem in elements {
+    for elem in elements.iter() {
         let start_line_idx = elem.line_start.saturating_sub(1) as usize;
 
         let backward_context = compute_backward_context(
@@ -87,15 +99,156 @@ pub fn chunk_elements(
             ));
         } else {
             let split_chunks = split_element(
-                elem, file_id, max_tokens, overlap_fraction, &context_header, counter,
+                elem, file_id, max_tokens, overlap_fraction, rules.split_strategy, &context_header,
+                counter,
             );
             chunks.extend(split_chunks);
         }
     }
 
+    let path_weight = config.indexing.path_weight(&file_info.path);
+    for chunk in &mut chunks {
+        chunk.weight = config.indexing.chunk_weight(chunk, path_weight);
+    }
+
     chunks
 }
 
+/// Merge runs of adjacent small siblings into one element each, so a class
+/// of one-line getters becomes one chunk instead of dozens of near-empty ones.
+///
+/// An element is small when its content is under `min_tokens` for its kind.
+/// A run holds elements with the same parent symbol and grows until the next
+/// element would push it past `max_tokens`. The merged element keeps the
+/// first element's symbol path and spans the source lines of the whole run.
+fn merge_small_elements<'a>(
+    elements: &'a [StructuralElement],
+    source_lines: &[&str],
+    min_tokens: impl Fn(ChunkKind) -> u32,
+    max_tokens: u32,
+    counter: &dyn TokenCounter,
+) -> Cow<'a, [StructuralElement]> {
+    let tokens: Vec<u32> = elements.iter().map(|e| counter.count(&e.content)).collect();
+    let small: Vec<bool> = elements
+        .iter()
+        .zip(&tokens)
+        .map(|(e, &t)| t < min_tokens(e.kind))
+        .collect();
+    if small.iter().filter(|&&s| s).count() < 2 {
+        return Cow::Borrowed(elements);
+    }
+
+    let mut merged = Vec::with_capacity(elements.len());
+    let mut run: Vec<&StructuralElement> = Vec::new();
+    let mut run_tokens = 0;
+    for (i, elem) in elements.iter().enumerate() {
+        let joins = small[i]
+            && run.last().is_some_and(|last| {
+                parent_path(&last.symbol_path) == parent_path(&elem.symbol_path)
+                    && elem.line_start > last.line_end
+            })
+            && run_tokens + tokens[i] <= max_tokens;
+        if !joins && !run.is_empty() {
+            merged.push(merge_run(&run, source_lines));
+            run.clear();
+            run_tokens = 0;
+        }
+        if small[i] {
+            run.push(elem);
+            run_tokens += tokens[i];
+        } else {
+            merged.push(elem.clone());
+        }
+    }
+    if !run.is_empty() {
+        merged.push(merge_run(&run, source_lines));
+    }
+    Cow::Owned(merged)
+}
+
+/// The symbol path an element is nested under (`""` at top level).
+fn parent_path(symbol_path: &str) -> &str {
+    symbol_path
+        .rfind(['.', ':'])
+        .map_or("", |i| symbol_path[..i].trim_end_matches(':'))
+}
+
+fn merge_run(run: &[&StructuralElement], source_lines: &[&str]) -> StructuralElement {
+    let (first, last) = (run[0], run[run.len() - 1]);
+    if run.len() == 1 {
+        return first.clone();
+    }
+    let start = first.line_start.saturating_sub(1) as usize;
+    let end = last.line_end as usize;
+    let content = if start < end && end <= source_lines.len() {
+        source_lines[start..end].join("\n")
+    } else {
+        run.iter()
+            .map(|e| e.content.as_str())
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    let mut elem = StructuralElement {
+        content,
+        line_end: last.line_end,
+        ..first.clone()
+    };
+    for other in &run[1..] {
+        elem.references.extend(other.references.iter().cloned());
+        elem.extends.extend(other.extends.iter().cloned());
+        elem.implements.extend(other.implements.iter().cloned());
+        elem.complexity.cyclomatic = elem.complexity.cyclomatic.max(other.complexity.cyclomatic);
+        elem.complexity.nesting_depth = elem
+            .complexity
+            .nesting_depth
+            .max(other.complexity.nesting_depth);
+    }
+    elem.references.sort();
+    elem.references.dedup();
+    elem
+}
+
+/// Symbol records for a file's chunks, plus the elements that were merged
+/// into another element's chunk (see `indexing.min_chunk_tokens`), so every
+/// definition stays resolvable by name. Summary chunks are skipped.
+pub fn chunk_symbols(
+    chunks: &[Chunk],
+    elements: &[StructuralElement],
+    file_id: i64,
+) -> Vec<Symbol> {
+    let symbol = |path: &str, kind: ChunkKind, line: u32| Symbol {
+        id: 0,
+        name: path.rsplit(['.', ':']).next().unwrap_or(path).to_string(),
+        fqn: path.to_string(),
+        kind,
+        file_id,
+        line,
+        chunk_id: None,
+    };
+    let mut symbols: Vec<Symbol> = chunks
+        .iter()
+        .filter(|c| !c.symbol_path.is_empty() && !c.is_summary)
+        .map(|c| symbol(&c.symbol_path, c.kind, c.line_start))
+        .collect();
+    let chunked: std::collections::HashSet<&str> =
+        chunks.iter().map(|c| c.symbol_path.as_str()).collect();
+    let merged_away: Vec<Symbol> = elements
+        .iter()
+        .filter(|e| !e.symbol_path.is_empty() && !chunked.contains(e.symbol_path.as_str()))
+        .filter(|e| {
+            chunks.iter().any(|c| {
+                !c.is_summary
+                    && c.line_start <= e.line_start
+                    && e.line_end <= c.line_end
+                    && parent_path(&c.symbol_path) == parent_path(&e.symbol_path)
+            })
+        })
+        .map(|e| symbol(&e.symbol_path, e.kind, e.line_start))
+        .collect();
+    symbols.extend(merged_away);
+    symbols
+}
+
 /// Compute backward context using token-based targeting with line-based fallback.
 ///
 /// Grabs lines preceding the element until either `target_tokens` is reached
@@ -376,7 +529,10 @@ fn element_to_chunk(
         weight: compute_weight(elem),
         vector_id: None,
         is_summary: false,
+        is_generated: false,
+        language: None,
         content_hash: 0, // computed by pipeline after chunking
+        complexity: elem.complexity,
     }
 }
 
@@ -390,15 +546,19 @@ fn compute_weight(elem: &StructuralElement) -> f64 {
 
 /// Split a large element into multiple chunks with overlap.
 ///
-/// Strategy depends on element kind:
+/// With [`SplitStrategy::Structural`], the split depends on element kind:
 /// - Class/Trait -> split at method boundaries (lines with `def` / `fn` / method signatures)
 /// - Function -> split at statement boundaries (lines that start at indent level 1)
 /// - Other -> split at line boundaries with overlap
+///
+/// [`SplitStrategy::Blocks`] splits at blank lines and [`SplitStrategy::Lines`]
+/// at fixed line intervals, regardless of kind.
 fn split_element(
     elem: &StructuralElement,
     file_id: i64,
     max_tokens: u32,
     overlap_fraction: f64,
+    strategy: SplitStrategy,
     context_header: &str,
     counter: &dyn TokenCounter,
 ) -> Vec<Chunk> {
@@ -411,11 +571,18 @@ fn split_element(
     // Build a header from the first line (signature) for context continuity
     let header = extract_header(elem);
 
-    // Find split points based on element kind
-    let split_points = match elem.kind {
-        ChunkKind::Class | ChunkKind::Trait | ChunkKind::Impl => find_class_split_points(&lines),
-        ChunkKind::Function | ChunkKind::Test => find_function_split_points(&lines),
-        _ => find_line_split_points(&lines, max_tokens),
+    // Find split points based on the strategy and element kind
+    let split_points = match (strategy, elem.kind) {
+        (SplitStrategy::Structural, ChunkKind::Class | ChunkKind::Trait | ChunkKind::Impl) => {
+            find_class_split_points(&lines, max_tokens)
+        }
+        (SplitStrategy::Structural, ChunkKind::Function | ChunkKind::Test) => {
+            find_function_split_points(&lines, max_tokens)
+        }
+        (SplitStrategy::Blocks, _) => find_block_split_points(&lines, max_tokens),
+        (SplitStrategy::Structural | SplitStrategy::Lines, _) => {
+            find_line_split_points(&lines, max_tokens)
+        }
     };
 
     create_chunks_from_splits(
@@ -456,7 +623,7 @@ fn extract_header(elem: &StructuralElement) -> String {
 }
 
 /// Find split points for class-like elements (at method boundaries).
-fn find_class_split_points(lines: &[&str]) -> Vec<usize> {
+fn find_class_split_points(lines: &[&str], max_tokens: u32) -> Vec<usize> {
     let mut split_points = Vec::new();
 
     for (i, line) in lines.iter().enumerate() {
@@ -493,14 +660,14 @@ fn find_class_split_points(lines: &[&str]) -> Vec<usize> {
 
     // If no method boundaries found, fall back to line-based splits
     if split_points.is_empty() {
-        return find_line_split_points(lines, 512); // default chunk size
+        return find_line_split_points(lines, max_tokens);
     }
 
     split_points
 }
 
 /// Find split points for function-like elements (at statement boundaries).
-fn find_function_split_points(lines: &[&str]) -> Vec<usize> {
+fn find_function_split_points(lines: &[&str], max_tokens: u32) -> Vec<usize> {
     let mut split_points = Vec::new();
 
     if lines.is_empty() {
@@ -548,7 +715,25 @@ fn find_function_split_points(lines: &[&str]) -> Vec<usize> {
     }
 
     if split_points.is_empty() {
-        return find_line_split_points(lines, 512);
+        return find_line_split_points(lines, max_tokens);
+    }
+
+    split_points
+}
+
+/// Split at blank lines: each paragraph of the body is a candidate piece,
+/// and small ones are merged afterwards by `merge_small_boundaries`.
+fn find_block_split_points(lines: &[&str], max_tokens: u32) -> Vec<usize> {
+    let split_points: Vec<usize> = lines
+        .windows(2)
+        .enumerate()
+        .filter(|(_, pair)| pair[0].trim().is_empty() && !pair[1].trim().is_empty())
+        .map(|(i, _)| i + 1)
+        .filter(|&i| i > 1)
+        .collect();
+
+    if split_points.is_empty() {
+        return find_line_split_points(lines, max_tokens);
     }
 
     split_points
@@ -627,7 +812,10 @@ fn create_chunks_from_splits(
             weight: compute_weight(elem),
             vector_id: None,
             is_summary: false,
+            is_generated: false,
+            language: None,
             content_hash: 0, // computed by pipeline after chunking
+            complexity: elem.complexity,
         });
         return chunks;
     }
@@ -697,7 +885,10 @@ fn create_chunks_from_splits(
             weight: compute_weight(elem),
             vector_id: None,
             is_summary: false,
+            is_generated: false,
+            language: None,
             content_hash: 0, // computed by pipeline after chunking
+            complexity: elem.complexity,
         });
     }
 
@@ -909,7 +1100,10 @@ pub fn generate_summary_chunks(
         weight: ChunkKind::Summary.default_weight(),
         vector_id: None,
         is_summary: true,
+        is_generated: false,
+        language: None,
         content_hash: 0, // summary chunks always re-embed; no delta detection
+        complexity: ComplexityMetrics::default(),
     });
 
     summaries
@@ -919,7 +1113,7 @@ pub fn generate_summary_chunks(
 ///
 /// Skips decorators (@...), doc comments (///), and blank lines
 /// to find the actual declaration line.
-fn extract_signature_from_content(content: &str) -> String {
+pub(crate) fn extract_signature_from_content(content: &str) -> String {
     for line in content.lines() {
         let trimmed = line.trim();
         if trimmed.is_empty()
@@ -959,6 +1153,7 @@ mod tests {
             references: vec!["foo".to_string()],
             extends: Vec::new(),
             implements: Vec::new(),
+            complexity: ComplexityMetrics::default(),
         }
     }
 
@@ -1126,11 +1321,128 @@ mod tests {
         assert_eq!(header, "def hello():");
     }
 
+    #[test]
+    fn test_language_rules_split_go_at_blank_lines() {
+        // A Go handler: paragraphs separated by blank lines, `if err != nil`
+        // after nearly every statement.
+        let mut lines = vec!["func Handle(w http.ResponseWriter, r *http.Request) {".to_string()];
+        for i in 0..12 {
+            lines.push(format!("\tv{i}, err := load{i}(r.Context())"));
+            lines.push("\tif err != nil {".to_string());
+            lines.push("\t\treturn".to_string());
+            lines.push("\t}".to_string());
+            lines.push(format!("\tw.Write(v{i})"));
+            lines.push(String::new());
+        }
+        lines.push("}".to_string());
+        let source = lines.join("\n");
+        let elem = make_element(&source, ChunkKind::Function);
+
+        let mut config = default_config();
+        config.indexing.languages.insert(
+            "Go".to_string(),
+            crate::config::LanguageChunkingConfig {
+                max_chunk_tokens: Some(120),
+                overlap_fraction: Some(0.0),
+                split_strategy: Some(SplitStrategy::Blocks),
+                ..Default::default()
+            },
+        );
+        let mut file_info = dummy_file_info();
+        file_info.language = crate::types::Language::Go;
+
+        let chunks = chunk_elements(
+            &[elem],
+            &file_info,
+            &[],
+            1,
+            &config,
+            &source,
+            &EstimateTokenCounter,
+        );
+        assert!(chunks.len() > 1, "got {} chunks", chunks.len());
+        for chunk in &chunks[1..] {
+            let previous = lines[chunk.line_start as usize - 2].as_str();
+            assert!(
+                previous.is_empty(),
+                "chunk at line {} does not start a paragraph",
+                chunk.line_start
+            );
+        }
+    }
+
+    #[test]
+    fn test_small_siblings_merged_into_one_chunk() {
+        let source = "class User:\n    def name(self):\n        return self._name\n    def email(self):\n        return self._email\n    def age(self):\n        return self._age\nLIMIT = 10\n";
+        let element = |path: &str, kind, lines: (u32, u32)| {
+            let content = source
+                .lines()
+                .skip(lines.0 as usize - 1)
+                .take((lines.1 - lines.0 + 1) as usize)
+                .collect::<Vec<_>>()
+                .join("\n");
+            StructuralElement {
+                symbol_path: path.to_string(),
+                name: path.rsplit('.').next().unwrap_or(path).to_string(),
+                line_start: lines.0,
+                line_end: lines.1,
+                ..make_element(&content, kind)
+            }
+        };
+        let elements = vec![
+            element("user.User.name", ChunkKind::Function, (2, 3)),
+            element("user.User.email", ChunkKind::Function, (4, 5)),
+            element("user.User.age", ChunkKind::Function, (6, 7)),
+            element("user.LIMIT", ChunkKind::Const, (8, 8)),
+        ];
+        let mut config = default_config();
+        config.indexing.include_module_declarations = false;
+        config.indexing.overlap_lines = 0;
+        let chunk = |config: &Config| {
+            chunk_elements(
+                &elements,
+                &dummy_file_info(),
+                &[],
+                1,
+                config,
+                source,
+                &EstimateTokenCounter,
+            )
+        };
+        assert_eq!(chunk(&config).len(), 4, "merging is off by default");
+
+        config.indexing.min_chunk_tokens = 32;
+        let chunks = chunk(&config);
+        assert_eq!(
+            chunks.len(),
+            2,
+            "getters merge; the constant has another parent"
+        );
+        assert_eq!(chunks[0].symbol_path, "user.User.name");
+        assert_eq!((chunks[0].line_start, chunks[0].line_end), (2, 7));
+        assert!(chunks[0].content.contains("self._age"));
+
+        let symbols = chunk_symbols(&chunks, &elements, 1);
+        let mut names: Vec<&str> = symbols.iter().map(|s| s.name.as_str()).collect();
+        names.sort_unstable();
+        assert_eq!(names, vec!["LIMIT", "age", "email", "name"]);
+
+        config
+            .indexing
+            .min_chunk_tokens_by_kind
+            .insert("function".to_string(), 0);
+        assert_eq!(
+            chunk(&config).len(),
+            4,
+            "per-kind override disables merging"
+        );
+    }
+
     #[test]
     fn test_find_class_split_points() {
         let code = "class Foo:\n    def a(self):\n        pass\n    def b(self):\n        pass\n";
         let lines: Vec<&str> = code.lines().collect();
-        let points = find_class_split_points(&lines);
+        let points = find_class_split_points(&lines, 512);
         assert!(points.contains(&1), "should split at def a: {points:?}");
         assert!(points.contains(&3), "should split at def b: {points:?}");
     }
@@ -1139,7 +1451,7 @@ mod tests {
     fn test_find_function_split_points_if_blocks() {
         let code = "def foo():\n    x = 1\n    if x > 0:\n        return x\n    return 0\n";
         let lines: Vec<&str> = code.lines().collect();
-        let points = find_function_split_points(&lines);
+        let points = find_function_split_points(&lines, 512);
         // Should find `if` and `return` at base indent
         assert!(!points.is_empty(), "should find split points");
     }
@@ -1193,6 +1505,90 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_complexity_weighting_is_opt_in() {
+        let mut getter = make_element("def get(self):\n    return self.x\n", ChunkKind::Function);
+        getter.complexity = ComplexityMetrics {
+            cyclomatic: 1,
+            nesting_depth: 0,
+        };
+        let mut gnarly = make_element("def run(self):\n    pass\n", ChunkKind::Function);
+        gnarly.complexity = ComplexityMetrics {
+            cyclomatic: 9,
+            nesting_depth: 3,
+        };
+        let file_info = dummy_file_info();
+        let elements = [getter, gnarly];
+
+        let mut config = default_config();
+        let plain = chunk_elements(
+            &elements,
+            &file_info,
+            &[],
+            1,
+            &config,
+            "",
+            &EstimateTokenCounter,
+        );
+        assert!((plain[0].weight - plain[1].weight).abs() < f64::EPSILON);
+        assert_eq!(plain[1].complexity.cyclomatic, 9);
+
+        config.indexing.complexity_weighting = true;
+        let weighted = chunk_elements(
+            &elements,
+            &file_info,
+            &[],
+            1,
+            &config,
+            "",
+            &EstimateTokenCounter,
+        );
+        assert!(
+            weighted[1].weight > weighted[0].weight,
+            "complex function should outweigh trivial getter"
+        );
+    }
+
+    #[test]
+    fn test_configured_kind_and_path_weights() {
+        let elements = [
+            make_element("def run():\n    pass\n", ChunkKind::Function),
+            make_element("def test_run():\n    pass\n", ChunkKind::Test),
+        ];
+        let chunk = |config: &Config, path: &str| {
+            let file_info = FileInfo {
+                path: path.into(),
+                ..dummy_file_info()
+            };
+            chunk_elements(
+                &elements,
+                &file_info,
+                &[],
+                1,
+                config,
+                "",
+                &EstimateTokenCounter,
+            )
+        };
+
+        let mut config = default_config();
+        config.indexing.kind_weights.insert("test".into(), 0.9);
+        config.indexing.path_weights.insert("examples/".into(), 0.5);
+        config
+            .indexing
+            .path_weights
+            .insert("src/core/**".into(), 1.2);
+
+        let plain = chunk(&config, "lib/run.py");
+        assert!((plain[0].weight - 0.85).abs() < 1e-9);
+        assert!((plain[1].weight - 0.9).abs() < 1e-9);
+
+        let demoted = chunk(&config, "examples/run.py");
+        assert!((demoted[0].weight - 0.425).abs() < 1e-9);
+        let boosted = chunk(&config, "src/core/run.py");
+        assert!((boosted[0].weight - 1.02).abs() < 1e-9);
+    }
+
     #[test]
     fn test_enrich_no_context() {
         let content = "fn foo() {}";
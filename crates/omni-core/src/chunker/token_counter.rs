@@ -6,6 +6,14 @@
 //! - `EstimateTokenCounter`: Heuristic estimator (chars / 4) for environments
 //!   without a loaded tokenizer (CI, unit tests, fallback).
 //!
+//! Chunk token counts are measured with the embedding model's tokenizer.
+//! Context windows are consumed by a different model, so
+//! [`counter_for_model`] resolves the tokenizer of the target LLM instead:
+//! OpenAI models map to their tiktoken encoding (`cl100k_base`,
+//! `o200k_base`), built in with the `tiktoken` feature or otherwise loaded
+//! from a tiktoken-compatible `tokenizer.json` in [`tokenizers_dir`]. Models
+//! without a published tokenizer use the estimator, with a warning.
+//!
 //! ## Why not just pass `&Tokenizer` directly?
 //!
 //! A trait abstraction is more extensible:
@@ -14,7 +22,9 @@
 //! 3. Future token counters (tiktoken, sentencepiece) slot in seamlessly
 //! 4. The estimator remains available as a zero-cost fallback
 
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
 
 /// Trait for counting tokens in text content.
 ///
@@ -116,6 +126,166 @@ fn estimate_tokens_heuristic(text: &str) -> u32 {
     estimate.max(1)
 }
 
+/// Counter for an OpenAI encoding embedded by `tiktoken-rs`, so no
+/// tokenizer file has to be installed.
+#[cfg(feature = "tiktoken")]
+pub struct TiktokenCounter {
+    bpe: tiktoken_rs::CoreBPE,
+}
+
+#[cfg(feature = "tiktoken")]
+impl TiktokenCounter {
+    /// Build the counter for `encoding`, or `None` if its tables fail to load.
+    pub fn new(encoding: ModelEncoding) -> Option<Self> {
+        let bpe = match encoding {
+            ModelEncoding::Cl100k => tiktoken_rs::cl100k_base(),
+            ModelEncoding::O200k => tiktoken_rs::o200k_base(),
+        };
+        match bpe {
+            Ok(bpe) => Some(Self { bpe }),
+            Err(e) => {
+                tracing::warn!(encoding = encoding.name(), error = %e, "failed to load tiktoken encoding");
+                None
+            }
+        }
+    }
+}
+
+#[cfg(feature = "tiktoken")]
+impl TokenCounter for TiktokenCounter {
+    fn count(&self, text: &str) -> u32 {
+        let len = self.bpe.encode_ordinary(text).len();
+        u32::try_from(len).unwrap_or(u32::MAX).max(1)
+    }
+
+    fn name(&self) -> &'static str {
+        "tiktoken"
+    }
+}
+
+/// Tokenizer encoding used by a family of hosted LLMs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ModelEncoding {
+    /// `cl100k_base`: GPT-4, GPT-3.5, `text-embedding-3`.
+    Cl100k,
+    /// `o200k_base`: GPT-4o, GPT-4.1, GPT-5, o-series reasoning models.
+    O200k,
+}
+
+impl ModelEncoding {
+    /// Encoding used by `model`, if it is a known OpenAI model.
+    ///
+    /// Matching is by prefix and ignores case and a provider prefix
+    /// (`openai/gpt-4o-mini` → `O200k`).
+    pub fn for_model(model: &str) -> Option<Self> {
+        let model = model.trim().to_ascii_lowercase();
+        let model = model.rsplit('/').next().unwrap_or(&model);
+        const O200K: &[&str] = &[
+            "gpt-4o", "chatgpt-4o", "gpt-4.1", "gpt-4.5", "gpt-5", "o1", "o3", "o4",
+        ];
+        const CL100K: &[&str] = &["gpt-4", "gpt-3.5", "text-embedding-3", "text-embedding-ada"];
+        if O200K.iter().any(|p| model.starts_with(p)) {
+            Some(Self::O200k)
+        } else if CL100K.iter().any(|p| model.starts_with(p)) {
+            Some(Self::Cl100k)
+        } else {
+            None
+        }
+    }
+
+    /// tiktoken encoding name, also the tokenizer file stem in [`tokenizers_dir`].
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Cl100k => "cl100k_base",
+            Self::O200k => "o200k_base",
+        }
+    }
+}
+
+/// Directory holding LLM tokenizers: `~/.omnicontext/tokenizers/`.
+///
+/// Each encoding is looked up as `<encoding>.json` (e.g. `o200k_base.json`)
+/// in HuggingFace `tokenizer.json` format.
+pub fn tokenizers_dir() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("omnicontext")
+        .join("tokenizers")
+}
+
+/// Token counter for the LLM that will consume a context window.
+///
+/// `tokenizer_path` (an explicit `tokenizer.json`) wins over the model's
+/// encoding. A known encoding uses the built-in tiktoken tables when the
+/// `tiktoken` feature is enabled, else `<encoding>.json` in
+/// [`tokenizers_dir`]. Anything else falls back to [`EstimateTokenCounter`]
+/// with a warning, logged once per model. Counters are cached for the life
+/// of the process.
+pub fn counter_for_model(
+    model: Option<&str>,
+    tokenizer_path: Option<&Path>,
+) -> Arc<dyn TokenCounter> {
+    static LOADED: OnceLock<Mutex<HashMap<String, Arc<dyn TokenCounter>>>> = OnceLock::new();
+
+    let encoding = model.and_then(ModelEncoding::for_model);
+    let key = match (tokenizer_path, encoding, model) {
+        (Some(path), _, _) => format!("file:{}", path.display()),
+        (None, Some(encoding), _) => format!("encoding:{}", encoding.name()),
+        (None, None, Some(model)) => format!("model:{model}"),
+        (None, None, None) => return Arc::new(EstimateTokenCounter),
+    };
+
+    let mut loaded = LOADED
+        .get_or_init(Mutex::default)
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    if let Some(counter) = loaded.get(&key) {
+        return Arc::clone(counter);
+    }
+    let counter = load_context_counter(model.unwrap_or(""), tokenizer_path, encoding);
+    loaded.insert(key, Arc::clone(&counter));
+    counter
+}
+
+/// Resolve a context counter; see [`counter_for_model`].
+fn load_context_counter(
+    model: &str,
+    tokenizer_path: Option<&Path>,
+    encoding: Option<ModelEncoding>,
+) -> Arc<dyn TokenCounter> {
+    if let Some(path) = tokenizer_path {
+        if let Some(counter) = ActualTokenCounter::from_path(path) {
+            tracing::info!(path = %path.display(), "loaded context tokenizer");
+            return Arc::new(counter);
+        }
+        tracing::warn!(path = %path.display(), model, "context tokenizer not found");
+    }
+    let Some(encoding) = encoding else {
+        tracing::warn!(
+            model,
+            "no known tokenizer for model, estimating context tokens as chars / 4"
+        );
+        return Arc::new(EstimateTokenCounter);
+    };
+
+    #[cfg(feature = "tiktoken")]
+    if let Some(counter) = TiktokenCounter::new(encoding) {
+        return Arc::new(counter);
+    }
+    let path = tokenizers_dir().join(format!("{}.json", encoding.name()));
+    if let Some(counter) = ActualTokenCounter::from_path(&path) {
+        tracing::info!(path = %path.display(), "loaded context tokenizer");
+        return Arc::new(counter);
+    }
+    tracing::warn!(
+        path = %path.display(),
+        model,
+        "context tokenizer not found, estimating context tokens as chars / 4 \
+         (install the tokenizer file or build with the `tiktoken` feature)"
+    );
+    Arc::new(EstimateTokenCounter)
+}
+
 /// Create the best available token counter.
 ///
 /// Tries to load the tokenizer from the model directory. Falls back to
@@ -196,6 +366,61 @@ mod tests {
         assert_eq!(counter.name(), "estimate");
     }
 
+    #[test]
+    fn test_model_encoding_lookup() {
+        assert_eq!(
+            ModelEncoding::for_model("gpt-4o-mini"),
+            Some(ModelEncoding::O200k)
+        );
+        assert_eq!(
+            ModelEncoding::for_model("openai/GPT-4.1"),
+            Some(ModelEncoding::O200k)
+        );
+        assert_eq!(
+            ModelEncoding::for_model("o3-mini"),
+            Some(ModelEncoding::O200k)
+        );
+        assert_eq!(
+            ModelEncoding::for_model("gpt-4-turbo"),
+            Some(ModelEncoding::Cl100k)
+        );
+        assert_eq!(
+            ModelEncoding::for_model("gpt-3.5-turbo"),
+            Some(ModelEncoding::Cl100k)
+        );
+        assert_eq!(ModelEncoding::for_model("claude-sonnet-4"), None);
+        assert_eq!(ModelEncoding::O200k.name(), "o200k_base");
+    }
+
+    #[test]
+    fn test_counter_for_model_falls_back_to_estimate() {
+        assert_eq!(counter_for_model(None, None).name(), "estimate");
+        assert_eq!(
+            counter_for_model(Some("claude-opus-4"), None).name(),
+            "estimate"
+        );
+        let missing = std::path::Path::new("/nonexistent/o200k_base.json");
+        let expected = if cfg!(feature = "tiktoken") {
+            "tiktoken"
+        } else {
+            "estimate"
+        };
+        assert_eq!(
+            counter_for_model(Some("gpt-4o"), Some(missing)).name(),
+            expected
+        );
+    }
+
+    #[cfg(feature = "tiktoken")]
+    #[test]
+    fn test_tiktoken_counts_known_encodings() {
+        for encoding in [ModelEncoding::Cl100k, ModelEncoding::O200k] {
+            let counter = TiktokenCounter::new(encoding).expect("embedded encoding");
+            assert_eq!(counter.count("hello world"), 2, "{}", encoding.name());
+            assert_eq!(counter.count(""), 1);
+        }
+    }
+
     #[test]
     fn test_counter_is_deterministic() {
         let counter = EstimateTokenCounter;
@@ -6,6 +6,7 @@
 
 use std::path::Path;
 
+use crate::diff_context::ChangedSymbol;
 use crate::error::{OmniError, OmniResult};
 use crate::index::MetadataIndex;
 
@@ -20,6 +21,19 @@ pub struct CoChangeFile {
     pub shared_commits: usize,
 }
 
+/// A file ranked by how often it changes and how complex it is.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Hotspot {
+    /// File path (relative to the repo root).
+    pub path: String,
+    /// Number of indexed commits that touched the file.
+    pub commits: usize,
+    /// Summed cyclomatic complexity of the file's chunks.
+    pub complexity: u64,
+    /// Churn x complexity, each normalised to the busiest file (0.0-1.0).
+    pub score: f64,
+}
+
 /// A parsed commit record.
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct CommitInfo {
@@ -37,6 +51,30 @@ pub struct CommitInfo {
     pub files_changed: Vec<String>,
 }
 
+/// A file touched by recent commits.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct RecentFile {
+    /// File path (relative to the repo root).
+    pub path: String,
+    /// Number of the recent commits that touched the file.
+    pub commits: usize,
+    /// Subjects of those commits, newest first.
+    pub messages: Vec<String>,
+}
+
+/// What changed in the last few commits; see
+/// [`crate::pipeline::Engine::recent_changes`].
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct RecentChanges {
+    /// The commits, newest first.
+    pub commits: Vec<CommitInfo>,
+    /// Files they touched, most often touched first.
+    pub files: Vec<RecentFile>,
+    /// Indexed symbols whose current lines the commits changed, most
+    /// changed lines first.
+    pub symbols: Vec<ChangedSymbol>,
+}
+
 /// Commit lineage engine that indexes git history.
 pub struct CommitEngine {
     /// Maximum number of commits to index.
@@ -251,12 +289,78 @@ impl CommitEngine {
             })
             .collect();
 
-        results.sort_by(|a, b| b.frequency.cmp(&a.frequency));
+        results.sort_by_key(|r| std::cmp::Reverse(r.frequency));
         results.truncate(limit);
 
         Ok(results)
     }
 
+    /// Rank indexed files by change frequency times complexity.
+    ///
+    /// Churn is the number of indexed commits touching the file; complexity
+    /// is the sum of its chunks' cyclomatic complexity, counting chunks
+    /// without computed metrics as 1. Files with no commits are skipped.
+    #[allow(clippy::missing_errors_doc)]
+    pub fn hotspots(index: &MetadataIndex, limit: usize) -> OmniResult<Vec<Hotspot>> {
+        let conn = index.connection();
+        let mut stmt = conn.prepare(
+            "SELECT f.path,
+                    (SELECT COUNT(*) FROM commit_files cf WHERE cf.file_path = f.path),
+                    (SELECT COALESCE(SUM(MAX(c.complexity, 1)), 0) FROM chunks c
+                     WHERE c.file_id = f.id AND c.kind != 'summary')
+             FROM files f",
+        )?;
+        let rows: Vec<(String, i64, i64)> = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+            .filter_map(std::result::Result::ok)
+            .filter(|(_, commits, complexity)| *commits > 0 && *complexity > 0)
+            .collect();
+
+        let max_commits = rows.iter().map(|r| r.1).max().unwrap_or(1) as f64;
+        let max_complexity = rows.iter().map(|r| r.2).max().unwrap_or(1) as f64;
+        let mut hotspots: Vec<Hotspot> = rows
+            .into_iter()
+            .map(|(path, commits, complexity)| Hotspot {
+                score: (commits as f64 / max_commits) * (complexity as f64 / max_complexity),
+                path,
+                commits: commits as usize,
+                complexity: complexity as u64,
+            })
+            .collect();
+
+        hotspots.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.path.cmp(&b.path))
+        });
+        hotspots.truncate(limit);
+        Ok(hotspots)
+    }
+
+    /// Group `commits` (newest first) by the files they touched, most
+    /// often touched first, then by path.
+    #[must_use]
+    pub fn files_touched(commits: &[CommitInfo]) -> Vec<RecentFile> {
+        let mut by_path: std::collections::HashMap<&str, RecentFile> =
+            std::collections::HashMap::new();
+        for commit in commits {
+            for file in &commit.files_changed {
+                let entry = by_path.entry(file.as_str()).or_insert_with(|| RecentFile {
+                    path: file.clone(),
+                    commits: 0,
+                    messages: Vec::new(),
+                });
+                entry.commits += 1;
+                entry.messages.push(commit.message.clone());
+            }
+        }
+
+        let mut files: Vec<RecentFile> = by_path.into_values().collect();
+        files.sort_by(|a, b| b.commits.cmp(&a.commits).then_with(|| a.path.cmp(&b.path)));
+        files
+    }
+
     #[allow(clippy::missing_errors_doc)]
     /// Get the most active authors for a file.
     pub fn top_authors(
@@ -273,7 +377,7 @@ impl CommitEngine {
         }
 
         let mut sorted: Vec<(String, usize)> = author_counts.into_iter().collect();
-        sorted.sort_by(|a, b| b.1.cmp(&a.1));
+        sorted.sort_by_key(|e| std::cmp::Reverse(e.1));
         sorted.truncate(limit);
 
         Ok(sorted)
@@ -305,6 +409,28 @@ mod tests {
         assert!(commits.is_empty());
     }
 
+    #[test]
+    fn test_files_touched_counts_commits_per_file() {
+        let commit = |message: &str, files: &[&str]| CommitInfo {
+            hash: message.into(),
+            message: message.into(),
+            author: "Alice".into(),
+            timestamp: "2024-01-15T10:00:00+00:00".into(),
+            summary: None,
+            files_changed: files.iter().map(|f| (*f).to_string()).collect(),
+        };
+        let files = CommitEngine::files_touched(&[
+            commit("fix: retry", &["src/net.rs", "src/lib.rs"]),
+            commit("feat: net", &["src/net.rs"]),
+            commit("docs", &["README.md"]),
+        ]);
+
+        let paths: Vec<&str> = files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, vec!["src/net.rs", "README.md", "src/lib.rs"]);
+        assert_eq!(files[0].commits, 2);
+        assert_eq!(files[0].messages, vec!["fix: retry", "feat: net"]);
+    }
+
     #[test]
     fn test_commits_for_file_uses_junction_table() {
         let dir = tempfile::tempdir().expect("tempdir");
@@ -364,4 +490,83 @@ mod tests {
 
         let _ = engine; // keep engine alive for type inference
     }
+
+    #[test]
+    fn test_hotspots_rank_churn_times_complexity() {
+        use crate::types::{Chunk, ChunkKind, ComplexityMetrics, FileInfo, Language, Visibility};
+
+        let dir = tempfile::tempdir().expect("tempdir");
+        let index =
+            crate::index::MetadataIndex::open(&dir.path().join("test.db")).expect("open db");
+
+        // (path, cyclomatic complexity of its single chunk)
+        for (path, cyclomatic) in [("src/busy.rs", 10), ("src/calm.rs", 10), ("src/tiny.rs", 1)] {
+            let file_id = index
+                .upsert_file(&FileInfo {
+                    id: 0,
+                    path: path.into(),
+                    language: Language::Rust,
+                    content_hash: path.to_string(),
+                    size_bytes: 1,
+                })
+                .expect("upsert file");
+            index
+                .insert_chunk(&Chunk {
+                    id: 0,
+                    file_id,
+                    symbol_path: path.to_string(),
+                    kind: ChunkKind::Function,
+                    visibility: Visibility::Public,
+                    line_start: 1,
+                    line_end: 5,
+                    content: format!("fn f() {{}} // {path}"),
+                    doc_comment: None,
+                    token_count: 4,
+                    weight: 1.0,
+                    vector_id: None,
+                    is_summary: false,
+                    is_generated: false,
+                    language: None,
+                    content_hash: 0,
+                    complexity: ComplexityMetrics {
+                        cyclomatic,
+                        nesting_depth: 0,
+                    },
+                })
+                .expect("insert chunk");
+        }
+
+        let touches: [(&str, &[&str]); 4] = [
+            ("c1", &["src/busy.rs", "src/tiny.rs"]),
+            ("c2", &["src/busy.rs", "src/tiny.rs"]),
+            ("c3", &["src/busy.rs", "src/tiny.rs"]),
+            ("c4", &["src/calm.rs", "README.md"]),
+        ];
+        for (hash, files) in touches {
+            CommitEngine::store_commit_pub(
+                &index,
+                &CommitInfo {
+                    hash: hash.into(),
+                    message: "change".into(),
+                    author: "Alice".into(),
+                    timestamp: "2024-01-15T10:00:00+00:00".into(),
+                    summary: None,
+                    files_changed: files.iter().map(|f| (*f).to_string()).collect(),
+                },
+            )
+            .expect("store commit");
+        }
+
+        let hotspots = CommitEngine::hotspots(&index, 10).expect("hotspots");
+        let paths: Vec<&str> = hotspots.iter().map(|h| h.path.as_str()).collect();
+        assert_eq!(paths, vec!["src/busy.rs", "src/calm.rs", "src/tiny.rs"]);
+        assert_eq!(hotspots[0].commits, 3);
+        assert_eq!(hotspots[0].complexity, 10);
+        assert!((hotspots[0].score - 1.0).abs() < f64::EPSILON);
+
+        assert_eq!(
+            CommitEngine::hotspots(&index, 1).expect("hotspots").len(),
+            1
+        );
+    }
 }
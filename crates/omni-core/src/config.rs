@@ -9,10 +9,12 @@
 //! 5. Compiled-in defaults
 
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 
 use crate::embedder::quantization::QuantizationMode;
 use crate::error::{OmniError, OmniResult};
+use crate::types::{ChunkKind, Language};
 
 /// Top-level configuration for OmniContext.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -43,15 +45,48 @@ pub struct Config {
     /// HyDE (Hypothetical Document Embedding) configuration.
     #[serde(default)]
     pub hyde: HydeConfig,
+
+    /// Sensitive-path exclusion policy.
+    #[serde(default)]
+    pub privacy: PrivacyConfig,
+
+    /// SQLite storage and WAL management.
+    #[serde(default)]
+    pub storage: StorageConfig,
+
+    /// Sandbox limits for WASM plugins in `.omnicontext/plugins/`.
+    #[serde(default)]
+    pub plugins: PluginsConfig,
+
+    /// Layering rules checked by `omnicontext check-arch`.
+    #[serde(default)]
+    pub architecture: ArchitectureConfig,
+
+    /// Store index files here instead of the per-repo data directory.
+    ///
+    /// Set programmatically (e.g. by distributed indexing workers, which
+    /// each write a partial index); never read from config files.
+    #[serde(skip)]
+    pub data_dir_override: Option<PathBuf>,
 }
 
 /// Indexing-specific settings.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[allow(clippy::struct_excessive_bools)] // Independent on/off settings, not a state machine.
 pub struct IndexingConfig {
     /// File patterns to exclude from indexing (glob syntax).
     #[serde(default = "IndexingConfig::default_excludes")]
     pub exclude_patterns: Vec<String>,
 
+    /// Vendored-dependency trees to index anyway (e.g. `node_modules`,
+    /// `vendor`), matched against path components like `exclude_patterns`.
+    ///
+    /// A match overrides an exclude pattern on the same component. Results
+    /// from these trees are scaled by `search.vendored_weight` and only
+    /// returned when a search asks for vendored code.
+    #[serde(default)]
+    pub vendored_patterns: Vec<String>,
+
     /// Maximum file size to index (in bytes). Files larger than this are skipped.
     #[serde(default = "IndexingConfig::default_max_file_size")]
     pub max_file_size: u64,
@@ -64,7 +99,22 @@ pub struct IndexingConfig {
     #[serde(default = "IndexingConfig::default_max_chunk_tokens")]
     pub max_chunk_tokens: u32,
 
+    /// Elements smaller than this many tokens (one-line getters, constants)
+    /// are merged with adjacent small siblings into one chunk, up to
+    /// `max_chunk_tokens`. 0 (the default) keeps every element separate.
+    #[serde(default)]
+    pub min_chunk_tokens: u32,
+
+    /// Per-kind overrides of `min_chunk_tokens`, keyed by chunk kind
+    /// (`function`, `const`, `typedef`, ...). Set a kind to 0 to never
+    /// merge it.
+    #[serde(default)]
+    pub min_chunk_tokens_by_kind: BTreeMap<String, u32>,
+
     /// Whether to follow symbolic links.
+    ///
+    /// Symlink cycles are broken and a target reachable through several
+    /// links is indexed once.
     #[serde(default)]
     pub follow_symlinks: bool,
 
@@ -89,25 +139,264 @@ pub struct IndexingConfig {
     /// type definitions) in each chunk's context header regardless of their distance.
     #[serde(default = "IndexingConfig::default_include_module_declarations")]
     pub include_module_declarations: bool,
+
+    /// Whether to scale each chunk's structural weight by its complexity, so
+    /// branchy core logic ranks above trivial getters. Off by default.
+    #[serde(default)]
+    pub complexity_weighting: bool,
+
+    /// Per-kind overrides of the structural weight in
+    /// [`ChunkKind::default_weight`], keyed by chunk kind (`function`,
+    /// `class`, `test`, ...). Re-index with `--force` after changing it.
+    #[serde(default)]
+    pub kind_weights: BTreeMap<String, f64>,
+
+    /// Weight multipliers by path pattern (gitignore syntax, as in
+    /// `[privacy] exclude`), e.g. `"src/core/**" = 1.3` or
+    /// `"examples/" = 0.5`. A chunk's weight is multiplied by every pattern
+    /// its file matches. Re-index with `--force` after changing it.
+    #[serde(default)]
+    pub path_weights: BTreeMap<String, f64>,
+
+    /// What to do with license headers and "generated by" notices in the
+    /// text sent to the embedder (default: `keep`). Stored chunk content is
+    /// never changed. Re-index with `--force` after changing it.
+    #[serde(default)]
+    pub leading_comments: LeadingComments,
+
+    /// Whether to detect comment blocks that open many files (license
+    /// headers, codegen banners) and leave them out of chunk content and the
+    /// full-text index. See [`crate::patterns::detect_header_templates`].
+    #[serde(default = "IndexingConfig::default_strip_repeated_headers")]
+    pub strip_repeated_headers: bool,
+
+    /// Whether to embed generated code (files with a "DO NOT EDIT" or
+    /// `@generated` banner; see [`crate::parser::generated`]). Off by
+    /// default: generated chunks stay keyword-searchable with
+    /// `include_generated`, but get no vectors. Re-index with `--force`
+    /// after turning it on.
+    #[serde(default)]
+    pub embed_generated: bool,
+
+    /// Per-language chunking overrides, keyed by language name (`go`,
+    /// `python`, `typescript`, ...) as in `[indexing.languages.go]`. Unset
+    /// fields fall back to the settings above.
+    #[serde(default)]
+    pub languages: BTreeMap<String, LanguageChunkingConfig>,
+}
+
+/// Chunking overrides for one language. See [`IndexingConfig::chunking_for`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LanguageChunkingConfig {
+    /// Overrides `indexing.max_chunk_tokens`.
+    #[serde(default)]
+    pub max_chunk_tokens: Option<u32>,
+    /// Overrides `indexing.min_chunk_tokens`.
+    #[serde(default)]
+    pub min_chunk_tokens: Option<u32>,
+    /// Overrides `indexing.overlap_tokens`.
+    #[serde(default)]
+    pub overlap_tokens: Option<u32>,
+    /// Overrides `indexing.overlap_lines`.
+    #[serde(default)]
+    pub overlap_lines: Option<usize>,
+    /// Overrides `indexing.overlap_fraction`.
+    #[serde(default)]
+    pub overlap_fraction: Option<f64>,
+    /// How oversized elements are split (default: `structural`).
+    #[serde(default)]
+    pub split_strategy: Option<SplitStrategy>,
+    /// Overrides `indexing.leading_comments`.
+    #[serde(default)]
+    pub leading_comments: Option<LeadingComments>,
+}
+
+/// Treatment of license and generated-code comment blocks before embedding.
+/// See [`crate::embedder::boilerplate`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LeadingComments {
+    /// Embed chunks as stored.
+    #[default]
+    Keep,
+    /// Drop the blocks from the embedded text.
+    Strip,
+    /// Keep only the first line of each block, enough to say the file is
+    /// licensed without letting the license text dominate the embedding.
+    Downweight,
+}
+
+/// Where an element too large for one chunk is cut.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SplitStrategy {
+    /// Method boundaries in classes, statement boundaries in functions,
+    /// fixed windows otherwise. Tuned on Python, Rust, and TypeScript.
+    #[default]
+    Structural,
+    /// Blank lines, i.e. the paragraphs formatters like `gofmt` leave
+    /// between logical steps.
+    Blocks,
+    /// Fixed windows of roughly `max_chunk_tokens`, nudged to blank lines.
+    Lines,
+}
+
+/// Chunking settings in effect for one language.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChunkingRules {
+    /// Maximum chunk size in tokens.
+    pub max_chunk_tokens: u32,
+    /// Elements below this size are merged with small neighbours.
+    pub min_chunk_tokens: u32,
+    /// Target backward-context overlap in tokens.
+    pub overlap_tokens: u32,
+    /// Upper bound on backward-context lines.
+    pub overlap_lines: usize,
+    /// Overlap fraction between pieces of a split element.
+    pub overlap_fraction: f64,
+    /// Where oversized elements are cut.
+    pub split_strategy: SplitStrategy,
 }
 
 impl Default for IndexingConfig {
     fn default() -> Self {
         Self {
             exclude_patterns: Self::default_excludes(),
+            vendored_patterns: Vec::new(),
             max_file_size: Self::default_max_file_size(),
             parse_concurrency: Self::default_parse_concurrency(),
             max_chunk_tokens: Self::default_max_chunk_tokens(),
+            min_chunk_tokens: 0,
+            min_chunk_tokens_by_kind: BTreeMap::new(),
             follow_symlinks: false,
             overlap_lines: Self::default_overlap_lines(),
             overlap_tokens: Self::default_overlap_tokens(),
             overlap_fraction: Self::default_overlap_fraction(),
             include_module_declarations: Self::default_include_module_declarations(),
+            complexity_weighting: false,
+            kind_weights: BTreeMap::new(),
+            path_weights: BTreeMap::new(),
+            leading_comments: LeadingComments::default(),
+            strip_repeated_headers: Self::default_strip_repeated_headers(),
+            embed_generated: false,
+            languages: BTreeMap::new(),
         }
     }
 }
 
 impl IndexingConfig {
+    /// Minimum size for elements of `kind` under `rules`: the
+    /// `min_chunk_tokens_by_kind` entry if any, else `rules.min_chunk_tokens`.
+    pub fn min_chunk_tokens_for(&self, rules: &ChunkingRules, kind: ChunkKind) -> u32 {
+        self.min_chunk_tokens_by_kind
+            .get(kind.as_str())
+            .copied()
+            .unwrap_or(rules.min_chunk_tokens)
+    }
+
+    /// Structural weight of `chunk`: its kind weight (the `kind_weights`
+    /// entry, else [`ChunkKind::default_weight`]) times its visibility
+    /// multiplier and, with `complexity_weighting`, its complexity
+    /// multiplier, then times `path_weight` (see [`Self::path_weight`]).
+    pub fn chunk_weight(&self, chunk: &crate::types::Chunk, path_weight: f64) -> f64 {
+        let kind = self
+            .kind_weights
+            .get(chunk.kind.as_str())
+            .copied()
+            .unwrap_or_else(|| chunk.kind.default_weight());
+        let mut weight = kind * chunk.visibility.weight_multiplier();
+        if self.complexity_weighting && !chunk.is_summary {
+            weight = (weight * chunk.complexity.weight_multiplier()).min(1.0);
+        }
+        weight * path_weight
+    }
+
+    /// Product of the `path_weights` multipliers matching `path` (relative
+    /// to the repo root); 1.0 when none match.
+    pub fn path_weight(&self, path: &Path) -> f64 {
+        if self.path_weights.is_empty() {
+            return 1.0;
+        }
+        let path = path.to_string_lossy().replace('\\', "/");
+        let path = path.trim_start_matches("./");
+        self.path_weights
+            .iter()
+            .filter(|(pattern, _)| {
+                crate::owners::compile_pattern(pattern).is_some_and(|set| set.is_match(path))
+            })
+            .map(|(_, multiplier)| multiplier)
+            .product()
+    }
+
+    /// Check `kind_weights` and `path_weights`: kinds must exist, patterns
+    /// must compile, and weights must be finite and non-negative.
+    pub fn validate_weights(&self) -> OmniResult<()> {
+        let invalid = |details: String| Err(OmniError::Config { details });
+        for (kind, weight) in &self.kind_weights {
+            if ChunkKind::from_str_lossy(kind).as_str() != kind {
+                return invalid(format!(
+                    "unknown chunk kind in [indexing.kind_weights]: {kind:?}"
+                ));
+            }
+            if !weight.is_finite() || *weight < 0.0 {
+                return invalid(format!(
+                    "invalid weight for {kind:?} in [indexing.kind_weights]: {weight}"
+                ));
+            }
+        }
+        for (pattern, weight) in &self.path_weights {
+            if crate::owners::compile_pattern(pattern).is_none() {
+                return invalid(format!(
+                    "invalid pattern in [indexing.path_weights]: {pattern:?}"
+                ));
+            }
+            if !weight.is_finite() || *weight < 0.0 {
+                return invalid(format!(
+                    "invalid weight for {pattern:?} in [indexing.path_weights]: {weight}"
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Chunking settings for `language`: its `[indexing.languages.<name>]`
+    /// overrides (name matched case-insensitively) over the global ones.
+    pub fn chunking_for(&self, language: Language) -> ChunkingRules {
+        let overrides = self
+            .languages
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(language.as_str()))
+            .map(|(_, rules)| rules);
+        ChunkingRules {
+            max_chunk_tokens: overrides
+                .and_then(|o| o.max_chunk_tokens)
+                .unwrap_or(self.max_chunk_tokens),
+            min_chunk_tokens: overrides
+                .and_then(|o| o.min_chunk_tokens)
+                .unwrap_or(self.min_chunk_tokens),
+            overlap_tokens: overrides
+                .and_then(|o| o.overlap_tokens)
+                .unwrap_or(self.overlap_tokens),
+            overlap_lines: overrides
+                .and_then(|o| o.overlap_lines)
+                .unwrap_or(self.overlap_lines),
+            overlap_fraction: overrides
+                .and_then(|o| o.overlap_fraction)
+                .unwrap_or(self.overlap_fraction),
+            split_strategy: overrides.and_then(|o| o.split_strategy).unwrap_or_default(),
+        }
+    }
+
+    /// `leading_comments` for `language`: its `[indexing.languages.<name>]`
+    /// override, if any, else the global setting.
+    pub fn leading_comments_for(&self, language: Language) -> LeadingComments {
+        self.languages
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(language.as_str()))
+            .and_then(|(_, rules)| rules.leading_comments)
+            .unwrap_or(self.leading_comments)
+    }
+
     fn default_excludes() -> Vec<String> {
         vec![
             ".git".into(),
@@ -154,6 +443,10 @@ impl IndexingConfig {
     fn default_include_module_declarations() -> bool {
         true
     }
+
+    fn default_strip_repeated_headers() -> bool {
+        true
+    }
 }
 
 /// Search-specific settings.
@@ -175,6 +468,25 @@ pub struct SearchConfig {
     #[serde(default = "SearchConfig::default_token_budget")]
     pub token_budget: u32,
 
+    /// LLM that consumes context windows (e.g. `gpt-4o`, `claude-sonnet-4`).
+    ///
+    /// Selects the tokenizer the budget is measured in and the entry in
+    /// `model_budgets`. When unset, budgets are measured with index-time
+    /// token counts.
+    #[serde(default)]
+    pub target_model: Option<String>,
+
+    /// Per-model token budgets, keyed by model name prefix
+    /// (`"gpt-4o" = 16000`). The longest matching prefix wins; models
+    /// without an entry use `token_budget`.
+    #[serde(default)]
+    pub model_budgets: BTreeMap<String, u32>,
+
+    /// Tokenizer (`tokenizer.json`) used to count context window tokens,
+    /// overriding the one derived from `target_model`.
+    #[serde(default)]
+    pub context_tokenizer: Option<PathBuf>,
+
     /// Reranker configuration.
     #[serde(default)]
     pub reranker: RerankerConfig,
@@ -183,6 +495,54 @@ pub struct SearchConfig {
     /// Shadow headers contain dependency counts, risk level, and co-change partners.
     #[serde(default)]
     pub shadow_headers: bool,
+
+    /// Per-column BM25 weights for keyword (FTS5) search.
+    #[serde(default)]
+    pub bm25: Bm25Weights,
+
+    /// Stem English words in keyword search (default: true).
+    ///
+    /// Controls both the Porter tokenizer on the full-text index and the
+    /// stemmed prefix terms added during query expansion. Changing it
+    /// rebuilds the full-text index on the next open.
+    #[serde(default = "default_true")]
+    pub stemming: bool,
+
+    /// Adjustments to the built-in stopword list.
+    #[serde(default)]
+    pub stopwords: StopwordConfig,
+
+    /// Record every query, its latency, contributing signals, and returned
+    /// chunk IDs in the local `search_log` table (default: false).
+    ///
+    /// Nothing leaves the machine; export with `omnicontext search-log`.
+    #[serde(default)]
+    pub log_queries: bool,
+
+    /// Score multiplier for results under `indexing.vendored_patterns`
+    /// (0.0 - 1.0, default 0.1), so library code ranks below your own.
+    #[serde(default = "SearchConfig::default_vendored_weight")]
+    pub vendored_weight: f64,
+
+    /// Latency budget per search in milliseconds (default: unlimited).
+    ///
+    /// Query embedding and reranking are skipped for a query when their
+    /// observed cost (a CPU estimate before they are first timed) would
+    /// exceed the remaining budget; results then list them in
+    /// `score_breakdown.skipped_signals`.
+    #[serde(default)]
+    pub max_latency_ms: Option<u64>,
+
+    /// Searches a long-running server (daemon) runs at once (default 2).
+    ///
+    /// Searches share the engine, so without a cap every client could
+    /// start model inference at once and oversubscribe the CPU.
+    #[serde(default = "SearchConfig::default_max_concurrent_searches")]
+    pub max_concurrent_searches: usize,
+
+    /// How search results are expanded into a context window.
+    #[serde(default)]
+    pub context: ContextConfig,
 }
 
 impl Default for SearchConfig {
@@ -192,13 +552,37 @@ impl Default for SearchConfig {
             max_limit: Self::default_max_limit(),
             rrf_k: Self::default_rrf_k(),
             token_budget: Self::default_token_budget(),
+            target_model: None,
+            model_budgets: BTreeMap::new(),
+            context_tokenizer: None,
             reranker: RerankerConfig::default(),
             shadow_headers: false,
+            bm25: Bm25Weights::default(),
+            stemming: true,
+            stopwords: StopwordConfig::default(),
+            log_queries: false,
+            vendored_weight: Self::default_vendored_weight(),
+            max_latency_ms: None,
+            max_concurrent_searches: Self::default_max_concurrent_searches(),
+            context: ContextConfig::default(),
         }
     }
 }
 
 impl SearchConfig {
+    /// Token budget for context windows consumed by `model`: the longest
+    /// matching `model_budgets` prefix (case-insensitive), else `token_budget`.
+    pub fn budget_for_model(&self, model: Option<&str>) -> u32 {
+        let Some(model) = model.map(str::to_ascii_lowercase) else {
+            return self.token_budget;
+        };
+        self.model_budgets
+            .iter()
+            .filter(|(prefix, _)| model.starts_with(&prefix.to_ascii_lowercase()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map_or(self.token_budget, |(_, budget)| *budget)
+    }
+
     fn default_limit() -> usize {
         10
     }
@@ -211,6 +595,112 @@ impl SearchConfig {
     fn default_token_budget() -> u32 {
         4000
     }
+    fn default_vendored_weight() -> f64 {
+        0.1
+    }
+    fn default_max_concurrent_searches() -> usize {
+        2
+    }
+}
+
+/// Context window assembly (`[search.context]`).
+///
+/// Search results are expanded before packing: files with many matches
+/// contribute all their chunks, and the top results pull in their
+/// dependency-graph neighbours. Neighbour and whole-file chunks are scored
+/// as a fraction of the results that brought them in.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContextConfig {
+    /// Matches in one file at which the whole file is included. 0 never
+    /// includes whole files.
+    #[serde(default = "ContextConfig::default_whole_file_min_matches")]
+    pub whole_file_min_matches: usize,
+
+    /// Score of a whole-file chunk relative to the file's average match.
+    #[serde(default = "ContextConfig::default_whole_file_discount")]
+    pub whole_file_discount: f64,
+
+    /// Pull in dependency-graph neighbours of the top results.
+    #[serde(default = "default_true")]
+    pub graph_neighbors: bool,
+
+    /// Skip neighbour pulls when the symbol graph has more nodes than
+    /// this; walking a huge graph can cost more than the context is worth.
+    #[serde(default)]
+    pub max_graph_nodes: Option<usize>,
+
+    /// Hops followed from each result.
+    #[serde(default = "ContextConfig::default_neighbor_depth")]
+    pub neighbor_depth: usize,
+
+    /// Top results whose neighbours are pulled in.
+    #[serde(default = "ContextConfig::default_neighbor_results")]
+    pub neighbor_results: usize,
+
+    /// Score of a dependency (what a result uses) relative to the result.
+    #[serde(default = "ContextConfig::default_upstream_discount")]
+    pub upstream_discount: f64,
+
+    /// Score of a dependent (what uses a result) relative to the result.
+    #[serde(default = "ContextConfig::default_downstream_discount")]
+    pub downstream_discount: f64,
+
+    /// Score of a graph-augmented retrieval neighbour relative to the top
+    /// result, before its own relevance.
+    #[serde(default = "ContextConfig::default_gar_discount")]
+    pub gar_discount: f64,
+
+    /// Most chunks taken from one file. 0 means no limit.
+    #[serde(default)]
+    pub max_chunks_per_file: usize,
+}
+
+impl Default for ContextConfig {
+    fn default() -> Self {
+        Self {
+            whole_file_min_matches: Self::default_whole_file_min_matches(),
+            whole_file_discount: Self::default_whole_file_discount(),
+            graph_neighbors: true,
+            max_graph_nodes: None,
+            neighbor_depth: Self::default_neighbor_depth(),
+            neighbor_results: Self::default_neighbor_results(),
+            upstream_discount: Self::default_upstream_discount(),
+            downstream_discount: Self::default_downstream_discount(),
+            gar_discount: Self::default_gar_discount(),
+            max_chunks_per_file: 0,
+        }
+    }
+}
+
+impl ContextConfig {
+    /// Whether neighbours should be pulled from a graph of `graph_nodes`.
+    pub fn pulls_neighbors(&self, graph_nodes: usize) -> bool {
+        self.graph_neighbors
+            && self.neighbor_depth > 0
+            && self.max_graph_nodes.map_or(true, |max| graph_nodes <= max)
+    }
+
+    fn default_whole_file_min_matches() -> usize {
+        3
+    }
+    fn default_whole_file_discount() -> f64 {
+        0.9
+    }
+    fn default_neighbor_depth() -> usize {
+        1
+    }
+    fn default_neighbor_results() -> usize {
+        3
+    }
+    fn default_upstream_discount() -> f64 {
+        0.5
+    }
+    fn default_downstream_discount() -> f64 {
+        0.4
+    }
+    fn default_gar_discount() -> f64 {
+        0.5
+    }
 }
 
 /// Cross-encoder reranker configuration.
@@ -237,6 +727,12 @@ pub struct RerankerConfig {
     /// Items beyond `max_candidates` have their score multiplied by this factor.
     #[serde(default = "RerankerConfig::default_unranked_demotion")]
     pub unranked_demotion: f64,
+
+    /// Number of (query, chunk) scores kept in an LRU cache, so repeated
+    /// searches skip inference for pairs already scored. Keyed by content,
+    /// so an edited chunk is rescored. 0 disables the cache.
+    #[serde(default = "RerankerConfig::default_cache_size")]
+    pub cache_size: usize,
 }
 
 impl Default for RerankerConfig {
@@ -247,6 +743,7 @@ impl Default for RerankerConfig {
             batch_size: Self::default_batch_size(),
             max_seq_length: Self::default_max_seq_length(),
             unranked_demotion: Self::default_unranked_demotion(),
+            cache_size: Self::default_cache_size(),
         }
     }
 }
@@ -267,6 +764,68 @@ impl RerankerConfig {
     fn default_unranked_demotion() -> f64 {
         0.5
     }
+    fn default_cache_size() -> usize {
+        4096
+    }
+}
+
+/// Per-column BM25 weights for the `chunks_fts` table (`[search.bm25]`).
+///
+/// A match in a column with weight 2.0 counts twice as much as one in a
+/// column with weight 1.0. Documentation-heavy repositories typically raise
+/// `doc_comment` to 1.0 or above so prose matches compete with code matches.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Bm25Weights {
+    /// Weight of the chunk body.
+    #[serde(default = "Bm25Weights::default_content")]
+    pub content: f64,
+
+    /// Weight of the doc comment attached to the chunk.
+    #[serde(default = "Bm25Weights::default_doc_comment")]
+    pub doc_comment: f64,
+
+    /// Weight of the symbol path (e.g. `auth::middleware::validate`).
+    #[serde(default = "Bm25Weights::default_symbol_path")]
+    pub symbol_path: f64,
+}
+
+impl Default for Bm25Weights {
+    fn default() -> Self {
+        Self {
+            content: Self::default_content(),
+            doc_comment: Self::default_doc_comment(),
+            symbol_path: Self::default_symbol_path(),
+        }
+    }
+}
+
+impl Bm25Weights {
+    fn default_content() -> f64 {
+        1.0
+    }
+    fn default_doc_comment() -> f64 {
+        0.5
+    }
+    fn default_symbol_path() -> f64 {
+        2.0
+    }
+}
+
+/// Adjustments to the stopwords stripped from natural-language queries
+/// (`[search.stopwords]`).
+///
+/// The built-in list drops words like `not` and `before`, which carry
+/// meaning in queries such as "retry not idempotent"; list those under
+/// `remove` to keep them.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StopwordConfig {
+    /// Extra words to strip.
+    #[serde(default)]
+    pub add: Vec<String>,
+
+    /// Built-in stopwords to keep.
+    #[serde(default)]
+    pub remove: Vec<String>,
 }
 
 /// Embedding model configuration.
@@ -329,6 +888,16 @@ pub struct EmbeddingConfig {
     /// Default: `QuantizationMode::None` (FP32 model, no quantization).
     #[serde(default)]
     pub quantization_mode: QuantizationMode,
+
+    /// Re-embed every chunk in the background when the loaded embedding model
+    /// differs from the one recorded with the index (default: false).
+    ///
+    /// The job is queued at startup and resumes across restarts; see
+    /// `Engine::start_reembed_job()`. When false, the mismatch is logged and
+    /// reported by `Engine::model_drift()`; call `Engine::reembed_all()` to
+    /// fix it.
+    #[serde(default)]
+    pub reembed_on_model_change: bool,
 }
 
 impl Default for EmbeddingConfig {
@@ -341,6 +910,7 @@ impl Default for EmbeddingConfig {
             enable_sparse_retrieval: Self::default_enable_sparse_retrieval(),
             cloud_api_key: None,
             quantization_mode: QuantizationMode::None,
+            reembed_on_model_change: false,
         }
     }
 }
@@ -381,16 +951,31 @@ pub struct WatcherConfig {
     #[serde(default = "WatcherConfig::default_debounce_ms")]
     pub debounce_ms: u64,
 
-    /// Interval between full scans (in seconds) for catching missed events.
+    /// How changes are detected (default: `auto`).
+    #[serde(default)]
+    pub mode: WatchMode,
+
+    /// Interval between rescans (in seconds). Rescans catch events the
+    /// native backend missed and are the only source of changes in poll
+    /// mode. 0 disables them.
     #[serde(default = "WatcherConfig::default_poll_interval_secs")]
     pub poll_interval_secs: u64,
+
+    /// File names of editor temp and swap files the watcher ignores.
+    ///
+    /// A pattern is an exact name or contains one `*` wildcard (`*.swp`,
+    /// `.#*`, `#*#`). Replacing the list drops the defaults.
+    #[serde(default = "WatcherConfig::default_ignore_patterns")]
+    pub ignore_patterns: Vec<String>,
 }
 
 impl Default for WatcherConfig {
     fn default() -> Self {
         Self {
             debounce_ms: Self::default_debounce_ms(),
+            mode: WatchMode::default(),
             poll_interval_secs: Self::default_poll_interval_secs(),
+            ignore_patterns: Self::default_ignore_patterns(),
         }
     }
 }
@@ -402,6 +987,137 @@ impl WatcherConfig {
     fn default_poll_interval_secs() -> u64 {
         300
     }
+    fn default_ignore_patterns() -> Vec<String> {
+        vec![
+            // Vim swap files and its writability probe
+            "*.swp".into(),
+            "*.swo".into(),
+            "*.swx".into(),
+            "4913".into(),
+            // Backups (Vim, Emacs) and Emacs lock/autosave files
+            "*~".into(),
+            ".#*".into(),
+            "#*#".into(),
+            // Atomic-save scratch files (VS Code, JetBrains, generic)
+            "*.crswap".into(),
+            "*___jb_tmp___".into(),
+            "*___jb_old___".into(),
+            "*.tmp".into(),
+        ]
+    }
+}
+
+/// How the file watcher detects changes. See [`crate::watcher`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WatchMode {
+    /// Native events, or polling when the repository is on a network
+    /// filesystem (NFS, SMB, 9p, FUSE) or the native backend cannot start.
+    #[default]
+    Auto,
+    /// Native events only; setup failures are errors.
+    Native,
+    /// Rescan every `poll_interval_secs`, for mounts where native events
+    /// are unreliable but not detected as such.
+    Poll,
+}
+
+/// SQLite storage settings.
+///
+/// `index.db` runs in WAL mode, and SQLite's automatic checkpoints never
+/// shrink the WAL file, so long writer sessions (the daemon) checkpoint it
+/// with `TRUNCATE` on a schedule.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StorageConfig {
+    /// Minimum seconds between WAL checkpoints while the index is written.
+    /// `0` disables time-based checkpoints.
+    #[serde(default = "StorageConfig::default_checkpoint_interval_secs")]
+    pub checkpoint_interval_secs: u64,
+
+    /// Checkpoint as soon as `index.db-wal` grows past this many megabytes,
+    /// whatever the interval. `0` disables the size check.
+    #[serde(default = "StorageConfig::default_wal_size_threshold_mb")]
+    pub wal_size_threshold_mb: u64,
+
+    /// Repair what the startup health check finds (stale full-text index,
+    /// vectors out of step with chunks after a writer crashed, a
+    /// `vectors.bin` of the wrong dimensions) instead of refusing to start
+    /// (default: true).
+    #[serde(default = "default_true")]
+    pub auto_repair: bool,
+
+    /// Approximate cap on memory held by the vector index, in megabytes.
+    /// Past it, the ANN structure is dropped and vectors spill to a file
+    /// next to `vectors.bin`; search still covers them, from disk.
+    /// Unset means no cap (default).
+    #[serde(default)]
+    pub memory_limit_mb: Option<u64>,
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            checkpoint_interval_secs: Self::default_checkpoint_interval_secs(),
+            wal_size_threshold_mb: Self::default_wal_size_threshold_mb(),
+            auto_repair: true,
+            memory_limit_mb: None,
+        }
+    }
+}
+
+impl StorageConfig {
+    /// [`Self::memory_limit_mb`] in bytes.
+    pub fn memory_limit_bytes(&self) -> Option<usize> {
+        self.memory_limit_mb
+            .map(|mb| usize::try_from(mb.saturating_mul(1024 * 1024)).unwrap_or(usize::MAX))
+    }
+
+    fn default_checkpoint_interval_secs() -> u64 {
+        300
+    }
+    fn default_wal_size_threshold_mb() -> u64 {
+        64
+    }
+}
+
+/// WebAssembly plugin settings.
+///
+/// Plugins run in an interpreter with no host imports, so they cannot touch
+/// the filesystem or network; these limits bound what a single call may
+/// consume before it is aborted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginsConfig {
+    /// Load plugins from `.omnicontext/plugins/` (default: true).
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+
+    /// Instruction budget (wasmi fuel) for one plugin call. A call that runs
+    /// out traps and the host keeps its own result.
+    #[serde(default = "PluginsConfig::default_fuel_per_call")]
+    pub fuel_per_call: u64,
+
+    /// Maximum linear memory a plugin instance may grow to, in megabytes.
+    #[serde(default = "PluginsConfig::default_max_memory_mb")]
+    pub max_memory_mb: u32,
+}
+
+impl Default for PluginsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            fuel_per_call: Self::default_fuel_per_call(),
+            max_memory_mb: Self::default_max_memory_mb(),
+        }
+    }
+}
+
+impl PluginsConfig {
+    fn default_fuel_per_call() -> u64 {
+        1_000_000_000
+    }
+    fn default_max_memory_mb() -> u32 {
+        64
+    }
 }
 
 /// HyDE (Hypothetical Document Embedding) configuration.
@@ -480,6 +1196,81 @@ impl Default for HydeConfig {
     }
 }
 
+/// Sensitive-path exclusion policy (`[privacy]`).
+///
+/// Paths matching these patterns are never indexed, whatever
+/// `indexing.exclude_patterns` or the file type say, and are purged from an
+/// existing index on the next run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrivacyConfig {
+    /// Gitignore-style patterns relative to the repository root. A pattern
+    /// without a `/` matches at any depth.
+    #[serde(default = "PrivacyConfig::default_exclude")]
+    pub exclude: Vec<String>,
+}
+
+impl Default for PrivacyConfig {
+    fn default() -> Self {
+        Self {
+            exclude: Self::default_exclude(),
+        }
+    }
+}
+
+impl PrivacyConfig {
+    fn default_exclude() -> Vec<String> {
+        vec![
+            ".env*".into(),
+            "**/secrets/**".into(),
+            "*.pem".into(),
+            "*.key".into(),
+            "id_rsa*".into(),
+        ]
+    }
+}
+
+/// Architecture layering rules (`[architecture]`).
+///
+/// Each layer names a set of files; each rule forbids dependencies from one
+/// layer onto others. Violations are reported by
+/// [`crate::patterns::check_layers`].
+///
+/// ```toml
+/// [architecture.layers]
+/// ui = ["src/ui/"]
+/// db = ["src/db/", "migrations/"]
+///
+/// [[architecture.rules]]
+/// from = "ui"
+/// must_not_depend_on = ["db"]
+/// reason = "go through the service layer"
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ArchitectureConfig {
+    /// Layer name to gitignore-style path patterns, relative to the
+    /// repository root. A file may belong to several layers.
+    #[serde(default)]
+    pub layers: BTreeMap<String, Vec<String>>,
+
+    /// Forbidden dependencies between layers.
+    #[serde(default)]
+    pub rules: Vec<LayerRule>,
+}
+
+/// One forbidden-dependency rule between layers.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LayerRule {
+    /// The layer the rule constrains.
+    pub from: String,
+
+    /// Layers that files in `from` must not depend on.
+    pub must_not_depend_on: Vec<String>,
+
+    /// Explanation shown alongside each violation.
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
 /// Logging configuration.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LoggingConfig {
@@ -542,11 +1333,19 @@ impl Config {
             watcher: WatcherConfig::default(),
             logging: LoggingConfig::default(),
             hyde: HydeConfig::default(),
+            privacy: PrivacyConfig::default(),
+            storage: StorageConfig::default(),
+            plugins: PluginsConfig::default(),
+            architecture: ArchitectureConfig::default(),
+            data_dir_override: None,
         }
     }
 
     /// Returns the data directory for this repo's index files.
     pub fn data_dir(&self) -> PathBuf {
+        if let Some(dir) = &self.data_dir_override {
+            return dir.clone();
+        }
         let hash = self.repo_hash();
         let base = dirs::data_local_dir()
             .unwrap_or_else(|| PathBuf::from("."))
@@ -556,6 +1355,11 @@ impl Config {
         base
     }
 
+    /// Path of the SQLite metadata index inside [`Self::data_dir`].
+    pub fn index_db_path(&self) -> PathBuf {
+        self.data_dir().join("index.db")
+    }
+
     /// Merge values from a TOML config file (non-destructive overlay).
     fn merge_from_file(&mut self, path: &Path) -> OmniResult<()> {
         let content = std::fs::read_to_string(path)?;
@@ -594,6 +1398,35 @@ impl Config {
                 self.hyde = parsed;
             }
         }
+        if let Some(privacy) = overlay.get("privacy") {
+            let parsed =
+                privacy
+                    .clone()
+                    .try_into::<PrivacyConfig>()
+                    .map_err(|e| OmniError::Config {
+                        details: format!("invalid [privacy] in {}: {e}", path.display()),
+                    })?;
+            self.privacy = parsed;
+        }
+        if let Some(architecture) = overlay.get("architecture") {
+            let parsed = architecture
+                .clone()
+                .try_into::<ArchitectureConfig>()
+                .map_err(|e| OmniError::Config {
+                    details: format!("invalid [architecture] in {}: {e}", path.display()),
+                })?;
+            self.architecture = parsed;
+        }
+        if let Some(storage) = overlay.get("storage") {
+            if let Ok(parsed) = storage.clone().try_into::<StorageConfig>() {
+                self.storage = parsed;
+            }
+        }
+        if let Some(plugins) = overlay.get("plugins") {
+            if let Ok(parsed) = plugins.clone().try_into::<PluginsConfig>() {
+                self.plugins = parsed;
+            }
+        }
 
         Ok(())
     }
@@ -665,6 +1498,43 @@ pub fn normalize_repo_hash(path: &str) -> String {
     hex::encode(&result[..4])
 }
 
+/// Pipe (Windows) or Unix socket path the daemon for `repo_path` listens on.
+///
+/// Shared by the daemon and its clients. Normalization must match the
+/// extension's `derivePipeName()`:
+///   1. Strip `\\?\` prefix
+///   2. Backslash -> forward slash
+///   3. Lowercase
+///   4. Strip trailing separator(s)
+pub fn daemon_pipe_name(repo_path: &Path) -> String {
+    use sha2::{Digest, Sha256};
+    let mut normalized = repo_path
+        .to_string_lossy()
+        .replace(r"\\?\", "")
+        .replace('\\', "/")
+        .to_lowercase();
+
+    // Strip trailing separator to match extension behavior
+    while normalized.ends_with('/') {
+        normalized.pop();
+    }
+
+    let mut hasher = Sha256::new();
+    hasher.update(normalized.as_bytes());
+    let hash = hex::encode(&hasher.finalize()[..6]);
+
+    #[cfg(windows)]
+    {
+        format!(r"\\.\pipe\omnicontext-{hash}")
+    }
+
+    #[cfg(not(windows))]
+    {
+        let runtime_dir = std::env::var("XDG_RUNTIME_DIR").unwrap_or_else(|_| "/tmp".to_string());
+        format!("{runtime_dir}/omnicontext-{hash}.sock")
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -696,6 +1566,25 @@ mod tests {
         assert!(ChunkKind::Function.default_weight() > ChunkKind::TopLevel.default_weight());
     }
 
+    #[test]
+    fn test_validate_weights() {
+        let mut config = Config::defaults(Path::new("/tmp/test-repo"));
+        config.indexing.kind_weights.insert("function".into(), 0.5);
+        config.indexing.path_weights.insert("examples/".into(), 0.5);
+        assert!(config.indexing.validate_weights().is_ok());
+
+        config.indexing.kind_weights.insert("fn".into(), 0.5);
+        assert!(config.indexing.validate_weights().is_err());
+        config.indexing.kind_weights.remove("fn");
+
+        config.indexing.path_weights.insert("src/".into(), -1.0);
+        assert!(config.indexing.validate_weights().is_err());
+        config.indexing.path_weights.remove("src/");
+
+        config.indexing.path_weights.insert("src/[".into(), 1.5);
+        assert!(config.indexing.validate_weights().is_err());
+    }
+
     #[test]
     fn test_normalize_repo_hash_case_insensitive() {
         let h1 = normalize_repo_hash(r"C:\Users\Dev\MyProject");
@@ -805,6 +1694,81 @@ mod tests {
         assert_eq!(original.max_seq_length, deserialized.max_seq_length);
     }
 
+    #[test]
+    fn test_bm25_weights_partial_override() {
+        let config: SearchConfig =
+            toml::from_str("[bm25]\ndoc_comment = 1.5\n").expect("deserialize");
+        assert!((config.bm25.doc_comment - 1.5).abs() < f64::EPSILON);
+        assert!((config.bm25.content - 1.0).abs() < f64::EPSILON);
+        assert!((config.bm25.symbol_path - 2.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_budget_for_model_prefers_longest_prefix() {
+        let config: SearchConfig = toml::from_str(
+            "token_budget = 4000\n[model_budgets]\n\"gpt-4\" = 8000\n\"gpt-4o\" = 16000\n",
+        )
+        .expect("deserialize");
+        assert_eq!(config.budget_for_model(Some("GPT-4o-mini")), 16000);
+        assert_eq!(config.budget_for_model(Some("gpt-4-turbo")), 8000);
+        assert_eq!(config.budget_for_model(Some("claude-sonnet-4")), 4000);
+        assert_eq!(config.budget_for_model(None), 4000);
+    }
+
+    #[test]
+    fn test_language_chunking_overrides() {
+        let config: IndexingConfig = toml::from_str(
+            "max_chunk_tokens = 400\n\
+             [languages.go]\n\
+             max_chunk_tokens = 256\n\
+             split_strategy = \"blocks\"\n",
+        )
+        .expect("deserialize");
+
+        let go = config.chunking_for(Language::Go);
+        assert_eq!(go.max_chunk_tokens, 256);
+        assert_eq!(go.split_strategy, SplitStrategy::Blocks);
+        assert_eq!(go.overlap_tokens, config.overlap_tokens);
+
+        let python = config.chunking_for(Language::Python);
+        assert_eq!(python.max_chunk_tokens, 400);
+        assert_eq!(python.split_strategy, SplitStrategy::Structural);
+    }
+
+    #[test]
+    fn test_leading_comments_per_language() {
+        let config: IndexingConfig = toml::from_str(
+            "leading_comments = \"downweight\"\n\
+             [languages.java]\n\
+             leading_comments = \"strip\"\n",
+        )
+        .expect("deserialize");
+
+        assert_eq!(
+            config.leading_comments_for(Language::Java),
+            LeadingComments::Strip
+        );
+        assert_eq!(
+            config.leading_comments_for(Language::Go),
+            LeadingComments::Downweight
+        );
+        assert_eq!(
+            IndexingConfig::default().leading_comments_for(Language::Java),
+            LeadingComments::Keep
+        );
+    }
+
+    #[test]
+    fn test_watcher_ignore_patterns_override() {
+        let config: WatcherConfig = toml::from_str("debounce_ms = 50\n").expect("deserialize");
+        assert!(config.ignore_patterns.iter().any(|p| p == "*.swp"));
+
+        let config: WatcherConfig =
+            toml::from_str("ignore_patterns = [\"*.bak\"]\n").expect("deserialize");
+        assert_eq!(config.ignore_patterns, vec!["*.bak".to_string()]);
+        assert_eq!(config.debounce_ms, 100);
+    }
+
     #[test]
     fn test_reranker_config_default_batch_size() {
         let config = RerankerConfig::default();
@@ -0,0 +1,294 @@
+//! Mapping a unified diff to the symbols it touches.
+//!
+//! [`parse`] reads `git diff` / `diff -u` output into the post-image lines
+//! each file's hunks change; a removed line counts as a change to the line
+//! now at its position. [`resolve`] maps those lines to the smallest
+//! indexed chunk enclosing each of them, and
+//! [`crate::pipeline::Engine::context_for_diff`] packs the changed symbols
+//! and their graph neighbours into a review context.
+//!
+//! Lines are matched against the indexed tree, so the diff should describe
+//! the changes that produced it (the working tree, or a checked-out PR head).
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::OmniResult;
+use crate::index::MetadataIndex;
+use crate::types::{Chunk, ContextWindow};
+
+/// The changes a diff makes to one file.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileDiff {
+    /// Path after the change, relative to the repository root. For a
+    /// deleted file, the path it was deleted from.
+    pub path: String,
+    /// Whether the diff deletes the file.
+    #[serde(default)]
+    pub deleted: bool,
+    /// Changed lines of the post-image, as sorted inclusive 1-based ranges.
+    #[serde(default)]
+    pub ranges: Vec<(u32, u32)>,
+}
+
+/// A symbol the diff changes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangedSymbol {
+    /// File the symbol is in, relative to the repository root.
+    pub file_path: PathBuf,
+    /// Fully qualified symbol path.
+    pub symbol_path: String,
+    /// Chunk kind (function, class, ...).
+    pub kind: String,
+    /// First line of the symbol.
+    pub line_start: u32,
+    /// Last line of the symbol.
+    pub line_end: u32,
+    /// How many of its lines the diff changes.
+    pub changed_lines: u32,
+    /// The symbol's chunk.
+    #[serde(skip)]
+    pub chunk: Option<Chunk>,
+}
+
+/// Result of [`crate::pipeline::Engine::context_for_diff`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffContext {
+    /// Every file in the diff.
+    pub files: Vec<FileDiff>,
+    /// Changed symbols, most changed lines first.
+    pub symbols: Vec<ChangedSymbol>,
+    /// Changed files that are not in the index (deleted, new since the
+    /// last index run, or not source code).
+    pub unindexed_files: Vec<String>,
+    /// The changed symbols and their graph neighbours.
+    pub window: ContextWindow,
+}
+
+/// Parse a unified diff into per-file changed line ranges.
+///
+/// Files whose diff has no hunks (pure renames, mode changes, binary
+/// files) are omitted.
+pub fn parse(diff: &str) -> Vec<FileDiff> {
+    let mut files: Vec<FileDiff> = Vec::new();
+    let mut old_path: Option<String> = None;
+    let mut lines: Vec<u32> = Vec::new();
+    // Inside a hunk: the next post-image line, and the pre- and post-image
+    // lines left in the hunk.
+    let mut hunk: Option<(u32, u32, u32)> = None;
+
+    for line in diff.lines() {
+        if let Some((next, old_left, new_left)) = hunk {
+            let (next, old_left, new_left) = match line.as_bytes().first() {
+                Some(b'+') => {
+                    lines.push(next);
+                    (next + 1, old_left, new_left.saturating_sub(1))
+                }
+                Some(b'-') => {
+                    lines.push(next.max(1));
+                    (next, old_left.saturating_sub(1), new_left)
+                }
+                Some(b'\\') => (next, old_left, new_left),
+                // Context; some tools strip the space from blank lines.
+                _ => (
+                    next + 1,
+                    old_left.saturating_sub(1),
+                    new_left.saturating_sub(1),
+                ),
+            };
+            hunk = (old_left > 0 || new_left > 0).then_some((next, old_left, new_left));
+            continue;
+        }
+
+        if line.starts_with("diff ") {
+            flush(&mut files, &mut lines);
+            old_path = None;
+        } else if let Some(path) = line.strip_prefix("--- ") {
+            flush(&mut files, &mut lines);
+            old_path = header_path(path);
+        } else if let Some(path) = line.strip_prefix("+++ ") {
+            let (path, deleted) = match header_path(path) {
+                Some(path) => (path, false),
+                None => (old_path.clone().unwrap_or_default(), true),
+            };
+            files.push(FileDiff {
+                path,
+                deleted,
+                ranges: Vec::new(),
+            });
+        } else if line.starts_with("@@ ") && !files.is_empty() {
+            hunk = parse_hunk_header(line);
+        }
+    }
+    flush(&mut files, &mut lines);
+    files.retain(|file| !file.path.is_empty() && (file.deleted || !file.ranges.is_empty()));
+    files
+}
+
+/// Fold the collected changed lines into ranges on the current file.
+fn flush(files: &mut [FileDiff], lines: &mut Vec<u32>) {
+    let Some(file) = files.last_mut() else {
+        lines.clear();
+        return;
+    };
+    lines.sort_unstable();
+    lines.dedup();
+    for &line in lines.iter() {
+        match file.ranges.last_mut() {
+            Some((_, end)) if *end + 1 >= line => *end = (*end).max(line),
+            _ => file.ranges.push((line, line)),
+        }
+    }
+    lines.clear();
+}
+
+/// The path in a `---`/`+++` header, without its `a/`/`b/` prefix or
+/// trailing timestamp. `None` for `/dev/null`.
+fn header_path(header: &str) -> Option<String> {
+    let path = header.split('\t').next().unwrap_or(header).trim();
+    let path = path.trim_matches('"');
+    if path == "/dev/null" {
+        return None;
+    }
+    let path = path
+        .strip_prefix("a/")
+        .or_else(|| path.strip_prefix("b/"))
+        .unwrap_or(path);
+    Some(path.to_string())
+}
+
+/// `(c, b, d)` of `@@ -a,b +c,d @@`; an omitted count is 1.
+fn parse_hunk_header(header: &str) -> Option<(u32, u32, u32)> {
+    let range = |sign: char| -> Option<(u32, u32)> {
+        let range = header
+            .split_whitespace()
+            .find_map(|part| part.strip_prefix(sign))?;
+        match range.split_once(',') {
+            Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+            None => Some((range.parse().ok()?, 1)),
+        }
+    };
+    let (_, old_count) = range('-')?;
+    let (start, new_count) = range('+')?;
+    Some((start, old_count, new_count))
+}
+
+/// Map each file's changed lines to the smallest indexed chunk enclosing
+/// them. Returns the changed symbols, most changed lines first, and the
+/// files not in the index.
+pub fn resolve(
+    index: &MetadataIndex,
+    files: &[FileDiff],
+) -> OmniResult<(Vec<ChangedSymbol>, Vec<String>)> {
+    let mut symbols: Vec<ChangedSymbol> = Vec::new();
+    let mut unindexed = Vec::new();
+    for diff in files {
+        let file = if diff.deleted {
+            None
+        } else {
+            index.get_file_by_path(std::path::Path::new(&diff.path))?
+        };
+        let Some(file) = file else {
+            unindexed.push(diff.path.clone());
+            continue;
+        };
+        let chunks = index.get_chunks_for_file(file.id)?;
+        let mut by_chunk = HashMap::new();
+        for line in diff.ranges.iter().flat_map(|&(start, end)| start..=end) {
+            let Some(chunk) = chunks
+                .iter()
+                .filter(|c| c.line_start <= line && line <= c.line_end)
+                .min_by_key(|c| c.line_end - c.line_start)
+            else {
+                continue;
+            };
+            let slot = *by_chunk.entry(chunk.id).or_insert_with(|| {
+                symbols.push(ChangedSymbol {
+                    file_path: file.path.clone(),
+                    symbol_path: chunk.symbol_path.clone(),
+                    kind: chunk.kind.as_str().to_string(),
+                    line_start: chunk.line_start,
+                    line_end: chunk.line_end,
+                    changed_lines: 0,
+                    chunk: Some(chunk.clone()),
+                });
+                symbols.len() - 1
+            });
+            symbols[slot].changed_lines += 1;
+        }
+    }
+    symbols.sort_by_key(|s| std::cmp::Reverse(s.changed_lines));
+    Ok((symbols, unindexed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_git_diff() {
+        let diff = "\
+diff --git a/src/lib.rs b/src/lib.rs
+index 1111111..2222222 100644
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -3,5 +3,6 @@ use std::io;
+ fn a() {
+-    old();
++    new();
++    more();
+ }
+
+ fn b() {
+@@ -20,3 +21,2 @@ fn c() {
+ fn d() {
+-    gone();
+ }
+diff --git a/old.py b/old.py
+deleted file mode 100644
+--- a/old.py
++++ /dev/null
+@@ -1,2 +0,0 @@
+-def f():
+-    pass
+diff --git a/img.png b/img.png
+Binary files a/img.png and b/img.png differ
+";
+        assert_eq!(
+            parse(diff),
+            vec![
+                FileDiff {
+                    path: "src/lib.rs".to_string(),
+                    deleted: false,
+                    ranges: vec![(4, 5), (22, 22)],
+                },
+                FileDiff {
+                    path: "old.py".to_string(),
+                    deleted: true,
+                    ranges: vec![(1, 1)],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_plain_diff_u() {
+        let diff = "\
+--- app/main.py\t2024-01-01 00:00:00
++++ app/main.py\t2024-01-02 00:00:00
+@@ -1 +1 @@
+-x = 1
++x = 2
+";
+        assert_eq!(
+            parse(diff),
+            vec![FileDiff {
+                path: "app/main.py".to_string(),
+                deleted: false,
+                ranges: vec![(1, 1)],
+            }]
+        );
+    }
+}
@@ -0,0 +1,257 @@
+//! Distributed indexing across worker processes or machines.
+//!
+//! Very large repositories can split the expensive parse and embed work
+//! across workers that share a coordination directory (a network mount or a
+//! synced bucket). The protocol is three steps, each a plain JSON file:
+//!
+//! 1. **Plan** -- the coordinator lists the repository's files and writes
+//!    [`PLAN_FILE`], assigning every file to one shard so that shard sizes
+//!    (in bytes) are balanced.
+//! 2. **Work** -- worker `k` indexes only shard `k`'s files into its own
+//!    partial index under `shard-<k>/`, then writes [`DONE_FILE`] there. The
+//!    marker is written last, so its presence means the output is complete.
+//! 3. **Merge** -- once every shard is done, the coordinator copies files,
+//!    chunks, symbols, dependency edges, and vectors into the final index.
+//!
+//! Workers only see their own files, so call and import edges that cross
+//! shard boundaries are missing after a merge. The next regular index run
+//! resolves them; it re-parses files but keeps every merged embedding, since
+//! chunk content hashes are unchanged.
+
+#![allow(clippy::doc_markdown, clippy::missing_errors_doc)]
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{OmniError, OmniResult};
+
+/// Shard assignment file in the coordination directory.
+pub const PLAN_FILE: &str = "plan.json";
+
+/// Completion marker in each shard directory.
+pub const DONE_FILE: &str = "done.json";
+
+/// Version of the plan and marker format.
+const PROTOCOL_VERSION: u32 = 1;
+
+/// Assignment of repository files to shards.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShardPlan {
+    /// Protocol version the plan was written with.
+    pub version: u32,
+    /// Files of each shard, relative to the repository root, in path order.
+    pub shards: Vec<Vec<String>>,
+}
+
+impl ShardPlan {
+    /// Split `files` (relative path, size in bytes) into `workers` shards.
+    ///
+    /// Largest files are placed first, each into the currently smallest
+    /// shard, which keeps shard sizes within one file of each other.
+    pub fn balance(mut files: Vec<(String, u64)>, workers: usize) -> Self {
+        let workers = workers.max(1);
+        files.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+        let mut shards = vec![Vec::new(); workers];
+        let mut sizes = vec![0u64; workers];
+        for (path, size) in files {
+            let (smallest, _) = sizes
+                .iter()
+                .enumerate()
+                .min_by_key(|(i, size)| (**size, *i))
+                .unwrap_or((0, &0));
+            sizes[smallest] += size;
+            shards[smallest].push(path);
+        }
+        for shard in &mut shards {
+            shard.sort();
+        }
+        Self {
+            version: PROTOCOL_VERSION,
+            shards,
+        }
+    }
+
+    /// Total number of files across all shards.
+    pub fn file_count(&self) -> usize {
+        self.shards.iter().map(Vec::len).sum()
+    }
+
+    /// Files of shard `shard`, or a `NotFound` error if the plan has fewer.
+    pub fn shard(&self, shard: usize) -> OmniResult<&[String]> {
+        self.shards
+            .get(shard)
+            .map(Vec::as_slice)
+            .ok_or_else(|| OmniError::NotFound {
+                entity: format!("shard {shard} (plan has {})", self.shards.len()),
+            })
+    }
+
+    /// Write the plan into `coord_dir`.
+    pub fn write(&self, coord_dir: &Path) -> OmniResult<()> {
+        write_json(&coord_dir.join(PLAN_FILE), self)
+    }
+
+    /// Read the plan from `coord_dir`.
+    pub fn read(coord_dir: &Path) -> OmniResult<Self> {
+        let plan: Self = read_json(&coord_dir.join(PLAN_FILE))?;
+        check_version(plan.version)?;
+        Ok(plan)
+    }
+}
+
+/// Completion marker a worker writes after indexing its shard.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShardReport {
+    /// Protocol version the worker ran.
+    pub version: u32,
+    /// Shard number.
+    pub shard: usize,
+    /// Files indexed.
+    pub files_processed: usize,
+    /// Files that failed to index.
+    pub files_failed: usize,
+    /// Chunks created.
+    pub chunks_created: usize,
+    /// Embeddings generated.
+    pub embeddings_generated: usize,
+}
+
+impl ShardReport {
+    /// An empty report for shard `shard`.
+    pub fn new(shard: usize) -> Self {
+        Self {
+            version: PROTOCOL_VERSION,
+            shard,
+            ..Self::default()
+        }
+    }
+
+    /// Write the marker into the shard's directory.
+    pub fn write(&self, coord_dir: &Path) -> OmniResult<()> {
+        write_json(&shard_dir(coord_dir, self.shard).join(DONE_FILE), self)
+    }
+}
+
+/// Counts from merging shard outputs into the final index.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MergeReport {
+    /// Shards merged.
+    pub shards: usize,
+    /// Files copied.
+    pub files: usize,
+    /// Chunks copied.
+    pub chunks: usize,
+    /// Symbols copied.
+    pub symbols: usize,
+    /// Dependency edges copied (edges within one shard only).
+    pub dependencies: usize,
+    /// Embedding vectors copied.
+    pub vectors: usize,
+}
+
+/// Output directory of shard `shard`.
+pub fn shard_dir(coord_dir: &Path, shard: usize) -> PathBuf {
+    coord_dir.join(format!("shard-{shard:03}"))
+}
+
+/// Read every shard's completion marker.
+///
+/// Fails with `NotFound` naming the shards that have not finished.
+pub fn read_reports(coord_dir: &Path, plan: &ShardPlan) -> OmniResult<Vec<ShardReport>> {
+    let mut reports = Vec::with_capacity(plan.shards.len());
+    let mut pending = Vec::new();
+    for shard in 0..plan.shards.len() {
+        let marker = shard_dir(coord_dir, shard).join(DONE_FILE);
+        if marker.exists() {
+            let report: ShardReport = read_json(&marker)?;
+            check_version(report.version)?;
+            reports.push(report);
+        } else {
+            pending.push(shard.to_string());
+        }
+    }
+    if !pending.is_empty() {
+        return Err(OmniError::NotFound {
+            entity: format!("completed output of shard(s) {}", pending.join(", ")),
+        });
+    }
+    Ok(reports)
+}
+
+fn check_version(version: u32) -> OmniResult<()> {
+    if version == PROTOCOL_VERSION {
+        Ok(())
+    } else {
+        Err(OmniError::Config {
+            details: format!(
+                "distributed indexing protocol version {version} is not supported \
+                 (expected {PROTOCOL_VERSION})"
+            ),
+        })
+    }
+}
+
+fn write_json<T: Serialize>(path: &Path, value: &T) -> OmniResult<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let json = serde_json::to_vec_pretty(value)
+        .map_err(|e| OmniError::Serialization(format!("{}: {e}", path.display())))?;
+    // Write then rename, so a reader polling for the file never sees it partial.
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, json)?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+fn read_json<T: for<'de> Deserialize<'de>>(path: &Path) -> OmniResult<T> {
+    let bytes = std::fs::read(path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => OmniError::NotFound {
+            entity: path.display().to_string(),
+        },
+        _ => e.into(),
+    })?;
+    serde_json::from_slice(&bytes)
+        .map_err(|e| OmniError::Serialization(format!("{}: {e}", path.display())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_balance_spreads_bytes_evenly() {
+        let files = vec![
+            ("a.rs".to_string(), 100),
+            ("b.rs".to_string(), 60),
+            ("c.rs".to_string(), 50),
+            ("d.rs".to_string(), 40),
+            ("e.rs".to_string(), 10),
+        ];
+        let plan = ShardPlan::balance(files, 2);
+        assert_eq!(plan.file_count(), 5);
+        assert_eq!(plan.shards[0], vec!["a.rs", "d.rs"]);
+        assert_eq!(plan.shards[1], vec!["b.rs", "c.rs", "e.rs"]);
+        assert!(plan.shard(2).is_err());
+
+        let single = ShardPlan::balance(vec![("x.rs".into(), 1)], 0);
+        assert_eq!(single.shards.len(), 1);
+    }
+
+    #[test]
+    fn test_reports_require_every_shard() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let plan = ShardPlan::balance(vec![("a.rs".into(), 1), ("b.rs".into(), 1)], 2);
+        plan.write(dir.path()).expect("write plan");
+        assert_eq!(ShardPlan::read(dir.path()).expect("read plan"), plan);
+
+        ShardReport::new(1).write(dir.path()).expect("write marker");
+        let err = read_reports(dir.path(), &plan).expect_err("shard 0 pending");
+        assert!(err.to_string().contains("shard(s) 0"), "{err}");
+
+        ShardReport::new(0).write(dir.path()).expect("write marker");
+        assert_eq!(read_reports(dir.path(), &plan).expect("reports").len(), 2);
+    }
+}
@@ -0,0 +1,197 @@
+//! License and generated-code boilerplate in embedding text.
+//!
+//! In some repositories every file opens with a twenty-line license header.
+//! The chunker carries it into the first chunk of each file (and into the
+//! surrounding context of the next ones), where it can outweigh the code in
+//! the embedding: every file then looks alike to semantic search.
+//!
+//! [`apply`] finds comment blocks, using the comment syntax of the chunk's
+//! language, that mention a license, a copyright, or a "generated by" /
+//! "do not edit" notice, and strips or shortens them according to
+//! `indexing.leading_comments`. Only the text sent to the embedder changes;
+//! stored chunk content keeps the comments.
+
+use crate::config::LeadingComments;
+use crate::types::Language;
+
+/// Lowercase phrases that mark a comment block as boilerplate.
+const MARKERS: &[&str] = &[
+    "copyright",
+    "license",
+    "licence",
+    "spdx-license-identifier",
+    "all rights reserved",
+    "generated by",
+    "auto-generated",
+    "autogenerated",
+    "do not edit",
+];
+
+/// Prefix of the chunker's own section lines (`// -- surrounding context --`),
+/// which are scaffolding rather than source comments.
+const CHUNKER_MARKER: &str = "// -- ";
+
+/// Comment syntax of a language.
+pub(crate) struct Syntax {
+    pub(crate) line: &'static [&'static str],
+    pub(crate) block: &'static [(&'static str, &'static str)],
+}
+
+pub(crate) fn syntax(language: Language) -> Syntax {
+    const C_BLOCK: &[(&str, &str)] = &[("/*", "*/")];
+    match language {
+        Language::Python => Syntax {
+            line: &["#"],
+            block: &[("\"\"\"", "\"\"\""), ("'''", "'''")],
+        },
+        Language::Ruby | Language::Shell | Language::Toml | Language::Yaml => Syntax {
+            line: &["#"],
+            block: &[],
+        },
+        Language::Php => Syntax {
+            line: &["//", "#"],
+            block: C_BLOCK,
+        },
+        Language::Css => Syntax {
+            line: &[],
+            block: C_BLOCK,
+        },
+        Language::Html | Language::Vue | Language::Svelte | Language::Markdown => Syntax {
+            line: &[],
+            block: &[("<!--", "-->")],
+        },
+        Language::Json => Syntax {
+            line: &[],
+            block: &[],
+        },
+        Language::Plugin | Language::Unknown => Syntax {
+            line: &["//", "#"],
+            block: C_BLOCK,
+        },
+        Language::TypeScript
+        | Language::JavaScript
+        | Language::Rust
+        | Language::Go
+        | Language::Java
+        | Language::C
+        | Language::Cpp
+        | Language::CSharp
+        | Language::Swift
+        | Language::Kotlin => Syntax {
+            line: &["//"],
+            block: C_BLOCK,
+        },
+    }
+}
+
+/// `text` with its boilerplate comment blocks handled per `mode`.
+pub fn apply(text: &str, language: Language, mode: LeadingComments) -> String {
+    if mode == LeadingComments::Keep {
+        return text.to_string();
+    }
+    let lines: Vec<&str> = text.lines().collect();
+    let mut out: Vec<&str> = Vec::with_capacity(lines.len());
+    let mut copied = 0;
+    for (start, end) in comment_blocks(&lines, &syntax(language)) {
+        let block = &lines[start..end];
+        if !is_boilerplate(block) {
+            continue;
+        }
+        out.extend(&lines[copied..start]);
+        if mode == LeadingComments::Downweight {
+            out.extend(block.iter().find(|l| l.chars().any(char::is_alphanumeric)));
+        }
+        copied = end;
+    }
+    out.extend(&lines[copied..]);
+    out.join("\n")
+}
+
+/// Half-open line ranges of the comment blocks in `lines`: runs of line
+/// comments, and block comments from their opening to their closing line.
+pub(crate) fn comment_blocks(lines: &[&str], syntax: &Syntax) -> Vec<(usize, usize)> {
+    let mut blocks = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let trimmed = lines[i].trim_start();
+        if let Some(&(open, close)) = syntax
+            .block
+            .iter()
+            .find(|(open, _)| trimmed.starts_with(open))
+        {
+            let start = i;
+            let rest = &trimmed[open.len()..];
+            if !rest.contains(close) {
+                i += 1;
+                while i < lines.len() && !lines[i].contains(close) {
+                    i += 1;
+                }
+            }
+            i = (i + 1).min(lines.len());
+            blocks.push((start, i));
+        } else if is_line_comment(trimmed, syntax) {
+            let start = i;
+            while i < lines.len() && is_line_comment(lines[i].trim_start(), syntax) {
+                i += 1;
+            }
+            blocks.push((start, i));
+        } else {
+            i += 1;
+        }
+    }
+    blocks
+}
+
+fn is_line_comment(trimmed: &str, syntax: &Syntax) -> bool {
+    !trimmed.starts_with(CHUNKER_MARKER)
+        && !trimmed.starts_with("#!")
+        && syntax.line.iter().any(|prefix| trimmed.starts_with(prefix))
+}
+
+pub(crate) fn is_boilerplate(block: &[&str]) -> bool {
+    let text = block.join("\n").to_lowercase();
+    MARKERS.iter().any(|marker| text.contains(marker))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RUST: &str = "// Copyright 2024 Example Corp.\n//\n// Licensed under the Apache License, Version 2.0.\n\n/// Parses a frame.\nfn parse() {}";
+
+    #[test]
+    fn test_strip_and_downweight_license_header() {
+        assert_eq!(
+            apply(RUST, Language::Rust, LeadingComments::Strip),
+            "\n/// Parses a frame.\nfn parse() {}"
+        );
+        assert_eq!(
+            apply(RUST, Language::Rust, LeadingComments::Downweight),
+            "// Copyright 2024 Example Corp.\n\n/// Parses a frame.\nfn parse() {}"
+        );
+        assert_eq!(apply(RUST, Language::Rust, LeadingComments::Keep), RUST);
+    }
+
+    #[test]
+    fn test_language_comment_syntax() {
+        let python = "#!/usr/bin/env python\n# SPDX-License-Identifier: MIT\n# Copyright Example\ndef run():\n    pass";
+        assert_eq!(
+            apply(python, Language::Python, LeadingComments::Strip),
+            "#!/usr/bin/env python\ndef run():\n    pass"
+        );
+        let go = "/*\n * Code generated by protoc. DO NOT EDIT.\n */\npackage pb";
+        assert_eq!(
+            apply(go, Language::Go, LeadingComments::Strip),
+            "package pb"
+        );
+        // `#` is not a comment in Rust.
+        let attr = "#[derive(Debug)] // license-free\nstruct S;";
+        assert_eq!(apply(attr, Language::Rust, LeadingComments::Strip), attr);
+    }
+
+    #[test]
+    fn test_keeps_ordinary_comments_and_chunker_markers() {
+        let text = "// -- surrounding context --\n// Retries with backoff.\nfn retry() {}";
+        assert_eq!(apply(text, Language::Rust, LeadingComments::Strip), text);
+    }
+}
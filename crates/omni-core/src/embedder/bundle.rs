@@ -0,0 +1,437 @@
+//! Offline model bundles.
+//!
+//! Air-gapped machines can't reach Hugging Face. [`create_bundle`] packs
+//! installed models on a connected machine into one tar file, and
+//! [`install_bundle`] unpacks it into the model cache on the target, so
+//! the engine finds the models as if it had downloaded them.
+//!
+//! A bundle is a tar archive (GNU headers, so models past 8 GiB fit):
+//! `manifest.json` first, then `<model-name>/model.onnx` and
+//! `<model-name>/tokenizer.json` for each model. The manifest lists every
+//! file's size and SHA-256; installation rejects the bundle if any entry is
+//! missing, unexpected, differs, or has an absolute or `..` path, and only
+//! then moves the files into place. Directory and pax entries are skipped,
+//! so archives repacked by other tools still install.
+
+use sha2::Digest as _;
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::model_manager::{self, ModelSpec};
+use crate::error::{OmniError, OmniResult};
+
+/// Bundle format version written to the manifest.
+const BUNDLE_FORMAT: u32 = 1;
+/// Name of the manifest entry.
+const MANIFEST: &str = "manifest.json";
+/// Files bundled for each model.
+const MODEL_FILES: [&str; 2] = ["model.onnx", "tokenizer.json"];
+/// Upper bound on the manifest entry, so a corrupt size can't exhaust memory.
+const MAX_MANIFEST_BYTES: u64 = 1024 * 1024;
+
+/// Contents of a bundle's `manifest.json`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundleManifest {
+    /// Bundle format version.
+    pub format: u32,
+    /// When the bundle was created.
+    pub created_at: String,
+    /// Bundled models.
+    pub models: Vec<BundledModel>,
+}
+
+/// One model in a bundle.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundledModel {
+    /// Model name (see [`model_manager::find_model`]).
+    pub name: String,
+    /// Hugging Face repository the model came from.
+    pub hf_repo: String,
+    /// The model's files.
+    pub files: Vec<BundledFile>,
+}
+
+/// One file of a bundled model.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundledFile {
+    /// File name within the model directory.
+    pub name: String,
+    /// Size in bytes.
+    pub size_bytes: u64,
+    /// SHA-256, as lowercase hex.
+    pub sha256: String,
+}
+
+/// Pack the installed models `specs` into a bundle at `dest`.
+pub fn create_bundle(specs: &[&ModelSpec], dest: &Path) -> OmniResult<BundleManifest> {
+    create_bundle_from(&model_manager::models_base_dir(), specs, dest)
+}
+
+/// Unpack the bundle at `bundle` into the model cache, replacing any
+/// installed copies of its models.
+pub fn install_bundle(bundle: &Path) -> OmniResult<BundleManifest> {
+    install_bundle_into(&model_manager::models_base_dir(), bundle)
+}
+
+fn invalid(details: impl std::fmt::Display) -> OmniError {
+    OmniError::Internal(format!("invalid model bundle: {details}"))
+}
+
+fn create_bundle_from(
+    models_dir: &Path,
+    specs: &[&ModelSpec],
+    dest: &Path,
+) -> OmniResult<BundleManifest> {
+    let mut models = Vec::with_capacity(specs.len());
+    for spec in specs {
+        let dir = models_dir.join(spec.name);
+        let mut files = Vec::with_capacity(MODEL_FILES.len());
+        for name in MODEL_FILES {
+            let path = dir.join(name);
+            let size_bytes = std::fs::metadata(&path)
+                .map_err(|_| OmniError::ModelUnavailable {
+                    reason: format!(
+                        "{} is not installed (missing {}); download it before bundling",
+                        spec.name,
+                        path.display()
+                    ),
+                })?
+                .len();
+            files.push(BundledFile {
+                name: name.to_string(),
+                size_bytes,
+                sha256: model_manager::file_sha256(&path)?,
+            });
+        }
+        models.push(BundledModel {
+            name: spec.name.to_string(),
+            hf_repo: spec.hf_repo.to_string(),
+            files,
+        });
+    }
+    let manifest = BundleManifest {
+        format: BUNDLE_FORMAT,
+        created_at: model_manager::chrono_now_iso(),
+        models,
+    };
+
+    // Written beside `dest` and renamed, so a failed run leaves no bundle
+    // that looks complete.
+    let partial = dest.with_extension("partial");
+    let result = (|| -> OmniResult<()> {
+        let mut archive =
+            tar::Builder::new(std::io::BufWriter::new(std::fs::File::create(&partial)?));
+        let manifest_json = serde_json::to_vec_pretty(&manifest)
+            .map_err(|e| OmniError::Serialization(e.to_string()))?;
+        append_file(
+            &mut archive,
+            MANIFEST,
+            manifest_json.len() as u64,
+            manifest_json.as_slice(),
+        )?;
+        for model in &manifest.models {
+            for file in &model.files {
+                let path = models_dir.join(&model.name).join(&file.name);
+                append_file(
+                    &mut archive,
+                    &format!("{}/{}", model.name, file.name),
+                    file.size_bytes,
+                    std::fs::File::open(&path)?.take(file.size_bytes),
+                )?;
+            }
+        }
+        archive
+            .into_inner()?
+            .into_inner()
+            .map_err(|e| OmniError::Io(e.into_error()))?
+            .sync_all()?;
+        Ok(())
+    })();
+    if let Err(e) = result {
+        let _ = std::fs::remove_file(&partial);
+        return Err(e);
+    }
+    std::fs::rename(&partial, dest)?;
+    Ok(manifest)
+}
+
+/// Append a regular file entry of `size` bytes read from `body`.
+fn append_file<W: Write>(
+    archive: &mut tar::Builder<W>,
+    name: &str,
+    size: u64,
+    body: impl Read,
+) -> OmniResult<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_entry_type(tar::EntryType::Regular);
+    header.set_size(size);
+    header.set_mode(0o644);
+    header.set_mtime(0);
+    archive.append_data(&mut header, name, body)?;
+    Ok(())
+}
+
+/// The next regular file in `entries` and its `/`-separated path,
+/// skipping directory and pax entries. Any other entry type, and paths
+/// that are absolute or climb out with `..`, are rejected.
+fn next_file<'a, R: Read>(
+    entries: &mut tar::Entries<'a, R>,
+) -> OmniResult<Option<(String, tar::Entry<'a, R>)>> {
+    for entry in entries {
+        let entry = entry?;
+        match entry.header().entry_type() {
+            tar::EntryType::Regular | tar::EntryType::Continuous => {}
+            tar::EntryType::Directory | tar::EntryType::XHeader | tar::EntryType::XGlobalHeader => {
+                continue
+            }
+            other => return Err(invalid(format!("unsupported entry type {other:?}"))),
+        }
+        let path = entry.path()?;
+        let mut parts = Vec::new();
+        for component in path.components() {
+            match component {
+                Component::Normal(part) => parts.push(part.to_string_lossy().into_owned()),
+                Component::CurDir => {}
+                Component::ParentDir | Component::RootDir | Component::Prefix(_) => {
+                    return Err(invalid(format!("unsafe entry path {}", path.display())));
+                }
+            }
+        }
+        return Ok(Some((parts.join("/"), entry)));
+    }
+    Ok(None)
+}
+
+fn install_bundle_into(models_dir: &Path, bundle: &Path) -> OmniResult<BundleManifest> {
+    let mut archive = tar::Archive::new(std::io::BufReader::new(std::fs::File::open(bundle)?));
+    let mut entries = archive.entries()?;
+
+    let (name, entry) = next_file(&mut entries)?.ok_or_else(|| invalid("empty archive"))?;
+    if name != MANIFEST {
+        return Err(invalid(format!("expected {MANIFEST} first, found {name}")));
+    }
+    if entry.size() > MAX_MANIFEST_BYTES {
+        return Err(invalid("manifest too large"));
+    }
+    let mut manifest_json = Vec::new();
+    read_entry(entry, &mut manifest_json)?;
+    let manifest: BundleManifest =
+        serde_json::from_slice(&manifest_json).map_err(|e| invalid(format!("manifest: {e}")))?;
+    if manifest.format != BUNDLE_FORMAT {
+        return Err(invalid(format!(
+            "unsupported format {} (expected {BUNDLE_FORMAT})",
+            manifest.format
+        )));
+    }
+
+    // Every file the manifest promises, keyed by archive path.
+    let mut expected: HashMap<String, (&'static ModelSpec, &BundledFile)> = HashMap::new();
+    for model in &manifest.models {
+        let spec = model_manager::find_model(&model.name)
+            .ok_or_else(|| invalid(format!("unknown model {:?}", model.name)))?;
+        for name in MODEL_FILES {
+            let file = model
+                .files
+                .iter()
+                .find(|f| f.name == name)
+                .ok_or_else(|| invalid(format!("{} has no {name}", model.name)))?;
+            expected.insert(format!("{}/{name}", model.name), (spec, file));
+        }
+    }
+
+    // Extract next to the final paths; nothing is replaced until every
+    // file has been verified.
+    let mut staged: Vec<(PathBuf, PathBuf)> = Vec::new();
+    let result = (|| -> OmniResult<()> {
+        while let Some((name, entry)) = next_file(&mut entries)? {
+            let Some((spec, file)) = expected.remove(&name) else {
+                return Err(invalid(format!("unexpected entry {name}")));
+            };
+            if entry.size() != file.size_bytes {
+                return Err(invalid(format!(
+                    "{name} is {} bytes, manifest says {}",
+                    entry.size(),
+                    file.size_bytes
+                )));
+            }
+            let dir = models_dir.join(spec.name);
+            std::fs::create_dir_all(&dir)?;
+            let dest = dir.join(&file.name);
+            let temp = dest.with_extension("installing");
+            staged.push((temp.clone(), dest));
+
+            let mut hashed = HashingWriter {
+                inner: std::io::BufWriter::new(std::fs::File::create(&temp)?),
+                hasher: sha2::Sha256::new(),
+            };
+            read_entry(entry, &mut hashed)?;
+            hashed.inner.flush()?;
+            let actual = hex::encode(hashed.hasher.finalize());
+            if actual != file.sha256 {
+                return Err(invalid(format!(
+                    "{name} checksum mismatch (expected {}, got {actual})",
+                    file.sha256
+                )));
+            }
+            if let (Some(pinned), "model.onnx") = (spec.sha256, file.name.as_str()) {
+                if actual != pinned {
+                    return Err(invalid(format!(
+                        "{name} does not match the pinned digest of {}",
+                        spec.name
+                    )));
+                }
+            }
+        }
+        if let Some(missing) = expected.keys().next() {
+            return Err(invalid(format!("missing entry {missing}")));
+        }
+        Ok(())
+    })();
+    if let Err(e) = result {
+        for (temp, _) in &staged {
+            let _ = std::fs::remove_file(temp);
+        }
+        return Err(e);
+    }
+
+    for (temp, dest) in &staged {
+        std::fs::rename(temp, dest)?;
+    }
+    for model in &manifest.models {
+        if let Some(spec) = model_manager::find_model(&model.name) {
+            let sha256 = model
+                .files
+                .iter()
+                .find(|f| f.name == "model.onnx")
+                .map(|f| f.sha256.as_str());
+            model_manager::write_model_meta(spec, &models_dir.join(spec.name), sha256);
+            tracing::info!(model = spec.name, "installed model from bundle");
+        }
+    }
+    Ok(manifest)
+}
+
+/// Copy all of `entry` into `out`, failing if the archive ends early.
+fn read_entry(mut entry: impl Read, out: &mut impl Write) -> OmniResult<()> {
+    std::io::copy(&mut entry, out)?;
+    Ok(())
+}
+
+/// Writer that hashes everything passing through it.
+struct HashingWriter<W> {
+    inner: W,
+    hasher: sha2::Sha256,
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.hasher.update(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn install_fake_model(models_dir: &Path, spec: &ModelSpec) {
+        let dir = models_dir.join(spec.name);
+        std::fs::create_dir_all(&dir).expect("create model dir");
+        std::fs::write(dir.join("model.onnx"), vec![7_u8; 1500]).expect("write model");
+        std::fs::write(dir.join("tokenizer.json"), b"{\"version\":\"1.0\"}").expect("write tok");
+    }
+
+    #[test]
+    fn test_bundle_round_trip() {
+        let source = tempfile::tempdir().expect("tempdir");
+        let target = tempfile::tempdir().expect("tempdir");
+        let spec = &model_manager::RERANKER_MODEL;
+        install_fake_model(source.path(), spec);
+
+        let bundle = source.path().join("models.tar");
+        let created = create_bundle_from(source.path(), &[spec], &bundle).expect("bundle");
+        assert_eq!(created.models.len(), 1);
+        assert!(!bundle.with_extension("partial").exists());
+
+        let installed = install_bundle_into(target.path(), &bundle).expect("install");
+        assert_eq!(installed, created);
+        for name in MODEL_FILES {
+            assert_eq!(
+                std::fs::read(target.path().join(spec.name).join(name)).expect("read"),
+                std::fs::read(source.path().join(spec.name).join(name)).expect("read"),
+            );
+        }
+        assert!(target.path().join(spec.name).join("meta.json").exists());
+    }
+
+    #[test]
+    fn test_corrupt_bundle_installs_nothing() {
+        let source = tempfile::tempdir().expect("tempdir");
+        let target = tempfile::tempdir().expect("tempdir");
+        let spec = &model_manager::RERANKER_MODEL;
+        install_fake_model(source.path(), spec);
+        let bundle = source.path().join("models.tar");
+        create_bundle_from(source.path(), &[spec], &bundle).expect("bundle");
+
+        // Flip a byte in the model body (after the manifest entry).
+        let mut bytes = std::fs::read(&bundle).expect("read bundle");
+        let at = bytes
+            .windows(7)
+            .position(|w| w == [7_u8; 7])
+            .expect("model body");
+        bytes[at] = 8;
+        std::fs::write(&bundle, &bytes).expect("write bundle");
+
+        let err = install_bundle_into(target.path(), &bundle).expect_err("must reject");
+        assert!(err.to_string().contains("checksum mismatch"), "{err}");
+        let dir = target.path().join(spec.name);
+        assert!(!dir.join("model.onnx").exists());
+        assert!(!dir.join("model.installing").exists());
+    }
+
+    #[test]
+    fn test_bundle_rejects_escaping_paths() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let manifest = serde_json::to_vec(&BundleManifest {
+            format: BUNDLE_FORMAT,
+            created_at: String::new(),
+            models: Vec::new(),
+        })
+        .expect("manifest");
+        for name in ["../escape.onnx", "/tmp/escape.onnx"] {
+            let bundle = dir.path().join("evil.tar");
+            let mut archive = tar::Builder::new(std::fs::File::create(&bundle).expect("create"));
+            append_file(
+                &mut archive,
+                MANIFEST,
+                manifest.len() as u64,
+                manifest.as_slice(),
+            )
+            .expect("manifest entry");
+            // `Builder` refuses such paths, so write the name field directly.
+            let mut header = tar::Header::new_gnu();
+            header.as_old_mut().name[..name.len()].copy_from_slice(name.as_bytes());
+            header.set_entry_type(tar::EntryType::Regular);
+            header.set_size(3);
+            header.set_cksum();
+            archive
+                .append(&header, b"bad".as_slice())
+                .expect("evil entry");
+            archive.finish().expect("finish");
+            drop(archive);
+
+            let models = dir.path().join("models");
+            let err = install_bundle_into(&models, &bundle).expect_err("must reject");
+            assert!(err.to_string().contains("unsafe entry path"), "{err}");
+            assert!(!dir.path().join("escape.onnx").exists());
+        }
+    }
+}
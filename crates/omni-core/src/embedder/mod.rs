@@ -49,10 +49,13 @@
     clippy::vec_init_then_push
 )]
 
+pub mod boilerplate;
+pub mod bundle;
 pub mod cloud;
 pub mod model_manager;
 pub mod quantization;
 pub mod session_pool;
+pub(crate) mod shared;
 
 pub use cloud::CloudEmbedder;
 
@@ -66,12 +69,57 @@ pub use model_manager::{
     ModelSpec, BGE_M3_MODEL, DEFAULT_MODEL, QWEN3_EMBEDDING_MODEL, RERANKER_MODEL,
 };
 
+/// The embedding model recorded with an index differs from the loaded one.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct ModelDrift {
+    /// Where the stale record was found (`index.db` or `vectors.bin`).
+    pub source: &'static str,
+    /// Model recorded with the existing vectors.
+    pub recorded: crate::types::EmbeddingModelInfo,
+    /// Model loaded now.
+    pub current: crate::types::EmbeddingModelInfo,
+}
+
+/// Store the time since `started` in `slot` (microseconds, at least 1 so
+/// that 0 keeps meaning "never ran").
+pub(crate) fn record_latency(slot: &std::sync::atomic::AtomicU64, started: std::time::Instant) {
+    let micros = u64::try_from(started.elapsed().as_micros()).unwrap_or(u64::MAX);
+    slot.store(micros.max(1), std::sync::atomic::Ordering::Relaxed);
+}
+
+/// Read a latency stored by [`record_latency`], in milliseconds.
+pub(crate) fn last_latency_ms(slot: &std::sync::atomic::AtomicU64) -> Option<f64> {
+    match slot.load(std::sync::atomic::Ordering::Relaxed) {
+        0 => None,
+        micros => Some(micros as f64 / 1000.0),
+    }
+}
+
+/// Compare the model recorded in `index.db` and in the vector file header
+/// against the current model. Missing records are not drift.
+pub fn detect_model_drift(
+    in_index: Option<&crate::types::EmbeddingModelInfo>,
+    in_vectors: Option<&crate::types::EmbeddingModelInfo>,
+    current: &crate::types::EmbeddingModelInfo,
+) -> Option<ModelDrift> {
+    [("index.db", in_index), ("vectors.bin", in_vectors)]
+        .into_iter()
+        .find_map(|(source, recorded)| {
+            recorded.filter(|r| *r != current).map(|r| ModelDrift {
+                source,
+                recorded: r.clone(),
+                current: current.clone(),
+            })
+        })
+}
+
 /// Embedding engine that uses ONNX Runtime for local inference.
 pub struct Embedder {
     config: EmbeddingConfig,
     /// The ONNX runtime session. None if model couldn't be loaded.
-    /// Stored in a Mutex because Session::run requires &mut self.
-    session: Option<std::sync::Mutex<Session>>,
+    /// Stored in a Mutex because Session::run requires &mut self, and so
+    /// that [`Self::reset_session`] can swap it while the embedder is shared.
+    session: std::sync::Mutex<Option<Session>>,
     /// Tokenizer for the embedding model. None if tokenizer couldn't be loaded.
     tokenizer: Option<tokenizers::Tokenizer>,
     /// Model fingerprint for staleness detection.
@@ -86,6 +134,9 @@ pub struct Embedder {
     /// model file is present.  `embed_sparse()` returns `Err(OmniError::Degraded)`
     /// when this is `None`, allowing callers to skip the sparse signal silently.
     sparse_session: Option<std::sync::Mutex<Session>>,
+    /// Duration of the last [`Self::embed_single`] call in microseconds;
+    /// 0 until one has run.
+    last_latency_us: std::sync::atomic::AtomicU64,
 }
 
 impl Embedder {
@@ -101,12 +152,13 @@ impl Embedder {
             tracing::info!("OMNI_SKIP_MODEL_DOWNLOAD is set, skipping embedding model loading");
             return Ok(Self {
                 config: config.clone(),
-                session: None,
+                session: std::sync::Mutex::new(None),
                 tokenizer: None,
-                model_fingerprint: format!("skip:{}:{}", config.dimensions, config.max_seq_length,),
+                model_fingerprint: format!("skip:{}:{}", config.dimensions, config.max_seq_length),
                 pool: None,
                 model_path: None,
                 sparse_session: None,
+                last_latency_us: std::sync::atomic::AtomicU64::new(0),
             });
         }
 
@@ -201,11 +253,11 @@ impl Embedder {
                     model = %bge_m3_path.display(),
                     "loading BGE-M3 sparse session"
                 );
-                Self::build_onnx_session(&bge_m3_path)
+                Self::build_onnx_session(&bge_m3_path).map(std::sync::Mutex::new)
             } else {
                 // Auto-download BGE-M3 when not present.
                 match model_manager::ensure_model(&model_manager::BGE_M3_MODEL) {
-                    Ok(_paths) => Self::build_onnx_session(&bge_m3_path),
+                    Ok(_paths) => Self::build_onnx_session(&bge_m3_path).map(std::sync::Mutex::new),
                     Err(e) => {
                         tracing::warn!(
                             error = %e,
@@ -221,7 +273,7 @@ impl Embedder {
 
         Ok(Self {
             config: config.clone(),
-            session,
+            session: std::sync::Mutex::new(session),
             tokenizer,
             model_fingerprint: format!(
                 "{}:{}:{}",
@@ -236,6 +288,7 @@ impl Embedder {
             pool,
             model_path: Some(model_path),
             sparse_session,
+            last_latency_us: std::sync::atomic::AtomicU64::new(0),
         })
     }
 
@@ -244,7 +297,7 @@ impl Embedder {
     /// Returns `None` on any failure (degraded mode). Never panics or propagates errors.
     /// This ensures the embedder always initializes, even if ONNX Runtime has problems.
     /// Uses `catch_unwind` to guard against native ONNX Runtime crashes.
-    fn build_onnx_session(model_path: &std::path::Path) -> Option<std::sync::Mutex<Session>> {
+    fn build_onnx_session(model_path: &std::path::Path) -> Option<Session> {
         let path = model_path.to_path_buf();
         match std::panic::catch_unwind(std::panic::AssertUnwindSafe(move || {
             Self::build_onnx_session_inner(&path)
@@ -283,7 +336,7 @@ impl Embedder {
     ///
     /// The GPU providers are additive: if CUDA is compiled in but the GPU
     /// runtime is not installed, ORT falls back to the next provider silently.
-    fn build_onnx_session_inner(model_path: &std::path::Path) -> Option<std::sync::Mutex<Session>> {
+    fn build_onnx_session_inner(model_path: &std::path::Path) -> Option<Session> {
         use ort::session::builder::GraphOptimizationLevel;
 
         // Determine optimal thread count once; reused across fallback paths.
@@ -364,7 +417,7 @@ impl Embedder {
                     path = %model_path.display(),
                     "ONNX session loaded (Level3, intra_threads={num_threads}, inter_threads=1)"
                 );
-                Some(std::sync::Mutex::new(session))
+                Some(session)
             }
             Err(primary_err) => {
                 // Primary build failed (e.g. ORT version doesn't support inter_threads).
@@ -391,7 +444,7 @@ impl Embedder {
                             path = %model_path.display(),
                             "ONNX session loaded via fallback config (Level3, intra_threads={num_threads})"
                         );
-                        Some(std::sync::Mutex::new(session))
+                        Some(session)
                     }
                     Err(fallback_err) => {
                         // Last resort: bare session with no thread/optimization overrides.
@@ -414,7 +467,7 @@ impl Embedder {
                                     path = %model_path.display(),
                                     "ONNX session loaded with ORT defaults (no thread config)"
                                 );
-                                Some(std::sync::Mutex::new(session))
+                                Some(session)
                             }
                             Err(e) => {
                                 tracing::error!(
@@ -484,74 +537,25 @@ impl Embedder {
             ))
         }
 
-        // Production path: download the Jina model with retry + exponential backoff.
+        // Production path: download the Jina model. Transient failures are
+        // retried inside the model manager, resuming the partial file.
         // No fallback to secondary models. Jina is the canonical embedding model.
         #[cfg(not(test))]
         {
-            const MAX_RETRIES: u32 = 3;
-            let mut last_error = None;
-
-            for attempt in 1..=MAX_RETRIES {
-                tracing::info!(
-                    attempt,
-                    max_retries = MAX_RETRIES,
-                    model = spec.name,
-                    "attempting model download"
-                );
-
-                match model_manager::ensure_model(spec) {
-                    Ok((model, tokenizer)) => {
-                        if attempt > 1 {
-                            tracing::info!(
-                                attempt,
-                                model = spec.name,
-                                "model download succeeded after retry"
-                            );
-                        }
-                        return Ok((model, tokenizer));
-                    }
-                    Err(e) => {
-                        tracing::warn!(
-                            attempt,
-                            max_retries = MAX_RETRIES,
-                            error = %e,
-                            model = spec.name,
-                            "model download failed"
-                        );
-                        last_error = Some(e);
-
-                        if attempt < MAX_RETRIES {
-                            // Exponential backoff: 2s, 4s
-                            let delay = std::time::Duration::from_secs(2u64.pow(attempt));
-                            tracing::info!(
-                                delay_secs = delay.as_secs(),
-                                "retrying model download after backoff"
-                            );
-                            std::thread::sleep(delay);
-
-                            // Clean up any partial downloads before retry
-                            let model_path = model_manager::model_path(spec);
-                            let partial = model_path.with_extension("downloading");
-                            if partial.exists() {
-                                let _ = std::fs::remove_file(&partial);
-                            }
-                        }
-                    }
-                }
-            }
-
-            // All retries exhausted -- log critical error but do not crash
-            if let Some(e) = last_error {
-                tracing::error!(
+            tracing::info!(model = spec.name, "attempting model download");
+            match model_manager::ensure_model(spec) {
+                Ok(paths) => return Ok(paths),
+                // Download failed -- log critical error but do not crash
+                Err(e) => tracing::error!(
                     error = %e,
                     model = spec.name,
-                    retries = MAX_RETRIES,
-                    "CRITICAL: All download attempts exhausted for {}. \
+                    "CRITICAL: Download failed for {}. \
                      Semantic search is DISABLED until model is available.\n\
-                     Fix: Run 'omnicontext setup model-download' manually,\n\
+                     Fix: Run 'omnicontext setup model-download' manually (the partial download \
+                     resumes), set OMNI_MODEL_MIRROR or OMNI_MODEL_PROXY,\n\
                      or set OMNI_MODEL_PATH to a local ONNX model file.",
                     spec.name
-                );
+                ),
             }
 
             Ok((
@@ -561,34 +565,47 @@ impl Embedder {
         }
     }
 
+    /// The embedder for `config`, shared with every other engine in the
+    /// process that uses the same configuration.
+    pub fn shared(config: &EmbeddingConfig) -> OmniResult<std::sync::Arc<Self>> {
+        static EMBEDDERS: shared::SharedModels<Embedder> = shared::SharedModels::new();
+        EMBEDDERS.get_or_load(config, || Self::new(config), Self::is_available)
+    }
+
     /// Create an embedder in degraded mode (for testing without a model).
     pub fn degraded(config: &EmbeddingConfig) -> Self {
         Self {
             config: config.clone(),
-            session: None,
+            session: std::sync::Mutex::new(None),
             tokenizer: None,
-            model_fingerprint: format!("degraded:{}:{}", config.dimensions, config.max_seq_length,),
+            model_fingerprint: format!("degraded:{}:{}", config.dimensions, config.max_seq_length),
             pool: None,
             model_path: None,
             sparse_session: None,
+            last_latency_us: std::sync::atomic::AtomicU64::new(0),
         }
     }
 
     /// Whether the embedding model is loaded and operational.
     pub fn is_available(&self) -> bool {
-        self.session.is_some()
+        self.session.lock().is_ok_and(|s| s.is_some())
     }
 
     /// Drop the current ONNX session and create a fresh one from disk.
     /// This releases the accumulated ONNX Runtime arena memory.
-    pub fn reset_session(&mut self) {
+    ///
+    /// Engines sharing this embedder wait on the session lock until the
+    /// fresh session is ready.
+    pub fn reset_session(&self) {
         if let Some(path) = &self.model_path {
-            let path = path.clone();
+            let Ok(mut session) = self.session.lock() else {
+                return;
+            };
             // Drop old session first to free memory
-            self.session = None;
+            *session = None;
             tracing::info!("reset_session: old session dropped, rebuilding...");
-            self.session = Self::build_onnx_session_inner(&path);
-            if self.session.is_some() {
+            *session = Self::build_onnx_session_inner(path);
+            if session.is_some() {
                 tracing::info!("reset_session: fresh session ready");
             } else {
                 tracing::error!("reset_session: failed to rebuild session");
@@ -598,7 +615,7 @@ impl Embedder {
 
     /// Number of ONNX sessions available (1 primary + pool).
     pub fn pool_size(&self) -> usize {
-        let base = usize::from(self.session.is_some());
+        let base = usize::from(self.is_available());
         base + self.pool.as_ref().map(|p| p.pool_size()).unwrap_or(0)
     }
 
@@ -702,6 +719,78 @@ impl Embedder {
         &self.model_fingerprint
     }
 
+    /// Identity of the configured embedding model, for drift detection.
+    ///
+    /// A user-supplied `model_path` is identified by file name and size;
+    /// managed models by [`ModelSpec::name`] and [`ModelSpec::revision`].
+    pub fn model_info(&self) -> crate::types::EmbeddingModelInfo {
+        let (name, revision) = if let Ok(meta) = std::fs::metadata(&self.config.model_path) {
+            (
+                self.config
+                    .model_path
+                    .file_name()
+                    .map(|f| f.to_string_lossy().into_owned())
+                    .unwrap_or_default(),
+                format!("{}b", meta.len()),
+            )
+        } else {
+            let spec = model_manager::resolve_model_spec();
+            (spec.name.to_string(), spec.revision().to_string())
+        };
+        crate::types::EmbeddingModelInfo {
+            name,
+            revision,
+            dimensions: self.config.dimensions,
+        }
+    }
+
+    /// Run one throwaway inference so ONNX Runtime finishes its lazy
+    /// allocation and kernel selection before the first real query.
+    ///
+    /// Not recorded as query latency. Returns whether the model answered.
+    pub fn warm_up(&self) -> bool {
+        self.is_available()
+            && matches!(
+                self.embed_batch(&[
+                    "Represent this code snippet for searching relevant code: fn main() {}"
+                ])
+                .pop(),
+                Some(Some(_))
+            )
+    }
+
+    /// Load state, size on disk, and last query latency of the model.
+    pub fn health(&self) -> crate::types::ModelHealth {
+        let info = self.model_info();
+        let user_model = self.config.model_path.exists();
+        let spec = model_manager::resolve_model_spec();
+        let size_bytes = if user_model {
+            std::fs::metadata(&self.config.model_path).map_or(0, |m| m.len())
+        } else {
+            model_manager::installed_size(spec)
+        };
+        let unavailable_reason = if self.is_available() {
+            None
+        } else if std::env::var("OMNI_SKIP_MODEL_DOWNLOAD").is_ok() {
+            Some("model loading skipped because OMNI_SKIP_MODEL_DOWNLOAD is set".to_string())
+        } else if !user_model && !model_manager::is_model_ready(spec) {
+            Some(format!(
+                "model {} is not downloaded; run `omnicontext setup model-download`",
+                spec.name
+            ))
+        } else {
+            Some("ONNX session failed to load; see the log for details".to_string())
+        };
+        crate::types::ModelHealth {
+            available: self.is_available(),
+            name: info.name,
+            revision: info.revision,
+            size_bytes,
+            last_latency_ms: last_latency_ms(&self.last_latency_us),
+            unavailable_reason,
+        }
+    }
+
     /// Check if vectors produced with the given fingerprint are stale.
     ///
     /// Returns `true` if the stored fingerprint differs from the current model,
@@ -723,14 +812,11 @@ impl Embedder {
     /// - Detailed logging for debugging coverage issues
     /// - Aggressive skipping of problematic chunks to prevent hangs
     pub fn embed_batch(&self, chunks: &[&str]) -> Vec<Option<Vec<f32>>> {
-        let session_mutex = match self.session.as_ref() {
-            Some(s) => s,
-            None => return vec![None; chunks.len()],
+        let Ok(mut guard) = self.session.lock() else {
+            return vec![None; chunks.len()];
         };
-
-        let mut session = match session_mutex.lock() {
-            Ok(s) => s,
-            Err(_) => return vec![None; chunks.len()],
+        let Some(session) = guard.as_mut() else {
+            return vec![None; chunks.len()];
         };
 
         let mut all_embeddings = Vec::with_capacity(chunks.len());
@@ -752,7 +838,7 @@ impl Embedder {
                 );
             }
 
-            match self.run_inference(&mut session, batch) {
+            match self.run_inference(session, batch) {
                 Ok(batch_embeddings) => {
                     for emb in batch_embeddings {
                         all_embeddings.push(Some(emb));
@@ -779,7 +865,7 @@ impl Embedder {
                             continue;
                         }
 
-                        let embedding = self.embed_single_with_retry(text, &mut session);
+                        let embedding = self.embed_single_with_retry(text, session);
                         if embedding.is_none() {
                             tracing::debug!(
                                 batch_idx = batch_idx,
@@ -907,7 +993,9 @@ impl Embedder {
                 reason: format!("model not loaded: {}", self.config.model_path.display()),
             });
         }
+        let started = std::time::Instant::now();
         let mut results = self.embed_batch(&[text]);
+        record_latency(&self.last_latency_us, started);
         if let Some(Some(emb)) = results.pop() {
             Ok(emb)
         } else {
@@ -1308,6 +1396,7 @@ mod tests {
             enable_sparse_retrieval: false,
             cloud_api_key: None,
             quantization_mode: crate::embedder::quantization::QuantizationMode::None,
+            reembed_on_model_change: false,
         };
         // Use degraded() directly to avoid triggering download
         let embedder = Embedder::degraded(&config);
@@ -1324,6 +1413,7 @@ mod tests {
             enable_sparse_retrieval: false,
             cloud_api_key: None,
             quantization_mode: crate::embedder::quantization::QuantizationMode::None,
+            reembed_on_model_change: false,
         };
         let embedder = Embedder::degraded(&config);
         let result = embedder.embed_single("test text");
@@ -1345,6 +1435,7 @@ mod tests {
             enable_sparse_retrieval: false,
             cloud_api_key: None,
             quantization_mode: crate::embedder::quantization::QuantizationMode::None,
+            reembed_on_model_change: false,
         };
         let embedder = Embedder::degraded(&config);
         assert_eq!(embedder.dimensions(), 768);
@@ -1365,6 +1456,38 @@ mod tests {
         assert_eq!(ts, "getUser() {}");
     }
 
+    #[test]
+    fn test_latency_slot_reports_last_call() {
+        let slot = std::sync::atomic::AtomicU64::new(0);
+        assert_eq!(last_latency_ms(&slot), None);
+        record_latency(&slot, std::time::Instant::now());
+        assert!(last_latency_ms(&slot).is_some_and(|ms| ms >= 0.0));
+    }
+
+    #[test]
+    fn test_detect_model_drift() {
+        let info = |name: &str, revision: &str| crate::types::EmbeddingModelInfo {
+            name: name.to_string(),
+            revision: revision.to_string(),
+            dimensions: 768,
+        };
+        let current = info("CodeRankEmbed", "main");
+
+        assert_eq!(detect_model_drift(None, None, &current), None);
+        assert_eq!(
+            detect_model_drift(Some(&current), Some(&current), &current),
+            None
+        );
+
+        let other = info("CodeRankEmbed", "v2");
+        let drift = detect_model_drift(Some(&current), Some(&other), &current).expect("drift");
+        assert_eq!(drift.source, "vectors.bin");
+        assert_eq!(drift.recorded, other);
+
+        let drift = detect_model_drift(Some(&other), None, &current).expect("drift");
+        assert_eq!(drift.source, "index.db");
+    }
+
     #[test]
     fn test_model_fingerprint_degraded() {
         let config = EmbeddingConfig {
@@ -1375,6 +1498,7 @@ mod tests {
             enable_sparse_retrieval: false,
             cloud_api_key: None,
             quantization_mode: crate::embedder::quantization::QuantizationMode::None,
+            reembed_on_model_change: false,
         };
         let embedder = Embedder::degraded(&config);
         assert!(
@@ -1395,6 +1519,7 @@ mod tests {
             enable_sparse_retrieval: false,
             cloud_api_key: None,
             quantization_mode: crate::embedder::quantization::QuantizationMode::None,
+            reembed_on_model_change: false,
         };
         let embedder = Embedder::degraded(&config);
         let fp = embedder.model_fingerprint().to_string();
@@ -1414,6 +1539,7 @@ mod tests {
             enable_sparse_retrieval: false,
             cloud_api_key: None,
             quantization_mode: crate::embedder::quantization::QuantizationMode::None,
+            reembed_on_model_change: false,
         };
         let embedder = Embedder::degraded(&config);
         assert!(
@@ -1432,6 +1558,7 @@ mod tests {
             enable_sparse_retrieval: false,
             cloud_api_key: None,
             quantization_mode: crate::embedder::quantization::QuantizationMode::None,
+            reembed_on_model_change: false,
         };
         let embedder = Embedder::degraded(&config);
         let result = embedder.embed_query("how does caching work?");
@@ -1451,6 +1578,7 @@ mod tests {
             enable_sparse_retrieval: false,
             cloud_api_key: None,
             quantization_mode: crate::embedder::quantization::QuantizationMode::None,
+            reembed_on_model_change: false,
         };
         let embedder = Embedder::degraded(&config);
         assert_eq!(
@@ -1470,6 +1598,7 @@ mod tests {
             enable_sparse_retrieval: false,
             cloud_api_key: None,
             quantization_mode: crate::embedder::quantization::QuantizationMode::None,
+            reembed_on_model_change: false,
         };
         let embedder = Embedder::degraded(&config);
         let results = embedder.embed_batch_parallel(&["test1", "test2"]);
@@ -1490,6 +1619,7 @@ mod tests {
             enable_sparse_retrieval: false,
             cloud_api_key: None,
             quantization_mode: crate::embedder::quantization::QuantizationMode::None,
+            reembed_on_model_change: false,
         };
         let embedder = Embedder::degraded(&config);
         let chunks: Vec<&str> = (0..100).map(|_| "test chunk content").collect();
@@ -1526,6 +1656,7 @@ mod tests {
             enable_sparse_retrieval: false,
             cloud_api_key: None,
             quantization_mode: crate::embedder::quantization::QuantizationMode::None,
+            reembed_on_model_change: false,
         };
         let embedder = Embedder::degraded(&config);
         // pool_size on a degraded embedder must be 0 (no primary, no pool).
@@ -1547,6 +1678,7 @@ mod tests {
             enable_sparse_retrieval: false,
             cloud_api_key: None,
             quantization_mode: crate::embedder::quantization::QuantizationMode::None,
+            reembed_on_model_change: false,
         };
         let embedder = Embedder::degraded(&config);
         let results = embedder.embed_batch_parallel(&[]);
@@ -1566,6 +1698,7 @@ mod tests {
             enable_sparse_retrieval: false,
             cloud_api_key: None,
             quantization_mode: crate::embedder::quantization::QuantizationMode::None,
+            reembed_on_model_change: false,
         };
         let embedder = Embedder::degraded(&config);
         let results = embedder.embed_batch_parallel(&["fn foo() {}"]);
@@ -1587,8 +1720,9 @@ mod tests {
             enable_sparse_retrieval: false,
             cloud_api_key: None,
             quantization_mode: crate::embedder::quantization::QuantizationMode::None,
+            reembed_on_model_change: false,
         };
-        let mut embedder = Embedder::degraded(&config);
+        let embedder = Embedder::degraded(&config);
         // Should not panic
         embedder.reset_session();
         assert!(!embedder.is_available(), "still degraded after no-op reset");
@@ -1614,6 +1748,7 @@ mod tests {
             enable_sparse_retrieval: false,
             cloud_api_key: None,
             quantization_mode: crate::embedder::quantization::QuantizationMode::None,
+            reembed_on_model_change: false,
         };
         // degraded() sets pool=None unconditionally — mirrors what OMNI_POOL_DISABLED does.
         let embedder = Embedder::degraded(&config);
@@ -1643,6 +1778,7 @@ mod tests {
             enable_sparse_retrieval: false,
             cloud_api_key: None,
             quantization_mode: crate::embedder::quantization::QuantizationMode::None,
+            reembed_on_model_change: false,
         };
         let embedder = Embedder::degraded(&config);
         assert!(
@@ -1663,6 +1799,7 @@ mod tests {
             enable_sparse_retrieval: false,
             cloud_api_key: None,
             quantization_mode: crate::embedder::quantization::QuantizationMode::None,
+            reembed_on_model_change: false,
         };
         let embedder = Embedder::degraded(&config);
         let result = embedder.embed_sparse("authentication middleware");
@@ -1688,6 +1825,7 @@ mod tests {
             enable_sparse_retrieval: false,
             cloud_api_key: None,
             quantization_mode: crate::embedder::quantization::QuantizationMode::None,
+            reembed_on_model_change: false,
         };
         let embedder = Embedder::degraded(&config);
         let result = embedder.embed_sparse("");
@@ -1,7 +1,29 @@
-//! Automatic embedding model management.
+//! Automatic model management.
 //!
-//! Downloads and caches the ONNX embedding model and tokenizer on first use.
-//! Models are stored in `~/.omnicontext/models/<model-name>/`.
+//! Downloads and caches every ONNX model OmniContext runs -- the embedding
+//! models, the sparse BGE-M3 model, and the cross-encoder reranker -- along
+//! with their tokenizers on first use. Models are stored in
+//! `~/.omnicontext/models/<model-name>/`.
+//!
+//! ## Downloads
+//!
+//! - **Resumable**: bytes land in `<file>.downloading`; an interrupted
+//!   download continues from where it stopped with an HTTP `Range` request.
+//!   Connection drops and server errors are retried with backoff
+//!   ([`DownloadOptions::retries`], default `OMNI_MODEL_DOWNLOAD_RETRIES`
+//!   or 3), each retry resuming the partial file.
+//! - **Verified**: the model file's SHA-256 is checked against the pinned
+//!   digest when one exists, and recorded in `meta.json` so [`verify_model`]
+//!   can detect later corruption.
+//! - **Observable**: [`DownloadOptions::progress`] receives byte counts;
+//!   without it a terminal progress bar is drawn, or, when stderr is not a
+//!   terminal (daemons, CI), progress is logged every 10%.
+//! - **Proxy-aware**: [`DownloadOptions::proxy`] (default `OMNI_MODEL_PROXY`)
+//!   routes all requests; the standard `HTTPS_PROXY` / `NO_PROXY` variables
+//!   are honoured otherwise.
+//! - **Mirrorable**: [`DownloadOptions::mirror`] (default `OMNI_MODEL_MIRROR`)
+//!   replaces `https://huggingface.co` in model URLs, for internal
+//!   artifact proxies and regional Hugging Face mirrors.
 //!
 //! ## Model Selection
 //!
@@ -22,8 +44,9 @@
 //! After download, the model path is stable and cached forever.
 
 use sha2::Digest as _;
-use std::io::Write;
+use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 use crate::error::{OmniError, OmniResult};
 
@@ -55,6 +78,21 @@ pub struct ModelSpec {
     pub sha256: Option<&'static str>,
 }
 
+impl ModelSpec {
+    /// Revision of the model weights: the pinned SHA-256 digest when known,
+    /// otherwise the HuggingFace ref the model URL resolves (e.g. `main`).
+    pub fn revision(&self) -> &'static str {
+        if let Some(digest) = self.sha256 {
+            return digest;
+        }
+        self.model_url
+            .split("/resolve/")
+            .nth(1)
+            .and_then(|rest| rest.split('/').next())
+            .unwrap_or("unknown")
+    }
+}
+
 /// Primary embedding model: `nomic-ai/CodeRankEmbed` (Apache-2.0).
 ///
 /// Architecture: bi-encoder, 137M parameters, initialized from Arctic-Embed-M-Long.
@@ -150,8 +188,98 @@ pub const RERANKER_MODEL: ModelSpec = ModelSpec {
     sha256: None,                   // TODO: pin once canonical HF ONNX digest is published
 };
 
+/// Every model the manager knows about, in display order.
+pub const ALL_MODELS: [&ModelSpec; 4] = [
+    &DEFAULT_MODEL,
+    &QWEN3_EMBEDDING_MODEL,
+    &BGE_M3_MODEL,
+    &RERANKER_MODEL,
+];
+
+/// Look up a model by name or HuggingFace repo (case-insensitive).
+pub fn find_model(name: &str) -> Option<&'static ModelSpec> {
+    ALL_MODELS.into_iter().find(|spec| {
+        spec.name.eq_ignore_ascii_case(name) || spec.hf_repo.eq_ignore_ascii_case(name)
+    })
+}
+
+/// Progress of a single file download.
+#[derive(Debug, Clone, Copy)]
+pub struct DownloadProgress<'a> {
+    /// Model being downloaded.
+    pub model: &'a str,
+    /// File within the model directory (`model.onnx`, `tokenizer.json`).
+    pub file: &'a str,
+    /// Bytes on disk so far, including any resumed prefix.
+    pub downloaded: u64,
+    /// Total size, when the server or the model spec reports it.
+    pub total: Option<u64>,
+}
+
+/// Callback receiving [`DownloadProgress`] updates.
+pub type ProgressCallback = dyn Fn(&DownloadProgress<'_>) + Send + Sync;
+
+/// How model files are fetched.
+#[derive(Clone, Default)]
+pub struct DownloadOptions {
+    /// Progress callback. When `None`, a terminal progress bar is drawn.
+    pub progress: Option<Arc<ProgressCallback>>,
+    /// Proxy URL for every request (e.g. `http://proxy.corp:3128`).
+    pub proxy: Option<String>,
+    /// Base URL serving the Hugging Face layout (`<repo>/resolve/<rev>/<file>`)
+    /// in place of `https://huggingface.co`.
+    pub mirror: Option<String>,
+    /// Retries after a connection drop or server error, each resuming the
+    /// partial file.
+    pub retries: u32,
+}
+
+/// Retries when `OMNI_MODEL_DOWNLOAD_RETRIES` is unset.
+const DEFAULT_DOWNLOAD_RETRIES: u32 = 3;
+
+impl DownloadOptions {
+    /// Options from the environment: `OMNI_MODEL_PROXY` sets the proxy,
+    /// `OMNI_MODEL_MIRROR` the mirror, and `OMNI_MODEL_DOWNLOAD_RETRIES`
+    /// the retry count.
+    pub fn from_env() -> Self {
+        let var = |name| {
+            std::env::var(name)
+                .ok()
+                .filter(|v: &String| !v.trim().is_empty())
+        };
+        Self {
+            progress: None,
+            proxy: var("OMNI_MODEL_PROXY"),
+            mirror: var("OMNI_MODEL_MIRROR"),
+            retries: var("OMNI_MODEL_DOWNLOAD_RETRIES")
+                .and_then(|v| v.trim().parse().ok())
+                .unwrap_or(DEFAULT_DOWNLOAD_RETRIES),
+        }
+    }
+
+    /// `url` with the Hugging Face origin replaced by the mirror, if any.
+    fn resolve_url(&self, url: &str) -> String {
+        const HF_ORIGIN: &str = "https://huggingface.co";
+        match (&self.mirror, url.strip_prefix(HF_ORIGIN)) {
+            (Some(mirror), Some(path)) => format!("{}{path}", mirror.trim_end_matches('/')),
+            _ => url.to_string(),
+        }
+    }
+}
+
+impl std::fmt::Debug for DownloadOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DownloadOptions")
+            .field("progress", &self.progress.is_some())
+            .field("proxy", &self.proxy)
+            .field("mirror", &self.mirror)
+            .field("retries", &self.retries)
+            .finish()
+    }
+}
+
 /// Get the models directory: `~/.omnicontext/models/`
-fn models_base_dir() -> PathBuf {
+pub(super) fn models_base_dir() -> PathBuf {
     dirs::data_local_dir()
         .unwrap_or_else(|| PathBuf::from("."))
         .join("omnicontext")
@@ -234,6 +362,14 @@ pub fn is_model_ready(spec: &ModelSpec) -> bool {
 ///
 /// Returns the paths to (model.onnx, tokenizer.json).
 pub fn ensure_model(spec: &ModelSpec) -> OmniResult<(PathBuf, PathBuf)> {
+    ensure_model_with(spec, &DownloadOptions::from_env())
+}
+
+/// [`ensure_model`] with explicit download options.
+pub fn ensure_model_with(
+    spec: &ModelSpec,
+    options: &DownloadOptions,
+) -> OmniResult<(PathBuf, PathBuf)> {
     let model = model_path(spec);
     let tokenizer = tokenizer_path(spec);
 
@@ -263,150 +399,460 @@ pub fn ensure_model(spec: &ModelSpec) -> OmniResult<(PathBuf, PathBuf)> {
             .unwrap_or(true)
     {
         download_file(
-            spec.model_url,
+            &FileDownload {
+                url: spec.model_url,
+                model: spec.name,
+                expected_size: Some(spec.approx_size_bytes),
+                expected_sha256: spec.sha256,
+            },
             &model,
-            &format!("Downloading {} model", spec.name),
-            Some(spec.approx_size_bytes),
-            spec.sha256,
+            options,
         )?;
     }
 
     // Download tokenizer
     if !tokenizer.exists() {
         download_file(
-            spec.tokenizer_url,
+            &FileDownload {
+                url: spec.tokenizer_url,
+                model: spec.name,
+                expected_size: None,
+                expected_sha256: None, // tokenizer is small JSON; no integrity pin needed
+            },
             &tokenizer,
-            &format!("Downloading {} tokenizer", spec.name),
-            None,
-            None, // tokenizer is small JSON; no integrity pin needed
+            options,
         )?;
     }
 
-    // Write a metadata file for tracking
-    let meta_path = dir.join("meta.json");
+    write_model_meta(spec, &dir, file_sha256(&model).ok().as_deref());
+
+    tracing::info!(
+        model = spec.name,
+        path = %model.display(),
+        "embedding model ready"
+    );
+
+    Ok((model, tokenizer))
+}
+
+/// Write `meta.json` for tracking, including the digest `verify_model`
+/// compares against when no digest is pinned.
+pub(super) fn write_model_meta(spec: &ModelSpec, dir: &Path, sha256: Option<&str>) {
     let meta = serde_json::json!({
         "model": spec.name,
         "hf_repo": spec.hf_repo,
         "dimensions": spec.dimensions,
         "max_seq_length": spec.max_seq_length,
+        "sha256": sha256,
         "downloaded_at": chrono_now_iso(),
     });
     if let Ok(content) = serde_json::to_string_pretty(&meta) {
-        let _ = std::fs::write(&meta_path, content);
+        let _ = std::fs::write(dir.join("meta.json"), content);
     }
+}
 
-    tracing::info!(
-        model = spec.name,
-        path = %model.display(),
-        "embedding model ready"
-    );
+/// One file to fetch for a model.
+struct FileDownload<'a> {
+    url: &'a str,
+    model: &'a str,
+    expected_size: Option<u64>,
+    expected_sha256: Option<&'a str>,
+}
 
-    Ok((model, tokenizer))
+/// Why a download attempt failed.
+#[derive(Debug)]
+enum DownloadFailure {
+    /// Worth retrying: the connection failed or dropped, or the server
+    /// returned a 5xx or 429.
+    Transient(OmniError),
+    /// Retrying would fail the same way.
+    Fatal(OmniError),
+}
+
+impl From<std::io::Error> for DownloadFailure {
+    fn from(e: std::io::Error) -> Self {
+        Self::Fatal(e.into())
+    }
+}
+
+impl From<OmniError> for DownloadFailure {
+    fn from(e: OmniError) -> Self {
+        Self::Fatal(e)
+    }
 }
 
-/// Download a file from a URL with progress bar.
+/// Download a file from a URL, resuming a previous partial download and
+/// retrying transient failures with exponential backoff.
 ///
 /// Uses `tokio::task::block_in_place` when called from within an async runtime
 /// to avoid panics from `reqwest::blocking` nesting a second tokio runtime.
-fn download_file(
-    url: &str,
-    dest: &Path,
-    message: &str,
-    expected_size: Option<u64>,
-    expected_sha256: Option<&str>,
-) -> OmniResult<()> {
+fn download_file(job: &FileDownload<'_>, dest: &Path, options: &DownloadOptions) -> OmniResult<()> {
+    let with_retries = || {
+        let mut attempt = 0;
+        loop {
+            match download_file_inner(job, dest, options) {
+                Ok(()) => return Ok(()),
+                Err(DownloadFailure::Transient(e)) if attempt < options.retries => {
+                    attempt += 1;
+                    let delay = std::time::Duration::from_secs(2_u64.pow(attempt.min(5)));
+                    tracing::warn!(
+                        error = %e,
+                        attempt,
+                        retries = options.retries,
+                        delay_secs = delay.as_secs(),
+                        "model download interrupted, resuming after backoff"
+                    );
+                    std::thread::sleep(delay);
+                }
+                Err(DownloadFailure::Transient(e) | DownloadFailure::Fatal(e)) => return Err(e),
+            }
+        }
+    };
     // If we're inside a tokio runtime, use block_in_place to allow blocking I/O.
     // reqwest::blocking creates its own internal runtime, which panics if a
     // tokio runtime is already running on this thread.
     if tokio::runtime::Handle::try_current().is_ok() {
-        return tokio::task::block_in_place(|| {
-            download_file_inner(url, dest, message, expected_size, expected_sha256)
-        });
+        return tokio::task::block_in_place(with_retries);
     }
-    download_file_inner(url, dest, message, expected_size, expected_sha256)
+    with_retries()
 }
 
 fn download_file_inner(
-    url: &str,
+    job: &FileDownload<'_>,
     dest: &Path,
-    message: &str,
-    expected_size: Option<u64>,
-    expected_sha256: Option<&str>,
-) -> OmniResult<()> {
-    // Use a temp file to avoid partial downloads
+    options: &DownloadOptions,
+) -> Result<(), DownloadFailure> {
+    let url = &options.resolve_url(job.url);
+    // Partial bytes live here until the download completes, so an interrupted
+    // download resumes instead of starting over.
     let temp_path = dest.with_extension("downloading");
-
-    let response = reqwest::blocking::Client::builder()
-        .timeout(std::time::Duration::from_secs(600)) // 10 min timeout for large models
+    let resume_from = std::fs::metadata(&temp_path).map_or(0, |m| m.len());
+
+    let mut client = reqwest::blocking::Client::builder()
+        .connect_timeout(std::time::Duration::from_secs(30))
+        .timeout(std::time::Duration::from_secs(3600)); // large models on slow links
+    if let Some(proxy) = &options.proxy {
+        let proxy = reqwest::Proxy::all(proxy).map_err(|e| OmniError::Config {
+            details: format!("invalid model download proxy '{proxy}': {e}"),
+        })?;
+        client = client.proxy(proxy);
+    }
+    let mut request = client
         .build()
         .map_err(|e| OmniError::Internal(format!("HTTP client error: {e}")))?
-        .get(url)
-        .send()
-        .map_err(|e| {
-            OmniError::Internal(format!(
-                "failed to download model from {url}: {e}\n\
-                 Hint: Check your internet connection. You can also manually download\n\
-                 the model and set OMNI_MODEL_PATH to point to it."
-            ))
-        })?;
-
-    if !response.status().is_success() {
-        return Err(OmniError::Internal(format!(
-            "model download failed: HTTP {} from {url}",
-            response.status()
-        )));
+        .get(url);
+    if resume_from > 0 {
+        request = request.header(reqwest::header::RANGE, format!("bytes={resume_from}-"));
     }
+    let mut response = request.send().map_err(|e| {
+        DownloadFailure::Transient(OmniError::Internal(format!(
+            "failed to download model from {url}: {e}\n\
+             Hint: Check your internet connection (or set OMNI_MODEL_PROXY or\n\
+             OMNI_MODEL_MIRROR). You can also manually download the model and set\n\
+             OMNI_MODEL_PATH to point to it."
+        )))
+    })?;
 
-    let total_size = response.content_length().or(expected_size).unwrap_or(0);
-
-    // Create progress bar
-    let pb = if total_size > 0 {
-        let pb = indicatif::ProgressBar::new(total_size);
-        pb.set_style(
-            indicatif::ProgressStyle::default_bar()
-                .template("{msg}\n  [{elapsed_precise}] [{bar:40.cyan/blue}] {bytes}/{total_bytes} ({eta})")
-                .unwrap_or_else(|_| indicatif::ProgressStyle::default_bar())
-                .progress_chars("##-"),
+    let status = response.status();
+    if status == reqwest::StatusCode::RANGE_NOT_SATISFIABLE && resume_from > 0 {
+        // The partial file is stale or already complete; start over.
+        std::fs::remove_file(&temp_path)?;
+        return download_file_inner(job, dest, options);
+    }
+    if !status.is_success() {
+        let error = OmniError::Internal(format!("model download failed: HTTP {status} from {url}"));
+        return Err(
+            if status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+                DownloadFailure::Transient(error)
+            } else {
+                DownloadFailure::Fatal(error)
+            },
         );
-        pb.set_message(message.to_string());
-        pb
+    }
+
+    let resumed = resume_from > 0 && status == reqwest::StatusCode::PARTIAL_CONTENT;
+    let (mut file, mut downloaded) = if resumed {
+        tracing::info!(url, bytes = resume_from, "resuming model download");
+        (
+            std::fs::OpenOptions::new().append(true).open(&temp_path)?,
+            resume_from,
+        )
     } else {
-        let pb = indicatif::ProgressBar::new_spinner();
-        pb.set_message(message.to_string());
-        pb
+        (std::fs::File::create(&temp_path)?, 0)
     };
-
-    // Stream download to temp file
-    let mut file = std::fs::File::create(&temp_path)?;
-    let mut downloaded: u64 = 0;
-
-    // Read in chunks
-    let bytes = response
-        .bytes()
-        .map_err(|e| OmniError::Internal(format!("download stream error: {e}")))?;
-
-    let chunk_size = 8192;
-    for chunk in bytes.chunks(chunk_size) {
-        file.write_all(chunk)?;
-        downloaded += chunk.len() as u64;
-        pb.set_position(downloaded);
+    let total = response
+        .content_length()
+        .map(|len| len + downloaded)
+        .or(job.expected_size);
+
+    let file_name = dest
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let mut progress = ProgressReporter::new(options, job.model, &file_name, total);
+    progress.update(downloaded);
+
+    // Stream to disk; an error here leaves the partial file for the next attempt.
+    let mut buf = vec![0_u8; 64 * 1024];
+    loop {
+        let n = response.read(&mut buf).map_err(|e| {
+            DownloadFailure::Transient(OmniError::Internal(format!("download stream error: {e}")))
+        })?;
+        if n == 0 {
+            break;
+        }
+        file.write_all(&buf[..n])?;
+        downloaded += n as u64;
+        progress.update(downloaded);
     }
 
     file.flush()?;
     drop(file);
-
-    pb.finish_with_message(format!("{message} -- done"));
+    progress.finish();
 
     // Atomic rename: temp -> final (prevents corrupt partial files)
     std::fs::rename(&temp_path, dest)?;
 
     // SHA-256 integrity check — guards against download corruption and supply-chain tampering.
     // Performed after the rename so any deletion targets the final path, not a temp artefact.
-    verify_sha256_after_download(dest, expected_sha256)?;
+    verify_sha256_after_download(dest, job.expected_sha256)?;
 
     Ok(())
 }
 
+/// Routes download progress to the caller's callback or a terminal bar.
+enum ProgressReporter<'a> {
+    Callback {
+        callback: Arc<ProgressCallback>,
+        model: &'a str,
+        file: &'a str,
+        total: Option<u64>,
+    },
+    Bar(indicatif::ProgressBar),
+    /// Stderr is not a terminal: log each 10% step (or each 50 MB when the
+    /// size is unknown).
+    Log {
+        model: &'a str,
+        file: &'a str,
+        total: Option<u64>,
+        logged_step: u64,
+    },
+}
+
+impl<'a> ProgressReporter<'a> {
+    fn new(options: &DownloadOptions, model: &'a str, file: &'a str, total: Option<u64>) -> Self {
+        if let Some(callback) = &options.progress {
+            return Self::Callback {
+                callback: Arc::clone(callback),
+                model,
+                file,
+                total,
+            };
+        }
+        if !std::io::IsTerminal::is_terminal(&std::io::stderr()) {
+            return Self::Log {
+                model,
+                file,
+                total,
+                logged_step: u64::MAX,
+            };
+        }
+        let message = format!("Downloading {model} {file}");
+        let pb = match total {
+            Some(total) if total > 0 => {
+                let pb = indicatif::ProgressBar::new(total);
+                pb.set_style(
+                    indicatif::ProgressStyle::default_bar()
+                        .template("{msg}\n  [{elapsed_precise}] [{bar:40.cyan/blue}] {bytes}/{total_bytes} ({eta})")
+                        .unwrap_or_else(|_| indicatif::ProgressStyle::default_bar())
+                        .progress_chars("##-"),
+                );
+                pb
+            }
+            _ => indicatif::ProgressBar::new_spinner(),
+        };
+        pb.set_message(message);
+        Self::Bar(pb)
+    }
+
+    fn update(&mut self, downloaded: u64) {
+        match self {
+            Self::Callback {
+                callback,
+                model,
+                file,
+                total,
+            } => callback(&DownloadProgress {
+                model,
+                file,
+                downloaded,
+                total: *total,
+            }),
+            Self::Bar(pb) => pb.set_position(downloaded),
+            Self::Log {
+                model,
+                file,
+                total,
+                logged_step,
+            } => {
+                let step = match *total {
+                    Some(total) if total > 0 => downloaded.saturating_mul(10) / total,
+                    _ => downloaded / (50 * 1024 * 1024),
+                };
+                if step != *logged_step {
+                    *logged_step = step;
+                    tracing::info!(
+                        model = *model,
+                        file = *file,
+                        downloaded_mb = downloaded / (1024 * 1024),
+                        total_mb = total.map(|t| t / (1024 * 1024)),
+                        percent = total.filter(|t| *t > 0).map(|t| downloaded * 100 / t),
+                        "model download progress"
+                    );
+                }
+            }
+        }
+    }
+
+    fn finish(&self) {
+        match self {
+            Self::Bar(pb) => pb.finish_with_message(format!("{} -- done", pb.message())),
+            Self::Log { model, file, .. } => {
+                tracing::info!(model = *model, file = *file, "model download complete");
+            }
+            Self::Callback { .. } => {}
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Inventory: list / remove / verify
+// ---------------------------------------------------------------------------
+
+/// Installation state of a known model.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct InstalledModel {
+    /// Model name (`CodeRankEmbed`, `bge-reranker-v2-m3`, ...).
+    pub name: &'static str,
+    /// HuggingFace repository the model is fetched from.
+    pub hf_repo: &'static str,
+    /// Model directory.
+    pub path: PathBuf,
+    /// Whether the model and tokenizer are present and plausibly complete.
+    pub ready: bool,
+    /// Bytes used on disk, including partial downloads.
+    pub size_bytes: u64,
+}
+
+/// Installation state of every known model.
+pub fn list_models() -> Vec<InstalledModel> {
+    ALL_MODELS
+        .into_iter()
+        .map(|spec| {
+            let path = model_dir(spec);
+            InstalledModel {
+                name: spec.name,
+                hf_repo: spec.hf_repo,
+                size_bytes: dir_size(&path),
+                ready: model_path(spec).exists() && tokenizer_path(spec).exists(),
+                path,
+            }
+        })
+        .collect()
+}
+
+/// Bytes `spec` occupies on disk, including partial downloads.
+pub fn installed_size(spec: &ModelSpec) -> u64 {
+    dir_size(&model_dir(spec))
+}
+
+/// Delete a model's directory. Returns the bytes freed (0 if not installed).
+pub fn remove_model(spec: &ModelSpec) -> OmniResult<u64> {
+    let dir = model_dir(spec);
+    if !dir.exists() {
+        return Ok(0);
+    }
+    let size = dir_size(&dir);
+    std::fs::remove_dir_all(&dir)?;
+    tracing::info!(model = spec.name, bytes = size, "removed model");
+    Ok(size)
+}
+
+/// Outcome of checking a model file's integrity.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ModelVerification {
+    /// The model file is not installed.
+    Missing,
+    /// The digest matches the pinned or recorded one.
+    Verified {
+        /// SHA-256 of the model file.
+        sha256: String,
+    },
+    /// The digest differs from the pinned or recorded one.
+    Mismatch {
+        /// Digest the file should have.
+        expected: String,
+        /// Digest the file has.
+        actual: String,
+    },
+    /// No reference digest exists (downloaded before digests were recorded).
+    Unpinned {
+        /// SHA-256 of the model file.
+        sha256: String,
+    },
+}
+
+/// Check the installed model file against its pinned digest, or the digest
+/// recorded in `meta.json` when it was downloaded.
+pub fn verify_model(spec: &ModelSpec) -> OmniResult<ModelVerification> {
+    verify_model_in(&model_dir(spec), spec)
+}
+
+fn verify_model_in(dir: &Path, spec: &ModelSpec) -> OmniResult<ModelVerification> {
+    let model = dir.join("model.onnx");
+    if !model.exists() {
+        return Ok(ModelVerification::Missing);
+    }
+    let actual = file_sha256(&model)?;
+    let recorded = std::fs::read_to_string(dir.join("meta.json"))
+        .ok()
+        .and_then(|meta| serde_json::from_str::<serde_json::Value>(&meta).ok())
+        .and_then(|meta| meta.get("sha256")?.as_str().map(str::to_string));
+    let expected = spec.sha256.map(str::to_string).or(recorded);
+    Ok(match expected {
+        None => ModelVerification::Unpinned { sha256: actual },
+        Some(expected) if expected.eq_ignore_ascii_case(&actual) => {
+            ModelVerification::Verified { sha256: actual }
+        }
+        Some(expected) => ModelVerification::Mismatch { expected, actual },
+    })
+}
+
+/// Streaming SHA-256 of a file, as lowercase hex.
+pub(super) fn file_sha256(path: &Path) -> OmniResult<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = sha2::Sha256::new();
+    std::io::copy(&mut file, &mut hasher)?;
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Total size of the files under `dir` (0 if it does not exist).
+fn dir_size(dir: &Path) -> u64 {
+    std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| match entry.metadata() {
+                    Ok(meta) if meta.is_dir() => dir_size(&entry.path()),
+                    Ok(meta) => meta.len(),
+                    Err(_) => 0,
+                })
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
 /// Resolve the active embedding model spec.
 ///
 /// Returns `DEFAULT_MODEL` (`CodeRankEmbed`, Apache-2.0) unless overridden by the
@@ -458,11 +904,9 @@ fn verify_sha256_after_download(path: &Path, expected: Option<&str>) -> OmniResu
         return Ok(());
     };
 
-    let data = std::fs::read(path).map_err(|e| {
+    let actual = file_sha256(path).map_err(|e| {
         OmniError::Internal(format!("failed to read downloaded file for checksum: {e}"))
     })?;
-    let digest = sha2::Sha256::digest(&data);
-    let actual = hex::encode(digest);
 
     if actual != expected_hex {
         tracing::warn!(
@@ -487,7 +931,7 @@ fn verify_sha256_after_download(path: &Path, expected: Option<&str>) -> OmniResu
 }
 
 /// Simple ISO 8601 timestamp without pulling in chrono.
-fn chrono_now_iso() -> String {
+pub(super) fn chrono_now_iso() -> String {
     use std::time::SystemTime;
     let now = SystemTime::now()
         .duration_since(SystemTime::UNIX_EPOCH)
@@ -511,6 +955,16 @@ mod tests {
         assert!(tokenizer.ends_with("tokenizer.json"));
     }
 
+    #[test]
+    fn test_model_revision() {
+        assert_eq!(DEFAULT_MODEL.revision(), "main");
+        let pinned = ModelSpec {
+            sha256: Some("abc123"),
+            ..DEFAULT_MODEL
+        };
+        assert_eq!(pinned.revision(), "abc123");
+    }
+
     #[test]
     fn test_reranker_model_different_path() {
         let default_dir = model_dir(&DEFAULT_MODEL);
@@ -639,4 +1093,224 @@ mod tests {
         );
         assert!(!dest.exists(), "corrupt file must be deleted on mismatch");
     }
+
+    #[test]
+    fn test_find_model_by_name_or_repo() {
+        assert_eq!(
+            find_model("coderankembed").map(|m| m.name),
+            Some("CodeRankEmbed")
+        );
+        assert_eq!(
+            find_model(RERANKER_MODEL.hf_repo).map(|m| m.name),
+            Some(RERANKER_MODEL.name)
+        );
+        assert!(find_model("no-such-model").is_none());
+    }
+
+    #[test]
+    fn test_verify_model_states() {
+        use sha2::Digest as _;
+
+        let dir = tempfile::tempdir().expect("tempdir");
+        let unpinned = ModelSpec {
+            sha256: None,
+            ..DEFAULT_MODEL
+        };
+        assert_eq!(
+            verify_model_in(dir.path(), &unpinned).expect("verify"),
+            ModelVerification::Missing
+        );
+
+        let content = b"model bytes";
+        let digest = hex::encode(sha2::Sha256::digest(content));
+        std::fs::write(dir.path().join("model.onnx"), content).expect("write");
+        assert_eq!(
+            verify_model_in(dir.path(), &unpinned).expect("verify"),
+            ModelVerification::Unpinned {
+                sha256: digest.clone()
+            }
+        );
+
+        // The digest recorded at download time is the reference when none is pinned.
+        let meta = serde_json::json!({ "sha256": digest });
+        std::fs::write(dir.path().join("meta.json"), meta.to_string()).expect("write");
+        assert_eq!(
+            verify_model_in(dir.path(), &unpinned).expect("verify"),
+            ModelVerification::Verified {
+                sha256: digest.clone()
+            }
+        );
+
+        std::fs::write(dir.path().join("model.onnx"), b"corrupted").expect("write");
+        assert!(matches!(
+            verify_model_in(dir.path(), &unpinned).expect("verify"),
+            ModelVerification::Mismatch { expected, .. } if expected == digest
+        ));
+    }
+
+    /// A partial `.downloading` file is resumed with a `Range` request and the
+    /// progress callback sees the full size.
+    #[test]
+    fn test_download_resumes_partial_file() {
+        use std::io::BufRead as _;
+        use std::sync::Mutex;
+
+        const BODY: &[u8] = b"0123456789abcdef";
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind");
+        let addr = listener.local_addr().expect("addr");
+        let server = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().expect("accept");
+            let mut reader = std::io::BufReader::new(stream.try_clone().expect("clone"));
+            let mut range = None;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).expect("read");
+                if line.trim().is_empty() {
+                    break;
+                }
+                if let Some(value) = line.to_ascii_lowercase().strip_prefix("range: bytes=") {
+                    range = value.trim().trim_end_matches('-').parse::<usize>().ok();
+                }
+            }
+            let start = range.unwrap_or(0);
+            let mut stream = stream;
+            write!(
+                stream,
+                "HTTP/1.1 206 Partial Content\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                BODY.len() - start
+            )
+            .expect("write head");
+            stream.write_all(&BODY[start..]).expect("write body");
+            range
+        });
+
+        let dir = tempfile::tempdir().expect("tempdir");
+        let dest = dir.path().join("model.onnx");
+        std::fs::write(dest.with_extension("downloading"), &BODY[..5]).expect("write");
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&seen);
+        let options = DownloadOptions {
+            progress: Some(Arc::new(move |p: &DownloadProgress<'_>| {
+                sink.lock().expect("lock").push((p.downloaded, p.total));
+            })),
+            ..DownloadOptions::default()
+        };
+        let url = format!("http://{addr}/model.onnx");
+        download_file_inner(
+            &FileDownload {
+                url: &url,
+                model: "test-model",
+                expected_size: None,
+                expected_sha256: None,
+            },
+            &dest,
+            &options,
+        )
+        .expect("download");
+
+        assert_eq!(
+            server.join().expect("server"),
+            Some(5),
+            "must request the missing suffix"
+        );
+        assert_eq!(std::fs::read(&dest).expect("read"), BODY);
+        assert!(!dest.with_extension("downloading").exists());
+        let seen = seen.lock().expect("lock");
+        assert_eq!(seen.first(), Some(&(5, Some(BODY.len() as u64))));
+        assert_eq!(
+            seen.last(),
+            Some(&(BODY.len() as u64, Some(BODY.len() as u64)))
+        );
+    }
+
+    #[test]
+    fn test_mirror_replaces_hugging_face_origin() {
+        let options = DownloadOptions {
+            mirror: Some("https://artifacts.corp/hf/".to_string()),
+            ..DownloadOptions::default()
+        };
+        assert_eq!(
+            options.resolve_url("https://huggingface.co/org/model/resolve/main/model.onnx"),
+            "https://artifacts.corp/hf/org/model/resolve/main/model.onnx"
+        );
+        assert_eq!(
+            options.resolve_url("https://example.com/model.onnx"),
+            "https://example.com/model.onnx"
+        );
+        assert_eq!(
+            DownloadOptions::default().resolve_url("https://huggingface.co/a"),
+            "https://huggingface.co/a"
+        );
+    }
+
+    /// A connection that drops mid-body is retried through the mirror,
+    /// resuming from the bytes already on disk.
+    #[test]
+    fn test_download_retries_dropped_connection_with_resume() {
+        use std::io::BufRead as _;
+
+        const BODY: &[u8] = b"0123456789abcdef";
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind");
+        let addr = listener.local_addr().expect("addr");
+        let server = std::thread::spawn(move || {
+            let mut ranges = Vec::new();
+            for attempt in 0..2 {
+                let (stream, _) = listener.accept().expect("accept");
+                let mut reader = std::io::BufReader::new(stream.try_clone().expect("clone"));
+                let mut range = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).expect("read");
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    if let Some(value) = line.to_ascii_lowercase().strip_prefix("range: bytes=") {
+                        range = value.trim().trim_end_matches('-').parse().unwrap_or(0);
+                    }
+                }
+                ranges.push(range);
+                let mut stream = stream;
+                let status = if range > 0 {
+                    "206 Partial Content"
+                } else {
+                    "200 OK"
+                };
+                write!(
+                    stream,
+                    "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    BODY.len() - range
+                )
+                .expect("write head");
+                // The first response is cut off after 6 bytes.
+                let end = if attempt == 0 { 6 } else { BODY.len() };
+                stream.write_all(&BODY[range..end]).expect("write body");
+                stream.flush().expect("flush");
+            }
+            ranges
+        });
+
+        let dir = tempfile::tempdir().expect("tempdir");
+        let dest = dir.path().join("model.onnx");
+        let options = DownloadOptions {
+            progress: Some(Arc::new(|_: &DownloadProgress<'_>| {})),
+            mirror: Some(format!("http://{addr}")),
+            retries: 1,
+            ..DownloadOptions::default()
+        };
+        download_file(
+            &FileDownload {
+                url: "https://huggingface.co/org/model/resolve/main/model.onnx",
+                model: "test-model",
+                expected_size: None,
+                expected_sha256: None,
+            },
+            &dest,
+            &options,
+        )
+        .expect("download");
+
+        assert_eq!(server.join().expect("server"), vec![0, 6]);
+        assert_eq!(std::fs::read(&dest).expect("read"), BODY);
+    }
 }
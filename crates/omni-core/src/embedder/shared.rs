@@ -0,0 +1,98 @@
+//! Process-wide sharing of loaded models.
+//!
+//! The daemon and workspace mode open one [`crate::pipeline::Engine`] per
+//! repository. Each would otherwise load its own ONNX sessions, so memory
+//! grew by the model size with every repository. A [`SharedModels`]
+//! registry hands every engine with the same model configuration the same
+//! instance.
+//!
+//! Entries are weak: a model is freed once the last engine using it is
+//! dropped. Models that failed to load are not shared, so an engine opened
+//! after the download finishes gets a working model.
+
+use std::sync::{Arc, Mutex, Weak};
+
+use crate::error::OmniResult;
+
+/// Loaded models keyed by their serialized configuration.
+pub(crate) struct SharedModels<T> {
+    entries: Mutex<Vec<(String, Weak<T>)>>,
+}
+
+impl<T> SharedModels<T> {
+    /// An empty registry.
+    pub(crate) const fn new() -> Self {
+        Self {
+            entries: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// The live model loaded for `config`, or a new one from `load`.
+    ///
+    /// `loaded` decides whether a new model is shared; models that fall
+    /// back to a degraded mode should not be. The lock is held while
+    /// loading so concurrent engines don't load the same model twice.
+    pub(crate) fn get_or_load(
+        &self,
+        config: &impl serde::Serialize,
+        load: impl FnOnce() -> OmniResult<T>,
+        loaded: impl FnOnce(&T) -> bool,
+    ) -> OmniResult<Arc<T>> {
+        let key = serde_json::to_string(config).unwrap_or_default();
+        let mut entries = self
+            .entries
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        entries.retain(|(_, model)| model.strong_count() > 0);
+        if let Some(model) = entries
+            .iter()
+            .find(|(k, _)| *k == key)
+            .and_then(|(_, model)| model.upgrade())
+        {
+            return Ok(model);
+        }
+
+        let model = Arc::new(load()?);
+        if loaded(&model) {
+            entries.push((key, Arc::downgrade(&model)));
+        }
+        Ok(model)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shared_while_alive_and_only_when_loaded() {
+        let registry = SharedModels::<(u32, bool)>::new();
+        let a = registry
+            .get_or_load(&"a", || Ok((1, true)), |m| m.1)
+            .expect("load");
+        let again = registry
+            .get_or_load(&"a", || Ok((2, true)), |m| m.1)
+            .expect("load");
+        assert!(Arc::ptr_eq(&a, &again));
+        let b = registry
+            .get_or_load(&"b", || Ok((3, true)), |m| m.1)
+            .expect("load");
+        assert_eq!(b.0, 3);
+
+        // Freed with its last user.
+        drop((a, again));
+        let reloaded = registry
+            .get_or_load(&"a", || Ok((4, true)), |m| m.1)
+            .expect("load");
+        assert_eq!(reloaded.0, 4);
+
+        // Degraded models are never handed to another engine.
+        let degraded = registry
+            .get_or_load(&"c", || Ok((5, false)), |m| m.1)
+            .expect("load");
+        let retried = registry
+            .get_or_load(&"c", || Ok((6, true)), |m| m.1)
+            .expect("load");
+        assert_eq!((degraded.0, retried.0), (5, 6));
+    }
+}
@@ -0,0 +1,166 @@
+//! Decoding of source files that are not UTF-8.
+//!
+//! Most repositories are UTF-8 throughout, but older C and Java trees carry
+//! Latin-1 comments and Windows tools still write UTF-16. Rejecting those
+//! files would drop them from the index entirely, so every file is decoded
+//! to text instead:
+//!
+//! 1. A byte-order mark decides (UTF-8, UTF-16LE, UTF-16BE).
+//! 2. UTF-16 without a BOM is recognised by its NUL bytes: ASCII-heavy
+//!    source has a zero in every other byte.
+//! 3. Valid UTF-8 is taken as-is, without copying.
+//! 4. Anything else is read as Windows-1252, the superset of Latin-1 that
+//!    editors use for "ANSI" files. It maps every byte, so decoding never
+//!    fails; a misdetected file is searchable, just with odd characters.
+//!
+//! Malformed sequences in the detected encoding become U+FFFD.
+
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8, WINDOWS_1252};
+
+/// Bytes inspected when sniffing for BOM-less UTF-16.
+const SNIFF_BYTES: usize = 4096;
+
+/// Decoded content of a source file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceText {
+    /// The file's content as UTF-8, without a byte-order mark.
+    pub text: String,
+    /// Encoding the file was decoded from, or `None` for UTF-8.
+    pub encoding: Option<&'static str>,
+    /// Whether malformed sequences were replaced with U+FFFD.
+    pub lossy: bool,
+}
+
+impl SourceText {
+    /// Whether the file was read as something other than UTF-8.
+    pub fn is_transcoded(&self) -> bool {
+        self.encoding.is_some() || self.lossy
+    }
+}
+
+/// Decode file content, detecting its encoding.
+pub fn decode(bytes: Vec<u8>) -> SourceText {
+    if let Some((encoding, bom_len)) = Encoding::for_bom(&bytes) {
+        return decode_with(encoding, &bytes[bom_len..]);
+    }
+    // ASCII in UTF-16 is valid UTF-8 too, NULs and all; NUL bytes never
+    // appear in real UTF-8 source, so they send the file to the sniffer.
+    if let Some(encoding) = sniff_utf16(&bytes) {
+        return decode_with(encoding, &bytes);
+    }
+    match String::from_utf8(bytes) {
+        Ok(text) => SourceText {
+            text,
+            encoding: None,
+            lossy: false,
+        },
+        Err(e) => decode_with(WINDOWS_1252, &e.into_bytes()),
+    }
+}
+
+/// Read and decode the file at `path`.
+pub fn read_source(path: &std::path::Path) -> std::io::Result<SourceText> {
+    std::fs::read(path).map(decode)
+}
+
+fn decode_with(encoding: &'static Encoding, bytes: &[u8]) -> SourceText {
+    let (text, lossy) = encoding.decode_without_bom_handling(bytes);
+    SourceText {
+        text: text.into_owned(),
+        encoding: (encoding != UTF_8).then(|| encoding.name()),
+        lossy,
+    }
+}
+
+/// UTF-16 without a BOM, recognised by the zero high bytes of ASCII text.
+fn sniff_utf16(bytes: &[u8]) -> Option<&'static Encoding> {
+    let sample = &bytes[..bytes.len().min(SNIFF_BYTES) & !1];
+    if !sample.contains(&0) {
+        return None;
+    }
+    let pairs = sample.len() / 2;
+    let (mut even, mut odd) = (0, 0);
+    for pair in sample.chunks_exact(2) {
+        even += usize::from(pair[0] == 0);
+        odd += usize::from(pair[1] == 0);
+    }
+    // Mostly-ASCII text: the high byte of nearly every code unit is zero,
+    // the low byte almost never.
+    if odd * 4 >= pairs * 3 && even * 10 < pairs {
+        Some(UTF_16LE)
+    } else if even * 4 >= pairs * 3 && odd * 10 < pairs {
+        Some(UTF_16BE)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16(text: &str, little_endian: bool) -> Vec<u8> {
+        text.encode_utf16()
+            .flat_map(|unit| {
+                if little_endian {
+                    unit.to_le_bytes()
+                } else {
+                    unit.to_be_bytes()
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_utf8_passes_through() {
+        let decoded = decode("fn café() {}".as_bytes().to_vec());
+        assert_eq!(decoded.text, "fn café() {}");
+        assert_eq!(decoded.encoding, None);
+        assert!(!decoded.is_transcoded());
+
+        let mut with_bom = vec![0xEF, 0xBB, 0xBF];
+        with_bom.extend_from_slice(b"x = 1");
+        let decoded = decode(with_bom);
+        assert_eq!(decoded.text, "x = 1", "BOM is stripped");
+        assert_eq!(decoded.encoding, None);
+    }
+
+    #[test]
+    fn test_latin1_decoded_as_windows_1252() {
+        // "/* Größe */" in ISO-8859-1.
+        let bytes = b"/* Gr\xF6\xDFe */".to_vec();
+        let decoded = decode(bytes);
+        assert_eq!(decoded.text, "/* Größe */");
+        assert_eq!(decoded.encoding, Some("windows-1252"));
+        assert!(!decoded.lossy);
+    }
+
+    #[test]
+    fn test_utf16_with_and_without_bom() {
+        let source = "int main() { return 0; }";
+
+        let mut le = vec![0xFF, 0xFE];
+        le.extend(utf16(source, true));
+        let decoded = decode(le);
+        assert_eq!(decoded.text, source);
+        assert_eq!(decoded.encoding, Some("UTF-16LE"));
+
+        let decoded = decode(utf16(source, true));
+        assert_eq!(decoded.text, source);
+        assert_eq!(decoded.encoding, Some("UTF-16LE"));
+
+        let decoded = decode(utf16(source, false));
+        assert_eq!(decoded.text, source);
+        assert_eq!(decoded.encoding, Some("UTF-16BE"));
+    }
+
+    #[test]
+    fn test_malformed_utf16_is_lossy() {
+        let mut bytes = vec![0xFF, 0xFE];
+        bytes.extend(utf16("ok", true));
+        bytes.extend_from_slice(&[0x00, 0xD8]); // unpaired high surrogate
+        let decoded = decode(bytes);
+        assert_eq!(decoded.text, "ok\u{FFFD}");
+        assert!(decoded.lossy);
+    }
+}
@@ -37,6 +37,22 @@ pub enum OmniError {
         entity: String,
     },
 
+    /// Another process holds the data directory's writer lock.
+    #[error("index locked: {details}")]
+    IndexLocked {
+        /// Who holds the lock and how to proceed.
+        details: String,
+    },
+
+    /// A WebAssembly plugin failed to load or trapped during a call.
+    #[error("plugin {name}: {details}")]
+    Plugin {
+        /// Plugin name (its file stem).
+        name: String,
+        /// What went wrong.
+        details: String,
+    },
+
     // ---- Degraded (system works with reduced capability) ----
     /// Embedding model is unavailable. System falls back to keyword-only search.
     #[error("embedding model unavailable: {reason}")]
@@ -60,6 +76,16 @@ pub enum OmniError {
         details: String,
     },
 
+    /// The startup health check found a problem it was not allowed to (or
+    /// could not) repair automatically.
+    #[error("index needs repair: {details}. Run `{command}`")]
+    NeedsRepair {
+        /// What the health check found.
+        details: String,
+        /// CLI command that repairs it.
+        command: String,
+    },
+
     /// Not enough disk space to continue indexing.
     #[error("insufficient disk space: {available_mb}MB available, {required_mb}MB required")]
     InsufficientDisk {
@@ -85,6 +111,11 @@ pub enum OmniError {
     #[error("I/O error: {0}")]
     Io(#[from] std::io::Error),
 
+    /// Remote object storage error (index push/pull).
+    #[cfg(feature = "remote-sync")]
+    #[error("remote storage error: {0}")]
+    RemoteStorage(#[from] opendal::Error),
+
     /// Serialization error.
     #[error("serialization error: {0}")]
     Serialization(String),
@@ -0,0 +1,447 @@
+//! Retrieval evaluation against a golden query set.
+//!
+//! The golden set lives in `.omnicontext/eval.toml`:
+//!
+//! ```toml
+//! k = 10
+//!
+//! [[query]]
+//! query = "how are database connections pooled"
+//! files = ["src/db/pool.rs"]
+//! symbols = ["db::pool::Pool::acquire"]
+//!
+//! # Optional: extra search configurations to compare against the
+//! # repository's own `[search]` settings.
+//! [[config]]
+//! name = "low-rrf-k"
+//! search = { rrf_k = 20, bm25 = { symbol_path = 4.0 } }
+//! ```
+//!
+//! Each `[[query]]` lists the files and/or symbols a good answer must
+//! surface. A result hits a file target when its path ends with the listed
+//! path, and a symbol target when its `symbol_path` equals the listed symbol
+//! or ends with `::<symbol>`. Each target is credited once, so several
+//! chunks from the same file count as one hit.
+//!
+//! For every configuration (the repository's own, named `"baseline"`, plus
+//! each `[[config]]`), [`run`] reports mean Recall@k, MRR, and nDCG@k with
+//! binary relevance.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::SearchConfig;
+use crate::error::{OmniError, OmniResult};
+use crate::pipeline::Engine;
+
+/// Name under which the repository's own search configuration is reported.
+pub const BASELINE: &str = "baseline";
+
+/// A golden query set loaded from `eval.toml`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct EvalSet {
+    /// Rank cutoff for Recall@k and nDCG@k.
+    #[serde(default = "EvalSet::default_k")]
+    pub k: usize,
+
+    /// Golden queries.
+    #[serde(default, rename = "query")]
+    pub queries: Vec<EvalCase>,
+
+    /// Search configurations to compare against the baseline.
+    #[serde(default, rename = "config")]
+    pub configs: Vec<EvalVariant>,
+}
+
+/// One golden query and the targets it should retrieve.
+#[derive(Debug, Clone, Deserialize)]
+pub struct EvalCase {
+    /// Query text, passed to [`Engine::search`] unchanged.
+    pub query: String,
+
+    /// Relevant files, relative to the repository root.
+    #[serde(default)]
+    pub files: Vec<PathBuf>,
+
+    /// Relevant symbols (fully qualified or a `::`-suffix of one).
+    #[serde(default)]
+    pub symbols: Vec<String>,
+}
+
+/// A named override of `[search]` settings.
+#[derive(Debug, Clone, Deserialize)]
+pub struct EvalVariant {
+    /// Label used in the report.
+    pub name: String,
+
+    /// Keys overlaid onto the baseline `[search]` table. Keys that are not
+    /// `[search]` settings are an error.
+    #[serde(default)]
+    pub search: toml::Table,
+}
+
+impl EvalSet {
+    fn default_k() -> usize {
+        10
+    }
+
+    /// Path of the golden set for `repo_path`.
+    pub fn default_path(repo_path: &Path) -> PathBuf {
+        repo_path.join(".omnicontext").join("eval.toml")
+    }
+
+    /// Load and validate a golden set.
+    pub fn load(path: &Path) -> OmniResult<Self> {
+        let content = std::fs::read_to_string(path)?;
+        Self::parse(&content).map_err(|e| match e {
+            OmniError::Config { details } => OmniError::Config {
+                details: format!("{}: {details}", path.display()),
+            },
+            other => other,
+        })
+    }
+
+    /// Parse and validate a golden set from TOML text.
+    pub fn parse(content: &str) -> OmniResult<Self> {
+        let set: Self = toml::from_str(content).map_err(|e| OmniError::Config {
+            details: format!("invalid eval set: {e}"),
+        })?;
+        if set.k == 0 {
+            return Err(OmniError::Config {
+                details: "k must be at least 1".into(),
+            });
+        }
+        if let Some(case) = set
+            .queries
+            .iter()
+            .find(|c| c.files.is_empty() && c.symbols.is_empty())
+        {
+            return Err(OmniError::Config {
+                details: format!("query {:?} lists no files or symbols", case.query),
+            });
+        }
+        Ok(set)
+    }
+}
+
+/// Scores for one query under one configuration.
+#[derive(Debug, Clone, Serialize)]
+pub struct QueryScores {
+    /// Query text.
+    pub query: String,
+    /// Fraction of targets retrieved in the top k.
+    pub recall: f64,
+    /// 1 / rank of the first hit, or 0 if nothing relevant was retrieved.
+    pub reciprocal_rank: f64,
+    /// Normalized discounted cumulative gain at k.
+    pub ndcg: f64,
+}
+
+/// Mean scores for one configuration.
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigScores {
+    /// Configuration name ([`BASELINE`] or an `[[config]]` name).
+    pub name: String,
+    /// Mean Recall@k.
+    pub recall: f64,
+    /// Mean reciprocal rank.
+    pub mrr: f64,
+    /// Mean nDCG@k.
+    pub ndcg: f64,
+    /// Per-query breakdown.
+    pub queries: Vec<QueryScores>,
+}
+
+/// Result of evaluating every configuration.
+#[derive(Debug, Clone, Serialize)]
+pub struct EvalReport {
+    /// Rank cutoff used.
+    pub k: usize,
+    /// One entry per configuration, baseline first.
+    pub configs: Vec<ConfigScores>,
+}
+
+/// Run every query under the baseline and each variant configuration.
+///
+/// The engine's search configuration is restored before returning, even
+/// when a variant fails to apply.
+pub fn run(engine: &mut Engine, set: &EvalSet) -> OmniResult<EvalReport> {
+    let baseline = engine.config().search.clone();
+    let mut configs = vec![evaluate(engine, set, BASELINE)];
+
+    let mut outcome = Ok(());
+    for variant in &set.configs {
+        let applied =
+            overlay(&baseline, &variant.search).and_then(|c| engine.apply_search_config(c));
+        if let Err(e) = applied {
+            outcome = Err(e);
+            break;
+        }
+        configs.push(evaluate(engine, set, &variant.name));
+    }
+    if !set.configs.is_empty() {
+        engine.apply_search_config(baseline)?;
+    }
+    outcome?;
+
+    Ok(EvalReport { k: set.k, configs })
+}
+
+fn evaluate(engine: &Engine, set: &EvalSet, name: &str) -> ConfigScores {
+    let queries: Vec<QueryScores> = set
+        .queries
+        .iter()
+        .map(|case| {
+            let results = engine.search(&case.query, set.k).unwrap_or_else(|e| {
+                tracing::warn!(query = %case.query, error = %e, "eval query failed");
+                Vec::new()
+            });
+            let ranked: Vec<(&Path, &str)> = results
+                .iter()
+                .map(|r| (r.file_path.as_path(), r.chunk.symbol_path.as_str()))
+                .collect();
+            score(case, &ranked, set.k)
+        })
+        .collect();
+
+    let n = queries.len().max(1) as f64;
+    ConfigScores {
+        name: name.to_string(),
+        recall: queries.iter().map(|q| q.recall).sum::<f64>() / n,
+        mrr: queries.iter().map(|q| q.reciprocal_rank).sum::<f64>() / n,
+        ndcg: queries.iter().map(|q| q.ndcg).sum::<f64>() / n,
+        queries,
+    }
+}
+
+/// Overlay `overrides` onto `base`, merging nested tables key by key.
+/// Keys that are not `[search]` settings are rejected rather than silently
+/// ignored, so a misspelt override cannot pass for the baseline.
+fn overlay(base: &SearchConfig, overrides: &toml::Table) -> OmniResult<SearchConfig> {
+    let invalid = |e: &dyn std::fmt::Display| OmniError::Config {
+        details: format!("invalid [[config]] search override: {e}"),
+    };
+    let mut merged = toml::Table::try_from(base).map_err(|e| invalid(&e))?;
+    merge(&mut merged, overrides);
+    let parsed: SearchConfig = toml::Value::Table(merged)
+        .try_into()
+        .map_err(|e| invalid(&e))?;
+
+    // Serde drops unknown keys, so any override key missing from the parsed
+    // config read back as a table is one it did not recognise.
+    let known = toml::Table::try_from(&parsed).map_err(|e| invalid(&e))?;
+    let mut unknown = Vec::new();
+    unknown_keys(overrides, &known, "search", &mut unknown);
+    if !unknown.is_empty() {
+        return Err(invalid(&format!("unknown keys {}", unknown.join(", "))));
+    }
+    Ok(parsed)
+}
+
+/// Dotted paths of the keys in `overrides` that `known` lacks.
+fn unknown_keys(overrides: &toml::Table, known: &toml::Table, prefix: &str, out: &mut Vec<String>) {
+    for (key, value) in overrides {
+        let path = format!("{prefix}.{key}");
+        match (known.get(key), value) {
+            (None, _) => out.push(path),
+            (Some(toml::Value::Table(known)), toml::Value::Table(value)) => {
+                unknown_keys(value, known, &path, out);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn merge(into: &mut toml::Table, from: &toml::Table) {
+    for (key, value) in from {
+        match (into.get_mut(key), value) {
+            (Some(toml::Value::Table(dst)), toml::Value::Table(src)) => merge(dst, src),
+            _ => {
+                into.insert(key.clone(), value.clone());
+            }
+        }
+    }
+}
+
+/// Score one ranked result list against a case's targets.
+fn score(case: &EvalCase, ranked: &[(&Path, &str)], k: usize) -> QueryScores {
+    let targets = case.files.len() + case.symbols.len();
+    let mut hit = vec![false; targets];
+    let mut dcg = 0.0;
+    let mut first_hit = None;
+
+    for (rank, (file, symbol)) in ranked.iter().take(k).enumerate() {
+        let matched = (0..targets).find(|&t| {
+            !hit[t]
+                && match case.files.get(t) {
+                    Some(target) => file.ends_with(target),
+                    None => symbol_matches(symbol, &case.symbols[t - case.files.len()]),
+                }
+        });
+        if let Some(t) = matched {
+            hit[t] = true;
+            dcg += 1.0 / (rank as f64 + 2.0).log2();
+            first_hit.get_or_insert(rank);
+        }
+    }
+
+    let ideal: f64 = (0..targets.min(k))
+        .map(|rank| 1.0 / (rank as f64 + 2.0).log2())
+        .sum();
+    let found = hit.iter().filter(|h| **h).count();
+
+    QueryScores {
+        query: case.query.clone(),
+        recall: if targets == 0 {
+            0.0
+        } else {
+            found as f64 / targets as f64
+        },
+        reciprocal_rank: first_hit.map_or(0.0, |r| 1.0 / (r as f64 + 1.0)),
+        ndcg: if ideal == 0.0 { 0.0 } else { dcg / ideal },
+    }
+}
+
+fn symbol_matches(symbol_path: &str, target: &str) -> bool {
+    symbol_path == target
+        || symbol_path
+            .strip_suffix(target)
+            .is_some_and(|prefix| prefix.ends_with("::"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn case(files: &[&str], symbols: &[&str]) -> EvalCase {
+        EvalCase {
+            query: "q".into(),
+            files: files.iter().map(PathBuf::from).collect(),
+            symbols: symbols.iter().map(|s| (*s).to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_score_perfect_and_empty() {
+        let c = case(&["src/a.rs"], &["b::run"]);
+        let perfect = score(
+            &c,
+            &[
+                (Path::new("/repo/src/a.rs"), "a::x"),
+                (Path::new("/repo/src/b.rs"), "crate::b::run"),
+            ],
+            10,
+        );
+        assert!((perfect.recall - 1.0).abs() < 1e-9);
+        assert!((perfect.reciprocal_rank - 1.0).abs() < 1e-9);
+        assert!((perfect.ndcg - 1.0).abs() < 1e-9);
+
+        let miss = score(&c, &[(Path::new("/repo/src/c.rs"), "c::y")], 10);
+        assert_eq!(miss.recall, 0.0);
+        assert_eq!(miss.reciprocal_rank, 0.0);
+        assert_eq!(miss.ndcg, 0.0);
+    }
+
+    #[test]
+    fn test_score_counts_each_target_once_and_respects_k() {
+        let c = case(&["src/a.rs"], &[]);
+        let ranked = [
+            (Path::new("/repo/src/z.rs"), "z"),
+            (Path::new("/repo/src/a.rs"), "a::one"),
+            (Path::new("/repo/src/a.rs"), "a::two"),
+        ];
+        let s = score(&c, &ranked, 10);
+        assert!((s.reciprocal_rank - 0.5).abs() < 1e-9);
+        assert!((s.ndcg - 1.0 / 3f64.log2()).abs() < 1e-9);
+
+        let cut = score(&c, &ranked, 1);
+        assert_eq!(cut.recall, 0.0);
+    }
+
+    #[test]
+    fn test_symbol_matching_requires_segment_boundary() {
+        assert!(symbol_matches("crate::db::Pool::acquire", "Pool::acquire"));
+        assert!(symbol_matches("Pool::acquire", "Pool::acquire"));
+        assert!(!symbol_matches(
+            "crate::db::MyPool::acquire",
+            "Pool::acquire"
+        ));
+    }
+
+    #[test]
+    fn test_parse_and_overlay_variant() {
+        let set = EvalSet::parse(
+            r#"
+            [[query]]
+            query = "pool"
+            files = ["src/pool.rs"]
+
+            [[config]]
+            name = "tight"
+            search = { rrf_k = 20, bm25 = { symbol_path = 4.0 } }
+            "#,
+        )
+        .expect("parse");
+        assert_eq!(set.k, 10);
+
+        let merged = overlay(&SearchConfig::default(), &set.configs[0].search).expect("overlay");
+        assert_eq!(merged.rrf_k, 20);
+        assert_eq!(merged.bm25.symbol_path, 4.0);
+        assert_eq!(merged.bm25.content, SearchConfig::default().bm25.content);
+
+        let mut typo = toml::Table::new();
+        typo.insert("rrf".into(), toml::Value::Integer(20));
+        let mut bm25 = toml::Table::new();
+        bm25.insert("symbols".into(), toml::Value::Float(4.0));
+        typo.insert("bm25".into(), toml::Value::Table(bm25));
+        let err = overlay(&SearchConfig::default(), &typo).expect_err("unknown keys");
+        assert!(
+            err.to_string().contains("search.bm25.symbols, search.rrf"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_case_without_targets() {
+        let err = EvalSet::parse("[[query]]\nquery = \"pool\"\n").expect_err("no targets");
+        assert!(err.to_string().contains("no files or symbols"));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_run_reports_each_config_and_restores_baseline() {
+        std::env::set_var("OMNI_SKIP_MODEL_DOWNLOAD", "1");
+        std::env::set_var("OMNI_DISABLE_RERANKER", "1");
+        let dir = tempfile::tempdir().expect("create temp dir");
+        std::fs::write(
+            dir.path().join("pool.py"),
+            "def acquire_connection():\n    return 1\n",
+        )
+        .expect("write pool.py");
+
+        let mut engine =
+            Engine::with_config(crate::Config::defaults(dir.path())).expect("create engine");
+        engine.run_index(false).await.expect("index");
+
+        let set = EvalSet::parse(
+            r#"
+            k = 5
+            [[query]]
+            query = "acquire_connection"
+            files = ["pool.py"]
+
+            [[config]]
+            name = "no-stemming"
+            search = { rrf_k = 10, stemming = false }
+            "#,
+        )
+        .expect("parse");
+        let report = run(&mut engine, &set).expect("run");
+
+        let names: Vec<&str> = report.configs.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec![BASELINE, "no-stemming"]);
+        assert!((report.configs[0].recall - 1.0).abs() < 1e-9);
+        assert_eq!(engine.config().search.rrf_k, SearchConfig::default().rrf_k);
+        assert!(engine.config().search.stemming);
+    }
+}
@@ -0,0 +1,66 @@
+//! Typed engine events for subscribers.
+//!
+//! The engine publishes what it does -- files indexed or failed, index runs
+//! completed, searches executed, subsystems degraded -- on a broadcast
+//! channel. Library consumers, the daemon, and the server subscribe with
+//! [`crate::pipeline::Engine::subscribe`] instead of scraping tracing logs.
+//!
+//! Events are only built when someone is subscribed. A subscriber that
+//! falls more than [`EVENT_CHANNEL_CAPACITY`] events behind skips the oldest
+//! ones and sees `RecvError::Lagged`.
+
+use std::path::PathBuf;
+
+use crate::pipeline::IndexResult;
+
+/// Events buffered per subscriber before the oldest are dropped.
+pub const EVENT_CHANNEL_CAPACITY: usize = 1024;
+
+/// Something the engine did.
+#[derive(Debug, Clone)]
+pub enum EngineEvent {
+    /// A file was parsed and stored.
+    FileIndexed {
+        /// Path relative to the repository root.
+        path: PathBuf,
+        /// Chunks stored for the file.
+        chunks: usize,
+        /// Symbols extracted from the file.
+        symbols: usize,
+        /// Time spent indexing the file in milliseconds. In a full index run
+        /// files are parsed in parallel, so this covers storage only.
+        elapsed_ms: u64,
+    },
+    /// A file could not be indexed; the previous index state is kept.
+    FileFailed {
+        /// Path relative to the repository root.
+        path: PathBuf,
+        /// Why indexing failed.
+        error: String,
+    },
+    /// An indexing run finished.
+    IndexCompleted {
+        /// Counts for the run.
+        result: IndexResult,
+        /// Wall-clock duration of the run in milliseconds.
+        elapsed_ms: u64,
+    },
+    /// A search ran (successfully or not).
+    SearchExecuted {
+        /// Query text as received.
+        query: String,
+        /// Number of results returned.
+        results: usize,
+        /// Wall-clock latency in milliseconds.
+        elapsed_ms: u64,
+        /// Error message if the search failed.
+        error: Option<String>,
+    },
+    /// A subsystem is running with reduced capability.
+    EngineDegraded {
+        /// Affected subsystem, e.g. `embedder` or `search`.
+        component: String,
+        /// What is wrong and what still works.
+        reason: String,
+    },
+}
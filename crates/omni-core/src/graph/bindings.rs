@@ -0,0 +1,728 @@
+//! Cross-language binding resolution.
+//!
+//! Polyglot repositories cross language boundaries through bindings that no
+//! single-language parser can follow: a Python call to a PyO3
+//! `#[pyfunction]`, a TypeScript call to a napi-rs `#[napi]` export, a gRPC
+//! client stub calling a method implemented by a servicer in another
+//! language. This pass links both sides with `Binds` edges, pointing from the
+//! calling symbol to the implementation the way `Calls` does.
+//!
+//! ## Exports
+//!
+//! | ABI    | Implementation side                                              | Exposed as                  |
+//! |--------|------------------------------------------------------------------|-----------------------------|
+//! | `pyo3` | Rust `#[pyfunction]`, `#[pyclass]`, methods in `#[pymethods]`    | same name, or `name = "…"`  |
+//! | `napi` | Rust `#[napi]` fns, structs, and methods in `#[napi] impl`       | camelCase, or `js_name`     |
+//! | `grpc` | Rust tonic `impl <svc>_server::Svc`, Python `SvcServicer` classes| `Svc/Method`                |
+//!
+//! Exports are found from the attributes and base types around each element
+//! and persisted in the index, so whichever side is indexed second creates
+//! the edge.
+//!
+//! ## Call sites
+//!
+//! Callers are found by scanning element bodies for `name(`. PyO3 exports are
+//! matched from Python, napi exports from TypeScript and JavaScript. gRPC
+//! methods are matched from any language, but only in files that mention the
+//! service's generated client (`GreeterStub`, `GreeterClient`); method names
+//! are compared case- and underscore-insensitively so `SayHello`,
+//! `say_hello`, and `sayHello` meet.
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use super::DependencyGraph;
+use crate::index::MetadataIndex;
+use crate::parser::StructuralElement;
+use crate::types::{ChunkKind, DependencyEdge, DependencyKind, Language};
+
+/// Names shorter than this are too generic to link across languages.
+const MIN_NAME_LEN: usize = 3;
+
+/// Maximum chunks scanned when backfilling callers of a new export.
+const CALLER_SCAN_LIMIT: usize = 500;
+
+/// Keywords that precede a definition rather than a call (`def add(`).
+const DEFINITION_KEYWORDS: &[&str] = &["def", "fn", "func", "function", "class", "async"];
+
+/// The mechanism exposing a symbol to another language.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BindingAbi {
+    /// Rust exposed to Python through PyO3.
+    Pyo3,
+    /// Rust exposed to Node.js through napi-rs.
+    Napi,
+    /// A gRPC service method, called through generated client stubs.
+    Grpc,
+}
+
+impl BindingAbi {
+    /// Database string.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Pyo3 => "pyo3",
+            Self::Napi => "napi",
+            Self::Grpc => "grpc",
+        }
+    }
+
+    /// Resolution confidence of a `Binds` edge through this ABI. Call sites
+    /// are matched by bare name, narrowed to the service for gRPC.
+    pub fn confidence(self) -> f32 {
+        match self {
+            Self::Pyo3 | Self::Napi => DependencyEdge::NAME_ONLY,
+            Self::Grpc => DependencyEdge::SUFFIX,
+        }
+    }
+
+    /// Whether code in `language` calls exports of this ABI.
+    pub fn is_consumed_by(self, language: Language) -> bool {
+        match self {
+            Self::Pyo3 => language == Language::Python,
+            Self::Napi => matches!(language, Language::TypeScript | Language::JavaScript),
+            Self::Grpc => language.is_code(),
+        }
+    }
+}
+
+/// A symbol exposed to another language.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BindingExport {
+    /// Symbol path of the implementing element.
+    pub symbol_path: String,
+    /// How it is exposed.
+    pub abi: BindingAbi,
+    /// Name callers use: the foreign-side name for `pyo3`/`napi`, a
+    /// [`grpc_key`] for `grpc`.
+    pub name: String,
+}
+
+/// Binding facts found in one file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileBindings {
+    /// Symbols this file exposes to other languages.
+    pub exports: Vec<BindingExport>,
+    /// gRPC services whose generated client this file uses.
+    pub grpc_clients: Vec<String>,
+}
+
+impl FileBindings {
+    /// Whether the file has nothing binding-related.
+    pub fn is_empty(&self) -> bool {
+        self.exports.is_empty() && self.grpc_clients.is_empty()
+    }
+}
+
+/// Lookup key for a gRPC method: `service/method`, lowercased without
+/// underscores so the casing conventions of each language meet.
+pub fn grpc_key(service: &str, method: &str) -> String {
+    let fold = |s: &str| -> String {
+        s.chars()
+            .filter(|c| *c != '_')
+            .map(|c| c.to_ascii_lowercase())
+            .collect()
+    };
+    format!("{}/{}", fold(service), fold(method))
+}
+
+/// Find the exports and gRPC client usages in a parsed file.
+pub fn scan_file(
+    language: Language,
+    content: &str,
+    elements: &[StructuralElement],
+) -> FileBindings {
+    let mut bindings = FileBindings::default();
+    match language {
+        Language::Rust => scan_rust(content, elements, &mut bindings.exports),
+        Language::Python => scan_python_servicers(elements, &mut bindings.exports),
+        _ => {}
+    }
+    if language.is_code() {
+        bindings.grpc_clients = grpc_clients(content);
+    }
+    bindings
+}
+
+/// Identifiers called in `content` (`name(`, `obj.name(`, `new Name(`),
+/// excluding definitions. Deduplicated, in first-seen order.
+pub fn called_names(content: &str) -> Vec<&str> {
+    let bytes = content.as_bytes();
+    let mut seen = HashSet::new();
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        if !is_ident_start(bytes[i]) || (i > 0 && is_ident_byte(bytes[i - 1])) {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < bytes.len() && is_ident_byte(bytes[i]) {
+            i += 1;
+        }
+        let name = &content[start..i];
+        let mut j = i;
+        // Skip generic arguments (`call::<T>(`, `call<T>(`) and whitespace.
+        while j < bytes.len() && bytes[j] == b' ' {
+            j += 1;
+        }
+        if content[j..].starts_with("::<") || bytes.get(j) == Some(&b'<') {
+            if let Some(close) = content[j..].find('>') {
+                j += close + 1;
+            }
+        }
+        if bytes.get(j) != Some(&b'(') || name.len() < MIN_NAME_LEN {
+            continue;
+        }
+        let before = content[..start].trim_end();
+        let is_definition = DEFINITION_KEYWORDS.iter().any(|kw| {
+            before.ends_with(kw)
+                && !before[..before.len() - kw.len()]
+                    .bytes()
+                    .next_back()
+                    .is_some_and(is_ident_byte)
+        });
+        if !is_definition && seen.insert(name) {
+            out.push(name);
+        }
+    }
+    out
+}
+
+/// Resolve `Binds` edges for one file, in both directions.
+///
+/// Persists the file's exports, links callers already in the index to them,
+/// and links the file's own call sites to exports already in the index.
+/// Edges are added to `graph` as they are found; the caller persists the
+/// returned edges.
+pub fn build_binding_edges(
+    graph: &DependencyGraph,
+    index: &MetadataIndex,
+    language: Language,
+    elements: &[StructuralElement],
+    bindings: &FileBindings,
+) -> Vec<DependencyEdge> {
+    let mut edges = Vec::new();
+    let mut push = |source_id: i64, target_id: i64, abi: BindingAbi| {
+        if source_id == target_id {
+            return;
+        }
+        let edge = DependencyEdge {
+            source_id,
+            target_id,
+            kind: DependencyKind::Binds,
+            confidence: abi.confidence(),
+        };
+        let _ = graph.add_edge(&edge);
+        edges.push(edge);
+    };
+
+    // Implementation side: record exports, then link existing callers.
+    for export in &bindings.exports {
+        let Ok(Some(symbol)) = index.get_symbol_by_fqn(&export.symbol_path) else {
+            continue;
+        };
+        if let Err(e) = index.insert_binding_export(symbol.id, export.abi.as_str(), &export.name) {
+            tracing::trace!(error = %e, "failed to record binding export");
+            continue;
+        }
+        for caller in existing_callers(index, export) {
+            push(caller, symbol.id, export.abi);
+        }
+    }
+
+    // Caller side: match called names against recorded exports.
+    let abis: Vec<BindingAbi> = [BindingAbi::Pyo3, BindingAbi::Napi]
+        .into_iter()
+        .filter(|abi| abi.is_consumed_by(language))
+        .collect();
+    if abis.is_empty() && bindings.grpc_clients.is_empty() {
+        return edges;
+    }
+    for elem in elements {
+        if elem.symbol_path.is_empty() || matches!(elem.kind, ChunkKind::Impl | ChunkKind::Module) {
+            continue;
+        }
+        let names = called_names(&elem.content);
+        if names.is_empty() {
+            continue;
+        }
+        let Ok(Some(source)) = index.get_symbol_by_fqn(&elem.symbol_path) else {
+            continue;
+        };
+        for name in names {
+            let mut keys: Vec<(BindingAbi, String)> =
+                abis.iter().map(|abi| (*abi, name.to_string())).collect();
+            keys.extend(
+                bindings
+                    .grpc_clients
+                    .iter()
+                    .map(|svc| (BindingAbi::Grpc, grpc_key(svc, name))),
+            );
+            for (abi, key) in keys {
+                for target in index
+                    .binding_export_targets(abi.as_str(), &key)
+                    .unwrap_or_default()
+                {
+                    push(source.id, target, abi);
+                }
+            }
+        }
+    }
+    edges
+}
+
+/// Symbols already in the index that call `export`.
+fn existing_callers(index: &MetadataIndex, export: &BindingExport) -> Vec<i64> {
+    // gRPC method names vary in case and underscores between languages, so
+    // find files using the service's client first and scan their chunks.
+    let (needle, service) = match export.abi {
+        BindingAbi::Grpc => match export.name.split_once('/') {
+            Some((svc, _)) => (svc, Some(svc)),
+            None => return Vec::new(),
+        },
+        _ => (export.name.as_str(), None),
+    };
+    let found = match index.find_chunks_containing(needle, service.is_none(), CALLER_SCAN_LIMIT) {
+        Ok(found) => found,
+        Err(e) => {
+            tracing::trace!(error = %e, "binding caller lookup failed");
+            return Vec::new();
+        }
+    };
+
+    let mut languages: HashMap<i64, Option<Language>> = HashMap::new();
+    let mut candidates = Vec::new();
+    for (chunk, _) in found {
+        let language = *languages.entry(chunk.file_id).or_insert_with(|| {
+            index
+                .get_file_by_id(chunk.file_id)
+                .ok()
+                .flatten()
+                .map(|f| f.language)
+        });
+        if !language.is_some_and(|l| export.abi.is_consumed_by(l)) {
+            continue;
+        }
+        match service {
+            None => candidates.push(chunk),
+            Some(svc) => {
+                let uses_client = grpc_clients(&chunk.content)
+                    .iter()
+                    .any(|client| grpc_key(client, "") == grpc_key(svc, ""));
+                if uses_client
+                    && !candidates
+                        .iter()
+                        .any(|c: &crate::types::Chunk| c.file_id == chunk.file_id)
+                {
+                    candidates.extend(index.get_chunks_for_file(chunk.file_id).unwrap_or_default());
+                }
+            }
+        }
+    }
+
+    let mut callers = BTreeSet::new();
+    for chunk in candidates {
+        let calls = called_names(&chunk.content);
+        let matched = match service {
+            None => calls.contains(&needle),
+            Some(svc) => calls.iter().any(|m| grpc_key(svc, m) == export.name),
+        };
+        if !matched {
+            continue;
+        }
+        if let Ok(Some(symbol)) = index.get_symbol_by_fqn(&chunk.symbol_path) {
+            callers.insert(symbol.id);
+        }
+    }
+    callers.into_iter().collect()
+}
+
+/// Rust: PyO3 and napi-rs attributes, and tonic server trait impls.
+fn scan_rust(content: &str, elements: &[StructuralElement], out: &mut Vec<BindingExport>) {
+    let lines: Vec<&str> = content.lines().collect();
+
+    // Impl blocks that expose their methods: (line range, abi or grpc service).
+    enum Exposing {
+        Abi(BindingAbi),
+        Grpc(String),
+    }
+    let mut blocks: Vec<(u32, u32, Exposing)> = Vec::new();
+    for elem in elements.iter().filter(|e| e.kind == ChunkKind::Impl) {
+        let attrs = attributes_above(&lines, elem.line_start);
+        if has_attr(&attrs, "pymethods") {
+            blocks.push((
+                elem.line_start,
+                elem.line_end,
+                Exposing::Abi(BindingAbi::Pyo3),
+            ));
+        } else if has_attr(&attrs, "napi") {
+            blocks.push((
+                elem.line_start,
+                elem.line_end,
+                Exposing::Abi(BindingAbi::Napi),
+            ));
+        } else if let Some(service) = elem
+            .implements
+            .iter()
+            .find(|t| t.contains("_server::"))
+            .and_then(|t| t.rsplit("::").next())
+        {
+            blocks.push((
+                elem.line_start,
+                elem.line_end,
+                Exposing::Grpc(service.to_string()),
+            ));
+        }
+    }
+
+    for elem in elements {
+        let attrs = attributes_above(&lines, elem.line_start);
+        match elem.kind {
+            ChunkKind::Function => {
+                let block = blocks
+                    .iter()
+                    .find(|(start, end, _)| elem.line_start > *start && elem.line_end <= *end);
+                let abi = match block {
+                    Some((_, _, Exposing::Grpc(service))) => {
+                        out.push(BindingExport {
+                            symbol_path: elem.symbol_path.clone(),
+                            abi: BindingAbi::Grpc,
+                            name: grpc_key(service, &elem.name),
+                        });
+                        continue;
+                    }
+                    Some((_, _, Exposing::Abi(abi))) => Some(*abi),
+                    None if has_attr(&attrs, "pyfunction") => Some(BindingAbi::Pyo3),
+                    None if has_attr(&attrs, "napi") => Some(BindingAbi::Napi),
+                    None => None,
+                };
+                // Constructors are reached through the class name.
+                let is_constructor = has_attr(&attrs, "new") || attr_arg(&attrs, "constructor");
+                if let Some(abi) = abi.filter(|_| !is_constructor && !elem.name.starts_with("__")) {
+                    out.push(BindingExport {
+                        symbol_path: elem.symbol_path.clone(),
+                        abi,
+                        name: foreign_name(abi, &attrs, &elem.name),
+                    });
+                }
+            }
+            ChunkKind::Class => {
+                let abi = if has_attr(&attrs, "pyclass") {
+                    BindingAbi::Pyo3
+                } else if has_attr(&attrs, "napi") {
+                    BindingAbi::Napi
+                } else {
+                    continue;
+                };
+                // Classes keep their casing under napi-rs.
+                let name = renamed(&attrs, abi).unwrap_or_else(|| elem.name.clone());
+                out.push(BindingExport {
+                    symbol_path: elem.symbol_path.clone(),
+                    abi,
+                    name,
+                });
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Python: methods of `class Impl(pb2_grpc.GreeterServicer)`.
+fn scan_python_servicers(elements: &[StructuralElement], out: &mut Vec<BindingExport>) {
+    for class in elements.iter().filter(|e| e.kind == ChunkKind::Class) {
+        let Some(service) = class.extends.iter().find_map(|base| {
+            base.rsplit('.')
+                .next()
+                .and_then(|b| b.strip_suffix("Servicer"))
+                .filter(|s| !s.is_empty())
+        }) else {
+            continue;
+        };
+        for method in elements.iter().filter(|e| {
+            e.kind == ChunkKind::Function
+                && e.line_start > class.line_start
+                && e.line_end <= class.line_end
+                && !e.name.starts_with('_')
+        }) {
+            out.push(BindingExport {
+                symbol_path: method.symbol_path.clone(),
+                abi: BindingAbi::Grpc,
+                name: grpc_key(service, &method.name),
+            });
+        }
+    }
+}
+
+/// gRPC services whose generated client `content` references:
+/// `GreeterStub` (Python), `GreeterClient` (Go, TS, Rust, C#, ...).
+fn grpc_clients(content: &str) -> Vec<String> {
+    let mut out = BTreeSet::new();
+    for word in content.split(|c: char| !c.is_ascii_alphanumeric() && c != '_') {
+        let word = word.strip_prefix("New").unwrap_or(word);
+        let service = word
+            .strip_suffix("Stub")
+            .or_else(|| word.strip_suffix("Client"));
+        if let Some(service) = service
+            .filter(|s| s.len() >= MIN_NAME_LEN && s.starts_with(|c: char| c.is_ascii_uppercase()))
+        {
+            out.insert(service.to_string());
+        }
+    }
+    out.into_iter().collect()
+}
+
+/// Attribute lines directly above a 1-based `line`, skipping comments.
+fn attributes_above<'a>(lines: &[&'a str], line: u32) -> Vec<&'a str> {
+    let mut attrs = Vec::new();
+    let mut i = (line as usize).saturating_sub(1);
+    while i > 0 {
+        i -= 1;
+        let text = lines.get(i).map_or("", |l| l.trim());
+        if text.starts_with("#[") {
+            attrs.push(text);
+        } else if !text.starts_with("//") {
+            break;
+        }
+    }
+    attrs
+}
+
+/// Whether an attribute named `name` (`#[name]`, `#[name(...)]`,
+/// `#[pyo3::name]`) is present.
+fn has_attr(attrs: &[&str], name: &str) -> bool {
+    attrs.iter().any(|a| {
+        let inner = a.trim_start_matches("#[").trim_end_matches(']');
+        let path = inner.split('(').next().unwrap_or("").trim();
+        path == name || path.rsplit("::").next() == Some(name)
+    })
+}
+
+/// Whether any attribute carries a bare `arg` (`#[napi(constructor)]`).
+fn attr_arg(attrs: &[&str], arg: &str) -> bool {
+    attrs.iter().any(|a| {
+        a.split_once('(').is_some_and(|(_, args)| {
+            args.trim_end_matches(")]")
+                .split(',')
+                .any(|part| part.trim() == arg)
+        })
+    })
+}
+
+/// Explicit foreign name: `#[pyo3(name = "x")]`, `#[pyclass(name = "X")]`,
+/// `#[napi(js_name = "x")]`.
+fn renamed(attrs: &[&str], abi: BindingAbi) -> Option<String> {
+    let key = match abi {
+        BindingAbi::Pyo3 => "name",
+        BindingAbi::Napi => "js_name",
+        BindingAbi::Grpc => return None,
+    };
+    attrs.iter().find_map(|a| {
+        let (_, args) = a.split_once('(')?;
+        args.split(',').find_map(|part| {
+            let (k, v) = part.split_once('=')?;
+            (k.trim() == key).then(|| {
+                v.trim()
+                    .trim_end_matches(")]")
+                    .trim_end_matches(')')
+                    .trim()
+                    .trim_matches('"')
+                    .to_string()
+            })
+        })
+    })
+}
+
+/// Name a function is called by from the other language.
+fn foreign_name(abi: BindingAbi, attrs: &[&str], name: &str) -> String {
+    if let Some(renamed) = renamed(attrs, abi) {
+        return renamed;
+    }
+    match abi {
+        BindingAbi::Napi => snake_to_camel(name),
+        _ => name.to_string(),
+    }
+}
+
+/// `add_numbers` -> `addNumbers`, as napi-rs exports functions by default.
+fn snake_to_camel(name: &str) -> String {
+    let mut out = String::with_capacity(name.len());
+    let mut upper = false;
+    for c in name.chars() {
+        if c == '_' && !out.is_empty() {
+            upper = true;
+        } else if upper {
+            out.push(c.to_ascii_uppercase());
+            upper = false;
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+fn is_ident_start(b: u8) -> bool {
+    b.is_ascii_alphabetic() || b == b'_' || b == b'$'
+}
+
+fn is_ident_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_' || b == b'$'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{ComplexityMetrics, Visibility};
+
+    fn element(path: &str, kind: ChunkKind, lines: (u32, u32), content: &str) -> StructuralElement {
+        StructuralElement {
+            symbol_path: path.to_string(),
+            name: path.rsplit(['.', ':']).next().unwrap_or(path).to_string(),
+            kind,
+            visibility: Visibility::Public,
+            line_start: lines.0,
+            line_end: lines.1,
+            content: content.to_string(),
+            doc_comment: None,
+            references: Vec::new(),
+            extends: Vec::new(),
+            implements: Vec::new(),
+            complexity: ComplexityMetrics::default(),
+        }
+    }
+
+    fn exported(bindings: &FileBindings) -> Vec<(&str, &str)> {
+        bindings
+            .exports
+            .iter()
+            .map(|e| (e.abi.as_str(), e.name.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_scan_rust_pyo3_and_napi_exports() {
+        let source = "\
+/// Adds.
+#[pyfunction]
+fn add_numbers(a: i64, b: i64) -> i64 { a + b }
+
+#[pyfunction]
+#[pyo3(name = \"mul\")]
+fn multiply(a: i64, b: i64) -> i64 { a * b }
+
+#[napi]
+pub fn parse_config(s: String) -> String { s }
+
+#[pyclass]
+struct Counter { n: u64 }
+
+#[pymethods]
+impl Counter {
+    #[new]
+    fn new() -> Self { Self { n: 0 } }
+    fn bump(&mut self) { self.n += 1 }
+}
+
+fn private_helper() {}
+";
+        let elements = vec![
+            element("lib::add_numbers", ChunkKind::Function, (3, 3), ""),
+            element("lib::multiply", ChunkKind::Function, (7, 7), ""),
+            element("lib::parse_config", ChunkKind::Function, (10, 10), ""),
+            element("lib::Counter", ChunkKind::Class, (13, 13), ""),
+            element("lib::impl Counter::new", ChunkKind::Function, (18, 18), ""),
+            element("lib::impl Counter::bump", ChunkKind::Function, (19, 19), ""),
+            element("lib::impl Counter", ChunkKind::Impl, (16, 20), ""),
+            element("lib::private_helper", ChunkKind::Function, (22, 22), ""),
+        ];
+        let bindings = scan_file(Language::Rust, source, &elements);
+        assert_eq!(
+            exported(&bindings),
+            [
+                ("pyo3", "add_numbers"),
+                ("pyo3", "mul"),
+                ("napi", "parseConfig"),
+                ("pyo3", "Counter"),
+                ("pyo3", "bump"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_scan_grpc_servers_and_clients() {
+        let mut rust_impl = element(
+            "srv::impl Greeter for MyGreeter",
+            ChunkKind::Impl,
+            (1, 5),
+            "",
+        );
+        rust_impl.implements = vec!["greeter_server::Greeter".into()];
+        let rust = scan_file(
+            Language::Rust,
+            "",
+            &[
+                rust_impl,
+                element(
+                    "srv::impl Greeter for MyGreeter::say_hello",
+                    ChunkKind::Function,
+                    (2, 4),
+                    "",
+                ),
+            ],
+        );
+        assert_eq!(exported(&rust), [("grpc", "greeter/sayhello")]);
+
+        let mut servicer = element("server.Greeter", ChunkKind::Class, (1, 6), "");
+        servicer.extends = vec!["helloworld_pb2_grpc.GreeterServicer".into()];
+        let python = scan_file(
+            Language::Python,
+            "",
+            &[
+                servicer,
+                element("server.Greeter.SayHello", ChunkKind::Function, (2, 3), ""),
+                element("server.Greeter._log", ChunkKind::Function, (4, 5), ""),
+            ],
+        );
+        assert_eq!(exported(&python), [("grpc", "greeter/sayhello")]);
+
+        let go = "c := pb.NewGreeterClient(conn)\nr, err := c.SayHello(ctx, req)";
+        assert_eq!(grpc_clients(go), ["Greeter"]);
+        assert_eq!(
+            grpc_clients("stub = helloworld_pb2_grpc.GreeterStub(channel)"),
+            ["Greeter"]
+        );
+        assert!(grpc_clients("let client = reqwest::Client::new();").is_empty());
+    }
+
+    #[test]
+    fn test_called_names_skips_definitions() {
+        let names = called_names(
+            "def add_numbers(a, b):\n    total = native.add_numbers(a, b)\n    return Counter().bump()",
+        );
+        assert_eq!(names, ["add_numbers", "Counter", "bump"]);
+        assert_eq!(
+            called_names("export function run() { return parseConfig<string>(x); }"),
+            ["parseConfig"]
+        );
+        assert!(called_names("fn main() {}").is_empty());
+    }
+
+    #[test]
+    fn test_attribute_helpers() {
+        let attrs = [
+            "#[napi(js_name = \"fromJson\", constructor)]",
+            "#[pyo3::pyfunction]",
+        ];
+        assert!(has_attr(&attrs, "napi"));
+        assert!(has_attr(&attrs, "pyfunction"));
+        assert!(!has_attr(&attrs, "pyclass"));
+        assert!(attr_arg(&attrs, "constructor"));
+        assert_eq!(
+            renamed(&attrs, BindingAbi::Napi).as_deref(),
+            Some("fromJson")
+        );
+        assert_eq!(snake_to_camel("load_all_files"), "loadAllFiles");
+        assert_eq!(
+            grpc_key("Greeter", "say_hello"),
+            grpc_key("greeter", "SayHello")
+        );
+    }
+}
@@ -126,7 +126,7 @@ fn aggregate_communities(
         .collect();
 
     // Sort by size (largest first)
-    communities.sort_by(|a, b| b.members.len().cmp(&a.members.len()));
+    communities.sort_by_key(|c| std::cmp::Reverse(c.members.len()));
 
     // Reassign IDs after sorting
     for (idx, comm) in communities.iter_mut().enumerate() {
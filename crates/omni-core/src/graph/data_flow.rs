@@ -364,6 +364,7 @@ impl DataFlowExtractor {
                 source_id: f.source_id,
                 target_id: f.target_id,
                 kind: DependencyKind::DataFlow,
+                confidence: f.confidence as f32,
             })
             .collect()
     }
@@ -0,0 +1,177 @@
+//! Dead code candidates from dependency graph analysis.
+//!
+//! A public symbol with no incoming edges is not imported, called, or used
+//! anywhere else in the indexed code. Some of those are legitimately
+//! unreferenced -- entry points, tests, trait methods invoked implicitly,
+//! and items re-exported from a crate or package root -- so those are
+//! filtered out by heuristics before reporting.
+//!
+//! Like the coverage detector, this is a static approximation: dynamic
+//! dispatch, reflection, and callers outside the repository are invisible.
+
+#![allow(clippy::must_use_candidate)]
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use super::coverage::is_test_file;
+use crate::types::{ChunkKind, Symbol};
+
+/// An unreferenced public symbol.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct UnreferencedSymbol {
+    /// Symbol ID in the index.
+    pub symbol_id: i64,
+    /// Fully qualified name.
+    pub fqn: String,
+    /// What kind of symbol this is.
+    pub kind: ChunkKind,
+    /// Line number of definition.
+    pub line: u32,
+}
+
+/// Unreferenced symbols of one file.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct UnreferencedFile {
+    /// File path (relative to the repo root).
+    pub file_path: String,
+    /// Candidates ordered by line.
+    pub symbols: Vec<UnreferencedSymbol>,
+}
+
+/// Names that are called by a runtime, framework, or trait machinery rather
+/// than by name from other code.
+const IMPLICIT_ENTRY_POINTS: &[&str] = &[
+    "main", "new", "default", "drop", "fmt", "from", "into", "try_from", "from_str", "clone", "eq",
+    "cmp", "partial_cmp", "hash", "deref", "deref_mut", "next", "serialize", "deserialize",
+    "setup", "teardown", "constructor", "init",
+];
+
+/// File names whose items form a package's exported API.
+const EXPORT_FILES: &[&str] = &[
+    "lib.rs", "main.rs", "mod.rs", "build.rs", "__init__.py", "__main__.py", "index.js",
+    "index.ts", "index.jsx", "index.tsx", "index.mjs",
+];
+
+/// Whether `symbol` in `file_path` is unreferenced by design.
+pub fn is_excluded(symbol: &Symbol, file_path: &str) -> bool {
+    if matches!(
+        symbol.kind,
+        ChunkKind::Test
+            | ChunkKind::Impl
+            | ChunkKind::Module
+            | ChunkKind::TopLevel
+            | ChunkKind::Summary
+    ) {
+        return true;
+    }
+    let name = symbol.name.as_str();
+    if IMPLICIT_ENTRY_POINTS.contains(&name)
+        || name.starts_with("test_")
+        || (name.starts_with("__") && name.ends_with("__"))
+    {
+        return true;
+    }
+    if is_test_file(file_path) {
+        return true;
+    }
+    Path::new(file_path)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .is_some_and(|n| EXPORT_FILES.contains(&n))
+}
+
+/// Group the public symbols that nothing references by file.
+///
+/// `symbols` pairs each public symbol with its file path; `is_referenced`
+/// reports whether a symbol has at least one incoming dependency edge.
+/// Files are returned in path order.
+pub fn find_unreferenced(
+    symbols: &[(Symbol, String)],
+    is_referenced: impl Fn(i64) -> bool,
+) -> Vec<UnreferencedFile> {
+    let mut by_file: BTreeMap<&str, Vec<UnreferencedSymbol>> = BTreeMap::new();
+    for (symbol, path) in symbols {
+        if is_excluded(symbol, path) || is_referenced(symbol.id) {
+            continue;
+        }
+        by_file
+            .entry(path.as_str())
+            .or_default()
+            .push(UnreferencedSymbol {
+                symbol_id: symbol.id,
+                fqn: symbol.fqn.clone(),
+                kind: symbol.kind,
+                line: symbol.line,
+            });
+    }
+
+    by_file
+        .into_iter()
+        .map(|(path, mut symbols)| {
+            symbols.sort_by_key(|s| s.line);
+            UnreferencedFile {
+                file_path: path.to_string(),
+                symbols,
+            }
+        })
+        .collect()
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sym(id: i64, name: &str, kind: ChunkKind, line: u32, path: &str) -> (Symbol, String) {
+        (
+            Symbol {
+                id,
+                name: name.to_string(),
+                fqn: format!("app::{name}"),
+                kind,
+                file_id: 1,
+                line,
+                chunk_id: None,
+            },
+            path.to_string(),
+        )
+    }
+
+    #[test]
+    fn test_exclusion_heuristics() {
+        let (main, _) = sym(1, "main", ChunkKind::Function, 1, "src/app.rs");
+        assert!(is_excluded(&main, "src/app.rs"));
+        let (dunder, _) = sym(2, "__call__", ChunkKind::Function, 1, "app.py");
+        assert!(is_excluded(&dunder, "app.py"));
+        let (helper, _) = sym(3, "helper", ChunkKind::Function, 1, "src/app.rs");
+        assert!(is_excluded(&helper, "src/lib.rs"));
+        assert!(is_excluded(&helper, "pkg/__init__.py"));
+        assert!(is_excluded(&helper, "tests/helpers.rs"));
+        assert!(!is_excluded(&helper, "src/app.rs"));
+        let (test, _) = sym(4, "works", ChunkKind::Test, 1, "src/app.rs");
+        assert!(is_excluded(&test, "src/app.rs"));
+    }
+
+    #[test]
+    fn test_find_unreferenced_groups_by_file() {
+        let symbols = vec![
+            sym(1, "late", ChunkKind::Function, 40, "src/b.rs"),
+            sym(2, "used", ChunkKind::Function, 1, "src/b.rs"),
+            sym(3, "early", ChunkKind::Class, 3, "src/b.rs"),
+            sym(4, "orphan", ChunkKind::Const, 7, "src/a.rs"),
+            sym(5, "main", ChunkKind::Function, 1, "src/a.rs"),
+        ];
+        let report = find_unreferenced(&symbols, |id| id == 2);
+
+        assert_eq!(report.len(), 2);
+        assert_eq!(report[0].file_path, "src/a.rs");
+        assert_eq!(report[0].symbols.len(), 1);
+        assert_eq!(report[0].symbols[0].fqn, "app::orphan");
+        let lines: Vec<u32> = report[1].symbols.iter().map(|s| s.line).collect();
+        assert_eq!(lines, vec![3, 40]);
+    }
+}
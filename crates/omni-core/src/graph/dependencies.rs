@@ -385,6 +385,49 @@ impl FileDependencyGraph {
             .unwrap_or(0)
     }
 
+    /// Approximate heap bytes held by file nodes and both adjacency lists.
+    ///
+    /// Paths are stored per node and again per edge endpoint, so this walks
+    /// the graph; call it for diagnostics, not on hot paths.
+    pub fn memory_usage_bytes(&self) -> usize {
+        const ENTRY_OVERHEAD: usize = 8;
+        let path_bytes = |p: &PathBuf| std::mem::size_of::<PathBuf>() + p.as_os_str().len();
+        self.inner
+            .read()
+            .map(|inner| {
+                let nodes: usize = inner
+                    .nodes
+                    .iter()
+                    .map(|(path, node)| {
+                        path_bytes(path)
+                            + std::mem::size_of::<FileNode>()
+                            + node.path.as_os_str().len()
+                            + node.language.len()
+                            + ENTRY_OVERHEAD
+                    })
+                    .sum();
+                let edges: usize = inner
+                    .outgoing
+                    .iter()
+                    .chain(&inner.incoming)
+                    .map(|(path, targets)| {
+                        path_bytes(path)
+                            + std::mem::size_of::<Vec<(PathBuf, EdgeType, f32)>>()
+                            + targets
+                                .iter()
+                                .map(|(target, _, _)| {
+                                    std::mem::size_of::<(PathBuf, EdgeType, f32)>()
+                                        + target.as_os_str().len()
+                                })
+                                .sum::<usize>()
+                            + ENTRY_OVERHEAD
+                    })
+                    .sum();
+                nodes + edges
+            })
+            .unwrap_or(0)
+    }
+
     /// Clear the entire graph.
     pub fn clear(&self) {
         if let Ok(mut inner) = self.inner.write() {
@@ -72,6 +72,21 @@ impl SymbolIndex {
         Self { symbol_to_files }
     }
 
+    /// Approximate heap bytes held by the name → files map.
+    pub fn memory_usage_bytes(&self) -> usize {
+        self.symbol_to_files
+            .iter()
+            .map(|(name, files)| {
+                std::mem::size_of::<(String, Vec<PathBuf>)>()
+                    + name.len()
+                    + files
+                        .iter()
+                        .map(|p| std::mem::size_of::<PathBuf>() + p.as_os_str().len())
+                        .sum::<usize>()
+            })
+            .sum()
+    }
+
     /// Resolve a callee short name to the most likely defining source file.
     ///
     /// Disambiguation order:
@@ -304,7 +319,7 @@ impl EdgeExtractor {
     /// Check if a reference is a class instantiation.
     fn is_instantiation(&self, reference: &str, language: Language) -> bool {
         match language {
-            Language::JavaScript | Language::TypeScript => {
+            Language::JavaScript | Language::TypeScript | Language::Vue | Language::Svelte => {
                 reference.starts_with("new ") || reference.contains("new ")
             }
             Language::Python => {
@@ -378,7 +393,7 @@ impl ImportResolver {
         match language {
             Language::Rust => self.resolve_rust_import(source_file, import_path),
             Language::Python => self.resolve_python_import(source_file, import_path),
-            Language::JavaScript | Language::TypeScript => {
+            Language::JavaScript | Language::TypeScript | Language::Vue | Language::Svelte => {
                 self.resolve_js_import(source_file, import_path)
             }
             Language::Go => self.resolve_go_import(source_file, import_path),
@@ -548,7 +563,7 @@ impl ImportResolver {
         let target = parent.join(import_path);
 
         // Try with common extensions
-        for ext in &["", ".ts", ".tsx", ".js", ".jsx"] {
+        for ext in &["", ".ts", ".tsx", ".js", ".jsx", ".vue", ".svelte"] {
             let candidate = if ext.is_empty() {
                 target.clone()
             } else {
@@ -686,6 +701,7 @@ mod tests {
             references: vec![],
             extends: vec![],
             implements: vec![],
+            complexity: crate::types::ComplexityMetrics::default(),
         }
     }
 
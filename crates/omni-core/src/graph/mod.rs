@@ -20,20 +20,26 @@
 )]
 
 pub mod attention;
+pub mod bindings;
 pub mod community;
 pub mod coverage;
 pub mod data_flow;
+pub mod dead_code;
 pub mod dependencies;
 pub mod edge_extractor;
 pub mod historical;
+pub mod modules;
+pub mod packages;
 pub mod queries;
 pub mod reasoning;
+pub mod test_linkage;
 
 use crate::error::OmniResult;
 use crate::types::{DependencyEdge, DependencyKind};
 
 use petgraph::algo::is_cyclic_directed;
 use petgraph::graph::{DiGraph, NodeIndex};
+use petgraph::visit::EdgeRef;
 use petgraph::Direction;
 use std::collections::HashMap;
 use std::sync::RwLock;
@@ -45,10 +51,17 @@ pub struct DependencyGraph {
 }
 
 struct GraphInner {
-    graph: DiGraph<i64, DependencyKind>,
+    graph: DiGraph<i64, GraphEdge>,
     symbol_to_node: HashMap<i64, NodeIndex>,
 }
 
+/// Edge weight: the dependency kind and its resolution confidence.
+#[derive(Debug, Clone, Copy)]
+struct GraphEdge {
+    kind: DependencyKind,
+    confidence: f32,
+}
+
 impl DependencyGraph {
     /// Create a new empty dependency graph.
     pub fn new() -> Self {
@@ -96,7 +109,14 @@ impl DependencyGraph {
         let source = inner.symbol_to_node[&edge.source_id];
         let target = inner.symbol_to_node[&edge.target_id];
 
-        inner.graph.add_edge(source, target, edge.kind);
+        inner.graph.add_edge(
+            source,
+            target,
+            GraphEdge {
+                kind: edge.kind,
+                confidence: edge.confidence,
+            },
+        );
         Ok(())
     }
 
@@ -211,6 +231,22 @@ impl DependencyGraph {
         self.inner.read().map(|i| i.graph.edge_count()).unwrap_or(0)
     }
 
+    /// Approximate heap bytes held by nodes, edges, and the symbol lookup.
+    pub fn memory_usage_bytes(&self) -> usize {
+        self.inner
+            .read()
+            .map(|inner| {
+                inner.graph.node_count()
+                    * std::mem::size_of::<petgraph::graph::Node<i64>>()
+                    + inner.graph.edge_count()
+                        * std::mem::size_of::<petgraph::graph::Edge<GraphEdge>>()
+                    // key + value + hash table control bytes and padding
+                    + inner.symbol_to_node.capacity()
+                        * (std::mem::size_of::<(i64, NodeIndex)>() + 8)
+            })
+            .unwrap_or(0)
+    }
+
     /// Clear the entire graph.
     pub fn clear(&self) {
         if let Ok(mut inner) = self.inner.write() {
@@ -285,6 +321,47 @@ impl DependencyGraph {
         Ok(results)
     }
 
+    /// Find the tests to run after changing a symbol.
+    ///
+    /// Walks the same incoming edges as [`Self::blast_radius`] (skipping
+    /// `TestedBy` edges themselves) and collects every test linked by a
+    /// `TestedBy` edge to the symbol or anything it affects. Each entry is
+    /// `(test_symbol_id, distance)`, where distance is the hop count from the
+    /// changed symbol to the tested symbol (0 = tests the symbol directly).
+    pub fn tests_for(&self, symbol_id: i64, max_depth: usize) -> OmniResult<Vec<(i64, usize)>> {
+        let inner = self
+            .inner
+            .read()
+            .map_err(|e| crate::error::OmniError::Internal(format!("graph lock poisoned: {e}")))?;
+
+        let Some(&node) = inner.symbol_to_node.get(&symbol_id) else {
+            return Ok(Vec::new());
+        };
+
+        use std::collections::VecDeque;
+        let mut visited: HashMap<NodeIndex, usize> = HashMap::new();
+        let mut tests: HashMap<i64, usize> = HashMap::new();
+        let mut queue = VecDeque::new();
+        visited.insert(node, 0);
+        queue.push_back((node, 0usize));
+
+        while let Some((current, dist)) = queue.pop_front() {
+            for edge in inner.graph.edges_directed(current, Direction::Incoming) {
+                let neighbor = edge.source();
+                if edge.weight().kind == DependencyKind::TestedBy {
+                    tests.entry(inner.graph[neighbor]).or_insert(dist);
+                } else if dist < max_depth && !visited.contains_key(&neighbor) {
+                    visited.insert(neighbor, dist + 1);
+                    queue.push_back((neighbor, dist + 1));
+                }
+            }
+        }
+
+        let mut results: Vec<(i64, usize)> = tests.into_iter().collect();
+        results.sort_unstable_by_key(|&(id, d)| (d, id));
+        Ok(results)
+    }
+
     /// Get all typed edges for a specific symbol.
     ///
     /// Returns `(target_symbol_id, edge_kind, direction_label)` tuples.
@@ -294,6 +371,19 @@ impl DependencyGraph {
         &self,
         symbol_id: i64,
     ) -> OmniResult<Vec<(i64, DependencyKind, &'static str)>> {
+        Ok(self
+            .get_weighted_edges_for_symbol(symbol_id)?
+            .into_iter()
+            .map(|(id, kind, _, direction)| (id, kind, direction))
+            .collect())
+    }
+
+    /// Like [`Self::get_edges_for_symbol`], with each edge's resolution
+    /// confidence: `(neighbor_id, kind, confidence, direction_label)`.
+    pub fn get_weighted_edges_for_symbol(
+        &self,
+        symbol_id: i64,
+    ) -> OmniResult<Vec<(i64, DependencyKind, f32, &'static str)>> {
         let inner = self
             .inner
             .read()
@@ -306,39 +396,102 @@ impl DependencyGraph {
         let mut edges = Vec::new();
 
         // Outgoing edges (what this symbol depends on / calls)
-        for neighbor in inner.graph.neighbors_directed(node, Direction::Outgoing) {
-            if let Some(edge_idx) = inner.graph.find_edge(node, neighbor) {
-                let kind = inner.graph[edge_idx];
-                let target_id = inner.graph[neighbor];
-                edges.push((target_id, kind, "outgoing"));
-            }
+        for edge in inner.graph.edges_directed(node, Direction::Outgoing) {
+            let weight = edge.weight();
+            let target_id = inner.graph[edge.target()];
+            edges.push((target_id, weight.kind, weight.confidence, "outgoing"));
         }
 
         // Incoming edges (what depends on / calls this symbol)
-        for neighbor in inner.graph.neighbors_directed(node, Direction::Incoming) {
-            if let Some(edge_idx) = inner.graph.find_edge(neighbor, node) {
-                let kind = inner.graph[edge_idx];
-                let source_id = inner.graph[neighbor];
-                edges.push((source_id, kind, "incoming"));
-            }
+        for edge in inner.graph.edges_directed(node, Direction::Incoming) {
+            let weight = edge.weight();
+            let source_id = inner.graph[edge.source()];
+            edges.push((source_id, weight.kind, weight.confidence, "incoming"));
         }
 
         Ok(edges)
     }
 
-    /// Resolve an import statement to a target symbol ID.
+    /// Sum of the confidences of a symbol's incoming edges: its in-degree,
+    /// discounted for dependents that were only guessed by name.
+    pub fn weighted_in_degree(&self, symbol_id: i64) -> f64 {
+        self.inner
+            .read()
+            .ok()
+            .and_then(|inner| {
+                inner.symbol_to_node.get(&symbol_id).map(|&node| {
+                    inner
+                        .graph
+                        .edges_directed(node, Direction::Incoming)
+                        .map(|e| f64::from(e.weight().confidence))
+                        .sum()
+                })
+            })
+            .unwrap_or(0.0)
+    }
+
+    /// Shortest undirected distance between two symbols, with the confidence
+    /// of the path: the product of its edges' confidences, taking the most
+    /// confident of the shortest paths. `None` if they are not connected
+    /// within `max_depth` hops.
+    pub fn proximity(&self, from: i64, to: i64, max_depth: usize) -> Option<(usize, f32)> {
+        let inner = self.inner.read().ok()?;
+        let (&from_node, &to_node) = (
+            inner.symbol_to_node.get(&from)?,
+            inner.symbol_to_node.get(&to)?,
+        );
+
+        let mut best: HashMap<NodeIndex, (usize, f32)> = HashMap::new();
+        best.insert(from_node, (0, 1.0));
+        let mut frontier = vec![from_node];
+        for dist in 1..=max_depth {
+            let mut next = Vec::new();
+            for &current in &frontier {
+                let confidence = best[&current].1;
+                for direction in [Direction::Outgoing, Direction::Incoming] {
+                    for edge in inner.graph.edges_directed(current, direction) {
+                        let neighbor = if direction == Direction::Outgoing {
+                            edge.target()
+                        } else {
+                            edge.source()
+                        };
+                        let path = confidence * edge.weight().confidence;
+                        match best.get_mut(&neighbor) {
+                            None => {
+                                best.insert(neighbor, (dist, path));
+                                next.push(neighbor);
+                            }
+                            Some(entry) if entry.0 == dist && entry.1 < path => entry.1 = path,
+                            Some(_) => {}
+                        }
+                    }
+                }
+            }
+            if let Some(&found) = best.get(&to_node) {
+                return Some(found);
+            }
+            frontier = next;
+        }
+        None
+    }
+
+    /// Resolve an import statement to a target symbol ID and the
+    /// confidence of the match.
     ///
     /// Multi-strategy resolution:
     /// 1. Exact FQN match (e.g., `crate::config::Config`)
-    /// 2. FQN suffix match (e.g., `config::Config` matches `crate::config::Config`)
-    /// 3. Name-only fallback with shortest FQN preference
+    /// 2. Re-export aliases (`pub use`, `export ... from`)
+    /// 3. FQN suffix match (e.g., `config::Config` matches `crate::config::Config`)
+    /// 4. Name-only fallback with shortest FQN preference
     ///
+    /// The first two are [`DependencyEdge::EXACT`], then
+    /// [`DependencyEdge::SUFFIX`] and [`DependencyEdge::NAME_ONLY`].
     /// Returns `None` if the import cannot be resolved.
     pub fn resolve_import(
         index: &crate::index::MetadataIndex,
         import_path: &str,
         imported_name: &str,
-    ) -> Option<i64> {
+    ) -> Option<(i64, f32)> {
         // Strategy 1: Exact FQN match
         // Try: import_path::imported_name (e.g., "crate::config" + "Config" -> "crate::config::Config")
         let fqn_candidate = if import_path.is_empty() {
@@ -348,7 +501,7 @@ impl DependencyGraph {
         };
 
         if let Ok(Some(sym)) = index.get_symbol_by_fqn(&fqn_candidate) {
-            return Some(sym.id);
+            return Some((sym.id, DependencyEdge::EXACT));
         }
 
         // Also try with dot separator (Python/TS style)
@@ -358,10 +511,19 @@ impl DependencyGraph {
             format!("{import_path}.{imported_name}")
         };
         if let Ok(Some(sym)) = index.get_symbol_by_fqn(&fqn_dot) {
-            return Some(sym.id);
+            return Some((sym.id, DependencyEdge::EXACT));
         }
 
-        // Strategy 2: FQN suffix match
+        // Strategy 2: Re-export aliases
+        // `use crate::models::User` where `models/mod.rs` has `pub use user::User`,
+        // or `import { Button } from './components'` through an index.ts barrel.
+        for candidate in alias_candidates(import_path, imported_name) {
+            if let Ok(Some(sym)) = index.resolve_alias(&candidate) {
+                return Some((sym.id, DependencyEdge::EXACT));
+            }
+        }
+
+        // Strategy 3: FQN suffix match
         // Try matching any symbol whose FQN ends with the import path
         let suffix = if imported_name.is_empty() {
             import_path.to_string()
@@ -370,26 +532,26 @@ impl DependencyGraph {
         };
         if let Ok(matches) = index.search_symbols_by_fqn_suffix(&suffix, 5) {
             if matches.len() == 1 {
-                return Some(matches[0].id);
+                return Some((matches[0].id, DependencyEdge::SUFFIX));
             }
             // If multiple matches, prefer the one whose FQN contains the import path
             if !import_path.is_empty() {
                 for m in &matches {
                     if m.fqn.contains(import_path) {
-                        return Some(m.id);
+                        return Some((m.id, DependencyEdge::SUFFIX));
                     }
                 }
             }
-            // Fall through to name-only if ambiguous
+            // Ambiguous: take the shortest, but trust it no more than a name
             if !matches.is_empty() {
-                return Some(matches[0].id);
+                return Some((matches[0].id, DependencyEdge::NAME_ONLY));
             }
         }
 
-        // Strategy 3: Name-only fallback (shortest FQN wins)
+        // Strategy 4: Name-only fallback (shortest FQN wins)
         if let Ok(matches) = index.search_symbols_by_name(imported_name, 5) {
             if !matches.is_empty() {
-                return Some(matches[0].id);
+                return Some((matches[0].id, DependencyEdge::NAME_ONLY));
             }
         }
 
@@ -441,7 +603,7 @@ impl DependencyGraph {
                 // Try to resolve: first check local file symbols, then global
                 let target_id = if let Some(&local_id) = name_to_symbol.get(ref_name) {
                     if local_id != source_id {
-                        Some(local_id)
+                        Some((local_id, DependencyEdge::EXACT))
                     } else {
                         None
                     }
@@ -451,14 +613,15 @@ impl DependencyGraph {
                         .search_symbols_by_name(ref_name, 1)
                         .ok()
                         .and_then(|v| v.into_iter().next())
-                        .map(|s| s.id)
+                        .map(|s| (s.id, DependencyEdge::NAME_ONLY))
                 };
 
-                if let Some(target) = target_id {
+                if let Some((target, confidence)) = target_id {
                     let edge = DependencyEdge {
                         source_id,
                         target_id: target,
                         kind: DependencyKind::Calls,
+                        confidence,
                     };
                     edges.push(edge.clone());
                     let _ = self.add_edge(&edge);
@@ -493,25 +656,25 @@ impl DependencyGraph {
                 continue;
             }
 
+            // A C# partial class part shares its FQN with parts in other
+            // files, so its symbol row may belong to one of those.
             let source_id = match name_to_symbol.get(&elem.name) {
                 Some(&id) => id,
-                None => continue,
+                None => match index.get_symbol_by_fqn(&elem.symbol_path) {
+                    Ok(Some(sym)) => sym.id,
+                    _ => continue,
+                },
             };
 
             for type_name in &elem.extends {
-                let target_id = name_to_symbol.get(type_name).copied().or_else(|| {
-                    index
-                        .search_symbols_by_name(type_name, 1)
-                        .ok()
-                        .and_then(|v| v.into_iter().next())
-                        .map(|s| s.id)
-                });
+                let target_id = resolve_type_name(index, &name_to_symbol, type_name);
 
-                if let Some(target) = target_id {
+                if let Some((target, confidence)) = target_id {
                     let edge = DependencyEdge {
                         source_id,
                         target_id: target,
                         kind: DependencyKind::Extends,
+                        confidence,
                     };
                     edges.push(edge.clone());
                     let _ = self.add_edge(&edge);
@@ -519,19 +682,14 @@ impl DependencyGraph {
             }
 
             for type_name in &elem.implements {
-                let target_id = name_to_symbol.get(type_name).copied().or_else(|| {
-                    index
-                        .search_symbols_by_name(type_name, 1)
-                        .ok()
-                        .and_then(|v| v.into_iter().next())
-                        .map(|s| s.id)
-                });
+                let target_id = resolve_type_name(index, &name_to_symbol, type_name);
 
-                if let Some(target) = target_id {
+                if let Some((target, confidence)) = target_id {
                     let edge = DependencyEdge {
                         source_id,
                         target_id: target,
                         kind: DependencyKind::Implements,
+                        confidence,
                     };
                     edges.push(edge.clone());
                     let _ = self.add_edge(&edge);
@@ -592,12 +750,12 @@ impl DependencyGraph {
         removed
     }
 
-    /// Remove symbol nodes entirely from the graph.
+    /// Remove symbol nodes, and every edge touching them, from the graph.
     ///
-    /// Only call this when a file is deleted and its symbols are gone for good.
-    /// For reindex (file changed), prefer `remove_edges_for_symbols` which keeps
-    /// nodes intact so cross-file edges pointing to these symbols still have a
-    /// target node.
+    /// Call this whenever the symbols leave the index: when their file is
+    /// deleted, and when it is reindexed, since reindexing deletes the old
+    /// symbol rows (and their edges) and inserts new ones under fresh IDs.
+    /// Unknown IDs are ignored.
     ///
     /// Returns the number of nodes removed.
     pub fn remove_symbols(&self, symbol_ids: &[i64]) -> usize {
@@ -614,7 +772,7 @@ impl DependencyGraph {
             .iter()
             .filter_map(|&id| inner.symbol_to_node.get(&id).map(|&n| (id, n)))
             .collect();
-        nodes.sort_by(|a, b| b.1.index().cmp(&a.1.index()));
+        nodes.sort_by_key(|n| std::cmp::Reverse(n.1.index()));
 
         for (sym_id, node) in nodes {
             inner.graph.remove_node(node);
@@ -679,24 +837,24 @@ impl DependencyGraph {
             // Distribute scores through edges
             for &node_idx in &node_indices {
                 let slot = idx_to_slot[&node_idx];
-                let out_degree = inner
+                // Out-edges share the score in proportion to their confidence,
+                // so a guessed edge passes on less than a resolved one.
+                let out_weight: f64 = inner
                     .graph
-                    .neighbors_directed(node_idx, Direction::Outgoing)
-                    .count();
-                if out_degree == 0 {
+                    .edges_directed(node_idx, Direction::Outgoing)
+                    .map(|e| f64::from(e.weight().confidence))
+                    .sum();
+                if out_weight <= 0.0 {
                     // Dangling node: distribute evenly to all OTHER nodes
                     let share = damping * scores[slot] / n_f64;
                     for s in &mut new_scores {
                         *s += share;
                     }
                 } else {
-                    let share = damping * scores[slot] / out_degree as f64;
-                    for neighbor in inner
-                        .graph
-                        .neighbors_directed(node_idx, Direction::Outgoing)
-                    {
-                        if let Some(&nb_slot) = idx_to_slot.get(&neighbor) {
-                            new_scores[nb_slot] += share;
+                    let share = damping * scores[slot] / out_weight;
+                    for edge in inner.graph.edges_directed(node_idx, Direction::Outgoing) {
+                        if let Some(&nb_slot) = idx_to_slot.get(&edge.target()) {
+                            new_scores[nb_slot] += share * f64::from(edge.weight().confidence);
                         }
                     }
                 }
@@ -749,9 +907,61 @@ impl Default for DependencyGraph {
     }
 }
 
+/// A supertype named in an `extends` / `implements` clause: a symbol of the
+/// same file, else any symbol with that name.
+fn resolve_type_name(
+    index: &crate::index::MetadataIndex,
+    name_to_symbol: &HashMap<String, i64>,
+    type_name: &str,
+) -> Option<(i64, f32)> {
+    if let Some(&id) = name_to_symbol.get(type_name) {
+        return Some((id, DependencyEdge::EXACT));
+    }
+    index
+        .search_symbols_by_name(type_name, 1)
+        .ok()
+        .and_then(|v| v.into_iter().next())
+        .map(|s| (s.id, DependencyEdge::NAME_ONLY))
+}
+
+/// Names to look up in the alias table for an import. Path anchors
+/// (`crate::`, `self::`, `super::`, `./`, `../`, leading Python dots) are
+/// dropped, leaving a module path that alias lookup matches by suffix.
+fn alias_candidates(import_path: &str, imported_name: &str) -> Vec<String> {
+    let mut path = import_path;
+    loop {
+        let stripped = ["crate::", "self::", "super::", "./", "../"]
+            .iter()
+            .find_map(|anchor| path.strip_prefix(anchor))
+            .unwrap_or_else(|| path.trim_start_matches('.'));
+        if stripped == path {
+            break;
+        }
+        path = stripped;
+    }
+    if path.is_empty() || imported_name.is_empty() || imported_name == "*" {
+        let name = if path.is_empty() { imported_name } else { path };
+        return if name.contains("::") || name.contains('.') {
+            vec![name.to_string()]
+        } else {
+            Vec::new()
+        };
+    }
+    if path.contains("::") {
+        vec![format!("{path}::{imported_name}")]
+    } else if path.contains('.') || path.contains('/') {
+        vec![format!("{path}.{imported_name}")]
+    } else {
+        vec![
+            format!("{path}::{imported_name}"),
+            format!("{path}.{imported_name}"),
+        ]
+    }
+}
+
 /// BFS helper: collect symbol IDs reachable within `depth` hops.
 fn bfs_collect(
-    graph: &DiGraph<i64, DependencyKind>,
+    graph: &DiGraph<i64, GraphEdge>,
     start: NodeIndex,
     depth: usize,
     direction: Direction,
@@ -790,6 +1000,7 @@ mod tests {
                 source_id: 1,
                 target_id: 2,
                 kind: DependencyKind::Calls,
+                confidence: DependencyEdge::EXACT,
             })
             .expect("add edge");
 
@@ -812,6 +1023,7 @@ mod tests {
                 source_id: 1,
                 target_id: 2,
                 kind: DependencyKind::Calls,
+                confidence: DependencyEdge::EXACT,
             })
             .expect("add edge 1->2");
         graph
@@ -819,6 +1031,7 @@ mod tests {
                 source_id: 3,
                 target_id: 2,
                 kind: DependencyKind::Imports,
+                confidence: DependencyEdge::EXACT,
             })
             .expect("add edge 3->2");
 
@@ -836,6 +1049,7 @@ mod tests {
                 source_id: 1,
                 target_id: 2,
                 kind: DependencyKind::Imports,
+                confidence: DependencyEdge::EXACT,
             })
             .expect("edge");
         graph
@@ -843,6 +1057,7 @@ mod tests {
                 source_id: 2,
                 target_id: 3,
                 kind: DependencyKind::Imports,
+                confidence: DependencyEdge::EXACT,
             })
             .expect("edge");
         graph
@@ -850,6 +1065,7 @@ mod tests {
                 source_id: 3,
                 target_id: 1,
                 kind: DependencyKind::Imports,
+                confidence: DependencyEdge::EXACT,
             })
             .expect("edge");
 
@@ -867,6 +1083,7 @@ mod tests {
                 source_id: 1,
                 target_id: 2,
                 kind: DependencyKind::Imports,
+                confidence: DependencyEdge::EXACT,
             })
             .expect("edge");
         graph
@@ -874,6 +1091,7 @@ mod tests {
                 source_id: 2,
                 target_id: 3,
                 kind: DependencyKind::Imports,
+                confidence: DependencyEdge::EXACT,
             })
             .expect("edge");
 
@@ -890,6 +1108,7 @@ mod tests {
                 source_id: 1,
                 target_id: 2,
                 kind: DependencyKind::Calls,
+                confidence: DependencyEdge::EXACT,
             })
             .expect("edge");
         graph
@@ -897,6 +1116,7 @@ mod tests {
                 source_id: 2,
                 target_id: 3,
                 kind: DependencyKind::Calls,
+                confidence: DependencyEdge::EXACT,
             })
             .expect("edge");
 
@@ -916,6 +1136,7 @@ mod tests {
                 source_id: 1,
                 target_id: 2,
                 kind: DependencyKind::Calls,
+                confidence: DependencyEdge::EXACT,
             })
             .expect("edge");
         graph
@@ -923,6 +1144,7 @@ mod tests {
                 source_id: 2,
                 target_id: 3,
                 kind: DependencyKind::Calls,
+                confidence: DependencyEdge::EXACT,
             })
             .expect("edge");
         graph
@@ -930,6 +1152,7 @@ mod tests {
                 source_id: 4,
                 target_id: 2,
                 kind: DependencyKind::Imports,
+                confidence: DependencyEdge::EXACT,
             })
             .expect("edge");
 
@@ -960,6 +1183,7 @@ mod tests {
                 source_id: 1,
                 target_id: 2,
                 kind: DependencyKind::Calls,
+                confidence: DependencyEdge::EXACT,
             })
             .expect("edge");
         graph
@@ -967,6 +1191,7 @@ mod tests {
                 source_id: 3,
                 target_id: 1,
                 kind: DependencyKind::Imports,
+                confidence: DependencyEdge::EXACT,
             })
             .expect("edge");
 
@@ -993,6 +1218,38 @@ mod tests {
         assert!(radius_unknown.is_empty());
     }
 
+    #[test]
+    fn test_tests_for() {
+        let graph = DependencyGraph::new();
+        // 2 calls 1, 3 calls 2; test 10 covers 1, test 20 covers 3
+        for (source_id, target_id, kind) in [
+            (2, 1, DependencyKind::Calls),
+            (3, 2, DependencyKind::Calls),
+            (10, 1, DependencyKind::TestedBy),
+            (20, 3, DependencyKind::TestedBy),
+        ] {
+            graph
+                .add_edge(&DependencyEdge {
+                    source_id,
+                    target_id,
+                    kind,
+                    confidence: DependencyEdge::EXACT,
+                })
+                .expect("edge");
+        }
+
+        let tests = graph.tests_for(1, 5).expect("tests_for");
+        assert_eq!(tests, vec![(10, 0), (20, 2)]);
+
+        // Depth limit stops before reaching symbol 3
+        let shallow = graph.tests_for(1, 1).expect("tests_for");
+        assert_eq!(shallow, vec![(10, 0)]);
+
+        // Tests are not themselves "covered" by anything
+        assert!(graph.tests_for(10, 5).expect("tests_for").is_empty());
+        assert!(graph.tests_for(999, 5).expect("tests_for").is_empty());
+    }
+
     #[test]
     fn test_remove_edges_for_symbols() {
         // Build: 1->2, 2->3, 4->2
@@ -1005,6 +1262,7 @@ mod tests {
                 source_id: 1,
                 target_id: 2,
                 kind: DependencyKind::Calls,
+                confidence: DependencyEdge::EXACT,
             })
             .expect("edge 1->2");
         graph
@@ -1012,6 +1270,7 @@ mod tests {
                 source_id: 2,
                 target_id: 3,
                 kind: DependencyKind::Calls,
+                confidence: DependencyEdge::EXACT,
             })
             .expect("edge 2->3");
         graph
@@ -1019,6 +1278,7 @@ mod tests {
                 source_id: 4,
                 target_id: 2,
                 kind: DependencyKind::Imports,
+                confidence: DependencyEdge::EXACT,
             })
             .expect("edge 4->2");
 
@@ -1045,6 +1305,7 @@ mod tests {
                 source_id: 1,
                 target_id: 2,
                 kind: DependencyKind::Calls,
+                confidence: DependencyEdge::EXACT,
             })
             .expect("edge");
         graph
@@ -1052,6 +1313,7 @@ mod tests {
                 source_id: 3,
                 target_id: 4,
                 kind: DependencyKind::Calls,
+                confidence: DependencyEdge::EXACT,
             })
             .expect("edge");
 
@@ -1077,6 +1339,7 @@ mod tests {
                 source_id: 1,
                 target_id: 2,
                 kind: DependencyKind::Calls,
+                confidence: DependencyEdge::EXACT,
             })
             .expect("edge");
         graph
@@ -1084,6 +1347,7 @@ mod tests {
                 source_id: 2,
                 target_id: 3,
                 kind: DependencyKind::Calls,
+                confidence: DependencyEdge::EXACT,
             })
             .expect("edge");
 
@@ -1103,6 +1367,31 @@ mod tests {
         assert!(upstream.is_empty());
     }
 
+    #[test]
+    fn test_remove_symbols_drops_incident_edges() {
+        // Build: 1->2, 3->4, 4->5
+        let graph = DependencyGraph::new();
+        for (source_id, target_id) in [(1, 2), (3, 4), (4, 5)] {
+            graph
+                .add_edge(&DependencyEdge {
+                    source_id,
+                    target_id,
+                    kind: DependencyKind::Calls,
+                    confidence: DependencyEdge::EXACT,
+                })
+                .expect("edge");
+        }
+
+        assert_eq!(graph.remove_symbols(&[1, 3, 99]), 2);
+        assert_eq!(graph.node_count(), 3);
+        assert_eq!(graph.edge_count(), 1);
+
+        // Nodes moved into the freed slots are still found by symbol ID.
+        assert_eq!(graph.upstream(4, 1).expect("upstream"), vec![5]);
+        assert_eq!(graph.downstream(5, 2).expect("downstream"), vec![4]);
+        assert_eq!(graph.in_degree(2), 0);
+    }
+
     #[test]
     fn test_remove_edges_empty_input() {
         let graph = DependencyGraph::new();
@@ -1135,6 +1424,53 @@ mod tests {
         assert!((pr[&1] - 1.0).abs() < 0.01);
     }
 
+    #[test]
+    fn test_edge_confidence_discounts_proximity_and_in_degree() {
+        // 1 -> 2 resolved exactly, 1 -> 3 guessed by name, 3 -> 4 exact.
+        let graph = DependencyGraph::new();
+        for (source_id, target_id, confidence) in [
+            (1, 2, DependencyEdge::EXACT),
+            (1, 3, DependencyEdge::NAME_ONLY),
+            (3, 4, DependencyEdge::EXACT),
+        ] {
+            graph
+                .add_edge(&DependencyEdge {
+                    source_id,
+                    target_id,
+                    kind: DependencyKind::Calls,
+                    confidence,
+                })
+                .expect("edge");
+        }
+
+        assert_eq!(graph.proximity(1, 2, 2), Some((1, 1.0)));
+        assert_eq!(
+            graph.proximity(3, 1, 2),
+            Some((1, DependencyEdge::NAME_ONLY))
+        );
+        assert_eq!(
+            graph.proximity(4, 1, 2),
+            Some((2, DependencyEdge::NAME_ONLY))
+        );
+        assert_eq!(graph.proximity(4, 2, 2), None, "three hops apart");
+        assert_eq!(graph.proximity(4, 2, 3).map(|(d, _)| d), Some(3));
+
+        assert!((graph.weighted_in_degree(3) - f64::from(DependencyEdge::NAME_ONLY)).abs() < 1e-6);
+        assert!((graph.weighted_in_degree(2) - 1.0).abs() < 1e-6);
+
+        // Rank flows mostly along the confident edge.
+        let pr = graph.compute_pagerank(0.85, 30);
+        assert!(pr[&2] > pr[&3], "{pr:?}");
+
+        let edges = graph.get_weighted_edges_for_symbol(1).expect("edges");
+        assert!(edges.contains(&(
+            3,
+            DependencyKind::Calls,
+            DependencyEdge::NAME_ONLY,
+            "outgoing"
+        )));
+    }
+
     #[test]
     fn test_pagerank_simple_chain() {
         // Chain: 1 → 2 → 3
@@ -1146,6 +1482,7 @@ mod tests {
                 source_id: 1,
                 target_id: 2,
                 kind: DependencyKind::Calls,
+                confidence: DependencyEdge::EXACT,
             })
             .expect("edge");
         graph
@@ -1153,6 +1490,7 @@ mod tests {
                 source_id: 2,
                 target_id: 3,
                 kind: DependencyKind::Calls,
+                confidence: DependencyEdge::EXACT,
             })
             .expect("edge");
 
@@ -1176,6 +1514,7 @@ mod tests {
                     source_id: src,
                     target_id: 5,
                     kind: DependencyKind::Calls,
+                    confidence: DependencyEdge::EXACT,
                 })
                 .expect("edge");
         }
@@ -1204,6 +1543,7 @@ mod tests {
                     source_id: src,
                     target_id: src + 1,
                     kind: DependencyKind::Calls,
+                    confidence: DependencyEdge::EXACT,
                 })
                 .expect("edge");
         }
@@ -1213,6 +1553,7 @@ mod tests {
                 source_id: 5,
                 target_id: 1,
                 kind: DependencyKind::Calls,
+                confidence: DependencyEdge::EXACT,
             })
             .expect("edge");
 
@@ -1234,6 +1575,7 @@ mod tests {
                     source_id: src,
                     target_id: 5,
                     kind: DependencyKind::Calls,
+                    confidence: DependencyEdge::EXACT,
                 })
                 .expect("edge");
         }
@@ -1271,6 +1613,7 @@ mod tests {
                 source_id: 1,
                 target_id: 2,
                 kind: DependencyKind::Calls,
+                confidence: DependencyEdge::EXACT,
             })
             .expect("edge");
         graph
@@ -1278,6 +1621,7 @@ mod tests {
                 source_id: 2,
                 target_id: 3,
                 kind: DependencyKind::Calls,
+                confidence: DependencyEdge::EXACT,
             })
             .expect("edge");
         graph
@@ -1285,6 +1629,7 @@ mod tests {
                 source_id: 3,
                 target_id: 1,
                 kind: DependencyKind::Calls,
+                confidence: DependencyEdge::EXACT,
             })
             .expect("edge");
 
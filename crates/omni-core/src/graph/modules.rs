@@ -0,0 +1,204 @@
+//! File- and directory-level dependency graph aggregated from symbol edges.
+//!
+//! The symbol graph is too fine-grained for architecture questions such as
+//! "which parts of the tree depend on `storage/`". This module rolls every
+//! cross-file symbol edge up into a file→file edge, and every cross-directory
+//! file edge up into a directory→directory edge. An edge's weight is the sum
+//! of the resolution confidences of the symbol edges behind it, so a dozen
+//! name-only guesses count for less than a dozen exact resolutions.
+//!
+//! Both levels export to Graphviz DOT via [`ModuleGraph::to_dot`].
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::OmniResult;
+use crate::index::MetadataIndex;
+
+/// Aggregation level of a [`ModuleGraph`] view.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ModuleLevel {
+    /// One node per indexed file.
+    File,
+    /// One node per directory containing indexed files.
+    Directory,
+}
+
+/// An aggregated dependency edge between two files or two directories.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModuleEdge {
+    /// Depending file or directory (relative, `/`-separated).
+    pub source: String,
+    /// File or directory depended upon.
+    pub target: String,
+    /// Sum of the confidences of the underlying symbol edges.
+    pub weight: f64,
+    /// Number of underlying symbol edges.
+    pub edges: usize,
+}
+
+/// File→file and directory→directory dependency edges.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ModuleGraph {
+    /// Edges between files, sorted by source then target.
+    pub files: Vec<ModuleEdge>,
+    /// Edges between directories, sorted by source then target. The
+    /// repository root is `.`.
+    pub directories: Vec<ModuleEdge>,
+}
+
+impl ModuleGraph {
+    /// Aggregate the persisted symbol dependency edges in `index`.
+    pub fn build(index: &MetadataIndex) -> OmniResult<Self> {
+        let files = index
+            .file_dependency_weights()?
+            .into_iter()
+            .map(|(source, target, weight, edges)| ModuleEdge {
+                source: normalize(&source),
+                target: normalize(&target),
+                weight,
+                edges,
+            })
+            .collect();
+        Ok(Self::from_file_edges(files))
+    }
+
+    /// Build the graph from file-level edges, deriving the directory level.
+    /// Self-loops are dropped at both levels.
+    pub fn from_file_edges(files: Vec<ModuleEdge>) -> Self {
+        let files = merge(files.into_iter().filter(|e| e.source != e.target));
+        let directories = merge(
+            files
+                .iter()
+                .map(|e| ModuleEdge {
+                    source: parent_dir(&e.source).to_string(),
+                    target: parent_dir(&e.target).to_string(),
+                    weight: e.weight,
+                    edges: e.edges,
+                })
+                .filter(|e| e.source != e.target),
+        );
+        Self { files, directories }
+    }
+
+    /// Edges at the given aggregation level.
+    pub fn edges(&self, level: ModuleLevel) -> &[ModuleEdge] {
+        match level {
+            ModuleLevel::File => &self.files,
+            ModuleLevel::Directory => &self.directories,
+        }
+    }
+
+    /// Render one level as a Graphviz DOT digraph. Edges are labelled with
+    /// their weight and pen width grows with it, relative to the heaviest.
+    pub fn to_dot(&self, level: ModuleLevel) -> String {
+        let edges = self.edges(level);
+        let max = edges.iter().map(|e| e.weight).fold(0.0_f64, f64::max);
+        let mut out = String::from("digraph modules {\n    rankdir=LR;\n    node [shape=box];\n");
+        for edge in edges {
+            let width = if max > 0.0 {
+                1.0 + 4.0 * edge.weight / max
+            } else {
+                1.0
+            };
+            let _ = writeln!(
+                out,
+                "    {} -> {} [label=\"{:.1}\", penwidth={width:.2}];",
+                quote(&edge.source),
+                quote(&edge.target),
+                edge.weight,
+            );
+        }
+        out.push_str("}\n");
+        out
+    }
+}
+
+/// Sum edges sharing the same (source, target) pair, sorted by the pair.
+fn merge(edges: impl Iterator<Item = ModuleEdge>) -> Vec<ModuleEdge> {
+    let mut merged: BTreeMap<(String, String), (f64, usize)> = BTreeMap::new();
+    for edge in edges {
+        let entry = merged.entry((edge.source, edge.target)).or_default();
+        entry.0 += edge.weight;
+        entry.1 += edge.edges;
+    }
+    merged
+        .into_iter()
+        .map(|((source, target), (weight, edges))| ModuleEdge {
+            source,
+            target,
+            weight,
+            edges,
+        })
+        .collect()
+}
+
+fn normalize(path: &str) -> String {
+    path.replace('\\', "/")
+}
+
+/// Directory part of a relative `/`-separated path; `.` for the root.
+fn parent_dir(path: &str) -> &str {
+    match path.rfind('/') {
+        Some(i) if i > 0 => &path[..i],
+        _ => ".",
+    }
+}
+
+/// DOT identifier: double-quoted with quotes and backslashes escaped.
+fn quote(id: &str) -> String {
+    format!("\"{}\"", id.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edge(source: &str, target: &str, weight: f64, edges: usize) -> ModuleEdge {
+        ModuleEdge {
+            source: source.to_string(),
+            target: target.to_string(),
+            weight,
+            edges,
+        }
+    }
+
+    #[test]
+    fn test_directory_level_sums_file_edges() {
+        let graph = ModuleGraph::from_file_edges(vec![
+            edge("src/api/handlers.rs", "src/store/db.rs", 2.0, 2),
+            edge("src/api/routes.rs", "src/store/cache.rs", 0.3, 1),
+            edge("src/api/routes.rs", "src/api/handlers.rs", 1.0, 1),
+            edge("main.rs", "src/api/routes.rs", 1.0, 1),
+            edge("main.rs", "main.rs", 5.0, 5),
+        ]);
+
+        assert_eq!(graph.files.len(), 4, "self-loops are dropped");
+        assert_eq!(
+            graph.directories,
+            vec![
+                edge(".", "src/api", 1.0, 1),
+                edge("src/api", "src/store", 2.3, 3),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_to_dot_quotes_and_weights_edges() {
+        let graph = ModuleGraph::from_file_edges(vec![
+            edge("a/x.py", "b/y.py", 2.0, 2),
+            edge("a/\"odd\".py", "b/y.py", 1.0, 1),
+        ]);
+
+        let dot = graph.to_dot(ModuleLevel::Directory);
+        assert!(dot.starts_with("digraph modules {"));
+        assert!(dot.contains("\"a\" -> \"b\" [label=\"3.0\", penwidth=5.00];"));
+
+        let dot = graph.to_dot(ModuleLevel::File);
+        assert!(dot.contains("\"a/\\\"odd\\\".py\" -> \"b/y.py\" [label=\"1.0\", penwidth=3.00];"));
+        assert!(dot.trim_end().ends_with('}'));
+    }
+}
@@ -0,0 +1,726 @@
+//! Package-level dependency graph from build manifests.
+//!
+//! The symbol graph answers "what calls this function"; this one answers
+//! "which of our packages depend on `omni-core`". Every `Cargo.toml`,
+//! `package.json`, `go.mod`, and `pyproject.toml` in the repository that
+//! declares a package becomes a node, and each dependency it lists becomes a
+//! depends-on edge. Edges whose target is another package in the same
+//! repository (and ecosystem) are marked internal.
+//!
+//! Manifests are parsed leniently: a manifest that fails to parse, or that
+//! only defines a workspace (a virtual `Cargo.toml`, a private monorepo root
+//! without a name), contributes no node and never fails the build.
+
+use std::collections::{BTreeSet, HashSet, VecDeque};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::OmniResult;
+
+/// Manifest file names recognised, with the ecosystem each belongs to.
+const MANIFESTS: &[(&str, Ecosystem)] = &[
+    ("Cargo.toml", Ecosystem::Cargo),
+    ("package.json", Ecosystem::Npm),
+    ("go.mod", Ecosystem::Go),
+    ("pyproject.toml", Ecosystem::Python),
+];
+
+/// The package ecosystem a manifest belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Ecosystem {
+    /// Rust crates (`Cargo.toml`).
+    Cargo,
+    /// Node.js packages (`package.json`).
+    Npm,
+    /// Go modules (`go.mod`).
+    Go,
+    /// Python projects (`pyproject.toml`).
+    Python,
+}
+
+impl Ecosystem {
+    /// Short identifier (`cargo`, `npm`, `go`, `python`).
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Cargo => "cargo",
+            Self::Npm => "npm",
+            Self::Go => "go",
+            Self::Python => "python",
+        }
+    }
+
+    /// Canonical form of a package name for comparison. Cargo treats `-`
+    /// and `_` alike; Python names are case-insensitive and treat runs of
+    /// `-`, `_`, and `.` alike (PEP 503).
+    fn normalize(self, name: &str) -> String {
+        match self {
+            Self::Cargo => name.replace('_', "-"),
+            Self::Python => {
+                let mut out = String::with_capacity(name.len());
+                for c in name.chars() {
+                    if matches!(c, '-' | '_' | '.') {
+                        if !out.ends_with('-') {
+                            out.push('-');
+                        }
+                    } else {
+                        out.push(c.to_ascii_lowercase());
+                    }
+                }
+                out
+            }
+            Self::Npm | Self::Go => name.to_string(),
+        }
+    }
+}
+
+/// When a dependency is needed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DependencyScope {
+    /// Needed at run time (including optional and peer dependencies).
+    Normal,
+    /// Needed only for tests and development.
+    Dev,
+    /// Needed only to build (Cargo build scripts, Python build backends).
+    Build,
+}
+
+impl DependencyScope {
+    /// Short identifier (`normal`, `dev`, `build`).
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Normal => "normal",
+            Self::Dev => "dev",
+            Self::Build => "build",
+        }
+    }
+}
+
+/// A depends-on edge from a package to a named dependency.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PackageDependency {
+    /// Dependency name as published (the real name for renamed Cargo deps).
+    pub name: String,
+    /// Version requirement, if the manifest gives one.
+    pub requirement: Option<String>,
+    /// When the dependency is needed.
+    pub scope: DependencyScope,
+    /// Whether the dependency is another package in this repository.
+    pub internal: bool,
+}
+
+/// A package declared by a manifest in the repository.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Package {
+    /// Package name (module path for Go).
+    pub name: String,
+    /// Ecosystem of the declaring manifest.
+    pub ecosystem: Ecosystem,
+    /// Manifest path relative to the repository root, `/`-separated.
+    pub manifest: String,
+    /// Declared version, if any.
+    pub version: Option<String>,
+    /// Declared dependencies, in manifest order.
+    pub dependencies: Vec<PackageDependency>,
+}
+
+/// The packages declared in a repository and their dependency edges.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PackageGraph {
+    /// Packages, sorted by manifest path.
+    pub packages: Vec<Package>,
+}
+
+impl PackageGraph {
+    /// Discover and parse every manifest under `root`.
+    ///
+    /// `skip` is called with each absolute path before it is visited;
+    /// returning `true` leaves that file or directory (and everything under
+    /// it) out. Symlinks are not followed.
+    pub fn build(root: &Path, skip: &dyn Fn(&Path) -> bool) -> OmniResult<Self> {
+        let mut packages = Vec::new();
+        let mut dirs = vec![root.to_path_buf()];
+        while let Some(dir) = dirs.pop() {
+            let entries = match std::fs::read_dir(&dir) {
+                Ok(entries) => entries,
+                Err(e) if dir == root => return Err(e.into()),
+                Err(e) => {
+                    tracing::warn!(path = %dir.display(), error = %e, "cannot read directory");
+                    continue;
+                }
+            };
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if skip(&path) {
+                    continue;
+                }
+                let Ok(file_type) = entry.file_type() else {
+                    continue;
+                };
+                if file_type.is_dir() {
+                    dirs.push(path);
+                    continue;
+                }
+                if !file_type.is_file() {
+                    continue;
+                }
+                let Some(ecosystem) = entry
+                    .file_name()
+                    .to_str()
+                    .and_then(|name| MANIFESTS.iter().find(|(m, _)| *m == name))
+                    .map(|(_, ecosystem)| *ecosystem)
+                else {
+                    continue;
+                };
+                let content = match std::fs::read_to_string(&path) {
+                    Ok(content) => content,
+                    Err(e) => {
+                        tracing::warn!(path = %path.display(), error = %e, "cannot read manifest");
+                        continue;
+                    }
+                };
+                let manifest = path
+                    .strip_prefix(root)
+                    .unwrap_or(&path)
+                    .to_string_lossy()
+                    .replace('\\', "/");
+                if let Some(package) = parse_manifest(ecosystem, &manifest, &content) {
+                    packages.push(package);
+                } else {
+                    tracing::debug!(manifest, "manifest declares no package");
+                }
+            }
+        }
+        Ok(Self::from_packages(packages))
+    }
+
+    /// Build a graph from parsed packages, marking internal edges.
+    pub fn from_packages(mut packages: Vec<Package>) -> Self {
+        packages.sort_by(|a, b| a.manifest.cmp(&b.manifest));
+        let known: HashSet<(Ecosystem, String)> = packages
+            .iter()
+            .map(|p| (p.ecosystem, p.ecosystem.normalize(&p.name)))
+            .collect();
+        for package in &mut packages {
+            let ecosystem = package.ecosystem;
+            for dep in &mut package.dependencies {
+                dep.internal = known.contains(&(ecosystem, ecosystem.normalize(&dep.name)));
+            }
+        }
+        Self { packages }
+    }
+
+    /// Packages named `name` (several when ecosystems share a name).
+    pub fn find(&self, name: &str) -> Vec<&Package> {
+        self.packages
+            .iter()
+            .filter(|p| p.ecosystem.normalize(&p.name) == p.ecosystem.normalize(name))
+            .collect()
+    }
+
+    /// Packages in this repository that depend on `name`, with the number
+    /// of hops to it (1 = direct). Without `transitive`, only direct
+    /// dependents are returned. Sorted by distance, then manifest path.
+    pub fn dependents(&self, name: &str, transitive: bool) -> Vec<(&Package, usize)> {
+        let mut seen: HashSet<usize> = HashSet::new();
+        let mut found = Vec::new();
+        let mut queue: VecDeque<(String, Option<Ecosystem>, usize)> =
+            VecDeque::from([(name.to_string(), None, 0)]);
+        while let Some((target, ecosystem, depth)) = queue.pop_front() {
+            for (i, package) in self.packages.iter().enumerate() {
+                if ecosystem.is_some_and(|e| e != package.ecosystem) || seen.contains(&i) {
+                    continue;
+                }
+                let wanted = package.ecosystem.normalize(&target);
+                let depends = package
+                    .dependencies
+                    .iter()
+                    .any(|d| package.ecosystem.normalize(&d.name) == wanted);
+                if depends {
+                    seen.insert(i);
+                    found.push((package, depth + 1));
+                    if transitive {
+                        queue.push_back((package.name.clone(), Some(package.ecosystem), depth + 1));
+                    }
+                }
+            }
+        }
+        found.sort_by(|a, b| a.1.cmp(&b.1).then_with(|| a.0.manifest.cmp(&b.0.manifest)));
+        found
+    }
+
+    /// Number of internal depends-on edges.
+    pub fn internal_edge_count(&self) -> usize {
+        self.packages
+            .iter()
+            .flat_map(|p| &p.dependencies)
+            .filter(|d| d.internal)
+            .count()
+    }
+}
+
+/// Parse one manifest. `None` when it declares no package.
+pub fn parse_manifest(ecosystem: Ecosystem, manifest: &str, content: &str) -> Option<Package> {
+    let (name, version, dependencies) = match ecosystem {
+        Ecosystem::Cargo => parse_cargo(content)?,
+        Ecosystem::Npm => parse_package_json(content)?,
+        Ecosystem::Go => parse_go_mod(content)?,
+        Ecosystem::Python => parse_pyproject(content)?,
+    };
+    Some(Package {
+        name,
+        ecosystem,
+        manifest: manifest.to_string(),
+        version,
+        dependencies: dedup(dependencies),
+    })
+}
+
+type Parsed = (String, Option<String>, Vec<PackageDependency>);
+
+/// Keep the first occurrence of each (name, scope) pair.
+fn dedup(dependencies: Vec<PackageDependency>) -> Vec<PackageDependency> {
+    let mut seen = BTreeSet::new();
+    dependencies
+        .into_iter()
+        .filter(|d| seen.insert((d.name.clone(), d.scope.as_str())))
+        .collect()
+}
+
+fn dependency(
+    name: &str,
+    requirement: Option<String>,
+    scope: DependencyScope,
+) -> PackageDependency {
+    PackageDependency {
+        name: name.to_string(),
+        requirement: requirement.filter(|r| !r.is_empty()),
+        scope,
+        internal: false,
+    }
+}
+
+fn parse_cargo(content: &str) -> Option<Parsed> {
+    let doc: toml::Table = toml::from_str(content).ok()?;
+    let package = doc.get("package")?.as_table()?;
+    let name = package.get("name")?.as_str()?.to_string();
+    let version = package
+        .get("version")
+        .and_then(toml::Value::as_str)
+        .map(str::to_string);
+
+    let mut deps = Vec::new();
+    let mut collect = |table: &toml::Table| {
+        for (section, scope) in [
+            ("dependencies", DependencyScope::Normal),
+            ("dev-dependencies", DependencyScope::Dev),
+            ("build-dependencies", DependencyScope::Build),
+        ] {
+            let Some(entries) = table.get(section).and_then(toml::Value::as_table) else {
+                continue;
+            };
+            for (key, spec) in entries {
+                let (real, requirement) = match spec {
+                    toml::Value::String(req) => (key.as_str(), Some(req.clone())),
+                    toml::Value::Table(t) => (
+                        t.get("package")
+                            .and_then(toml::Value::as_str)
+                            .unwrap_or(key),
+                        t.get("version")
+                            .and_then(toml::Value::as_str)
+                            .or_else(|| t.get("path").and_then(toml::Value::as_str))
+                            .map(str::to_string),
+                    ),
+                    _ => (key.as_str(), None),
+                };
+                deps.push(dependency(real, requirement, scope));
+            }
+        }
+    };
+    collect(&doc);
+    // [target.'cfg(...)'.dependencies] and friends
+    if let Some(targets) = doc.get("target").and_then(toml::Value::as_table) {
+        for target in targets.values().filter_map(toml::Value::as_table) {
+            collect(target);
+        }
+    }
+    Some((name, version, deps))
+}
+
+fn parse_package_json(content: &str) -> Option<Parsed> {
+    let doc: serde_json::Value = serde_json::from_str(content).ok()?;
+    let name = doc.get("name")?.as_str()?.to_string();
+    let version = doc
+        .get("version")
+        .and_then(serde_json::Value::as_str)
+        .map(str::to_string);
+
+    let mut deps = Vec::new();
+    for (section, scope) in [
+        ("dependencies", DependencyScope::Normal),
+        ("peerDependencies", DependencyScope::Normal),
+        ("optionalDependencies", DependencyScope::Normal),
+        ("devDependencies", DependencyScope::Dev),
+    ] {
+        let Some(entries) = doc.get(section).and_then(serde_json::Value::as_object) else {
+            continue;
+        };
+        for (dep, req) in entries {
+            deps.push(dependency(dep, req.as_str().map(str::to_string), scope));
+        }
+    }
+    Some((name, version, deps))
+}
+
+fn parse_go_mod(content: &str) -> Option<Parsed> {
+    let mut name = None;
+    let mut deps = Vec::new();
+    let mut in_require = false;
+    for line in content.lines() {
+        let line = line.split("//").next().unwrap_or("").trim();
+        if in_require {
+            if line == ")" {
+                in_require = false;
+            } else if let Some(dep) = go_requirement(line) {
+                deps.push(dep);
+            }
+        } else if let Some(module) = line.strip_prefix("module ") {
+            name = Some(module.trim().trim_matches('"').to_string());
+        } else if let Some(rest) = line.strip_prefix("require") {
+            let rest = rest.trim();
+            if rest == "(" {
+                in_require = true;
+            } else if let Some(dep) = go_requirement(rest) {
+                deps.push(dep);
+            }
+        }
+    }
+    Some((name?, None, deps))
+}
+
+/// `github.com/x/y v1.2.3` -> dependency.
+fn go_requirement(line: &str) -> Option<PackageDependency> {
+    let mut parts = line.split_whitespace();
+    let path = parts.next()?;
+    let version = parts.next().map(str::to_string);
+    Some(dependency(path, version, DependencyScope::Normal))
+}
+
+fn parse_pyproject(content: &str) -> Option<Parsed> {
+    let doc: toml::Table = toml::from_str(content).ok()?;
+    let project = doc.get("project").and_then(toml::Value::as_table);
+    let poetry = doc
+        .get("tool")
+        .and_then(|t| t.get("poetry"))
+        .and_then(toml::Value::as_table);
+
+    let field = |key: &str| {
+        project
+            .and_then(|p| p.get(key))
+            .or_else(|| poetry.and_then(|p| p.get(key)))
+            .and_then(toml::Value::as_str)
+            .map(str::to_string)
+    };
+    let name = field("name")?;
+    let version = field("version");
+
+    let mut deps = Vec::new();
+    // PEP 621 / PEP 735
+    if let Some(project) = project {
+        pep508_list(
+            project.get("dependencies"),
+            DependencyScope::Normal,
+            &mut deps,
+        );
+        if let Some(extras) = project
+            .get("optional-dependencies")
+            .and_then(toml::Value::as_table)
+        {
+            for list in extras.values() {
+                pep508_list(Some(list), DependencyScope::Normal, &mut deps);
+            }
+        }
+    }
+    if let Some(groups) = doc.get("dependency-groups").and_then(toml::Value::as_table) {
+        for list in groups.values() {
+            pep508_list(Some(list), DependencyScope::Dev, &mut deps);
+        }
+    }
+    pep508_list(
+        doc.get("build-system").and_then(|b| b.get("requires")),
+        DependencyScope::Build,
+        &mut deps,
+    );
+    // Poetry
+    if let Some(poetry) = poetry {
+        poetry_table(
+            poetry.get("dependencies"),
+            DependencyScope::Normal,
+            &mut deps,
+        );
+        poetry_table(
+            poetry.get("dev-dependencies"),
+            DependencyScope::Dev,
+            &mut deps,
+        );
+        if let Some(groups) = poetry.get("group").and_then(toml::Value::as_table) {
+            for group in groups.values() {
+                poetry_table(group.get("dependencies"), DependencyScope::Dev, &mut deps);
+            }
+        }
+    }
+    Some((name, version, deps))
+}
+
+/// Collect PEP 508 requirement strings (`requests>=2.0; python_version>"3"`).
+fn pep508_list(
+    list: Option<&toml::Value>,
+    scope: DependencyScope,
+    out: &mut Vec<PackageDependency>,
+) {
+    let Some(list) = list.and_then(toml::Value::as_array) else {
+        return;
+    };
+    for spec in list.iter().filter_map(toml::Value::as_str) {
+        let spec = spec.split(';').next().unwrap_or("").trim();
+        let end = spec
+            .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')))
+            .unwrap_or(spec.len());
+        let (name, rest) = spec.split_at(end);
+        if name.is_empty() {
+            continue;
+        }
+        // Drop extras (`pkg[extra]>=1`) from the requirement.
+        let rest = match rest.strip_prefix('[') {
+            Some(r) => r.split_once(']').map_or("", |(_, after)| after),
+            None => rest,
+        };
+        out.push(dependency(name, Some(rest.trim().to_string()), scope));
+    }
+}
+
+/// Collect a Poetry dependency table; the interpreter entry is skipped.
+fn poetry_table(
+    table: Option<&toml::Value>,
+    scope: DependencyScope,
+    out: &mut Vec<PackageDependency>,
+) {
+    let Some(table) = table.and_then(toml::Value::as_table) else {
+        return;
+    };
+    for (name, spec) in table {
+        if name.eq_ignore_ascii_case("python") {
+            continue;
+        }
+        let requirement = match spec {
+            toml::Value::String(req) => Some(req.clone()),
+            toml::Value::Table(t) => t
+                .get("version")
+                .or_else(|| t.get("path"))
+                .and_then(toml::Value::as_str)
+                .map(str::to_string),
+            _ => None,
+        };
+        out.push(dependency(name, requirement, scope));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(package: &Package, scope: DependencyScope) -> Vec<&str> {
+        package
+            .dependencies
+            .iter()
+            .filter(|d| d.scope == scope)
+            .map(|d| d.name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_cargo_manifest() {
+        let content = r#"
+[package]
+name = "omni-mcp"
+version = "1.5.0"
+
+[dependencies]
+omni-core = { path = "../omni-core" }
+serde = "1"
+json = { package = "serde_json", version = "1" }
+
+[dev-dependencies]
+tempfile = { workspace = true }
+
+[build-dependencies]
+cc = "1"
+
+[target.'cfg(windows)'.dependencies]
+windows-sys = "0.52"
+"#;
+        let pkg = parse_manifest(Ecosystem::Cargo, "crates/omni-mcp/Cargo.toml", content)
+            .expect("package");
+        assert_eq!(pkg.name, "omni-mcp");
+        assert_eq!(pkg.version.as_deref(), Some("1.5.0"));
+        let mut normal = names(&pkg, DependencyScope::Normal);
+        normal.sort_unstable();
+        assert_eq!(normal, ["omni-core", "serde", "serde_json", "windows-sys"]);
+        let core = pkg.dependencies.iter().find(|d| d.name == "omni-core");
+        assert_eq!(
+            core.and_then(|d| d.requirement.as_deref()),
+            Some("../omni-core")
+        );
+        assert_eq!(names(&pkg, DependencyScope::Dev), ["tempfile"]);
+        assert_eq!(names(&pkg, DependencyScope::Build), ["cc"]);
+
+        // A virtual workspace manifest declares no package.
+        assert!(parse_manifest(
+            Ecosystem::Cargo,
+            "Cargo.toml",
+            "[workspace]\nmembers = []\n"
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn test_parse_package_json() {
+        let content = r#"{
+            "name": "@acme/web",
+            "version": "0.1.0",
+            "dependencies": { "@acme/ui": "workspace:*", "react": "^18" },
+            "devDependencies": { "vitest": "^1" }
+        }"#;
+        let pkg =
+            parse_manifest(Ecosystem::Npm, "apps/web/package.json", content).expect("package");
+        assert_eq!(pkg.name, "@acme/web");
+        assert_eq!(names(&pkg, DependencyScope::Normal), ["@acme/ui", "react"]);
+        assert_eq!(names(&pkg, DependencyScope::Dev), ["vitest"]);
+        assert!(parse_manifest(Ecosystem::Npm, "package.json", r#"{"private": true}"#).is_none());
+    }
+
+    #[test]
+    fn test_parse_go_mod() {
+        let content = "module github.com/acme/api\n\ngo 1.22\n\nrequire github.com/acme/lib v0.3.0\n\nrequire (\n\tgolang.org/x/sync v0.7.0 // indirect\n\tgithub.com/google/uuid v1.6.0\n)\n";
+        let pkg = parse_manifest(Ecosystem::Go, "api/go.mod", content).expect("package");
+        assert_eq!(pkg.name, "github.com/acme/api");
+        assert_eq!(
+            names(&pkg, DependencyScope::Normal),
+            [
+                "github.com/acme/lib",
+                "golang.org/x/sync",
+                "github.com/google/uuid"
+            ]
+        );
+        assert_eq!(pkg.dependencies[1].requirement.as_deref(), Some("v0.7.0"));
+    }
+
+    #[test]
+    fn test_parse_pyproject_pep621_and_poetry() {
+        let pep621 = r#"
+[project]
+name = "Acme.Core"
+version = "2.0"
+dependencies = ["requests[socks]>=2.31; python_version >= '3.9'", "acme_utils"]
+
+[project.optional-dependencies]
+fast = ["orjson"]
+
+[dependency-groups]
+test = ["pytest>=8"]
+
+[build-system]
+requires = ["hatchling"]
+"#;
+        let pkg = parse_manifest(Ecosystem::Python, "pyproject.toml", pep621).expect("package");
+        assert_eq!(pkg.name, "Acme.Core");
+        assert_eq!(
+            names(&pkg, DependencyScope::Normal),
+            ["requests", "acme_utils", "orjson"]
+        );
+        assert_eq!(pkg.dependencies[0].requirement.as_deref(), Some(">=2.31"));
+        assert_eq!(pkg.dependencies[1].requirement, None);
+        assert_eq!(names(&pkg, DependencyScope::Dev), ["pytest"]);
+        assert_eq!(names(&pkg, DependencyScope::Build), ["hatchling"]);
+
+        let poetry = r#"
+[tool.poetry]
+name = "acme-cli"
+
+[tool.poetry.dependencies]
+python = "^3.11"
+acme-core = { path = "../core" }
+
+[tool.poetry.group.dev.dependencies]
+ruff = "*"
+"#;
+        let pkg = parse_manifest(Ecosystem::Python, "cli/pyproject.toml", poetry).expect("package");
+        assert_eq!(names(&pkg, DependencyScope::Normal), ["acme-core"]);
+        assert_eq!(names(&pkg, DependencyScope::Dev), ["ruff"]);
+    }
+
+    #[test]
+    fn test_internal_edges_and_dependents() {
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let root = dir.path();
+        let write = |path: &str, body: &str| {
+            let path = root.join(path);
+            std::fs::create_dir_all(path.parent().expect("parent")).expect("mkdir");
+            std::fs::write(path, body).expect("write");
+        };
+        write("Cargo.toml", "[workspace]\nmembers = [\"crates/*\"]\n");
+        write(
+            "crates/core/Cargo.toml",
+            "[package]\nname = \"omni_core\"\n[dependencies]\nserde = \"1\"\n",
+        );
+        write(
+            "crates/mcp/Cargo.toml",
+            "[package]\nname = \"omni-mcp\"\n[dependencies]\nomni-core = { path = \"../core\" }\n",
+        );
+        write(
+            "crates/cli/Cargo.toml",
+            "[package]\nname = \"omni-cli\"\n[dependencies]\nomni-mcp = { path = \"../mcp\" }\n",
+        );
+        write(
+            "crates/cli/node_modules/x/package.json",
+            r#"{"name": "omni-core"}"#,
+        );
+        write("py/pyproject.toml", "[project]\nname = \"omni-core\"\n");
+
+        let graph = PackageGraph::build(root, &|p: &Path| {
+            p.file_name().is_some_and(|n| n == "node_modules")
+        })
+        .expect("build");
+        let manifests: Vec<&str> = graph.packages.iter().map(|p| p.manifest.as_str()).collect();
+        assert_eq!(
+            manifests,
+            [
+                "crates/cli/Cargo.toml",
+                "crates/core/Cargo.toml",
+                "crates/mcp/Cargo.toml",
+                "py/pyproject.toml"
+            ]
+        );
+        assert_eq!(graph.internal_edge_count(), 2);
+        assert!(
+            !graph.packages[1].dependencies[0].internal,
+            "serde is external"
+        );
+        assert_eq!(graph.find("omni-core").len(), 2);
+
+        let direct: Vec<_> = graph
+            .dependents("omni-core", false)
+            .into_iter()
+            .map(|(p, d)| (p.name.as_str(), d))
+            .collect();
+        assert_eq!(direct, [("omni-mcp", 1)]);
+        let all: Vec<_> = graph
+            .dependents("omni-core", true)
+            .into_iter()
+            .map(|(p, d)| (p.name.as_str(), d))
+            .collect();
+        assert_eq!(all, [("omni-mcp", 1), ("omni-cli", 2)]);
+    }
+}
@@ -64,6 +64,10 @@ pub struct EdgeWeights {
     pub type_flow: f64,
     /// Weight for `HistoricalCoChange` edges (files that historically change together).
     pub historical_co_change: f64,
+    /// Weight for `TestedBy` edges (test → production symbol it exercises).
+    pub tested_by: f64,
+    /// Weight for `Binds` edges (cross-language call → native implementation).
+    pub binds: f64,
 }
 
 impl Default for EdgeWeights {
@@ -82,6 +86,10 @@ impl Default for EdgeWeights {
             error_flow: 0.9,
             type_flow: 0.6,
             historical_co_change: 0.5,
+            // Tests are useful context but rarely the answer itself
+            tested_by: 0.4,
+            // A binding is a call that happens to cross a language boundary
+            binds: 0.6,
         }
     }
 }
@@ -101,6 +109,8 @@ impl EdgeWeights {
             DependencyKind::ErrorFlow => self.error_flow,
             DependencyKind::TypeFlow => self.type_flow,
             DependencyKind::HistoricalCoChange => self.historical_co_change,
+            DependencyKind::TestedBy => self.tested_by,
+            DependencyKind::Binds => self.binds,
         }
     }
 }
@@ -214,7 +224,7 @@ impl ReasoningEngine {
     ///
     /// This is the core of **Graph-Augmented Retrieval (GAR)**:
     /// 1. BFS from `symbol_id` over ALL edge types
-    /// 2. Score each discovered symbol by edge weight * hop decay
+    /// 2. Score each discovered symbol by edge weight * confidence * hop decay
     /// 3. Return top-N symbols by accumulated score
     ///
     /// Used to inject "shadow context" — structurally relevant code
@@ -240,13 +250,13 @@ impl ReasoningEngine {
             }
 
             // Get all edges for current symbol (both outgoing and incoming)
-            if let Ok(edges) = graph.get_edges_for_symbol(current) {
-                for (neighbor_id, kind, direction) in &edges {
+            if let Ok(edges) = graph.get_weighted_edges_for_symbol(current) {
+                for (neighbor_id, kind, confidence, direction) in &edges {
                     // Only follow outgoing edges for forward traversal
                     if *direction != "outgoing" {
                         continue;
                     }
-                    let edge_weight = self.weights.weight_for(kind);
+                    let edge_weight = self.weights.weight_for(kind) * f64::from(*confidence);
                     let hop_score = edge_weight * self.hop_decay.powi((current_depth + 1) as i32);
 
                     let mut new_path = path.clone();
@@ -275,14 +285,15 @@ impl ReasoningEngine {
                 // Also follow incoming edges (upstream — bidirectional walk with weaker signal)
                 // Use actual edge kind and weight, but apply a 0.5× discount since
                 // incoming edges carry less directional relevance than outgoing.
-                for (neighbor_id, kind, direction) in &edges {
+                for (neighbor_id, kind, confidence, direction) in &edges {
                     if *direction != "incoming" {
                         continue;
                     }
                     if visited.contains(neighbor_id) {
                         continue;
                     }
-                    let edge_weight = self.weights.weight_for(kind) * 0.5; // incoming discount
+                    // incoming discount
+                    let edge_weight = self.weights.weight_for(kind) * f64::from(*confidence) * 0.5;
                     let hop_score = edge_weight * self.hop_decay.powi((current_depth + 1) as i32);
                     let mut new_path = path.clone();
                     new_path.push(*kind);
@@ -398,8 +409,8 @@ impl ReasoningEngine {
                 continue;
             }
 
-            if let Ok(edges) = graph.get_edges_for_symbol(current) {
-                for (neighbor_id, kind, direction) in &edges {
+            if let Ok(edges) = graph.get_weighted_edges_for_symbol(current) {
+                for (neighbor_id, kind, confidence, direction) in &edges {
                     if *direction != target_direction {
                         continue;
                     }
@@ -408,8 +419,9 @@ impl ReasoningEngine {
                     }
                     let mut new_path = path.clone();
                     new_path.push(*kind);
-                    let hop_score =
-                        self.weights.weight_for(kind) * self.hop_decay.powi((depth + 1) as i32);
+                    let hop_score = self.weights.weight_for(kind)
+                        * f64::from(*confidence)
+                        * self.hop_decay.powi((depth + 1) as i32);
 
                     // Accumulate score across paths
                     let entry =
@@ -471,6 +483,7 @@ mod tests {
                 source_id: 1,
                 target_id: 2,
                 kind: DependencyKind::Calls,
+                confidence: DependencyEdge::EXACT,
             })
             .unwrap();
         graph
@@ -478,6 +491,7 @@ mod tests {
                 source_id: 2,
                 target_id: 3,
                 kind: DependencyKind::Calls,
+                confidence: DependencyEdge::EXACT,
             })
             .unwrap();
 
@@ -487,6 +501,7 @@ mod tests {
                 source_id: 1,
                 target_id: 5,
                 kind: DependencyKind::DataFlow,
+                confidence: DependencyEdge::EXACT,
             })
             .unwrap();
         graph
@@ -494,6 +509,7 @@ mod tests {
                 source_id: 1,
                 target_id: 4,
                 kind: DependencyKind::ErrorFlow,
+                confidence: DependencyEdge::EXACT,
             })
             .unwrap();
         graph
@@ -501,6 +517,7 @@ mod tests {
                 source_id: 3,
                 target_id: 5,
                 kind: DependencyKind::DataFlow,
+                confidence: DependencyEdge::EXACT,
             })
             .unwrap();
 
@@ -0,0 +1,466 @@
+//! Test-to-code linkage.
+//!
+//! Infers which production symbols each test exercises and records the
+//! relationship as `TestedBy` edges in the symbol-level dependency graph.
+//! Impact analysis can then answer "which tests should I run after changing
+//! this function?" without executing anything.
+//!
+//! ## Signals
+//!
+//! 1. **References** -- symbols called or used inside the test body, resolved
+//!    through the test file's imports first and the global symbol table second
+//! 2. **Naming conventions** -- `test_parse_config` → `parse_config`,
+//!    `TestParseConfig` → `ParseConfig`, `ParserTest` → `Parser`; candidates
+//!    from the test file's own subject (`test_parser.py` → `parser`) win ties
+//!
+//! Targets are only ever production symbols: anything defined in a test file
+//! or itself classified as a test is skipped, so helpers and fixtures don't
+//! show up as "tested".
+
+#![allow(clippy::doc_markdown, clippy::must_use_candidate)]
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use super::coverage::is_test_file;
+use super::DependencyGraph;
+use crate::index::MetadataIndex;
+use crate::parser::StructuralElement;
+use crate::types::{ChunkKind, DependencyEdge, DependencyKind, ImportStatement, Symbol};
+
+/// Minimum length of a name-derived candidate. Shorter stems (`a`, `io`)
+/// match far too many unrelated symbols to be useful.
+const MIN_CANDIDATE_LEN: usize = 3;
+
+/// Maximum symbols fetched per name lookup.
+const LOOKUP_LIMIT: usize = 16;
+
+/// Whether an element should be treated as a test.
+///
+/// Elements explicitly classified as tests by the parser always qualify.
+/// In test files, plain functions qualify too -- many frameworks (pytest,
+/// Go, Jest helpers) don't mark tests syntactically.
+pub fn is_test_element(elem: &StructuralElement, in_test_file: bool) -> bool {
+    elem.kind == ChunkKind::Test || (in_test_file && elem.kind == ChunkKind::Function)
+}
+
+/// Derive the production "subject" of a test file from its name.
+///
+/// `test_parser.py`, `parser_test.go`, `parser.spec.ts` and `ParserTest.java`
+/// all yield `parser` (lowercased). Returns `None` for files whose name
+/// carries no subject (`conftest.py`, `tests.rs`).
+pub fn test_file_subject(path: &Path) -> Option<String> {
+    let stem = path.file_stem()?.to_str()?;
+    let lower = stem.to_lowercase();
+
+    let subject = lower
+        .strip_suffix(".test")
+        .or_else(|| lower.strip_suffix(".spec"))
+        .or_else(|| lower.strip_prefix("test_"))
+        .or_else(|| lower.strip_suffix("_test"))
+        .or_else(|| lower.strip_suffix("_spec"))
+        .or_else(|| lower.strip_suffix("_tests"))
+        .or_else(|| {
+            lower
+                .strip_suffix("tests")
+                .filter(|_| stem.ends_with("Tests"))
+        })
+        .or_else(|| {
+            lower
+                .strip_suffix("test")
+                .filter(|_| stem.ends_with("Test"))
+        })
+        .or_else(|| {
+            lower
+                .strip_prefix("test")
+                .filter(|_| starts_with_camel_test(stem))
+        })?;
+
+    let subject = subject.trim_matches(|c: char| c == '_' || c == '.');
+    if subject.len() < MIN_CANDIDATE_LEN {
+        return None;
+    }
+    Some(subject.to_string())
+}
+
+/// Candidate production symbol names for a test, most specific first.
+///
+/// Strips the test marker and then progressively drops trailing words, so
+/// `test_parse_config_handles_empty` yields `parse_config_handles_empty`,
+/// `parse_config_handles`, `parse_config`, `parse`. Camel-case names split
+/// on humps the same way. For `TestFoo`-style names a lower-camel variant
+/// (`foo`) is included for languages that export lowercase functions.
+pub fn candidate_names(test_name: &str) -> Vec<String> {
+    let stripped = strip_test_marker(test_name);
+    let Some(stripped) = stripped else {
+        return Vec::new();
+    };
+
+    let mut out: Vec<String> = Vec::new();
+    let mut push = |s: &str| {
+        if s.len() >= MIN_CANDIDATE_LEN && s != test_name && !out.iter().any(|o| o == s) {
+            out.push(s.to_string());
+        }
+    };
+
+    if stripped.contains('_') {
+        let parts: Vec<&str> = stripped.split('_').filter(|p| !p.is_empty()).collect();
+        for end in (1..=parts.len()).rev() {
+            push(&parts[..end].join("_"));
+        }
+    } else {
+        let humps = camel_hump_offsets(stripped);
+        push(stripped);
+        for &off in humps.iter().rev() {
+            push(&stripped[..off]);
+        }
+        if stripped.chars().next().is_some_and(char::is_uppercase) {
+            let lowered = lower_first(stripped);
+            push(&lowered);
+        }
+    }
+
+    out
+}
+
+/// Whether [`build_test_edges`] has anything to link in a file: it is a test
+/// file, or defines elements the parser classified as tests.
+pub fn has_tests(rel_path: &Path, elements: &[StructuralElement]) -> bool {
+    is_test_file(&rel_path.to_string_lossy()) || elements.iter().any(|e| e.kind == ChunkKind::Test)
+}
+
+/// Infer `TestedBy` edges for the tests defined in one file.
+///
+/// Edges run from the test symbol to the production symbol it exercises and
+/// are added to `graph` as they are found. The caller is responsible for
+/// persisting the returned edges to the index.
+///
+/// Targets resolve against stored symbols only, so call this once the
+/// files a test exercises are stored, and again for a test file when its
+/// subject is added later.
+pub fn build_test_edges(
+    graph: &DependencyGraph,
+    index: &MetadataIndex,
+    file_id: i64,
+    rel_path: &Path,
+    elements: &[StructuralElement],
+    imports: &[ImportStatement],
+) -> Vec<DependencyEdge> {
+    let mut edges = Vec::new();
+
+    if !has_tests(rel_path, elements) {
+        return edges;
+    }
+    let in_test_file = is_test_file(&rel_path.to_string_lossy());
+
+    let Ok(file_symbols) = index.get_all_symbols_for_file(file_id) else {
+        return edges;
+    };
+    let name_to_symbol: HashMap<&str, &Symbol> =
+        file_symbols.iter().map(|s| (s.name.as_str(), s)).collect();
+
+    // Imported name -> module path, for import-first reference resolution
+    let mut imported_from: HashMap<&str, &str> = HashMap::new();
+    for import in imports {
+        for name in &import.imported_names {
+            if name != "*" {
+                imported_from.insert(name.as_str(), import.import_path.as_str());
+            }
+        }
+    }
+
+    let subject = if in_test_file {
+        test_file_subject(rel_path)
+    } else {
+        None
+    };
+    let mut lookup = TargetResolver::new(index, file_id, in_test_file);
+    let mut seen: HashSet<(i64, i64)> = HashSet::new();
+
+    for elem in elements {
+        if !is_test_element(elem, in_test_file) {
+            continue;
+        }
+        let Some(source_id) = name_to_symbol.get(elem.name.as_str()).map(|s| s.id) else {
+            continue;
+        };
+
+        let mut targets: Vec<i64> = Vec::new();
+
+        // Signal 1: references inside the test body
+        for ref_name in &elem.references {
+            if ref_name == &elem.name {
+                continue;
+            }
+            let mut resolved = imported_from
+                .get(ref_name.as_str())
+                .and_then(|path| DependencyGraph::resolve_import(index, path, ref_name))
+                .map(|(id, _)| id)
+                .filter(|&id| lookup.is_production(id));
+            if resolved.is_none() {
+                if let Some(local) = name_to_symbol.get(ref_name.as_str()) {
+                    resolved = lookup.is_production_symbol(local).then_some(local.id);
+                }
+            }
+            if resolved.is_none() {
+                resolved = lookup.by_exact_name(ref_name, subject.as_deref());
+            }
+
+            if let Some(target) = resolved {
+                targets.push(target);
+            }
+        }
+
+        // Signal 2: naming convention (first candidate that resolves wins)
+        for candidate in candidate_names(&elem.name) {
+            if let Some(target) = lookup.by_exact_name(&candidate, subject.as_deref()) {
+                targets.push(target);
+                break;
+            }
+        }
+
+        for target in targets {
+            if target == source_id || !seen.insert((source_id, target)) {
+                continue;
+            }
+            let edge = DependencyEdge {
+                source_id,
+                target_id: target,
+                kind: DependencyKind::TestedBy,
+                confidence: DependencyEdge::EXACT,
+            };
+            let _ = graph.add_edge(&edge);
+            edges.push(edge);
+        }
+    }
+
+    edges
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Resolves names to production symbols, caching per-file test classification.
+struct TargetResolver<'a> {
+    index: &'a MetadataIndex,
+    file_id: i64,
+    in_test_file: bool,
+    file_is_test: HashMap<i64, bool>,
+}
+
+impl<'a> TargetResolver<'a> {
+    fn new(index: &'a MetadataIndex, file_id: i64, in_test_file: bool) -> Self {
+        Self {
+            index,
+            file_id,
+            in_test_file,
+            file_is_test: HashMap::new(),
+        }
+    }
+
+    fn is_production(&mut self, symbol_id: i64) -> bool {
+        match self.index.get_symbol_by_id(symbol_id) {
+            Ok(Some(sym)) => self.is_production_symbol(&sym),
+            _ => false,
+        }
+    }
+
+    fn is_production_symbol(&mut self, sym: &Symbol) -> bool {
+        if sym.kind == ChunkKind::Test {
+            return false;
+        }
+        if sym.file_id == self.file_id {
+            return !self.in_test_file;
+        }
+        let index = self.index;
+        let is_test = *self.file_is_test.entry(sym.file_id).or_insert_with(|| {
+            index
+                .get_file_by_id(sym.file_id)
+                .ok()
+                .flatten()
+                .is_some_and(|f| is_test_file(&f.path.to_string_lossy()))
+        });
+        !is_test
+    }
+
+    /// Find a production symbol named exactly `name`.
+    ///
+    /// With several matches, prefer one defined in a file whose stem equals
+    /// the test file's subject; otherwise only an unambiguous match counts.
+    fn by_exact_name(&mut self, name: &str, subject: Option<&str>) -> Option<i64> {
+        let matches: Vec<Symbol> = self
+            .index
+            .search_symbols_by_name(name, LOOKUP_LIMIT)
+            .ok()?
+            .into_iter()
+            .filter(|s| s.name == name)
+            .collect();
+        let production: Vec<&Symbol> = matches
+            .iter()
+            .filter(|s| self.is_production_symbol(s))
+            .collect();
+
+        match production.as_slice() {
+            [] => None,
+            [only] => Some(only.id),
+            many => {
+                let subject = subject?;
+                many.iter()
+                    .find(|s| {
+                        self.index
+                            .get_file_by_id(s.file_id)
+                            .ok()
+                            .flatten()
+                            .and_then(|f| {
+                                f.path
+                                    .file_stem()
+                                    .and_then(|st| st.to_str())
+                                    .map(str::to_lowercase)
+                            })
+                            .is_some_and(|stem| stem == subject)
+                    })
+                    .map(|s| s.id)
+            }
+        }
+    }
+}
+
+/// `testFoo` / `TestFoo`: "test" followed by an uppercase letter.
+fn starts_with_camel_test(name: &str) -> bool {
+    name.len() > 4
+        && name[..4].eq_ignore_ascii_case("test")
+        && name[4..].chars().next().is_some_and(char::is_uppercase)
+}
+
+/// Remove the test marker from a test name, if it has one.
+fn strip_test_marker(name: &str) -> Option<&str> {
+    let stripped = if let Some(rest) = name.strip_prefix("test_") {
+        rest
+    } else if starts_with_camel_test(name) {
+        &name[4..]
+    } else if let Some(rest) = name
+        .strip_suffix("_test")
+        .or_else(|| name.strip_suffix("_spec"))
+    {
+        rest
+    } else {
+        // Unmarked names (`#[test] fn parses_config`) are left to the
+        // reference signal; guessing from them is too noisy.
+        name.strip_suffix("Tests")
+            .or_else(|| name.strip_suffix("Test"))?
+    };
+    let stripped = stripped.trim_matches('_');
+    (!stripped.is_empty()).then_some(stripped)
+}
+
+/// Byte offsets of interior camel-case humps (`ParseConfig` → `[5]`).
+fn camel_hump_offsets(name: &str) -> Vec<usize> {
+    name.char_indices()
+        .skip(1)
+        .filter(|(_, c)| c.is_uppercase())
+        .map(|(i, _)| i)
+        .collect()
+}
+
+fn lower_first(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(first) => first.to_lowercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_candidate_names_snake_case() {
+        let c = candidate_names("test_parse_config_handles_empty");
+        assert_eq!(
+            c,
+            vec![
+                "parse_config_handles_empty",
+                "parse_config_handles",
+                "parse_config",
+                "parse",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_candidate_names_camel_case() {
+        let c = candidate_names("TestParseConfig");
+        assert_eq!(c, vec!["ParseConfig", "Parse", "parseConfig"]);
+
+        let c = candidate_names("testValidateToken");
+        assert_eq!(c[0], "ValidateToken");
+        assert!(c.contains(&"validateToken".to_string()));
+    }
+
+    #[test]
+    fn test_candidate_names_suffix_markers() {
+        assert_eq!(candidate_names("ParserTest")[0], "Parser");
+        assert_eq!(candidate_names("ParserTests")[0], "Parser");
+        assert_eq!(candidate_names("tokenize_spec")[0], "tokenize");
+    }
+
+    #[test]
+    fn test_candidate_names_without_marker() {
+        assert!(candidate_names("test_a").is_empty());
+        assert!(candidate_names("test").is_empty());
+        assert!(candidate_names("parses_config").is_empty());
+    }
+
+    #[test]
+    fn test_file_subject_variants() {
+        assert_eq!(
+            test_file_subject(Path::new("tests/test_parser.py")).as_deref(),
+            Some("parser")
+        );
+        assert_eq!(
+            test_file_subject(Path::new("pkg/parser_test.go")).as_deref(),
+            Some("parser")
+        );
+        assert_eq!(
+            test_file_subject(Path::new("src/parser.spec.ts")).as_deref(),
+            Some("parser")
+        );
+        assert_eq!(
+            test_file_subject(Path::new("src/ParserTest.java")).as_deref(),
+            Some("parser")
+        );
+        assert_eq!(test_file_subject(Path::new("tests/conftest.py")), None);
+    }
+
+    #[test]
+    fn test_is_test_element() {
+        let elem = StructuralElement {
+            symbol_path: "tests::helper".to_string(),
+            name: "helper".to_string(),
+            kind: ChunkKind::Function,
+            visibility: crate::types::Visibility::Private,
+            line_start: 1,
+            line_end: 3,
+            content: String::new(),
+            doc_comment: None,
+            references: Vec::new(),
+            extends: Vec::new(),
+            implements: Vec::new(),
+            complexity: crate::types::ComplexityMetrics::default(),
+        };
+        assert!(is_test_element(&elem, true));
+        assert!(!is_test_element(&elem, false));
+
+        let test_elem = StructuralElement {
+            kind: ChunkKind::Test,
+            ..elem
+        };
+        assert!(is_test_element(&test_elem, false));
+    }
+}
@@ -0,0 +1,146 @@
+//! Pipeline stage hooks.
+//!
+//! Hooks let embedders of the engine inject their own enrichment into the
+//! pipeline without forking it. A [`PipelineHook`] may implement any of four
+//! stages; each defaults to doing nothing:
+//!
+//! | Stage         | Runs                                         | Typical use                       |
+//! |---------------|----------------------------------------------|-----------------------------------|
+//! | `pre_chunk`   | after parsing, before chunking               | drop or rewrite structural elements |
+//! | `post_chunk`  | after chunking, before hashing and storage   | annotate chunk content or weights |
+//! | `pre_embed`   | on the text sent to the embedding model      | add domain glossary terms         |
+//! | `post_search` | on final results, before they are returned   | company-specific re-ranking       |
+//!
+//! Hooks run in registration order. `pre_chunk` and `post_chunk` run on the
+//! parse thread pool, so hooks must be `Send + Sync`. Changing what a hook
+//! does changes chunk content for files only when they are next reindexed;
+//! force a full reindex to apply it everywhere.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::parser::StructuralElement;
+use crate::types::{Chunk, SearchResult};
+
+/// A set of callbacks into the indexing and search pipeline.
+pub trait PipelineHook: Send + Sync {
+    /// Name used in logs.
+    fn name(&self) -> &str;
+
+    /// Transform the structural elements parsed from `path` (relative to the
+    /// repository root) before they are chunked.
+    fn pre_chunk(&self, _path: &Path, _elements: &mut Vec<StructuralElement>) {}
+
+    /// Annotate the chunks of `path` before they are hashed and stored.
+    fn post_chunk(&self, _path: &Path, _chunks: &mut Vec<Chunk>) {}
+
+    /// Rewrite the text embedded for `chunk`.
+    fn pre_embed(&self, _chunk: &Chunk, _text: &mut String) {}
+
+    /// Re-rank or filter the results of `query`.
+    fn post_search(&self, _query: &str, _results: &mut Vec<SearchResult>) {}
+}
+
+/// Registered hooks, applied in registration order.
+#[derive(Clone, Default)]
+pub struct HookRegistry {
+    hooks: Vec<Arc<dyn PipelineHook>>,
+}
+
+impl std::fmt::Debug for HookRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list()
+            .entries(self.hooks.iter().map(|h| h.name()))
+            .finish()
+    }
+}
+
+impl HookRegistry {
+    /// Add a hook after the ones already registered.
+    pub fn register(&mut self, hook: Arc<dyn PipelineHook>) {
+        tracing::info!(hook = hook.name(), "registered pipeline hook");
+        self.hooks.push(hook);
+    }
+
+    /// Whether no hooks are registered.
+    pub fn is_empty(&self) -> bool {
+        self.hooks.is_empty()
+    }
+
+    /// Run every `pre_chunk` hook.
+    pub fn pre_chunk(&self, path: &Path, elements: &mut Vec<StructuralElement>) {
+        for hook in &self.hooks {
+            hook.pre_chunk(path, elements);
+        }
+    }
+
+    /// Run every `post_chunk` hook.
+    pub fn post_chunk(&self, path: &Path, chunks: &mut Vec<Chunk>) {
+        for hook in &self.hooks {
+            hook.post_chunk(path, chunks);
+        }
+    }
+
+    /// Run every `pre_embed` hook.
+    pub fn pre_embed(&self, chunk: &Chunk, text: &mut String) {
+        for hook in &self.hooks {
+            hook.pre_embed(chunk, text);
+        }
+    }
+
+    /// Run every `post_search` hook.
+    pub fn post_search(&self, query: &str, results: &mut Vec<SearchResult>) {
+        for hook in &self.hooks {
+            hook.post_search(query, results);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Suffix(&'static str);
+
+    impl PipelineHook for Suffix {
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        fn pre_embed(&self, _chunk: &Chunk, text: &mut String) {
+            text.push_str(self.0);
+        }
+    }
+
+    #[test]
+    fn test_hooks_run_in_registration_order() {
+        let mut registry = HookRegistry::default();
+        assert!(registry.is_empty());
+        registry.register(Arc::new(Suffix("-a")));
+        registry.register(Arc::new(Suffix("-b")));
+
+        let chunk = Chunk {
+            id: 0,
+            file_id: 0,
+            symbol_path: String::new(),
+            kind: crate::types::ChunkKind::Function,
+            visibility: crate::types::Visibility::Public,
+            line_start: 1,
+            line_end: 1,
+            content: String::new(),
+            doc_comment: None,
+            token_count: 0,
+            weight: 1.0,
+            vector_id: None,
+            is_summary: false,
+            is_generated: false,
+            language: None,
+            content_hash: 0,
+            complexity: crate::types::ComplexityMetrics::default(),
+        };
+        let mut text = "fn".to_string();
+        registry.pre_embed(&chunk, &mut text);
+        assert_eq!(text, "fn-a-b");
+        assert_eq!(format!("{registry:?}"), r#"["-a", "-b"]"#);
+    }
+}
@@ -0,0 +1,308 @@
+//! Safe FTS5 query builder for user-supplied keyword queries.
+//!
+//! Supported syntax:
+//!
+//! - bare terms: `auth token` (implicit AND)
+//! - quoted phrases: `"token refresh"`
+//! - boolean operators (uppercase only): `auth AND token`, `jwt OR oauth`,
+//!   `auth NOT test`
+//! - prefix wildcards: `authent*`
+//!
+//! Every term and phrase is emitted as an FTS5 string literal, so characters
+//! that are FTS5 syntax (`-`, `:`, `^`, `(`, `::`, `->`) are matched
+//! literally instead of raising a syntax error. Dangling or repeated
+//! operators are dropped rather than rejected. FTS5 `NOT` is binary, so a
+//! leading `NOT term` is moved behind the rest of the query (`NOT test auth`
+//! → `auth NOT test`); a query of excluded terms alone has nothing to match.
+//!
+//! CJK text has no word separators, so `unicode61` indexes a whole run of
+//! Han/Kana/Hangul as one token. [`cjk_runs`] extracts those runs from a
+//! query so they can be matched as substrings against the trigram-tokenized
+//! `chunks_cjk` table instead.
+
+/// A compiled FTS5 `MATCH` expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FtsQuery {
+    /// Primary expression.
+    pub expr: String,
+    /// OR-joined fallback for plain multi-term queries without explicit
+    /// operators. `None` when the user wrote operators or a single term.
+    pub fallback: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Term { text: String, prefix: bool },
+    Phrase(String),
+    And,
+    Or,
+    Not,
+}
+
+impl Token {
+    fn is_operand(&self) -> bool {
+        matches!(self, Self::Term { .. } | Self::Phrase(_))
+    }
+
+    fn to_fts(&self) -> String {
+        match self {
+            Self::Term { text, prefix } => {
+                let quoted = quote(text);
+                if *prefix {
+                    format!("{quoted} *")
+                } else {
+                    quoted
+                }
+            }
+            Self::Phrase(text) => quote(text),
+            Self::And => "AND".to_string(),
+            Self::Or => "OR".to_string(),
+            Self::Not => "NOT".to_string(),
+        }
+    }
+}
+
+/// Compile a user query into an FTS5 expression.
+///
+/// Returns `None` when the query has no searchable terms.
+pub fn build(query: &str) -> Option<FtsQuery> {
+    let tokens = normalize(tokenize(query));
+    if tokens.is_empty() {
+        return None;
+    }
+
+    let has_operators = tokens.iter().any(|t| !t.is_operand());
+    let mut parts = Vec::with_capacity(tokens.len() * 2);
+    for (i, token) in tokens.iter().enumerate() {
+        // Adjacent operands get an explicit AND.
+        if i > 0 && token.is_operand() && tokens[i - 1].is_operand() {
+            parts.push("AND".to_string());
+        }
+        parts.push(token.to_fts());
+    }
+
+    let operands: Vec<String> = tokens
+        .iter()
+        .filter(|t| t.is_operand())
+        .map(Token::to_fts)
+        .collect();
+    let fallback = (!has_operators && operands.len() > 1).then(|| operands.join(" OR "));
+
+    Some(FtsQuery {
+        expr: parts.join(" "),
+        fallback,
+    })
+}
+
+/// Whether the query uses any operator syntax (phrases, boolean operators,
+/// or prefix wildcards). Such queries should be passed through verbatim
+/// rather than rewritten by natural-language query expansion.
+pub fn has_syntax(query: &str) -> bool {
+    tokenize(query)
+        .iter()
+        .any(|t| !matches!(t, Token::Term { prefix: false, .. }))
+}
+
+/// Whether `c` is a Han, Kana, or Hangul character.
+pub fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30FF}'     // Hiragana, Katakana
+        | '\u{3400}'..='\u{4DBF}'   // CJK Extension A
+        | '\u{4E00}'..='\u{9FFF}'   // CJK Unified Ideographs
+        | '\u{AC00}'..='\u{D7AF}'   // Hangul syllables
+        | '\u{F900}'..='\u{FAFF}'   // CJK Compatibility Ideographs
+        | '\u{FF66}'..='\u{FF9F}'   // Half-width Katakana
+        | '\u{20000}'..='\u{2FA1F}' // CJK Extensions B-F, Compatibility Supplement
+    )
+}
+
+/// Whether `text` contains any CJK character.
+pub fn contains_cjk(text: &str) -> bool {
+    text.chars().any(is_cjk)
+}
+
+/// Maximal runs of consecutive CJK characters in `query`.
+pub fn cjk_runs(query: &str) -> Vec<String> {
+    let mut runs = Vec::new();
+    let mut current = String::new();
+    for c in query.chars() {
+        if is_cjk(c) {
+            current.push(c);
+        } else if !current.is_empty() {
+            runs.push(std::mem::take(&mut current));
+        }
+    }
+    if !current.is_empty() {
+        runs.push(current);
+    }
+    runs
+}
+
+/// Quote text as an FTS5 string literal (`"` is escaped by doubling).
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('"', "\"\""))
+}
+
+/// Split a query into terms, phrases, and operators.
+fn tokenize(query: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = query.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        if c == '"' {
+            chars.next();
+            let mut phrase = String::new();
+            for ch in chars.by_ref() {
+                if ch == '"' {
+                    break;
+                }
+                phrase.push(ch);
+            }
+            let phrase = phrase.split_whitespace().collect::<Vec<_>>().join(" ");
+            if !phrase.is_empty() {
+                tokens.push(Token::Phrase(phrase));
+            }
+            continue;
+        }
+
+        let mut word = String::new();
+        while let Some(&ch) = chars.peek() {
+            if ch.is_whitespace() || ch == '"' {
+                break;
+            }
+            word.push(ch);
+            chars.next();
+        }
+
+        match word.as_str() {
+            "AND" => tokens.push(Token::And),
+            "OR" => tokens.push(Token::Or),
+            "NOT" => tokens.push(Token::Not),
+            _ => {
+                let prefix = word.ends_with('*');
+                let text = word.trim_end_matches('*');
+                if !text.is_empty() {
+                    tokens.push(Token::Term {
+                        text: text.to_string(),
+                        prefix,
+                    });
+                }
+            }
+        }
+    }
+
+    tokens
+}
+
+/// Drop operators that have no operand on one side, collapse runs of
+/// operators to the last one (`a AND OR b` → `a OR b`), and move a leading
+/// `NOT term` to the end.
+fn normalize(mut tokens: Vec<Token>) -> Vec<Token> {
+    let first = tokens
+        .iter()
+        .position(Token::is_operand)
+        .unwrap_or(tokens.len());
+    if first > 0 && tokens[first - 1] == Token::Not {
+        let rest = tokens.split_off(first + 1);
+        let excluded = tokens.split_off(first - 1);
+        let mut out = normalize(rest);
+        if !out.is_empty() {
+            out.extend(excluded);
+        }
+        return out;
+    }
+
+    let mut out: Vec<Token> = Vec::with_capacity(tokens.len());
+    for token in tokens {
+        if token.is_operand() {
+            out.push(token);
+        } else if out.last().is_some_and(Token::is_operand) {
+            out.push(token);
+        } else if let Some(last) = out.last_mut() {
+            *last = token;
+        }
+        // Leading operators are dropped.
+    }
+    while out.last().is_some_and(|t| !t.is_operand()) {
+        out.pop();
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expr(q: &str) -> String {
+        build(q).expect("non-empty query").expr
+    }
+
+    #[test]
+    fn test_plain_terms_are_anded_with_or_fallback() {
+        let q = build("auth token").expect("query");
+        assert_eq!(q.expr, r#""auth" AND "token""#);
+        assert_eq!(q.fallback.as_deref(), Some(r#""auth" OR "token""#));
+        assert!(build("auth").expect("query").fallback.is_none());
+    }
+
+    #[test]
+    fn test_boolean_operators() {
+        assert_eq!(expr("auth AND token"), r#""auth" AND "token""#);
+        assert_eq!(expr("jwt OR oauth"), r#""jwt" OR "oauth""#);
+        assert_eq!(expr("auth NOT test"), r#""auth" NOT "test""#);
+        // Lowercase words are ordinary terms.
+        assert_eq!(expr("read or write"), r#""read" AND "or" AND "write""#);
+        assert!(build("auth OR token").expect("query").fallback.is_none());
+    }
+
+    #[test]
+    fn test_phrases_and_prefixes() {
+        assert_eq!(
+            expr(r#""token refresh" authent*"#),
+            r#""token refresh" AND "authent" *"#
+        );
+        assert_eq!(expr(r#""unterminated phrase"#), r#""unterminated phrase""#);
+    }
+
+    #[test]
+    fn test_dangling_operators_and_special_chars() {
+        assert_eq!(expr("AND auth OR"), r#""auth""#);
+        assert_eq!(expr("a AND OR b"), r#""a" OR "b""#);
+        assert_eq!(expr("NOT test auth"), r#""auth" NOT "test""#);
+        assert_eq!(
+            expr("NOT test NOT mock auth token"),
+            r#""auth" AND "token" NOT "mock" NOT "test""#
+        );
+        assert_eq!(expr("OR NOT test auth"), r#""auth" NOT "test""#);
+        assert!(build("NOT test").is_none());
+        assert_eq!(
+            expr("std::fs -> Result"),
+            r#""std::fs" AND "->" AND "Result""#
+        );
+        assert!(build("  * AND \"\" ").is_none());
+    }
+
+    #[test]
+    fn test_cjk_runs() {
+        assert_eq!(
+            cjk_runs("验证用户 token をチェック"),
+            vec!["验证用户".to_string(), "をチェック".to_string()]
+        );
+        assert!(cjk_runs("plain ascii").is_empty());
+        assert!(contains_cjk("// 사용자 인증"));
+        assert!(!contains_cjk("naïve café"));
+    }
+
+    #[test]
+    fn test_has_syntax() {
+        assert!(!has_syntax("how does auth work"));
+        assert!(has_syntax("auth AND token"));
+        assert!(has_syntax("authent*"));
+        assert!(has_syntax("\"token refresh\""));
+    }
+}
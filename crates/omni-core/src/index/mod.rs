@@ -6,7 +6,10 @@
 //! ## Concurrency
 //!
 //! SQLite is configured in WAL mode for concurrent reads during writes.
-//! Only one writer is allowed at a time (SQLite constraint).
+//! Only one writer is allowed at a time (SQLite constraint). SQLite's own
+//! checkpoints never shrink the WAL file, so writers call
+//! [`MetadataIndex::maybe_checkpoint`] to truncate it on the schedule set
+//! by [`CheckpointPolicy`].
 //!
 //! ## Design
 //!
@@ -22,21 +25,129 @@
     clippy::redundant_closure_for_method_calls
 )]
 
-use std::path::Path;
+pub mod fts_query;
 
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use parking_lot::{Mutex, ReentrantMutex, ReentrantMutexGuard};
 use rusqlite::{params, Connection, OptionalExtension};
 
-use crate::error::OmniResult;
+use crate::config::{Bm25Weights, StorageConfig};
+use crate::error::{OmniError, OmniResult};
 use crate::types::{
-    Chunk, ChunkKind, DependencyEdge, DependencyKind, FileInfo, Language, Symbol, Visibility,
+    BackgroundJob, ChangeOp, Chunk, ChunkKind, ComplexityMetrics, DependencyEdge, DependencyKind,
+    EmbeddingModelInfo, FileChange, FileInfo, IndexMetadata, JobState, Language, ModuleSummary,
+    SearchLogEntry, SearchOptions, SummaryKind, Symbol, SymbolAlias, TodoComment, Visibility,
 };
 
 /// Current database schema version. Increment when schema changes.
-const SCHEMA_VERSION: i64 = 6;
+const SCHEMA_VERSION: i64 = 24;
+
+/// Well-known `index_meta` keys, read and written with
+/// [`MetadataIndex::get_meta`] / [`MetadataIndex::set_meta`].
+pub mod meta_keys {
+    /// When the database was created.
+    pub const CREATED_AT: &str = "created_at";
+    /// Version of the engine that last indexed the repository.
+    pub const ENGINE_VERSION: &str = "engine_version";
+    /// JSON embedding model info (name, revision, dimensions).
+    pub const EMBEDDING_MODEL: &str = "embedding_model";
+    /// Root commit of the indexed repository.
+    pub const REPO_FINGERPRINT: &str = "repo_fingerprint";
+    /// `origin` remote URL of the indexed repository.
+    pub const REPO_ORIGIN: &str = "repo_origin";
+    /// When the last index run finished.
+    pub const LAST_FULL_INDEX: &str = "last_full_index";
+    /// Absolute repository root the stored paths are relative to.
+    pub const REPO_ROOT: &str = "repo_root";
+    /// JSON repeated file headers left out of chunk content.
+    pub const HEADER_TEMPLATES: &str = "header_templates";
+    /// Whether `chunks_fts` uses the Porter stemmer (`true` / `false`).
+    pub const FTS_STEMMING: &str = "fts_stemming";
+}
+
+/// The `chunks_fts` table. It lives here rather than in schema.sql so
+/// [`MetadataIndex::set_stemming`] can recreate it with another tokenizer;
+/// `{tokenizer}` is filled in by [`chunks_fts_ddl`].
+const CHUNKS_FTS_DDL: &str = "CREATE VIRTUAL TABLE IF NOT EXISTS chunks_fts USING fts5(
+    content,
+    doc_comment,
+    symbol_path,
+    content='chunk_texts',
+    content_rowid='id',
+    tokenize='{tokenizer}'
+);";
+
+/// [`CHUNKS_FTS_DDL`] with the tokenizer for `stemming`.
+fn chunks_fts_ddl(stemming: bool) -> String {
+    let tokenizer = if stemming {
+        "porter unicode61 remove_diacritics 2"
+    } else {
+        "unicode61 remove_diacritics 2"
+    };
+    CHUNKS_FTS_DDL.replace("{tokenizer}", tokenizer)
+}
+
+/// Create every table, index, and trigger that does not exist yet. A new
+/// `chunks_fts` stems; [`MetadataIndex::set_stemming`] switches it.
+fn create_schema(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(&chunks_fts_ddl(true))?;
+    conn.execute_batch(include_str!("schema.sql"))
+}
+
+/// Longest chain of re-exports [`MetadataIndex::resolve_alias`] follows.
+const MAX_ALIAS_HOPS: usize = 8;
+
+/// When [`MetadataIndex::maybe_checkpoint`] truncates the WAL.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CheckpointPolicy {
+    /// Checkpoint a non-empty WAL once this long has passed since the last
+    /// checkpoint. `None` disables time-based checkpoints.
+    pub interval: Option<Duration>,
+    /// Checkpoint once the WAL file is at least this many bytes. `None`
+    /// disables the size check.
+    pub wal_size_threshold: Option<u64>,
+}
+
+impl From<&StorageConfig> for CheckpointPolicy {
+    fn from(config: &StorageConfig) -> Self {
+        Self {
+            interval: (config.checkpoint_interval_secs > 0)
+                .then(|| Duration::from_secs(config.checkpoint_interval_secs)),
+            wal_size_threshold: (config.wal_size_threshold_mb > 0)
+                .then(|| config.wal_size_threshold_mb.saturating_mul(1024 * 1024)),
+        }
+    }
+}
+
+impl Default for CheckpointPolicy {
+    fn default() -> Self {
+        Self::from(&StorageConfig::default())
+    }
+}
+
+/// Outcome of `PRAGMA wal_checkpoint(TRUNCATE)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WalCheckpoint {
+    /// A reader or writer blocked the checkpoint; the WAL was not truncated.
+    pub busy: bool,
+    /// Frames in the WAL before the checkpoint.
+    pub wal_frames: i64,
+    /// Frames copied back into the database.
+    pub checkpointed_frames: i64,
+}
 
 /// SQLite-backed metadata and full-text search index.
 pub struct MetadataIndex {
-    conn: Connection,
+    /// Shared by every thread reading through `&self`; reentrant because
+    /// index methods call one another while a statement is open.
+    conn: ReentrantMutex<Connection>,
+    /// WAL file of a writable database; `None` for read-only replicas,
+    /// which never checkpoint.
+    wal_path: Option<PathBuf>,
+    checkpoint_policy: CheckpointPolicy,
+    last_checkpoint: Mutex<Instant>,
 }
 
 impl MetadataIndex {
@@ -57,23 +168,174 @@ impl MetadataIndex {
         conn.pragma_update(None, "busy_timeout", "5000")?; // 5s retry on SQLITE_BUSY
         conn.pragma_update(None, "mmap_size", "268435456")?; // 256MB memory-mapped I/O
         conn.pragma_update(None, "temp_store", "MEMORY")?;
-
-        let index = Self { conn };
+        register_functions(&conn)?;
+
+        let mut wal_path = db_path.as_os_str().to_owned();
+        wal_path.push("-wal");
+        let index = Self {
+            conn: ReentrantMutex::new(conn),
+            wal_path: Some(PathBuf::from(wal_path)),
+            checkpoint_policy: CheckpointPolicy::default(),
+            last_checkpoint: Mutex::new(Instant::now()),
+        };
+        // Migrate first: schema.sql references columns older databases lack.
+        let created = index.ensure_schema_version()?;
         index.ensure_schema()?;
-        index.ensure_schema_version()?;
+        if created {
+            index.conn.lock().execute(
+                "INSERT OR IGNORE INTO index_meta (key, value) VALUES (?1, datetime('now'))",
+                params![meta_keys::CREATED_AT],
+            )?;
+        }
 
         Ok(index)
     }
 
+    /// Open an existing index database read-only, for serving replicas.
+    ///
+    /// The file is opened `immutable`, so SQLite takes no locks and ignores
+    /// any WAL: the database must be fully checkpointed (a snapshot, or one
+    /// whose writer has closed) and must not change while it is open. No
+    /// migrations run, so the schema must match this build exactly.
+    pub fn open_read_only(db_path: &Path) -> OmniResult<Self> {
+        Self::open_without_writes(db_path, true)
+    }
+
+    /// Open an existing index database read-only while other processes may
+    /// be writing it, for diagnostics.
+    ///
+    /// Unlike [`Self::open_read_only`], SQLite's usual locks are taken and
+    /// the WAL is read, so committed writes are visible. Nothing is migrated
+    /// or written, so the schema must match this build exactly.
+    pub fn open_for_reading(db_path: &Path) -> OmniResult<Self> {
+        Self::open_without_writes(db_path, false)
+    }
+
+    fn open_without_writes(db_path: &Path, immutable: bool) -> OmniResult<Self> {
+        if !db_path.exists() {
+            return Err(OmniError::NotFound {
+                entity: format!("index database {}", db_path.display()),
+            });
+        }
+        let uri = format!(
+            "file:{}{}",
+            db_path
+                .to_string_lossy()
+                .replace('%', "%25")
+                .replace('?', "%3f")
+                .replace('#', "%23"),
+            if immutable { "?immutable=1" } else { "" }
+        );
+        let conn = Connection::open_with_flags(
+            uri,
+            rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY
+                | rusqlite::OpenFlags::SQLITE_OPEN_URI
+                | rusqlite::OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )?;
+        conn.pragma_update(None, "cache_size", "-64000")?;
+        conn.pragma_update(None, "mmap_size", "268435456")?;
+        conn.pragma_update(None, "temp_store", "MEMORY")?;
+        register_functions(&conn)?;
+
+        let version: Option<i64> = conn
+            .query_row("SELECT MAX(version) FROM schema_version", [], |row| {
+                row.get(0)
+            })
+            .optional()?
+            .flatten();
+        if version != Some(SCHEMA_VERSION) {
+            return Err(OmniError::Config {
+                details: format!(
+                    "index schema version {} does not match this build ({SCHEMA_VERSION}); \
+                     rebuild the index with a matching version",
+                    version.map_or_else(|| "unknown".to_string(), |v| v.to_string())
+                ),
+            });
+        }
+        Ok(Self {
+            conn: ReentrantMutex::new(conn),
+            wal_path: None,
+            checkpoint_policy: CheckpointPolicy::default(),
+            last_checkpoint: Mutex::new(Instant::now()),
+        })
+    }
+
+    /// Replace the WAL checkpoint schedule.
+    pub fn set_checkpoint_policy(&mut self, policy: CheckpointPolicy) {
+        self.checkpoint_policy = policy;
+    }
+
+    /// Current size of the WAL file in bytes (0 when there is none).
+    pub fn wal_size(&self) -> u64 {
+        self.wal_path
+            .as_deref()
+            .and_then(|path| std::fs::metadata(path).ok())
+            .map_or(0, |meta| meta.len())
+    }
+
+    /// Checkpoint the WAL into the database and truncate it to zero bytes.
+    ///
+    /// Readers still using older snapshots make the checkpoint report
+    /// `busy`; it is retried on the next scheduled call.
+    pub fn checkpoint(&self) -> OmniResult<WalCheckpoint> {
+        let (busy, wal_frames, checkpointed_frames): (i64, i64, i64) =
+            self.conn
+                .lock()
+                .query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |row| {
+                    Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+                })?;
+        *self.last_checkpoint.lock() = Instant::now();
+        Ok(WalCheckpoint {
+            busy: busy != 0,
+            wal_frames,
+            checkpointed_frames,
+        })
+    }
+
+    /// Checkpoint if the policy says one is due: the WAL has reached the
+    /// size threshold, or it is non-empty and the interval has elapsed.
+    ///
+    /// Does nothing on read-only replicas and inside a batch transaction.
+    /// Cheap enough to call after every write.
+    pub fn maybe_checkpoint(&self) -> OmniResult<Option<WalCheckpoint>> {
+        if self.wal_path.is_none() || !self.conn.lock().is_autocommit() {
+            return Ok(None);
+        }
+        let wal_size = self.wal_size();
+        if wal_size == 0 {
+            return Ok(None);
+        }
+        let policy = self.checkpoint_policy;
+        let too_large = policy
+            .wal_size_threshold
+            .is_some_and(|threshold| wal_size >= threshold);
+        let overdue = policy
+            .interval
+            .is_some_and(|interval| self.last_checkpoint.lock().elapsed() >= interval);
+        if !too_large && !overdue {
+            return Ok(None);
+        }
+
+        let result = self.checkpoint()?;
+        tracing::debug!(
+            wal_bytes = wal_size,
+            frames = result.checkpointed_frames,
+            busy = result.busy,
+            "WAL checkpoint"
+        );
+        Ok(Some(result))
+    }
+
     /// Create all tables and indexes if they don't exist.
     fn ensure_schema(&self) -> OmniResult<()> {
-        self.conn.execute_batch(include_str!("schema.sql"))?;
+        create_schema(&self.conn.lock())?;
         Ok(())
     }
 
-    /// Ensure schema version is tracked and compatible.
-    fn ensure_schema_version(&self) -> OmniResult<()> {
-        self.conn.execute_batch(
+    /// Ensure schema version is tracked and compatible. Returns whether the
+    /// database is new.
+    fn ensure_schema_version(&self) -> OmniResult<bool> {
+        self.conn.lock().execute_batch(
             "CREATE TABLE IF NOT EXISTS schema_version (
                 version INTEGER NOT NULL,
                 migrated_at TEXT NOT NULL DEFAULT (datetime('now'))
@@ -82,6 +344,7 @@ impl MetadataIndex {
 
         let current: Option<i64> = self
             .conn
+            .lock()
             .query_row("SELECT MAX(version) FROM schema_version", [], |row| {
                 row.get(0)
             })
@@ -91,17 +354,18 @@ impl MetadataIndex {
         match current {
             None => {
                 // First run -- set initial version
-                self.conn.execute(
+                self.conn.lock().execute(
                     "INSERT INTO schema_version (version) VALUES (?1)",
                     params![SCHEMA_VERSION],
                 )?;
+                return Ok(true);
             }
             Some(v) if v < SCHEMA_VERSION => {
                 tracing::info!(from = v, to = SCHEMA_VERSION, "schema migration required");
                 // v1 → v2: add content_hash column to chunks table.
                 // ALTER TABLE … ADD COLUMN is safe on SQLite (no data loss).
                 if v < 2 {
-                    self.conn.execute_batch(
+                    self.conn.lock().execute_batch(
                         "ALTER TABLE chunks ADD COLUMN content_hash INTEGER NOT NULL DEFAULT 0;",
                     )?;
                     tracing::info!("migrated schema: added content_hash column to chunks");
@@ -109,7 +373,7 @@ impl MetadataIndex {
                 // v2 → v3: add commits_fts virtual table + external_docs table.
                 // CREATE VIRTUAL TABLE IF NOT EXISTS is safe to run on existing dbs.
                 if v < 3 {
-                    self.conn.execute_batch(
+                    self.conn.lock().execute_batch(
                         "CREATE VIRTUAL TABLE IF NOT EXISTS commits_fts USING fts5(
                             message,
                             summary,
@@ -132,7 +396,7 @@ impl MetadataIndex {
                 }
                 // v3 → v4: add commit_files junction table for O(1) path lookup.
                 if v < 4 {
-                    self.conn.execute_batch(
+                    self.conn.lock().execute_batch(
                         "CREATE TABLE IF NOT EXISTS commit_files (
                             commit_hash  TEXT NOT NULL REFERENCES commits(hash) ON DELETE CASCADE,
                             file_path    TEXT NOT NULL,
@@ -145,7 +409,7 @@ impl MetadataIndex {
                 }
                 // v4 → v5: add file_graph_edges table for persistent FileDependencyGraph.
                 if v < 5 {
-                    self.conn.execute_batch(
+                    self.conn.lock().execute_batch(
                         "CREATE TABLE IF NOT EXISTS file_graph_edges (
                             source_path  TEXT NOT NULL,
                             target_path  TEXT NOT NULL,
@@ -160,7 +424,7 @@ impl MetadataIndex {
                 }
                 // v5 → v6: add sparse_vectors table for BGE-M3 SPLADE output.
                 if v < 6 {
-                    self.conn.execute_batch(
+                    self.conn.lock().execute_batch(
                         "CREATE TABLE IF NOT EXISTS sparse_vectors (
                             chunk_id   INTEGER NOT NULL PRIMARY KEY REFERENCES chunks(id) ON DELETE CASCADE,
                             tokens     TEXT    NOT NULL
@@ -169,7 +433,225 @@ impl MetadataIndex {
                     )?;
                     tracing::info!("migrated schema v6: sparse_vectors table");
                 }
-                self.conn.execute(
+                // v6 → v7: add structural complexity columns to chunks.
+                if v < 7 {
+                    self.conn.lock().execute_batch(
+                        "ALTER TABLE chunks ADD COLUMN complexity INTEGER NOT NULL DEFAULT 0;
+                        ALTER TABLE chunks ADD COLUMN nesting_depth INTEGER NOT NULL DEFAULT 0;",
+                    )?;
+                    tracing::info!("migrated schema v7: chunk complexity metrics");
+                }
+                // v7 → v8: move chunk bodies into the content-addressed chunk_blobs table.
+                if v < 8 {
+                    self.migrate_chunk_blobs()?;
+                    tracing::info!("migrated schema v8: deduplicated chunk content");
+                }
+                // v8 → v9: flag chunks containing CJK text for the trigram FTS table.
+                if v < 9 {
+                    self.migrate_cjk_flags()?;
+                    // schema.sql indexes the v21 column.
+                    self.add_generated_column()?;
+                    // Recreate FTS tables/triggers and repopulate them from chunk_texts.
+                    create_schema(&self.conn.lock())?;
+                    self.conn.lock().execute_batch(
+                        "INSERT INTO chunks_fts(chunks_fts) VALUES('rebuild');
+                        INSERT INTO chunks_cjk(chunks_cjk) VALUES('rebuild');",
+                    )?;
+                    tracing::info!("migrated schema v9: CJK trigram index");
+                }
+                // v9 → v10: add search_log table for opt-in search telemetry.
+                if v < 10 {
+                    self.conn.lock().execute_batch(
+                        "CREATE TABLE IF NOT EXISTS search_log (
+                            id          INTEGER PRIMARY KEY AUTOINCREMENT,
+                            logged_at   TEXT    NOT NULL DEFAULT (datetime('now')),
+                            query       TEXT    NOT NULL,
+                            query_type  TEXT    NOT NULL,
+                            latency_ms  REAL    NOT NULL,
+                            signals     TEXT    NOT NULL,
+                            chunk_ids   TEXT    NOT NULL,
+                            error       TEXT
+                        );",
+                    )?;
+                    tracing::info!("migrated schema v10: search_log table");
+                }
+                // v10 → v11: add index_meta key/value table.
+                if v < 11 {
+                    self.conn.lock().execute_batch(
+                        "CREATE TABLE IF NOT EXISTS index_meta (
+                            key    TEXT PRIMARY KEY,
+                            value  TEXT NOT NULL
+                        );",
+                    )?;
+                    tracing::info!("migrated schema v11: index_meta table");
+                }
+                // v11 → v12: per-file and per-directory summaries.
+                if v < 12 {
+                    self.conn.lock().execute_batch(
+                        "CREATE TABLE IF NOT EXISTS summaries (
+                            path         TEXT    PRIMARY KEY,
+                            kind         TEXT    NOT NULL,
+                            summary      TEXT    NOT NULL,
+                            key_symbols  TEXT    NOT NULL,
+                            file_count   INTEGER NOT NULL,
+                            chunk_count  INTEGER NOT NULL,
+                            updated_at   TEXT    NOT NULL DEFAULT (datetime('now'))
+                        );",
+                    )?;
+                    tracing::info!("migrated schema v12: summaries table");
+                }
+                // v12 → v13: append-only change journal.
+                if v < 13 {
+                    self.conn.lock().execute_batch(
+                        "CREATE TABLE IF NOT EXISTS changes (
+                            seq        INTEGER PRIMARY KEY AUTOINCREMENT,
+                            file       TEXT    NOT NULL,
+                            op         TEXT    NOT NULL,
+                            timestamp  TEXT    NOT NULL DEFAULT (datetime('now'))
+                        );",
+                    )?;
+                    tracing::info!("migrated schema v13: changes journal");
+                }
+                // v13 → v14: cross-language binding exports.
+                if v < 14 {
+                    self.conn.lock().execute_batch(
+                        "CREATE TABLE IF NOT EXISTS binding_exports (
+                            symbol_id INTEGER NOT NULL REFERENCES symbols(id) ON DELETE CASCADE,
+                            abi       TEXT    NOT NULL,
+                            name      TEXT    NOT NULL,
+                            PRIMARY KEY (symbol_id, abi, name)
+                        );
+                        CREATE INDEX IF NOT EXISTS idx_binding_exports_name
+                            ON binding_exports(abi, name);",
+                    )?;
+                    tracing::info!("migrated schema v14: binding_exports table");
+                }
+                // v14 → v15: resumable background jobs.
+                if v < 15 {
+                    self.conn.lock().execute_batch(
+                        "CREATE TABLE IF NOT EXISTS jobs (
+                            id          INTEGER PRIMARY KEY AUTOINCREMENT,
+                            kind        TEXT    NOT NULL,
+                            state       TEXT    NOT NULL DEFAULT 'running',
+                            total       INTEGER NOT NULL DEFAULT 0,
+                            done        INTEGER NOT NULL DEFAULT 0,
+                            error       TEXT,
+                            created_at  TEXT    NOT NULL DEFAULT (datetime('now')),
+                            updated_at  TEXT    NOT NULL DEFAULT (datetime('now'))
+                        );
+                        CREATE INDEX IF NOT EXISTS idx_jobs_state ON jobs(state, kind);",
+                    )?;
+                    tracing::info!("migrated schema v15: jobs table");
+                }
+                // v15 → v16: case-insensitive path index. On Windows, rows
+                // that differ from another only in case are one file indexed
+                // twice; keep the most recent.
+                if v < 16 {
+                    let conn = self.conn.lock();
+                    conn.execute_batch(
+                        "CREATE INDEX IF NOT EXISTS idx_files_path_nocase
+                            ON files(path COLLATE NOCASE);",
+                    )?;
+                    if crate::paths::CASE_INSENSITIVE {
+                        conn.execute(
+                            "DELETE FROM files WHERE id NOT IN
+                             (SELECT MAX(id) FROM files GROUP BY path COLLATE NOCASE)",
+                            [],
+                        )?;
+                    }
+                    tracing::info!("migrated schema v16: case-insensitive path index");
+                }
+                // v16 → v17: source encoding of files that were not UTF-8.
+                if v < 17 {
+                    self.conn
+                        .lock()
+                        .execute_batch("ALTER TABLE files ADD COLUMN encoding TEXT;")?;
+                    tracing::info!("migrated schema v17: files.encoding");
+                }
+                // v17 → v18: re-export aliases. Filled as files are
+                // re-parsed; a full reindex picks up existing barrels.
+                if v < 18 {
+                    self.conn.lock().execute_batch(
+                        "CREATE TABLE IF NOT EXISTS symbol_aliases (
+                            id          INTEGER PRIMARY KEY,
+                            file_id     INTEGER NOT NULL REFERENCES files(id) ON DELETE CASCADE,
+                            alias_fqn   TEXT    NOT NULL,
+                            target_fqn  TEXT    NOT NULL,
+                            line        INTEGER NOT NULL
+                        );
+                        CREATE INDEX IF NOT EXISTS idx_symbol_aliases_alias
+                            ON symbol_aliases(alias_fqn);
+                        CREATE INDEX IF NOT EXISTS idx_symbol_aliases_file
+                            ON symbol_aliases(file_id);",
+                    )?;
+                    tracing::info!("migrated schema v18: symbol_aliases table");
+                }
+                // v18 → v19: resolution confidence per dependency edge.
+                // Existing edges count as exact until their file is reindexed.
+                // Databases older than the table get it from schema.sql.
+                if v < 19 {
+                    let conn = self.conn.lock();
+                    if !has_column(&conn, "dependencies", "confidence")? {
+                        conn.execute_batch(
+                            "ALTER TABLE dependencies ADD COLUMN confidence REAL NOT NULL DEFAULT 1.0;",
+                        )?;
+                    }
+                    tracing::info!("migrated schema v19: dependencies.confidence");
+                }
+                // v19 → v20: tombstones for vectors of deleted files, purged
+                // once vectors.bin is saved without them.
+                if v < 20 {
+                    self.conn.lock().execute_batch(
+                        "CREATE TABLE IF NOT EXISTS vector_tombstones (
+                            vector_id   INTEGER PRIMARY KEY,
+                            deleted_at  TEXT    NOT NULL DEFAULT (datetime('now'))
+                        );",
+                    )?;
+                    tracing::info!("migrated schema v20: vector_tombstones table");
+                }
+                // v20 → v21: flag chunks of generated code.
+                if v < 21 {
+                    self.add_generated_column()?;
+                    tracing::info!("migrated schema v21: chunks.generated");
+                }
+                // v21 → v22: TODO / FIXME / HACK comments. Filled as files
+                // are re-parsed; a full reindex picks up existing ones.
+                if v < 22 {
+                    self.conn.lock().execute_batch(
+                        "CREATE TABLE IF NOT EXISTS todos (
+                            id          INTEGER PRIMARY KEY,
+                            file_id     INTEGER NOT NULL REFERENCES files(id) ON DELETE CASCADE,
+                            line        INTEGER NOT NULL,
+                            tag         TEXT    NOT NULL,
+                            assignee    TEXT,
+                            text        TEXT    NOT NULL
+                        );
+                        CREATE INDEX IF NOT EXISTS idx_todos_file ON todos(file_id);",
+                    )?;
+                    tracing::info!("migrated schema v22: todos table");
+                }
+                // v22 → v23: language of code embedded in Markdown / HTML.
+                // Existing chunks keep NULL (the file's language) until re-parsed.
+                if v < 23 {
+                    self.add_chunk_language_column()?;
+                    tracing::info!("migrated schema v23: chunks.language");
+                }
+                // v23 → v24: per-file parts of symbols declared across
+                // files (C# partial classes).
+                if v < 24 {
+                    self.conn.lock().execute_batch(
+                        "CREATE TABLE IF NOT EXISTS symbol_parts (
+                            symbol_id   INTEGER NOT NULL REFERENCES symbols(id) ON DELETE CASCADE,
+                            file_id     INTEGER NOT NULL REFERENCES files(id) ON DELETE CASCADE,
+                            line        INTEGER NOT NULL,
+                            chunk_id    INTEGER REFERENCES chunks(id),
+                            PRIMARY KEY (symbol_id, file_id)
+                        );
+                        CREATE INDEX IF NOT EXISTS idx_symbol_parts_file ON symbol_parts(file_id);",
+                    )?;
+                    tracing::info!("migrated schema v24: symbol_parts table");
+                }
+                self.conn.lock().execute(
                     "INSERT INTO schema_version (version) VALUES (?1)",
                     params![SCHEMA_VERSION],
                 )?;
@@ -186,25 +668,119 @@ impl MetadataIndex {
             }
         }
 
+        Ok(false)
+    }
+
+    /// Move `chunks.content` into `chunk_blobs`. The FTS index is dropped here
+    /// and rebuilt once all migrations have run.
+    fn migrate_chunk_blobs(&self) -> OmniResult<()> {
+        let conn = self.conn.lock();
+        let tx = conn.unchecked_transaction()?;
+        tx.execute_batch(
+            "DROP TRIGGER IF EXISTS chunks_ai;
+            DROP TRIGGER IF EXISTS chunks_ad;
+            DROP TRIGGER IF EXISTS chunks_au;
+            DROP TABLE IF EXISTS chunks_fts;
+            CREATE TABLE IF NOT EXISTS chunk_blobs (
+                hash    INTEGER PRIMARY KEY,
+                content TEXT    NOT NULL
+            );
+            ALTER TABLE chunks ADD COLUMN blob_hash INTEGER NOT NULL DEFAULT 0;",
+        )?;
+        {
+            let mut select = tx.prepare("SELECT id, content FROM chunks")?;
+            let mut update = tx.prepare("UPDATE chunks SET blob_hash = ?1 WHERE id = ?2")?;
+            let rows = select.query_map([], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+            })?;
+            for row in rows {
+                let (id, content) = row?;
+                let hash = store_blob(&tx, &content)?;
+                update.execute(params![hash, id])?;
+            }
+        }
+        tx.execute_batch("ALTER TABLE chunks DROP COLUMN content;")?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Add `chunks.has_cjk` and compute it for existing rows.
+    fn migrate_cjk_flags(&self) -> OmniResult<()> {
+        let conn = self.conn.lock();
+        let tx = conn.unchecked_transaction()?;
+        tx.execute_batch("ALTER TABLE chunks ADD COLUMN has_cjk INTEGER NOT NULL DEFAULT 0;")?;
+        {
+            let mut select = tx.prepare(
+                "SELECT c.id, b.content, c.doc_comment
+                 FROM chunks c JOIN chunk_blobs b ON b.hash = c.blob_hash",
+            )?;
+            let mut update = tx.prepare("UPDATE chunks SET has_cjk = 1 WHERE id = ?1")?;
+            let rows = select.query_map([], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                ))
+            })?;
+            for row in rows {
+                let (id, content, doc) = row?;
+                if chunk_has_cjk(&content, doc.as_deref()) {
+                    update.execute(params![id])?;
+                }
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Add `chunks.generated` unless an earlier migration step already did.
+    fn add_generated_column(&self) -> OmniResult<()> {
+        let conn = self.conn.lock();
+        if !has_column(&conn, "chunks", "generated")? {
+            conn.execute_batch(
+                "ALTER TABLE chunks ADD COLUMN generated INTEGER NOT NULL DEFAULT 0;",
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Add `chunks.language` unless an earlier migration step already did.
+    fn add_chunk_language_column(&self) -> OmniResult<()> {
+        let conn = self.conn.lock();
+        if !has_column(&conn, "chunks", "language")? {
+            conn.execute_batch("ALTER TABLE chunks ADD COLUMN language TEXT;")?;
+        }
         Ok(())
     }
 
     /// Clear all indexed repository data while keeping schema and indexes intact.
     pub fn clear_all(&self) -> OmniResult<()> {
-        let tx = self.conn.unchecked_transaction()?;
+        let conn = self.conn.lock();
+        let tx = conn.unchecked_transaction()?;
 
         // Clear in dependency-safe order.
         tx.execute("DELETE FROM dependencies", [])?;
+        tx.execute("DELETE FROM binding_exports", [])?;
+        tx.execute("DELETE FROM symbol_aliases", [])?;
+        tx.execute("DELETE FROM todos", [])?;
+        tx.execute("DELETE FROM symbol_parts", [])?;
         tx.execute("DELETE FROM symbols", [])?;
         tx.execute("DELETE FROM chunks", [])?;
+        tx.execute("DELETE FROM chunk_blobs", [])?;
         tx.execute("DELETE FROM files", [])?;
         tx.execute("DELETE FROM commits", [])?;
+        tx.execute("DELETE FROM summaries", [])?;
+        tx.execute("DELETE FROM vector_tombstones", [])?;
 
         // Ensure FTS content is emptied as well.
         tx.execute("DELETE FROM chunks_fts", [])?;
+        tx.execute("DELETE FROM chunks_cjk", [])?;
         // Tolerate missing commits_fts (may not exist on older dbs before v3 migration)
         let _ = tx.execute("DELETE FROM commits_fts", []);
 
+        // The journal itself is append-only: record the reset instead.
+        record_change(&tx, "", ChangeOp::Cleared)?;
+
         tx.commit()?;
         Ok(())
     }
@@ -215,50 +791,44 @@ impl MetadataIndex {
 
     /// Insert or update a file record. Returns the file ID.
     pub fn upsert_file(&self, file: &FileInfo) -> OmniResult<i64> {
-        self.conn.execute(
+        let conn = self.conn.lock();
+        let path = file_path_key(&conn, &file.path)?;
+        // RETURNING yields the id for updates too; last_insert_rowid() would
+        // report the journal row written below.
+        let id: i64 = conn.query_row(
             "INSERT INTO files (path, language, hash, size_bytes, last_modified)
              VALUES (?1, ?2, ?3, ?4, datetime('now'))
              ON CONFLICT(path) DO UPDATE SET
                 language = excluded.language,
                 hash = excluded.hash,
                 size_bytes = excluded.size_bytes,
-                indexed_at = datetime('now'),
-                last_modified = excluded.last_modified",
+                encoding = NULL,
+                indexed_at = datetime('now')
+             RETURNING id",
             params![
-                file.path.to_string_lossy().as_ref(),
+                path,
                 file.language.as_str(),
                 file.content_hash,
                 file.size_bytes,
             ],
+            |row| row.get(0),
         )?;
-
-        let id = self.conn.last_insert_rowid();
-        // If the row was updated (not inserted), last_insert_rowid returns 0
-        // In that case, query for the existing ID
-        if id == 0 {
-            let existing_id: i64 = self.conn.query_row(
-                "SELECT id FROM files WHERE path = ?1",
-                params![file.path.to_string_lossy().as_ref()],
-                |row| row.get(0),
-            )?;
-            Ok(existing_id)
-        } else {
-            Ok(id)
-        }
+        record_change(&conn, &path, ChangeOp::Indexed)?;
+        Ok(id)
     }
 
     /// Get a file record by path.
     pub fn get_file_by_path(&self, path: &Path) -> OmniResult<Option<FileInfo>> {
-        let result = self
-            .conn
+        let conn = self.conn.lock();
+        let result = conn
             .query_row(
                 "SELECT id, path, language, hash, size_bytes FROM files WHERE path = ?1",
-                params![path.to_string_lossy().as_ref()],
+                params![file_path_key(&conn, path)?],
                 |row| {
                     Ok(FileInfo {
                         id: row.get(0)?,
                         path: std::path::PathBuf::from(row.get::<_, String>(1)?),
-                        language: Language::from_extension(&row.get::<_, String>(2)?),
+                        language: Language::from_str_lossy(&row.get::<_, String>(2)?),
                         content_hash: row.get(3)?,
                         size_bytes: row.get(4)?,
                     })
@@ -269,23 +839,43 @@ impl MetadataIndex {
         Ok(result)
     }
 
+    /// Ids of every chunk matching the filters of `options`. Dates are
+    /// normalized by SQLite, so any ISO 8601 date or datetime (with or
+    /// without offset) is accepted.
+    pub fn chunk_ids_matching(
+        &self,
+        options: &SearchOptions,
+    ) -> OmniResult<std::collections::HashSet<i64>> {
+        let conn = self.conn.lock();
+        let mut values = Vec::new();
+        let conditions = scope_conditions(&conn, options, &mut values)?;
+        let sql = format!(
+            "SELECT c.id FROM chunks c JOIN files f ON f.id = c.file_id WHERE {}",
+            conditions.unwrap_or_else(|| "1".to_string())
+        );
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map(rusqlite::params_from_iter(values), |row| row.get(0))?;
+        let mut ids = std::collections::HashSet::new();
+        for id in rows {
+            ids.insert(id?);
+        }
+        Ok(ids)
+    }
+
     /// Get a file record by its database ID.
     pub fn get_file_by_id(&self, id: i64) -> OmniResult<Option<FileInfo>> {
-        let result = self
-            .conn
-            .query_row(
-                "SELECT id, path, language, hash, size_bytes FROM files WHERE id = ?1",
-                params![id],
-                |row| {
-                    Ok(FileInfo {
-                        id: row.get(0)?,
-                        path: std::path::PathBuf::from(row.get::<_, String>(1)?),
-                        language: Language::from_extension(&row.get::<_, String>(2)?),
-                        content_hash: row.get(3)?,
-                        size_bytes: row.get(4)?,
-                    })
-                },
-            )
+        let conn = self.conn.lock();
+        let result = conn
+            .prepare_cached("SELECT id, path, language, hash, size_bytes FROM files WHERE id = ?1")?
+            .query_row(params![id], |row| {
+                Ok(FileInfo {
+                    id: row.get(0)?,
+                    path: std::path::PathBuf::from(row.get::<_, String>(1)?),
+                    language: Language::from_str_lossy(&row.get::<_, String>(2)?),
+                    content_hash: row.get(3)?,
+                    size_bytes: row.get(4)?,
+                })
+            })
             .optional()?;
 
         Ok(result)
@@ -293,11 +883,11 @@ impl MetadataIndex {
 
     /// Get the hash of an indexed file (for change detection).
     pub fn get_file_hash(&self, path: &Path) -> OmniResult<Option<String>> {
-        let hash = self
-            .conn
+        let conn = self.conn.lock();
+        let hash = conn
             .query_row(
                 "SELECT hash FROM files WHERE path = ?1",
-                params![path.to_string_lossy().as_ref()],
+                params![file_path_key(&conn, path)?],
                 |row| row.get(0),
             )
             .optional()?;
@@ -307,24 +897,153 @@ impl MetadataIndex {
 
     /// Delete a file and all its associated chunks and symbols.
     pub fn delete_file(&self, path: &Path) -> OmniResult<bool> {
-        let changes = self.conn.execute(
-            "DELETE FROM files WHERE path = ?1",
-            params![path.to_string_lossy().as_ref()],
-        )?;
+        let conn = self.conn.lock();
+        let path = file_path_key(&conn, path)?;
+        release_symbol_parts_by_path(&conn, &path)?;
+        let changes = conn.execute("DELETE FROM files WHERE path = ?1", params![path])?;
+        if changes > 0 {
+            record_change(&conn, &path, ChangeOp::Deleted)?;
+        }
         Ok(changes > 0)
     }
 
+    /// Delete the file at `path` and tombstone its chunks' vector ids, in
+    /// one transaction. Returns whether a row was deleted and the ids.
+    ///
+    /// This is the first phase of a crash-safe delete: the caller removes
+    /// the ids from the vector index and, once the removal is on disk, calls
+    /// [`Self::purge_vector_tombstones`]. Tombstones that survive a crash
+    /// are finished on the next start.
+    pub fn delete_file_and_tombstone(&self, path: &Path) -> OmniResult<(bool, Vec<u64>)> {
+        let conn = self.conn.lock();
+        let path = file_path_key(&conn, path)?;
+        conn.execute_batch("SAVEPOINT delete_file_sp")?;
+        let result: OmniResult<(bool, Vec<u64>)> = (|| {
+            let mut stmt = conn.prepare_cached(
+                "SELECT c.vector_id FROM chunks c JOIN files f ON f.id = c.file_id
+                 WHERE f.path = ?1 AND c.vector_id IS NOT NULL",
+            )?;
+            let ids = stmt
+                .query_map(params![path], |row| row.get::<_, i64>(0))?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            let mut tombstone = conn.prepare_cached(
+                "INSERT OR IGNORE INTO vector_tombstones (vector_id) VALUES (?1)",
+            )?;
+            for &id in &ids {
+                tombstone.execute(params![id])?;
+            }
+            release_symbol_parts_by_path(&conn, &path)?;
+            let changes = conn.execute("DELETE FROM files WHERE path = ?1", params![path])?;
+            if changes > 0 {
+                record_change(&conn, &path, ChangeOp::Deleted)?;
+            }
+            Ok((changes > 0, ids.into_iter().map(|id| id as u64).collect()))
+        })();
+        if result.is_err() {
+            let _ = conn.execute_batch("ROLLBACK TO delete_file_sp");
+        }
+        conn.execute_batch("RELEASE delete_file_sp")?;
+        result
+    }
+
+    /// Vector ids tombstoned by deletes whose vector purge has not been
+    /// confirmed yet, ascending.
+    pub fn vector_tombstones(&self) -> OmniResult<Vec<u64>> {
+        let conn = self.conn.lock();
+        let mut stmt =
+            conn.prepare("SELECT vector_id FROM vector_tombstones ORDER BY vector_id")?;
+        let ids = stmt
+            .query_map([], |row| row.get::<_, i64>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(ids.into_iter().map(|id| id as u64).collect())
+    }
+
+    /// Drop every vector tombstone: the second phase of a delete, once the
+    /// vector index has persisted the removal of the tombstoned ids.
+    pub fn purge_vector_tombstones(&self) -> OmniResult<usize> {
+        Ok(self
+            .conn
+            .lock()
+            .execute("DELETE FROM vector_tombstones", [])?)
+    }
+
+    /// Vector ids of every embedded chunk of the file at `path`.
+    ///
+    /// Read before [`Self::delete_file`]: the cascade removes the chunk rows,
+    /// and the caller must drop the matching vectors from the vector index.
+    pub fn vector_ids_for_file(&self, path: &Path) -> OmniResult<Vec<u64>> {
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare(
+            "SELECT c.vector_id FROM chunks c JOIN files f ON f.id = c.file_id
+             WHERE f.path = ?1 AND c.vector_id IS NOT NULL",
+        )?;
+        let rows = stmt.query_map(params![file_path_key(&conn, path)?], |row| {
+            row.get::<_, i64>(0)
+        })?;
+        let mut ids = Vec::new();
+        for row in rows {
+            ids.push(row? as u64);
+        }
+        Ok(ids)
+    }
+
+    /// Record that a file was decoded from `encoding` rather than read as
+    /// UTF-8. Cleared by the next [`Self::upsert_file`] of the file.
+    pub fn set_file_encoding(&self, file_id: i64, encoding: &str) -> OmniResult<()> {
+        self.conn.lock().execute(
+            "UPDATE files SET encoding = ?1 WHERE id = ?2",
+            params![encoding, file_id],
+        )?;
+        Ok(())
+    }
+
+    /// Record the filesystem modification time of a file, which the
+    /// `modified_after` / `modified_before` search filters compare against.
+    ///
+    /// [`Self::upsert_file`] stamps new rows with the current time and leaves
+    /// the column alone on update, so a file whose mtime cannot be read keeps
+    /// its previous value.
+    pub fn set_file_modified(
+        &self,
+        file_id: i64,
+        modified: std::time::SystemTime,
+    ) -> OmniResult<()> {
+        let secs = match modified.duration_since(std::time::UNIX_EPOCH) {
+            Ok(since) => since.as_secs() as i64,
+            Err(before) => -(before.duration().as_secs() as i64),
+        };
+        self.conn.lock().execute(
+            "UPDATE files SET last_modified = datetime(?1, 'unixepoch') WHERE id = ?2",
+            params![secs, file_id],
+        )?;
+        Ok(())
+    }
+
+    /// Number of indexed files per non-UTF-8 source encoding, most common
+    /// first.
+    pub fn encoding_counts(&self) -> OmniResult<Vec<(String, usize)>> {
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare(
+            "SELECT encoding, COUNT(*) FROM files WHERE encoding IS NOT NULL
+             GROUP BY encoding ORDER BY COUNT(*) DESC, encoding",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)? as usize))
+        })?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
     /// Get all indexed files.
     pub fn get_all_files(&self) -> OmniResult<Vec<FileInfo>> {
-        let mut stmt = self
-            .conn
-            .prepare("SELECT id, path, language, hash, size_bytes FROM files ORDER BY path")?;
+        let conn = self.conn.lock();
+        let mut stmt =
+            conn.prepare("SELECT id, path, language, hash, size_bytes FROM files ORDER BY path")?;
 
         let files = stmt.query_map([], |row| {
             Ok(FileInfo {
                 id: row.get(0)?,
                 path: std::path::PathBuf::from(row.get::<_, String>(1)?),
-                language: Language::from_extension(&row.get::<_, String>(2)?),
+                language: Language::from_str_lossy(&row.get::<_, String>(2)?),
                 content_hash: row.get(3)?,
                 size_bytes: row.get(4)?,
             })
@@ -341,6 +1060,7 @@ impl MetadataIndex {
     pub fn file_count(&self) -> OmniResult<usize> {
         let count: i64 = self
             .conn
+            .lock()
             .query_row("SELECT COUNT(*) FROM files", [], |row| row.get(0))?;
         Ok(count as usize)
     }
@@ -350,7 +1070,8 @@ impl MetadataIndex {
     /// Returns the `indexed_at` timestamp for every file, which indicates when
     /// the file was last re-indexed (and therefore last modified).
     pub fn get_file_freshness(&self) -> OmniResult<Vec<(i64, String)>> {
-        let mut stmt = self.conn.prepare("SELECT id, indexed_at FROM files")?;
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare("SELECT id, indexed_at FROM files")?;
         let rows = stmt.query_map([], |row| {
             Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
         })?;
@@ -367,10 +1088,11 @@ impl MetadataIndex {
 
     /// Insert a chunk record. Returns the chunk ID.
     pub fn insert_chunk(&self, chunk: &Chunk) -> OmniResult<i64> {
-        self.conn.execute(
+        self.conn.lock().execute(
             "INSERT INTO chunks (file_id, symbol_path, kind, visibility, line_start,
-             line_end, content, doc_comment, token_count, weight, vector_id, content_hash)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+             line_end, blob_hash, doc_comment, token_count, weight, vector_id, content_hash,
+             complexity, nesting_depth, has_cjk, generated, language)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)",
             params![
                 chunk.file_id,
                 chunk.symbol_path,
@@ -378,16 +1100,21 @@ impl MetadataIndex {
                 format!("{:?}", chunk.visibility).to_lowercase(),
                 chunk.line_start,
                 chunk.line_end,
-                chunk.content,
+                store_blob(&self.conn.lock(), &chunk.content)?,
                 chunk.doc_comment,
                 chunk.token_count,
                 chunk.weight,
                 chunk.vector_id.map(|v| v as i64),
                 chunk.content_hash as i64,
+                chunk.complexity.cyclomatic,
+                chunk.complexity.nesting_depth,
+                chunk_has_cjk(&chunk.content, chunk.doc_comment.as_deref()),
+                chunk.is_generated,
+                chunk.language.map(|l| l.as_str()),
             ],
         )?;
 
-        Ok(self.conn.last_insert_rowid())
+        Ok(self.conn.lock().last_insert_rowid())
     }
 
     /// Insert multiple chunks in a single transaction for better performance.
@@ -397,14 +1124,16 @@ impl MetadataIndex {
             return Ok(Vec::new());
         }
 
-        let tx = self.conn.unchecked_transaction()?;
+        let conn = self.conn.lock();
+        let tx = conn.unchecked_transaction()?;
         let mut chunk_ids = Vec::with_capacity(chunks.len());
 
         for chunk in chunks {
             tx.execute(
                 "INSERT INTO chunks (file_id, symbol_path, kind, visibility, line_start,
-                 line_end, content, doc_comment, token_count, weight, vector_id, content_hash)
-                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+                 line_end, blob_hash, doc_comment, token_count, weight, vector_id, content_hash,
+                 complexity, nesting_depth, has_cjk, generated, language)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)",
                 params![
                     chunk.file_id,
                     chunk.symbol_path,
@@ -412,12 +1141,17 @@ impl MetadataIndex {
                     chunk.visibility.as_str(),
                     chunk.line_start,
                     chunk.line_end,
-                    chunk.content,
+                    store_blob(&tx, &chunk.content)?,
                     chunk.doc_comment,
                     chunk.token_count,
                     chunk.weight,
                     chunk.vector_id.map(|v| v as i64),
                     chunk.content_hash as i64,
+                    chunk.complexity.cyclomatic,
+                    chunk.complexity.nesting_depth,
+                    chunk_has_cjk(&chunk.content, chunk.doc_comment.as_deref()),
+                    chunk.is_generated,
+                    chunk.language.map(|l| l.as_str()),
                 ],
             )?;
             chunk_ids.push(tx.last_insert_rowid());
@@ -431,16 +1165,19 @@ impl MetadataIndex {
     pub fn delete_chunks_for_file(&self, file_id: i64) -> OmniResult<usize> {
         let changes = self
             .conn
+            .lock()
             .execute("DELETE FROM chunks WHERE file_id = ?1", params![file_id])?;
         Ok(changes)
     }
 
     /// Get all chunks for a file.
     pub fn get_chunks_for_file(&self, file_id: i64) -> OmniResult<Vec<Chunk>> {
-        let mut stmt = self.conn.prepare(
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare(
             "SELECT id, file_id, symbol_path, kind, visibility, line_start,
-             line_end, content, doc_comment, token_count, weight, vector_id, content_hash
-             FROM chunks WHERE file_id = ?1 ORDER BY line_start",
+             line_end, b.content, doc_comment, token_count, weight, vector_id, content_hash,
+             complexity, nesting_depth, generated, language
+             FROM chunks c JOIN chunk_blobs b ON b.hash = c.blob_hash WHERE file_id = ?1 ORDER BY line_start",
         )?;
 
         let chunks = stmt.query_map(params![file_id], |row| {
@@ -458,7 +1195,13 @@ impl MetadataIndex {
                 weight: row.get(10)?,
                 vector_id: row.get::<_, Option<i64>>(11)?.map(|v| v as u64),
                 is_summary: false,
+                is_generated: row.get(15)?,
+                language: parse_chunk_language(row.get(16)?),
                 content_hash: row.get::<_, i64>(12)? as u64,
+                complexity: ComplexityMetrics {
+                    cyclomatic: row.get(13)?,
+                    nesting_depth: row.get(14)?,
+                },
             })
         })?;
 
@@ -469,9 +1212,47 @@ impl MetadataIndex {
         Ok(result)
     }
 
+    /// Look up a chunk by its database ID.
+    pub fn get_chunk_by_id(&self, chunk_id: i64) -> OmniResult<Option<Chunk>> {
+        let conn = self.conn.lock();
+        let chunk = conn
+            .prepare_cached(
+                "SELECT id, file_id, symbol_path, kind, visibility, line_start,
+                 line_end, b.content, doc_comment, token_count, weight, vector_id, content_hash,
+                 complexity, nesting_depth, generated, language
+                 FROM chunks c JOIN chunk_blobs b ON b.hash = c.blob_hash WHERE id = ?1",
+            )?
+            .query_row(params![chunk_id], |row| {
+                Ok(Chunk {
+                    id: row.get(0)?,
+                    file_id: row.get(1)?,
+                    symbol_path: row.get(2)?,
+                    kind: parse_chunk_kind(&row.get::<_, String>(3)?),
+                    visibility: parse_visibility(&row.get::<_, String>(4)?),
+                    line_start: row.get(5)?,
+                    line_end: row.get(6)?,
+                    content: row.get(7)?,
+                    doc_comment: row.get(8)?,
+                    token_count: row.get(9)?,
+                    weight: row.get(10)?,
+                    vector_id: row.get::<_, Option<i64>>(11)?.map(|v| v as u64),
+                    is_summary: false,
+                    is_generated: row.get(15)?,
+                    language: parse_chunk_language(row.get(16)?),
+                    content_hash: row.get::<_, i64>(12)? as u64,
+                    complexity: ComplexityMetrics {
+                        cyclomatic: row.get(13)?,
+                        nesting_depth: row.get(14)?,
+                    },
+                })
+            })
+            .optional()?;
+        Ok(chunk)
+    }
+
     /// Update the vector_id for a chunk (after embedding).
     pub fn set_chunk_vector_id(&self, chunk_id: i64, vector_id: u64) -> OmniResult<()> {
-        self.conn.execute(
+        self.conn.lock().execute(
             "UPDATE chunks SET vector_id = ?1 WHERE id = ?2",
             params![vector_id as i64, chunk_id],
         )?;
@@ -488,9 +1269,9 @@ impl MetadataIndex {
         &self,
         file_id: i64,
     ) -> OmniResult<std::collections::HashMap<String, u64>> {
-        let mut stmt = self
-            .conn
-            .prepare("SELECT symbol_path, content_hash FROM chunks WHERE file_id = ?1")?;
+        let conn = self.conn.lock();
+        let mut stmt =
+            conn.prepare("SELECT symbol_path, content_hash FROM chunks WHERE file_id = ?1")?;
 
         let rows = stmt.query_map(params![file_id], |row| {
             Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
@@ -515,19 +1296,26 @@ impl MetadataIndex {
     /// useful for bulk index runs where sequential per-file transactions would
     /// each incur fsync overhead.
     pub fn begin_batch_transaction(&self) -> OmniResult<()> {
-        self.conn.execute_batch("BEGIN DEFERRED TRANSACTION")?;
+        self.conn
+            .lock()
+            .execute_batch("BEGIN DEFERRED TRANSACTION")?;
         Ok(())
     }
 
-    /// Commit the active batch transaction.
+    /// Commit the active batch transaction, then checkpoint the WAL if one
+    /// is due.
     pub fn commit_batch_transaction(&self) -> OmniResult<()> {
-        self.conn.execute_batch("COMMIT")?;
+        self.conn.lock().execute_batch("COMMIT")?;
+        // The commit stands either way; a failed checkpoint is retried later.
+        if let Err(e) = self.maybe_checkpoint() {
+            tracing::warn!(error = %e, "WAL checkpoint failed");
+        }
         Ok(())
     }
 
     /// Roll back the active batch transaction.
     pub fn rollback_batch_transaction(&self) -> OmniResult<()> {
-        self.conn.execute_batch("ROLLBACK")?;
+        self.conn.lock().execute_batch("ROLLBACK")?;
         Ok(())
     }
 
@@ -535,13 +1323,14 @@ impl MetadataIndex {
     pub fn chunk_count(&self) -> OmniResult<usize> {
         let count: i64 = self
             .conn
+            .lock()
             .query_row("SELECT COUNT(*) FROM chunks", [], |row| row.get(0))?;
         Ok(count as usize)
     }
 
     /// Count chunks that have embeddings (vector_id is not NULL).
     pub fn embedded_chunk_count(&self) -> OmniResult<usize> {
-        let count: i64 = self.conn.query_row(
+        let count: i64 = self.conn.lock().query_row(
             "SELECT COUNT(*) FROM chunks WHERE vector_id IS NOT NULL",
             [],
             |row| row.get(0),
@@ -549,9 +1338,47 @@ impl MetadataIndex {
         Ok(count as usize)
     }
 
+    /// Count chunks that should have embeddings: all but generated chunks
+    /// left unembedded (see [`Chunk::is_generated`]).
+    pub fn embeddable_chunk_count(&self) -> OmniResult<usize> {
+        let count: i64 = self.conn.lock().query_row(
+            "SELECT COUNT(*) FROM chunks WHERE generated = 0 OR vector_id IS NOT NULL",
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(count as usize)
+    }
+
+    /// Ids of the chunks flagged as generated code.
+    pub fn generated_chunk_ids(&self) -> OmniResult<std::collections::HashSet<i64>> {
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare_cached("SELECT id FROM chunks WHERE generated = 1")?;
+        let rows = stmt.query_map([], |row| row.get(0))?;
+        let mut ids = std::collections::HashSet::new();
+        for id in rows {
+            ids.insert(id?);
+        }
+        Ok(ids)
+    }
+
+    /// `(chunk_id, vector_id)` for every chunk that has an embedding.
+    pub fn chunk_vector_ids(&self) -> OmniResult<Vec<(i64, u64)>> {
+        let conn = self.conn.lock();
+        let mut stmt = conn
+            .prepare("SELECT id, vector_id FROM chunks WHERE vector_id IS NOT NULL ORDER BY id")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)? as u64))
+        })?;
+        let mut ids = Vec::new();
+        for row in rows {
+            ids.push(row?);
+        }
+        Ok(ids)
+    }
+
     /// Get embedding coverage percentage (0.0 to 100.0).
     pub fn embedding_coverage(&self) -> OmniResult<f64> {
-        let total = self.chunk_count()? as f64;
+        let total = self.embeddable_chunk_count()? as f64;
         if total == 0.0 {
             return Ok(0.0);
         }
@@ -559,17 +1386,35 @@ impl MetadataIndex {
         Ok((embedded / total) * 100.0)
     }
 
-    /// Get all chunks that don't have embeddings (vector_id IS NULL).
+    /// Get all chunks that don't have embeddings (vector_id IS NULL), other
+    /// than generated chunks.
     ///
     /// This is useful for retrying failed embeddings.
     pub fn get_chunks_without_vectors(&self) -> OmniResult<Vec<Chunk>> {
-        let mut stmt = self.conn.prepare(
+        self.get_chunks_without_vectors_limited(0, usize::MAX)
+    }
+
+    /// Like [`Self::get_chunks_without_vectors`], but at most `limit` chunks
+    /// with ids above `after_id`, in id order. Background re-embedding works
+    /// through these a batch at a time, passing the last id it tried so that
+    /// chunks that fail to embed are skipped rather than retried forever.
+    pub fn get_chunks_without_vectors_limited(
+        &self,
+        after_id: i64,
+        limit: usize,
+    ) -> OmniResult<Vec<Chunk>> {
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare(
             "SELECT id, file_id, symbol_path, kind, visibility, line_start,
-             line_end, content, doc_comment, token_count, weight, vector_id, content_hash
-             FROM chunks WHERE vector_id IS NULL ORDER BY file_id, line_start",
+             line_end, b.content, doc_comment, token_count, weight, vector_id, content_hash,
+             complexity, nesting_depth, generated, language
+             FROM chunks c JOIN chunk_blobs b ON b.hash = c.blob_hash
+             WHERE vector_id IS NULL AND generated = 0 AND id > ?1
+             ORDER BY id LIMIT ?2",
         )?;
 
-        let chunks = stmt.query_map([], |row| {
+        let limit = i64::try_from(limit).unwrap_or(i64::MAX);
+        let chunks = stmt.query_map(params![after_id, limit], |row| {
             Ok(Chunk {
                 id: row.get(0)?,
                 file_id: row.get(1)?,
@@ -584,7 +1429,13 @@ impl MetadataIndex {
                 weight: row.get(10)?,
                 vector_id: row.get::<_, Option<i64>>(11)?.map(|v| v as u64),
                 is_summary: false,
+                is_generated: row.get(15)?,
+                language: parse_chunk_language(row.get(16)?),
                 content_hash: row.get::<_, i64>(12)? as u64,
+                complexity: ComplexityMetrics {
+                    cyclomatic: row.get(13)?,
+                    nesting_depth: row.get(14)?,
+                },
             })
         })?;
 
@@ -601,7 +1452,7 @@ impl MetadataIndex {
 
     /// Insert a symbol record. Returns the symbol ID.
     pub fn insert_symbol(&self, symbol: &Symbol) -> OmniResult<i64> {
-        self.conn.execute(
+        self.conn.lock().execute(
             "INSERT OR REPLACE INTO symbols (name, fqn, kind, file_id, line, chunk_id)
              VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
             params![
@@ -614,7 +1465,7 @@ impl MetadataIndex {
             ],
         )?;
 
-        Ok(self.conn.last_insert_rowid())
+        Ok(self.conn.lock().last_insert_rowid())
     }
 
     /// Insert multiple symbols in a single transaction for better performance.
@@ -624,7 +1475,8 @@ impl MetadataIndex {
             return Ok(Vec::new());
         }
 
-        let tx = self.conn.unchecked_transaction()?;
+        let conn = self.conn.lock();
+        let tx = conn.unchecked_transaction()?;
         let mut symbol_ids = Vec::with_capacity(symbols.len());
 
         for symbol in symbols {
@@ -651,6 +1503,7 @@ impl MetadataIndex {
     pub fn get_symbol_by_fqn(&self, fqn: &str) -> OmniResult<Option<Symbol>> {
         let result = self
             .conn
+            .lock()
             .query_row(
                 "SELECT id, name, fqn, kind, file_id, line, chunk_id
              FROM symbols WHERE fqn = ?1",
@@ -676,6 +1529,7 @@ impl MetadataIndex {
     pub fn get_symbol_by_id(&self, id: i64) -> OmniResult<Option<Symbol>> {
         let result = self
             .conn
+            .lock()
             .query_row(
                 "SELECT id, name, fqn, kind, file_id, line, chunk_id
              FROM symbols WHERE id = ?1",
@@ -703,6 +1557,7 @@ impl MetadataIndex {
     pub fn get_chunk_for_symbol(&self, symbol_id: i64) -> OmniResult<Option<i64>> {
         let result = self
             .conn
+            .lock()
             .query_row(
                 "SELECT chunk_id FROM symbols WHERE id = ?1",
                 params![symbol_id],
@@ -715,7 +1570,8 @@ impl MetadataIndex {
 
     /// Search symbols by name prefix (for autocomplete).
     pub fn search_symbols_by_name(&self, prefix: &str, limit: usize) -> OmniResult<Vec<Symbol>> {
-        let mut stmt = self.conn.prepare(
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare(
             "SELECT id, name, fqn, kind, file_id, line, chunk_id
              FROM symbols WHERE name LIKE ?1 ORDER BY name LIMIT ?2",
         )?;
@@ -740,10 +1596,27 @@ impl MetadataIndex {
         Ok(result)
     }
 
+    /// Ids of the symbols `file_id` declares that other files declare too
+    /// (parts of a C# partial class). They outlive the file.
+    pub fn shared_symbol_ids(&self, file_id: i64) -> OmniResult<std::collections::HashSet<i64>> {
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare_cached(
+            "SELECT DISTINCT p.symbol_id FROM symbol_parts p
+             WHERE p.file_id = ?1 AND EXISTS (
+                 SELECT 1 FROM symbol_parts o
+                 WHERE o.symbol_id = p.symbol_id AND o.file_id != ?1)",
+        )?;
+        let ids = stmt
+            .query_map(params![file_id], |row| row.get(0))?
+            .collect::<rusqlite::Result<std::collections::HashSet<i64>>>()?;
+        Ok(ids)
+    }
+
     /// Delete all symbols belonging to a file.
     pub fn delete_symbols_for_file(&self, file_id: i64) -> OmniResult<usize> {
         let changes = self
             .conn
+            .lock()
             .execute("DELETE FROM symbols WHERE file_id = ?1", params![file_id])?;
         Ok(changes)
     }
@@ -752,6 +1625,7 @@ impl MetadataIndex {
     pub fn symbol_count(&self) -> OmniResult<usize> {
         let count: i64 = self
             .conn
+            .lock()
             .query_row("SELECT COUNT(*) FROM symbols", [], |row| row.get(0))?;
         Ok(count as usize)
     }
@@ -762,6 +1636,7 @@ impl MetadataIndex {
     pub fn get_first_symbol_for_file(&self, file_id: i64) -> OmniResult<Option<Symbol>> {
         let result = self
             .conn
+            .lock()
             .query_row(
                 "SELECT id, name, fqn, kind, file_id, line, chunk_id
              FROM symbols WHERE file_id = ?1 ORDER BY line LIMIT 1",
@@ -792,7 +1667,8 @@ impl MetadataIndex {
         suffix: &str,
         limit: usize,
     ) -> OmniResult<Vec<Symbol>> {
-        let mut stmt = self.conn.prepare(
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare(
             "SELECT id, name, fqn, kind, file_id, line, chunk_id
              FROM symbols WHERE fqn LIKE ?1 ORDER BY length(fqn) ASC LIMIT ?2",
         )?;
@@ -823,7 +1699,8 @@ impl MetadataIndex {
     /// Used for call graph construction -- we need to iterate all symbols
     /// in a file to resolve their references.
     pub fn get_all_symbols_for_file(&self, file_id: i64) -> OmniResult<Vec<Symbol>> {
-        let mut stmt = self.conn.prepare(
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare(
             "SELECT id, name, fqn, kind, file_id, line, chunk_id
              FROM symbols WHERE file_id = ?1 ORDER BY line",
         )?;
@@ -851,7 +1728,8 @@ impl MetadataIndex {
     ///
     /// Used for loading the dependency graph on startup.
     pub fn get_all_symbols(&self) -> OmniResult<Vec<Symbol>> {
-        let mut stmt = self.conn.prepare(
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare(
             "SELECT id, name, fqn, kind, file_id, line, chunk_id
              FROM symbols ORDER BY id",
         )?;
@@ -879,73 +1757,308 @@ impl MetadataIndex {
     // FTS5 keyword search
     // -----------------------------------------------------------------------
 
+    /// Switch the `chunks_fts` tokenizer between Porter-stemmed and plain
+    /// `unicode61`, rebuilding the full-text index only if the setting
+    /// recorded under [`meta_keys::FTS_STEMMING`] differs.
+    pub fn set_stemming(&self, enabled: bool) -> OmniResult<()> {
+        let stored = if let Some(value) = self.get_meta(meta_keys::FTS_STEMMING)? {
+            value == "true"
+        } else {
+            // Databases from before the key: read the tokenizer off the table.
+            let ddl: String = self.conn.lock().query_row(
+                "SELECT sql FROM sqlite_master WHERE name = 'chunks_fts'",
+                [],
+                |row| row.get(0),
+            )?;
+            let stored = ddl.contains("porter");
+            self.set_meta(meta_keys::FTS_STEMMING, &stored.to_string())?;
+            stored
+        };
+        if stored == enabled {
+            return Ok(());
+        }
+
+        let conn = self.conn.lock();
+        let tx = conn.unchecked_transaction()?;
+        tx.execute_batch(&format!(
+            "DROP TABLE chunks_fts;
+            {}
+            INSERT INTO chunks_fts(chunks_fts) VALUES('rebuild');",
+            chunks_fts_ddl(enabled)
+        ))?;
+        self.set_meta(meta_keys::FTS_STEMMING, &enabled.to_string())?;
+        tx.commit()?;
+        tracing::info!(stemming = enabled, "rebuilt full-text index");
+        Ok(())
+    }
+
     /// Search chunks using FTS5 full-text search.
     ///
     /// Returns (chunk_id, bm25_score) pairs, ordered by relevance.
     ///
     /// ## Query Building
     ///
-    /// FTS5 phrase quoting (`"foo bar"`) requires exact consecutive token matching,
-    /// which produces zero results for multi-word queries when tokens appear in different
-    /// parts of a file (the common case). Instead we:
+    /// The query is compiled by [`fts_query::build`], which supports quoted
+    /// phrases, `AND` / `OR` / `NOT`, and `prefix*` wildcards while quoting
+    /// every term so special chars (hyphens, colons, `::`, `->`, etc.) cannot
+    /// cause FTS5 syntax errors.
     ///
-    /// 1. Split the query into individual tokens.
-    /// 2. Quote each token individually to prevent FTS5 syntax errors on
-    ///    special chars (hyphens, colons, `::`, `->`, etc.).
-    /// 3. Join with AND — all tokens must appear somewhere in the document.
-    /// 4. If the AND query returns zero results, fall back to OR so partial
-    ///    matches are still surfaced (R16: graceful degradation).
+    /// Plain multi-term queries are ANDed; if that returns zero results we
+    /// fall back to OR so partial matches are still surfaced (R16: graceful
+    /// degradation). Queries with explicit operators are run as written.
     pub fn keyword_search(&self, query: &str, limit: usize) -> OmniResult<Vec<(i64, f64)>> {
-        // Design: per-token quoting with AND → OR fallback.
-        // This eliminates zero-result multi-word queries while retaining
-        // FTS5 special-character safety from individual token quoting.
-        let tokens: Vec<String> = query
-            .split_whitespace()
-            .filter(|t| !t.is_empty())
-            .map(|t| format!("\"{}\"", t.replace('"', "")))
-            .collect();
-
-        if tokens.is_empty() {
-            return Ok(Vec::new());
-        }
-
-        // AND query: all tokens must appear (high precision)
-        let and_query = tokens.join(" AND ");
+        self.keyword_search_weighted(query, limit, &Bm25Weights::default())
+    }
 
-        let sql = "SELECT rowid, bm25(chunks_fts, 1.0, 0.5, 2.0) as score
-                   FROM chunks_fts
-                   WHERE chunks_fts MATCH ?1
-                   ORDER BY score
-                   LIMIT ?2";
+    /// [`Self::keyword_search`] with explicit per-column BM25 weights.
+    pub fn keyword_search_weighted(
+        &self,
+        query: &str,
+        limit: usize,
+        weights: &Bm25Weights,
+    ) -> OmniResult<Vec<(i64, f64)>> {
+        self.keyword_search_filtered(query, limit, weights, &SearchOptions::default())
+    }
 
-        let mut stmt = self.conn.prepare(sql)?;
+    /// [`Self::keyword_search_weighted`] over the chunks matching `filter`.
+    ///
+    /// The filters are part of the FTS query, so `limit` counts matching
+    /// chunks only and a narrow filter still gets a full result list.
+    pub fn keyword_search_filtered(
+        &self,
+        query: &str,
+        limit: usize,
+        weights: &Bm25Weights,
+        filter: &SearchOptions,
+    ) -> OmniResult<Vec<(i64, f64)>> {
+        use rusqlite::types::Value;
 
-        let and_results = stmt.query_map(params![and_query, limit as i64], |row| {
-            Ok((row.get::<_, i64>(0)?, row.get::<_, f64>(1)?))
-        })?;
+        let Some(fts) = fts_query::build(query) else {
+            return Ok(Vec::new());
+        };
+
+        let conn = self.conn.lock();
+        // ?1 is the match expression, bound per run below.
+        let mut values = vec![
+            Value::Null,
+            Value::Integer(limit as i64),
+            Value::Real(weights.content),
+            Value::Real(weights.doc_comment),
+            Value::Real(weights.symbol_path),
+        ];
+        let scope = scoped_rowids(&conn, filter, &mut values)?;
+        let generated = if filter.include_generated {
+            ""
+        } else {
+            " AND rowid NOT IN (SELECT id FROM chunks WHERE generated = 1)"
+        };
+        let sql = format!(
+            "SELECT rowid, bm25(chunks_fts, ?3, ?4, ?5) as score
+             FROM chunks_fts
+             WHERE chunks_fts MATCH ?1{}{generated}
+             ORDER BY score
+             LIMIT ?2",
+            scope.map(|s| format!(" AND {s}")).unwrap_or_default()
+        );
+        let mut stmt = conn.prepare_cached(&sql)?;
+        let mut run = |expr: &str| -> OmniResult<Vec<(i64, f64)>> {
+            values[0] = Value::Text(expr.to_string());
+            let rows = stmt.query_map(rusqlite::params_from_iter(&values), |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, f64>(1)?))
+            })?;
+            let mut out = Vec::new();
+            for r in rows {
+                out.push(r?);
+            }
+            Ok(out)
+        };
 
-        let mut out = Vec::new();
-        for r in and_results {
-            out.push(r?);
-        }
+        let mut out = run(&fts.expr)?;
 
         // R16 — Graceful degradation: if AND produced nothing, retry with OR
         // so at least one token match is returned. Useful when embedding
         // coverage is 0% and keyword is the sole retrieval signal.
-        if out.is_empty() && tokens.len() > 1 {
-            let or_query = tokens.join(" OR ");
-            let mut stmt2 = self.conn.prepare(sql)?;
-            let or_results = stmt2.query_map(params![or_query, limit as i64], |row| {
-                Ok((row.get::<_, i64>(0)?, row.get::<_, f64>(1)?))
-            })?;
-            for r in or_results {
-                out.push(r?);
+        if out.is_empty() {
+            if let Some(or_query) = fts.fallback {
+                out = run(&or_query)?;
+            }
+        }
+
+        // unicode61 cannot segment CJK text; match CJK runs as substrings
+        // against the trigram table and append any new hits.
+        let runs = fts_query::cjk_runs(query);
+        if !runs.is_empty() && out.len() < limit {
+            for hit in self.cjk_search(&runs, limit, weights, filter)? {
+                if !out.iter().any(|(id, _)| *id == hit.0) {
+                    out.push(hit);
+                }
             }
+            out.truncate(limit);
         }
 
         Ok(out)
     }
 
+    /// Substring search for CJK runs in the trigram-tokenized `chunks_cjk` table.
+    ///
+    /// Runs of 3+ characters go through `MATCH` (BM25-ranked); shorter runs
+    /// fall back to `LIKE`, which the trigram tokenizer cannot accelerate.
+    /// All runs must match.
+    fn cjk_search(
+        &self,
+        runs: &[String],
+        limit: usize,
+        weights: &Bm25Weights,
+        filter: &SearchOptions,
+    ) -> OmniResult<Vec<(i64, f64)>> {
+        use rusqlite::types::Value;
+
+        let conn = self.conn.lock();
+        let (long, short): (Vec<&String>, Vec<&String>) =
+            runs.iter().partition(|r| r.chars().count() >= 3);
+
+        let mut conditions = Vec::new();
+        let mut values: Vec<Value> = vec![
+            Value::Real(weights.content),
+            Value::Real(weights.doc_comment),
+            Value::Real(weights.symbol_path),
+            Value::Integer(limit as i64),
+        ];
+        if !long.is_empty() {
+            let expr = long
+                .iter()
+                .map(|r| format!("\"{}\"", r.replace('"', "\"\"")))
+                .collect::<Vec<_>>()
+                .join(" AND ");
+            values.push(Value::Text(expr));
+            conditions.push(format!("chunks_cjk MATCH ?{}", values.len()));
+        }
+        for run in short {
+            values.push(Value::Text(format!("%{run}%")));
+            let n = values.len();
+            conditions.push(format!("(content LIKE ?{n} OR doc_comment LIKE ?{n})"));
+        }
+        conditions.extend(scoped_rowids(&conn, filter, &mut values)?);
+
+        // bm25() is only defined for MATCH queries.
+        let score = if long.is_empty() {
+            "0.0"
+        } else {
+            "bm25(chunks_cjk, ?1, ?2, ?3)"
+        };
+        let sql = format!(
+            "SELECT rowid, {score} AS score FROM chunks_cjk
+             WHERE {}
+             ORDER BY score, rowid
+             LIMIT ?4",
+            conditions.join(" AND ")
+        );
+
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map(rusqlite::params_from_iter(values), |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, f64>(1)?))
+        })?;
+        let mut out = Vec::new();
+        for r in rows {
+            out.push(r?);
+        }
+        Ok(out)
+    }
+
+    /// Find chunks whose body contains `needle` as a raw substring.
+    ///
+    /// Unlike [`Self::keyword_search`] this bypasses FTS tokenization: the
+    /// case-sensitive pass uses `instr()`, the case-insensitive pass uses an
+    /// escaped `LIKE` (ASCII case folding only). Callers are expected to
+    /// verify matches (e.g. whole-word boundaries) themselves. Returns at most
+    /// `limit` candidates together with their file paths.
+    ///
+    /// Candidates are ordered the way [`crate::search::exact`] ranks them:
+    /// chunks whose symbol is the needle first, then by occurrence count, so
+    /// when more than `limit` chunks match, the cut drops the least relevant
+    /// ones rather than whichever sort last by path.
+    pub fn find_chunks_containing(
+        &self,
+        needle: &str,
+        case_sensitive: bool,
+        limit: usize,
+    ) -> OmniResult<Vec<(Chunk, std::path::PathBuf)>> {
+        if needle.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let (predicate, pattern) = if case_sensitive {
+            ("instr(b.content, ?1) > 0", needle.to_string())
+        } else {
+            let escaped = needle
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_");
+            ("b.content LIKE ?1 ESCAPE '\\'", format!("%{escaped}%"))
+        };
+        // Case-insensitive ranking folds ASCII only, like LIKE does.
+        let fold = |expr: &str| {
+            if case_sensitive {
+                expr.to_string()
+            } else {
+                format!("lower({expr})")
+            }
+        };
+        let (content, symbol, name) = (fold("b.content"), fold("c.symbol_path"), fold("?3"));
+        let sql = format!(
+            "SELECT c.id, c.file_id, c.symbol_path, c.kind, c.visibility, c.line_start,
+             c.line_end, b.content, c.doc_comment, c.token_count, c.weight, c.vector_id,
+             c.content_hash, c.complexity, c.nesting_depth, c.generated, f.path, c.language
+             FROM chunks c
+             JOIN chunk_blobs b ON b.hash = c.blob_hash
+             JOIN files f ON f.id = c.file_id
+             WHERE {predicate}
+             ORDER BY
+                {symbol} = {name}
+                    OR (substr({symbol}, -length(?3)) = {name}
+                        AND substr({symbol}, -length(?3) - 1, 1) IN (':', '.')) DESC,
+                (length({content}) - length(replace({content}, {name}, ''))) / length(?3) DESC,
+                f.path, c.line_start
+             LIMIT ?2"
+        );
+
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map(params![pattern, limit as i64, needle], |row| {
+            Ok((
+                Chunk {
+                    id: row.get(0)?,
+                    file_id: row.get(1)?,
+                    symbol_path: row.get(2)?,
+                    kind: parse_chunk_kind(&row.get::<_, String>(3)?),
+                    visibility: parse_visibility(&row.get::<_, String>(4)?),
+                    line_start: row.get(5)?,
+                    line_end: row.get(6)?,
+                    content: row.get(7)?,
+                    doc_comment: row.get(8)?,
+                    token_count: row.get(9)?,
+                    weight: row.get(10)?,
+                    vector_id: row.get::<_, Option<i64>>(11)?.map(|v| v as u64),
+                    is_summary: false,
+                    is_generated: row.get(15)?,
+                    language: parse_chunk_language(row.get(17)?),
+                    content_hash: row.get::<_, i64>(12)? as u64,
+                    complexity: ComplexityMetrics {
+                        cyclomatic: row.get(13)?,
+                        nesting_depth: row.get(14)?,
+                    },
+                },
+                std::path::PathBuf::from(row.get::<_, String>(16)?),
+            ))
+        })?;
+
+        let mut result = Vec::new();
+        for row in rows {
+            result.push(row?);
+        }
+        Ok(result)
+    }
+
     // -----------------------------------------------------------------------
     // Transaction helpers
     // -----------------------------------------------------------------------
@@ -967,14 +2080,16 @@ impl MetadataIndex {
         // reentrant — they work correctly at any nesting depth.
         let savepoint_name = "reindex_file_sp";
         self.conn
+            .lock()
             .execute_batch(&format!("SAVEPOINT {savepoint_name}"))?;
 
         // All writes go through a macro-local closure so we can ROLLBACK TO
         // the savepoint on any error without propagating a half-written state.
         let result: rusqlite::Result<(i64, Vec<i64>)> = (|| {
-            let conn = &self.conn;
+            let conn = self.conn.lock();
 
             // Upsert the file
+            let path = file_path_key(&conn, &file.path)?;
             conn.execute(
                 "INSERT INTO files (path, language, hash, size_bytes, last_modified)
              VALUES (?1, ?2, ?3, ?4, datetime('now'))
@@ -982,10 +2097,9 @@ impl MetadataIndex {
                 language = excluded.language,
                 hash = excluded.hash,
                 size_bytes = excluded.size_bytes,
-                indexed_at = datetime('now'),
-                last_modified = excluded.last_modified",
+                indexed_at = datetime('now')",
                 params![
-                    file.path.to_string_lossy().as_ref(),
+                    path,
                     file.language.as_str(),
                     file.content_hash,
                     file.size_bytes,
@@ -994,10 +2108,13 @@ impl MetadataIndex {
 
             let file_id: i64 = conn.query_row(
                 "SELECT id FROM files WHERE path = ?1",
-                params![file.path.to_string_lossy().as_ref()],
+                params![path],
                 |row| row.get(0),
             )?;
 
+            // Symbols other files also declare survive, owned by one of them.
+            release_symbol_parts(&conn, file_id)?;
+
             // Delete stale dependency edges for symbols in this file BEFORE
             // deleting the symbols themselves. This prevents ghost edges.
             conn.execute(
@@ -1015,8 +2132,9 @@ impl MetadataIndex {
             {
                 let mut chunk_stmt = conn.prepare_cached(
                     "INSERT INTO chunks (file_id, symbol_path, kind, visibility, line_start,
-             line_end, content, doc_comment, token_count, weight, vector_id, content_hash)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+             line_end, blob_hash, doc_comment, token_count, weight, vector_id, content_hash,
+             complexity, nesting_depth, has_cjk, generated, language)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)",
                 )?;
 
                 for chunk in chunks {
@@ -1027,19 +2145,50 @@ impl MetadataIndex {
                         chunk.visibility.as_str(),
                         chunk.line_start,
                         chunk.line_end,
-                        chunk.content,
+                        store_blob(&conn, &chunk.content)?,
                         chunk.doc_comment,
                         chunk.token_count,
                         chunk.weight,
                         chunk.vector_id.map(|v| v as i64),
                         chunk.content_hash as i64,
+                        chunk.complexity.cyclomatic,
+                        chunk.complexity.nesting_depth,
+                        chunk_has_cjk(&chunk.content, chunk.doc_comment.as_deref()),
+                        chunk.is_generated,
+                        chunk.language.map(|l| l.as_str()),
                     ])?;
                     chunk_ids.push(conn.last_insert_rowid());
                 }
             }
 
-            // Insert new symbols using a prepared, cached statement
-            {
+            // Insert new symbols using a prepared, cached statement. The
+            // parts of a C# partial class share one fqn across files: each
+            // file records its part, and the symbol row keeps whichever part
+            // stored it first, so edges to it outlive the other parts.
+            if file.language == Language::CSharp {
+                let mut symbol_stmt = conn.prepare_cached(
+                    "INSERT INTO symbols (name, fqn, kind, file_id, line, chunk_id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             ON CONFLICT(fqn) DO NOTHING",
+                )?;
+                let mut part_stmt = conn.prepare_cached(
+                    "INSERT OR REPLACE INTO symbol_parts (symbol_id, file_id, line, chunk_id)
+             SELECT id, ?2, ?3, ?4 FROM symbols WHERE fqn = ?1",
+                )?;
+
+                for symbol in symbols {
+                    symbol_stmt.execute(params![
+                        symbol.name,
+                        symbol.fqn,
+                        symbol.kind.as_str(),
+                        file_id,
+                        symbol.line,
+                        symbol.chunk_id,
+                    ])?;
+                    part_stmt
+                        .execute(params![symbol.fqn, file_id, symbol.line, symbol.chunk_id])?;
+                }
+            } else {
                 let mut symbol_stmt = conn.prepare_cached(
                     "INSERT OR REPLACE INTO symbols (name, fqn, kind, file_id, line, chunk_id)
              VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
@@ -1063,6 +2212,7 @@ impl MetadataIndex {
         match result {
             Ok(val) => {
                 self.conn
+                    .lock()
                     .execute_batch(&format!("RELEASE {savepoint_name}"))?;
                 Ok(val)
             }
@@ -1071,15 +2221,411 @@ impl MetadataIndex {
                 // then release it (required to free the savepoint even after rollback).
                 let _ = self
                     .conn
+                    .lock()
                     .execute_batch(&format!("ROLLBACK TO {savepoint_name}"));
                 let _ = self
                     .conn
+                    .lock()
                     .execute_batch(&format!("RELEASE {savepoint_name}"));
                 Err(crate::error::OmniError::Database(e))
             }
         }
     }
 
+    // -----------------------------------------------------------------------
+    // Index metadata
+    // -----------------------------------------------------------------------
+
+    /// Value recorded under `key` in `index_meta` (see [`meta_keys`]).
+    pub fn get_meta(&self, key: &str) -> OmniResult<Option<String>> {
+        Ok(self
+            .conn
+            .lock()
+            .query_row(
+                "SELECT value FROM index_meta WHERE key = ?1",
+                params![key],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    /// [`Self::get_meta`] on the database at `db_path` without opening it
+    /// as an index: no migrations run and nothing is written.
+    pub fn peek_meta(db_path: &Path, key: &str) -> OmniResult<Option<String>> {
+        let conn =
+            Connection::open_with_flags(db_path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        Ok(conn
+            .query_row(
+                "SELECT value FROM index_meta WHERE key = ?1",
+                params![key],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    /// Record `value` under `key` in `index_meta`, replacing any previous one.
+    pub fn set_meta(&self, key: &str, value: &str) -> OmniResult<()> {
+        self.conn.lock().execute(
+            "INSERT INTO index_meta (key, value) VALUES (?1, ?2)
+             ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+            params![key, value],
+        )?;
+        Ok(())
+    }
+
+    /// [`Self::get_meta`] for a JSON value.
+    fn get_meta_json<T: serde::de::DeserializeOwned>(&self, key: &str) -> OmniResult<Option<T>> {
+        self.get_meta(key)?
+            .map(|v| serde_json::from_str(&v).map_err(|e| OmniError::Serialization(e.to_string())))
+            .transpose()
+    }
+
+    /// [`Self::set_meta`] for a JSON value.
+    fn set_meta_json<T: serde::Serialize + ?Sized>(&self, key: &str, value: &T) -> OmniResult<()> {
+        let value =
+            serde_json::to_string(value).map_err(|e| OmniError::Serialization(e.to_string()))?;
+        self.set_meta(key, &value)
+    }
+
+    /// The well-known metadata values, for status and diagnostics.
+    pub fn index_metadata(&self) -> OmniResult<IndexMetadata> {
+        Ok(IndexMetadata {
+            created_at: self.get_meta(meta_keys::CREATED_AT)?,
+            engine_version: self.get_meta(meta_keys::ENGINE_VERSION)?,
+            embedding_model: self.embedding_model()?,
+            repo_fingerprint: self.get_meta(meta_keys::REPO_FINGERPRINT)?,
+            repo_origin: self.get_meta(meta_keys::REPO_ORIGIN)?,
+            last_full_index: self.get_meta(meta_keys::LAST_FULL_INDEX)?,
+        })
+    }
+
+    /// Record that an index run by `engine_version` just finished.
+    pub fn record_full_index(&self, engine_version: &str) -> OmniResult<()> {
+        self.set_meta(meta_keys::ENGINE_VERSION, engine_version)?;
+        self.conn.lock().execute(
+            "INSERT INTO index_meta (key, value) VALUES (?1, datetime('now'))
+             ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+            params![meta_keys::LAST_FULL_INDEX],
+        )?;
+        Ok(())
+    }
+
+    /// Embedding model recorded as having produced the stored vectors.
+    pub fn embedding_model(&self) -> OmniResult<Option<EmbeddingModelInfo>> {
+        self.get_meta_json(meta_keys::EMBEDDING_MODEL)
+    }
+
+    /// Record the embedding model that produced the stored vectors.
+    pub fn set_embedding_model(&self, model: &EmbeddingModelInfo) -> OmniResult<()> {
+        self.set_meta_json(meta_keys::EMBEDDING_MODEL, model)
+    }
+
+    /// Repeated file headers recorded by the last index run.
+    pub fn header_templates(&self) -> OmniResult<Vec<crate::patterns::HeaderTemplate>> {
+        self.get_meta_json(meta_keys::HEADER_TEMPLATES)
+            .map(Option::unwrap_or_default)
+    }
+
+    /// Record the repeated file headers left out of chunk content.
+    pub fn set_header_templates(
+        &self,
+        templates: &[crate::patterns::HeaderTemplate],
+    ) -> OmniResult<()> {
+        self.set_meta_json(meta_keys::HEADER_TEMPLATES, templates)
+    }
+
+    /// Rewrite absolute file paths as relative to `repo_root`, and record
+    /// `repo_root` as the root the index was built for.
+    ///
+    /// Paths are matched against `repo_root`, its canonical form, and the
+    /// root recorded by the previous run, so an index built elsewhere or
+    /// before a move is still migrated. Where the relative path is already
+    /// indexed, the absolute duplicate is dropped. Each rewrite is recorded
+    /// in the change log as a deletion of the absolute path (plus indexing
+    /// of the relative one when it moved). Returns the number of paths
+    /// rewritten.
+    pub fn relativize_paths(&self, repo_root: &Path) -> OmniResult<usize> {
+        let conn = self.conn.lock();
+        let recorded: Option<String> = conn
+            .query_row(
+                "SELECT value FROM index_meta WHERE key = ?1",
+                params![meta_keys::REPO_ROOT],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let mut roots = vec![repo_root.to_path_buf()];
+        roots.extend(crate::paths::canonicalize(repo_root).ok());
+        roots.extend(recorded.as_deref().map(PathBuf::from));
+        let relative = |path: &str| -> Option<String> {
+            let path = Path::new(path);
+            if !path.is_absolute() {
+                return None;
+            }
+            roots
+                .iter()
+                .find_map(|root| crate::paths::strip_root(path, root))
+                .map(|rel| crate::paths::index_form(&rel))
+        };
+
+        let tx = conn.unchecked_transaction()?;
+        let mut rewritten = 0;
+        let files: Vec<(i64, String)> = tx
+            .prepare("SELECT id, path FROM files ORDER BY id")?
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<_, _>>()?;
+        for (id, path) in files {
+            let Some(rel) = relative(&path) else {
+                continue;
+            };
+            let moved = tx.execute(
+                "UPDATE OR IGNORE files SET path = ?1 WHERE id = ?2",
+                params![rel, id],
+            )?;
+            if moved == 0 {
+                tx.execute("DELETE FROM files WHERE id = ?1", params![id])?;
+            }
+            record_change(&tx, &path, ChangeOp::Deleted)?;
+            if moved > 0 {
+                record_change(&tx, &rel, ChangeOp::Indexed)?;
+            }
+            rewritten += 1;
+        }
+
+        let edges: Vec<(String, String, String)> = tx
+            .prepare("SELECT source_path, target_path, edge_type FROM file_graph_edges")?
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+            .collect::<Result<_, _>>()?;
+        for (source, target, edge_type) in edges {
+            let (new_source, new_target) = (relative(&source), relative(&target));
+            if new_source.is_none() && new_target.is_none() {
+                continue;
+            }
+            let moved = tx.execute(
+                "UPDATE OR IGNORE file_graph_edges SET source_path = ?1, target_path = ?2
+                 WHERE source_path = ?3 AND target_path = ?4 AND edge_type = ?5",
+                params![
+                    new_source.as_deref().unwrap_or(&source),
+                    new_target.as_deref().unwrap_or(&target),
+                    source,
+                    target,
+                    edge_type
+                ],
+            )?;
+            if moved == 0 {
+                tx.execute(
+                    "DELETE FROM file_graph_edges
+                     WHERE source_path = ?1 AND target_path = ?2 AND edge_type = ?3",
+                    params![source, target, edge_type],
+                )?;
+            }
+        }
+
+        let root = repo_root.to_string_lossy();
+        if recorded.as_deref() != Some(root.as_ref()) {
+            tx.execute(
+                "INSERT INTO index_meta (key, value) VALUES (?1, ?2)
+                 ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+                params![meta_keys::REPO_ROOT, root.as_ref()],
+            )?;
+        }
+        tx.commit()?;
+        if rewritten > 0 {
+            tracing::info!(rewritten, "migrated absolute file paths to repo-relative");
+        }
+        Ok(rewritten)
+    }
+
+    /// Detach every chunk from its vector so all chunks are re-embedded.
+    pub fn clear_vector_ids(&self) -> OmniResult<usize> {
+        Ok(self.conn.lock().execute(
+            "UPDATE chunks SET vector_id = NULL WHERE vector_id IS NOT NULL",
+            [],
+        )?)
+    }
+
+    /// Detach chunks from the given vector ids so they are embedded again.
+    /// Returns the number of chunks updated.
+    pub fn detach_vector_ids(&self, vector_ids: &[u64]) -> OmniResult<usize> {
+        let conn = self.conn.lock();
+        let tx = conn.unchecked_transaction()?;
+        let mut updated = 0;
+        {
+            let mut stmt = tx.prepare("UPDATE chunks SET vector_id = NULL WHERE vector_id = ?1")?;
+            for &id in vector_ids {
+                updated += stmt.execute(params![id as i64])?;
+            }
+        }
+        tx.commit()?;
+        Ok(updated)
+    }
+
+    // -----------------------------------------------------------------------
+    // Module summaries
+    // -----------------------------------------------------------------------
+
+    /// Insert or replace the summary for `summary.path`.
+    pub fn upsert_summary(&self, summary: &ModuleSummary) -> OmniResult<()> {
+        let key_symbols = serde_json::to_string(&summary.key_symbols)
+            .map_err(|e| OmniError::Serialization(e.to_string()))?;
+        self.conn.lock().execute(
+            "INSERT INTO summaries (path, kind, summary, key_symbols, file_count, chunk_count)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             ON CONFLICT(path) DO UPDATE SET
+                kind = excluded.kind, summary = excluded.summary,
+                key_symbols = excluded.key_symbols, file_count = excluded.file_count,
+                chunk_count = excluded.chunk_count, updated_at = datetime('now')",
+            params![
+                summary.path,
+                summary.kind.as_str(),
+                summary.summary,
+                key_symbols,
+                summary.file_count as i64,
+                summary.chunk_count as i64,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Summary stored for `path`, if any.
+    pub fn get_summary(&self, path: &str) -> OmniResult<Option<ModuleSummary>> {
+        let mut summaries = self.query_summaries("WHERE path = ?1", params![path])?;
+        Ok(summaries.pop())
+    }
+
+    /// Remove the summary for `path`. Returns whether one existed.
+    pub fn delete_summary(&self, path: &str) -> OmniResult<bool> {
+        Ok(self
+            .conn
+            .lock()
+            .execute("DELETE FROM summaries WHERE path = ?1", params![path])?
+            > 0)
+    }
+
+    /// File summaries beneath directory `dir` (`.` for the whole repository),
+    /// ordered by path.
+    pub fn file_summaries_under(&self, dir: &str) -> OmniResult<Vec<ModuleSummary>> {
+        if dir == "." {
+            return self.query_summaries("WHERE kind = 'file' ORDER BY path", params![]);
+        }
+        self.query_summaries(
+            "WHERE kind = 'file' AND substr(path, 1, length(?1) + 1) = ?1 || '/' ORDER BY path",
+            params![dir],
+        )
+    }
+
+    /// Number of stored summaries.
+    pub fn summary_count(&self) -> OmniResult<usize> {
+        let count: i64 =
+            self.conn
+                .lock()
+                .query_row("SELECT COUNT(*) FROM summaries", [], |row| row.get(0))?;
+        Ok(count as usize)
+    }
+
+    fn query_summaries(
+        &self,
+        clause: &str,
+        params: &[&dyn rusqlite::ToSql],
+    ) -> OmniResult<Vec<ModuleSummary>> {
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT path, kind, summary, key_symbols, file_count, chunk_count
+             FROM summaries {clause}"
+        ))?;
+        let rows = stmt.query_map(params, |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, i64>(4)?,
+                row.get::<_, i64>(5)?,
+            ))
+        })?;
+        let mut summaries = Vec::new();
+        for row in rows {
+            let (path, kind, summary, key_symbols, file_count, chunk_count) = row?;
+            summaries.push(ModuleSummary {
+                path,
+                kind: SummaryKind::from_str_lossy(&kind),
+                summary,
+                key_symbols: serde_json::from_str(&key_symbols)
+                    .map_err(|e| OmniError::Serialization(e.to_string()))?,
+                file_count: file_count as usize,
+                chunk_count: chunk_count as usize,
+            });
+        }
+        Ok(summaries)
+    }
+
+    // -----------------------------------------------------------------------
+    // Search telemetry
+    // -----------------------------------------------------------------------
+
+    /// Append a search to the telemetry log. `logged_at` is set by SQLite.
+    pub fn log_search(&self, entry: &SearchLogEntry) -> OmniResult<()> {
+        self.conn.lock().execute(
+            "INSERT INTO search_log (query, query_type, latency_ms, signals, chunk_ids, error)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                entry.query,
+                entry.query_type,
+                entry.latency_ms,
+                serde_json::to_string(&entry.signals)
+                    .map_err(|e| OmniError::Serialization(e.to_string()))?,
+                serde_json::to_string(&entry.chunk_ids)
+                    .map_err(|e| OmniError::Serialization(e.to_string()))?,
+                entry.error,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Read the telemetry log in insertion order, optionally only entries
+    /// logged at or after `since` (`YYYY-MM-DD[ HH:MM:SS]`).
+    pub fn search_log(&self, since: Option<&str>) -> OmniResult<Vec<SearchLogEntry>> {
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare(
+            "SELECT logged_at, query, query_type, latency_ms, signals, chunk_ids, error
+             FROM search_log
+             WHERE ?1 IS NULL OR logged_at >= ?1
+             ORDER BY id",
+        )?;
+        let rows = stmt.query_map(params![since], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, f64>(3)?,
+                row.get::<_, String>(4)?,
+                row.get::<_, String>(5)?,
+                row.get::<_, Option<String>>(6)?,
+            ))
+        })?;
+
+        let mut out = Vec::new();
+        for row in rows {
+            let (logged_at, query, query_type, latency_ms, signals, chunk_ids, error) = row?;
+            out.push(SearchLogEntry {
+                logged_at,
+                query,
+                query_type,
+                latency_ms,
+                signals: serde_json::from_str(&signals)
+                    .map_err(|e| OmniError::Serialization(e.to_string()))?,
+                chunk_ids: serde_json::from_str(&chunk_ids)
+                    .map_err(|e| OmniError::Serialization(e.to_string()))?,
+                error,
+            });
+        }
+        Ok(out)
+    }
+
+    /// Delete all telemetry log entries, returning how many were removed.
+    pub fn clear_search_log(&self) -> OmniResult<usize> {
+        Ok(self.conn.lock().execute("DELETE FROM search_log", [])?)
+    }
+
     // -----------------------------------------------------------------------
     // Status / diagnostics
     // -----------------------------------------------------------------------
@@ -1088,10 +2634,91 @@ impl MetadataIndex {
     pub fn check_integrity(&self) -> OmniResult<bool> {
         let result: String = self
             .conn
+            .lock()
             .query_row("PRAGMA integrity_check", [], |row| row.get(0))?;
         Ok(result == "ok")
     }
 
+    /// Tables and columns of the current schema that this database lacks,
+    /// as `table` or `table.column`.
+    ///
+    /// A non-empty result means a migration was skipped or only partly
+    /// applied: the recorded schema version claims more than is there.
+    pub fn schema_gaps(&self) -> OmniResult<Vec<String>> {
+        use std::collections::{HashMap, HashSet};
+
+        fn columns(conn: &Connection) -> OmniResult<HashMap<String, HashSet<String>>> {
+            let mut stmt = conn.prepare(
+                "SELECT m.name, p.name FROM sqlite_master m, pragma_table_info(m.name) p
+                 WHERE m.type = 'table' AND m.name NOT LIKE 'sqlite_%'",
+            )?;
+            let rows = stmt.query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })?;
+            let mut tables: HashMap<String, HashSet<String>> = HashMap::new();
+            for row in rows {
+                let (table, column) = row?;
+                tables.entry(table).or_default().insert(column);
+            }
+            Ok(tables)
+        }
+
+        let reference = Connection::open_in_memory()?;
+        create_schema(&reference)?;
+        let expected = columns(&reference)?;
+        let actual = columns(&self.conn.lock())?;
+
+        let mut gaps = Vec::new();
+        for (table, wanted) in &expected {
+            match actual.get(table) {
+                None => gaps.push(table.clone()),
+                Some(have) => gaps.extend(
+                    wanted
+                        .difference(have)
+                        .map(|column| format!("{table}.{column}")),
+                ),
+            }
+        }
+        gaps.sort();
+        Ok(gaps)
+    }
+
+    /// Describe how the full-text indexes disagree with the chunk table,
+    /// or `None` when every chunk is indexed exactly once.
+    pub fn fts_out_of_sync(&self) -> OmniResult<Option<String>> {
+        let conn = self.conn.lock();
+        for (table, chunks_sql) in [
+            ("chunks_fts", "SELECT COUNT(*) FROM chunks"),
+            (
+                "chunks_cjk",
+                "SELECT COUNT(*) FROM chunks WHERE has_cjk = 1",
+            ),
+        ] {
+            let chunks: i64 = conn.query_row(chunks_sql, [], |row| row.get(0))?;
+            let indexed: i64 = conn.query_row(
+                &format!("SELECT COUNT(*) FROM {table}_docsize"),
+                [],
+                |row| row.get(0),
+            )?;
+            if chunks != indexed {
+                return Ok(Some(format!(
+                    "{table} indexes {indexed} rows for {chunks} chunks"
+                )));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Rebuild both full-text indexes from the chunk table.
+    pub fn rebuild_fts(&self) -> OmniResult<()> {
+        self.conn.lock().execute_batch(
+            "INSERT INTO chunks_fts(chunks_fts) VALUES('rebuild');
+            INSERT INTO chunks_cjk(chunks_cjk) VALUES('rebuild');",
+        )?;
+        tracing::info!("rebuilt full-text index");
+        Ok(())
+    }
+
     /// Get aggregate statistics about the index.
     pub fn statistics(&self) -> OmniResult<IndexStats> {
         Ok(IndexStats {
@@ -1103,7 +2730,8 @@ impl MetadataIndex {
 
     /// Get file counts grouped by language.
     pub fn language_distribution(&self) -> OmniResult<Vec<(String, usize)>> {
-        let mut stmt = self.conn.prepare(
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare(
             "SELECT language, COUNT(*) FROM files GROUP BY language ORDER BY COUNT(*) DESC",
         )?;
         let rows = stmt.query_map([], |row| {
@@ -1125,6 +2753,7 @@ impl MetadataIndex {
         let like_pattern = format!("%{normalized}");
         let result = self
             .conn
+            .lock()
             .query_row(
                 "SELECT id, path, language, hash, size_bytes FROM files WHERE path LIKE ?1 LIMIT 1",
                 params![like_pattern],
@@ -1132,7 +2761,7 @@ impl MetadataIndex {
                     Ok(FileInfo {
                         id: row.get(0)?,
                         path: std::path::PathBuf::from(row.get::<_, String>(1)?),
-                        language: Language::from_extension(&row.get::<_, String>(2)?),
+                        language: Language::from_str_lossy(&row.get::<_, String>(2)?),
                         content_hash: row.get(3)?,
                         size_bytes: row.get(4)?,
                     })
@@ -1143,64 +2772,249 @@ impl MetadataIndex {
         Ok(result)
     }
 
+    /// Write a consistent copy of the database to `dest` (via `VACUUM INTO`).
+    ///
+    /// The copy can later be compared against the live index with
+    /// [`crate::index_diff`]. `dest` must not already exist.
+    pub fn snapshot_to(&self, dest: &Path) -> OmniResult<()> {
+        if let Some(parent) = dest.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        self.conn
+            .lock()
+            .execute("VACUUM INTO ?1", params![dest.to_string_lossy()])?;
+        Ok(())
+    }
+
     /// Get the raw connection for advanced queries.
     /// Use sparingly -- prefer adding methods to this struct.
-    pub fn connection(&self) -> &Connection {
-        &self.conn
+    pub fn connection(&self) -> ReentrantMutexGuard<'_, Connection> {
+        self.conn.lock()
     }
 
     // -----------------------------------------------------------------------
     // Dependency operations
     // -----------------------------------------------------------------------
 
-    /// Insert a dependency edge. Idempotent (ignores duplicates).
+    /// Insert a dependency edge. Idempotent: a duplicate keeps the higher
+    /// confidence of the two.
     pub fn insert_dependency(&self, edge: &DependencyEdge) -> OmniResult<()> {
-        self.conn.execute(
-            "INSERT OR IGNORE INTO dependencies (source_id, target_id, kind) VALUES (?1, ?2, ?3)",
-            params![edge.source_id, edge.target_id, edge.kind.as_str()],
+        self.conn.lock().execute(
+            "INSERT INTO dependencies (source_id, target_id, kind, confidence)
+             VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT (source_id, target_id, kind)
+             DO UPDATE SET confidence = max(confidence, excluded.confidence)",
+            params![
+                edge.source_id,
+                edge.target_id,
+                edge.kind.as_str(),
+                f64::from(edge.confidence)
+            ],
         )?;
         Ok(())
     }
 
-    /// Get all dependencies FROM a given symbol (outgoing edges = what it depends on).
-    pub fn get_upstream_dependencies(&self, symbol_id: i64) -> OmniResult<Vec<DependencyEdge>> {
-        let mut stmt = self
-            .conn
-            .prepare("SELECT source_id, target_id, kind FROM dependencies WHERE source_id = ?1")?;
-        let edges = stmt.query_map(params![symbol_id], |row| {
-            let kind_str: String = row.get(2)?;
-            Ok(DependencyEdge {
-                source_id: row.get(0)?,
-                target_id: row.get(1)?,
-                kind: DependencyKind::from_str_lossy(&kind_str),
+    /// Record that a symbol is exposed to other languages as `name` through
+    /// `abi` (`pyo3`, `napi`, `grpc`). Idempotent. Rows go away with the
+    /// symbol.
+    pub fn insert_binding_export(&self, symbol_id: i64, abi: &str, name: &str) -> OmniResult<()> {
+        self.conn.lock().execute(
+            "INSERT OR IGNORE INTO binding_exports (symbol_id, abi, name) VALUES (?1, ?2, ?3)",
+            params![symbol_id, abi, name],
+        )?;
+        Ok(())
+    }
+
+    /// Symbols exported as `name` through `abi`.
+    pub fn binding_export_targets(&self, abi: &str, name: &str) -> OmniResult<Vec<i64>> {
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare_cached(
+            "SELECT symbol_id FROM binding_exports WHERE abi = ?1 AND name = ?2 ORDER BY symbol_id",
+        )?;
+        let ids = stmt.query_map(params![abi, name], |row| row.get(0))?;
+        Ok(ids.filter_map(|id| id.ok()).collect())
+    }
+
+    /// Replace the re-export aliases declared by a file.
+    ///
+    /// Runs in a SAVEPOINT, like [`reindex_file`](Self::reindex_file), so it
+    /// is atomic standalone and inside a batch transaction alike.
+    pub fn replace_symbol_aliases(&self, file_id: i64, aliases: &[SymbolAlias]) -> OmniResult<()> {
+        let conn = self.conn.lock();
+        conn.execute_batch("SAVEPOINT replace_symbol_aliases_sp")?;
+        let result: rusqlite::Result<()> = (|| {
+            conn.execute(
+                "DELETE FROM symbol_aliases WHERE file_id = ?1",
+                params![file_id],
+            )?;
+            let mut stmt = conn.prepare_cached(
+                "INSERT INTO symbol_aliases (file_id, alias_fqn, target_fqn, line)
+                 VALUES (?1, ?2, ?3, ?4)",
+            )?;
+            for alias in aliases {
+                stmt.execute(params![
+                    file_id, alias.alias_fqn, alias.target_fqn, alias.line
+                ])?;
+            }
+            Ok(())
+        })();
+        if result.is_err() {
+            let _ = conn.execute_batch("ROLLBACK TO replace_symbol_aliases_sp");
+        }
+        conn.execute_batch("RELEASE replace_symbol_aliases_sp")?;
+        Ok(result?)
+    }
+
+    /// Replace the TODO / FIXME / HACK comments recorded for a file.
+    ///
+    /// Runs in a SAVEPOINT, like [`replace_symbol_aliases`](Self::replace_symbol_aliases).
+    pub fn replace_todos(&self, file_id: i64, todos: &[TodoComment]) -> OmniResult<()> {
+        let conn = self.conn.lock();
+        conn.execute_batch("SAVEPOINT replace_todos_sp")?;
+        let result: rusqlite::Result<()> = (|| {
+            conn.execute("DELETE FROM todos WHERE file_id = ?1", params![file_id])?;
+            let mut stmt = conn.prepare_cached(
+                "INSERT INTO todos (file_id, line, tag, assignee, text)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+            )?;
+            for todo in todos {
+                stmt.execute(params![
+                    file_id, todo.line, todo.tag, todo.assignee, todo.text
+                ])?;
+            }
+            Ok(())
+        })();
+        if result.is_err() {
+            let _ = conn.execute_batch("ROLLBACK TO replace_todos_sp");
+        }
+        conn.execute_batch("RELEASE replace_todos_sp")?;
+        Ok(result?)
+    }
+
+    /// Recorded TODO / FIXME / HACK comments, by path then line.
+    ///
+    /// `path_prefix` keeps those in a file or directory (matched on whole
+    /// path components); `tag` keeps one marker. At most `limit` are returned.
+    pub fn todos(
+        &self,
+        path_prefix: Option<&str>,
+        tag: Option<&str>,
+        limit: usize,
+    ) -> OmniResult<Vec<TodoComment>> {
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare_cached(
+            "SELECT f.path, t.line, t.tag, t.assignee, t.text
+             FROM todos t JOIN files f ON f.id = t.file_id
+             WHERE (?1 IS NULL OR t.tag = ?1)
+             ORDER BY f.path, t.line",
+        )?;
+        let prefix = path_prefix
+            .map(|p| p.trim_start_matches("./").trim_end_matches('/'))
+            .filter(|p| !p.is_empty());
+        let rows = stmt.query_map(params![tag], |row| {
+            Ok(TodoComment {
+                file_path: PathBuf::from(row.get::<_, String>(0)?),
+                line: row.get(1)?,
+                tag: row.get(2)?,
+                assignee: row.get(3)?,
+                text: row.get(4)?,
+                author: None,
+                age_days: None,
             })
         })?;
+        Ok(rows
+            .filter_map(Result::ok)
+            .filter(|todo| prefix.map_or(true, |p| todo.file_path.starts_with(p)))
+            .take(limit)
+            .collect())
+    }
+
+    /// Resolve `fqn` through re-export aliases to the symbol it names.
+    ///
+    /// Each hop tries an alias for the exact name, then a glob re-export of
+    /// its module, and matches either by suffix when `fqn` is qualified, so
+    /// `models::User` finds a barrel whose full module path has a prefix.
+    /// Chains are followed up to [`MAX_ALIAS_HOPS`] deep, which also stops
+    /// cycles.
+    pub fn resolve_alias(&self, fqn: &str) -> OmniResult<Option<Symbol>> {
+        let mut current = fqn.to_string();
+        for _ in 0..MAX_ALIAS_HOPS {
+            let Some(target) = self.alias_target(&current)? else {
+                return Ok(None);
+            };
+            if let Some(symbol) = self.get_symbol_by_fqn(&target)? {
+                return Ok(Some(symbol));
+            }
+            current = target;
+        }
+        Ok(None)
+    }
+
+    /// One alias hop: what `fqn` is re-exported from, if anything.
+    fn alias_target(&self, fqn: &str) -> OmniResult<Option<String>> {
+        if let Some(target) = self.lookup_alias(fqn)? {
+            return Ok(Some(target));
+        }
+        let Some((module, sep, name)) = split_fqn(fqn) else {
+            return Ok(None);
+        };
+        let glob = format!("{module}{sep}*");
+        Ok(self.lookup_alias(&glob)?.and_then(|target| {
+            let prefix = target.strip_suffix('*')?;
+            Some(format!("{prefix}{name}"))
+        }))
+    }
+
+    fn lookup_alias(&self, alias_fqn: &str) -> OmniResult<Option<String>> {
+        let conn = self.conn.lock();
+        let target = conn
+            .prepare_cached("SELECT target_fqn FROM symbol_aliases WHERE alias_fqn = ?1 LIMIT 1")?
+            .query_row(params![alias_fqn], |row| row.get(0))
+            .optional()?;
+        if target.is_some() || split_fqn(alias_fqn).is_none() {
+            return Ok(target);
+        }
+        // Suffix match on a segment boundary; shortest alias wins.
+        let target = conn
+            .prepare_cached(
+                "SELECT target_fqn FROM symbol_aliases
+                 WHERE length(alias_fqn) > length(?1)
+                   AND substr(alias_fqn, -length(?1)) = ?1
+                   AND substr(alias_fqn, -length(?1) - 1, 1) IN ('/', '.', ':')
+                 ORDER BY length(alias_fqn), id LIMIT 1",
+            )?
+            .query_row(params![alias_fqn], |row| row.get(0))
+            .optional()?;
+        Ok(target)
+    }
+
+    /// Get all dependencies FROM a given symbol (outgoing edges = what it depends on).
+    pub fn get_upstream_dependencies(&self, symbol_id: i64) -> OmniResult<Vec<DependencyEdge>> {
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare(
+            "SELECT source_id, target_id, kind, confidence FROM dependencies WHERE source_id = ?1",
+        )?;
+        let edges = stmt.query_map(params![symbol_id], dependency_from_row)?;
         Ok(edges.filter_map(|e| e.ok()).collect())
     }
 
     /// Get all dependencies TO a given symbol (incoming edges = what depends on it).
     pub fn get_downstream_dependencies(&self, symbol_id: i64) -> OmniResult<Vec<DependencyEdge>> {
-        let mut stmt = self
-            .conn
-            .prepare("SELECT source_id, target_id, kind FROM dependencies WHERE target_id = ?1")?;
-        let edges = stmt.query_map(params![symbol_id], |row| {
-            let kind_str: String = row.get(2)?;
-            Ok(DependencyEdge {
-                source_id: row.get(0)?,
-                target_id: row.get(1)?,
-                kind: DependencyKind::from_str_lossy(&kind_str),
-            })
-        })?;
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare(
+            "SELECT source_id, target_id, kind, confidence FROM dependencies WHERE target_id = ?1",
+        )?;
+        let edges = stmt.query_map(params![symbol_id], dependency_from_row)?;
         Ok(edges.filter_map(|e| e.ok()).collect())
     }
 
     /// Delete dependencies involving a symbol (both as source and target).
     pub fn delete_dependencies_for_symbol(&self, symbol_id: i64) -> OmniResult<usize> {
-        let count1 = self.conn.execute(
+        let count1 = self.conn.lock().execute(
             "DELETE FROM dependencies WHERE source_id = ?1",
             params![symbol_id],
         )?;
-        let count2 = self.conn.execute(
+        let count2 = self.conn.lock().execute(
             "DELETE FROM dependencies WHERE target_id = ?1",
             params![symbol_id],
         )?;
@@ -1209,27 +3023,81 @@ impl MetadataIndex {
 
     /// Count total dependency edges.
     pub fn dependency_count(&self) -> OmniResult<usize> {
-        let count: i64 = self
-            .conn
-            .query_row("SELECT COUNT(*) FROM dependencies", [], |row| row.get(0))?;
+        let count: i64 =
+            self.conn
+                .lock()
+                .query_row("SELECT COUNT(*) FROM dependencies", [], |row| row.get(0))?;
         Ok(count as usize)
     }
     /// Get ALL dependency edges from the database.
     ///
-    /// Used to populate the in-memory dependency graph on engine startup.
-    pub fn get_all_dependencies(&self) -> OmniResult<Vec<DependencyEdge>> {
-        let mut stmt = self
-            .conn
-            .prepare("SELECT source_id, target_id, kind FROM dependencies")?;
-        let edges = stmt.query_map([], |row| {
-            let kind_str: String = row.get(2)?;
-            Ok(DependencyEdge {
-                source_id: row.get(0)?,
-                target_id: row.get(1)?,
-                kind: DependencyKind::from_str_lossy(&kind_str),
-            })
+    /// Used to populate the in-memory dependency graph on engine startup.
+    pub fn get_all_dependencies(&self) -> OmniResult<Vec<DependencyEdge>> {
+        let conn = self.conn.lock();
+        let mut stmt =
+            conn.prepare("SELECT source_id, target_id, kind, confidence FROM dependencies")?;
+        let edges = stmt.query_map([], dependency_from_row)?;
+        Ok(edges.filter_map(|e| e.ok()).collect())
+    }
+
+    /// Symbol dependency edges summed per (source file, target file) pair.
+    ///
+    /// Returns `(source_path, target_path, confidence_sum, edge_count)`;
+    /// edges between symbols of the same file are left out.
+    pub fn file_dependency_weights(&self) -> OmniResult<Vec<(String, String, f64, usize)>> {
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare(
+            "SELECT fs.path, ft.path, SUM(d.confidence), COUNT(*)
+             FROM dependencies d
+             JOIN symbols s ON s.id = d.source_id
+             JOIN symbols t ON t.id = d.target_id
+             JOIN files fs ON fs.id = s.file_id
+             JOIN files ft ON ft.id = t.file_id
+             WHERE s.file_id != t.file_id
+             GROUP BY s.file_id, t.file_id
+             ORDER BY fs.path, ft.path",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, f64>(2)?,
+                row.get::<_, i64>(3)? as usize,
+            ))
         })?;
-        Ok(edges.filter_map(|e| e.ok()).collect())
+        Ok(rows.filter_map(|r| r.ok()).collect())
+    }
+
+    /// The symbol edges from `source` to `target` (both file paths), as
+    /// `(source_fqn, source_line, target_fqn, kind)` ordered by line.
+    pub fn dependencies_between_files(
+        &self,
+        source: &Path,
+        target: &Path,
+    ) -> OmniResult<Vec<(String, u32, String, DependencyKind)>> {
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare(
+            "SELECT s.fqn, s.line, t.fqn, d.kind
+             FROM dependencies d
+             JOIN symbols s ON s.id = d.source_id
+             JOIN symbols t ON t.id = d.target_id
+             JOIN files fs ON fs.id = s.file_id
+             JOIN files ft ON ft.id = t.file_id
+             WHERE fs.path = ?1 AND ft.path = ?2
+             ORDER BY s.line, t.fqn",
+        )?;
+        let rows = stmt.query_map(
+            params![file_path_key(&conn, source)?, file_path_key(&conn, target)?],
+            |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, u32>(1)?,
+                    row.get::<_, String>(2)?,
+                    DependencyKind::from_str_lossy(&row.get::<_, String>(3)?),
+                ))
+            },
+        )?;
+        Ok(rows.filter_map(|r| r.ok()).collect())
     }
 }
 
@@ -1249,7 +3117,7 @@ pub struct IndexStats {
 // ---------------------------------------------------------------------------
 
 impl MetadataIndex {
-    // Design: per-token AND search with OR fallback, same strategy as keyword_search.
+    // Design: fts_query syntax with OR fallback, same strategy as keyword_search.
     // Searches message + summary + author fields via commits_fts virtual table.
 
     /// Search commits using FTS5 full-text search over message and summary.
@@ -1257,36 +3125,28 @@ impl MetadataIndex {
     /// Returns rowids sorted by BM25 relevance. The caller resolves each
     /// rowid to a `CommitInfo` via the `commits` table.
     pub fn search_commits(&self, query: &str, limit: usize) -> OmniResult<Vec<i64>> {
-        let tokens: Vec<String> = query
-            .split_whitespace()
-            .filter(|t| !t.is_empty())
-            .map(|t| format!("\"{}\"", t.replace('"', "")))
-            .collect();
-
-        if tokens.is_empty() {
+        let Some(fts) = fts_query::build(query) else {
             return Ok(Vec::new());
-        }
+        };
 
         let sql = "SELECT rowid FROM commits_fts
                    WHERE commits_fts MATCH ?1
                    ORDER BY bm25(commits_fts)
                    LIMIT ?2";
 
-        let and_query = tokens.join(" AND ");
-        let mut stmt = self.conn.prepare(sql)?;
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare(sql)?;
         let and_ids: Vec<i64> = stmt
-            .query_map(params![and_query, limit as i64], |row| row.get(0))?
+            .query_map(params![fts.expr, limit as i64], |row| row.get(0))?
             .filter_map(|r| r.ok())
             .collect();
 
-        if !and_ids.is_empty() || tokens.len() == 1 {
+        let Some(or_query) = fts.fallback.filter(|_| and_ids.is_empty()) else {
             return Ok(and_ids);
-        }
+        };
 
         // OR fallback
-        let or_query = tokens.join(" OR ");
-        let mut stmt2 = self.conn.prepare(sql)?;
-        let or_ids: Vec<i64> = stmt2
+        let or_ids: Vec<i64> = stmt
             .query_map(params![or_query, limit as i64], |row| row.get(0))?
             .filter_map(|r| r.ok())
             .collect();
@@ -1312,7 +3172,8 @@ impl MetadataIndex {
              FROM commits WHERE rowid IN ({placeholders})"
         );
 
-        let mut stmt = self.conn.prepare(&sql)?;
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare(&sql)?;
         let params_vec: Vec<&dyn rusqlite::ToSql> =
             rowids.iter().map(|r| r as &dyn rusqlite::ToSql).collect();
         let commits = stmt
@@ -1364,7 +3225,7 @@ impl MetadataIndex {
         chunk_ids: &[i64],
     ) -> OmniResult<i64> {
         let ids_json = serde_json::to_string(chunk_ids).unwrap_or_else(|_| "[]".into());
-        self.conn.execute(
+        self.conn.lock().execute(
             "INSERT INTO external_docs (source_url, title, content, chunk_ids)
              VALUES (?1, ?2, ?3, ?4)
              ON CONFLICT(source_url) DO UPDATE SET
@@ -1373,7 +3234,7 @@ impl MetadataIndex {
                 chunk_ids = excluded.chunk_ids",
             params![source_url, title, content, ids_json],
         )?;
-        let id: i64 = self.conn.query_row(
+        let id: i64 = self.conn.lock().query_row(
             "SELECT id FROM external_docs WHERE source_url = ?1",
             params![source_url],
             |row| row.get(0),
@@ -1383,7 +3244,8 @@ impl MetadataIndex {
 
     /// List all ingested external documents.
     pub fn list_external_docs(&self) -> OmniResult<Vec<ExternalDoc>> {
-        let mut stmt = self.conn.prepare(
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare(
             "SELECT source_url, title, content, chunk_ids FROM external_docs
              ORDER BY ingested_at DESC",
         )?;
@@ -1406,6 +3268,7 @@ impl MetadataIndex {
     /// Check if a URL has already been ingested.
     pub fn external_doc_exists(&self, source_url: &str) -> bool {
         self.conn
+            .lock()
             .query_row(
                 "SELECT 1 FROM external_docs WHERE source_url = ?1",
                 params![source_url],
@@ -1428,7 +3291,8 @@ impl MetadataIndex {
         if files.is_empty() {
             return Ok(());
         }
-        let mut stmt = self.conn.prepare_cached(
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare_cached(
             "INSERT OR IGNORE INTO commit_files (commit_hash, file_path) VALUES (?1, ?2)",
         )?;
         for file_path in files {
@@ -1449,6 +3313,7 @@ impl MetadataIndex {
         // Capability check: use junction table only when it exists.
         let table_exists: bool = self
             .conn
+            .lock()
             .query_row(
                 "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name='commit_files'",
                 [],
@@ -1462,7 +3327,8 @@ impl MetadataIndex {
             return self.commits_for_file_like(file_path, limit);
         }
 
-        let mut stmt = self.conn.prepare(
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare(
             "SELECT c.hash, c.message, c.author, c.timestamp, c.summary, c.files_changed
              FROM commits c
              JOIN commit_files cf ON c.hash = cf.commit_hash
@@ -1497,7 +3363,8 @@ impl MetadataIndex {
         limit: usize,
     ) -> OmniResult<Vec<crate::commits::CommitInfo>> {
         let pattern = format!("%\"{file_path}\"%");
-        let mut stmt = self.conn.prepare(
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare(
             "SELECT hash, message, author, timestamp, summary, files_changed
              FROM commits
              WHERE files_changed LIKE ?1
@@ -1539,7 +3406,8 @@ impl MetadataIndex {
             return Ok(());
         }
 
-        let tx = self.conn.unchecked_transaction()?;
+        let conn = self.conn.lock();
+        let tx = conn.unchecked_transaction()?;
         {
             let mut stmt = tx.prepare_cached(
                 "INSERT OR REPLACE INTO file_graph_edges
@@ -1548,8 +3416,8 @@ impl MetadataIndex {
             )?;
             for edge in edges {
                 stmt.execute(params![
-                    edge.source.to_string_lossy().as_ref(),
-                    edge.target.to_string_lossy().as_ref(),
+                    file_path_key(&tx, &edge.source)?,
+                    file_path_key(&tx, &edge.target)?,
                     edge.edge_type.as_str(),
                     edge.weight,
                 ])?;
@@ -1571,6 +3439,7 @@ impl MetadataIndex {
         // Table may not exist on databases created before schema v5.
         let table_exists: bool = self
             .conn
+            .lock()
             .query_row(
                 "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name='file_graph_edges'",
                 [],
@@ -1583,8 +3452,9 @@ impl MetadataIndex {
             return Ok(Vec::new());
         }
 
-        let mut stmt = self
-            .conn
+        let conn = self.conn.lock();
+
+        let mut stmt = conn
             .prepare("SELECT source_path, target_path, edge_type, weight FROM file_graph_edges")?;
 
         let edges = stmt
@@ -1614,10 +3484,10 @@ impl MetadataIndex {
     ///
     /// Called before re-persisting edges for a re-indexed file.
     pub fn delete_file_graph_edges_for_file(&self, path: &std::path::Path) -> OmniResult<()> {
-        let path_str = path.to_string_lossy();
-        self.conn.execute(
+        let conn = self.conn.lock();
+        conn.execute(
             "DELETE FROM file_graph_edges WHERE source_path = ?1",
-            params![path_str.as_ref()],
+            params![file_path_key(&conn, path)?],
         )?;
         Ok(())
     }
@@ -1635,7 +3505,7 @@ impl MetadataIndex {
         let json = serde_json::to_string(tokens).map_err(|e| {
             crate::error::OmniError::Internal(format!("sparse vector serialize: {e}"))
         })?;
-        self.conn.execute(
+        self.conn.lock().execute(
             "INSERT OR REPLACE INTO sparse_vectors (chunk_id, tokens) VALUES (?1, ?2)",
             params![chunk_id, json],
         )?;
@@ -1646,6 +3516,7 @@ impl MetadataIndex {
     pub fn get_sparse_vector(&self, chunk_id: i64) -> OmniResult<Option<Vec<(u32, f32)>>> {
         let result: Option<String> = self
             .conn
+            .lock()
             .query_row(
                 "SELECT tokens FROM sparse_vectors WHERE chunk_id = ?1",
                 params![chunk_id],
@@ -1682,9 +3553,9 @@ impl MetadataIndex {
         // Build a lookup map for the query tokens: token_id → weight.
         let query_map: std::collections::HashMap<u32, f32> = query_tokens.iter().copied().collect();
 
-        let mut stmt = self
-            .conn
-            .prepare("SELECT chunk_id, tokens FROM sparse_vectors")?;
+        let conn = self.conn.lock();
+
+        let mut stmt = conn.prepare("SELECT chunk_id, tokens FROM sparse_vectors")?;
 
         let mut scores: Vec<(i64, f32)> = stmt
             .query_map([], |row| {
@@ -1716,9 +3587,8 @@ impl MetadataIndex {
     /// Returns an empty Vec (not an error) when the table is empty.
     #[allow(clippy::type_complexity)]
     pub fn get_all_sparse_vectors(&self) -> OmniResult<Vec<(i64, Vec<(u32, f32)>)>> {
-        let mut stmt = self
-            .conn
-            .prepare("SELECT chunk_id, tokens FROM sparse_vectors")?;
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare("SELECT chunk_id, tokens FROM sparse_vectors")?;
 
         let rows = stmt
             .query_map([], |row| {
@@ -1735,6 +3605,376 @@ impl MetadataIndex {
     }
 }
 
+/// Whether a chunk should be indexed in the CJK trigram table.
+fn chunk_has_cjk(content: &str, doc_comment: Option<&str>) -> bool {
+    fts_query::contains_cjk(content) || doc_comment.is_some_and(fts_query::contains_cjk)
+}
+
+/// Store a chunk body in `chunk_blobs` (if not already present) and return its key.
+///
+/// Bodies are keyed by xxh3_64 so identical chunks across files share a row.
+/// A key already holding different text is a collision: probe the following
+/// keys until one is free or holds this text, so distinct bodies never share
+/// a row.
+fn store_blob(conn: &Connection, content: &str) -> rusqlite::Result<i64> {
+    let mut hash = xxhash_rust::xxh3::xxh3_64(content.as_bytes()) as i64;
+    let mut lookup = conn.prepare_cached("SELECT content FROM chunk_blobs WHERE hash = ?1")?;
+    loop {
+        match lookup
+            .query_row(params![hash], |row| row.get::<_, String>(0))
+            .optional()?
+        {
+            Some(existing) if existing == content => return Ok(hash),
+            Some(_) => hash = hash.wrapping_add(1),
+            None => break,
+        }
+    }
+    conn.prepare_cached("INSERT INTO chunk_blobs (hash, content) VALUES (?1, ?2)")?
+        .execute(params![hash, content])?;
+    Ok(hash)
+}
+
+// ---------------------------------------------------------------------------
+// Change journal (schema v13)
+// ---------------------------------------------------------------------------
+
+impl MetadataIndex {
+    /// Journal entries with a sequence number greater than `seq`, oldest
+    /// first. Pass `0` to read the whole journal.
+    ///
+    /// A file can appear several times; only its last entry matters. After a
+    /// [`ChangeOp::Cleared`] entry, consumers should drop everything they
+    /// hold before applying the entries that follow it.
+    pub fn changes_since(&self, seq: i64) -> OmniResult<Vec<FileChange>> {
+        let conn = self.conn.lock();
+        let mut stmt = conn
+            .prepare("SELECT seq, file, op, timestamp FROM changes WHERE seq > ?1 ORDER BY seq")?;
+        let rows = stmt.query_map(params![seq], |row| {
+            Ok(FileChange {
+                seq: row.get(0)?,
+                file: row.get(1)?,
+                op: ChangeOp::from_str_lossy(&row.get::<_, String>(2)?),
+                timestamp: row.get(3)?,
+            })
+        })?;
+        let mut changes = Vec::new();
+        for row in rows {
+            changes.push(row?);
+        }
+        Ok(changes)
+    }
+
+    /// Sequence number of the newest journal entry (0 when empty).
+    pub fn last_change_seq(&self) -> OmniResult<i64> {
+        let seq: Option<i64> =
+            self.conn
+                .lock()
+                .query_row("SELECT MAX(seq) FROM changes", [], |row| row.get(0))?;
+        Ok(seq.unwrap_or(0))
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Background jobs (schema v15)
+// ---------------------------------------------------------------------------
+
+const JOB_COLUMNS: &str = "id, kind, state, total, done, error, created_at, updated_at";
+
+fn job_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<BackgroundJob> {
+    Ok(BackgroundJob {
+        id: row.get(0)?,
+        kind: row.get(1)?,
+        state: JobState::from_str_lossy(&row.get::<_, String>(2)?),
+        total: row.get::<_, i64>(3)?.max(0) as usize,
+        done: row.get::<_, i64>(4)?.max(0) as usize,
+        error: row.get(5)?,
+        created_at: row.get(6)?,
+        updated_at: row.get(7)?,
+    })
+}
+
+impl MetadataIndex {
+    /// Start a `kind` job covering `total` units of work.
+    ///
+    /// At most one job of each kind runs at a time: a running job of the
+    /// same kind is marked failed as superseded.
+    pub fn create_job(&self, kind: &str, total: usize) -> OmniResult<BackgroundJob> {
+        let conn = self.conn.lock();
+        let tx = conn.unchecked_transaction()?;
+        tx.execute(
+            "UPDATE jobs SET state = 'failed', error = 'superseded by a newer job',
+             updated_at = datetime('now') WHERE kind = ?1 AND state = 'running'",
+            params![kind],
+        )?;
+        tx.execute(
+            "INSERT INTO jobs (kind, total) VALUES (?1, ?2)",
+            params![kind, total as i64],
+        )?;
+        let id = tx.last_insert_rowid();
+        tx.commit()?;
+        drop(conn);
+        self.get_job(id)?
+            .ok_or_else(|| OmniError::Internal(format!("job {id} vanished after insert")))
+    }
+
+    /// Look up a job by id.
+    pub fn get_job(&self, id: i64) -> OmniResult<Option<BackgroundJob>> {
+        Ok(self
+            .conn
+            .lock()
+            .query_row(
+                &format!("SELECT {JOB_COLUMNS} FROM jobs WHERE id = ?1"),
+                params![id],
+                job_from_row,
+            )
+            .optional()?)
+    }
+
+    /// The running job of `kind`, if any.
+    pub fn active_job(&self, kind: &str) -> OmniResult<Option<BackgroundJob>> {
+        Ok(self
+            .conn
+            .lock()
+            .query_row(
+                &format!(
+                    "SELECT {JOB_COLUMNS} FROM jobs WHERE kind = ?1 AND state = 'running'
+                     ORDER BY id DESC LIMIT 1"
+                ),
+                params![kind],
+                job_from_row,
+            )
+            .optional()?)
+    }
+
+    /// The `limit` most recently created jobs, newest first.
+    pub fn recent_jobs(&self, limit: usize) -> OmniResult<Vec<BackgroundJob>> {
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {JOB_COLUMNS} FROM jobs ORDER BY id DESC LIMIT ?1"
+        ))?;
+        let rows = stmt.query_map(params![limit as i64], job_from_row)?;
+        let mut jobs = Vec::new();
+        for row in rows {
+            jobs.push(row?);
+        }
+        Ok(jobs)
+    }
+
+    /// Record that `done` units of job `id` have completed.
+    pub fn update_job_progress(&self, id: i64, done: usize) -> OmniResult<()> {
+        self.conn.lock().execute(
+            "UPDATE jobs SET done = ?1, updated_at = datetime('now') WHERE id = ?2",
+            params![done as i64, id],
+        )?;
+        Ok(())
+    }
+
+    /// Move job `id` to a final `state`, with an optional error message.
+    pub fn finish_job(&self, id: i64, state: JobState, error: Option<&str>) -> OmniResult<()> {
+        self.conn.lock().execute(
+            "UPDATE jobs SET state = ?1, error = ?2, updated_at = datetime('now') WHERE id = ?3",
+            params![state.as_str(), error, id],
+        )?;
+        Ok(())
+    }
+}
+
+/// Whether `table` has a column named `column`.
+fn has_column(conn: &Connection, table: &str, column: &str) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT COUNT(*) FROM pragma_table_info(?1) WHERE name = ?2",
+        params![table, column],
+        |row| row.get::<_, i64>(0),
+    )
+    .map(|n| n > 0)
+}
+
+/// Map a `source_id, target_id, kind, confidence` row.
+fn dependency_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<DependencyEdge> {
+    Ok(DependencyEdge {
+        source_id: row.get(0)?,
+        target_id: row.get(1)?,
+        kind: DependencyKind::from_str_lossy(&row.get::<_, String>(2)?),
+        confidence: row.get::<_, f64>(3)? as f32,
+    })
+}
+
+/// Split a qualified name at its last `::` or `.` into module, separator,
+/// and name.
+fn split_fqn(fqn: &str) -> Option<(&str, &str, &str)> {
+    if let Some((module, name)) = fqn.rsplit_once("::") {
+        return Some((module, "::", name));
+    }
+    fqn.rsplit_once('.')
+        .map(|(module, name)| (module, ".", name))
+}
+
+/// The `files.path` spelling for `path`. Where paths are case-insensitive,
+/// an indexed row that differs only in case wins, so one file keeps one row
+/// whichever spelling the watcher or an editor reports.
+fn file_path_key(conn: &Connection, path: &Path) -> rusqlite::Result<String> {
+    let path = crate::paths::index_form(path);
+    if !crate::paths::CASE_INSENSITIVE {
+        return Ok(path);
+    }
+    let existing: Option<String> = conn
+        .prepare_cached("SELECT path FROM files WHERE path = ?1 COLLATE NOCASE LIMIT 1")?
+        .query_row(params![path], |row| row.get(0))
+        .optional()?;
+    Ok(existing.unwrap_or(path))
+}
+
+/// SQL conditions over `chunks c JOIN files f` for the filters in
+/// `options`, ANDed together, or `None` when no filter is set. Parameters
+/// are numbered after, and appended to, `values`.
+fn scope_conditions(
+    conn: &Connection,
+    options: &SearchOptions,
+    values: &mut Vec<rusqlite::types::Value>,
+) -> OmniResult<Option<String>> {
+    use rusqlite::types::Value;
+
+    let mut conditions = Vec::new();
+    for (name, date, op) in [
+        ("modified_after", &options.modified_after, ">="),
+        ("modified_before", &options.modified_before, "<"),
+    ] {
+        let Some(date) = date else { continue };
+        let normalized: Option<String> =
+            conn.query_row("SELECT datetime(?1)", params![date], |row| row.get(0))?;
+        let Some(normalized) = normalized else {
+            return Err(OmniError::Config {
+                details: format!("invalid {name} {date:?}: expected an ISO 8601 date or datetime"),
+            });
+        };
+        values.push(Value::Text(normalized));
+        conditions.push(format!("f.last_modified {op} ?{}", values.len()));
+    }
+    for (size, op) in [
+        (options.min_size_bytes, ">="),
+        (options.max_size_bytes, "<="),
+    ] {
+        if let Some(size) = size {
+            values.push(Value::Integer(i64::try_from(size).unwrap_or(i64::MAX)));
+            conditions.push(format!("f.size_bytes {op} ?{}", values.len()));
+        }
+    }
+    if !options.files.is_empty() {
+        let first = values.len() + 1;
+        for path in &options.files {
+            values.push(Value::Text(file_path_key(conn, path)?));
+        }
+        let placeholders = (first..=values.len())
+            .map(|n| format!("?{n}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        conditions.push(format!("f.path IN ({placeholders})"));
+    }
+    if let Some(language) = &options.language {
+        values.push(Value::Text(language.clone()));
+        // Embedded code (a fenced block, a <script>) matches its own language.
+        conditions.push(format!(
+            "COALESCE(c.language, f.language) = ?{} COLLATE NOCASE",
+            values.len()
+        ));
+    }
+    if let Some(kind) = &options.kind {
+        values.push(Value::Text(kind.clone()));
+        conditions.push(format!("c.kind = ?{} COLLATE NOCASE", values.len()));
+    }
+    if let Some(glob) = &options.path_glob {
+        if let Err(e) = globset::Glob::new(glob) {
+            return Err(OmniError::Config {
+                details: format!("invalid path_glob {glob:?}: {e}"),
+            });
+        }
+        values.push(Value::Text(glob.clone()));
+        conditions.push(format!("path_glob_match(?{}, f.path)", values.len()));
+    }
+    Ok((!conditions.is_empty()).then(|| conditions.join(" AND ")))
+}
+
+/// [`scope_conditions`] as a `rowid IN (...)` condition for the FTS tables,
+/// whose rowids are chunk ids.
+fn scoped_rowids(
+    conn: &Connection,
+    options: &SearchOptions,
+    values: &mut Vec<rusqlite::types::Value>,
+) -> OmniResult<Option<String>> {
+    Ok(scope_conditions(conn, options, values)?.map(|conditions| {
+        format!(
+            "rowid IN (SELECT c.id FROM chunks c JOIN files f ON f.id = c.file_id \
+             WHERE {conditions})"
+        )
+    }))
+}
+
+/// Register the SQL functions the index queries use:
+///
+/// - `path_glob_match(glob, path)`: case-insensitive [`globset`] match of a
+///   stored path, with `\` read as `/`. The compiled glob is cached for the
+///   statement.
+fn register_functions(conn: &Connection) -> rusqlite::Result<()> {
+    use rusqlite::functions::FunctionFlags;
+
+    conn.create_scalar_function(
+        "path_glob_match",
+        2,
+        FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+        |ctx| {
+            let matcher = ctx.get_or_create_aux(0, |glob| {
+                globset::GlobBuilder::new(glob.as_str()?)
+                    .case_insensitive(true)
+                    .build()
+                    .map(|g| g.compile_matcher())
+                    .map_err(|e| rusqlite::Error::UserFunctionError(e.into()))
+            })?;
+            let path = ctx.get::<String>(1)?;
+            Ok(matcher.is_match(path.replace('\\', "/")))
+        },
+    )
+}
+
+fn record_change(conn: &Connection, file: &str, op: ChangeOp) -> OmniResult<()> {
+    conn.prepare_cached("INSERT INTO changes (file, op) VALUES (?1, ?2)")?
+        .execute(params![file, op.as_str()])?;
+    Ok(())
+}
+
+/// Drop `file_id`'s parts of symbols declared across files, handing each
+/// symbol it owns that another file still declares over to one of those
+/// files. Whatever the file still owns afterwards is its alone.
+fn release_symbol_parts(conn: &Connection, file_id: i64) -> rusqlite::Result<()> {
+    conn.prepare_cached(
+        "UPDATE symbols SET (file_id, line, chunk_id) = (
+             SELECT p.file_id, p.line, p.chunk_id FROM symbol_parts p
+             WHERE p.symbol_id = symbols.id AND p.file_id != ?1
+             ORDER BY p.file_id LIMIT 1)
+         WHERE file_id = ?1 AND EXISTS (
+             SELECT 1 FROM symbol_parts p
+             WHERE p.symbol_id = symbols.id AND p.file_id != ?1)",
+    )?
+    .execute(params![file_id])?;
+    conn.prepare_cached("DELETE FROM symbol_parts WHERE file_id = ?1")?
+        .execute(params![file_id])?;
+    Ok(())
+}
+
+/// [`release_symbol_parts`] for the file stored under `path`, if any.
+fn release_symbol_parts_by_path(conn: &Connection, path: &str) -> rusqlite::Result<()> {
+    let file_id: Option<i64> = conn
+        .query_row(
+            "SELECT id FROM files WHERE path = ?1",
+            params![path],
+            |row| row.get(0),
+        )
+        .optional()?;
+    match file_id {
+        Some(file_id) => release_symbol_parts(conn, file_id),
+        None => Ok(()),
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Parse helpers (delegates to centralized methods on types)
 // ---------------------------------------------------------------------------
@@ -1743,6 +3983,10 @@ fn parse_chunk_kind(s: &str) -> ChunkKind {
     ChunkKind::from_str_lossy(s)
 }
 
+fn parse_chunk_language(s: Option<String>) -> Option<Language> {
+    s.map(|s| Language::from_str_lossy(&s))
+}
+
 fn parse_visibility(s: &str) -> Visibility {
     Visibility::from_str_lossy(s)
 }
@@ -1786,7 +4030,10 @@ mod tests {
             weight: 0.85,
             vector_id: None,
             is_summary: false,
+            is_generated: false,
+            language: None,
             content_hash: 0,
+            complexity: ComplexityMetrics::default(),
         }
     }
 
@@ -1826,6 +4073,25 @@ mod tests {
         assert_eq!(retrieved.size_bytes, 1024);
     }
 
+    /// Readers on several threads share one index through `&self`.
+    #[test]
+    fn test_index_is_shared_across_threads() {
+        let index = open_test_db();
+        let file_id = index.upsert_file(&test_file_info()).expect("upsert file");
+        index
+            .insert_chunk(&test_chunk(file_id))
+            .expect("insert chunk");
+
+        std::thread::scope(|scope| {
+            let readers: Vec<_> = (0..4)
+                .map(|_| scope.spawn(|| index.chunk_count().expect("count chunks")))
+                .collect();
+            for reader in readers {
+                assert_eq!(reader.join().expect("reader thread"), 1);
+            }
+        });
+    }
+
     #[test]
     fn test_upsert_file_updates_existing() {
         let index = open_test_db();
@@ -1875,6 +4141,25 @@ mod tests {
         assert!(retrieved.is_none());
     }
 
+    #[test]
+    fn test_vector_ids_for_file() {
+        let index = open_test_db();
+        let file = test_file_info();
+        let file_id = index.upsert_file(&file).expect("upsert");
+        let embedded = index.insert_chunk(&test_chunk(file_id)).expect("insert");
+        let mut pending = test_chunk(file_id);
+        pending.symbol_path = "main.pending".to_string();
+        index.insert_chunk(&pending).expect("insert");
+        index.set_chunk_vector_id(embedded, 7).expect("set vector");
+
+        assert_eq!(index.vector_ids_for_file(&file.path).expect("ids"), vec![7]);
+        index.delete_file(&file.path).expect("delete");
+        assert!(index
+            .vector_ids_for_file(&file.path)
+            .expect("ids")
+            .is_empty());
+    }
+
     #[test]
     fn test_insert_and_get_chunks() {
         let index = open_test_db();
@@ -1942,6 +4227,105 @@ mod tests {
         assert_eq!(found.kind, ChunkKind::Function);
     }
 
+    #[test]
+    fn test_dependency_confidence_keeps_best_resolution() {
+        let index = open_test_db();
+        let file_id = index.upsert_file(&test_file_info()).expect("upsert file");
+        let source = index
+            .insert_symbol(&test_symbol(file_id))
+            .expect("insert symbol");
+        let target = index
+            .insert_symbol(&Symbol {
+                name: "world".to_string(),
+                fqn: "main.world".to_string(),
+                ..test_symbol(file_id)
+            })
+            .expect("insert symbol");
+
+        let edge = |confidence| DependencyEdge {
+            source_id: source,
+            target_id: target,
+            kind: DependencyKind::Calls,
+            confidence,
+        };
+        index
+            .insert_dependency(&edge(DependencyEdge::NAME_ONLY))
+            .expect("insert");
+        index
+            .insert_dependency(&edge(DependencyEdge::SUFFIX))
+            .expect("insert");
+        index
+            .insert_dependency(&edge(DependencyEdge::NAME_ONLY))
+            .expect("insert");
+
+        let edges = index.get_upstream_dependencies(source).expect("edges");
+        assert_eq!(edges.len(), 1);
+        assert!((edges[0].confidence - DependencyEdge::SUFFIX).abs() < 1e-6);
+        assert_eq!(index.get_all_dependencies().expect("edges").len(), 1);
+    }
+
+    #[test]
+    fn test_file_dependency_weights_sum_cross_file_edges() {
+        let index = open_test_db();
+        let a = index.upsert_file(&test_file_info()).expect("upsert file");
+        let b = index
+            .upsert_file(&FileInfo {
+                path: PathBuf::from("src/util.py"),
+                ..test_file_info()
+            })
+            .expect("upsert file");
+        let symbol = |file_id, name: &str| {
+            index
+                .insert_symbol(&Symbol {
+                    name: name.to_string(),
+                    fqn: format!("{file_id}.{name}"),
+                    ..test_symbol(file_id)
+                })
+                .expect("insert symbol")
+        };
+        let (run, local, helper, parse) = (
+            symbol(a, "run"),
+            symbol(a, "local"),
+            symbol(b, "helper"),
+            symbol(b, "parse"),
+        );
+        for (source_id, target_id, confidence) in [
+            (run, helper, DependencyEdge::EXACT),
+            (run, parse, DependencyEdge::NAME_ONLY),
+            (run, local, DependencyEdge::EXACT),
+        ] {
+            index
+                .insert_dependency(&DependencyEdge {
+                    source_id,
+                    target_id,
+                    kind: DependencyKind::Calls,
+                    confidence,
+                })
+                .expect("insert");
+        }
+
+        let weights = index.file_dependency_weights().expect("weights");
+        assert_eq!(weights.len(), 1, "same-file edges are excluded");
+        let (source, target, weight, count) = &weights[0];
+        assert_eq!(
+            (source.as_str(), target.as_str()),
+            ("src/main.py", "src/util.py")
+        );
+        assert_eq!(*count, 2);
+        assert!((weight - 1.3).abs() < 1e-6);
+
+        let sites = index
+            .dependencies_between_files(Path::new("src/main.py"), Path::new("src/util.py"))
+            .expect("sites");
+        assert_eq!(sites.len(), 2);
+        assert!(
+            sites
+                .iter()
+                .all(|(source, _, _, kind)| source.ends_with(".run")
+                    && *kind == DependencyKind::Calls)
+        );
+    }
+
     #[test]
     fn test_search_symbols_by_name() {
         let index = open_test_db();
@@ -1978,58 +4362,445 @@ mod tests {
             },
         ];
 
-        for s in &symbols {
-            index.insert_symbol(s).expect("insert");
-        }
+        for s in &symbols {
+            index.insert_symbol(s).expect("insert");
+        }
+
+        let results = index.search_symbols_by_name("hel", 10).expect("search");
+        assert_eq!(results.len(), 2); // hello, help_me
+        assert!(results.iter().all(|s| s.name.starts_with("hel")));
+    }
+
+    #[test]
+    fn test_keyword_search() {
+        let index = open_test_db();
+        let file = test_file_info();
+        let file_id = index.upsert_file(&file).expect("upsert file");
+
+        let mut chunk1 = test_chunk(file_id);
+        chunk1.content =
+            "def authenticate_user(username, password):\n    return check_db(username, password)"
+                .to_string();
+        chunk1.symbol_path = "auth.authenticate_user".to_string();
+        index.insert_chunk(&chunk1).expect("insert");
+
+        let mut chunk2 = test_chunk(file_id);
+        chunk2.content =
+            "def list_users():\n    return db.query('SELECT * FROM users')".to_string();
+        chunk2.symbol_path = "users.list_users".to_string();
+        index.insert_chunk(&chunk2).expect("insert");
+
+        let results = index.keyword_search("authenticate", 10).expect("search");
+        assert!(
+            !results.is_empty(),
+            "should find results for 'authenticate'"
+        );
+
+        // Multi-word AND query — both tokens appear in chunk1; must return results.
+        let multi_results = index
+            .keyword_search("authenticate_user username", 10)
+            .expect("multi-word search");
+        assert!(
+            !multi_results.is_empty(),
+            "multi-word AND query should return results when all tokens present"
+        );
+
+        // OR fallback — "authenticate_user" is present, "xyznonexistent" is not.
+        // Should still return a result via OR fallback.
+        let fallback_results = index
+            .keyword_search("authenticate_user xyznonexistent", 10)
+            .expect("or fallback search");
+        assert!(
+            !fallback_results.is_empty(),
+            "OR fallback should surface partial matches when AND returns nothing"
+        );
+    }
+
+    #[test]
+    fn test_keyword_search_operators() {
+        let index = open_test_db();
+        let file_id = index.upsert_file(&test_file_info()).expect("upsert");
+
+        let mut login = test_chunk(file_id);
+        login.symbol_path = "auth.login".to_string();
+        login.content = "def login(user):\n    return authenticate(user, token)".to_string();
+        login.doc_comment = None;
+        let mut refresh = test_chunk(file_id);
+        refresh.symbol_path = "auth.refresh".to_string();
+        refresh.content = "def refresh():\n    return refresh_token()".to_string();
+        refresh.doc_comment = Some("Token refresh flow.".to_string());
+        let ids = index
+            .insert_chunks_batch(&[login, refresh])
+            .expect("insert chunks");
+
+        let hits = |q: &str| -> Vec<i64> {
+            let mut ids: Vec<i64> = index
+                .keyword_search(q, 10)
+                .expect("search")
+                .into_iter()
+                .map(|(id, _)| id)
+                .collect();
+            ids.sort_unstable();
+            ids
+        };
+
+        assert_eq!(hits("authent*"), vec![ids[0]]);
+        assert_eq!(hits("login OR refresh"), ids);
+        assert_eq!(hits("token NOT login"), vec![ids[1]]);
+        assert_eq!(hits("\"token refresh\""), vec![ids[1]]);
+        // Explicit AND has no OR fallback.
+        assert!(hits("login AND refresh").is_empty());
+    }
+
+    #[test]
+    fn test_find_chunks_containing_respects_case() {
+        let index = open_test_db();
+        let file_id = index.upsert_file(&test_file_info()).expect("upsert");
+
+        let mut upper = test_chunk(file_id);
+        upper.symbol_path = "main.Device".to_string();
+        upper.content = "class Device:\n    pass".to_string();
+        let mut lower = test_chunk(file_id);
+        lower.symbol_path = "main.probe".to_string();
+        lower.content = "def probe(device):\n    return 100%_done".to_string();
+        index
+            .insert_chunks_batch(&[upper, lower])
+            .expect("insert chunks");
+
+        let symbols = |needle: &str, case_sensitive: bool| -> Vec<String> {
+            index
+                .find_chunks_containing(needle, case_sensitive, 10)
+                .expect("scan")
+                .into_iter()
+                .map(|(c, _)| c.symbol_path)
+                .collect()
+        };
+
+        assert_eq!(symbols("Device", true), vec!["main.Device".to_string()]);
+        assert_eq!(symbols("Device", false).len(), 2);
+        // LIKE wildcards in the needle are matched literally.
+        assert_eq!(symbols("0%_d", false), vec!["main.probe".to_string()]);
+        assert!(symbols("0%xd", false).is_empty());
+    }
+
+    #[test]
+    fn test_find_chunks_containing_keeps_most_relevant_past_limit() {
+        let index = open_test_db();
+        for (path, symbol, content) in [
+            ("src/a.rs", "a::once", "fn once() { Device::new() }"),
+            ("src/m.rs", "m::Device", "struct Device;"),
+            (
+                "src/z.rs",
+                "z::many",
+                "fn many(a: Device, b: Device, c: Device) {}",
+            ),
+        ] {
+            let mut info = test_file_info();
+            info.path = PathBuf::from(path);
+            let file_id = index.upsert_file(&info).expect("upsert");
+            let mut chunk = test_chunk(file_id);
+            chunk.symbol_path = symbol.to_string();
+            chunk.content = content.to_string();
+            index.insert_chunks_batch(&[chunk]).expect("insert chunks");
+        }
+
+        let found: Vec<String> = index
+            .find_chunks_containing("device", false, 2)
+            .expect("scan")
+            .into_iter()
+            .map(|(c, _)| c.symbol_path)
+            .collect();
+        assert_eq!(found, vec!["m::Device".to_string(), "z::many".to_string()]);
+    }
+
+    #[test]
+    fn test_keyword_search_finds_cjk_substrings() {
+        let index = open_test_db();
+        let file_id = index.upsert_file(&test_file_info()).expect("upsert");
+
+        let mut cjk = test_chunk(file_id);
+        cjk.symbol_path = "main.login".to_string();
+        cjk.content = "def login():\n    # 验证用户身份并返回令牌\n    pass".to_string();
+        cjk.doc_comment = Some("ユーザー認証を行う".to_string());
+        let ids = index
+            .insert_chunks_batch(&[cjk, test_chunk(file_id)])
+            .expect("insert chunks");
+
+        let hits = |q: &str| -> Vec<i64> {
+            index
+                .keyword_search(q, 10)
+                .expect("search")
+                .into_iter()
+                .map(|(id, _)| id)
+                .collect()
+        };
+        // Trigram MATCH (3+ chars) in body and doc comment.
+        assert_eq!(hits("用户身份"), vec![ids[0]]);
+        assert_eq!(hits("認証を"), vec![ids[0]]);
+        // Short runs fall back to LIKE.
+        assert_eq!(hits("令牌"), vec![ids[0]]);
+        assert!(hits("数据库").is_empty());
+
+        // Deleting the chunk removes it from the trigram index too.
+        index.delete_chunks_for_file(file_id).expect("delete");
+        assert!(hits("用户身份").is_empty());
+    }
+
+    #[test]
+    fn test_keyword_search_column_weights() {
+        let index = open_test_db();
+        let file_id = index.upsert_file(&test_file_info()).expect("upsert");
+
+        // "token" appears in the body of one chunk and the doc comment of another.
+        let mut in_body = test_chunk(file_id);
+        in_body.symbol_path = "main.in_body".to_string();
+        in_body.content = "def in_body():\n    return token".to_string();
+        in_body.doc_comment = None;
+        let mut in_doc = test_chunk(file_id);
+        in_doc.symbol_path = "main.in_doc".to_string();
+        in_doc.content = "def in_doc():\n    return 1".to_string();
+        in_doc.doc_comment = Some("Returns the token.".to_string());
+        let ids = index
+            .insert_chunks_batch(&[in_body, in_doc])
+            .expect("insert chunks");
+
+        let code_first = Bm25Weights {
+            content: 5.0,
+            doc_comment: 0.1,
+            symbol_path: 1.0,
+        };
+        let docs_first = Bm25Weights {
+            content: 0.1,
+            doc_comment: 5.0,
+            symbol_path: 1.0,
+        };
+        let top = |w: &Bm25Weights| {
+            index
+                .keyword_search_weighted("token", 10, w)
+                .expect("search")[0]
+                .0
+        };
+        assert_eq!(top(&code_first), ids[0]);
+        assert_eq!(top(&docs_first), ids[1]);
+    }
+
+    #[test]
+    fn test_set_stemming_rebuilds_fts() {
+        let index = open_test_db();
+        let file_id = index.upsert_file(&test_file_info()).expect("upsert");
+        let mut chunk = test_chunk(file_id);
+        chunk.content = "def acquire():\n    # connection pooling".to_string();
+        let ids = index.insert_chunks_batch(&[chunk]).expect("insert");
+
+        let hits = |q: &str| index.keyword_search(q, 10).expect("search");
+        assert_eq!(hits("connections")[0].0, ids[0]);
 
-        let results = index.search_symbols_by_name("hel", 10).expect("search");
-        assert_eq!(results.len(), 2); // hello, help_me
-        assert!(results.iter().all(|s| s.name.starts_with("hel")));
+        index.set_stemming(false).expect("disable stemming");
+        assert!(hits("connections").is_empty());
+        assert_eq!(hits("connection")[0].0, ids[0]);
+
+        // Rows inserted after the switch go through the triggers as usual.
+        let mut other = test_chunk(file_id);
+        other.symbol_path = "main.other".to_string();
+        other.content = "def other():\n    # connections".to_string();
+        let other_ids = index.insert_chunks_batch(&[other]).expect("insert");
+        assert_eq!(hits("connections")[0].0, other_ids[0]);
+
+        index.set_stemming(true).expect("enable stemming");
+        assert_eq!(hits("connections").len(), 2);
+        assert_eq!(
+            index
+                .get_meta(meta_keys::FTS_STEMMING)
+                .expect("read")
+                .as_deref(),
+            Some("true")
+        );
     }
 
     #[test]
-    fn test_keyword_search() {
+    fn test_embedding_model_meta_and_vector_reset() {
         let index = open_test_db();
-        let file = test_file_info();
-        let file_id = index.upsert_file(&file).expect("upsert file");
+        assert_eq!(index.embedding_model().expect("read"), None);
+
+        let mut model = EmbeddingModelInfo {
+            name: "CodeRankEmbed".to_string(),
+            revision: "main".to_string(),
+            dimensions: 768,
+        };
+        index.set_embedding_model(&model).expect("write");
+        model.revision = "v2".to_string();
+        index.set_embedding_model(&model).expect("overwrite");
+        assert_eq!(index.embedding_model().expect("read"), Some(model));
+
+        let file_id = index.upsert_file(&test_file_info()).expect("upsert");
+        let chunk_id = index.insert_chunk(&test_chunk(file_id)).expect("insert");
+        index.set_chunk_vector_id(chunk_id, 42).expect("set vector");
+        assert_eq!(index.clear_vector_ids().expect("clear"), 1);
+        assert_eq!(index.get_chunks_without_vectors().expect("get").len(), 1);
+    }
 
-        let mut chunk1 = test_chunk(file_id);
-        chunk1.content =
-            "def authenticate_user(username, password):\n    return check_db(username, password)"
-                .to_string();
-        chunk1.symbol_path = "auth.authenticate_user".to_string();
-        index.insert_chunk(&chunk1).expect("insert");
+    #[test]
+    fn test_index_metadata_keys() {
+        let index = open_test_db();
+        let created = index.index_metadata().expect("read").created_at;
+        assert!(
+            created.is_some(),
+            "new databases record their creation time"
+        );
+        assert_eq!(index.get_meta("custom").expect("read"), None);
+        index.set_meta("custom", "one").expect("write");
+        index.set_meta("custom", "two").expect("overwrite");
+        assert_eq!(
+            index.get_meta("custom").expect("read").as_deref(),
+            Some("two")
+        );
 
-        let mut chunk2 = test_chunk(file_id);
-        chunk2.content =
-            "def list_users():\n    return db.query('SELECT * FROM users')".to_string();
-        chunk2.symbol_path = "users.list_users".to_string();
-        index.insert_chunk(&chunk2).expect("insert");
+        index.record_full_index("9.9.9").expect("record run");
+        index
+            .set_meta(meta_keys::REPO_FINGERPRINT, "abc123")
+            .expect("write");
+        let metadata = index.index_metadata().expect("read");
+        assert_eq!(metadata.created_at, created);
+        assert_eq!(metadata.engine_version.as_deref(), Some("9.9.9"));
+        assert_eq!(metadata.repo_fingerprint.as_deref(), Some("abc123"));
+        assert!(metadata.last_full_index.is_some());
+        assert_eq!(metadata.embedding_model, None);
+    }
 
-        let results = index.keyword_search("authenticate", 10).expect("search");
-        assert!(
-            !results.is_empty(),
-            "should find results for 'authenticate'"
+    #[test]
+    fn test_verbatim_prefix_stripped_from_file_paths() {
+        let index = open_test_db();
+        let mut file = test_file_info();
+        file.path = PathBuf::from(r"\\?\C:\repo\src\main.py");
+        let id = index.upsert_file(&file).expect("upsert");
+
+        let plain = Path::new(r"C:\repo\src\main.py");
+        let found = index.get_file_by_path(plain).expect("get").expect("found");
+        assert_eq!(found.id, id);
+        assert_eq!(
+            index.get_file_hash(&file.path).expect("hash").as_deref(),
+            Some("abc123def456")
         );
+        assert!(index.delete_file(plain).expect("delete"));
+    }
 
-        // Multi-word AND query — both tokens appear in chunk1; must return results.
-        let multi_results = index
-            .keyword_search("authenticate_user username", 10)
-            .expect("multi-word search");
-        assert!(
-            !multi_results.is_empty(),
-            "multi-word AND query should return results when all tokens present"
+    #[test]
+    fn test_relativize_paths_migrates_absolute_paths() {
+        use crate::graph::dependencies::{DependencyEdge as FileEdge, EdgeType};
+
+        let index = open_test_db();
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let old_root = dir.path().join("old");
+        let file = |path: PathBuf| FileInfo {
+            path,
+            ..test_file_info()
+        };
+        index
+            .upsert_file(&file(old_root.join("src/main.py")))
+            .expect("upsert");
+        index
+            .upsert_file(&file(old_root.join("src/dup.py")))
+            .expect("upsert");
+        index
+            .upsert_file(&file(PathBuf::from("src/dup.py")))
+            .expect("upsert");
+        let outside = dir.path().join("vendor/x.py");
+        index.upsert_file(&file(outside.clone())).expect("upsert");
+        index
+            .save_file_graph_edges(&[FileEdge {
+                source: old_root.join("src/main.py"),
+                target: old_root.join("src/dup.py"),
+                edge_type: EdgeType::Imports,
+                weight: 1.0,
+            }])
+            .expect("save edges");
+        let seq = index
+            .changes_since(0)
+            .expect("changes")
+            .last()
+            .map_or(0, |c| c.seq);
+
+        assert_eq!(index.relativize_paths(&old_root).expect("migrate"), 2);
+        let changes: Vec<(String, ChangeOp)> = index
+            .changes_since(seq)
+            .expect("changes")
+            .into_iter()
+            .map(|c| (c.file, c.op))
+            .collect();
+        let abs = |rel: &str| crate::paths::index_form(&old_root.join(rel));
+        assert_eq!(
+            changes,
+            vec![
+                (abs("src/main.py"), ChangeOp::Deleted),
+                ("src/main.py".to_string(), ChangeOp::Indexed),
+                (abs("src/dup.py"), ChangeOp::Deleted),
+            ]
+        );
+        let paths: Vec<PathBuf> = index
+            .get_all_files()
+            .expect("files")
+            .into_iter()
+            .map(|f| f.path)
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                outside,
+                PathBuf::from("src/dup.py"),
+                PathBuf::from("src/main.py")
+            ]
         );
+        let edges = index.load_file_graph_edges().expect("edges");
+        assert_eq!(edges[0].source, Path::new("src/main.py"));
+        assert_eq!(edges[0].target, Path::new("src/dup.py"));
 
-        // OR fallback — "authenticate_user" is present, "xyznonexistent" is not.
-        // Should still return a result via OR fallback.
-        let fallback_results = index
-            .keyword_search("authenticate_user xyznonexistent", 10)
-            .expect("or fallback search");
-        assert!(
-            !fallback_results.is_empty(),
-            "OR fallback should surface partial matches when AND returns nothing"
+        // After a move, paths written under the recorded root still migrate.
+        index
+            .upsert_file(&file(old_root.join("lib.py")))
+            .expect("upsert");
+        let new_root = dir.path().join("new");
+        assert_eq!(index.relativize_paths(&new_root).expect("migrate"), 1);
+        assert!(index
+            .get_file_by_path(Path::new("lib.py"))
+            .expect("get")
+            .is_some());
+        assert_eq!(index.relativize_paths(&new_root).expect("migrate"), 0);
+    }
+
+    #[test]
+    fn test_search_log_round_trip() {
+        let index = open_test_db();
+        let entry = SearchLogEntry {
+            logged_at: String::new(),
+            query: "connection pool".to_string(),
+            query_type: "Mixed".to_string(),
+            latency_ms: 12.5,
+            signals: crate::types::SignalCounts {
+                keyword: 2,
+                ..Default::default()
+            },
+            chunk_ids: vec![3, 1],
+            error: None,
+        };
+        index.log_search(&entry).expect("log");
+
+        let log = index.search_log(None).expect("read");
+        assert_eq!(log.len(), 1);
+        assert!(!log[0].logged_at.is_empty());
+        assert_eq!(
+            log[0],
+            SearchLogEntry {
+                logged_at: log[0].logged_at.clone(),
+                ..entry
+            }
         );
+
+        assert!(index
+            .search_log(Some("9999-01-01"))
+            .expect("read")
+            .is_empty());
+        assert_eq!(index.clear_search_log().expect("clear"), 1);
+        assert!(index.search_log(None).expect("read").is_empty());
     }
 
     #[test]
@@ -2089,6 +4860,78 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_partial_symbol_outlives_its_owning_part() {
+        let index = open_test_db();
+        let order = |file_id| Symbol {
+            name: "Order".into(),
+            fqn: "App.Order".into(),
+            kind: ChunkKind::Class,
+            ..test_symbol(file_id)
+        };
+        let part = |path: &str| FileInfo {
+            path: PathBuf::from(path),
+            language: Language::CSharp,
+            ..test_file_info()
+        };
+
+        let (first, _) = index
+            .reindex_file(&part("Order.cs"), &[], &[order(0)])
+            .expect("reindex");
+        let (second, _) = index
+            .reindex_file(&part("Order.Validation.cs"), &[], &[order(0)])
+            .expect("reindex second part");
+        let target = index
+            .get_symbol_by_fqn("App.Order")
+            .expect("lookup")
+            .expect("symbol");
+        assert_eq!(target.file_id, first, "first part keeps the row");
+        assert_eq!(
+            index.shared_symbol_ids(first).expect("shared"),
+            std::collections::HashSet::from([target.id])
+        );
+
+        let caller_file = index.upsert_file(&test_file_info()).expect("upsert file");
+        let caller = index
+            .insert_symbol(&Symbol {
+                name: "checkout".into(),
+                fqn: "main.checkout".into(),
+                ..test_symbol(caller_file)
+            })
+            .expect("insert symbol");
+        index
+            .insert_dependency(&DependencyEdge {
+                source_id: caller,
+                target_id: target.id,
+                kind: DependencyKind::Calls,
+                confidence: DependencyEdge::EXACT,
+            })
+            .expect("insert");
+
+        // Re-indexing, then deleting, the owning part hands the row to the
+        // part that remains; the edge to the class survives both.
+        index
+            .reindex_file(&part("Order.cs"), &[], &[order(0)])
+            .expect("reindex first part");
+        index
+            .delete_file(Path::new("Order.cs"))
+            .expect("delete first part");
+        let merged = index
+            .get_symbol_by_fqn("App.Order")
+            .expect("lookup")
+            .expect("symbol");
+        assert_eq!(merged.id, target.id);
+        assert_eq!(merged.file_id, second);
+        assert!(index.shared_symbol_ids(second).expect("shared").is_empty());
+        assert_eq!(
+            index
+                .get_upstream_dependencies(caller)
+                .expect("edges")
+                .len(),
+            1
+        );
+    }
+
     #[test]
     fn test_cascade_delete() {
         let index = open_test_db();
@@ -2148,6 +4991,147 @@ mod tests {
         assert_eq!(chunks[0].content_hash, 0xDEAD_BEEF_1234_5678_u64);
     }
 
+    #[test]
+    fn test_insert_chunk_stores_complexity() {
+        let index = open_test_db();
+        let file = test_file_info();
+        let file_id = index.upsert_file(&file).expect("upsert");
+
+        let mut chunk = test_chunk(file_id);
+        chunk.complexity = ComplexityMetrics {
+            cyclomatic: 7,
+            nesting_depth: 3,
+        };
+        index.insert_chunks_batch(&[chunk]).expect("insert chunks");
+
+        let chunks = index.get_chunks_for_file(file_id).expect("get chunks");
+        assert_eq!(chunks[0].complexity.cyclomatic, 7);
+        assert_eq!(chunks[0].complexity.nesting_depth, 3);
+    }
+
+    #[test]
+    fn test_identical_chunk_content_is_stored_once() {
+        let index = open_test_db();
+        let blob_count = |index: &MetadataIndex| -> i64 {
+            index
+                .connection()
+                .query_row("SELECT COUNT(*) FROM chunk_blobs", [], |row| row.get(0))
+                .expect("count blobs")
+        };
+
+        let mut vendored = test_file_info();
+        vendored.path = PathBuf::from("vendor/main.py");
+        let file_a = index.upsert_file(&test_file_info()).expect("upsert a");
+        let file_b = index.upsert_file(&vendored).expect("upsert b");
+        index
+            .insert_chunks_batch(&[test_chunk(file_a)])
+            .expect("insert a");
+        index
+            .insert_chunks_batch(&[test_chunk(file_b)])
+            .expect("insert b");
+
+        assert_eq!(index.chunk_count().expect("count"), 2);
+        assert_eq!(blob_count(&index), 1);
+
+        // Both copies remain searchable and readable.
+        let hits = index.keyword_search("hello", 10).expect("search");
+        assert_eq!(hits.len(), 2);
+        let chunks = index.get_chunks_for_file(file_b).expect("get chunks");
+        assert_eq!(chunks[0].content, test_chunk(file_b).content);
+
+        // The blob survives while any chunk references it.
+        index.delete_chunks_for_file(file_a).expect("delete a");
+        assert_eq!(blob_count(&index), 1);
+        assert_eq!(index.keyword_search("hello", 10).expect("search").len(), 1);
+        index.delete_chunks_for_file(file_b).expect("delete b");
+        assert_eq!(blob_count(&index), 0);
+        assert!(index
+            .keyword_search("hello", 10)
+            .expect("search")
+            .is_empty());
+    }
+
+    #[test]
+    fn test_colliding_blob_keys_keep_distinct_content() {
+        let index = open_test_db();
+        let file_id = index.upsert_file(&test_file_info()).expect("upsert");
+        let chunk = test_chunk(file_id);
+        // Occupy the chunk's key with other text, as an xxh3 collision would.
+        let home = xxhash_rust::xxh3::xxh3_64(chunk.content.as_bytes()) as i64;
+        index
+            .connection()
+            .execute(
+                "INSERT INTO chunk_blobs (hash, content) VALUES (?1, 'something else')",
+                params![home],
+            )
+            .expect("insert colliding blob");
+
+        index
+            .insert_chunks_batch(&[chunk.clone(), chunk.clone()])
+            .expect("insert chunks");
+        let chunks = index.get_chunks_for_file(file_id).expect("get chunks");
+        assert_eq!(chunks.len(), 2);
+        assert!(chunks.iter().all(|c| c.content == chunk.content));
+        let stored: i64 = index
+            .connection()
+            .query_row("SELECT COUNT(*) FROM chunk_blobs", [], |row| row.get(0))
+            .expect("count blobs");
+        assert_eq!(stored, 2);
+    }
+
+    #[test]
+    fn test_migration_moves_chunk_content_into_blobs() {
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let db_path = dir.path().join("legacy.db");
+        {
+            // Minimal v7 layout: content stored inline on chunks.
+            let conn = Connection::open(&db_path).expect("open legacy");
+            conn.execute_batch(
+                "CREATE TABLE files (
+                    id INTEGER PRIMARY KEY, path TEXT NOT NULL UNIQUE, language TEXT NOT NULL,
+                    hash TEXT NOT NULL, size_bytes INTEGER NOT NULL,
+                    indexed_at TEXT NOT NULL DEFAULT (datetime('now')), last_modified TEXT NOT NULL
+                );
+                CREATE TABLE chunks (
+                    id INTEGER PRIMARY KEY, file_id INTEGER NOT NULL, symbol_path TEXT NOT NULL,
+                    kind TEXT NOT NULL, visibility TEXT NOT NULL DEFAULT 'private',
+                    line_start INTEGER NOT NULL, line_end INTEGER NOT NULL, content TEXT NOT NULL,
+                    doc_comment TEXT, metadata TEXT, vector_id INTEGER, token_count INTEGER NOT NULL,
+                    weight REAL NOT NULL DEFAULT 1.0, content_hash INTEGER NOT NULL DEFAULT 0,
+                    complexity INTEGER NOT NULL DEFAULT 0, nesting_depth INTEGER NOT NULL DEFAULT 0
+                );
+                CREATE VIRTUAL TABLE chunks_fts USING fts5(
+                    content, doc_comment, symbol_path, content='chunks', content_rowid='id'
+                );
+                CREATE TABLE schema_version (
+                    version INTEGER NOT NULL, migrated_at TEXT NOT NULL DEFAULT (datetime('now'))
+                );
+                INSERT INTO schema_version (version) VALUES (7);
+                INSERT INTO files (path, language, hash, size_bytes, last_modified)
+                    VALUES ('a.py', 'python', 'h', 1, 'now'), ('b.py', 'python', 'h', 1, 'now');
+                INSERT INTO chunks (file_id, symbol_path, kind, line_start, line_end, content, token_count)
+                    VALUES (1, 'a.dup', 'function', 1, 2, 'def dup(): pass', 4),
+                           (2, 'b.dup', 'function', 1, 2, 'def dup(): pass', 4),
+                           (2, 'b.other', 'function', 3, 4, 'def other(): pass', 4);",
+            )
+            .expect("create legacy schema");
+        }
+
+        let index = MetadataIndex::open(&db_path).expect("migrate");
+        let blobs: i64 = index
+            .connection()
+            .query_row("SELECT COUNT(*) FROM chunk_blobs", [], |row| row.get(0))
+            .expect("count blobs");
+        assert_eq!(blobs, 2);
+        let chunks = index.get_chunks_for_file(2).expect("get chunks");
+        assert_eq!(chunks[0].content, "def dup(): pass");
+        assert_eq!(index.keyword_search("dup", 10).expect("search").len(), 2);
+        assert_eq!(
+            index.schema_gaps().expect("schema gaps"),
+            Vec::<String>::new()
+        );
+    }
+
     #[test]
     fn test_get_chunk_content_hashes_returns_map() {
         let index = open_test_db();
@@ -2344,4 +5328,397 @@ mod tests {
             assert_eq!(tokens.len(), 1);
         }
     }
+
+    #[test]
+    fn test_open_read_only_rejects_schema_mismatch() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let db_path = dir.path().join("index.db");
+        {
+            let index = MetadataIndex::open(&db_path).expect("open");
+            index
+                .upsert_file(&FileInfo {
+                    id: 0,
+                    path: "src/lib.rs".into(),
+                    language: Language::Rust,
+                    content_hash: "h".into(),
+                    size_bytes: 1,
+                })
+                .expect("upsert");
+        }
+        let replica = MetadataIndex::open_read_only(&db_path).expect("open read-only");
+        assert_eq!(replica.get_all_files().expect("files").len(), 1);
+        assert!(replica
+            .connection()
+            .execute("DELETE FROM files", [])
+            .is_err());
+        drop(replica);
+
+        {
+            let index = MetadataIndex::open(&db_path).expect("open");
+            index
+                .connection()
+                .execute("INSERT INTO schema_version (version) VALUES (999)", [])
+                .expect("bump version");
+        }
+        assert!(matches!(
+            MetadataIndex::open_read_only(&db_path),
+            Err(OmniError::Config { .. })
+        ));
+    }
+
+    #[test]
+    fn test_checkpoint_policy_truncates_wal() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let mut index = MetadataIndex::open(&dir.path().join("index.db")).expect("open");
+        index.set_checkpoint_policy(CheckpointPolicy {
+            interval: None,
+            wal_size_threshold: None,
+        });
+        index.upsert_file(&test_file_info()).expect("upsert");
+        assert!(index.wal_size() > 0, "writes go to the WAL first");
+        assert_eq!(index.maybe_checkpoint().expect("disabled"), None);
+
+        index.set_checkpoint_policy(CheckpointPolicy {
+            interval: None,
+            wal_size_threshold: Some(1),
+        });
+        index.begin_batch_transaction().expect("begin");
+        assert_eq!(index.maybe_checkpoint().expect("inside transaction"), None);
+        index
+            .commit_batch_transaction()
+            .expect("commit checkpoints");
+        assert_eq!(index.wal_size(), 0);
+
+        index.set_checkpoint_policy(CheckpointPolicy {
+            interval: Some(Duration::ZERO),
+            wal_size_threshold: None,
+        });
+        index
+            .upsert_file(&FileInfo {
+                path: PathBuf::from("src/other.py"),
+                ..test_file_info()
+            })
+            .expect("upsert another");
+        let result = index.maybe_checkpoint().expect("checkpoint").expect("due");
+        assert!(!result.busy);
+        assert_eq!(result.wal_frames, result.checkpointed_frames);
+        assert_eq!(index.wal_size(), 0);
+        assert_eq!(index.get_all_files().expect("files").len(), 2);
+    }
+
+    #[test]
+    fn test_checkpoint_policy_from_storage_config() {
+        let policy = CheckpointPolicy::from(&StorageConfig {
+            checkpoint_interval_secs: 0,
+            wal_size_threshold_mb: 2,
+            ..StorageConfig::default()
+        });
+        assert_eq!(policy.interval, None);
+        assert_eq!(policy.wal_size_threshold, Some(2 * 1024 * 1024));
+    }
+
+    #[test]
+    fn test_change_journal_records_index_delete_and_clear() {
+        let index = open_test_db();
+        assert_eq!(index.last_change_seq().expect("seq"), 0);
+
+        index.upsert_file(&test_file_info()).expect("upsert");
+        index
+            .upsert_file(&FileInfo {
+                path: PathBuf::from("src/util.py"),
+                ..test_file_info()
+            })
+            .expect("upsert util");
+        let start = index.last_change_seq().expect("seq");
+        assert_eq!(start, 2);
+
+        assert!(index.delete_file(Path::new("src/util.py")).expect("delete"));
+        // Deleting a file that is not indexed is not a change.
+        assert!(!index.delete_file(Path::new("missing.py")).expect("delete"));
+        index.clear_all().expect("clear");
+
+        let changes = index.changes_since(start).expect("changes");
+        let ops: Vec<_> = changes.iter().map(|c| (c.file.as_str(), c.op)).collect();
+        assert_eq!(
+            ops,
+            vec![("src/util.py", ChangeOp::Deleted), ("", ChangeOp::Cleared)]
+        );
+        assert!(changes.windows(2).all(|w| w[0].seq < w[1].seq));
+        assert_eq!(index.changes_since(0).expect("all").len(), 4);
+        assert!(index
+            .changes_since(index.last_change_seq().expect("seq"))
+            .expect("none")
+            .is_empty());
+    }
+
+    #[test]
+    fn test_jobs_track_progress_and_supersede() {
+        let index = open_test_db();
+        assert!(index.active_job("reembed").expect("active").is_none());
+
+        let first = index.create_job("reembed", 10).expect("create");
+        assert_eq!(first.state, JobState::Running);
+        index.update_job_progress(first.id, 4).expect("progress");
+        let active = index.active_job("reembed").expect("active").expect("job");
+        assert_eq!((active.id, active.done, active.total), (first.id, 4, 10));
+
+        // Starting another job of the same kind retires the first.
+        let second = index.create_job("reembed", 12).expect("create");
+        let first = index.get_job(first.id).expect("get").expect("job");
+        assert_eq!(first.state, JobState::Failed);
+        assert!(first.error.is_some());
+        assert_eq!(
+            index.active_job("reembed").expect("active").map(|j| j.id),
+            Some(second.id)
+        );
+
+        index
+            .finish_job(second.id, JobState::Done, None)
+            .expect("finish");
+        assert!(index.active_job("reembed").expect("active").is_none());
+        let recent = index.recent_jobs(5).expect("recent");
+        assert_eq!(
+            recent.iter().map(|j| j.id).collect::<Vec<_>>(),
+            vec![second.id, first.id]
+        );
+    }
+
+    #[test]
+    fn test_chunks_without_vectors_limited_and_detach() {
+        let index = open_test_db();
+        let file_id = index.upsert_file(&test_file_info()).expect("upsert file");
+        let mut ids = Vec::new();
+        for line in [1, 10, 20] {
+            let chunk = Chunk {
+                line_start: line,
+                line_end: line + 5,
+                ..test_chunk(file_id)
+            };
+            ids.push(index.insert_chunk(&chunk).expect("insert chunk"));
+        }
+
+        let first_two = index
+            .get_chunks_without_vectors_limited(0, 2)
+            .expect("limited");
+        assert_eq!(
+            first_two.iter().map(|c| c.id).collect::<Vec<_>>(),
+            ids[..2].to_vec()
+        );
+        let after = index
+            .get_chunks_without_vectors_limited(ids[1], 2)
+            .expect("limited");
+        assert_eq!(after.iter().map(|c| c.id).collect::<Vec<_>>(), vec![ids[2]]);
+
+        for &id in &ids {
+            index
+                .set_chunk_vector_id(id, id as u64)
+                .expect("set vector");
+        }
+        assert!(index.get_chunks_without_vectors().expect("all").is_empty());
+        assert_eq!(
+            index.detach_vector_ids(&[ids[1] as u64]).expect("detach"),
+            1
+        );
+        let missing = index.get_chunks_without_vectors().expect("all");
+        assert_eq!(
+            missing.iter().map(|c| c.id).collect::<Vec<_>>(),
+            vec![ids[1]]
+        );
+    }
+
+    #[test]
+    fn test_chunk_ids_matching_filters_by_file_metadata() {
+        let index = open_test_db();
+        let small = index.upsert_file(&test_file_info()).expect("upsert small");
+        let large = index
+            .upsert_file(&FileInfo {
+                path: PathBuf::from("src/big.py"),
+                size_bytes: 50_000,
+                ..test_file_info()
+            })
+            .expect("upsert large");
+        let small_chunk = index.insert_chunk(&test_chunk(small)).expect("chunk");
+        let large_chunk = index.insert_chunk(&test_chunk(large)).expect("chunk");
+
+        let by_size = index
+            .chunk_ids_matching(&SearchOptions {
+                min_size_bytes: Some(10_000),
+                ..SearchOptions::default()
+            })
+            .expect("size filter");
+        assert_eq!(by_size, [large_chunk].into_iter().collect());
+
+        let by_file = index
+            .chunk_ids_matching(&SearchOptions {
+                files: vec![PathBuf::from("src/main.py")],
+                ..SearchOptions::default()
+            })
+            .expect("file filter");
+        assert_eq!(by_file, [small_chunk].into_iter().collect());
+
+        let recent = index
+            .chunk_ids_matching(&SearchOptions {
+                modified_after: Some("2000-01-01".to_string()),
+                modified_before: Some("9999-01-01T00:00:00Z".to_string()),
+                ..SearchOptions::default()
+            })
+            .expect("date filter");
+        assert_eq!(recent.len(), 2);
+
+        let future = index
+            .chunk_ids_matching(&SearchOptions {
+                modified_after: Some("9999-01-01".to_string()),
+                ..SearchOptions::default()
+            })
+            .expect("date filter");
+        assert!(future.is_empty());
+
+        // The filter compares the recorded mtime, not the time of indexing,
+        // and a later upsert keeps it.
+        let mtime = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_600_000_000);
+        index.set_file_modified(large, mtime).expect("set mtime");
+        index
+            .upsert_file(&FileInfo {
+                path: PathBuf::from("src/big.py"),
+                size_bytes: 50_000,
+                ..test_file_info()
+            })
+            .expect("re-upsert large");
+        let old = index
+            .chunk_ids_matching(&SearchOptions {
+                modified_before: Some("2021-01-01".to_string()),
+                ..SearchOptions::default()
+            })
+            .expect("date filter");
+        assert_eq!(old, [large_chunk].into_iter().collect());
+
+        let err = index
+            .chunk_ids_matching(&SearchOptions {
+                modified_before: Some("last tuesday".to_string()),
+                ..SearchOptions::default()
+            })
+            .expect_err("invalid date");
+        assert!(matches!(err, OmniError::Config { .. }), "got {err}");
+    }
+
+    #[test]
+    fn test_language_kind_and_path_filters_apply_inside_keyword_search() {
+        let index = open_test_db();
+        // Twenty Python hits crowd the single Rust one out of an unfiltered
+        // top-5.
+        for n in 0..20 {
+            let file_id = index
+                .upsert_file(&FileInfo {
+                    path: PathBuf::from(format!("src/py/mod{n}.py")),
+                    ..test_file_info()
+                })
+                .expect("upsert python");
+            index.insert_chunk(&test_chunk(file_id)).expect("chunk");
+        }
+        let rust_file = index
+            .upsert_file(&FileInfo {
+                path: PathBuf::from("crates/core/src/lib.rs"),
+                language: Language::Rust,
+                ..test_file_info()
+            })
+            .expect("upsert rust");
+        let rust_chunk = index
+            .insert_chunk(&Chunk {
+                kind: ChunkKind::Test,
+                ..test_chunk(rust_file)
+            })
+            .expect("chunk");
+
+        let weights = Bm25Weights::default();
+        let unfiltered = index
+            .keyword_search_weighted("hello", 5, &weights)
+            .expect("search");
+        assert_eq!(unfiltered.len(), 5);
+
+        for filter in [
+            SearchOptions {
+                language: Some("RUST".to_string()),
+                ..SearchOptions::default()
+            },
+            SearchOptions {
+                kind: Some("test".to_string()),
+                ..SearchOptions::default()
+            },
+            SearchOptions {
+                path_glob: Some("crates/**/*.RS".to_string()),
+                ..SearchOptions::default()
+            },
+        ] {
+            let hits = index
+                .keyword_search_filtered("hello", 5, &weights, &filter)
+                .expect("filtered search");
+            assert_eq!(
+                hits.iter().map(|h| h.0).collect::<Vec<_>>(),
+                vec![rust_chunk],
+                "{filter:?}"
+            );
+            assert_eq!(
+                index.chunk_ids_matching(&filter).expect("ids"),
+                [rust_chunk].into_iter().collect(),
+                "{filter:?}"
+            );
+        }
+
+        let err = index
+            .chunk_ids_matching(&SearchOptions {
+                path_glob: Some("src/[".to_string()),
+                ..SearchOptions::default()
+            })
+            .expect_err("invalid glob");
+        assert!(matches!(err, OmniError::Config { .. }), "got {err}");
+    }
+
+    #[test]
+    fn test_schema_gaps_report_missing_tables_and_columns() {
+        let index = open_test_db();
+        assert!(index.schema_gaps().expect("gaps").is_empty());
+
+        index
+            .connection()
+            .execute_batch("ALTER TABLE jobs DROP COLUMN error; DROP TABLE external_docs;")
+            .expect("damage schema");
+        assert_eq!(
+            index.schema_gaps().expect("gaps"),
+            vec!["external_docs".to_string(), "jobs.error".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_fts_out_of_sync_and_rebuild() {
+        let index = open_test_db();
+        let file_id = index.upsert_file(&test_file_info()).expect("upsert file");
+        index
+            .insert_chunk(&test_chunk(file_id))
+            .expect("insert chunk");
+        assert_eq!(index.fts_out_of_sync().expect("check"), None);
+
+        index
+            .connection()
+            .execute(
+                "INSERT INTO chunks_fts(chunks_fts) VALUES('delete-all')",
+                [],
+            )
+            .expect("clear fts");
+        let details = index
+            .fts_out_of_sync()
+            .expect("check")
+            .expect("out of sync");
+        assert!(
+            details.starts_with("chunks_fts indexes 0 rows"),
+            "{details}"
+        );
+        assert!(index
+            .keyword_search("hello", 10)
+            .expect("search")
+            .is_empty());
+
+        index.rebuild_fts().expect("rebuild");
+        assert_eq!(index.fts_out_of_sync().expect("check"), None);
+        assert_eq!(index.keyword_search("hello", 10).expect("search").len(), 1);
+    }
 }
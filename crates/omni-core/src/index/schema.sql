@@ -9,7 +9,20 @@ CREATE TABLE IF NOT EXISTS files (
     hash        TEXT    NOT NULL,
     size_bytes  INTEGER NOT NULL,
     indexed_at  TEXT    NOT NULL DEFAULT (datetime('now')),
-    last_modified TEXT  NOT NULL
+    last_modified TEXT  NOT NULL,
+    encoding    TEXT    -- source encoding when not UTF-8, else NULL
+);
+
+-- Case-insensitive path lookups for Windows, where `Src\Main.rs` and
+-- `src\main.rs` name the same file.
+CREATE INDEX IF NOT EXISTS idx_files_path_nocase ON files(path COLLATE NOCASE);
+
+-- Content-addressed chunk bodies. Identical chunks (vendored copies,
+-- generated code) share one row. Rows are garbage-collected by the chunk
+-- delete/update triggers once no chunk references them.
+CREATE TABLE IF NOT EXISTS chunk_blobs (
+    hash    INTEGER PRIMARY KEY,  -- xxh3_64 of content
+    content TEXT    NOT NULL
 );
 
 CREATE TABLE IF NOT EXISTS chunks (
@@ -20,43 +33,84 @@ CREATE TABLE IF NOT EXISTS chunks (
     visibility   TEXT    NOT NULL DEFAULT 'private',
     line_start   INTEGER NOT NULL,
     line_end     INTEGER NOT NULL,
-    content      TEXT    NOT NULL,
+    blob_hash    INTEGER NOT NULL,  -- chunk_blobs(hash)
     doc_comment  TEXT,
     metadata     TEXT,
     vector_id    INTEGER,
     token_count  INTEGER NOT NULL,
     weight       REAL    NOT NULL DEFAULT 1.0,
-    content_hash INTEGER NOT NULL DEFAULT 0
+    content_hash INTEGER NOT NULL DEFAULT 0,
+    complexity   INTEGER NOT NULL DEFAULT 0,
+    nesting_depth INTEGER NOT NULL DEFAULT 0,
+    has_cjk      INTEGER NOT NULL DEFAULT 0, -- indexed in chunks_cjk
+    generated    INTEGER NOT NULL DEFAULT 0, -- generated code, see Chunk::is_generated
+    language     TEXT               -- embedded code's language; NULL = the file's
 );
 
--- FTS5 virtual table for full-text search
-CREATE VIRTUAL TABLE IF NOT EXISTS chunks_fts USING fts5(
+-- Chunk text as seen by FTS (the body lives in chunk_blobs)
+CREATE VIEW IF NOT EXISTS chunk_texts AS
+    SELECT c.id AS id, b.content AS content, c.doc_comment AS doc_comment,
+           c.symbol_path AS symbol_path
+    FROM chunks c JOIN chunk_blobs b ON b.hash = c.blob_hash;
+
+-- FTS5 virtual table for full-text search: `chunks_fts`, created from
+-- CHUNKS_FTS_DDL (index/mod.rs) before this file runs, so its tokenizer can
+-- follow `search.stemming`.
+
+-- Trigram FTS for chunks containing CJK text (which unicode61 cannot segment)
+CREATE VIEW IF NOT EXISTS chunk_texts_cjk AS
+    SELECT c.id AS id, b.content AS content, c.doc_comment AS doc_comment,
+           c.symbol_path AS symbol_path
+    FROM chunks c JOIN chunk_blobs b ON b.hash = c.blob_hash
+    WHERE c.has_cjk = 1;
+
+CREATE VIRTUAL TABLE IF NOT EXISTS chunks_cjk USING fts5(
     content,
     doc_comment,
     symbol_path,
-    content='chunks',
+    content='chunk_texts_cjk',
     content_rowid='id',
-    tokenize='porter unicode61 remove_diacritics 2'
+    tokenize='trigram'
 );
 
--- FTS sync triggers
+-- FTS sync triggers (blobs are inserted before the chunks that reference them,
+-- and garbage-collected only after both FTS tables have seen the old text)
 CREATE TRIGGER IF NOT EXISTS chunks_ai AFTER INSERT ON chunks BEGIN
     INSERT INTO chunks_fts(rowid, content, doc_comment, symbol_path)
-    VALUES (new.id, new.content, new.doc_comment, new.symbol_path);
+    VALUES (new.id, (SELECT content FROM chunk_blobs WHERE hash = new.blob_hash),
+            new.doc_comment, new.symbol_path);
+    INSERT INTO chunks_cjk(rowid, content, doc_comment, symbol_path)
+    SELECT new.id, content, new.doc_comment, new.symbol_path
+    FROM chunk_blobs WHERE hash = new.blob_hash AND new.has_cjk = 1;
 END;
 
 CREATE TRIGGER IF NOT EXISTS chunks_ad AFTER DELETE ON chunks BEGIN
     INSERT INTO chunks_fts(chunks_fts, rowid, content, doc_comment, symbol_path)
-    VALUES ('delete', old.id, old.content, old.doc_comment, old.symbol_path);
+    VALUES ('delete', old.id, (SELECT content FROM chunk_blobs WHERE hash = old.blob_hash),
+            old.doc_comment, old.symbol_path);
+    INSERT INTO chunks_cjk(chunks_cjk, rowid, content, doc_comment, symbol_path)
+    SELECT 'delete', old.id, content, old.doc_comment, old.symbol_path
+    FROM chunk_blobs WHERE hash = old.blob_hash AND old.has_cjk = 1;
+    DELETE FROM chunk_blobs WHERE hash = old.blob_hash
+        AND NOT EXISTS (SELECT 1 FROM chunks WHERE blob_hash = old.blob_hash);
 END;
 
 CREATE TRIGGER IF NOT EXISTS chunks_au AFTER UPDATE ON chunks BEGIN
     INSERT INTO chunks_fts(chunks_fts, rowid, content, doc_comment, symbol_path)
-    VALUES ('delete', old.id, old.content, old.doc_comment, old.symbol_path);
+    VALUES ('delete', old.id, (SELECT content FROM chunk_blobs WHERE hash = old.blob_hash),
+            old.doc_comment, old.symbol_path);
     INSERT INTO chunks_fts(rowid, content, doc_comment, symbol_path)
-    VALUES (new.id, new.content, new.doc_comment, new.symbol_path);
+    VALUES (new.id, (SELECT content FROM chunk_blobs WHERE hash = new.blob_hash),
+            new.doc_comment, new.symbol_path);
+    INSERT INTO chunks_cjk(chunks_cjk, rowid, content, doc_comment, symbol_path)
+    SELECT 'delete', old.id, content, old.doc_comment, old.symbol_path
+    FROM chunk_blobs WHERE hash = old.blob_hash AND old.has_cjk = 1;
+    INSERT INTO chunks_cjk(rowid, content, doc_comment, symbol_path)
+    SELECT new.id, content, new.doc_comment, new.symbol_path
+    FROM chunk_blobs WHERE hash = new.blob_hash AND new.has_cjk = 1;
+    DELETE FROM chunk_blobs WHERE hash = old.blob_hash AND old.blob_hash != new.blob_hash
+        AND NOT EXISTS (SELECT 1 FROM chunks WHERE blob_hash = old.blob_hash);
 END;
-
 CREATE TABLE IF NOT EXISTS symbols (
     id      INTEGER PRIMARY KEY,
     name    TEXT    NOT NULL,
@@ -68,9 +122,10 @@ CREATE TABLE IF NOT EXISTS symbols (
 );
 
 CREATE TABLE IF NOT EXISTS dependencies (
-    source_id INTEGER NOT NULL REFERENCES symbols(id),
-    target_id INTEGER NOT NULL REFERENCES symbols(id),
-    kind      TEXT    NOT NULL,
+    source_id  INTEGER NOT NULL REFERENCES symbols(id),
+    target_id  INTEGER NOT NULL REFERENCES symbols(id),
+    kind       TEXT    NOT NULL,
+    confidence REAL    NOT NULL DEFAULT 1.0,  -- resolution confidence (schema v19)
     PRIMARY KEY (source_id, target_id, kind)
 );
 
@@ -154,11 +209,122 @@ CREATE TABLE IF NOT EXISTS sparse_vectors (
 );
 CREATE INDEX IF NOT EXISTS idx_sparse_vectors_chunk ON sparse_vectors(chunk_id);
 
+-- Opt-in search telemetry (schema v10), written when [search] log_queries = true.
+CREATE TABLE IF NOT EXISTS search_log (
+    id          INTEGER PRIMARY KEY AUTOINCREMENT,
+    logged_at   TEXT    NOT NULL DEFAULT (datetime('now')),
+    query       TEXT    NOT NULL,
+    query_type  TEXT    NOT NULL,
+    latency_ms  REAL    NOT NULL,
+    signals     TEXT    NOT NULL,  -- JSON: SignalCounts
+    chunk_ids   TEXT    NOT NULL,  -- JSON: [chunk_id, ...]
+    error       TEXT
+);
+
+-- Index-wide key/value metadata (schema v11): creation time, engine
+-- version, embedding model, repository fingerprint, last index run. Keys
+-- are listed in `meta_keys` (index/mod.rs).
+CREATE TABLE IF NOT EXISTS index_meta (
+    key    TEXT PRIMARY KEY,
+    value  TEXT NOT NULL
+);
+
+-- Per-file and per-directory summaries (schema v12), refreshed on reindex.
+CREATE TABLE IF NOT EXISTS summaries (
+    path         TEXT    PRIMARY KEY,  -- relative, '/'-separated; '.' = repo root
+    kind         TEXT    NOT NULL,     -- 'file' | 'directory'
+    summary      TEXT    NOT NULL,
+    key_symbols  TEXT    NOT NULL,     -- JSON: [symbol_path, ...]
+    file_count   INTEGER NOT NULL,
+    chunk_count  INTEGER NOT NULL,
+    updated_at   TEXT    NOT NULL DEFAULT (datetime('now'))
+);
+
+-- Append-only change journal (schema v13) for incremental consumers.
+-- AUTOINCREMENT keeps seq monotonic even after rows are deleted.
+CREATE TABLE IF NOT EXISTS changes (
+    seq        INTEGER PRIMARY KEY AUTOINCREMENT,
+    file       TEXT    NOT NULL,  -- relative path; '' for 'cleared'
+    op         TEXT    NOT NULL,  -- 'indexed' | 'deleted' | 'cleared'
+    timestamp  TEXT    NOT NULL DEFAULT (datetime('now'))
+);
+
+-- Symbols exposed to other languages (schema v14): PyO3 / napi-rs exports
+-- and gRPC service methods, matched against call sites to build `binds` edges.
+CREATE TABLE IF NOT EXISTS binding_exports (
+    symbol_id INTEGER NOT NULL REFERENCES symbols(id) ON DELETE CASCADE,
+    abi       TEXT    NOT NULL,  -- 'pyo3' | 'napi' | 'grpc'
+    name      TEXT    NOT NULL,  -- foreign name, or 'service/method' for grpc
+    PRIMARY KEY (symbol_id, abi, name)
+);
+
+-- Resumable background work (schema v15), e.g. re-embedding after a model
+-- change. Remaining work is derived from the index; `done` is for display.
+CREATE TABLE IF NOT EXISTS jobs (
+    id          INTEGER PRIMARY KEY AUTOINCREMENT,
+    kind        TEXT    NOT NULL,  -- 'reembed'
+    state       TEXT    NOT NULL DEFAULT 'running',  -- 'running' | 'done' | 'failed'
+    total       INTEGER NOT NULL DEFAULT 0,
+    done        INTEGER NOT NULL DEFAULT 0,
+    error       TEXT,
+    created_at  TEXT    NOT NULL DEFAULT (datetime('now')),
+    updated_at  TEXT    NOT NULL DEFAULT (datetime('now'))
+);
+
+-- Re-exports (schema v18): `pub use`, `export ... from`, and package
+-- `__init__.py` imports. Globs end both names in `*`.
+CREATE TABLE IF NOT EXISTS symbol_aliases (
+    id          INTEGER PRIMARY KEY,
+    file_id     INTEGER NOT NULL REFERENCES files(id) ON DELETE CASCADE,
+    alias_fqn   TEXT    NOT NULL,  -- name the symbol is re-exported under
+    target_fqn  TEXT    NOT NULL,  -- what it points at, possibly another alias
+    line        INTEGER NOT NULL
+);
+
+-- Vectors of deleted files (schema v20). A delete records its chunks'
+-- vector ids here in the same transaction as the row delete; they are
+-- purged once vectors.bin is saved without them, and finished at startup
+-- if that never happened.
+CREATE TABLE IF NOT EXISTS vector_tombstones (
+    vector_id   INTEGER PRIMARY KEY,
+    deleted_at  TEXT    NOT NULL DEFAULT (datetime('now'))
+);
+
+-- TODO / FIXME / HACK comments (schema v22). Author and age come from
+-- git blame when listing, so they are not stored.
+CREATE TABLE IF NOT EXISTS todos (
+    id          INTEGER PRIMARY KEY,
+    file_id     INTEGER NOT NULL REFERENCES files(id) ON DELETE CASCADE,
+    line        INTEGER NOT NULL,
+    tag         TEXT    NOT NULL,  -- 'TODO' | 'FIXME' | 'HACK'
+    assignee    TEXT,              -- name in `TODO(name):`
+    text        TEXT    NOT NULL
+);
+
+-- Declarations of one symbol spread over several files (schema v24): the
+-- parts of a C# partial class. `symbols` holds one row per fqn, owned by one
+-- part; when that part's file goes, ownership passes to a remaining part.
+CREATE TABLE IF NOT EXISTS symbol_parts (
+    symbol_id   INTEGER NOT NULL REFERENCES symbols(id) ON DELETE CASCADE,
+    file_id     INTEGER NOT NULL REFERENCES files(id) ON DELETE CASCADE,
+    line        INTEGER NOT NULL,
+    chunk_id    INTEGER REFERENCES chunks(id),
+    PRIMARY KEY (symbol_id, file_id)
+);
+
 -- Indexes for query performance
 CREATE INDEX IF NOT EXISTS idx_chunks_file       ON chunks(file_id);
 CREATE INDEX IF NOT EXISTS idx_chunks_kind       ON chunks(kind);
 CREATE INDEX IF NOT EXISTS idx_chunks_visibility ON chunks(visibility);
+CREATE INDEX IF NOT EXISTS idx_chunks_blob       ON chunks(blob_hash);
+CREATE INDEX IF NOT EXISTS idx_chunks_generated  ON chunks(id) WHERE generated = 1;
 CREATE INDEX IF NOT EXISTS idx_symbols_name      ON symbols(name);
 CREATE INDEX IF NOT EXISTS idx_symbols_fqn       ON symbols(fqn);
 CREATE INDEX IF NOT EXISTS idx_deps_source       ON dependencies(source_id);
 CREATE INDEX IF NOT EXISTS idx_deps_target       ON dependencies(target_id);
+CREATE INDEX IF NOT EXISTS idx_binding_exports_name ON binding_exports(abi, name);
+CREATE INDEX IF NOT EXISTS idx_jobs_state        ON jobs(state, kind);
+CREATE INDEX IF NOT EXISTS idx_symbol_aliases_alias ON symbol_aliases(alias_fqn);
+CREATE INDEX IF NOT EXISTS idx_symbol_aliases_file  ON symbol_aliases(file_id);
+CREATE INDEX IF NOT EXISTS idx_todos_file        ON todos(file_id);
+CREATE INDEX IF NOT EXISTS idx_symbol_parts_file ON symbol_parts(file_id);
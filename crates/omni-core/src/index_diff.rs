@@ -0,0 +1,368 @@
+//! Semantic diff between two index snapshots.
+//!
+//! Compares two `index.db` states (e.g. `main` vs. a feature branch) at the
+//! level reviewers care about: which symbols appeared, disappeared or changed,
+//! which dependency edges moved, and what happened to the public API surface.
+//! The result serializes to JSON for PR review bots.
+//!
+//! Symbol IDs are database-local, so everything is keyed by fully qualified
+//! name. A symbol counts as *changed* when the content hash of its defining
+//! chunk differs between the two snapshots; pure line moves are ignored.
+//!
+//! Snapshots are opened read-only, so diffing never migrates or otherwise
+//! touches an archived database. Use [`MetadataIndex::snapshot_to`] to take a
+//! consistent copy of a live index.
+//!
+//! [`MetadataIndex::snapshot_to`]: crate::index::MetadataIndex::snapshot_to
+
+#![allow(clippy::doc_markdown, clippy::missing_errors_doc)]
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use rusqlite::{Connection, OpenFlags};
+use serde::{Deserialize, Serialize};
+
+use crate::error::OmniResult;
+
+/// A symbol as seen in one snapshot.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotSymbol {
+    /// Fully qualified name (the diff key).
+    pub fqn: String,
+    /// Short name.
+    pub name: String,
+    /// Symbol kind (`function`, `class`, ...).
+    pub kind: String,
+    /// File path relative to the repository root.
+    pub file_path: String,
+    /// Definition line (1-indexed).
+    pub line: u32,
+    /// Visibility of the defining chunk (`public`, `private`, ...).
+    pub visibility: String,
+    /// Content hash of the defining chunk (0 if unknown).
+    #[serde(skip)]
+    pub content_hash: u64,
+}
+
+impl SnapshotSymbol {
+    /// Whether this symbol is part of the public API.
+    pub fn is_public(&self) -> bool {
+        self.visibility == "public"
+    }
+}
+
+/// A dependency edge keyed by symbol FQNs.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct SnapshotEdge {
+    /// FQN of the dependent symbol.
+    pub source: String,
+    /// FQN of the dependency.
+    pub target: String,
+    /// Edge kind (`calls`, `imports`, ...).
+    pub kind: String,
+}
+
+/// Symbols and edges loaded from one index database.
+#[derive(Debug, Clone, Default)]
+pub struct IndexSnapshot {
+    /// Symbols by FQN.
+    pub symbols: BTreeMap<String, SnapshotSymbol>,
+    /// All dependency edges.
+    pub edges: BTreeSet<SnapshotEdge>,
+}
+
+/// A symbol present in both snapshots whose definition differs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangedSymbol {
+    /// State in the old snapshot.
+    pub old: SnapshotSymbol,
+    /// State in the new snapshot.
+    pub new: SnapshotSymbol,
+    /// Visibility changed (e.g. `private` → `public`).
+    pub visibility_changed: bool,
+    /// Kind changed (e.g. `function` → `class`).
+    pub kind_changed: bool,
+}
+
+/// Changes to the public API surface.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ApiDiff {
+    /// FQNs that became public (new public symbols or visibility widened).
+    pub added: Vec<String>,
+    /// FQNs that stopped being public (removed or visibility narrowed).
+    pub removed: Vec<String>,
+    /// Public FQNs whose definition changed.
+    pub changed: Vec<String>,
+}
+
+impl ApiDiff {
+    /// Whether the change may break downstream consumers.
+    pub fn is_breaking(&self) -> bool {
+        !self.removed.is_empty()
+    }
+}
+
+/// Full semantic diff between two snapshots.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IndexDiff {
+    /// Symbols only in the new snapshot.
+    pub symbols_added: Vec<SnapshotSymbol>,
+    /// Symbols only in the old snapshot.
+    pub symbols_removed: Vec<SnapshotSymbol>,
+    /// Symbols in both snapshots whose definition changed.
+    pub symbols_changed: Vec<ChangedSymbol>,
+    /// Edges only in the new snapshot.
+    pub edges_added: Vec<SnapshotEdge>,
+    /// Edges only in the old snapshot.
+    pub edges_removed: Vec<SnapshotEdge>,
+    /// Public API changes.
+    pub public_api: ApiDiff,
+}
+
+impl IndexDiff {
+    /// Whether the two snapshots are semantically identical.
+    pub fn is_empty(&self) -> bool {
+        self.symbols_added.is_empty()
+            && self.symbols_removed.is_empty()
+            && self.symbols_changed.is_empty()
+            && self.edges_added.is_empty()
+            && self.edges_removed.is_empty()
+    }
+}
+
+impl IndexSnapshot {
+    /// Load a snapshot from an open index connection.
+    pub fn load(conn: &Connection) -> OmniResult<Self> {
+        let mut symbols = BTreeMap::new();
+        {
+            let mut stmt = conn.prepare(
+                "SELECT s.fqn, s.name, s.kind, f.path, s.line,
+                        COALESCE(c.visibility, 'private'), COALESCE(c.content_hash, 0)
+                 FROM symbols s
+                 JOIN files f ON s.file_id = f.id
+                 LEFT JOIN chunks c ON s.chunk_id = c.id",
+            )?;
+            let rows = stmt.query_map([], |row| {
+                Ok(SnapshotSymbol {
+                    fqn: row.get(0)?,
+                    name: row.get(1)?,
+                    kind: row.get(2)?,
+                    file_path: row.get(3)?,
+                    line: row.get(4)?,
+                    visibility: row.get(5)?,
+                    content_hash: row.get::<_, i64>(6)? as u64,
+                })
+            })?;
+            for sym in rows {
+                let sym = sym?;
+                symbols.insert(sym.fqn.clone(), sym);
+            }
+        }
+
+        let mut edges = BTreeSet::new();
+        {
+            let mut stmt = conn.prepare(
+                "SELECT src.fqn, tgt.fqn, d.kind
+                 FROM dependencies d
+                 JOIN symbols src ON d.source_id = src.id
+                 JOIN symbols tgt ON d.target_id = tgt.id",
+            )?;
+            let rows = stmt.query_map([], |row| {
+                Ok(SnapshotEdge {
+                    source: row.get(0)?,
+                    target: row.get(1)?,
+                    kind: row.get(2)?,
+                })
+            })?;
+            for edge in rows {
+                edges.insert(edge?);
+            }
+        }
+
+        Ok(Self { symbols, edges })
+    }
+
+    /// Open an index database read-only and load it.
+    ///
+    /// `path` may point at the `index.db` file itself or at a data directory
+    /// containing one.
+    pub fn open(path: &Path) -> OmniResult<Self> {
+        Self::load(&open_snapshot(path)?)
+    }
+}
+
+/// Open an index snapshot read-only. `path` is an `index.db` file or a data
+/// directory containing one.
+pub(crate) fn open_snapshot(path: &Path) -> OmniResult<Connection> {
+    let db_path = if path.is_dir() {
+        path.join("index.db")
+    } else {
+        path.to_path_buf()
+    };
+    if !db_path.exists() {
+        return Err(crate::error::OmniError::NotFound {
+            entity: format!("index snapshot {}", db_path.display()),
+        });
+    }
+    Ok(Connection::open_with_flags(
+        &db_path,
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )?)
+}
+
+/// Compute the semantic diff from `old` to `new`.
+pub fn diff(old: &IndexSnapshot, new: &IndexSnapshot) -> IndexDiff {
+    let mut out = IndexDiff::default();
+
+    for (fqn, new_sym) in &new.symbols {
+        match old.symbols.get(fqn) {
+            None => {
+                if new_sym.is_public() {
+                    out.public_api.added.push(fqn.clone());
+                }
+                out.symbols_added.push(new_sym.clone());
+            }
+            Some(old_sym) => {
+                let body_changed = old_sym.content_hash != 0
+                    && new_sym.content_hash != 0
+                    && old_sym.content_hash != new_sym.content_hash;
+                let visibility_changed = old_sym.visibility != new_sym.visibility;
+                let kind_changed = old_sym.kind != new_sym.kind;
+
+                match (old_sym.is_public(), new_sym.is_public()) {
+                    (false, true) => out.public_api.added.push(fqn.clone()),
+                    (true, false) => out.public_api.removed.push(fqn.clone()),
+                    (true, true) if body_changed || kind_changed => {
+                        out.public_api.changed.push(fqn.clone());
+                    }
+                    _ => {}
+                }
+
+                if body_changed || visibility_changed || kind_changed {
+                    out.symbols_changed.push(ChangedSymbol {
+                        old: old_sym.clone(),
+                        new: new_sym.clone(),
+                        visibility_changed,
+                        kind_changed,
+                    });
+                }
+            }
+        }
+    }
+
+    for (fqn, old_sym) in &old.symbols {
+        if !new.symbols.contains_key(fqn) {
+            if old_sym.is_public() {
+                out.public_api.removed.push(fqn.clone());
+            }
+            out.symbols_removed.push(old_sym.clone());
+        }
+    }
+
+    out.edges_added = new.edges.difference(&old.edges).cloned().collect();
+    out.edges_removed = old.edges.difference(&new.edges).cloned().collect();
+
+    out.public_api.added.sort();
+    out.public_api.removed.sort();
+    out.public_api.changed.sort();
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sym(fqn: &str, visibility: &str, hash: u64) -> SnapshotSymbol {
+        SnapshotSymbol {
+            fqn: fqn.to_string(),
+            name: fqn.rsplit("::").next().unwrap_or(fqn).to_string(),
+            kind: "function".to_string(),
+            file_path: "src/lib.rs".to_string(),
+            line: 1,
+            visibility: visibility.to_string(),
+            content_hash: hash,
+        }
+    }
+
+    fn edge(source: &str, target: &str) -> SnapshotEdge {
+        SnapshotEdge {
+            source: source.to_string(),
+            target: target.to_string(),
+            kind: "calls".to_string(),
+        }
+    }
+
+    fn snapshot(symbols: Vec<SnapshotSymbol>, edges: Vec<SnapshotEdge>) -> IndexSnapshot {
+        IndexSnapshot {
+            symbols: symbols.into_iter().map(|s| (s.fqn.clone(), s)).collect(),
+            edges: edges.into_iter().collect(),
+        }
+    }
+
+    #[test]
+    fn test_identical_snapshots_produce_empty_diff() {
+        let a = snapshot(vec![sym("a::f", "public", 1)], vec![edge("a::f", "a::g")]);
+        let d = diff(&a, &a.clone());
+        assert!(d.is_empty());
+        assert!(d.public_api.added.is_empty() && d.public_api.removed.is_empty());
+    }
+
+    #[test]
+    fn test_symbol_and_edge_changes() {
+        let old = snapshot(
+            vec![
+                sym("a::keep", "public", 1),
+                sym("a::gone", "public", 2),
+                sym("a::edit", "private", 3),
+            ],
+            vec![edge("a::edit", "a::gone")],
+        );
+        let mut moved = sym("a::keep", "public", 1);
+        moved.line = 40; // line moves alone are not changes
+        let new = snapshot(
+            vec![
+                moved,
+                sym("a::edit", "private", 99),
+                sym("a::new", "private", 4),
+            ],
+            vec![edge("a::edit", "a::new")],
+        );
+
+        let d = diff(&old, &new);
+        assert_eq!(d.symbols_added.len(), 1);
+        assert_eq!(d.symbols_added[0].fqn, "a::new");
+        assert_eq!(d.symbols_removed[0].fqn, "a::gone");
+        assert_eq!(d.symbols_changed.len(), 1);
+        assert_eq!(d.symbols_changed[0].new.fqn, "a::edit");
+        assert_eq!(d.edges_added, vec![edge("a::edit", "a::new")]);
+        assert_eq!(d.edges_removed, vec![edge("a::edit", "a::gone")]);
+
+        assert_eq!(d.public_api.removed, vec!["a::gone".to_string()]);
+        assert!(d.public_api.is_breaking());
+    }
+
+    #[test]
+    fn test_visibility_changes_affect_public_api() {
+        let old = snapshot(
+            vec![sym("a::widen", "private", 1), sym("a::narrow", "public", 2)],
+            vec![],
+        );
+        let new = snapshot(
+            vec![sym("a::widen", "public", 1), sym("a::narrow", "crate", 2)],
+            vec![],
+        );
+
+        let d = diff(&old, &new);
+        assert_eq!(d.public_api.added, vec!["a::widen".to_string()]);
+        assert_eq!(d.public_api.removed, vec!["a::narrow".to_string()]);
+        assert!(d.symbols_changed.iter().all(|c| c.visibility_changed));
+    }
+
+    #[test]
+    fn test_open_missing_snapshot_is_not_found() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let err = IndexSnapshot::open(&dir.path().join("nope.db")).expect_err("missing");
+        assert!(matches!(err, crate::error::OmniError::NotFound { .. }));
+    }
+}
@@ -0,0 +1,249 @@
+//! Cross-process writer lock for a repository's data directory.
+//!
+//! `index.db` and `vectors.bin` assume a single writer: a CLI `index` run
+//! racing the daemon leaves `vectors.bin` holding whichever process saved
+//! last, out of step with the chunk table. Writers therefore take an
+//! advisory exclusive lock on `index.lock` in the data directory. The lock
+//! file records who holds it, so a refused writer can say whether the
+//! daemon or another indexer is in the way.
+//!
+//! The lock is released when the [`IndexLock`] is dropped, or by the OS if
+//! the holder exits. Readers (search, status) never take it. A clean
+//! release empties the lock file, so a holder still recorded when the lock
+//! is next taken died while writing (see
+//! [`IndexLock::previous_holder_crashed`]).
+
+#![allow(clippy::doc_markdown, clippy::missing_errors_doc)]
+
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, Write};
+use std::path::{Path, PathBuf};
+
+use fs4::fs_std::FileExt;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{OmniError, OmniResult};
+
+/// Lock file name inside the data directory.
+pub const LOCK_FILE: &str = "index.lock";
+
+/// Kind of process holding the writer lock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WriterKind {
+    /// The background daemon, which holds the lock for its whole lifetime.
+    Daemon,
+    /// A one-off writer such as `omnicontext index` or the MCP server.
+    Indexer,
+}
+
+/// Contents of the lock file while it is held.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockHolder {
+    /// Process id of the holder.
+    pub pid: u32,
+    /// What kind of process the holder is.
+    pub kind: WriterKind,
+}
+
+/// An exclusive writer lock on a data directory.
+#[derive(Debug)]
+pub struct IndexLock {
+    file: File,
+    path: PathBuf,
+    previous_holder_crashed: bool,
+}
+
+impl IndexLock {
+    /// Take the lock, failing with [`OmniError::IndexLocked`] if another
+    /// process holds it.
+    pub fn acquire(data_dir: &Path, kind: WriterKind) -> OmniResult<Self> {
+        let (file, path) = open_lock_file(data_dir)?;
+        if !file.try_lock_exclusive()? {
+            return Err(locked_error(read_holder(&path).as_ref()));
+        }
+        Self::claim(file, path, kind)
+    }
+
+    /// Take the lock, blocking until the current holder releases it.
+    pub fn acquire_blocking(data_dir: &Path, kind: WriterKind) -> OmniResult<Self> {
+        let (file, path) = open_lock_file(data_dir)?;
+        if !file.try_lock_exclusive()? {
+            if let Some(holder) = read_holder(&path) {
+                tracing::info!(pid = holder.pid, kind = ?holder.kind, "waiting for the index lock");
+            } else {
+                tracing::info!("waiting for the index lock");
+            }
+            file.lock_exclusive()?;
+        }
+        Self::claim(file, path, kind)
+    }
+
+    /// Path of the lock file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether the previous holder exited (crashed, was killed, or
+    /// panicked) without releasing the lock, so its last write to the index
+    /// may be incomplete.
+    pub fn previous_holder_crashed(&self) -> bool {
+        self.previous_holder_crashed
+    }
+
+    fn claim(mut file: File, path: PathBuf, kind: WriterKind) -> OmniResult<Self> {
+        let mut previous = Vec::new();
+        file.read_to_end(&mut previous)?;
+        let previous_holder_crashed = serde_json::from_slice::<LockHolder>(&previous).is_ok();
+        let holder = LockHolder {
+            pid: std::process::id(),
+            kind,
+        };
+        let json = serde_json::to_vec(&holder)
+            .map_err(|e| OmniError::Serialization(format!("{}: {e}", path.display())))?;
+        file.set_len(0)?;
+        file.rewind()?;
+        file.write_all(&json)?;
+        file.flush()?;
+        Ok(Self {
+            file,
+            path,
+            previous_holder_crashed,
+        })
+    }
+}
+
+impl Drop for IndexLock {
+    fn drop(&mut self) {
+        // An empty file marks a clean release. While unwinding from a panic
+        // the holder stays recorded, like after a crash.
+        if !std::thread::panicking() {
+            let _ = self.file.set_len(0);
+        }
+        // Closing the handle releases the lock too; unlocking explicitly just
+        // makes the release immediate. The file stays so that every process
+        // keeps locking the same inode.
+        let _ = FileExt::unlock(&self.file);
+    }
+}
+
+/// Holder recorded in `data_dir`'s lock file, if any.
+///
+/// This is the current holder, or one that exited without releasing the
+/// lock; use [`IndexLock::acquire`] to find out whether it is held now.
+pub fn last_holder(data_dir: &Path) -> Option<LockHolder> {
+    read_holder(&data_dir.join(LOCK_FILE))
+}
+
+fn open_lock_file(data_dir: &Path) -> OmniResult<(File, PathBuf)> {
+    std::fs::create_dir_all(data_dir)?;
+    let path = data_dir.join(LOCK_FILE);
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(&path)?;
+    Ok((file, path))
+}
+
+fn read_holder(path: &Path) -> Option<LockHolder> {
+    // Fails on platforms with mandatory locks (Windows); the error then
+    // just cannot name the holder.
+    let bytes = std::fs::read(path).ok()?;
+    serde_json::from_slice(&bytes).ok()
+}
+
+fn locked_error(holder: Option<&LockHolder>) -> OmniError {
+    let details = match holder {
+        Some(LockHolder {
+            pid,
+            kind: WriterKind::Daemon,
+        }) => format!(
+            "daemon holds the index (pid {pid}); use `omnicontext daemon reindex`, \
+             or pass --wait to run once the daemon stops"
+        ),
+        Some(LockHolder {
+            pid,
+            kind: WriterKind::Indexer,
+        }) => format!(
+            "another indexer holds the index (pid {pid}); pass --wait to run after it finishes"
+        ),
+        None => "another process holds the index; pass --wait to run after it finishes".into(),
+    };
+    OmniError::IndexLocked { details }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_second_writer_is_refused_until_release() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let daemon = IndexLock::acquire(dir.path(), WriterKind::Daemon).expect("first lock");
+        assert_eq!(
+            last_holder(dir.path()),
+            Some(LockHolder {
+                pid: std::process::id(),
+                kind: WriterKind::Daemon,
+            })
+        );
+
+        let err = IndexLock::acquire(dir.path(), WriterKind::Indexer).expect_err("held");
+        assert!(matches!(err, OmniError::IndexLocked { .. }), "{err}");
+        #[cfg(unix)]
+        assert!(
+            err.to_string().contains("omnicontext daemon reindex"),
+            "{err}"
+        );
+
+        drop(daemon);
+        assert_eq!(last_holder(dir.path()), None);
+        let indexer = IndexLock::acquire(dir.path(), WriterKind::Indexer).expect("released");
+        assert!(!indexer.previous_holder_crashed());
+        assert_eq!(
+            last_holder(dir.path()).map(|h| h.kind),
+            Some(WriterKind::Indexer)
+        );
+        assert!(indexer.path().ends_with(LOCK_FILE));
+    }
+
+    #[test]
+    fn test_holder_left_in_lock_file_means_crash() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        std::fs::write(
+            dir.path().join(LOCK_FILE),
+            r#"{"pid":4242,"kind":"indexer"}"#,
+        )
+        .expect("write stale holder");
+        let lock = IndexLock::acquire(dir.path(), WriterKind::Indexer).expect("lock");
+        assert!(lock.previous_holder_crashed());
+        assert_eq!(
+            last_holder(dir.path()).map(|h| h.pid),
+            Some(std::process::id())
+        );
+    }
+
+    #[test]
+    fn test_blocking_acquire_waits_for_holder() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let held = IndexLock::acquire(dir.path(), WriterKind::Indexer).expect("lock");
+
+        let data_dir = dir.path().to_path_buf();
+        let waiter = std::thread::spawn(move || {
+            IndexLock::acquire_blocking(&data_dir, WriterKind::Indexer).map(|_| ())
+        });
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        assert!(
+            !waiter.is_finished(),
+            "waiter must block while the lock is held"
+        );
+
+        drop(held);
+        waiter
+            .join()
+            .expect("join")
+            .expect("acquired after release");
+    }
+}
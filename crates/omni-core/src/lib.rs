@@ -21,6 +21,9 @@
 //! - **`search`** -- Hybrid retrieval engine (RRF fusion + ranking)
 //! - **`watcher`** -- File system watcher with debouncing
 //! - **`pipeline`** -- Orchestrates the ingestion pipeline
+//! - **`events`** -- Typed engine events for subscribers
+//! - **`hooks`** -- Custom pipeline stage hooks
+//! - **`plugins`** -- Sandboxed WASM analyzers and hooks from `.omnicontext/plugins/`
 //! - **`workspace`** -- Multi-repo workspace management (Pro)
 //! - **`commits`** -- Git commit lineage indexing (Pro)
 //! - **`patterns`** -- Convention and pattern recognition (Pro)
@@ -97,13 +100,32 @@ pub mod vector;
 pub mod watcher;
 
 // Extended Capabilities
+pub mod api_surface;
 pub mod branch_diff;
 pub mod commits;
+pub mod diff_context;
+pub mod distributed;
+pub mod encoding;
+pub mod eval;
+pub mod events;
+pub mod hooks;
+pub mod index_diff;
+pub mod index_lock;
 pub mod memory;
+pub mod overview;
+pub mod owners;
+pub mod paths;
 pub mod patterns;
 pub mod plan_auditor;
+pub mod plugins;
+pub mod privacy;
+pub mod relocate;
+#[cfg(feature = "remote-sync")]
+pub mod remote;
 pub mod rules;
 pub mod scip;
+pub mod stack_trace;
+pub mod summaries;
 pub mod workspace;
 
 // Service Layer
@@ -114,10 +136,14 @@ pub use commits::CommitInfo;
 pub use config::normalize_repo_hash;
 pub use config::Config;
 pub use error::OmniError;
+pub use events::EngineEvent;
 /// Re-export cross-file data flow extraction types.
 pub use graph::data_flow::{DataFlowEdge, DataFlowExtractor, FlowInference};
 /// Re-export the semantic reasoning engine types for Graph-Augmented Retrieval (GAR).
 pub use graph::reasoning::{EdgeWeights, ReasoningEngine, ReasoningHit};
 pub use index::ExternalDoc;
 /// Re-export the primary engine interface.
-pub use pipeline::{Engine, FileProcessStats, IndexDelta, RetryEmbeddingResult};
+pub use pipeline::{
+    Engine, FileProcessStats, IndexDelta, IndexIssue, IntegrityReport, RetryEmbeddingResult,
+    WarmUpReport,
+};
@@ -0,0 +1,413 @@
+//! Repository overview for orientation.
+//!
+//! The first thing an agent needs in an unfamiliar repository: which
+//! languages it is written in, how its directories are laid out, what each
+//! top-level directory holds, where execution starts, and which symbols the
+//! rest of the code leans on most. Everything is derived from the index --
+//! files, stored directory summaries, and the symbol dependency graph -- so
+//! building an overview never reads source files.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::OmniResult;
+use crate::graph::coverage::is_test_file;
+use crate::graph::DependencyGraph;
+use crate::index::MetadataIndex;
+use crate::types::{ChunkKind, FileInfo, Language, ModuleSummary, Symbol};
+
+/// Directory depth covered by the module map.
+const MODULE_DEPTH: usize = 2;
+
+/// Directories listed in the module map.
+const MAX_MODULES: usize = 40;
+
+/// Entry points listed.
+const MAX_ENTRY_POINTS: usize = 20;
+
+/// Key symbols listed.
+const MAX_KEY_SYMBOLS: usize = 15;
+
+/// File names that conventionally start a program or define a package root,
+/// with the role they play.
+const ENTRY_FILES: &[(&str, &str)] = &[
+    ("main.rs", "binary"),
+    ("lib.rs", "library root"),
+    ("build.rs", "build script"),
+    ("main.go", "binary"),
+    ("__main__.py", "module entry"),
+    ("main.py", "script"),
+    ("app.py", "application"),
+    ("manage.py", "Django management"),
+    ("wsgi.py", "WSGI application"),
+    ("asgi.py", "ASGI application"),
+    ("index.ts", "package root"),
+    ("index.tsx", "package root"),
+    ("index.js", "package root"),
+    ("index.jsx", "package root"),
+    ("index.mjs", "package root"),
+    ("main.ts", "application"),
+    ("main.js", "application"),
+    ("server.ts", "server"),
+    ("server.js", "server"),
+    ("app.ts", "application"),
+    ("app.js", "application"),
+    ("Program.cs", "binary"),
+    ("main.c", "binary"),
+    ("main.cpp", "binary"),
+];
+
+/// Files of one language.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LanguageShare {
+    /// Language identifier (`rust`, `python`, ...).
+    pub language: String,
+    /// Indexed files.
+    pub files: usize,
+    /// Total size of those files in bytes.
+    pub bytes: u64,
+    /// Share of all indexed files, in percent.
+    pub percent: f64,
+}
+
+/// A directory in the module map.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModuleEntry {
+    /// Directory path relative to the repository root (`.` for the root).
+    pub path: String,
+    /// Indexed files beneath it, at any depth.
+    pub files: usize,
+    /// Languages of those files, most common first.
+    pub languages: Vec<String>,
+}
+
+/// A file where execution starts or a package is rooted.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EntryPoint {
+    /// File path relative to the repository root.
+    pub path: String,
+    /// Why the file counts as an entry point (`binary`, `library root`, ...).
+    pub role: String,
+}
+
+/// A symbol many others depend on.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeySymbol {
+    /// Fully qualified name.
+    pub fqn: String,
+    /// Symbol kind.
+    pub kind: ChunkKind,
+    /// Defining file, relative to the repository root.
+    pub file: String,
+    /// Symbols with a direct dependency edge onto this one.
+    pub dependents: usize,
+}
+
+/// Orientation summary of an indexed repository.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RepoOverview {
+    /// Indexed files.
+    pub files: usize,
+    /// Indexed symbols.
+    pub symbols: usize,
+    /// Root directory summary, if summaries have been built.
+    pub summary: Option<String>,
+    /// Language distribution, most files first.
+    pub languages: Vec<LanguageShare>,
+    /// Directories up to two levels deep, in path order.
+    pub modules: Vec<ModuleEntry>,
+    /// Summaries of the top-level directories, in path order.
+    pub directories: Vec<ModuleSummary>,
+    /// Likely entry points, shallowest first.
+    pub entry_points: Vec<EntryPoint>,
+    /// Most depended-on symbols, best first.
+    pub key_symbols: Vec<KeySymbol>,
+}
+
+/// Build the overview of the repository behind `index` and `graph`.
+pub fn build(index: &MetadataIndex, graph: &DependencyGraph) -> OmniResult<RepoOverview> {
+    let files = index.get_all_files()?;
+    let symbols = index.get_all_symbols()?;
+
+    let directories = top_level_dirs(&files)
+        .iter()
+        .filter_map(|dir| index.get_summary(dir).transpose())
+        .collect::<OmniResult<Vec<_>>>()?;
+
+    Ok(RepoOverview {
+        files: files.len(),
+        symbols: symbols.len(),
+        summary: index
+            .get_summary(crate::summaries::ROOT)?
+            .map(|s| s.summary),
+        languages: language_shares(&files),
+        modules: module_map(&files),
+        directories,
+        entry_points: entry_points(&files, &symbols),
+        key_symbols: key_symbols(&files, &symbols, |id| graph.in_degree(id)),
+    })
+}
+
+/// Relative path of `file` with `/` separators.
+fn rel_path(file: &FileInfo) -> String {
+    crate::summaries::normalize_path(&file.path.to_string_lossy())
+}
+
+/// Language distribution, most files first.
+fn language_shares(files: &[FileInfo]) -> Vec<LanguageShare> {
+    let mut counts: BTreeMap<&str, (usize, u64)> = BTreeMap::new();
+    for file in files {
+        let entry = counts.entry(file.language.as_str()).or_default();
+        entry.0 += 1;
+        entry.1 += file.size_bytes;
+    }
+    #[allow(clippy::cast_precision_loss)]
+    let total = files.len().max(1) as f64;
+    let mut shares: Vec<LanguageShare> = counts
+        .into_iter()
+        .map(|(language, (files, bytes))| LanguageShare {
+            language: language.to_string(),
+            files,
+            bytes,
+            #[allow(clippy::cast_precision_loss)]
+            percent: (files as f64 * 1000.0 / total).round() / 10.0,
+        })
+        .collect();
+    shares.sort_by(|a, b| b.files.cmp(&a.files).then(b.bytes.cmp(&a.bytes)));
+    shares
+}
+
+/// Directories up to [`MODULE_DEPTH`] levels deep with their file counts.
+/// When there are more than [`MAX_MODULES`], the largest are kept.
+fn module_map(files: &[FileInfo]) -> Vec<ModuleEntry> {
+    let mut dirs: BTreeMap<String, BTreeMap<&str, usize>> = BTreeMap::new();
+    for file in files {
+        let path = rel_path(file);
+        let parts: Vec<&str> = path.split('/').collect();
+        let depth = (parts.len() - 1).min(MODULE_DEPTH);
+        let dir = if depth == 0 {
+            crate::summaries::ROOT.to_string()
+        } else {
+            parts[..depth].join("/")
+        };
+        *dirs
+            .entry(dir)
+            .or_default()
+            .entry(file.language.as_str())
+            .or_default() += 1;
+        // Count the file towards its ancestors too.
+        for ancestor in 1..depth {
+            *dirs
+                .entry(parts[..ancestor].join("/"))
+                .or_default()
+                .entry(file.language.as_str())
+                .or_default() += 1;
+        }
+    }
+
+    let mut modules: Vec<ModuleEntry> = dirs
+        .into_iter()
+        .map(|(path, langs)| {
+            let mut langs: Vec<(&str, usize)> = langs.into_iter().collect();
+            langs.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+            ModuleEntry {
+                path,
+                files: langs.iter().map(|(_, n)| n).sum(),
+                languages: langs.into_iter().map(|(l, _)| l.to_string()).collect(),
+            }
+        })
+        .collect();
+    if modules.len() > MAX_MODULES {
+        modules.sort_by(|a, b| b.files.cmp(&a.files).then(a.path.cmp(&b.path)));
+        modules.truncate(MAX_MODULES);
+        modules.sort_by(|a, b| a.path.cmp(&b.path));
+    }
+    modules
+}
+
+/// Top-level directories containing indexed files, in path order.
+fn top_level_dirs(files: &[FileInfo]) -> Vec<String> {
+    let mut dirs: Vec<String> = files
+        .iter()
+        .filter_map(|f| rel_path(f).split_once('/').map(|(dir, _)| dir.to_string()))
+        .collect();
+    dirs.sort();
+    dirs.dedup();
+    dirs
+}
+
+/// Files named like entry points, plus files defining a `main` function.
+/// Test files are skipped.
+fn entry_points(files: &[FileInfo], symbols: &[Symbol]) -> Vec<EntryPoint> {
+    let with_main: std::collections::HashSet<i64> = symbols
+        .iter()
+        .filter(|s| s.name == "main" && s.kind == ChunkKind::Function)
+        .map(|s| s.file_id)
+        .collect();
+
+    let mut found: Vec<EntryPoint> = files
+        .iter()
+        .filter_map(|file| {
+            let path = rel_path(file);
+            if is_test_file(&path) {
+                return None;
+            }
+            let name = Path::new(&path).file_name()?.to_str()?;
+            let role = if path.contains("/bin/") && file.language == Language::Rust {
+                Some("binary")
+            } else {
+                ENTRY_FILES
+                    .iter()
+                    .find(|(file_name, _)| *file_name == name)
+                    .map(|(_, role)| *role)
+            };
+            let role = role.or_else(|| with_main.contains(&file.id).then_some("defines main"))?;
+            Some(EntryPoint {
+                path,
+                role: role.to_string(),
+            })
+        })
+        .collect();
+    found.sort_by(|a, b| {
+        a.path
+            .matches('/')
+            .count()
+            .cmp(&b.path.matches('/').count())
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    found.truncate(MAX_ENTRY_POINTS);
+    found
+}
+
+/// Non-test symbols with the most direct dependents. Symbols nothing depends
+/// on are left out.
+fn key_symbols(
+    files: &[FileInfo],
+    symbols: &[Symbol],
+    dependents: impl Fn(i64) -> usize,
+) -> Vec<KeySymbol> {
+    let paths: HashMap<i64, String> = files.iter().map(|f| (f.id, rel_path(f))).collect();
+    let mut ranked: Vec<KeySymbol> = symbols
+        .iter()
+        .filter(|s| {
+            !matches!(
+                s.kind,
+                ChunkKind::Test
+                    | ChunkKind::Impl
+                    | ChunkKind::Module
+                    | ChunkKind::TopLevel
+                    | ChunkKind::Summary
+            )
+        })
+        .filter_map(|s| {
+            let file = paths.get(&s.file_id)?;
+            if is_test_file(file) {
+                return None;
+            }
+            let dependents = dependents(s.id);
+            (dependents > 0).then(|| KeySymbol {
+                fqn: s.fqn.clone(),
+                kind: s.kind,
+                file: file.clone(),
+                dependents,
+            })
+        })
+        .collect();
+    ranked.sort_by(|a, b| {
+        b.dependents
+            .cmp(&a.dependents)
+            .then_with(|| a.fqn.cmp(&b.fqn))
+    });
+    ranked.truncate(MAX_KEY_SYMBOLS);
+    ranked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(id: i64, path: &str, language: Language) -> FileInfo {
+        FileInfo {
+            id,
+            path: path.into(),
+            language,
+            content_hash: String::new(),
+            size_bytes: 100,
+        }
+    }
+
+    fn symbol(id: i64, file_id: i64, fqn: &str, kind: ChunkKind) -> Symbol {
+        Symbol {
+            id,
+            name: fqn.rsplit("::").next().unwrap_or(fqn).to_string(),
+            fqn: fqn.to_string(),
+            kind,
+            file_id,
+            line: 1,
+            chunk_id: None,
+        }
+    }
+
+    #[test]
+    fn test_overview_sections_from_index_rows() {
+        let files = vec![
+            file(1, "src/main.rs", Language::Rust),
+            file(2, "src/auth/token.rs", Language::Rust),
+            file(3, "src/auth/deep/store.rs", Language::Rust),
+            file(4, "tools/gen.py", Language::Python),
+            file(5, "tests/token_test.rs", Language::Rust),
+            file(6, "src/Server.java", Language::Java),
+        ];
+        let symbols = vec![
+            symbol(10, 2, "auth::token::verify", ChunkKind::Function),
+            symbol(11, 3, "auth::deep::store::Store", ChunkKind::Class),
+            symbol(12, 5, "token_test::verify_rejects", ChunkKind::Function),
+            symbol(13, 6, "Server::main", ChunkKind::Function),
+        ];
+
+        let languages = language_shares(&files);
+        assert_eq!(languages[0].language, "rust");
+        assert_eq!(languages[0].files, 4);
+        assert!((languages[0].percent - 66.7).abs() < f64::EPSILON);
+
+        let modules = module_map(&files);
+        let paths: Vec<&str> = modules.iter().map(|m| m.path.as_str()).collect();
+        assert_eq!(paths, ["src", "src/auth", "tests", "tools"]);
+        assert_eq!(modules[0].files, 4);
+        assert_eq!(modules[0].languages, ["rust", "java"]);
+        assert_eq!(modules[1].files, 2, "deeper files count towards depth 2");
+
+        assert_eq!(top_level_dirs(&files), ["src", "tests", "tools"]);
+
+        let entries = entry_points(&files, &symbols);
+        assert_eq!(
+            entries,
+            [
+                EntryPoint {
+                    path: "src/Server.java".into(),
+                    role: "defines main".into()
+                },
+                EntryPoint {
+                    path: "src/main.rs".into(),
+                    role: "binary".into()
+                },
+            ]
+        );
+
+        let dependents = |id| match id {
+            10 => 3,
+            11 | 12 => 5,
+            _ => 0,
+        };
+        let keys = key_symbols(&files, &symbols, dependents);
+        let fqns: Vec<&str> = keys.iter().map(|k| k.fqn.as_str()).collect();
+        assert_eq!(
+            fqns,
+            ["auth::deep::store::Store", "auth::token::verify"],
+            "test files and symbols nothing depends on are left out"
+        );
+        assert_eq!(keys[0].file, "src/auth/deep/store.rs");
+    }
+}
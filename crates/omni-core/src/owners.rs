@@ -0,0 +1,223 @@
+//! CODEOWNERS integration.
+//!
+//! Parses the repository's `CODEOWNERS` file (looked up in `.github/`, the
+//! root, then `docs/`, the same order GitHub uses) and resolves the owners
+//! of any relative path. As on GitHub, the *last* matching rule wins, and a
+//! rule with no owners explicitly un-owns its paths.
+//!
+//! Patterns follow gitignore rules: a pattern without a `/` (other than a
+//! trailing one) matches at any depth, a pattern with one is anchored at the
+//! root, `*` does not cross directories, and a pattern naming a directory
+//! covers everything beneath it. As on GitHub, a pattern ending in a `*`
+//! segment (`docs/*`) covers only the files directly in that directory.
+//!
+//! Search queries may carry `owner:@team` terms; see [`split_owner_filter`].
+
+use std::path::Path;
+
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+
+use crate::error::OmniResult;
+
+/// Candidate CODEOWNERS locations, relative to the repo root, in lookup order.
+pub const CODEOWNERS_PATHS: &[&str] = &[".github/CODEOWNERS", "CODEOWNERS", "docs/CODEOWNERS"];
+
+/// Query prefix selecting results by owner.
+pub const OWNER_FILTER_PREFIX: &str = "owner:";
+
+/// One `pattern owner...` line.
+#[derive(Debug, Clone)]
+struct OwnerRule {
+    matcher: GlobSet,
+    owners: Vec<String>,
+}
+
+/// Parsed CODEOWNERS rules.
+#[derive(Debug, Clone, Default)]
+pub struct CodeOwners {
+    rules: Vec<OwnerRule>,
+}
+
+impl CodeOwners {
+    /// Parse CODEOWNERS text. Comments, blank lines, and lines whose pattern
+    /// is not a valid glob are skipped.
+    pub fn parse(text: &str) -> Self {
+        let rules = text
+            .lines()
+            .filter_map(|line| {
+                let line = line.split_once('#').map_or(line, |(before, _)| before);
+                let mut parts = line.split_whitespace();
+                let pattern = parts.next()?;
+                let matcher = compile_globs(pattern, false)?;
+                Some(OwnerRule {
+                    matcher,
+                    owners: parts.map(str::to_string).collect(),
+                })
+            })
+            .collect();
+        Self { rules }
+    }
+
+    /// Load the first CODEOWNERS file found under `repo_root`.
+    ///
+    /// Returns `Ok(None)` when the repository has none.
+    pub fn load(repo_root: &Path) -> OmniResult<Option<Self>> {
+        for candidate in CODEOWNERS_PATHS {
+            let path = repo_root.join(candidate);
+            if path.is_file() {
+                return Ok(Some(Self::parse(&std::fs::read_to_string(path)?)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Number of rules parsed.
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    /// Whether no rules were parsed.
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Owners of `path` (relative to the repo root), or an empty slice when no
+    /// rule matches.
+    pub fn owners_of(&self, path: &Path) -> &[String] {
+        let path = path.to_string_lossy().replace('\\', "/");
+        let path = path.trim_start_matches("./");
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| rule.matcher.is_match(path))
+            .map_or(&[], |rule| rule.owners.as_slice())
+    }
+}
+
+/// Compile one gitignore-style pattern into globs for the path itself and,
+/// when it names a directory, everything beneath it.
+pub(crate) fn compile_pattern(pattern: &str) -> Option<GlobSet> {
+    compile_globs(pattern, true)
+}
+
+/// [`compile_pattern`], where `wildcard_dirs` says whether a trailing `*`
+/// segment may match a directory and so everything beneath it. Gitignore
+/// says yes; CODEOWNERS says no.
+fn compile_globs(pattern: &str, wildcard_dirs: bool) -> Option<GlobSet> {
+    let dir_only = pattern.ends_with('/');
+    let trimmed = pattern.trim_end_matches('/');
+    let anchored = trimmed.contains('/');
+    let trimmed = trimmed.trim_start_matches('/');
+    if trimmed.is_empty() {
+        return None;
+    }
+
+    let base = if anchored || trimmed.starts_with("**") {
+        trimmed.to_string()
+    } else {
+        format!("**/{trimmed}")
+    };
+    let last = trimmed.rsplit('/').next().unwrap_or(trimmed);
+    let wildcard_leaf = last.contains('*') && last != "**";
+    let mut globs = Vec::with_capacity(2);
+    if wildcard_dirs || dir_only || !wildcard_leaf {
+        globs.push(format!("{base}/**"));
+    }
+    if !dir_only {
+        globs.push(base);
+    }
+
+    let mut builder = GlobSetBuilder::new();
+    for glob in globs {
+        builder.add(
+            GlobBuilder::new(&glob)
+                .literal_separator(true)
+                .build()
+                .ok()?,
+        );
+    }
+    builder.build().ok()
+}
+
+/// Split `owner:` terms out of a search query.
+///
+/// Returns the remaining query text and the requested owners (as written,
+/// e.g. `@org/platform-team`).
+pub fn split_owner_filter(query: &str) -> (String, Vec<String>) {
+    let mut owners = Vec::new();
+    let mut terms = Vec::new();
+    for term in query.split_whitespace() {
+        match term.strip_prefix(OWNER_FILTER_PREFIX) {
+            Some(owner) if !owner.is_empty() => owners.push(owner.to_string()),
+            _ => terms.push(term),
+        }
+    }
+    (terms.join(" "), owners)
+}
+
+/// Whether any of `owners` matches one of the `wanted` owners. The leading
+/// `@` is optional and comparison ignores case.
+pub fn matches_owner(owners: &[String], wanted: &[String]) -> bool {
+    let norm = |s: &str| s.trim_start_matches('@').to_ascii_lowercase();
+    wanted
+        .iter()
+        .any(|w| owners.iter().any(|o| norm(o) == norm(w)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "\
+# Default owners
+*                @org/everyone
+*.rs             @org/rust   # inline comment
+/docs/           @org/docs
+src/auth/        @org/security alice@example.com
+build/generated
+";
+
+    fn owners(co: &CodeOwners, path: &str) -> Vec<String> {
+        co.owners_of(Path::new(path)).to_vec()
+    }
+
+    #[test]
+    fn test_last_matching_rule_wins() {
+        let co = CodeOwners::parse(SAMPLE);
+        assert_eq!(co.len(), 5);
+        assert_eq!(owners(&co, "README.md"), vec!["@org/everyone"]);
+        assert_eq!(owners(&co, "crates/core/src/lib.rs"), vec!["@org/rust"]);
+        assert_eq!(owners(&co, "docs/guide/intro.md"), vec!["@org/docs"]);
+        assert_eq!(
+            owners(&co, "src/auth/token.rs"),
+            vec!["@org/security", "alice@example.com"]
+        );
+        assert!(owners(&co, "build/generated/out.rs").is_empty());
+    }
+
+    #[test]
+    fn test_anchoring_and_separators() {
+        let co =
+            CodeOwners::parse("/docs/ @root-docs\nconfig @cfg\nsrc/*.py @py\nassets/* @design\n");
+        assert_eq!(owners(&co, "docs/a.md"), vec!["@root-docs"]);
+        assert!(owners(&co, "pkg/docs/a.md").is_empty());
+        assert_eq!(owners(&co, "pkg/config/app.toml"), vec!["@cfg"]);
+        assert_eq!(owners(&co, "src/app.py"), vec!["@py"]);
+        assert!(owners(&co, "src/nested/app.py").is_empty());
+        assert_eq!(owners(&co, "assets/logo.svg"), vec!["@design"]);
+        assert!(owners(&co, "assets/icons/close.svg").is_empty());
+    }
+
+    #[test]
+    fn test_split_owner_filter() {
+        let (query, wanted) = split_owner_filter("token refresh owner:@org/security retry");
+        assert_eq!(query, "token refresh retry");
+        assert_eq!(wanted, vec!["@org/security"]);
+        assert!(matches_owner(&["@Org/Security".to_string()], &wanted));
+        assert!(matches_owner(
+            &["@org/security".to_string()],
+            &["org/security".to_string()]
+        ));
+        assert!(!matches_owner(&[], &wanted));
+    }
+}
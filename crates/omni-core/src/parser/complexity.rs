@@ -0,0 +1,297 @@
+//! Structural complexity metrics computed from tree-sitter syntax trees.
+//!
+//! Rather than teaching every language analyzer about control flow, this
+//! module walks the finished tree once using a cross-grammar table of node
+//! kinds (`if_statement`, `match_arm`, `catch_clause`, ...) and attributes
+//! each decision point to the elements whose line range contains it.
+//!
+//! - **Cyclomatic complexity** = 1 + decision points (branches, loops, case
+//!   arms, catch clauses, `&&` / `||` / `and` / `or`)
+//! - **Nesting depth** = deepest stack of control-flow constructs inside the
+//!   element, with `else if` chains counted as one level
+
+use super::StructuralElement;
+use crate::types::ComplexityMetrics;
+
+/// Node kinds that add a decision point (cyclomatic +1).
+const DECISION_KINDS: &[&str] = &[
+    // conditionals
+    "if_statement",
+    "if_expression",
+    "if_let_expression",
+    "elif_clause",
+    "else_if_clause",
+    "conditional_expression",
+    "ternary_expression",
+    "guard_statement",
+    "if",
+    "unless",
+    "elsif",
+    "if_modifier",
+    "unless_modifier",
+    // loops
+    "for_statement",
+    "for_expression",
+    "for_in_statement",
+    "for_range_loop",
+    "enhanced_for_statement",
+    "foreach_statement",
+    "while_statement",
+    "while_expression",
+    "loop_expression",
+    "do_statement",
+    "do_while_statement",
+    "repeat_while_statement",
+    "while",
+    "until",
+    "for",
+    "while_modifier",
+    "until_modifier",
+    // multi-way branches (each arm counts)
+    "match_arm",
+    "case_clause",
+    "case_statement",
+    "switch_case",
+    "switch_section",
+    "switch_block_statement_group",
+    "switch_rule",
+    "expression_case",
+    "type_case",
+    "communication_case",
+    "when_entry",
+    "when",
+    "case_pattern",
+    // exception handling
+    "catch_clause",
+    "except_clause",
+    "rescue",
+    "catch_block",
+];
+
+/// Node kinds that open a new nesting level.
+const NESTING_KINDS: &[&str] = &[
+    "if_statement",
+    "if_expression",
+    "if_let_expression",
+    "guard_statement",
+    "if",
+    "unless",
+    "for_statement",
+    "for_expression",
+    "for_in_statement",
+    "for_range_loop",
+    "enhanced_for_statement",
+    "foreach_statement",
+    "while_statement",
+    "while_expression",
+    "loop_expression",
+    "do_statement",
+    "do_while_statement",
+    "repeat_while_statement",
+    "while",
+    "until",
+    "for",
+    "match_expression",
+    "switch_statement",
+    "switch_expression",
+    "expression_switch_statement",
+    "type_switch_statement",
+    "select_statement",
+    "when_expression",
+    "case",
+    "try_statement",
+    "try_expression",
+    "begin",
+];
+
+/// Parent kinds under which a `&&` / `||` / `and` / `or` token is a boolean operator.
+const BOOLEAN_PARENT_KINDS: &[&str] = &[
+    "binary_expression",
+    "boolean_operator",
+    "binary",
+    "conjunction_expression",
+    "disjunction_expression",
+    "infix_expression",
+];
+
+/// Parent kinds that make an `if` node an `else if` (same nesting level).
+const ELSE_KINDS: &[&str] = &["else_clause", "else", "else_if_clause"];
+
+/// Compute complexity metrics for each element from the file's syntax tree.
+///
+/// Every decision point and nesting construct is bucketed by its start row;
+/// an element collects everything inside its `line_start..=line_end` range.
+/// Container elements (classes, impls) therefore aggregate their methods.
+pub fn annotate_elements(tree: &tree_sitter::Tree, elements: &mut [StructuralElement]) {
+    if elements.is_empty() {
+        return;
+    }
+
+    let points = collect_points(tree);
+
+    for elem in elements.iter_mut() {
+        let first = elem.line_start.saturating_sub(1) as usize;
+        let last = elem.line_end.saturating_sub(1) as usize;
+
+        let mut decisions = 0u32;
+        let mut min_level = u32::MAX;
+        let mut max_level = 0u32;
+        for p in points.iter().filter(|p| p.row >= first && p.row <= last) {
+            if p.decision {
+                decisions += 1;
+            }
+            if let Some(level) = p.nesting_level {
+                min_level = min_level.min(level);
+                max_level = max_level.max(level);
+            }
+        }
+
+        // Levels are absolute within the file; measure relative to the
+        // outermost construct inside this element.
+        let nesting_depth = if max_level == 0 {
+            0
+        } else {
+            max_level - min_level + 1
+        };
+
+        elem.complexity = ComplexityMetrics {
+            cyclomatic: 1 + decisions,
+            nesting_depth,
+        };
+    }
+}
+
+/// A decision point or nesting construct located at a source row.
+struct Point {
+    row: usize,
+    decision: bool,
+    /// Absolute nesting level (1-based) if this node opens a nesting level.
+    nesting_level: Option<u32>,
+}
+
+/// Walk the whole tree once, recording decision points and nesting levels.
+fn collect_points(tree: &tree_sitter::Tree) -> Vec<Point> {
+    let mut points = Vec::new();
+    let mut stack: Vec<(tree_sitter::Node<'_>, u32)> = vec![(tree.root_node(), 0)];
+
+    while let Some((node, level)) = stack.pop() {
+        let kind = node.kind();
+        let parent_kind = node.parent().map(|p| p.kind());
+
+        let decision = if node.is_named() {
+            DECISION_KINDS.contains(&kind)
+        } else {
+            matches!(kind, "&&" | "||" | "and" | "or")
+                && parent_kind.is_some_and(|pk| BOOLEAN_PARENT_KINDS.contains(&pk))
+        };
+
+        let opens_level = node.is_named()
+            && NESTING_KINDS.contains(&kind)
+            && !parent_kind.is_some_and(|pk| ELSE_KINDS.contains(&pk));
+        let child_level = if opens_level { level + 1 } else { level };
+
+        if decision || opens_level {
+            points.push(Point {
+                row: node.start_position().row,
+                decision,
+                nesting_level: opens_level.then_some(child_level),
+            });
+        }
+
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            stack.push((child, child_level));
+        }
+    }
+
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::types::Language;
+    use std::path::Path;
+
+    fn metrics_for(
+        path: &str,
+        source: &str,
+        language: Language,
+        name: &str,
+    ) -> crate::types::ComplexityMetrics {
+        let elements =
+            crate::parser::parse_file(Path::new(path), source.as_bytes(), language).expect("parse");
+        elements
+            .iter()
+            .find(|e| e.name == name)
+            .unwrap_or_else(|| panic!("element {name} not found"))
+            .complexity
+    }
+
+    #[test]
+    fn test_straight_line_function() {
+        let m = metrics_for(
+            "src/lib.rs",
+            "pub fn get(&self) -> u32 {\n    self.value\n}\n",
+            Language::Rust,
+            "get",
+        );
+        assert_eq!(m.cyclomatic, 1);
+        assert_eq!(m.nesting_depth, 0);
+    }
+
+    #[test]
+    fn test_rust_branches_and_nesting() {
+        let src = "\
+pub fn classify(xs: &[i32]) -> i32 {
+    let mut n = 0;
+    for x in xs {
+        if *x > 0 && *x < 10 {
+            n += 1;
+        } else if *x < 0 {
+            n -= 1;
+        }
+    }
+    match n {
+        0 => 0,
+        _ => 1,
+    }
+}
+";
+        let m = metrics_for("src/lib.rs", src, Language::Rust, "classify");
+        // for, if, &&, else-if, 2 match arms
+        assert_eq!(m.cyclomatic, 7);
+        // for > if (else-if stays on the same level)
+        assert_eq!(m.nesting_depth, 2);
+    }
+
+    #[test]
+    fn test_python_branches() {
+        let src = "\
+def check(x):
+    if x and x > 1:
+        return 1
+    elif x:
+        return 2
+    try:
+        pass
+    except ValueError:
+        pass
+    return 0
+";
+        let m = metrics_for("check.py", src, Language::Python, "check");
+        // if, and, elif, except
+        assert_eq!(m.cyclomatic, 5);
+        assert_eq!(m.nesting_depth, 1);
+    }
+
+    #[test]
+    fn test_documents_are_not_annotated() {
+        let elements = crate::parser::parse_file(
+            Path::new("README.md"),
+            b"# Title\n\nSome text.\n",
+            Language::Markdown,
+        )
+        .expect("parse");
+        assert!(elements.iter().all(|e| !e.complexity.is_computed()));
+    }
+}
@@ -0,0 +1,291 @@
+//! Code embedded in another language's file.
+//!
+//! Markdown fenced blocks and the `<script>` / `<style>` blocks of HTML,
+//! Vue, and Svelte files hold code in a language other than the file's.
+//! [`regions`] finds them so [`super::parse_file`] can run each through its
+//! own analyzer, and [`tag_chunks`] records the language on the chunks that
+//! fall inside one:
+//!
+//! ````text
+//! ```python              -> Language::Python
+//! <script lang="ts">     -> Language::TypeScript
+//! <script>               -> Language::JavaScript
+//! <style lang="scss">    -> Language::Css
+//! ````
+//!
+//! Only languages with a code analyzer ([`Language::is_code`]) count; a
+//! `bash` or `json` fence stays part of the document.
+
+use crate::types::{Chunk, Language};
+
+/// A block of code in a language other than its file's.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmbeddedRegion {
+    /// Language of the code.
+    pub language: Language,
+    /// First line of the code in the host file (1-indexed).
+    pub line_start: u32,
+    /// Last line of the code in the host file (1-indexed, inclusive).
+    pub line_end: u32,
+    /// The code itself, line 1 of which is `line_start` in the host file.
+    pub code: String,
+}
+
+/// Find the embedded code regions of a `host` file. Empty for hosts that do
+/// not embed other languages.
+pub fn regions(source: &str, host: Language) -> Vec<EmbeddedRegion> {
+    let regions = match host {
+        Language::Markdown => markdown_fences(source),
+        Language::Html | Language::Vue | Language::Svelte => html_blocks(source),
+        _ => Vec::new(),
+    };
+    regions
+        .into_iter()
+        .filter(|r| r.language.is_code() && !r.code.trim().is_empty())
+        .collect()
+}
+
+/// Set [`Chunk::language`] on chunks that lie entirely inside a region.
+pub fn tag_chunks(chunks: &mut [Chunk], regions: &[EmbeddedRegion]) {
+    for chunk in chunks {
+        if let Some(region) = regions
+            .iter()
+            .find(|r| r.line_start <= chunk.line_start && chunk.line_end <= r.line_end)
+        {
+            chunk.language = Some(region.language);
+        }
+    }
+}
+
+/// Fenced code blocks (```` ``` ```` or `~~~`) with a language tag.
+fn markdown_fences(source: &str) -> Vec<EmbeddedRegion> {
+    let lines: Vec<&str> = source.lines().collect();
+    let mut regions = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let Some((fence, info)) = fence_open(lines[i]) else {
+            i += 1;
+            continue;
+        };
+        let body_start = i + 1;
+        let mut end = body_start;
+        while end < lines.len() && !is_fence_close(lines[end], fence) {
+            end += 1;
+        }
+        // The tag is the info string's first word: ```rust,ignore, ```{python}.
+        let tag = info
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .next()
+            .unwrap_or("")
+            .trim_matches(['{', '}', '.']);
+        if end > body_start && !tag.is_empty() {
+            regions.push(EmbeddedRegion {
+                language: Language::from_tag(tag),
+                line_start: line_number(body_start),
+                line_end: line_number(end - 1),
+                code: lines[body_start..end].join("\n"),
+            });
+        }
+        i = end + 1;
+    }
+    regions
+}
+
+/// The fence (e.g. "```") and info string of a line opening a code block.
+fn fence_open(line: &str) -> Option<(&str, &str)> {
+    let trimmed = line.trim_start();
+    let marker = trimmed.chars().next().filter(|&c| c == '`' || c == '~')?;
+    let len = trimmed.len() - trimmed.trim_start_matches(marker).len();
+    if len < 3 {
+        return None;
+    }
+    let (fence, info) = trimmed.split_at(len);
+    // A backtick fence's info string may not contain backticks.
+    (marker == '~' || !info.contains('`')).then(|| (fence, info.trim()))
+}
+
+/// Whether `line` closes a block opened with `fence`.
+fn is_fence_close(line: &str, fence: &str) -> bool {
+    let trimmed = line.trim();
+    let marker = fence.as_bytes()[0] as char;
+    trimmed.len() >= fence.len() && trimmed.chars().all(|c| c == marker)
+}
+
+/// `<script>` and `<style>` blocks.
+fn html_blocks(source: &str) -> Vec<EmbeddedRegion> {
+    // ASCII lowercasing keeps byte offsets, so tags match case-insensitively.
+    let lower = source.to_ascii_lowercase();
+    let mut regions = Vec::new();
+    let mut pos = 0;
+    while let Some((tag, open)) = next_tag(&lower, pos) {
+        let Some(open_end) = lower[open..].find('>').map(|i| open + i + 1) else {
+            break;
+        };
+        let close_tag = format!("</{tag}");
+        let close = lower[open_end..]
+            .find(&close_tag)
+            .map_or(source.len(), |i| open_end + i);
+        pos = close;
+
+        let attrs = &source[open + tag.len() + 1..open_end - 1];
+        if attrs.trim_end().ends_with('/') {
+            continue; // <script src="..." />
+        }
+        let language = match (attr(attrs, "lang").or_else(|| attr(attrs, "type")), tag) {
+            (Some(value), _) => Language::from_tag(value),
+            (None, "script") => Language::JavaScript,
+            (None, _) => Language::Css,
+        };
+
+        // Start at the line after the tag when the tag ends its line.
+        let mut start = open_end;
+        if let Some(newline) = source[start..close].find('\n') {
+            if source[start..start + newline].trim().is_empty() {
+                start += newline + 1;
+            }
+        }
+        let code = source[start..close].trim_end();
+        let line_start = line_number(source[..start].matches('\n').count());
+        regions.push(EmbeddedRegion {
+            language,
+            line_start,
+            line_end: line_start + code.lines().count().saturating_sub(1) as u32,
+            code: code.to_string(),
+        });
+    }
+    regions
+}
+
+/// The next `<script` or `<style` tag at or after `from`, and its offset.
+fn next_tag(lower: &str, from: usize) -> Option<(&'static str, usize)> {
+    ["script", "style"]
+        .into_iter()
+        .filter_map(|tag| {
+            let mut at = from;
+            while let Some(i) = lower[at..].find(&format!("<{tag}")) {
+                let open = at + i;
+                let next = lower[open + tag.len() + 1..].chars().next();
+                if next.is_some_and(|c| c.is_whitespace() || c == '>' || c == '/') {
+                    return Some((tag, open));
+                }
+                at = open + 1;
+            }
+            None
+        })
+        .min_by_key(|&(_, open)| open)
+}
+
+/// Value of attribute `name` in a tag's attribute text, quoted or not.
+fn attr<'a>(attrs: &'a str, name: &str) -> Option<&'a str> {
+    let lower = attrs.to_ascii_lowercase();
+    let mut at = 0;
+    while let Some(i) = lower[at..].find(name) {
+        let start = at + i;
+        at = start + name.len();
+        let standalone = !lower[..start]
+            .chars()
+            .next_back()
+            .is_some_and(|c| c.is_alphanumeric() || c == '-' || c == ':');
+        let rest = attrs[at..].trim_start();
+        let Some(value) = rest.strip_prefix('=').filter(|_| standalone) else {
+            continue;
+        };
+        let value = value.trim_start();
+        return Some(match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => {
+                let value = &value[1..];
+                &value[..value.find(quote).unwrap_or(value.len())]
+            }
+            _ => value.split_whitespace().next().unwrap_or(""),
+        });
+    }
+    None
+}
+
+/// 1-indexed line number of the 0-indexed line `index`.
+fn line_number(index: usize) -> u32 {
+    u32::try_from(index + 1).unwrap_or(u32::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_markdown_fences_by_tag() {
+        let source = "\
+# Usage
+
+```python
+def greet(name):
+    return f\"hi {name}\"
+```
+
+```bash
+pip install greet
+```
+
+~~~rust,ignore
+fn main() {}
+~~~
+
+```
+untagged
+```
+";
+        let found: Vec<(Language, u32, u32)> = regions(source, Language::Markdown)
+            .iter()
+            .map(|r| (r.language, r.line_start, r.line_end))
+            .collect();
+        assert_eq!(
+            found,
+            vec![(Language::Python, 4, 5), (Language::Rust, 13, 13)]
+        );
+        let python = &regions(source, Language::Markdown)[0];
+        assert!(python.code.starts_with("def greet(name):"));
+    }
+
+    #[test]
+    fn test_single_file_component_blocks() {
+        let source = "\
+<template>
+  <button @click=\"inc\">{{ count }}</button>
+</template>
+
+<script setup lang=\"ts\">
+const count = ref<number>(0);
+function inc(): void { count.value++; }
+</script>
+
+<SCRIPT src=\"vendor.js\"></SCRIPT>
+
+<style scoped>
+button { color: red; }
+</style>
+";
+        let found = regions(source, Language::Vue);
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].language, Language::TypeScript);
+        assert_eq!((found[0].line_start, found[0].line_end), (6, 7));
+        assert_eq!(
+            found[0].code,
+            "const count = ref<number>(0);\nfunction inc(): void { count.value++; }"
+        );
+        assert_eq!(found[1].language, Language::Css);
+        assert_eq!((found[1].line_start, found[1].line_end), (13, 13));
+    }
+
+    #[test]
+    fn test_non_code_scripts_are_skipped() {
+        let source = "\
+<script type=\"application/ld+json\">{\"@type\": \"Person\"}</script>
+<script type=\"text/x-template\"><div></div></script>
+<script>init();</script>
+";
+        let found = regions(source, Language::Html);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].language, Language::JavaScript);
+        assert_eq!((found[0].line_start, found[0].line_end), (3, 3));
+        assert_eq!(found[0].code, "init();");
+    }
+}
@@ -0,0 +1,133 @@
+//! Generated-code detection.
+//!
+//! Protobuf stubs, ORM models, and bindings can outnumber the hand-written
+//! code a query is after, so indexing flags generated code
+//! ([`crate::types::Chunk::is_generated`]):
+//!
+//! - a file is generated when a comment in its first [`HEADER_LINES`] lines
+//!   carries a marker such as Go's "Code generated ... DO NOT EDIT." or
+//!   `@generated`; all of its chunks are flagged;
+//! - a chunk in a hand-written file is flagged on its own when it is
+//!   annotated `@Generated` / `[GeneratedCode(...)]` or has an `@generated`
+//!   comment.
+//!
+//! Quoted markers do not count, so code and docs that merely talk about
+//! generated code are not flagged.
+
+/// Lines at the top of a file searched for a generated-file marker.
+pub const HEADER_LINES: usize = 40;
+
+/// Lowercase phrases marking a whole file as generated.
+const FILE_MARKERS: &[&str] = &[
+    "do not edit",
+    "do not modify",
+    "@generated",
+    "code generated",
+    "autogenerated",
+    "auto-generated",
+    "automatically generated",
+];
+
+/// Lowercase phrases marking a single chunk as generated. Narrower than
+/// [`FILE_MARKERS`]: a "do not edit" comment inside hand-written code is a
+/// warning to the reader, not provenance.
+const CHUNK_MARKERS: &[&str] = &["@generated", "<auto-generated"];
+
+/// Annotations and attributes a code generator puts on what it emits.
+const ANNOTATIONS: &[&str] = &[
+    "@Generated",
+    "@javax.annotation.Generated",
+    "@jakarta.annotation.Generated",
+    "[GeneratedCode",
+    "[System.CodeDom.Compiler.GeneratedCode",
+    "[global::System.CodeDom.Compiler.GeneratedCode",
+];
+
+/// Whether `source` opens with a generated-file marker comment.
+pub fn is_generated_file(source: &str) -> bool {
+    source
+        .lines()
+        .take(HEADER_LINES)
+        .any(|line| is_marker_comment(line, FILE_MARKERS))
+}
+
+/// Whether `content` (one chunk) is marked as generated by itself.
+pub fn is_generated_chunk(content: &str) -> bool {
+    content.lines().any(|line| {
+        let trimmed = line.trim_start();
+        ANNOTATIONS.iter().any(|a| trimmed.starts_with(a)) || is_marker_comment(line, CHUNK_MARKERS)
+    })
+}
+
+/// Whether `line` is a comment containing one of `markers` as a word.
+fn is_marker_comment(line: &str, markers: &[&str]) -> bool {
+    if !is_comment(line.trim_start()) {
+        return false;
+    }
+    let lower = line.to_lowercase();
+    markers.iter().any(|marker| {
+        lower.match_indices(marker).any(|(at, _)| {
+            !lower[..at]
+                .chars()
+                .next_back()
+                .is_some_and(|c| c.is_alphanumeric() || matches!(c, '_' | '`' | '"' | '\''))
+        })
+    })
+}
+
+/// Whether a trimmed line starts a comment in one of the indexed languages.
+/// `#` counts unless it opens a directive or attribute (`#include`,
+/// `#[derive]`, `#!`).
+fn is_comment(trimmed: &str) -> bool {
+    if let Some(rest) = trimmed.strip_prefix('#') {
+        return !rest.starts_with(|c: char| c.is_ascii_alphabetic() || c == '[' || c == '!');
+    }
+    ["//", "/*", "*", "--", "<!--", ";", "\"\"\"", "'''"]
+        .iter()
+        .any(|prefix| trimmed.starts_with(prefix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generated_file_markers() {
+        assert!(is_generated_file(
+            "// Code generated by protoc-gen-go. DO NOT EDIT.\npackage pb\n"
+        ));
+        assert!(is_generated_file(
+            "# -*- coding: utf-8 -*-\n# Generated by the protocol buffer compiler.  DO NOT EDIT!\n"
+        ));
+        assert!(is_generated_file(
+            "/**\n * @generated\n */\nexport const x = 1;\n"
+        ));
+        assert!(!is_generated_file(
+            "// Parses `@generated` banners.\nfn parse() {}\n"
+        ));
+        assert!(!is_generated_file(
+            "#include \"autogenerated_table.h\"\nint x;\n"
+        ));
+        assert!(!is_generated_file("fn main() {}\n"));
+
+        let late = format!("{}// DO NOT EDIT\n", "fn f() {}\n".repeat(HEADER_LINES));
+        assert!(!is_generated_file(&late));
+    }
+
+    #[test]
+    fn test_generated_chunk_markers() {
+        assert!(is_generated_chunk(
+            "@Generated(\"jsonschema2pojo\")\npublic class Order {}"
+        ));
+        assert!(is_generated_chunk(
+            "[GeneratedCode(\"xsd\", \"4.0\")]\npublic partial class Item {}"
+        ));
+        assert!(is_generated_chunk(
+            "// @generated from schema.graphql\ntype Q = {};"
+        ));
+        // A warning to readers, not provenance.
+        assert!(!is_generated_chunk(
+            "// Do not edit without updating the docs.\nfn f() {}"
+        ));
+    }
+}
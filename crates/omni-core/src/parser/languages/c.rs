@@ -5,7 +5,7 @@
 use std::path::Path;
 
 use crate::parser::{LanguageAnalyzer, StructuralElement};
-use crate::types::{ChunkKind, DependencyKind, ImportStatement, Visibility};
+use crate::types::{ChunkKind, ComplexityMetrics, DependencyKind, ImportStatement, Visibility};
 
 /// Analyzer for C source files.
 pub struct CAnalyzer;
@@ -102,6 +102,7 @@ impl CAnalyzer {
                                 references: Vec::new(),
                                 extends: Vec::new(),
                                 implements: Vec::new(),
+                                complexity: ComplexityMetrics::default(),
                             });
                         }
                     }
@@ -127,6 +128,7 @@ impl CAnalyzer {
                             references: Vec::new(),
                             extends: Vec::new(),
                             implements: Vec::new(),
+                            complexity: ComplexityMetrics::default(),
                         });
                     }
                 }
@@ -149,6 +151,7 @@ impl CAnalyzer {
                                 references: Vec::new(),
                                 extends: Vec::new(),
                                 implements: Vec::new(),
+                                complexity: ComplexityMetrics::default(),
                             });
                         }
                     }
@@ -168,6 +171,7 @@ impl CAnalyzer {
                             references: Vec::new(),
                             extends: Vec::new(),
                             implements: Vec::new(),
+                            complexity: ComplexityMetrics::default(),
                         });
                     }
                 }
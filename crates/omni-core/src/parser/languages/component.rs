@@ -0,0 +1,647 @@
+//! Vue and Svelte single-file component analyzer.
+//!
+//! A component file is split into its sections: the markup (`template`),
+//! each `<script>` block, and each `<style>` block. The functions and
+//! classes inside the script and the rules inside the style are extracted
+//! by the TypeScript / JavaScript / CSS analyzers through
+//! [`crate::parser::embedded`]; this analyzer adds the sections themselves
+//! and the component's interface as symbols:
+//!
+//! | | Vue | Svelte |
+//! |---|---|---|
+//! | props | `defineProps(...)`, `props:` | `export let`, `let { .. } = $props()` |
+//! | events | `defineEmits(...)`, `emits:`, `emit('x')` / `$emit('x')` | `dispatch('x')` |
+//!
+//! Props are recorded as `<module>.props.<name>` and events as
+//! `<module>.events.<name>`, so `Counter.vue`'s `step` prop is found by
+//! searching for `Counter.props.step`.
+
+use std::path::Path;
+
+use crate::parser::embedded::{self, EmbeddedRegion};
+use crate::parser::{LanguageAnalyzer, StructuralElement};
+use crate::types::{ChunkKind, ComplexityMetrics, ImportStatement, Language, Visibility};
+
+/// Analyzer for Vue (`.vue`) and Svelte (`.svelte`) components.
+pub struct ComponentAnalyzer {
+    lang: Language,
+}
+
+impl ComponentAnalyzer {
+    /// Create a component analyzer for [`Language::Vue`] or
+    /// [`Language::Svelte`].
+    pub fn new(lang: Language) -> Self {
+        Self { lang }
+    }
+
+    /// Props declared by a script block, with byte offsets into `script`.
+    fn props<'a>(&self, script: &'a str) -> Vec<(&'a str, usize)> {
+        if self.lang == Language::Svelte {
+            let mut props = svelte_exports(script);
+            props.extend(svelte_rune_props(script));
+            return props;
+        }
+        let declared = macro_names(script, "defineProps");
+        if declared.is_empty() {
+            option_names(script, "props")
+        } else {
+            declared
+        }
+    }
+
+    /// Events declared by a script block or emitted from `text`, with byte
+    /// offsets into `text`.
+    fn events<'a>(&self, text: &'a str) -> Vec<(&'a str, usize)> {
+        if self.lang == Language::Svelte {
+            return call_names(text, "dispatch");
+        }
+        let mut events = macro_names(text, "defineEmits");
+        if events.is_empty() {
+            events = option_names(text, "emits");
+        }
+        events.extend(call_names(text, "emit"));
+        events
+    }
+}
+
+impl LanguageAnalyzer for ComponentAnalyzer {
+    fn language_id(&self) -> &str {
+        self.lang.as_str()
+    }
+
+    fn tree_sitter_language(&self) -> tree_sitter::Language {
+        // Sections are found by scanning the text; the placeholder grammar
+        // only satisfies the trait, as for the document analyzer.
+        tree_sitter_md::LANGUAGE.into()
+    }
+
+    fn extract_structure(
+        &self,
+        _tree: &tree_sitter::Tree,
+        source: &[u8],
+        file_path: &Path,
+    ) -> Vec<StructuralElement> {
+        let source = std::str::from_utf8(source).unwrap_or("");
+        let module_name = crate::parser::build_module_name_from_path(file_path);
+        let lines: Vec<&str> = source.lines().collect();
+        let regions = embedded::regions(source, self.lang);
+
+        let mut elements = Vec::new();
+        let mut props = Vec::new();
+        let mut events = Vec::new();
+
+        for (i, (start, end)) in markup_runs(source).into_iter().enumerate() {
+            let name = section_name("template", i);
+            let content = lines[start as usize - 1..end as usize].join("\n");
+            for (event, offset) in self.events(&content) {
+                events.push((event.to_string(), line_at(&content, offset, start)));
+            }
+            elements.push(element(&module_name, &name, (start, end), content));
+        }
+
+        let mut scripts = 0;
+        let mut styles = 0;
+        for region in &regions {
+            let name = if region.language == Language::Css {
+                styles += 1;
+                section_name("style", styles - 1)
+            } else {
+                scripts += 1;
+                let code = region.code.as_str();
+                for (prop, offset) in self.props(code) {
+                    props.push((prop.to_string(), line_at(code, offset, region.line_start)));
+                }
+                for (event, offset) in self.events(code) {
+                    events.push((event.to_string(), line_at(code, offset, region.line_start)));
+                }
+                section_name("script", scripts - 1)
+            };
+            elements.push(element(
+                &module_name,
+                &name,
+                (region.line_start, region.line_end),
+                region.code.clone(),
+            ));
+        }
+
+        for (group, names) in [("props", props), ("events", events)] {
+            let mut seen = std::collections::HashSet::new();
+            let mut names: Vec<(String, u32)> = names
+                .into_iter()
+                .filter(|(name, _)| seen.insert(name.clone()))
+                .collect();
+            names.sort_by_key(|&(_, line)| line);
+            for (name, line) in names {
+                let content = lines
+                    .get(line as usize - 1)
+                    .map_or_else(String::new, |l| l.trim().to_string());
+                elements.push(StructuralElement {
+                    kind: ChunkKind::Const,
+                    ..element(
+                        &format!("{module_name}.{group}"),
+                        &name,
+                        (line, line),
+                        content,
+                    )
+                });
+            }
+        }
+
+        elements
+    }
+
+    fn extract_imports(
+        &self,
+        _tree: &tree_sitter::Tree,
+        source: &[u8],
+        file_path: &Path,
+    ) -> Vec<ImportStatement> {
+        let source = std::str::from_utf8(source).unwrap_or("");
+        embedded::regions(source, self.lang)
+            .iter()
+            .filter(|r| r.language != Language::Css)
+            .flat_map(|r| script_imports(file_path, r))
+            .collect()
+    }
+}
+
+/// Imports of a script block, numbered by component file line.
+fn script_imports(file_path: &Path, region: &EmbeddedRegion) -> Vec<ImportStatement> {
+    let mut imports =
+        crate::parser::parse_imports(file_path, region.code.as_bytes(), region.language)
+            .unwrap_or_default();
+    for import in &mut imports {
+        import.line += region.line_start - 1;
+    }
+    imports
+}
+
+/// `name` for the first section of its kind, `name_2` and so on after.
+fn section_name(name: &str, index: usize) -> String {
+    if index == 0 {
+        name.to_string()
+    } else {
+        format!("{name}_{}", index + 1)
+    }
+}
+
+fn element(parent: &str, name: &str, lines: (u32, u32), content: String) -> StructuralElement {
+    StructuralElement {
+        symbol_path: format!("{parent}.{name}"),
+        name: name.to_string(),
+        kind: ChunkKind::TopLevel,
+        visibility: Visibility::Public,
+        line_start: lines.0,
+        line_end: lines.1,
+        content,
+        doc_comment: None,
+        references: Vec::new(),
+        extends: Vec::new(),
+        implements: Vec::new(),
+        complexity: ComplexityMetrics::default(),
+    }
+}
+
+/// File line of byte `offset` in `text`, whose first line is `first_line`.
+fn line_at(text: &str, offset: usize, first_line: u32) -> u32 {
+    first_line + text[..offset].matches('\n').count() as u32
+}
+
+/// Line ranges (1-indexed, inclusive) of the markup: the non-blank lines
+/// outside top-level `<script>` and `<style>` blocks, split at each block.
+fn markup_runs(source: &str) -> Vec<(u32, u32)> {
+    let mut runs = Vec::new();
+    let mut run: Option<(u32, u32)> = None;
+    let mut open_block: Option<&str> = None;
+    for (i, line) in source.lines().enumerate() {
+        let number = i as u32 + 1;
+        let lower = line.trim_start().to_ascii_lowercase();
+        if open_block.is_none() {
+            open_block = ["script", "style"].into_iter().find(|tag| {
+                lower
+                    .strip_prefix('<')
+                    .and_then(|rest| rest.strip_prefix(tag))
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with([' ', '>', '\t']))
+            });
+        }
+        if let Some(tag) = open_block {
+            if lower.contains(&format!("</{tag}")) {
+                open_block = None;
+            }
+            runs.extend(run.take());
+        } else if !line.trim().is_empty() {
+            run = Some(run.map_or((number, number), |(start, _)| (start, number)));
+        }
+    }
+    runs.extend(run);
+    runs
+}
+
+/// Names declared through a compiler macro such as `defineProps`: from its
+/// type argument (`<{ a: string }>` or `<Props>`), or from an array or
+/// object argument.
+fn macro_names<'a>(script: &'a str, name: &str) -> Vec<(&'a str, usize)> {
+    let mut names = Vec::new();
+    for (at, _) in script.match_indices(name) {
+        if !is_word(script, at, name.len()) {
+            continue;
+        }
+        let Some(open) = next_non_space(script, at + name.len()) else {
+            continue;
+        };
+        match script.as_bytes()[open] {
+            b'<' => {
+                let (Some(close), Some(arg)) =
+                    (matching(script, open), next_non_space(script, open + 1))
+                else {
+                    continue;
+                };
+                if script.as_bytes()[arg] == b'{' {
+                    names.extend(member_names(script, arg));
+                } else if let Some(body) = type_body(script, &script[arg..close]) {
+                    names.extend(member_names(script, body));
+                }
+            }
+            b'(' => {
+                if let Some(arg) = next_non_space(script, open + 1) {
+                    names.extend(member_names(script, arg));
+                }
+            }
+            _ => {}
+        }
+    }
+    names
+}
+
+/// Names in an options-API property such as `props: [..]` or `emits: {..}`.
+fn option_names<'a>(script: &'a str, key: &str) -> Vec<(&'a str, usize)> {
+    for (at, _) in script.match_indices(key) {
+        if !is_word(script, at, key.len()) {
+            continue;
+        }
+        let Some(colon) = next_non_space(script, at + key.len()) else {
+            continue;
+        };
+        if script.as_bytes()[colon] != b':' {
+            continue;
+        }
+        if let Some(open) = next_non_space(script, colon + 1) {
+            if matches!(script.as_bytes()[open], b'[' | b'{') {
+                return member_names(script, open);
+            }
+        }
+    }
+    Vec::new()
+}
+
+/// First string argument of each `name(...)` call, including `$name(`.
+fn call_names<'a>(text: &'a str, name: &str) -> Vec<(&'a str, usize)> {
+    let mut names = Vec::new();
+    for (at, _) in text.match_indices(name) {
+        let after = at + name.len();
+        let preceded_by_dollar = text[..at].ends_with('$');
+        let word_start = if preceded_by_dollar { at - 1 } else { at };
+        if !is_word(text, word_start, after - word_start) || !text[after..].starts_with('(') {
+            continue;
+        }
+        if let Some(arg) = next_non_space(text, after + 1) {
+            if let Some(value) = quoted(&text[arg..]) {
+                names.push((value, arg + 1));
+            }
+        }
+    }
+    names
+}
+
+/// Svelte 4 props: `export let name`.
+fn svelte_exports(script: &str) -> Vec<(&str, usize)> {
+    script
+        .match_indices("export let ")
+        .filter(|&(at, _)| is_word(script, at, "export".len()))
+        .filter_map(|(at, m)| {
+            let start = next_non_space(script, at + m.len())?;
+            let name = identifier(&script[start..])?;
+            Some((name, start))
+        })
+        .collect()
+}
+
+/// Svelte 5 props: `let { a, b = 1 } = $props()`.
+fn svelte_rune_props(script: &str) -> Vec<(&str, usize)> {
+    let mut names = Vec::new();
+    for (at, _) in script.match_indices("let") {
+        if !is_word(script, at, 3) {
+            continue;
+        }
+        let Some(open) = next_non_space(script, at + 3) else {
+            continue;
+        };
+        if script.as_bytes()[open] != b'{' {
+            continue;
+        }
+        let Some(close) = matching(script, open) else {
+            continue;
+        };
+        let statement_end = script[close..]
+            .find([';', '\n'])
+            .map_or(script.len(), |i| close + i);
+        if script[close..statement_end].contains("$props(") {
+            names.extend(member_names(script, open));
+        }
+    }
+    names
+}
+
+/// Body offset of `interface Name {` or `type Name = {` in `script`.
+fn type_body(script: &str, name: &str) -> Option<usize> {
+    let name = identifier(name)?;
+    ["interface ", "type "].into_iter().find_map(|keyword| {
+        script.match_indices(keyword).find_map(|(at, m)| {
+            let start = next_non_space(script, at + m.len())?;
+            if identifier(&script[start..]) != Some(name) || !is_word(script, at, m.len() - 1) {
+                return None;
+            }
+            let open = start + script[start..].find('{')?;
+            script[start + name.len()..open]
+                .chars()
+                .all(|c| c.is_whitespace() || c == '=')
+                .then_some(open)
+        })
+    })
+}
+
+/// Names of the members of the `[..]` or `{..}` opening at `open`: string
+/// elements of an array, or keys of an object literal, type literal, or
+/// destructuring pattern. Call signatures (`(e: 'close'): void`) yield their
+/// first string literal.
+fn member_names(text: &str, open: usize) -> Vec<(&str, usize)> {
+    if !matches!(text.as_bytes()[open], b'[' | b'{') {
+        return Vec::new();
+    }
+    let Some(close) = matching(text, open) else {
+        return Vec::new();
+    };
+    let is_array = text.as_bytes()[open] == b'[';
+    split_members(text, open + 1, close)
+        .into_iter()
+        .filter_map(|start| {
+            let member = &text[start..close];
+            if is_array {
+                quoted(member).map(|name| (name, start + 1))
+            } else if let Some(signature) = member.strip_prefix('(') {
+                let params = &signature[..signature.find(')').unwrap_or(signature.len())];
+                let quote = params.find(['\'', '"'])?;
+                quoted(&params[quote..]).map(|name| (name, start + quote + 2))
+            } else if member.starts_with(['\'', '"']) {
+                quoted(member).map(|name| (name, start + 1))
+            } else if member.starts_with("...") {
+                None
+            } else {
+                let key = member.strip_prefix("readonly ").unwrap_or(member);
+                identifier(key).map(|name| (name, start + member.len() - key.len()))
+            }
+        })
+        .collect()
+}
+
+/// Start offsets of the top-level members between `start` and `end`,
+/// separated by `,` or `;` (or a line break in type literals), skipping
+/// comments.
+fn split_members(text: &str, start: usize, end: usize) -> Vec<usize> {
+    let bytes = text.as_bytes();
+    let mut members = Vec::new();
+    // Whether the next non-space byte starts a member.
+    let mut expecting = true;
+    let mut i = start;
+    while i < end {
+        match bytes[i] {
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                i = text[i..end].find('\n').map_or(end, |n| i + n);
+                continue;
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i = text[i..end].find("*/").map_or(end, |n| i + n + 2);
+                continue;
+            }
+            b',' | b';' => expecting = true,
+            // A line break ends a member unless the value continues.
+            b'\n' => expecting |= !text[..i].trim_end().ends_with([':', '=', '|', '&']),
+            c if c.is_ascii_whitespace() => {}
+            c => {
+                if expecting {
+                    members.push(i);
+                    expecting = false;
+                }
+                let opens = matches!(c, b'(' | b'[' | b'{' | b'\'' | b'"' | b'`')
+                    || (c == b'<' && is_generic_open(bytes, i));
+                if opens {
+                    i = matching(text, i).map_or(end, |close| close + 1);
+                    continue;
+                }
+            }
+        }
+        i += 1;
+    }
+    members
+}
+
+/// Offset of the byte closing the bracket or quote at `open`, skipping
+/// nested brackets and string literals. `<` only opens a type argument
+/// list when it follows a name (`Array<T>`), and `>` only closes one, so
+/// comparisons and arrows are not brackets.
+fn matching(text: &str, open: usize) -> Option<usize> {
+    let bytes = text.as_bytes();
+    if let quote @ (b'\'' | b'"' | b'`') = bytes[open] {
+        return text[open + 1..].find(quote as char).map(|i| open + 1 + i);
+    }
+    let mut stack = Vec::new();
+    let mut i = open;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\'' | b'"' | b'`' => i = matching(text, i)?,
+            b'<' if i == open || is_generic_open(bytes, i) => stack.push(b'<'),
+            b'(' | b'[' | b'{' => stack.push(bytes[i]),
+            b'>' if stack.last() == Some(&b'<') && bytes[i - 1] != b'=' => {
+                stack.pop();
+                if stack.is_empty() {
+                    return Some(i);
+                }
+            }
+            closer @ (b')' | b']' | b'}') => {
+                let opener = match closer {
+                    b')' => b'(',
+                    b']' => b'[',
+                    _ => b'{',
+                };
+                if stack.pop() != Some(opener) {
+                    return None;
+                }
+                if stack.is_empty() {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Whether the `<` at `i` opens type arguments: it directly follows a name.
+fn is_generic_open(bytes: &[u8], i: usize) -> bool {
+    i > 0 && (bytes[i - 1].is_ascii_alphanumeric() || bytes[i - 1] == b'_')
+}
+
+/// Contents of the string literal `text` starts with.
+fn quoted(text: &str) -> Option<&str> {
+    let quote = text.chars().next().filter(|&c| c == '\'' || c == '"')?;
+    let rest = &text[1..];
+    let value = &rest[..rest.find(quote)?];
+    (!value.is_empty()).then_some(value)
+}
+
+/// JavaScript identifier at the start of `text`.
+fn identifier(text: &str) -> Option<&str> {
+    let end = text
+        .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$'))
+        .unwrap_or(text.len());
+    (end > 0 && !text.as_bytes()[0].is_ascii_digit()).then(|| &text[..end])
+}
+
+/// Whether `len` bytes at `at` in `text` form a whole identifier.
+fn is_word(text: &str, at: usize, len: usize) -> bool {
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_' || c == '$';
+    !text[..at].chars().next_back().is_some_and(is_ident)
+        && !text[at + len..].chars().next().is_some_and(is_ident)
+}
+
+/// Offset of the first non-whitespace byte at or after `from`.
+fn next_non_space(text: &str, from: usize) -> Option<usize> {
+    text[from..]
+        .find(|c: char| !c.is_whitespace())
+        .map(|i| from + i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(lang: Language, path: &str, source: &str) -> Vec<StructuralElement> {
+        let analyzer = ComponentAnalyzer::new(lang);
+        let mut parser = tree_sitter::Parser::new();
+        parser
+            .set_language(&analyzer.tree_sitter_language())
+            .expect("set language");
+        let tree = parser.parse(source.as_bytes(), None).expect("parse");
+        analyzer.extract_structure(&tree, source.as_bytes(), Path::new(path))
+    }
+
+    fn names<'a>(elements: &'a [StructuralElement], group: &str) -> Vec<(&'a str, u32)> {
+        elements
+            .iter()
+            .filter(|e| e.symbol_path.ends_with(&format!(".{group}.{}", e.name)))
+            .map(|e| (e.name.as_str(), e.line_start))
+            .collect()
+    }
+
+    #[test]
+    fn test_vue_script_setup() {
+        let source = "\
+<template>
+  <button @click=\"$emit('close')\">{{ label }}</button>
+</template>
+
+<script setup lang=\"ts\">
+import { computed } from 'vue';
+
+const props = defineProps<{
+  label: string;
+  // optional
+  count?: number;
+}>();
+const emit = defineEmits<{
+  (e: 'change', value: number): void;
+  (e: 'close'): void;
+}>();
+</script>
+
+<style scoped>
+button { color: red; }
+</style>
+";
+        let elements = parse(Language::Vue, "src/components/Counter.vue", source);
+        let sections: Vec<(&str, u32, u32)> = elements
+            .iter()
+            .filter(|e| e.kind == ChunkKind::TopLevel)
+            .map(|e| (e.symbol_path.as_str(), e.line_start, e.line_end))
+            .collect();
+        assert_eq!(
+            sections,
+            vec![
+                ("components/Counter.template", 1, 3),
+                ("components/Counter.script", 6, 16),
+                ("components/Counter.style", 20, 20),
+            ]
+        );
+        assert_eq!(names(&elements, "props"), vec![("label", 9), ("count", 11)]);
+        assert_eq!(
+            names(&elements, "events"),
+            vec![("close", 2), ("change", 14)]
+        );
+    }
+
+    #[test]
+    fn test_vue_options_api() {
+        let source = "\
+<template><div /></template>
+<script>
+export default {
+  props: ['title', \"items\"],
+  emits: { select: null, 'update:modelValue': (v) => v > 0 },
+  methods: {
+    pick(item) { this.$emit('select', item); },
+  },
+};
+</script>
+";
+        let elements = parse(Language::Vue, "List.vue", source);
+        assert_eq!(names(&elements, "props"), vec![("title", 4), ("items", 4)]);
+        assert_eq!(
+            names(&elements, "events"),
+            vec![("select", 5), ("update:modelValue", 5)]
+        );
+    }
+
+    #[test]
+    fn test_svelte_props_and_events() {
+        let source = "\
+<script>
+  import { createEventDispatcher } from 'svelte';
+  export let name;
+  export let greeting = 'Hello';
+  const dispatch = createEventDispatcher();
+</script>
+
+<h1 on:click={() => dispatch('greet', name)}>{greeting} {name}!</h1>
+";
+        let elements = parse(Language::Svelte, "Hello.svelte", source);
+        assert_eq!(
+            names(&elements, "props"),
+            vec![("name", 3), ("greeting", 4)]
+        );
+        assert_eq!(names(&elements, "events"), vec![("greet", 8)]);
+        assert!(elements.iter().any(|e| e.symbol_path == "Hello.template"));
+    }
+
+    #[test]
+    fn test_svelte_rune_props() {
+        let source = "\
+<script lang=\"ts\">
+  let { title, count = 0, ...rest }: Props = $props();
+  let { a } = other();
+</script>
+";
+        let elements = parse(Language::Svelte, "Card.svelte", source);
+        assert_eq!(names(&elements, "props"), vec![("title", 2), ("count", 2)]);
+    }
+}
@@ -6,7 +6,7 @@
 use std::path::Path;
 
 use crate::parser::{LanguageAnalyzer, StructuralElement};
-use crate::types::{ChunkKind, DependencyKind, ImportStatement, Visibility};
+use crate::types::{ChunkKind, ComplexityMetrics, DependencyKind, ImportStatement, Visibility};
 
 /// Analyzer for C++ source files.
 pub struct CppAnalyzer;
@@ -101,6 +101,7 @@ impl CppAnalyzer {
                                 references: Vec::new(),
                                 extends: Vec::new(),
                                 implements: Vec::new(),
+                                complexity: ComplexityMetrics::default(),
                             });
                         }
                     }
@@ -123,6 +124,7 @@ impl CppAnalyzer {
                             references: Vec::new(),
                             extends: Vec::new(),
                             implements: Vec::new(),
+                            complexity: ComplexityMetrics::default(),
                         });
 
                         // Recurse into body
@@ -152,6 +154,7 @@ impl CppAnalyzer {
                             references: Vec::new(),
                             extends: Vec::new(),
                             implements: Vec::new(),
+                            complexity: ComplexityMetrics::default(),
                         });
 
                         if let Some(body) = child.child_by_field_name("body") {
@@ -180,6 +183,7 @@ impl CppAnalyzer {
                             references: Vec::new(),
                             extends: Vec::new(),
                             implements: Vec::new(),
+                            complexity: ComplexityMetrics::default(),
                         });
                     }
                 }
@@ -198,6 +202,7 @@ impl CppAnalyzer {
                             references: Vec::new(),
                             extends: Vec::new(),
                             implements: Vec::new(),
+                            complexity: ComplexityMetrics::default(),
                         });
                     }
                 }
@@ -1,11 +1,19 @@
 //! C# language analyzer.
 //!
 //! Extracts structural elements from C# source files using tree-sitter.
+//!
+//! Symbols are named the way .NET names them: by namespace, not by file.
+//! A block (`namespace A.B { }`) or file-scoped (`namespace A.B;`)
+//! namespace qualifies everything declared under it, so `class UserService`
+//! in `namespace MyApp.Services;` is `MyApp.Services.UserService` whatever
+//! file it lives in. The parts of a `partial` class therefore share one FQN
+//! and are indexed as a single symbol. Files without a namespace fall back
+//! to a module name built from their path.
 
 use std::path::Path;
 
 use crate::parser::{LanguageAnalyzer, StructuralElement};
-use crate::types::{ChunkKind, DependencyKind, ImportStatement, Visibility};
+use crate::types::{ChunkKind, ComplexityMetrics, DependencyKind, ImportStatement, Visibility};
 
 /// Analyzer for C# source files.
 pub struct CSharpAnalyzer;
@@ -26,12 +34,13 @@ impl LanguageAnalyzer for CSharpAnalyzer {
         file_path: &Path,
     ) -> Vec<StructuralElement> {
         let mut elements = Vec::new();
-        let module_name_str =
-            crate::parser::build_module_name_from_path(file_path).replace("/", ".");
-        let module_name = &module_name_str;
-
         let root = tree.root_node();
-        self.walk_node(root, source, module_name, &[], &mut elements);
+        let scope = if declares_namespace(root) {
+            Vec::new()
+        } else {
+            vec![crate::parser::build_module_name_from_path(file_path).replace("/", ".")]
+        };
+        self.walk_node(root, source, &scope, &mut elements);
         elements
     }
 
@@ -49,16 +58,21 @@ impl LanguageAnalyzer for CSharpAnalyzer {
             if child.kind() == "using_directive" {
                 let line = child.start_position().row as u32 + 1;
                 let text = node_text(child, source)
+                    .trim_start_matches("global ")
+                    .trim_start()
                     .trim_start_matches("using ")
+                    .trim_start()
+                    .trim_start_matches("static ")
                     .trim_end_matches(';')
                     .trim()
                     .to_string();
-                // Skip using aliases like "using X = Y;"
+                // Skip using aliases like "using X = Y;". A using imports a
+                // namespace, not names from it, so the edge goes to the
+                // namespace symbol itself.
                 if !text.contains('=') && !text.is_empty() {
-                    let name = text.rsplit('.').next().unwrap_or(&text).to_string();
                     imports.push(ImportStatement {
                         import_path: text,
-                        imported_names: vec![name],
+                        imported_names: Vec::new(),
                         line,
                         kind: DependencyKind::Imports,
                     });
@@ -75,53 +89,27 @@ impl CSharpAnalyzer {
         &self,
         node: tree_sitter::Node<'_>,
         source: &[u8],
-        module_name: &str,
         scope_path: &[String],
         elements: &mut Vec<StructuralElement>,
     ) {
         let mut cursor = node.walk();
+        // Set by `namespace A.B;`, which scopes the declarations after it.
+        let mut file_scope: Option<Vec<String>> = None;
 
         for child in node.children(&mut cursor) {
+            let scope_path = file_scope.as_deref().unwrap_or(scope_path);
             match child.kind() {
                 "class_declaration" | "record_declaration" => {
-                    self.extract_type_decl(
-                        child,
-                        source,
-                        module_name,
-                        scope_path,
-                        ChunkKind::Class,
-                        elements,
-                    );
+                    self.extract_type_decl(child, source, scope_path, ChunkKind::Class, elements);
                 }
                 "interface_declaration" => {
-                    self.extract_type_decl(
-                        child,
-                        source,
-                        module_name,
-                        scope_path,
-                        ChunkKind::Trait,
-                        elements,
-                    );
+                    self.extract_type_decl(child, source, scope_path, ChunkKind::Trait, elements);
                 }
                 "struct_declaration" => {
-                    self.extract_type_decl(
-                        child,
-                        source,
-                        module_name,
-                        scope_path,
-                        ChunkKind::Class,
-                        elements,
-                    );
+                    self.extract_type_decl(child, source, scope_path, ChunkKind::Class, elements);
                 }
                 "enum_declaration" => {
-                    self.extract_type_decl(
-                        child,
-                        source,
-                        module_name,
-                        scope_path,
-                        ChunkKind::TypeDef,
-                        elements,
-                    );
+                    self.extract_type_decl(child, source, scope_path, ChunkKind::TypeDef, elements);
                 }
                 "namespace_declaration" => {
                     let name = child
@@ -131,7 +119,7 @@ impl CSharpAnalyzer {
 
                     if !name.is_empty() {
                         elements.push(StructuralElement {
-                            symbol_path: build_path(module_name, scope_path, &name),
+                            symbol_path: build_path(scope_path, &name),
                             name: name.clone(),
                             kind: ChunkKind::Module,
                             visibility: Visibility::Public,
@@ -142,15 +130,47 @@ impl CSharpAnalyzer {
                             references: Vec::new(),
                             extends: Vec::new(),
                             implements: Vec::new(),
+                            complexity: ComplexityMetrics::default(),
                         });
 
                         if let Some(body) = child.child_by_field_name("body") {
                             let mut inner = scope_path.to_vec();
                             inner.push(name);
-                            self.walk_node(body, source, module_name, &inner, elements);
+                            self.walk_node(body, source, &inner, elements);
                         }
                     }
                 }
+                "file_scoped_namespace_declaration" => {
+                    let name = child
+                        .child_by_field_name("name")
+                        .map(|n| node_text(n, source).to_string())
+                        .unwrap_or_default();
+
+                    if !name.is_empty() {
+                        let line = child.start_position().row as u32 + 1;
+                        elements.push(StructuralElement {
+                            symbol_path: build_path(scope_path, &name),
+                            name: name.clone(),
+                            kind: ChunkKind::Module,
+                            visibility: Visibility::Public,
+                            line_start: line,
+                            line_end: line,
+                            content: format!("namespace {name};"),
+                            doc_comment: None,
+                            references: Vec::new(),
+                            extends: Vec::new(),
+                            implements: Vec::new(),
+                            complexity: ComplexityMetrics::default(),
+                        });
+
+                        let mut inner = scope_path.to_vec();
+                        inner.push(name);
+                        // Older grammars nest the declarations that follow
+                        // inside this node; newer ones make them siblings.
+                        self.walk_node(child, source, &inner, elements);
+                        file_scope = Some(inner);
+                    }
+                }
                 "method_declaration" | "constructor_declaration" => {
                     if let Some(name_node) = child.child_by_field_name("name") {
                         let name = node_text(name_node, source).to_string();
@@ -158,7 +178,7 @@ impl CSharpAnalyzer {
                         let doc = extract_xml_doc(child, source);
 
                         elements.push(StructuralElement {
-                            symbol_path: build_path(module_name, scope_path, &name),
+                            symbol_path: build_path(scope_path, &name),
                             name,
                             kind: ChunkKind::Function,
                             visibility: vis,
@@ -169,6 +189,7 @@ impl CSharpAnalyzer {
                             references: Vec::new(),
                             extends: Vec::new(),
                             implements: Vec::new(),
+                            complexity: ComplexityMetrics::default(),
                         });
                     }
                 }
@@ -178,7 +199,7 @@ impl CSharpAnalyzer {
                         let vis = extract_cs_visibility(child, source);
 
                         elements.push(StructuralElement {
-                            symbol_path: build_path(module_name, scope_path, &name),
+                            symbol_path: build_path(scope_path, &name),
                             name,
                             kind: ChunkKind::Const,
                             visibility: vis,
@@ -189,12 +210,13 @@ impl CSharpAnalyzer {
                             references: Vec::new(),
                             extends: Vec::new(),
                             implements: Vec::new(),
+                            complexity: ComplexityMetrics::default(),
                         });
                     }
                 }
                 _ => {
                     if child.child_count() > 0 {
-                        self.walk_node(child, source, module_name, scope_path, elements);
+                        self.walk_node(child, source, scope_path, elements);
                     }
                 }
             }
@@ -205,16 +227,16 @@ impl CSharpAnalyzer {
         &self,
         node: tree_sitter::Node<'_>,
         source: &[u8],
-        module_name: &str,
         scope_path: &[String],
         kind: ChunkKind,
         elements: &mut Vec<StructuralElement>,
     ) {
         if let Some(name_node) = node.child_by_field_name("name") {
             let name = node_text(name_node, source).to_string();
-            let symbol_path = build_path(module_name, scope_path, &name);
+            let symbol_path = build_path(scope_path, &name);
             let vis = extract_cs_visibility(node, source);
             let doc = extract_xml_doc(node, source);
+            let (extends, implements) = split_bases(node.kind(), base_types(node, source));
 
             elements.push(StructuralElement {
                 symbol_path: symbol_path.clone(),
@@ -225,16 +247,17 @@ impl CSharpAnalyzer {
                 line_end: node.end_position().row as u32 + 1,
                 content: node_text(node, source).to_string(),
                 doc_comment: doc,
-                references: Vec::new(),
-                extends: Vec::new(),
-                implements: Vec::new(),
+                references: extends.iter().chain(&implements).cloned().collect(),
+                extends,
+                implements,
+                complexity: ComplexityMetrics::default(),
             });
 
             // Recurse into body
             if let Some(body) = node.child_by_field_name("body") {
                 let mut inner = scope_path.to_vec();
                 inner.push(name);
-                self.walk_node(body, source, module_name, &inner, elements);
+                self.walk_node(body, source, &inner, elements);
             }
         }
     }
@@ -248,15 +271,77 @@ fn node_text<'a>(node: tree_sitter::Node<'_>, source: &'a [u8]) -> &'a str {
     node.utf8_text(source).unwrap_or("")
 }
 
-fn build_path(module: &str, scope: &[String], name: &str) -> String {
-    let mut parts: Vec<&str> = vec![module];
-    for s in scope {
-        parts.push(s);
-    }
+fn build_path(scope: &[String], name: &str) -> String {
+    let mut parts: Vec<&str> = scope.iter().map(String::as_str).collect();
     parts.push(name);
     parts.join(".")
 }
 
+/// Whether the file declares a namespace, block or file-scoped.
+fn declares_namespace(root: tree_sitter::Node<'_>) -> bool {
+    let mut cursor = root.walk();
+    for child in root.children(&mut cursor) {
+        if matches!(
+            child.kind(),
+            "namespace_declaration" | "file_scoped_namespace_declaration"
+        ) {
+            return true;
+        }
+    }
+    false
+}
+
+/// Simple names of the types in a declaration's base list:
+/// `class Repo : Base<User>, IRepo` gives `["Base", "IRepo"]`.
+fn base_types(node: tree_sitter::Node<'_>, source: &[u8]) -> Vec<String> {
+    let mut bases = Vec::new();
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        if child.kind() != "base_list" {
+            continue;
+        }
+        let mut base_cursor = child.walk();
+        for base in child.named_children(&mut base_cursor) {
+            // Drop type arguments and a record's primary constructor arguments.
+            let text = node_text(base, source)
+                .split(['<', '('])
+                .next()
+                .unwrap_or("");
+            let name = text.rsplit('.').next().unwrap_or("").trim();
+            if !name.is_empty() {
+                bases.push(name.to_string());
+            }
+        }
+    }
+    bases
+}
+
+/// Split a declaration's base list into (extends, implements). A class puts
+/// its base class, if any, first; past it every entry is an interface, which
+/// .NET names `IFoo`. Structs only implement, and an enum's base is its
+/// underlying integer type.
+fn split_bases(decl_kind: &str, bases: Vec<String>) -> (Vec<String>, Vec<String>) {
+    match decl_kind {
+        "interface_declaration" => (bases, Vec::new()),
+        "struct_declaration" => (Vec::new(), bases),
+        "class_declaration" | "record_declaration" => {
+            let mut bases = bases.into_iter();
+            match bases.next() {
+                Some(first) if !is_interface_name(&first) => (vec![first], bases.collect()),
+                Some(first) => (Vec::new(), std::iter::once(first).chain(bases).collect()),
+                None => (Vec::new(), Vec::new()),
+            }
+        }
+        _ => (Vec::new(), Vec::new()),
+    }
+}
+
+/// `IRepository`, `IDisposable`: the .NET naming convention for interfaces.
+fn is_interface_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next() == Some('I') && chars.next().is_some_and(|c| c.is_ascii_uppercase())
+}
+
 fn extract_cs_visibility(node: tree_sitter::Node<'_>, source: &[u8]) -> Visibility {
     let mut cursor = node.walk();
     for child in node.children(&mut cursor) {
@@ -330,4 +415,115 @@ public interface IRepository {
             .iter()
             .any(|e| e.name == "IRepository" && e.kind == ChunkKind::Trait));
     }
+
+    fn path_of<'a>(elements: &'a [StructuralElement], name: &str) -> &'a str {
+        elements
+            .iter()
+            .find(|e| e.name == name)
+            .map(|e| e.symbol_path.as_str())
+            .unwrap_or_else(|| panic!("no element named {name}"))
+    }
+
+    #[test]
+    fn test_cs_file_scoped_namespace_qualifies_symbols() {
+        let src = r"
+using System;
+
+namespace MyApp.Services;
+
+public class UserService {
+    public void GetUser() {}
+}
+";
+        let elements = parse_cs(src);
+        assert_eq!(path_of(&elements, "MyApp.Services"), "MyApp.Services");
+        assert_eq!(
+            path_of(&elements, "UserService"),
+            "MyApp.Services.UserService"
+        );
+        assert_eq!(
+            path_of(&elements, "GetUser"),
+            "MyApp.Services.UserService.GetUser"
+        );
+    }
+
+    #[test]
+    fn test_cs_block_namespace_qualifies_symbols() {
+        let src = r"
+namespace MyApp {
+    namespace Data {
+        public class Repo {}
+    }
+}
+";
+        let elements = parse_cs(src);
+        assert_eq!(path_of(&elements, "Data"), "MyApp.Data");
+        assert_eq!(path_of(&elements, "Repo"), "MyApp.Data.Repo");
+    }
+
+    #[test]
+    fn test_cs_without_namespace_uses_file_module() {
+        let elements = parse_cs("public class Program {}");
+        assert_eq!(path_of(&elements, "Program"), "Test.Program");
+    }
+
+    #[test]
+    fn test_cs_partial_class_parts_share_fqn() {
+        let part_one = "namespace App;\npublic partial class Order { public void Submit() {} }";
+        let part_two = "namespace App;\npublic partial class Order { public void Cancel() {} }";
+        let one = parse_cs(part_one);
+        let two = parse_cs(part_two);
+        assert_eq!(path_of(&one, "Order"), "App.Order");
+        assert_eq!(path_of(&one, "Order"), path_of(&two, "Order"));
+        assert_eq!(path_of(&two, "Cancel"), "App.Order.Cancel");
+    }
+
+    #[test]
+    fn test_cs_base_list() {
+        let src = r"
+namespace App;
+
+public class UserRepo : Repository<User>, IUserRepo, IDisposable {}
+public class Handler : IHandler {}
+public interface IUserRepo : IRepository<User> {}
+public struct Point : IEquatable<Point> {}
+";
+        let elements = parse_cs(src);
+        let get = |name: &str| elements.iter().find(|e| e.name == name).unwrap();
+
+        let repo = get("UserRepo");
+        assert_eq!(repo.extends, vec!["Repository"]);
+        assert_eq!(repo.implements, vec!["IUserRepo", "IDisposable"]);
+        assert!(repo.references.contains(&"Repository".to_string()));
+
+        let handler = get("Handler");
+        assert!(handler.extends.is_empty());
+        assert_eq!(handler.implements, vec!["IHandler"]);
+
+        assert_eq!(get("IUserRepo").extends, vec!["IRepository"]);
+        assert_eq!(get("Point").implements, vec!["IEquatable"]);
+    }
+
+    #[test]
+    fn test_cs_using_directives_import_namespaces() {
+        let src = r"
+using System.Collections.Generic;
+global using MyApp.Models;
+using static System.Math;
+using Json = System.Text.Json;
+";
+        let analyzer = CSharpAnalyzer;
+        let mut parser = tree_sitter::Parser::new();
+        parser
+            .set_language(&analyzer.tree_sitter_language())
+            .expect("set language");
+        let tree = parser.parse(src.as_bytes(), None).expect("parse");
+        let imports = analyzer.extract_imports(&tree, src.as_bytes(), Path::new("Test.cs"));
+        let paths: Vec<&str> = imports.iter().map(|i| i.import_path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec!["System.Collections.Generic", "MyApp.Models", "System.Math"]
+        );
+        assert!(imports.iter().all(|i| i.imported_names.is_empty()));
+    }
 }
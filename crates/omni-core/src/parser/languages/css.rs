@@ -5,7 +5,7 @@
 use std::path::Path;
 
 use crate::parser::{LanguageAnalyzer, StructuralElement};
-use crate::types::{ChunkKind, Visibility};
+use crate::types::{ChunkKind, ComplexityMetrics, Visibility};
 
 /// Analyzer for CSS/SCSS source files.
 pub struct CssAnalyzer;
@@ -74,6 +74,7 @@ impl LanguageAnalyzer for CssAnalyzer {
                                 references: Vec::new(),
                                 extends: Vec::new(),
                                 implements: Vec::new(),
+                                complexity: ComplexityMetrics::default(),
                             });
                         }
                     }
@@ -98,6 +99,7 @@ impl LanguageAnalyzer for CssAnalyzer {
                         references: Vec::new(),
                         extends: Vec::new(),
                         implements: Vec::new(),
+                        complexity: ComplexityMetrics::default(),
                     });
                 }
                 _ => {}
@@ -7,7 +7,7 @@
 use std::path::Path;
 
 use crate::parser::{LanguageAnalyzer, StructuralElement};
-use crate::types::{ChunkKind, Language, Visibility};
+use crate::types::{ChunkKind, ComplexityMetrics, Language, Visibility};
 
 /// Text-based analyzer for documentation and configuration files.
 ///
@@ -49,6 +49,7 @@ impl DocumentAnalyzer {
                         references: Vec::new(),
                         extends: Vec::new(),
                         implements: Vec::new(),
+                        complexity: ComplexityMetrics::default(),
                     });
                 }
 
@@ -81,6 +82,7 @@ impl DocumentAnalyzer {
                     references: Vec::new(),
                     extends: Vec::new(),
                     implements: Vec::new(),
+                    complexity: ComplexityMetrics::default(),
                 });
             }
         } else if !source.trim().is_empty() {
@@ -97,6 +99,7 @@ impl DocumentAnalyzer {
                 references: Vec::new(),
                 extends: Vec::new(),
                 implements: Vec::new(),
+                complexity: ComplexityMetrics::default(),
             });
         }
 
@@ -130,6 +133,7 @@ impl DocumentAnalyzer {
                         references: Vec::new(),
                         extends: Vec::new(),
                         implements: Vec::new(),
+                        complexity: ComplexityMetrics::default(),
                     });
                 }
 
@@ -159,6 +163,7 @@ impl DocumentAnalyzer {
                         references: Vec::new(),
                         extends: Vec::new(),
                         implements: Vec::new(),
+                        complexity: ComplexityMetrics::default(),
                     });
                 }
 
@@ -194,6 +199,7 @@ impl DocumentAnalyzer {
                     references: Vec::new(),
                     extends: Vec::new(),
                     implements: Vec::new(),
+                    complexity: ComplexityMetrics::default(),
                 });
             }
         } else if !source.trim().is_empty() {
@@ -209,6 +215,7 @@ impl DocumentAnalyzer {
                 references: Vec::new(),
                 extends: Vec::new(),
                 implements: Vec::new(),
+                complexity: ComplexityMetrics::default(),
             });
         }
 
@@ -241,6 +248,7 @@ impl DocumentAnalyzer {
                     references: Vec::new(),
                     extends: Vec::new(),
                     implements: Vec::new(),
+                    complexity: ComplexityMetrics::default(),
                 });
                 block.clear();
                 block_start = line_num + 1;
@@ -274,6 +282,7 @@ impl DocumentAnalyzer {
                 references: Vec::new(),
                 extends: Vec::new(),
                 implements: Vec::new(),
+                complexity: ComplexityMetrics::default(),
             });
         }
 
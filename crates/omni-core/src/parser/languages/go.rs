@@ -10,7 +10,7 @@
 use std::path::Path;
 
 use crate::parser::{LanguageAnalyzer, StructuralElement};
-use crate::types::{ChunkKind, DependencyKind, ImportStatement, Visibility};
+use crate::types::{ChunkKind, ComplexityMetrics, DependencyKind, ImportStatement, Visibility};
 
 /// Analyzer for Go source files.
 pub struct GoAnalyzer;
@@ -158,6 +158,7 @@ impl GoAnalyzer {
             references: Vec::new(),
             extends: Vec::new(),
             implements: Vec::new(),
+            complexity: ComplexityMetrics::default(),
         })
     }
 
@@ -207,6 +208,7 @@ impl GoAnalyzer {
             references: Vec::new(),
             extends: Vec::new(),
             implements: Vec::new(),
+            complexity: ComplexityMetrics::default(),
         })
     }
 
@@ -252,6 +254,7 @@ impl GoAnalyzer {
                     references: Vec::new(),
                     extends: Vec::new(),
                     implements: Vec::new(),
+                    complexity: ComplexityMetrics::default(),
                 });
             }
         }
@@ -288,6 +291,7 @@ impl GoAnalyzer {
                     references: Vec::new(),
                     extends: Vec::new(),
                     implements: Vec::new(),
+                    complexity: ComplexityMetrics::default(),
                 });
             }
         }
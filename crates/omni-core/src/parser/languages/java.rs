@@ -5,7 +5,7 @@
 use std::path::Path;
 
 use crate::parser::{LanguageAnalyzer, StructuralElement};
-use crate::types::{ChunkKind, DependencyKind, ImportStatement, Visibility};
+use crate::types::{ChunkKind, ComplexityMetrics, DependencyKind, ImportStatement, Visibility};
 
 /// Analyzer for Java source files.
 pub struct JavaAnalyzer;
@@ -139,6 +139,7 @@ impl JavaAnalyzer {
                             references,
                             extends,
                             implements,
+                            complexity: ComplexityMetrics::default(),
                         });
 
                         // Recurse into class body
@@ -168,6 +169,7 @@ impl JavaAnalyzer {
                             references: Vec::new(),
                             extends: Vec::new(),
                             implements: Vec::new(),
+                            complexity: ComplexityMetrics::default(),
                         });
                     }
                 }
@@ -193,6 +195,7 @@ impl JavaAnalyzer {
                                     references: Vec::new(),
                                     extends: Vec::new(),
                                     implements: Vec::new(),
+                                    complexity: ComplexityMetrics::default(),
                                 });
                             }
                         }
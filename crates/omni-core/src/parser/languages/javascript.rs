@@ -6,7 +6,7 @@
 use std::path::Path;
 
 use crate::parser::{LanguageAnalyzer, StructuralElement};
-use crate::types::ImportStatement;
+use crate::types::{ImportStatement, SymbolAlias};
 
 /// Analyzer for JavaScript source files.
 pub struct JavaScriptAnalyzer;
@@ -46,6 +46,15 @@ impl LanguageAnalyzer for JavaScriptAnalyzer {
         super::typescript::collect_ts_imports(tree.root_node(), source, &mut imports);
         imports
     }
+
+    fn extract_aliases(
+        &self,
+        tree: &tree_sitter::Tree,
+        source: &[u8],
+        file_path: &Path,
+    ) -> Vec<SymbolAlias> {
+        super::typescript::collect_ts_reexports(tree.root_node(), source, file_path)
+    }
 }
 
 #[cfg(test)]
@@ -5,7 +5,7 @@
 use std::path::Path;
 
 use crate::parser::{LanguageAnalyzer, StructuralElement};
-use crate::types::{ChunkKind, DependencyKind, ImportStatement, Visibility};
+use crate::types::{ChunkKind, ComplexityMetrics, DependencyKind, ImportStatement, Visibility};
 
 /// Analyzer for Kotlin source files.
 pub struct KotlinAnalyzer;
@@ -141,6 +141,7 @@ impl KotlinAnalyzer {
             references: vec![],
             extends: vec![],
             implements: vec![],
+            complexity: ComplexityMetrics::default(),
         })
     }
 
@@ -176,6 +177,7 @@ impl KotlinAnalyzer {
             references: vec![],
             extends: vec![],
             implements: vec![],
+            complexity: ComplexityMetrics::default(),
         })
     }
 
@@ -211,6 +213,7 @@ impl KotlinAnalyzer {
             references: vec![],
             extends: vec![],
             implements: vec![],
+            complexity: ComplexityMetrics::default(),
         })
     }
 
@@ -4,6 +4,7 @@
 //! They are registered in the `registry` module at startup.
 
 pub mod c;
+pub mod component;
 pub mod cpp;
 pub mod csharp;
 pub mod css;
@@ -5,7 +5,7 @@
 use std::path::Path;
 
 use crate::parser::{LanguageAnalyzer, StructuralElement};
-use crate::types::{ChunkKind, DependencyKind, ImportStatement, Visibility};
+use crate::types::{ChunkKind, ComplexityMetrics, DependencyKind, ImportStatement, Visibility};
 
 /// Analyzer for PHP source files.
 pub struct PhpAnalyzer;
@@ -158,6 +158,7 @@ impl PhpAnalyzer {
             references: vec![],
             extends: vec![],
             implements: vec![],
+            complexity: ComplexityMetrics::default(),
         })
     }
 
@@ -196,6 +197,7 @@ impl PhpAnalyzer {
             references: vec![],
             extends: vec![],
             implements: vec![],
+            complexity: ComplexityMetrics::default(),
         })
     }
 
@@ -231,6 +233,7 @@ impl PhpAnalyzer {
             references: vec![],
             extends: vec![],
             implements: vec![],
+            complexity: ComplexityMetrics::default(),
         })
     }
 
@@ -266,6 +269,7 @@ impl PhpAnalyzer {
             references: vec![],
             extends: vec![],
             implements: vec![],
+            complexity: ComplexityMetrics::default(),
         })
     }
 
@@ -14,7 +14,9 @@
 use std::path::Path;
 
 use crate::parser::{LanguageAnalyzer, StructuralElement};
-use crate::types::{ChunkKind, DependencyKind, ImportStatement, Visibility};
+use crate::types::{
+    ChunkKind, ComplexityMetrics, DependencyKind, ImportStatement, SymbolAlias, Visibility,
+};
 
 /// Analyzer for Python source files.
 pub struct PythonAnalyzer;
@@ -127,6 +129,95 @@ impl LanguageAnalyzer for PythonAnalyzer {
 
         imports
     }
+
+    /// Names a package `__init__.py` re-exports with `from ... import`.
+    fn extract_aliases(
+        &self,
+        tree: &tree_sitter::Tree,
+        source: &[u8],
+        file_path: &Path,
+    ) -> Vec<SymbolAlias> {
+        if file_path.file_stem().and_then(|s| s.to_str()) != Some("__init__") {
+            return Vec::new();
+        }
+        let module = crate::parser::build_module_name_from_path(file_path);
+        let package = module
+            .strip_suffix("__init__")
+            .unwrap_or(&module)
+            .trim_end_matches('/')
+            .replace('/', ".");
+
+        let root = tree.root_node();
+        let mut aliases = Vec::new();
+        let mut cursor = root.walk();
+        for child in root.children(&mut cursor) {
+            if child.kind() != "import_from_statement" {
+                continue;
+            }
+            let Some(module_node) = child.child_by_field_name("module_name") else {
+                continue;
+            };
+            let Some(target) = resolve_from_module(file_path, node_text(module_node, source))
+            else {
+                continue;
+            };
+            let line = child.start_position().row as u32 + 1;
+            let mut push = |name: &str, bound: &str| {
+                let alias_fqn = qualify(&package, bound);
+                let target_fqn = qualify(&target, name);
+                if alias_fqn != target_fqn {
+                    aliases.push(SymbolAlias {
+                        alias_fqn,
+                        target_fqn,
+                        line,
+                    });
+                }
+            };
+            let mut inner = child.walk();
+            for name_node in child.children(&mut inner) {
+                match name_node.kind() {
+                    "dotted_name" if name_node != module_node => {
+                        let name = node_text(name_node, source);
+                        push(name, name);
+                    }
+                    "aliased_import" => {
+                        let name = name_node
+                            .child_by_field_name("name")
+                            .map_or("", |n| node_text(n, source));
+                        let bound = name_node
+                            .child_by_field_name("alias")
+                            .map_or(name, |n| node_text(n, source));
+                        if !name.is_empty() {
+                            push(name, bound);
+                        }
+                    }
+                    "wildcard_import" => push("*", "*"),
+                    _ => {}
+                }
+            }
+        }
+        aliases
+    }
+}
+
+/// Dotted module a `from` clause names, with relative imports (`.models`,
+/// `..util`) resolved against the file's package.
+fn resolve_from_module(file_path: &Path, module: &str) -> Option<String> {
+    let rest = module.trim_start_matches('.');
+    let dots = module.len() - rest.len();
+    if dots == 0 {
+        return Some(module.to_string());
+    }
+    let relative = format!("./{}{}", "../".repeat(dots - 1), rest.replace('.', "/"));
+    crate::parser::relative_module_name(file_path, &relative).map(|m| m.replace('/', "."))
+}
+
+fn qualify(module: &str, name: &str) -> String {
+    if module.is_empty() {
+        name.to_string()
+    } else {
+        format!("{module}.{name}")
+    }
 }
 
 impl PythonAnalyzer {
@@ -262,6 +353,7 @@ impl PythonAnalyzer {
             references,
             extends: Vec::new(),
             implements: Vec::new(),
+            complexity: ComplexityMetrics::default(),
         })
     }
 
@@ -308,6 +400,7 @@ impl PythonAnalyzer {
             references,
             extends,
             implements: Vec::new(),
+            complexity: ComplexityMetrics::default(),
         })
     }
 
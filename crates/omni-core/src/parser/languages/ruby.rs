@@ -5,7 +5,7 @@
 use std::path::Path;
 
 use crate::parser::{LanguageAnalyzer, StructuralElement};
-use crate::types::{ChunkKind, DependencyKind, ImportStatement, Visibility};
+use crate::types::{ChunkKind, ComplexityMetrics, DependencyKind, ImportStatement, Visibility};
 
 /// Analyzer for Ruby source files.
 pub struct RubyAnalyzer;
@@ -166,6 +166,7 @@ impl RubyAnalyzer {
             references: vec![],
             extends: vec![],
             implements: vec![],
+            complexity: ComplexityMetrics::default(),
         })
     }
 
@@ -201,6 +202,7 @@ impl RubyAnalyzer {
             references: vec![],
             extends: vec![],
             implements: vec![],
+            complexity: ComplexityMetrics::default(),
         })
     }
 
@@ -236,6 +238,7 @@ impl RubyAnalyzer {
             references: vec![],
             extends: vec![],
             implements: vec![],
+            complexity: ComplexityMetrics::default(),
         })
     }
 }
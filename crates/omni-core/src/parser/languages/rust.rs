@@ -18,7 +18,9 @@
 use std::path::Path;
 
 use crate::parser::{LanguageAnalyzer, StructuralElement};
-use crate::types::{ChunkKind, DependencyKind, ImportStatement, Visibility};
+use crate::types::{
+    ChunkKind, ComplexityMetrics, DependencyKind, ImportStatement, SymbolAlias, Visibility,
+};
 
 /// Analyzer for Rust source files.
 pub struct RustAnalyzer;
@@ -59,6 +61,168 @@ impl LanguageAnalyzer for RustAnalyzer {
 
         imports
     }
+
+    fn extract_aliases(
+        &self,
+        tree: &tree_sitter::Tree,
+        source: &[u8],
+        file_path: &Path,
+    ) -> Vec<SymbolAlias> {
+        let scope = UseScope::for_file(file_path);
+        let root = tree.root_node();
+        let mut aliases = Vec::new();
+        let mut cursor = root.walk();
+        for child in root.children(&mut cursor) {
+            if child.kind() != "use_declaration" {
+                continue;
+            }
+            let mut inner = child.walk();
+            let exported = child
+                .children(&mut inner)
+                .any(|c| c.kind() == "visibility_modifier");
+            let Some(argument) = child.child_by_field_name("argument") else {
+                continue;
+            };
+            if !exported {
+                continue;
+            }
+            let line = child.start_position().row as u32 + 1;
+            for (path, name) in expand_use_tree(node_text(argument, source)) {
+                let alias_fqn = join_path(&scope.self_module, &name);
+                let target_fqn = scope.resolve(&path);
+                if alias_fqn != target_fqn {
+                    aliases.push(SymbolAlias {
+                        alias_fqn,
+                        target_fqn,
+                        line,
+                    });
+                }
+            }
+        }
+        aliases
+    }
+}
+
+/// Module context for resolving `use` paths in one file.
+struct UseScope {
+    /// Module the file defines: its crate root for `lib.rs` and `main.rs`.
+    self_module: String,
+    /// Module of the crate root, for `crate::` paths.
+    crate_root: String,
+}
+
+impl UseScope {
+    fn for_file(file_path: &Path) -> Self {
+        let module = crate::parser::build_module_name_from_path(file_path).replace('/', "::");
+        let segments: Vec<&str> = module.split("::").collect();
+        // Workspace members keep their `<crate>/src` prefix in symbol FQNs.
+        let crate_root = segments
+            .iter()
+            .rposition(|s| *s == "src")
+            .map(|i| segments[..=i].join("::"))
+            .unwrap_or_default();
+        let stem = file_path.file_stem().and_then(|s| s.to_str());
+        let self_module = if matches!(stem, Some("lib" | "main")) {
+            crate_root.clone()
+        } else {
+            module
+        };
+        Self {
+            self_module,
+            crate_root,
+        }
+    }
+
+    /// FQN of a `use` path. Paths not starting with `crate`, `self`,
+    /// `super`, or `::` are taken relative to this module (2018 uniform
+    /// paths); external crates then simply resolve to nothing.
+    fn resolve(&self, path: &str) -> String {
+        if let Some(external) = path.strip_prefix("::") {
+            return external.to_string();
+        }
+        let mut segments = path.split("::").peekable();
+        let base = match segments.peek() {
+            Some(&"crate") => {
+                segments.next();
+                self.crate_root.clone()
+            }
+            Some(&"self") => {
+                segments.next();
+                self.self_module.clone()
+            }
+            Some(&"super") => {
+                let mut base: Vec<&str> = self.self_module.split("::").collect();
+                while segments.next_if_eq(&"super").is_some() {
+                    base.pop();
+                }
+                base.join("::")
+            }
+            _ => self.self_module.clone(),
+        };
+        segments.fold(base, |acc, segment| join_path(&acc, segment))
+    }
+}
+
+fn join_path(base: &str, name: &str) -> String {
+    if base.is_empty() {
+        name.to_string()
+    } else {
+        format!("{base}::{name}")
+    }
+}
+
+/// Flatten a use tree (`a::{b, c::D as E, f::*}`) into `(path, name)`
+/// pairs: each item's full path and the name it is bound to (`*` for a
+/// glob, whose path then ends in `::*`).
+fn expand_use_tree(tree: &str) -> Vec<(String, String)> {
+    let tree: String = tree.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut out = Vec::new();
+    expand_use_tree_into("", &tree, &mut out);
+    out
+}
+
+fn expand_use_tree_into(prefix: &str, tree: &str, out: &mut Vec<(String, String)>) {
+    let tree = tree.trim();
+    if let Some(open) = tree.find('{') {
+        let Some(close) = tree.rfind('}') else {
+            return;
+        };
+        let base = join_path(prefix, tree[..open].trim().trim_end_matches("::"));
+        let mut depth = 0;
+        let mut start = open + 1;
+        for (i, c) in tree.char_indices().take(close).skip(open + 1) {
+            match c {
+                '{' => depth += 1,
+                '}' => depth -= 1,
+                ',' if depth == 0 => {
+                    expand_use_tree_into(&base, &tree[start..i], out);
+                    start = i + 1;
+                }
+                _ => {}
+            }
+        }
+        expand_use_tree_into(&base, &tree[start..close], out);
+        return;
+    }
+    if tree.is_empty() {
+        return;
+    }
+    let (path, rename) = match tree.split_once(" as ") {
+        Some((path, rename)) => (path.trim(), Some(rename.trim())),
+        None => (tree, None),
+    };
+    if path == "self" {
+        // `a::{self}` binds the module `a` itself.
+        if let Some(name) = prefix.rsplit("::").next().filter(|n| !n.is_empty()) {
+            out.push((prefix.to_string(), rename.unwrap_or(name).to_string()));
+        }
+        return;
+    }
+    let full = join_path(prefix, path);
+    let name = rename.unwrap_or_else(|| full.rsplit("::").next().unwrap_or(&full));
+    if name != "_" {
+        out.push((full.clone(), name.to_string()));
+    }
 }
 
 impl RustAnalyzer {
@@ -199,6 +363,7 @@ impl RustAnalyzer {
             references,
             extends: Vec::new(),
             implements: Vec::new(),
+            complexity: ComplexityMetrics::default(),
         })
     }
 
@@ -230,6 +395,7 @@ impl RustAnalyzer {
             references: Vec::new(),
             extends: Vec::new(),
             implements: Vec::new(),
+            complexity: ComplexityMetrics::default(),
         })
     }
 
@@ -271,6 +437,7 @@ impl RustAnalyzer {
             references: Vec::new(),
             extends: Vec::new(),
             implements,
+            complexity: ComplexityMetrics::default(),
         })
     }
 
@@ -301,6 +468,7 @@ impl RustAnalyzer {
             references: Vec::new(),
             extends: Vec::new(),
             implements: Vec::new(),
+            complexity: ComplexityMetrics::default(),
         })
     }
 
@@ -336,6 +504,7 @@ impl RustAnalyzer {
             references: Vec::new(),
             extends: Vec::new(),
             implements: Vec::new(),
+            complexity: ComplexityMetrics::default(),
         });
 
         // If inline module, recurse into body
@@ -361,12 +530,10 @@ impl RustAnalyzer {
             match child.kind() {
                 "use_declaration" => {
                     let line = child.start_position().row as u32 + 1;
-                    // Extract the use path text (everything after `use` and before `;`)
-                    let text = node_text(child, source);
-                    let path = text
-                        .strip_prefix("use ")
-                        .unwrap_or(text)
-                        .trim_end_matches(';')
+                    // The use tree, without visibility, `use`, or `;`.
+                    let path = child
+                        .child_by_field_name("argument")
+                        .map_or("", |argument| node_text(argument, source))
                         .trim();
 
                     if path.is_empty() {
@@ -778,4 +945,60 @@ impl Config {
             "path should use '::' separator: {path}"
         );
     }
+
+    #[test]
+    fn test_expand_use_tree() {
+        assert_eq!(
+            expand_use_tree("a::{b, c::D as E, f::{self, *}, _g as _}"),
+            vec![
+                ("a::b".to_string(), "b".to_string()),
+                ("a::c::D".to_string(), "E".to_string()),
+                ("a::f".to_string(), "f".to_string()),
+                ("a::f::*".to_string(), "*".to_string()),
+            ]
+        );
+        assert_eq!(
+            expand_use_tree("crate::config::Config"),
+            vec![("crate::config::Config".to_string(), "Config".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_pub_use_aliases() {
+        let src = "mod user;\nuse std::fmt;\npub use user::{User, Role as UserRole};\npub use crate::error::*;\npub(crate) use super::db::Pool;\n";
+        let (imports, aliases) = crate::parser::parse_imports_and_aliases(
+            Path::new("crates/app/src/models/mod.rs"),
+            src.as_bytes(),
+            Language::Rust,
+        )
+        .expect("parse should succeed");
+        assert!(
+            imports.iter().all(|i| !i.import_path.starts_with("pub")),
+            "{imports:?}"
+        );
+
+        let pairs: Vec<_> = aliases
+            .iter()
+            .map(|a| (a.alias_fqn.as_str(), a.target_fqn.as_str()))
+            .collect();
+        assert_eq!(
+            pairs,
+            vec![
+                (
+                    "crates::app::src::models::User",
+                    "crates::app::src::models::user::User"
+                ),
+                (
+                    "crates::app::src::models::UserRole",
+                    "crates::app::src::models::user::Role"
+                ),
+                ("crates::app::src::models::*", "crates::app::src::error::*"),
+                (
+                    "crates::app::src::models::Pool",
+                    "crates::app::src::db::Pool"
+                ),
+            ]
+        );
+        assert_eq!(aliases[0].line, 3);
+    }
 }
@@ -5,7 +5,7 @@
 use std::path::Path;
 
 use crate::parser::{LanguageAnalyzer, StructuralElement};
-use crate::types::{ChunkKind, DependencyKind, ImportStatement, Visibility};
+use crate::types::{ChunkKind, ComplexityMetrics, DependencyKind, ImportStatement, Visibility};
 
 /// Analyzer for Swift source files.
 pub struct SwiftAnalyzer;
@@ -156,6 +156,7 @@ impl SwiftAnalyzer {
             references: vec![],
             extends: vec![],
             implements: vec![],
+            complexity: ComplexityMetrics::default(),
         })
     }
 
@@ -191,6 +192,7 @@ impl SwiftAnalyzer {
             references: vec![],
             extends: vec![],
             implements: vec![],
+            complexity: ComplexityMetrics::default(),
         })
     }
 
@@ -226,6 +228,7 @@ impl SwiftAnalyzer {
             references: vec![],
             extends: vec![],
             implements: vec![],
+            complexity: ComplexityMetrics::default(),
         })
     }
 
@@ -261,6 +264,7 @@ impl SwiftAnalyzer {
             references: vec![],
             extends: vec![],
             implements: vec![],
+            complexity: ComplexityMetrics::default(),
         })
     }
 
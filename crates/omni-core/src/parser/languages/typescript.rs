@@ -6,7 +6,9 @@
 use std::path::Path;
 
 use crate::parser::{LanguageAnalyzer, StructuralElement};
-use crate::types::{ChunkKind, DependencyKind, ImportStatement, Visibility};
+use crate::types::{
+    ChunkKind, ComplexityMetrics, DependencyKind, ImportStatement, SymbolAlias, Visibility,
+};
 
 /// Analyzer for TypeScript source files.
 pub struct TypeScriptAnalyzer;
@@ -45,6 +47,15 @@ impl LanguageAnalyzer for TypeScriptAnalyzer {
         collect_ts_imports(tree.root_node(), source, &mut imports);
         imports
     }
+
+    fn extract_aliases(
+        &self,
+        tree: &tree_sitter::Tree,
+        source: &[u8],
+        file_path: &Path,
+    ) -> Vec<SymbolAlias> {
+        collect_ts_reexports(tree.root_node(), source, file_path)
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -206,6 +217,7 @@ pub(crate) fn extract_function_decl(
         references: Vec::new(),
         extends: Vec::new(),
         implements: Vec::new(),
+        complexity: ComplexityMetrics::default(),
     })
 }
 
@@ -272,6 +284,7 @@ pub(crate) fn extract_class_decl(
         references,
         extends,
         implements,
+        complexity: ComplexityMetrics::default(),
     })
 }
 
@@ -300,6 +313,7 @@ fn extract_interface(
         references: Vec::new(),
         extends: Vec::new(),
         implements: Vec::new(),
+        complexity: ComplexityMetrics::default(),
     })
 }
 
@@ -327,6 +341,7 @@ fn extract_type_alias(
         references: Vec::new(),
         extends: Vec::new(),
         implements: Vec::new(),
+        complexity: ComplexityMetrics::default(),
     })
 }
 
@@ -355,6 +370,7 @@ pub(crate) fn extract_method(
         references: Vec::new(),
         extends: Vec::new(),
         implements: Vec::new(),
+        complexity: ComplexityMetrics::default(),
     })
 }
 
@@ -396,6 +412,7 @@ pub(crate) fn extract_variable_declarations(
                 references: Vec::new(),
                 extends: Vec::new(),
                 implements: Vec::new(),
+                complexity: ComplexityMetrics::default(),
             });
         }
     }
@@ -481,6 +498,70 @@ pub(crate) fn collect_ts_imports(
     }
 }
 
+/// Collect `export { A, B as C } from './mod'` and `export * from './mod'`
+/// re-exports as aliases. `export * as ns from` binds a namespace object
+/// rather than a symbol and is skipped.
+pub(crate) fn collect_ts_reexports(
+    node: tree_sitter::Node<'_>,
+    source: &[u8],
+    file_path: &Path,
+) -> Vec<SymbolAlias> {
+    let module_name = crate::parser::build_module_name_from_path(file_path);
+    let mut aliases = Vec::new();
+    let mut cursor = node.walk();
+
+    for child in node.children(&mut cursor) {
+        if child.kind() != "export_statement" {
+            continue;
+        }
+        let Some(src_node) = child.child_by_field_name("source") else {
+            continue;
+        };
+        let module_path = node_text(src_node, source).trim_matches(|c: char| c == '\'' || c == '"');
+        let target_module = if module_path.starts_with('.') {
+            match crate::parser::relative_module_name(file_path, module_path) {
+                Some(m) => m,
+                None => continue,
+            }
+        } else {
+            module_path.to_string()
+        };
+        let line = child.start_position().row as u32 + 1;
+        let mut push = |name: &str, bound: &str| {
+            aliases.push(SymbolAlias {
+                alias_fqn: build_symbol_path(&module_name, &[], bound),
+                target_fqn: build_symbol_path(&target_module, &[], name),
+                line,
+            });
+        };
+
+        let mut inner = child.walk();
+        let parts: Vec<_> = child.children(&mut inner).collect();
+        if let Some(clause) = parts.iter().find(|n| n.kind() == "export_clause") {
+            let mut specs = clause.walk();
+            for spec in clause.children(&mut specs) {
+                if spec.kind() != "export_specifier" {
+                    continue;
+                }
+                let Some(name_node) = spec.child_by_field_name("name") else {
+                    continue;
+                };
+                let name = node_text(name_node, source);
+                let bound = spec
+                    .child_by_field_name("alias")
+                    .map_or(name, |n| node_text(n, source));
+                push(name, bound);
+            }
+        } else if parts.iter().any(|n| n.kind() == "*")
+            && !parts.iter().any(|n| n.kind() == "namespace_export")
+        {
+            push("*", "*");
+        }
+    }
+
+    aliases
+}
+
 /// Collect named imports from an import clause node.
 fn collect_import_names(node: tree_sitter::Node<'_>, source: &[u8], names: &mut Vec<String>) {
     let mut cursor = node.walk();
@@ -15,13 +15,19 @@
 //! The parser is stateless and can be invoked from multiple threads
 //! via `spawn_blocking`.
 
+pub mod complexity;
+pub mod embedded;
+pub mod generated;
 pub mod languages;
 pub mod registry;
+pub mod todos;
 
 use std::path::Path;
 
 use crate::error::OmniResult;
-use crate::types::{ChunkKind, ImportStatement, Language, Visibility};
+use crate::types::{
+    ChunkKind, ComplexityMetrics, ImportStatement, Language, SymbolAlias, Visibility,
+};
 
 /// A structural element extracted from an AST.
 #[derive(Debug, Clone)]
@@ -48,6 +54,9 @@ pub struct StructuralElement {
     pub extends: Vec<String>,
     /// Interfaces/traits this element implements.
     pub implements: Vec<String>,
+    /// Structural complexity. Analyzers leave this at the default; it is
+    /// filled in from the syntax tree by [`parse_file`].
+    pub complexity: ComplexityMetrics,
 }
 
 /// Trait that each language analyzer must implement.
@@ -77,6 +86,19 @@ pub trait LanguageAnalyzer: Send + Sync {
     ) -> Vec<ImportStatement> {
         Vec::new()
     }
+
+    /// Extract the names this file re-exports from other modules, so
+    /// imports of a re-exported name resolve to its definition.
+    ///
+    /// Default implementation returns empty (languages can override).
+    fn extract_aliases(
+        &self,
+        _tree: &tree_sitter::Tree,
+        _source: &[u8],
+        _file_path: &Path,
+    ) -> Vec<SymbolAlias> {
+        Vec::new()
+    }
 }
 
 /// Parse a source file and extract its structural elements.
@@ -86,19 +108,36 @@ pub trait LanguageAnalyzer: Send + Sync {
 /// 2. Loads the appropriate tree-sitter grammar
 /// 3. Parses the source code
 /// 4. Extracts structural elements via the language analyzer
+/// 5. Annotates code elements with complexity metrics
+/// 6. Parses code embedded in Markdown and HTML files with its own analyzer
 pub fn parse_file(
     file_path: &Path,
     source: &[u8],
     language: Language,
 ) -> OmniResult<Vec<StructuralElement>> {
-    let registry = registry::global_registry();
+    let (analyzer, tree) = parse_tree(file_path, source, language)?;
+    let mut elements = analyzer.extract_structure(&tree, source, file_path);
+    if language.is_code() {
+        complexity::annotate_elements(&tree, &mut elements);
+    }
+    if let Ok(text) = std::str::from_utf8(source) {
+        elements.extend(parse_embedded(file_path, text, language));
+    }
+    Ok(elements)
+}
 
-    let analyzer = registry
-        .get(language)
-        .ok_or_else(|| crate::error::OmniError::Parse {
+/// Parse `source` with the tree-sitter grammar of `language`'s analyzer.
+pub(crate) fn parse_tree(
+    file_path: &Path,
+    source: &[u8],
+    language: Language,
+) -> OmniResult<(&'static dyn LanguageAnalyzer, tree_sitter::Tree)> {
+    let analyzer = registry::global_registry().get(language).ok_or_else(|| {
+        crate::error::OmniError::Parse {
             path: file_path.to_path_buf(),
             message: format!("no analyzer registered for language: {language}"),
-        })?;
+        }
+    })?;
 
     let mut parser = tree_sitter::Parser::new();
     parser
@@ -114,8 +153,34 @@ pub fn parse_file(
             path: file_path.to_path_buf(),
             message: "tree-sitter returned None (parse timeout or cancellation)".into(),
         })?;
+    Ok((analyzer, tree))
+}
 
-    Ok(analyzer.extract_structure(&tree, source, file_path))
+/// Structural elements of the code embedded in a Markdown or HTML file (see
+/// [`embedded`]), numbered by host file line. They follow the host's own
+/// elements, which may overlap them.
+fn parse_embedded(file_path: &Path, source: &str, host: Language) -> Vec<StructuralElement> {
+    let mut elements = Vec::new();
+    for region in embedded::regions(source, host) {
+        match parse_file(file_path, region.code.as_bytes(), region.language) {
+            Ok(nested) => {
+                let offset = region.line_start - 1;
+                elements.extend(nested.into_iter().map(|mut elem| {
+                    elem.line_start += offset;
+                    elem.line_end = (elem.line_end + offset).min(region.line_end);
+                    elem
+                }));
+            }
+            Err(e) => tracing::debug!(
+                path = %file_path.display(),
+                language = %region.language,
+                line = region.line_start,
+                error = %e,
+                "skipping embedded code block"
+            ),
+        }
+    }
+    elements
 }
 
 /// Extract import statements from a source file.
@@ -126,31 +191,22 @@ pub fn parse_imports(
     source: &[u8],
     language: Language,
 ) -> OmniResult<Vec<ImportStatement>> {
-    let registry = registry::global_registry();
-
-    let analyzer = registry
-        .get(language)
-        .ok_or_else(|| crate::error::OmniError::Parse {
-            path: file_path.to_path_buf(),
-            message: format!("no analyzer registered for language: {language}"),
-        })?;
-
-    let mut parser = tree_sitter::Parser::new();
-    parser
-        .set_language(&analyzer.tree_sitter_language())
-        .map_err(|e| crate::error::OmniError::Parse {
-            path: file_path.to_path_buf(),
-            message: format!("failed to set tree-sitter language: {e}"),
-        })?;
-
-    let tree = parser
-        .parse(source, None)
-        .ok_or_else(|| crate::error::OmniError::Parse {
-            path: file_path.to_path_buf(),
-            message: "tree-sitter returned None".into(),
-        })?;
+    parse_imports_and_aliases(file_path, source, language).map(|(imports, _)| imports)
+}
 
-    Ok(analyzer.extract_imports(&tree, source, file_path))
+/// Extract import statements and re-export aliases from a source file in
+/// one parse. `file_path` must be relative to the repository root, as for
+/// `parse_file`, so alias FQNs match symbol FQNs.
+pub fn parse_imports_and_aliases(
+    file_path: &Path,
+    source: &[u8],
+    language: Language,
+) -> OmniResult<(Vec<ImportStatement>, Vec<SymbolAlias>)> {
+    let (analyzer, tree) = parse_tree(file_path, source, language)?;
+    Ok((
+        analyzer.extract_imports(&tree, source, file_path),
+        analyzer.extract_aliases(&tree, source, file_path),
+    ))
 }
 
 /// Convert a relative file path (from repo root) into a module-like FQN prefix.
@@ -196,10 +252,74 @@ pub fn build_module_name_from_path(path: &Path) -> String {
     parts.join("/") // Callers will `.replace("/", delimiter)` if needed
 }
 
+/// Module name, as from [`build_module_name_from_path`], of a module path
+/// written relative to `file_path`'s directory (`./button`, `../util`).
+/// Source extensions are ignored. Returns `None` when the path climbs
+/// above the repository root.
+pub(crate) fn relative_module_name(file_path: &Path, relative: &str) -> Option<String> {
+    let mut parts: Vec<&str> = file_path
+        .parent()
+        .into_iter()
+        .flat_map(Path::components)
+        .filter_map(|c| match c {
+            std::path::Component::Normal(s) => s.to_str(),
+            _ => None,
+        })
+        .collect();
+    for segment in relative.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                parts.pop()?;
+            }
+            other => parts.push(other),
+        }
+    }
+    let last = parts.pop()?;
+    // Stand-in extension, so dotted names like `foo.service` keep their
+    // full stem.
+    let stem = [
+        ".ts", ".tsx", ".js", ".jsx", ".mjs", ".cjs", ".vue", ".svelte", ".py",
+    ]
+    .iter()
+    .find_map(|ext| last.strip_suffix(ext))
+    .unwrap_or(last);
+    let mut path: std::path::PathBuf = parts.into_iter().collect();
+    path.push(format!("{stem}.src"));
+    Some(build_module_name_from_path(&path))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse_file_includes_embedded_code() {
+        let source = "\
+# Greeter
+
+Call it like this:
+
+```python
+def greet(name):
+    return name
+```
+";
+        let elements = parse_file(
+            Path::new("README.md"),
+            source.as_bytes(),
+            Language::Markdown,
+        )
+        .unwrap();
+        let greet = elements
+            .iter()
+            .find(|e| e.name == "greet")
+            .expect("embedded function extracted");
+        assert_eq!(greet.kind, ChunkKind::Function);
+        assert_eq!((greet.line_start, greet.line_end), (6, 7));
+        assert!(greet.content.starts_with("def greet"));
+    }
+
     #[test]
     fn test_parse_file_unknown_language_returns_error() {
         let result = parse_file(Path::new("test.xyz"), b"hello world", Language::Unknown);
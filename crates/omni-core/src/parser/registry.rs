@@ -75,6 +75,21 @@ impl Registry {
             Box::new(super::languages::kotlin::KotlinAnalyzer),
         );
 
+        // Frontend components (sections, props, and events; script and
+        // style bodies go through the analyzers above)
+        analyzers.insert(
+            Language::Vue,
+            Box::new(super::languages::component::ComponentAnalyzer::new(
+                Language::Vue,
+            )),
+        );
+        analyzers.insert(
+            Language::Svelte,
+            Box::new(super::languages::component::ComponentAnalyzer::new(
+                Language::Svelte,
+            )),
+        );
+
         // Document and config formats (section-based text chunking)
         analyzers.insert(
             Language::Markdown,
@@ -151,6 +166,9 @@ mod tests {
         assert!(reg.get(Language::Php).is_some());
         assert!(reg.get(Language::Swift).is_some());
         assert!(reg.get(Language::Kotlin).is_some());
+        // Frontend components
+        assert!(reg.get(Language::Vue).is_some());
+        assert!(reg.get(Language::Svelte).is_some());
     }
 
     #[test]
@@ -0,0 +1,280 @@
+//! TODO / FIXME / HACK comment extraction.
+//!
+//! Indexing records every tech-debt marker in a comment
+//! ([`crate::types::TodoComment`]) so [`crate::pipeline::Engine::todos`] can
+//! list the ones near the code an agent is about to touch. A marker counts
+//! when it is an upper-case word inside a comment node of the file's
+//! syntax tree, so markers in string literals are ignored and every line
+//! of a block comment is searched:
+//!
+//! ```text
+//! // TODO: retry on timeout
+//! x = parse(raw)  # FIXME(alice) drops the sign
+//! /*
+//!  * HACK: upstream returns -1 here
+//!  */
+//! ```
+//!
+//! An optional `(name)` after the marker is kept as the assignee. Author and
+//! age are not stored; they come from `git blame` when listing. Files in a
+//! language without a tree-sitter analyzer have no markers.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::types::{Language, TodoComment};
+
+/// Markers recorded, matched case-sensitively as whole words.
+pub const MARKERS: &[&str] = &["TODO", "FIXME", "HACK"];
+
+/// Longest comment text kept per marker, in characters.
+const MAX_TEXT_CHARS: usize = 200;
+
+/// Find the TODO, FIXME, and HACK comments in `content`.
+pub fn extract(rel_path: &Path, content: &str, language: Language) -> Vec<TodoComment> {
+    if !MARKERS.iter().any(|marker| content.contains(marker)) {
+        return Vec::new();
+    }
+    let tree = match super::parse_tree(rel_path, content.as_bytes(), language) {
+        Ok((_, tree)) => tree,
+        Err(e) => {
+            tracing::debug!(path = %rel_path.display(), error = %e, "no syntax tree for TODOs");
+            return Vec::new();
+        }
+    };
+
+    let mut todos = Vec::new();
+    let mut cursor = tree.walk();
+    'walk: loop {
+        let node = cursor.node();
+        // Grammars name them `comment`, `line_comment`, `block_comment`, ...
+        let is_comment = node.kind().contains("comment");
+        if is_comment {
+            let first_row = node.start_position().row;
+            let text = content.get(node.byte_range()).unwrap_or_default();
+            for (i, line) in text.lines().enumerate() {
+                let Some((tag, rest)) = find_marker(line) else {
+                    continue;
+                };
+                let (assignee, text) = split_assignee(rest);
+                todos.push(TodoComment {
+                    file_path: rel_path.to_path_buf(),
+                    line: u32::try_from(first_row + i + 1).unwrap_or(u32::MAX),
+                    tag: tag.to_string(),
+                    assignee,
+                    text,
+                    author: None,
+                    age_days: None,
+                });
+            }
+        }
+        // Comment nodes are leaves for our purposes: a doc comment's marker
+        // children would repeat its text.
+        if !is_comment && cursor.goto_first_child() {
+            continue;
+        }
+        while !cursor.goto_next_sibling() {
+            if !cursor.goto_parent() {
+                break 'walk;
+            }
+        }
+    }
+    todos
+}
+
+/// The first marker on a comment `line`, and the text after it.
+fn find_marker(line: &str) -> Option<(&'static str, &str)> {
+    MARKERS
+        .iter()
+        .filter_map(|marker| {
+            line.match_indices(marker)
+                .find(|&(at, _)| is_word(line, at, marker.len()))
+                .map(|(at, _)| (at, *marker))
+        })
+        .min_by_key(|&(at, _)| at)
+        .map(|(at, marker)| (marker, &line[at + marker.len()..]))
+}
+
+/// Whether `len` bytes at `at` in `text` form a whole word.
+fn is_word(text: &str, at: usize, len: usize) -> bool {
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    !text[..at].chars().next_back().is_some_and(is_ident)
+        && !text[at + len..].chars().next().is_some_and(is_ident)
+}
+
+/// Split `(name): text` into the assignee and the cleaned-up text.
+fn split_assignee(rest: &str) -> (Option<String>, String) {
+    let mut assignee = None;
+    let mut rest = rest;
+    if let Some(inner) = rest.strip_prefix('(') {
+        if let Some(close) = inner.find(')') {
+            let name = inner[..close].trim();
+            if !name.is_empty() {
+                assignee = Some(name.to_string());
+            }
+            rest = &inner[close + 1..];
+        }
+    }
+    let text = rest
+        .trim_start_matches([':', '-', ' ', '\t'])
+        .trim_end()
+        .trim_end_matches("*/")
+        .trim_end_matches("-->")
+        .trim_end();
+    (assignee, text.chars().take(MAX_TEXT_CHARS).collect())
+}
+
+/// Fill in `author` and `age_days` from `git blame`, one blame run per
+/// file. Lines git cannot attribute (uncommitted, untracked, or outside a
+/// repository) are left unset.
+pub fn annotate(repo_root: &Path, todos: &mut [TodoComment]) {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+
+    let mut by_file: HashMap<std::path::PathBuf, Vec<usize>> = HashMap::new();
+    for (i, todo) in todos.iter().enumerate() {
+        by_file.entry(todo.file_path.clone()).or_default().push(i);
+    }
+    for (file, indices) in by_file {
+        let mut args = vec!["blame".to_string(), "--line-porcelain".to_string()];
+        for &i in &indices {
+            args.push(format!("-L{0},{0}", todos[i].line));
+        }
+        args.push("--".to_string());
+        args.push(file.to_string_lossy().into_owned());
+        let Ok(output) = std::process::Command::new("git")
+            .args(&args)
+            .current_dir(repo_root)
+            .output()
+        else {
+            return; // git unavailable
+        };
+        if !output.status.success() {
+            continue;
+        }
+        let blamed = parse_blame(&String::from_utf8_lossy(&output.stdout));
+        for i in indices {
+            if let Some((author, time)) = blamed.get(&todos[i].line) {
+                todos[i].author = Some(author.clone());
+                todos[i].age_days = Some(now.saturating_sub(*time) / 86_400);
+            }
+        }
+    }
+}
+
+/// Author and author time (Unix seconds) per final line number from
+/// `git blame --line-porcelain` output, skipping uncommitted lines.
+fn parse_blame(output: &str) -> HashMap<u32, (String, u64)> {
+    let mut blamed = HashMap::new();
+    // (final line, committed, author, author time) of the entry being read.
+    let mut entry: Option<(u32, bool, String, u64)> = None;
+    for line in output.lines() {
+        if line.starts_with('\t') {
+            // The line's content ends its entry.
+            if let Some((final_line, true, author, time)) = entry.take() {
+                blamed.insert(final_line, (author, time));
+            }
+        } else if let Some(current) = entry.as_mut() {
+            if let Some(author) = line.strip_prefix("author ") {
+                current.2 = author.to_string();
+            } else if let Some(time) = line.strip_prefix("author-time ") {
+                current.3 = time.parse().unwrap_or(0);
+            }
+        } else {
+            // Header: `<sha> <orig line> <final line> [<group size>]`.
+            let mut fields = line.split(' ');
+            let (Some(sha), Some(final_line)) = (fields.next(), fields.nth(1)) else {
+                continue;
+            };
+            if let Ok(final_line) = final_line.parse() {
+                let committed = sha.bytes().any(|b| b != b'0');
+                entry = Some((final_line, committed, String::new(), 0));
+            }
+        }
+    }
+    blamed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extracts_markers_in_comments() {
+        let source = "\
+fn parse() {
+    // TODO: retry on timeout
+    let x = 1; // FIXME(alice) drops the sign
+}
+/// HACK - works around upstream bug
+fn bounds() {}
+/* TODO tighten bounds */
+/*
+ * FIXME: continuation line
+ */
+fn strings() {
+    let todo_list = \"TODO: not a comment\";
+    let url = \"http://example.com/#HACK\";
+}
+// TODOS are not markers, nor is MYTODO
+";
+        let todos = extract(Path::new("src/lib.rs"), source, Language::Rust);
+        let found: Vec<(u32, &str, Option<&str>, &str)> = todos
+            .iter()
+            .map(|t| {
+                (
+                    t.line,
+                    t.tag.as_str(),
+                    t.assignee.as_deref(),
+                    t.text.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (2, "TODO", None, "retry on timeout"),
+                (3, "FIXME", Some("alice"), "drops the sign"),
+                (5, "HACK", None, "works around upstream bug"),
+                (7, "TODO", None, "tighten bounds"),
+                (9, "FIXME", None, "continuation line"),
+            ]
+        );
+        assert_eq!(todos[0].file_path, Path::new("src/lib.rs"));
+    }
+
+    #[test]
+    fn test_extracts_markers_in_python_comments_only() {
+        let source = "\
+x = raw  # HACK - works around upstream bug
+label = \"# TODO: not a comment\"
+";
+        let todos = extract(Path::new("fix.py"), source, Language::Python);
+        assert_eq!(todos.len(), 1);
+        assert_eq!((todos[0].line, todos[0].tag.as_str()), (1, "HACK"));
+        assert!(extract(Path::new("notes.txt"), source, Language::Unknown).is_empty());
+    }
+
+    #[test]
+    fn test_parse_blame_porcelain() {
+        let output = "\
+1f0e3c2a9b8d7e6f5a4b3c2d1e0f9a8b7c6d5e4f 10 12 1
+author Alice
+author-mail <alice@example.com>
+author-time 1700000000
+author-tz +0000
+summary add retry
+filename src/net.rs
+\t    // TODO: retry on timeout
+0000000000000000000000000000000000000000 20 30 1
+author Not Committed Yet
+author-time 1800000000
+filename src/net.rs
+\t    // FIXME: new
+";
+        let blamed = parse_blame(output);
+        assert_eq!(blamed.len(), 1);
+        assert_eq!(blamed[&12], ("Alice".to_string(), 1_700_000_000));
+    }
+}
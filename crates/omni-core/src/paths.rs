@@ -0,0 +1,179 @@
+//! Path normalization shared by the watcher, index, and search.
+//!
+//! On Windows one file reaches the engine under several spellings:
+//! `std::fs::canonicalize` returns verbatim paths (`\\?\C:\repo`,
+//! `\\?\UNC\server\share\repo`), the watcher reports `C:\repo\src\Main.rs`,
+//! and editors send `c:/repo/src/main.rs`. NTFS treats them as one file, so
+//! the engine must too: verbatim prefixes are stripped, both separators are
+//! accepted, and components compare case-insensitively.
+//!
+//! Elsewhere only the verbatim stripping applies (it never matches) and
+//! comparisons stay byte-exact.
+
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+
+/// Whether path comparisons ignore case on this platform.
+pub const CASE_INSENSITIVE: bool = cfg!(windows);
+
+/// `path` without a Windows verbatim prefix: `\\?\C:\x` becomes `C:\x` and
+/// `\\?\UNC\server\share` becomes `\\server\share`.
+pub fn simplify(path: &Path) -> Cow<'_, Path> {
+    match simplify_str(&path.to_string_lossy()) {
+        Some(simplified) => Cow::Owned(PathBuf::from(simplified)),
+        None => Cow::Borrowed(path),
+    }
+}
+
+/// Owned form of [`simplify`] that reuses `path` when nothing changes.
+pub fn into_simplified(path: PathBuf) -> PathBuf {
+    match simplify_str(&path.to_string_lossy()) {
+        Some(simplified) => PathBuf::from(simplified),
+        None => path,
+    }
+}
+
+/// [`std::fs::canonicalize`] without the verbatim prefix Windows adds, so
+/// the result compares equal to paths from the watcher and from editors.
+pub fn canonicalize(path: &Path) -> std::io::Result<PathBuf> {
+    let canonical = std::fs::canonicalize(path)?;
+    Ok(into_simplified(canonical))
+}
+
+/// The part of `path` below `root`, or `None` when `path` is not under it.
+///
+/// Tolerates verbatim prefixes, mixed separators, and (on Windows)
+/// differences in case. `root` itself yields an empty path.
+pub fn strip_root(path: &Path, root: &Path) -> Option<PathBuf> {
+    // Fast path: the common case of identical spelling.
+    if let Ok(rel) = path.strip_prefix(root) {
+        return Some(rel.to_path_buf());
+    }
+    strip_root_str(
+        &path.to_string_lossy(),
+        &root.to_string_lossy(),
+        CASE_INSENSITIVE,
+    )
+    .map(PathBuf::from)
+}
+
+/// `path` relative to `root` when it is under it, else `path` unchanged.
+pub fn relative_to<'a>(path: &'a Path, root: &Path) -> Cow<'a, Path> {
+    match path.strip_prefix(root) {
+        Ok(rel) => Cow::Borrowed(rel),
+        Err(_) => strip_root(path, root).map_or(Cow::Borrowed(path), Cow::Owned),
+    }
+}
+
+/// How `path` is written to the index: verbatim prefix stripped and, on
+/// Windows, `\` separators throughout.
+pub fn index_form(path: &Path) -> String {
+    let simplified = simplify(path);
+    let text = simplified.to_string_lossy();
+    if cfg!(windows) {
+        text.replace('/', "\\")
+    } else {
+        text.into_owned()
+    }
+}
+
+fn simplify_str(path: &str) -> Option<String> {
+    if let Some(unc) = path.strip_prefix(r"\\?\UNC\") {
+        Some(format!(r"\\{unc}"))
+    } else {
+        path.strip_prefix(r"\\?\").map(str::to_string)
+    }
+}
+
+/// Components of `path` split on either separator. A leading separator is
+/// kept as an empty first component so absolute and relative paths differ.
+fn components(path: &str) -> Vec<&str> {
+    let mut parts = path.split(['/', '\\']);
+    let first = parts.next().unwrap_or_default();
+    std::iter::once(first)
+        .chain(parts.filter(|p| !p.is_empty() && *p != "."))
+        .collect()
+}
+
+fn strip_root_str(path: &str, root: &str, case_insensitive: bool) -> Option<String> {
+    let path = simplify_str(path).unwrap_or_else(|| path.to_string());
+    let root = simplify_str(root).unwrap_or_else(|| root.to_string());
+    let path_parts = components(&path);
+    let root_parts: Vec<&str> = components(&root);
+    if root_parts.len() > path_parts.len() {
+        return None;
+    }
+    let same = |a: &str, b: &str| {
+        if case_insensitive {
+            a.to_lowercase() == b.to_lowercase()
+        } else {
+            a == b
+        }
+    };
+    if !root_parts.iter().zip(&path_parts).all(|(r, p)| same(r, p)) {
+        return None;
+    }
+    let separator = if path.contains('\\') { "\\" } else { "/" };
+    Some(path_parts[root_parts.len()..].join(separator))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_simplify_strips_verbatim_prefixes() {
+        assert_eq!(
+            simplify_str(r"\\?\C:\repo\src").as_deref(),
+            Some(r"C:\repo\src")
+        );
+        assert_eq!(
+            simplify_str(r"\\?\UNC\server\share\repo").as_deref(),
+            Some(r"\\server\share\repo")
+        );
+        assert_eq!(simplify_str("/home/dev/repo"), None);
+    }
+
+    #[test]
+    fn test_strip_root_matches_windows_spellings() {
+        let root = r"\\?\C:\Users\Dev\Repo";
+        assert_eq!(
+            strip_root_str(r"c:/users/dev/repo/src/Main.rs", root, true).as_deref(),
+            Some("src/Main.rs")
+        );
+        assert_eq!(
+            strip_root_str(r"C:\Users\Dev\Repo\src\lib.rs", root, true).as_deref(),
+            Some(r"src\lib.rs")
+        );
+        assert_eq!(
+            strip_root_str(r"\\?\UNC\nas\code\repo\a.rs", r"\\nas\code\repo", true).as_deref(),
+            Some(r"a.rs")
+        );
+        assert_eq!(strip_root_str(r"C:\Users\Dev\Repo2\a.rs", root, true), None);
+        assert_eq!(
+            strip_root_str(r"c:\users\dev\repo\a.rs", r"C:\Users\Dev\Repo", false),
+            None,
+            "case matters where the filesystem says so"
+        );
+    }
+
+    #[test]
+    fn test_strip_root_on_unix_paths() {
+        assert_eq!(
+            strip_root(Path::new("/srv/repo/src/a.rs"), Path::new("/srv/repo/")),
+            Some(PathBuf::from("src/a.rs"))
+        );
+        assert_eq!(
+            strip_root(Path::new("/srv/repo"), Path::new("/srv/repo")),
+            Some(PathBuf::new())
+        );
+        assert_eq!(
+            strip_root(Path::new("srv/repo/a.rs"), Path::new("/srv/repo")),
+            None
+        );
+        assert_eq!(
+            relative_to(Path::new("/elsewhere/a.rs"), Path::new("/srv/repo")),
+            Path::new("/elsewhere/a.rs")
+        );
+    }
+}
@@ -3,6 +3,15 @@
 //! Detects recurring code patterns across the codebase:
 //! error handling conventions, logging patterns, authentication flows,
 //! naming conventions, etc.
+//!
+//! [`cluster_codebase`] groups chunk embeddings into topics for a quick map of
+//! an unfamiliar repository.
+//!
+//! [`check_layers`] checks the file-level dependency graph against the
+//! `[architecture]` layering rules and reports each violating symbol edge.
+//!
+//! [`detect_header_templates`] finds license headers and codegen banners
+//! repeated across files, which indexing then leaves out of chunk content.
 #![allow(
     clippy::cast_possible_truncation,
     clippy::cast_precision_loss,
@@ -12,8 +21,19 @@
     clippy::uninlined_format_args
 )]
 
-use crate::error::OmniResult;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use globset::GlobSet;
+
+use crate::config::ArchitectureConfig;
+use crate::embedder::boilerplate;
+use crate::error::{OmniError, OmniResult};
+use crate::graph::modules::ModuleGraph;
 use crate::index::MetadataIndex;
+use crate::types::DependencyKind;
+use crate::vector::VectorIndex;
 
 /// A detected code pattern / convention.
 #[derive(Debug, Clone, serde::Serialize)]
@@ -81,13 +101,13 @@ impl PatternEngine {
 
         // Check for Result/? pattern (Rust)
         let result_count: i64 = conn.query_row(
-            "SELECT COUNT(*) FROM chunks WHERE content LIKE '%-> Result<%' OR content LIKE '%-> OmniResult<%'",
+            "SELECT COUNT(*) FROM chunk_texts WHERE content LIKE '%-> Result<%' OR content LIKE '%-> OmniResult<%'",
             [],
             |row| row.get(0),
         )?;
 
         let unwrap_count: i64 = conn.query_row(
-            "SELECT COUNT(*) FROM chunks WHERE content LIKE '%.unwrap()%'",
+            "SELECT COUNT(*) FROM chunk_texts WHERE content LIKE '%.unwrap()%'",
             [],
             |row| row.get(0),
         )?;
@@ -140,13 +160,13 @@ impl PatternEngine {
         let conn = index.connection();
 
         let tracing_count: i64 = conn.query_row(
-            "SELECT COUNT(*) FROM chunks WHERE content LIKE '%tracing::%'",
+            "SELECT COUNT(*) FROM chunk_texts WHERE content LIKE '%tracing::%'",
             [],
             |row| row.get(0),
         )?;
 
         let println_count: i64 = conn.query_row(
-            "SELECT COUNT(*) FROM chunks WHERE content LIKE '%println!%' OR content LIKE '%print!%'",
+            "SELECT COUNT(*) FROM chunk_texts WHERE content LIKE '%println!%' OR content LIKE '%print!%'",
             [],
             |row| row.get(0),
         )?;
@@ -289,6 +309,506 @@ impl PatternEngine {
     }
 }
 
+// ---------------------------------------------------------------------------
+// Topic clustering
+// ---------------------------------------------------------------------------
+
+/// Label terms per topic.
+const TOPIC_LABEL_TERMS: usize = 3;
+/// Representative symbols per topic.
+const TOPIC_REPRESENTATIVES: usize = 5;
+/// Top directories listed per topic.
+const TOPIC_MODULES: usize = 3;
+
+/// A group of semantically related chunks found by [`cluster_codebase`].
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Topic {
+    /// Identifier terms most distinctive of the topic, best first.
+    pub label: Vec<String>,
+    /// Number of chunks in the topic.
+    pub size: usize,
+    /// Directories holding most of the topic's chunks, with chunk counts.
+    pub modules: Vec<(String, usize)>,
+    /// Symbols closest to the topic centroid.
+    pub representatives: Vec<String>,
+}
+
+/// Cluster chunk embeddings into at most `k` topics.
+///
+/// Runs k-means over every embedded chunk, then labels each cluster with the
+/// identifier terms that are frequent in it but rare in other clusters.
+/// Topics are returned largest first; empty clusters are dropped. Returns an
+/// empty map when nothing has been embedded.
+pub fn cluster_codebase(
+    index: &MetadataIndex,
+    vectors: &VectorIndex,
+    k: usize,
+) -> OmniResult<Vec<Topic>> {
+    let conn = index.connection();
+    let mut stmt = conn.prepare(
+        "SELECT c.vector_id, c.symbol_path, f.path FROM chunks c
+         JOIN files f ON f.id = c.file_id
+         WHERE c.vector_id IS NOT NULL",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok((
+            row.get::<_, i64>(0)? as u64,
+            row.get::<_, String>(1)?,
+            row.get::<_, String>(2)?,
+        ))
+    })?;
+    let mut chunks: HashMap<u64, (String, String)> = HashMap::new();
+    for row in rows {
+        let (vector_id, symbol_path, path) = row?;
+        chunks.insert(vector_id, (symbol_path, path));
+    }
+
+    // Sorted by id so the k-means seed, and thus the map, is stable.
+    let mut points: Vec<(u64, &[f32])> = vectors
+        .iter()
+        .filter(|(id, _)| chunks.contains_key(id))
+        .collect();
+    points.sort_unstable_by_key(|(id, _)| *id);
+    let k = k.min(points.len());
+    if k == 0 {
+        return Ok(Vec::new());
+    }
+
+    let slices: Vec<&[f32]> = points.iter().map(|(_, v)| *v).collect();
+    let centroids = crate::vector::kmeans(&slices, k, vectors.dimensions());
+
+    // (similarity to centroid, vector id) per cluster
+    let mut members: Vec<Vec<(f32, u64)>> = vec![Vec::new(); k];
+    for (id, vec) in &points {
+        let (cluster, sim) = crate::vector::nearest_centroid(vec, &centroids);
+        members[cluster].push((sim, *id));
+    }
+    members.retain(|m| !m.is_empty());
+
+    // Term frequency per cluster, and how many clusters use each term.
+    let term_counts: Vec<HashMap<String, usize>> = members
+        .iter()
+        .map(|m| {
+            let mut counts = HashMap::new();
+            for (_, id) in m {
+                for term in identifier_terms(&chunks[id].0) {
+                    *counts.entry(term).or_insert(0) += 1;
+                }
+            }
+            counts
+        })
+        .collect();
+    let mut cluster_freq: HashMap<&str, usize> = HashMap::new();
+    for counts in &term_counts {
+        for term in counts.keys() {
+            *cluster_freq.entry(term.as_str()).or_insert(0) += 1;
+        }
+    }
+
+    let n_clusters = members.len() as f64;
+    let mut topics: Vec<Topic> = members
+        .iter_mut()
+        .zip(&term_counts)
+        .map(|(m, counts)| {
+            let mut scored: Vec<(&String, f64)> = counts
+                .iter()
+                .map(|(term, &tf)| {
+                    let idf = (n_clusters / cluster_freq[term.as_str()] as f64).ln() + 1.0;
+                    (term, tf as f64 * idf)
+                })
+                .collect();
+            scored.sort_by(|a, b| {
+                b.1.partial_cmp(&a.1)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+                    .then_with(|| a.0.cmp(b.0))
+            });
+
+            let mut dirs: HashMap<String, usize> = HashMap::new();
+            for (_, id) in m.iter() {
+                let dir = std::path::Path::new(&chunks[id].1)
+                    .parent()
+                    .map(|p| p.to_string_lossy().replace('\\', "/"))
+                    .filter(|p| !p.is_empty())
+                    .unwrap_or_else(|| ".".to_string());
+                *dirs.entry(dir).or_insert(0) += 1;
+            }
+            let mut modules: Vec<(String, usize)> = dirs.into_iter().collect();
+            modules.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+            modules.truncate(TOPIC_MODULES);
+
+            m.sort_by(|a, b| {
+                b.0.partial_cmp(&a.0)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+                    .then_with(|| a.1.cmp(&b.1))
+            });
+
+            Topic {
+                label: scored
+                    .into_iter()
+                    .take(TOPIC_LABEL_TERMS)
+                    .map(|(term, _)| term.clone())
+                    .collect(),
+                size: m.len(),
+                modules,
+                representatives: m
+                    .iter()
+                    .take(TOPIC_REPRESENTATIVES)
+                    .map(|(_, id)| chunks[id].0.clone())
+                    .collect(),
+            }
+        })
+        .collect();
+
+    topics.sort_by_key(|t| std::cmp::Reverse(t.size));
+    Ok(topics)
+}
+
+// ---------------------------------------------------------------------------
+// Architecture layering rules
+// ---------------------------------------------------------------------------
+
+/// A dependency that breaks an `[architecture]` layering rule.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct LayerViolation {
+    /// Layer of the depending file.
+    pub from_layer: String,
+    /// Forbidden layer of the file depended upon.
+    pub to_layer: String,
+    /// Depending file, relative to the repository root.
+    pub path: String,
+    /// Definition line of the depending symbol.
+    pub line: u32,
+    /// Depending symbol.
+    pub symbol: String,
+    /// File depended upon.
+    pub target_path: String,
+    /// Symbol depended upon.
+    pub target: String,
+    /// How `symbol` depends on `target`.
+    pub kind: DependencyKind,
+    /// The rule's `reason`, if it gave one.
+    pub reason: Option<String>,
+}
+
+/// Check indexed dependencies against the configured layering rules.
+///
+/// File pairs are taken from the aggregated [`ModuleGraph`]; every symbol
+/// edge behind a forbidden pair is reported, ordered by path and line. A
+/// pattern that fails to compile, or a rule naming an undeclared layer, is a
+/// configuration error so a typo can't silently pass CI.
+pub fn check_layers(
+    config: &ArchitectureConfig,
+    index: &MetadataIndex,
+) -> OmniResult<Vec<LayerViolation>> {
+    if config.rules.is_empty() {
+        return Ok(Vec::new());
+    }
+    let layers = compile_layers(config)?;
+    for rule in &config.rules {
+        for name in std::iter::once(&rule.from).chain(&rule.must_not_depend_on) {
+            if !config.layers.contains_key(name) {
+                return Err(OmniError::Config {
+                    details: format!("[architecture] rule refers to unknown layer {name:?}"),
+                });
+            }
+        }
+    }
+    let layers_of = |path: &str| -> Vec<&str> {
+        layers
+            .iter()
+            .filter(|(_, sets)| sets.iter().any(|set| set.is_match(path)))
+            .map(|(name, _)| name.as_str())
+            .collect()
+    };
+
+    let mut violations = Vec::new();
+    for edge in ModuleGraph::build(index)?.files {
+        let (sources, targets) = (layers_of(&edge.source), layers_of(&edge.target));
+        for rule in config
+            .rules
+            .iter()
+            .filter(|r| sources.contains(&r.from.as_str()))
+        {
+            for denied in rule
+                .must_not_depend_on
+                .iter()
+                .filter(|d| targets.contains(&d.as_str()) && *d != &rule.from)
+            {
+                let sites = index
+                    .dependencies_between_files(Path::new(&edge.source), Path::new(&edge.target))?;
+                violations.extend(sites.into_iter().map(|(symbol, line, target, kind)| {
+                    LayerViolation {
+                        from_layer: rule.from.clone(),
+                        to_layer: denied.clone(),
+                        path: edge.source.clone(),
+                        line,
+                        symbol,
+                        target_path: edge.target.clone(),
+                        target,
+                        kind,
+                        reason: rule.reason.clone(),
+                    }
+                }));
+            }
+        }
+    }
+    violations.sort_by(|a, b| {
+        (&a.path, a.line, &a.target, &a.to_layer).cmp(&(&b.path, b.line, &b.target, &b.to_layer))
+    });
+    violations.dedup();
+    Ok(violations)
+}
+
+fn compile_layers(config: &ArchitectureConfig) -> OmniResult<Vec<(String, Vec<GlobSet>)>> {
+    config
+        .layers
+        .iter()
+        .map(|(name, patterns)| {
+            let sets = patterns
+                .iter()
+                .map(|p| {
+                    crate::owners::compile_pattern(p).ok_or_else(|| OmniError::Config {
+                        details: format!(
+                            "invalid [architecture] pattern for layer {name:?}: {p:?}"
+                        ),
+                    })
+                })
+                .collect::<OmniResult<_>>()?;
+            Ok((name.clone(), sets))
+        })
+        .collect()
+}
+
+// ---------------------------------------------------------------------------
+// Repeated file headers
+// ---------------------------------------------------------------------------
+
+/// Files that must open with the same comment block for it to be a template.
+const MIN_HEADER_FILES: usize = 3;
+/// Lines a repeated block needs when it mentions no license or generator.
+const MIN_HEADER_LINES: usize = 3;
+/// Example paths kept per template.
+const HEADER_EXAMPLES: usize = 3;
+
+/// A comment block that opens many files: a license header or a codegen
+/// banner, found by [`detect_header_templates`].
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct HeaderTemplate {
+    /// The block as it opens the first of `examples`.
+    pub text: String,
+    /// Number of lines in the block.
+    pub lines: usize,
+    /// Number of files opening with the block, years and other numbers
+    /// aside.
+    pub file_count: usize,
+    /// First few of those files, by path.
+    pub examples: Vec<String>,
+}
+
+/// Find the comment blocks that open at least [`MIN_HEADER_FILES`] of
+/// `files`, given as `(path, language, content)`.
+///
+/// Only comment blocks before a file's first line of code count, and blocks
+/// are compared line by line with whitespace trimmed and digit runs
+/// collapsed, so `Copyright 2019` and `Copyright 2024` headers match. A
+/// block of fewer than [`MIN_HEADER_LINES`] lines only counts when it
+/// mentions a license or a code generator, so a shared one-line
+/// `# -*- coding: utf-8 -*-` is left alone. Most common first.
+pub fn detect_header_templates<'a>(
+    files: impl IntoIterator<Item = (&'a str, crate::types::Language, &'a str)>,
+) -> Vec<HeaderTemplate> {
+    let mut files: Vec<_> = files.into_iter().collect();
+    files.sort_unstable_by_key(|(path, _, _)| *path);
+
+    let mut seen: HashMap<String, HeaderTemplate> = HashMap::new();
+    for (path, language, content) in files {
+        let lines: Vec<&str> = content.lines().collect();
+        let mut in_file = HashSet::new();
+        for (start, end) in leading_blocks(&lines, language) {
+            let block = &lines[start..end];
+            let key = header_key(block);
+            if !in_file.insert(key.clone()) {
+                continue;
+            }
+            let template = seen.entry(key).or_insert_with(|| HeaderTemplate {
+                text: block.join("\n"),
+                lines: block.len(),
+                file_count: 0,
+                examples: Vec::new(),
+            });
+            template.file_count += 1;
+            if template.examples.len() < HEADER_EXAMPLES {
+                template.examples.push(path.to_string());
+            }
+        }
+    }
+
+    let mut templates: Vec<HeaderTemplate> = seen
+        .into_values()
+        .filter(|t| {
+            t.file_count >= MIN_HEADER_FILES
+                && (t.lines >= MIN_HEADER_LINES
+                    || boilerplate::is_boilerplate(&t.text.lines().collect::<Vec<_>>()))
+        })
+        .collect();
+    templates.sort_by(|a, b| {
+        b.file_count
+            .cmp(&a.file_count)
+            .then_with(|| a.text.cmp(&b.text))
+    });
+    templates
+}
+
+/// Detected header templates, ready to strip from file content.
+#[derive(Debug, Clone, Default)]
+pub struct HeaderTemplates {
+    templates: Vec<HeaderTemplate>,
+    keys: HashSet<String>,
+}
+
+impl HeaderTemplates {
+    /// Index `templates` for matching.
+    pub fn new(templates: Vec<HeaderTemplate>) -> Self {
+        let keys = templates.iter().map(template_key).collect();
+        Self { templates, keys }
+    }
+
+    /// The templates, most common first.
+    pub fn templates(&self) -> &[HeaderTemplate] {
+        &self.templates
+    }
+
+    /// Add `detected` templates. A known template keeps whichever entry
+    /// counts more files: an incremental run sees only the changed ones.
+    pub fn merge(&mut self, detected: Vec<HeaderTemplate>) {
+        let mut templates = std::mem::take(&mut self.templates);
+        for template in detected {
+            let key = template_key(&template);
+            match templates.iter_mut().find(|t| template_key(t) == key) {
+                Some(known) if known.file_count >= template.file_count => {}
+                Some(known) => *known = template,
+                None => templates.push(template),
+            }
+        }
+        templates.sort_by(|a, b| {
+            b.file_count
+                .cmp(&a.file_count)
+                .then_with(|| a.text.cmp(&b.text))
+        });
+        *self = Self::new(templates);
+    }
+
+    /// `content` with the lines of every leading comment block matching a
+    /// template blanked out. Line breaks are kept, so line numbers do not
+    /// move.
+    pub fn strip<'a>(&self, content: &'a str, language: crate::types::Language) -> Cow<'a, str> {
+        if self.keys.is_empty() {
+            return Cow::Borrowed(content);
+        }
+        let lines: Vec<&str> = content.lines().collect();
+        let mut blank = vec![false; lines.len()];
+        let mut any = false;
+        for (start, end) in leading_blocks(&lines, language) {
+            if self.keys.contains(&header_key(&lines[start..end])) {
+                blank[start..end].fill(true);
+                any = true;
+            }
+        }
+        if !any {
+            return Cow::Borrowed(content);
+        }
+
+        let mut out = String::with_capacity(content.len());
+        for (i, line) in content.split_inclusive('\n').enumerate() {
+            if blank.get(i).copied().unwrap_or(false) {
+                let body = line.trim_end_matches(['\r', '\n']);
+                out.push_str(&line[body.len()..]);
+            } else {
+                out.push_str(line);
+            }
+        }
+        Cow::Owned(out)
+    }
+}
+
+/// Line ranges of the comment blocks before the first line of code in
+/// `lines`. A `#!/` line at the top is skipped; a block comment that shares
+/// its closing line with code ends the search.
+fn leading_blocks(lines: &[&str], language: crate::types::Language) -> Vec<(usize, usize)> {
+    let syntax = boilerplate::syntax(language);
+    let mut blocks = Vec::new();
+    let mut next = 0;
+    for (start, end) in boilerplate::comment_blocks(lines, &syntax) {
+        let only_blank = lines[next..start]
+            .iter()
+            .enumerate()
+            .all(|(i, line)| line.trim().is_empty() || (next + i == 0 && line.starts_with("#!/")));
+        let last = lines[end - 1].trim();
+        let closed = syntax.line.iter().any(|p| last.starts_with(p))
+            || syntax.block.iter().any(|(_, close)| last.ends_with(close));
+        if !only_blank || !closed {
+            break;
+        }
+        blocks.push((start, end));
+        next = end;
+    }
+    blocks
+}
+
+fn template_key(template: &HeaderTemplate) -> String {
+    header_key(&template.text.lines().collect::<Vec<_>>())
+}
+
+/// Matching key of a header block: trimmed lines with digit runs collapsed
+/// to `0`.
+fn header_key(block: &[&str]) -> String {
+    let mut key = String::new();
+    for line in block {
+        let mut digits = false;
+        for c in line.trim().chars() {
+            if c.is_ascii_digit() {
+                if !digits {
+                    key.push('0');
+                }
+                digits = true;
+            } else {
+                key.push(c);
+                digits = false;
+            }
+        }
+        key.push('\n');
+    }
+    key
+}
+
+/// Lowercased words of a symbol path's final segment: `auth::TokenStore`
+/// gives `token`, `store`. Words shorter than 3 characters are dropped.
+fn identifier_terms(symbol_path: &str) -> Vec<String> {
+    let name = symbol_path
+        .rsplit(|c| c == ':' || c == '.')
+        .next()
+        .unwrap_or(symbol_path);
+    let mut terms = Vec::new();
+    let mut current = String::new();
+    let mut prev_lower = false;
+    for c in name.chars() {
+        if !c.is_alphanumeric() || (c.is_uppercase() && prev_lower) {
+            if current.len() >= 3 {
+                terms.push(std::mem::take(&mut current));
+            }
+            current.clear();
+        }
+        if c.is_alphanumeric() {
+            current.extend(c.to_lowercase());
+        }
+        prev_lower = c.is_lowercase() || c.is_ascii_digit();
+    }
+    if current.len() >= 3 {
+        terms.push(current);
+    }
+    terms
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -301,4 +821,207 @@ mod tests {
         let json = serde_json::to_string(&PatternCategory::NamingConvention).expect("serialize");
         assert_eq!(json, "\"naming_convention\"");
     }
+
+    #[test]
+    fn test_detect_and_strip_header_templates() {
+        use crate::types::Language;
+
+        let license = |year: u32| {
+            format!(
+                "// Copyright {year} Example Corp.\n//\n// Licensed under the Apache License 2.0.\n\n"
+            )
+        };
+        let a = format!("{}fn a() {{}}\n", license(2019));
+        let b = format!("{}fn b() {{}}\n", license(2024));
+        let c = format!("{}// Package-level note.\nfn c() {{}}\n", license(2021));
+        let banner = "#!/usr/bin/env python\n# Code generated by protoc. DO NOT EDIT.\nx = 1\n";
+        let files = [
+            ("src/b.rs", Language::Rust, b.as_str()),
+            ("src/a.rs", Language::Rust, a.as_str()),
+            ("src/c.rs", Language::Rust, c.as_str()),
+            ("gen/x_pb2.py", Language::Python, banner),
+            ("gen/y_pb2.py", Language::Python, banner),
+            // Not at the top of the file: not a header.
+            (
+                "src/d.rs",
+                Language::Rust,
+                "fn d() {}\n// Licensed under MIT.\n",
+            ),
+        ];
+
+        let templates = detect_header_templates(files.iter().copied());
+        assert_eq!(templates.len(), 1, "{templates:?}");
+        assert_eq!(templates[0].file_count, 3);
+        assert_eq!(templates[0].lines, 3);
+        assert_eq!(
+            templates[0].examples,
+            vec!["src/a.rs", "src/b.rs", "src/c.rs"]
+        );
+        assert!(templates[0].text.starts_with("// Copyright 2019"));
+
+        let mut headers = HeaderTemplates::new(templates);
+        let stripped = headers.strip(&c, Language::Rust);
+        assert_eq!(stripped, "\n\n\n\n// Package-level note.\nfn c() {}\n");
+        assert!(matches!(
+            headers.strip("fn e() {}\n", Language::Rust),
+            Cow::Borrowed(_)
+        ));
+
+        // Three more generated files bring the banner over the threshold.
+        headers.merge(detect_header_templates(
+            ["gen/x_pb2.py", "gen/y_pb2.py", "gen/z_pb2.py"]
+                .map(|path| (path, Language::Python, banner)),
+        ));
+        assert_eq!(headers.templates().len(), 2);
+        assert_eq!(
+            headers.strip(banner, Language::Python),
+            "#!/usr/bin/env python\n\nx = 1\n"
+        );
+    }
+
+    #[test]
+    fn test_check_layers_reports_violating_edges() {
+        use crate::types::{ChunkKind, DependencyEdge, FileInfo, Language, Symbol};
+
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let index = MetadataIndex::open(&dir.path().join("index.db")).expect("open db");
+        let symbol = |path: &str, name: &str, line: u32| {
+            let file_id = index
+                .upsert_file(&FileInfo {
+                    id: 0,
+                    path: path.into(),
+                    language: Language::Python,
+                    content_hash: path.to_string(),
+                    size_bytes: 1,
+                })
+                .expect("upsert file");
+            index
+                .insert_symbol(&Symbol {
+                    id: 0,
+                    name: name.to_string(),
+                    fqn: format!("{path}.{name}"),
+                    kind: ChunkKind::Function,
+                    file_id,
+                    line,
+                    chunk_id: None,
+                })
+                .expect("insert symbol")
+        };
+        let render = symbol("src/ui/view.py", "render", 12);
+        let save = symbol("src/db/store.py", "save", 3);
+        let handle = symbol("src/service/orders.py", "handle", 7);
+        for (source_id, target_id) in [(render, save), (render, handle), (handle, save)] {
+            index
+                .insert_dependency(&DependencyEdge {
+                    source_id,
+                    target_id,
+                    kind: DependencyKind::Calls,
+                    confidence: DependencyEdge::EXACT,
+                })
+                .expect("insert dependency");
+        }
+
+        let config: ArchitectureConfig = toml::from_str(
+            r#"
+[layers]
+ui = ["src/ui/"]
+service = ["src/service/"]
+db = ["src/db/"]
+
+[[rules]]
+from = "ui"
+must_not_depend_on = ["db"]
+reason = "go through the service layer"
+"#,
+        )
+        .expect("parse config");
+
+        let violations = check_layers(&config, &index).expect("check");
+        assert_eq!(violations.len(), 1);
+        let v = &violations[0];
+        assert_eq!((v.path.as_str(), v.line), ("src/ui/view.py", 12));
+        assert_eq!((v.from_layer.as_str(), v.to_layer.as_str()), ("ui", "db"));
+        assert_eq!(v.target, "src/db/store.py.save");
+        assert_eq!(v.reason.as_deref(), Some("go through the service layer"));
+
+        let mut bad = config.clone();
+        bad.rules[0].must_not_depend_on = vec!["storage".to_string()];
+        assert!(check_layers(&bad, &index).is_err());
+    }
+
+    #[test]
+    fn test_identifier_terms() {
+        assert_eq!(identifier_terms("auth::TokenStore"), vec!["token", "store"]);
+        assert_eq!(identifier_terms("db.open_pool_v2"), vec!["open", "pool"]);
+        assert!(identifier_terms("a.go").is_empty());
+    }
+
+    #[test]
+    fn test_cluster_codebase_groups_by_embedding() {
+        use crate::types::{Chunk, ChunkKind, ComplexityMetrics, FileInfo, Language, Visibility};
+
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let index = MetadataIndex::open(&dir.path().join("index.db")).expect("open db");
+        let mut vectors = VectorIndex::in_memory(2);
+        let symbols = [
+            ("src/auth/token.rs", "auth::parse_token", [1.0, 0.05]),
+            ("src/auth/token.rs", "auth::refresh_token", [1.0, 0.0]),
+            ("src/auth/login.rs", "auth::validate_token", [0.95, 0.1]),
+            ("src/db/pool.rs", "db::open_pool", [0.0, 1.0]),
+            ("src/db/pool.rs", "db::close_pool", [0.1, 1.0]),
+        ];
+        let mut file_ids = HashMap::new();
+        for (i, (path, symbol, vec)) in symbols.iter().enumerate() {
+            let file_id = *file_ids.entry(*path).or_insert_with(|| {
+                index
+                    .upsert_file(&FileInfo {
+                        id: 0,
+                        path: (*path).into(),
+                        language: Language::Rust,
+                        content_hash: (*path).to_string(),
+                        size_bytes: 1,
+                    })
+                    .expect("upsert file")
+            });
+            let chunk_id = index
+                .insert_chunk(&Chunk {
+                    id: 0,
+                    file_id,
+                    symbol_path: (*symbol).to_string(),
+                    kind: ChunkKind::Function,
+                    visibility: Visibility::Public,
+                    line_start: i as u32 + 1,
+                    line_end: i as u32 + 1,
+                    content: format!("fn f{i}() {{}}"),
+                    doc_comment: None,
+                    token_count: 4,
+                    weight: 1.0,
+                    vector_id: None,
+                    is_summary: false,
+                    is_generated: false,
+                    language: None,
+                    content_hash: 0,
+                    complexity: ComplexityMetrics::default(),
+                })
+                .expect("insert chunk");
+            index
+                .set_chunk_vector_id(chunk_id, chunk_id as u64)
+                .expect("set vector id");
+            let mut v = vec.to_vec();
+            crate::vector::l2_normalize(&mut v);
+            vectors.add(chunk_id as u64, &v).expect("add vector");
+        }
+
+        let topics = cluster_codebase(&index, &vectors, 2).expect("cluster");
+        assert_eq!(topics.len(), 2);
+        assert_eq!(topics[0].size, 3);
+        assert_eq!(topics[0].label.first().map(String::as_str), Some("token"));
+        assert_eq!(topics[0].modules[0], ("src/auth".to_string(), 3));
+        assert_eq!(topics[1].label.first().map(String::as_str), Some("pool"));
+        assert_eq!(topics[1].representatives.len(), 2);
+
+        assert!(cluster_codebase(&index, &VectorIndex::in_memory(2), 4)
+            .expect("cluster")
+            .is_empty());
+    }
 }
@@ -0,0 +1,772 @@
+//! Sandboxed WebAssembly plugins.
+//!
+//! Teams extend parsing and the pipeline by dropping `.wasm` modules into
+//! `<repo>/.omnicontext/plugins/`. A plugin can act as a language analyzer
+//! for extensions no built-in analyzer handles (DSLs, IDLs, config formats)
+//! and as a [`PipelineHook`], without native compilation or loading dylibs.
+//!
+//! ## Sandbox
+//!
+//! Modules run in the `wasmi` interpreter with an empty linker: a module
+//! that imports anything (WASI, host functions) is rejected at load, so a
+//! plugin cannot reach the filesystem, network, or clock. Every call gets a
+//! fresh instance with its own memory, an instruction budget
+//! ([`PluginsConfig::fuel_per_call`]) and a memory cap
+//! ([`PluginsConfig::max_memory_mb`]). A trapping call fails that one file
+//! or hook invocation; the rest of the pipeline carries on.
+//!
+//! ## ABI
+//!
+//! Data crosses the boundary as UTF-8 in linear memory. The host copies
+//! input into a buffer from `omni_alloc` and passes `(ptr, len)`; the plugin
+//! returns its output as an `i64` packing `ptr << 32 | len`. A hook that
+//! returns length 0 leaves its input unchanged.
+//!
+//! | Export             | Signature              | Input JSON                                 | Output                                   |
+//! |--------------------|------------------------|--------------------------------------------|------------------------------------------|
+//! | `memory`           | memory                 |                                            |                                          |
+//! | `omni_alloc`       | `(len) -> ptr`         |                                            |                                          |
+//! | `omni_manifest`    | `() -> packed`         |                                            | `{"name"?, "extensions": [..]}`          |
+//! | `omni_analyze`     | `(ptr, len) -> packed` | `{"path", "source"}`                       | `[{"name", "line_start", "line_end", ..}]` |
+//! | `omni_post_chunk`  | `(ptr, len) -> packed` | `{"path", "chunks": [{"symbol_path", "kind", "content", "weight"}]}` | `[{"content"?, "weight"?}]`, one per chunk |
+//! | `omni_pre_embed`   | `(ptr, len) -> packed` | `{"symbol_path", "kind", "text"}`          | replacement text (not JSON)              |
+//! | `omni_post_search` | `(ptr, len) -> packed` | `{"query", "results": [{"path", "symbol_path", "kind", "score"}]}` | result indices in the new order |
+//!
+//! Only `memory` and `omni_manifest` are required; `omni_alloc` is required
+//! once any other export is present. Analyzer elements may also carry
+//! `symbol_path`, `kind`, `visibility`, `doc_comment`, and `references`; the
+//! host fills `content` from the line range.
+//!
+//! Built-in analyzers always win: a plugin extension only takes effect for
+//! files the core would otherwise skip.
+
+#![allow(clippy::doc_markdown, clippy::missing_errors_doc)]
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use serde::Deserialize;
+use wasmi::{Linker, Module, Store, StoreLimits, StoreLimitsBuilder};
+
+use crate::config::PluginsConfig;
+use crate::error::{OmniError, OmniResult};
+use crate::hooks::PipelineHook;
+use crate::parser::StructuralElement;
+use crate::types::{Chunk, ChunkKind, ComplexityMetrics, Language, SearchResult, Visibility};
+
+/// Plugin directory, relative to the repository root.
+pub const PLUGINS_DIR: &str = ".omnicontext/plugins";
+
+const EXPORT_MEMORY: &str = "memory";
+const EXPORT_ALLOC: &str = "omni_alloc";
+const EXPORT_MANIFEST: &str = "omni_manifest";
+const EXPORT_ANALYZE: &str = "omni_analyze";
+const EXPORT_POST_CHUNK: &str = "omni_post_chunk";
+const EXPORT_PRE_EMBED: &str = "omni_pre_embed";
+const EXPORT_POST_SEARCH: &str = "omni_post_search";
+
+/// What `omni_manifest` returns.
+#[derive(Debug, Deserialize)]
+struct Manifest {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    extensions: Vec<String>,
+}
+
+/// One element returned by `omni_analyze`.
+#[derive(Debug, Deserialize)]
+struct PluginElement {
+    name: String,
+    #[serde(default)]
+    symbol_path: Option<String>,
+    #[serde(default)]
+    kind: Option<String>,
+    #[serde(default)]
+    visibility: Option<String>,
+    line_start: u32,
+    line_end: u32,
+    #[serde(default)]
+    doc_comment: Option<String>,
+    #[serde(default)]
+    references: Vec<String>,
+}
+
+/// Per-chunk patch returned by `omni_post_chunk`.
+#[derive(Debug, Default, Deserialize)]
+struct ChunkPatch {
+    #[serde(default)]
+    content: Option<String>,
+    #[serde(default)]
+    weight: Option<f64>,
+}
+
+/// A loaded, validated plugin module.
+pub struct WasmPlugin {
+    name: String,
+    path: PathBuf,
+    extensions: Vec<String>,
+    engine: wasmi::Engine,
+    module: Module,
+    fuel_per_call: u64,
+    max_memory_bytes: usize,
+    analyzes: bool,
+    /// Hook exports the module provides.
+    hooks: Vec<&'static str>,
+}
+
+impl std::fmt::Debug for WasmPlugin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WasmPlugin")
+            .field("name", &self.name)
+            .field("path", &self.path)
+            .field("extensions", &self.extensions)
+            .finish_non_exhaustive()
+    }
+}
+
+impl WasmPlugin {
+    /// Compile and validate the module at `path`, then read its manifest.
+    pub fn load(path: &Path, config: &PluginsConfig) -> OmniResult<Self> {
+        let stem = path.file_stem().map_or_else(
+            || "plugin".to_string(),
+            |s| s.to_string_lossy().into_owned(),
+        );
+        let fail = |details: String| OmniError::Plugin {
+            name: stem.clone(),
+            details,
+        };
+
+        let bytes = std::fs::read(path)?;
+        let mut wasm_config = wasmi::Config::default();
+        wasm_config.consume_fuel(true);
+        let engine = wasmi::Engine::new(&wasm_config);
+        let module =
+            Module::new(&engine, &bytes).map_err(|e| fail(format!("invalid module: {e}")))?;
+
+        if let Some(import) = module.imports().next() {
+            return Err(fail(format!(
+                "imports `{}::{}`; plugins must be self-contained (no WASI or host functions)",
+                import.module(),
+                import.name()
+            )));
+        }
+        let has = |export: &str| module.get_export(export).is_some();
+        if !has(EXPORT_MEMORY) || !has(EXPORT_MANIFEST) {
+            return Err(fail(format!(
+                "must export `{EXPORT_MEMORY}` and `{EXPORT_MANIFEST}`"
+            )));
+        }
+        let analyzes = has(EXPORT_ANALYZE);
+        let hooks: Vec<&'static str> = [EXPORT_POST_CHUNK, EXPORT_PRE_EMBED, EXPORT_POST_SEARCH]
+            .into_iter()
+            .filter(|export| has(export))
+            .collect();
+        if (analyzes || !hooks.is_empty()) && !has(EXPORT_ALLOC) {
+            return Err(fail(format!("must export `{EXPORT_ALLOC}`")));
+        }
+
+        let mut plugin = Self {
+            name: stem.clone(),
+            path: path.to_path_buf(),
+            extensions: Vec::new(),
+            engine,
+            module,
+            fuel_per_call: config.fuel_per_call,
+            max_memory_bytes: usize::try_from(u64::from(config.max_memory_mb) << 20)
+                .unwrap_or(usize::MAX),
+            analyzes,
+            hooks,
+        };
+        let manifest: Manifest = serde_json::from_slice(&plugin.call(EXPORT_MANIFEST, None)?)
+            .map_err(|e| fail(format!("invalid manifest: {e}")))?;
+        if let Some(name) = manifest.name.filter(|n| !n.is_empty()) {
+            plugin.name = name;
+        }
+        plugin.extensions = manifest
+            .extensions
+            .iter()
+            .map(|e| e.trim_start_matches('.').to_ascii_lowercase())
+            .collect();
+        if !plugin.extensions.is_empty() && !plugin.analyzes {
+            return Err(fail(format!(
+                "claims extensions but does not export `{EXPORT_ANALYZE}`"
+            )));
+        }
+        Ok(plugin)
+    }
+
+    /// Plugin name from its manifest, or its file stem.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Path the module was loaded from.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Lowercase file extensions this plugin analyzes.
+    pub fn extensions(&self) -> &[String] {
+        &self.extensions
+    }
+
+    /// Whether the plugin implements any pipeline hook.
+    pub fn has_hooks(&self) -> bool {
+        !self.hooks.is_empty()
+    }
+
+    /// Extract structural elements from `source` with `omni_analyze`.
+    pub fn analyze(&self, path: &Path, source: &str) -> OmniResult<Vec<StructuralElement>> {
+        let input = serde_json::json!({
+            "path": path.to_string_lossy().replace('\\', "/"),
+            "source": source,
+        });
+        let output = self.call_json(EXPORT_ANALYZE, &input)?;
+        if output.is_empty() {
+            return Ok(Vec::new());
+        }
+        let elements: Vec<PluginElement> = serde_json::from_slice(&output)
+            .map_err(|e| self.error(format!("invalid `{EXPORT_ANALYZE}` output: {e}")))?;
+
+        let lines: Vec<&str> = source.lines().collect();
+        Ok(elements
+            .into_iter()
+            .map(|el| {
+                let line_start = el.line_start.max(1);
+                let line_end = el.line_end.max(line_start);
+                let from = (line_start as usize - 1).min(lines.len());
+                let to = (line_end as usize).min(lines.len());
+                StructuralElement {
+                    symbol_path: el.symbol_path.unwrap_or_else(|| el.name.clone()),
+                    name: el.name,
+                    kind: el
+                        .kind
+                        .as_deref()
+                        .map_or(ChunkKind::TopLevel, ChunkKind::from_str_lossy),
+                    visibility: el
+                        .visibility
+                        .as_deref()
+                        .map_or(Visibility::Public, Visibility::from_str_lossy),
+                    line_start,
+                    line_end,
+                    content: lines[from..to].join("\n"),
+                    doc_comment: el.doc_comment,
+                    references: el.references,
+                    extends: Vec::new(),
+                    implements: Vec::new(),
+                    complexity: ComplexityMetrics::default(),
+                }
+            })
+            .collect())
+    }
+
+    fn error(&self, details: String) -> OmniError {
+        OmniError::Plugin {
+            name: self.name.clone(),
+            details,
+        }
+    }
+
+    fn call_json(&self, export: &str, input: &serde_json::Value) -> OmniResult<Vec<u8>> {
+        let bytes =
+            serde_json::to_vec(input).map_err(|e| OmniError::Serialization(e.to_string()))?;
+        self.call(export, Some(&bytes))
+    }
+
+    /// Instantiate a fresh sandbox and call `export`, passing `input` as
+    /// `(ptr, len)` when given.
+    fn call(&self, export: &str, input: Option<&[u8]>) -> OmniResult<Vec<u8>> {
+        let trap = |e: wasmi::Error| self.error(format!("`{export}` failed: {e}"));
+
+        let limits = StoreLimitsBuilder::new()
+            .memory_size(self.max_memory_bytes)
+            .instances(1)
+            .build();
+        let mut store = Store::new(&self.engine, limits);
+        store.limiter(|limits: &mut StoreLimits| limits);
+        store.set_fuel(self.fuel_per_call).map_err(trap)?;
+
+        let instance = Linker::<StoreLimits>::new(&self.engine)
+            .instantiate(&mut store, &self.module)
+            .and_then(|pre| pre.start(&mut store))
+            .map_err(trap)?;
+        let memory = instance
+            .get_memory(&store, EXPORT_MEMORY)
+            .ok_or_else(|| self.error(format!("missing `{EXPORT_MEMORY}` export")))?;
+
+        let packed = if let Some(bytes) = input {
+            let len = i32::try_from(bytes.len())
+                .map_err(|_| self.error(format!("`{export}` input too large")))?;
+            let ptr = instance
+                .get_typed_func::<i32, i32>(&store, EXPORT_ALLOC)
+                .and_then(|alloc| alloc.call(&mut store, len))
+                .map_err(trap)?;
+            memory
+                .write(&mut store, wasm_offset(ptr), bytes)
+                .map_err(|e| self.error(format!("`{EXPORT_ALLOC}` returned a bad buffer: {e}")))?;
+            instance
+                .get_typed_func::<(i32, i32), i64>(&store, export)
+                .and_then(|f| f.call(&mut store, (ptr, len)))
+                .map_err(trap)?
+        } else {
+            instance
+                .get_typed_func::<(), i64>(&store, export)
+                .and_then(|f| f.call(&mut store, ()))
+                .map_err(trap)?
+        };
+
+        // Check the range before allocating: `len` comes from the plugin and
+        // may be anything up to 4 GiB. Linear memory is already capped at
+        // `max_memory_mb`, so a range inside it is bounded too.
+        let (ptr, len) = unpack(packed);
+        let size = memory.data_size(&store);
+        if !matches!(ptr.checked_add(len), Some(end) if end <= size) {
+            return Err(self.error(format!(
+                "`{export}` returned a buffer outside its memory ({len} bytes at {ptr})"
+            )));
+        }
+        let mut output = vec![0; len];
+        memory
+            .read(&store, ptr, &mut output)
+            .map_err(|e| self.error(format!("`{export}` returned a bad buffer: {e}")))?;
+        Ok(output)
+    }
+}
+
+/// Split an ABI return value into `(ptr, len)`.
+#[allow(clippy::cast_sign_loss)] // two u32 halves reinterpreted from the i64 bits
+fn unpack(packed: i64) -> (usize, usize) {
+    let bits = packed as u64;
+    ((bits >> 32) as usize, (bits & 0xFFFF_FFFF) as usize)
+}
+
+#[allow(clippy::cast_sign_loss)] // wasm32 pointers are unsigned
+fn wasm_offset(ptr: i32) -> usize {
+    ptr as u32 as usize
+}
+
+impl PipelineHook for WasmPlugin {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn post_chunk(&self, path: &Path, chunks: &mut Vec<Chunk>) {
+        if !self.hooks.contains(&EXPORT_POST_CHUNK) || chunks.is_empty() {
+            return;
+        }
+        let input = serde_json::json!({
+            "path": path.to_string_lossy().replace('\\', "/"),
+            "chunks": chunks
+                .iter()
+                .map(|c| serde_json::json!({
+                    "symbol_path": c.symbol_path,
+                    "kind": c.kind.as_str(),
+                    "content": c.content,
+                    "weight": c.weight,
+                }))
+                .collect::<Vec<_>>(),
+        });
+        let patches = self.call_json(EXPORT_POST_CHUNK, &input).and_then(|out| {
+            if out.is_empty() {
+                return Ok(Vec::new());
+            }
+            serde_json::from_slice::<Vec<Option<ChunkPatch>>>(&out)
+                .map_err(|e| self.error(format!("invalid `{EXPORT_POST_CHUNK}` output: {e}")))
+        });
+        match patches {
+            Ok(patches) if patches.is_empty() => {}
+            Ok(patches) if patches.len() == chunks.len() => {
+                for (chunk, patch) in chunks.iter_mut().zip(patches) {
+                    let ChunkPatch { content, weight } = patch.unwrap_or_default();
+                    if let Some(content) = content {
+                        chunk.content = content;
+                    }
+                    if let Some(weight) = weight {
+                        chunk.weight = weight;
+                    }
+                }
+            }
+            Ok(patches) => tracing::warn!(
+                plugin = %self.name,
+                expected = chunks.len(),
+                got = patches.len(),
+                "ignoring post_chunk output with wrong length"
+            ),
+            Err(e) => {
+                tracing::warn!(path = %path.display(), error = %e, "post_chunk plugin failed");
+            }
+        }
+    }
+
+    fn pre_embed(&self, chunk: &Chunk, text: &mut String) {
+        if !self.hooks.contains(&EXPORT_PRE_EMBED) {
+            return;
+        }
+        let input = serde_json::json!({
+            "symbol_path": chunk.symbol_path,
+            "kind": chunk.kind.as_str(),
+            "text": text.as_str(),
+        });
+        match self.call_json(EXPORT_PRE_EMBED, &input) {
+            Ok(out) if out.is_empty() => {}
+            Ok(out) => {
+                if let Ok(replacement) = String::from_utf8(out) {
+                    *text = replacement;
+                } else {
+                    tracing::warn!(plugin = %self.name, "pre_embed returned invalid UTF-8");
+                }
+            }
+            Err(e) => tracing::warn!(error = %e, "pre_embed plugin failed"),
+        }
+    }
+
+    fn post_search(&self, query: &str, results: &mut Vec<SearchResult>) {
+        if !self.hooks.contains(&EXPORT_POST_SEARCH) || results.is_empty() {
+            return;
+        }
+        let input = serde_json::json!({
+            "query": query,
+            "results": results
+                .iter()
+                .map(|r| serde_json::json!({
+                    "path": r.file_path.to_string_lossy().replace('\\', "/"),
+                    "symbol_path": r.chunk.symbol_path,
+                    "kind": r.chunk.kind.as_str(),
+                    "score": r.score,
+                }))
+                .collect::<Vec<_>>(),
+        });
+        let order = self.call_json(EXPORT_POST_SEARCH, &input).and_then(|out| {
+            if out.is_empty() {
+                return Ok(None);
+            }
+            serde_json::from_slice::<Vec<usize>>(&out)
+                .map(Some)
+                .map_err(|e| self.error(format!("invalid `{EXPORT_POST_SEARCH}` output: {e}")))
+        });
+        match order {
+            Ok(None) => {}
+            Ok(Some(order)) => {
+                let mut slots: Vec<Option<SearchResult>> = results.drain(..).map(Some).collect();
+                results.extend(
+                    order
+                        .into_iter()
+                        .filter_map(|i| slots.get_mut(i).and_then(Option::take)),
+                );
+            }
+            Err(e) => tracing::warn!(error = %e, "post_search plugin failed"),
+        }
+    }
+}
+
+/// Plugins loaded for one repository.
+#[derive(Debug, Clone, Default)]
+pub struct PluginSet {
+    plugins: Vec<Arc<WasmPlugin>>,
+}
+
+impl PluginSet {
+    /// Load every `*.wasm` in `<repo_path>/.omnicontext/plugins/`, in file
+    /// name order. Plugins that fail to load are logged and skipped.
+    pub fn load(repo_path: &Path, config: &PluginsConfig) -> Self {
+        if !config.enabled {
+            return Self::default();
+        }
+        let dir = repo_path.join(PLUGINS_DIR);
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            return Self::default();
+        };
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(Result::ok)
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|e| e == "wasm"))
+            .collect();
+        paths.sort();
+
+        let mut plugins = Vec::new();
+        for path in paths {
+            match WasmPlugin::load(&path, config) {
+                Ok(plugin) => {
+                    tracing::info!(
+                        plugin = %plugin.name,
+                        extensions = ?plugin.extensions,
+                        hooks = plugin.has_hooks(),
+                        "loaded wasm plugin"
+                    );
+                    plugins.push(Arc::new(plugin));
+                }
+                Err(e) => tracing::warn!(path = %path.display(), error = %e, "skipping plugin"),
+            }
+        }
+        Self { plugins }
+    }
+
+    /// Whether no plugins are loaded.
+    pub fn is_empty(&self) -> bool {
+        self.plugins.is_empty()
+    }
+
+    /// Loaded plugins, in load order.
+    pub fn iter(&self) -> impl Iterator<Item = &Arc<WasmPlugin>> {
+        self.plugins.iter()
+    }
+
+    /// Extensions claimed by plugin analyzers that no built-in analyzer
+    /// handles.
+    pub fn extensions(&self) -> Vec<String> {
+        let mut exts: Vec<String> = self
+            .plugins
+            .iter()
+            .flat_map(|p| p.extensions.iter().cloned())
+            .filter(|e| matches!(Language::from_extension(e), Language::Unknown))
+            .collect();
+        exts.sort();
+        exts.dedup();
+        exts
+    }
+
+    /// The first plugin analyzing `ext` (lowercase, without the dot).
+    pub fn analyzer_for(&self, ext: &str) -> Option<&WasmPlugin> {
+        self.plugins
+            .iter()
+            .find(|p| p.extensions.iter().any(|e| e == ext))
+            .map(AsRef::as_ref)
+    }
+
+    /// Plugins implementing pipeline hooks.
+    pub fn hooks(&self) -> impl Iterator<Item = Arc<dyn PipelineHook>> + '_ {
+        self.plugins
+            .iter()
+            .filter(|p| p.has_hooks())
+            .map(|p| Arc::clone(p) as Arc<dyn PipelineHook>)
+    }
+
+    /// Language for a file extension: a built-in language, else
+    /// [`Language::Plugin`] if a plugin analyzes it, else `Unknown`.
+    pub fn detect_language(&self, ext: &str) -> Language {
+        match Language::from_extension(ext) {
+            Language::Unknown if self.analyzer_for(ext).is_some() => Language::Plugin,
+            language => language,
+        }
+    }
+
+    /// Parse `source` with the analyzer for `language`, routing
+    /// [`Language::Plugin`] files to the plugin claiming `ext`.
+    pub fn parse_file(
+        &self,
+        path: &Path,
+        source: &str,
+        language: Language,
+        ext: &str,
+    ) -> OmniResult<Vec<StructuralElement>> {
+        if language != Language::Plugin {
+            return crate::parser::parse_file(path, source.as_bytes(), language);
+        }
+        let plugin = self.analyzer_for(ext).ok_or_else(|| OmniError::Parse {
+            path: path.to_path_buf(),
+            message: format!("no plugin analyzes .{ext} files"),
+        })?;
+        plugin.analyze(path, source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// WAT for a plugin whose exports return fixed strings from a data
+    /// segment. `exports` pairs an export name with its output.
+    fn fixed_output_plugin(exports: &[(&str, &str)]) -> String {
+        let mut data = String::new();
+        let mut funcs = String::new();
+        let mut offset = 0usize;
+        for (export, output) in exports {
+            let escaped = output.replace('\\', "\\\\").replace('"', "\\\"");
+            data.push_str(&format!("  (data (i32.const {offset}) \"{escaped}\")\n"));
+            let packed = (offset << 32) | output.len();
+            let params = if *export == EXPORT_MANIFEST {
+                ""
+            } else {
+                "(param i32 i32) "
+            };
+            funcs.push_str(&format!(
+                "  (func (export \"{export}\") {params}(result i64) (i64.const {packed}))\n"
+            ));
+            offset += output.len().next_multiple_of(8);
+        }
+        format!(
+            "(module\n  (memory (export \"memory\") 1)\n  (global $heap (mut i32) (i32.const 32768))\n  \
+             (func (export \"omni_alloc\") (param $len i32) (result i32)\n    \
+             (local $ptr i32)\n    (local.set $ptr (global.get $heap))\n    \
+             (global.set $heap (i32.add (global.get $heap) (local.get $len)))\n    \
+             (local.get $ptr))\n{data}{funcs})"
+        )
+    }
+
+    fn write_plugin(dir: &Path, name: &str, wat: &str) -> PathBuf {
+        let plugins = dir.join(PLUGINS_DIR);
+        std::fs::create_dir_all(&plugins).expect("plugins dir");
+        let path = plugins.join(format!("{name}.wasm"));
+        std::fs::write(&path, wat::parse_str(wat).expect("valid wat")).expect("write plugin");
+        path
+    }
+
+    #[test]
+    fn test_plugin_analyzer_handles_claimed_extension() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        write_plugin(
+            dir.path(),
+            "thrift",
+            &fixed_output_plugin(&[
+                (EXPORT_MANIFEST, r#"{"extensions":[".Thrift","py"]}"#),
+                (
+                    EXPORT_ANALYZE,
+                    r#"[{"name":"Ping","symbol_path":"Api.Ping","kind":"function","line_start":2,"line_end":3,"doc_comment":"Health check."}]"#,
+                ),
+            ]),
+        );
+
+        let set = PluginSet::load(dir.path(), &PluginsConfig::default());
+        assert_eq!(set.iter().count(), 1);
+        // Built-in languages keep their analyzers.
+        assert_eq!(set.extensions(), vec!["thrift".to_string()]);
+        assert_eq!(set.detect_language("py"), Language::Python);
+        assert_eq!(set.detect_language("thrift"), Language::Plugin);
+        assert_eq!(set.detect_language("proto"), Language::Unknown);
+
+        let source = "service Api {\n  bool Ping()\n  // end\n}\n";
+        let elements = set
+            .parse_file(Path::new("api.thrift"), source, Language::Plugin, "thrift")
+            .expect("analyze");
+        assert_eq!(elements.len(), 1);
+        assert_eq!(elements[0].symbol_path, "Api.Ping");
+        assert_eq!(elements[0].kind, ChunkKind::Function);
+        assert_eq!(elements[0].content, "  bool Ping()\n  // end");
+        assert_eq!(elements[0].doc_comment.as_deref(), Some("Health check."));
+    }
+
+    #[test]
+    fn test_plugin_hooks_rewrite_embed_text_and_reorder_results() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = write_plugin(
+            dir.path(),
+            "glossary",
+            &fixed_output_plugin(&[
+                (EXPORT_MANIFEST, r#"{"name":"glossary"}"#),
+                (EXPORT_PRE_EMBED, "ledger entry (LE)"),
+                (EXPORT_POST_SEARCH, "[1,7,0]"),
+            ]),
+        );
+        let plugin = WasmPlugin::load(&path, &PluginsConfig::default()).expect("load");
+        assert_eq!(PipelineHook::name(&plugin), "glossary");
+        assert!(plugin.has_hooks());
+
+        let chunk = |symbol: &str| Chunk {
+            id: 0,
+            file_id: 0,
+            symbol_path: symbol.into(),
+            kind: ChunkKind::Function,
+            visibility: Visibility::Public,
+            line_start: 1,
+            line_end: 1,
+            content: String::new(),
+            doc_comment: None,
+            token_count: 0,
+            weight: 1.0,
+            vector_id: None,
+            is_summary: false,
+            is_generated: false,
+            language: None,
+            content_hash: 0,
+            complexity: ComplexityMetrics::default(),
+        };
+        let mut text = "fn le()".to_string();
+        plugin.pre_embed(&chunk("le"), &mut text);
+        assert_eq!(text, "ledger entry (LE)");
+
+        let result = |symbol: &str| SearchResult {
+            chunk: chunk(symbol),
+            file_path: PathBuf::from("src/lib.rs"),
+            score: 1.0,
+            relevance: 1.0,
+            score_breakdown: crate::types::ScoreBreakdown::default(),
+            owners: Vec::new(),
+        };
+        let mut results = vec![result("a"), result("b"), result("c")];
+        plugin.post_search("ledger", &mut results);
+        let order: Vec<_> = results
+            .iter()
+            .map(|r| r.chunk.symbol_path.as_str())
+            .collect();
+        assert_eq!(order, ["b", "a"]);
+    }
+
+    #[test]
+    fn test_sandbox_rejects_imports_and_stops_runaway_calls() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let importing = write_plugin(
+            dir.path(),
+            "wasi",
+            r#"(module
+                 (import "wasi_snapshot_preview1" "fd_write" (func (param i32 i32 i32 i32) (result i32)))
+                 (memory (export "memory") 1)
+                 (func (export "omni_manifest") (result i64) (i64.const 0)))"#,
+        );
+        let err = WasmPlugin::load(&importing, &PluginsConfig::default()).expect_err("imports");
+        assert!(err.to_string().contains("self-contained"), "{err}");
+
+        let spinning = write_plugin(
+            dir.path(),
+            "spin",
+            r#"(module
+                 (memory (export "memory") 1)
+                 (data (i32.const 0) "{\"extensions\":[\"dsl\"]}")
+                 (func (export "omni_alloc") (param i32) (result i32) (i32.const 1024))
+                 (func (export "omni_manifest") (result i64) (i64.const 22))
+                 (func (export "omni_analyze") (param i32 i32) (result i64)
+                   (loop $forever (br $forever))
+                   (i64.const 0)))"#,
+        );
+        let config = PluginsConfig {
+            fuel_per_call: 100_000,
+            ..PluginsConfig::default()
+        };
+        let plugin = WasmPlugin::load(&spinning, &config).expect("load");
+        let err = plugin
+            .analyze(Path::new("a.dsl"), "x")
+            .expect_err("out of fuel");
+        assert!(matches!(err, OmniError::Plugin { .. }), "{err}");
+
+        let oversized = write_plugin(
+            dir.path(),
+            "huge",
+            r#"(module
+                 (memory (export "memory") 1)
+                 (data (i32.const 0) "{\"extensions\":[\"big\"]}")
+                 (func (export "omni_alloc") (param i32) (result i32) (i32.const 1024))
+                 (func (export "omni_manifest") (result i64) (i64.const 22))
+                 (func (export "omni_analyze") (param i32 i32) (result i64)
+                   (i64.const 0xFFFFFFFF)))"#,
+        );
+        let plugin = WasmPlugin::load(&oversized, &config).expect("load");
+        let err = plugin
+            .analyze(Path::new("a.big"), "x")
+            .expect_err("out-of-bounds output");
+        assert!(err.to_string().contains("outside its memory"), "{err}");
+
+        // All plugins live in the directory; only the import-free ones load.
+        let set = PluginSet::load(dir.path(), &config);
+        let mut names: Vec<_> = set.iter().map(|p| p.name()).collect();
+        names.sort_unstable();
+        assert_eq!(names, ["huge", "spin"]);
+        assert!(PluginSet::load(
+            dir.path(),
+            &PluginsConfig {
+                enabled: false,
+                ..config
+            }
+        )
+        .is_empty());
+    }
+}
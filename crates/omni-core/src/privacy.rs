@@ -0,0 +1,176 @@
+//! Sensitive-path exclusion policy and audit.
+//!
+//! [`PrivacyPolicy`] compiles the `[privacy] exclude` patterns. The watcher
+//! and the pipeline both consult it, so a matching path is never indexed no
+//! matter what the other indexing settings say, and files indexed before a
+//! pattern was added are purged on the next run.
+//!
+//! [`audit`] lists indexed files that look sensitive: those the policy
+//! excludes (still present until the next index run) and those matching the
+//! broader [`RISKY_PATTERNS`] heuristics that the policy does not cover.
+
+use std::path::Path;
+
+use globset::GlobSet;
+use serde::{Deserialize, Serialize};
+
+use crate::config::PrivacyConfig;
+use crate::error::{OmniError, OmniResult};
+use crate::index::MetadataIndex;
+
+/// Patterns the audit treats as risky, in addition to the policy's own.
+pub const RISKY_PATTERNS: &[&str] = &[
+    ".env*", "**/secrets/**", "**/secret/**", "**/credentials/**", "*secret*", "*credential*",
+    "*password*", "*.pem", "*.key", "*.p12", "*.pfx", "*.keystore", "*.jks", "*.tfstate",
+    "id_rsa*", "id_ed25519*", ".npmrc", ".pypirc", ".netrc",
+];
+
+/// Compiled sensitive-path exclusion policy.
+#[derive(Debug, Clone, Default)]
+pub struct PrivacyPolicy {
+    patterns: Vec<(String, GlobSet)>,
+}
+
+impl PrivacyPolicy {
+    /// Compile the configured patterns. An invalid pattern is a configuration
+    /// error rather than being skipped, so a typo can't silently disable it.
+    pub fn new(config: &PrivacyConfig) -> OmniResult<Self> {
+        Self::from_patterns(&config.exclude)
+    }
+
+    fn from_patterns<S: AsRef<str>>(patterns: &[S]) -> OmniResult<Self> {
+        let patterns = patterns
+            .iter()
+            .map(|p| {
+                let p = p.as_ref();
+                crate::owners::compile_pattern(p)
+                    .map(|set| (p.to_string(), set))
+                    .ok_or_else(|| OmniError::Config {
+                        details: format!("invalid [privacy] exclude pattern: {p:?}"),
+                    })
+            })
+            .collect::<OmniResult<_>>()?;
+        Ok(Self { patterns })
+    }
+
+    /// The first pattern matching `path` (relative to the repo root).
+    pub fn matching_pattern(&self, path: &Path) -> Option<&str> {
+        let path = path.to_string_lossy().replace('\\', "/");
+        let path = path.trim_start_matches("./");
+        self.patterns
+            .iter()
+            .find(|(_, set)| set.is_match(path))
+            .map(|(pattern, _)| pattern.as_str())
+    }
+
+    /// Whether `path` (relative to the repo root) must not be indexed.
+    pub fn is_excluded(&self, path: &Path) -> bool {
+        self.matching_pattern(path).is_some()
+    }
+}
+
+/// An indexed file that looks sensitive.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PrivacyFinding {
+    /// File path relative to the repository root.
+    pub path: String,
+    /// The pattern that matched.
+    pub pattern: String,
+    /// Whether the policy excludes the file (it will be purged on the next
+    /// index run). `false` means only the audit heuristics flagged it.
+    pub excluded: bool,
+}
+
+/// List indexed files matching the policy or [`RISKY_PATTERNS`], in path order.
+pub fn audit(index: &MetadataIndex, policy: &PrivacyPolicy) -> OmniResult<Vec<PrivacyFinding>> {
+    let risky = PrivacyPolicy::from_patterns(RISKY_PATTERNS)?;
+    let mut findings: Vec<PrivacyFinding> = index
+        .get_all_files()?
+        .into_iter()
+        .filter_map(|file| {
+            let (pattern, excluded) = match policy.matching_pattern(&file.path) {
+                Some(pattern) => (pattern, true),
+                None => (risky.matching_pattern(&file.path)?, false),
+            };
+            Some(PrivacyFinding {
+                path: file.path.to_string_lossy().replace('\\', "/"),
+                pattern: pattern.to_string(),
+                excluded,
+            })
+        })
+        .collect();
+    findings.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(findings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{FileInfo, Language};
+
+    #[test]
+    fn test_default_policy_matches_sensitive_paths() {
+        let policy = PrivacyPolicy::new(&PrivacyConfig::default()).expect("policy");
+        assert!(policy.is_excluded(Path::new(".env")));
+        assert!(policy.is_excluded(Path::new("deploy/.env.production")));
+        assert!(policy.is_excluded(Path::new("app/secrets/db.py")));
+        assert!(policy.is_excluded(Path::new("secrets/db.py")));
+        assert!(policy.is_excluded(Path::new("certs/server.pem")));
+        assert!(!policy.is_excluded(Path::new("src/secretary.py")));
+        assert!(!policy.is_excluded(Path::new("src/main.rs")));
+        assert_eq!(
+            policy.matching_pattern(Path::new("a/secrets/b.rs")),
+            Some("**/secrets/**")
+        );
+    }
+
+    #[test]
+    fn test_invalid_pattern_is_config_error() {
+        let config = PrivacyConfig {
+            exclude: vec!["src/[".into()],
+        };
+        assert!(matches!(
+            PrivacyPolicy::new(&config),
+            Err(OmniError::Config { .. })
+        ));
+    }
+
+    #[test]
+    fn test_audit_flags_excluded_and_risky_files() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let index = MetadataIndex::open(&dir.path().join("index.db")).expect("open db");
+        for path in [
+            "src/main.rs",
+            "config/secrets/keys.py",
+            "src/password_reset.py",
+        ] {
+            index
+                .upsert_file(&FileInfo {
+                    id: 0,
+                    path: path.into(),
+                    language: Language::Python,
+                    content_hash: path.to_string(),
+                    size_bytes: 1,
+                })
+                .expect("upsert file");
+        }
+
+        let policy = PrivacyPolicy::new(&PrivacyConfig::default()).expect("policy");
+        let findings = audit(&index, &policy).expect("audit");
+        assert_eq!(
+            findings,
+            vec![
+                PrivacyFinding {
+                    path: "config/secrets/keys.py".into(),
+                    pattern: "**/secrets/**".into(),
+                    excluded: true,
+                },
+                PrivacyFinding {
+                    path: "src/password_reset.py".into(),
+                    pattern: "*password*".into(),
+                    excluded: false,
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,115 @@
+//! Finding the index of a repository whose checkout moved.
+//!
+//! The data directory is keyed by a hash of the repository's absolute path
+//! (see [`crate::config::Config::data_dir`]), so a moved checkout would look
+//! like a new repository and be indexed from scratch. Each index records its
+//! repository identity — root commit and `origin` URL — and the root it was
+//! built at; an index whose identity matches but whose root no longer exists
+//! is the index of a moved checkout, and can be adopted.
+
+use std::path::{Path, PathBuf};
+
+use crate::index::{meta_keys, MetadataIndex};
+
+/// What identifies a repository independently of where it is checked out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepoIdentity {
+    /// Root commit; see [`crate::branch_diff::repo_fingerprint`].
+    pub root_commit: String,
+    /// `origin` remote URL, if the repository has one.
+    pub origin: Option<String>,
+}
+
+impl RepoIdentity {
+    /// Identity of the git repository at `repo`, or `None` outside git or
+    /// before the first commit.
+    pub fn of(repo: &Path) -> Option<Self> {
+        Some(Self {
+            root_commit: crate::branch_diff::repo_fingerprint(repo)?,
+            origin: crate::branch_diff::repo_origin(repo),
+        })
+    }
+
+    /// Whether a recorded root commit and origin describe this repository.
+    /// Origins are only compared when both are known.
+    pub fn matches(&self, root_commit: &str, origin: Option<&str>) -> bool {
+        self.root_commit == root_commit
+            && match (self.origin.as_deref(), origin) {
+                (Some(ours), Some(theirs)) => ours == theirs,
+                _ => true,
+            }
+    }
+}
+
+/// Data directory under `repos_dir` holding the index of `identity` built
+/// at a root that no longer exists, i.e. the index of a moved checkout.
+/// When several match, the most recently modified wins.
+pub fn find_moved_index(repos_dir: &Path, identity: &RepoIdentity) -> Option<PathBuf> {
+    let entries = std::fs::read_dir(repos_dir).ok()?;
+    entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let db_path = entry.path().join("index.db");
+            let peek = |key| MetadataIndex::peek_meta(&db_path, key).ok().flatten();
+            let root_commit = peek(meta_keys::REPO_FINGERPRINT)?;
+            let origin = peek(meta_keys::REPO_ORIGIN);
+            if !identity.matches(&root_commit, origin.as_deref()) {
+                return None;
+            }
+            let root = peek(meta_keys::REPO_ROOT)?;
+            if Path::new(&root).exists() {
+                return None;
+            }
+            let modified = std::fs::metadata(&db_path).ok()?.modified().ok()?;
+            Some((modified, entry.path()))
+        })
+        .max_by_key(|(modified, _)| *modified)
+        .map(|(_, dir)| dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn identity(origin: Option<&str>) -> RepoIdentity {
+        RepoIdentity {
+            root_commit: "abc".to_string(),
+            origin: origin.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_identity_matches() {
+        let ours = identity(Some("https://example.com/a.git"));
+        assert!(ours.matches("abc", Some("https://example.com/a.git")));
+        assert!(ours.matches("abc", None));
+        assert!(!ours.matches("abc", Some("https://example.com/fork.git")));
+        assert!(!ours.matches("def", Some("https://example.com/a.git")));
+        assert!(identity(None).matches("abc", Some("https://example.com/a.git")));
+    }
+
+    #[test]
+    fn test_find_moved_index_skips_live_and_foreign_roots() {
+        let repos = tempfile::tempdir().expect("tempdir");
+        let live_root = tempfile::tempdir().expect("tempdir");
+        let record = |name: &str, root_commit: &str, root: &Path| {
+            let index =
+                MetadataIndex::open(&repos.path().join(name).join("index.db")).expect("open index");
+            index
+                .set_meta(meta_keys::REPO_FINGERPRINT, root_commit)
+                .expect("set fingerprint");
+            index
+                .set_meta(meta_keys::REPO_ROOT, &root.to_string_lossy())
+                .expect("set root");
+        };
+        record("live", "abc", live_root.path());
+        record("foreign", "def", Path::new("/nonexistent/foreign"));
+        assert_eq!(find_moved_index(repos.path(), &identity(None)), None);
+
+        record("moved", "abc", Path::new("/nonexistent/moved"));
+        assert_eq!(
+            find_moved_index(repos.path(), &identity(None)),
+            Some(repos.path().join("moved"))
+        );
+    }
+}
@@ -0,0 +1,441 @@
+//! Remote index sync to object storage.
+//!
+//! Lets one machine (typically CI) build the index and publish it, and every
+//! other machine pull it instead of indexing locally. The synced artifacts
+//! are [`SYNCED_FILES`]: a consistent `index.db` snapshot and `vectors.bin`.
+//! The hash cache is machine-specific (absolute paths, mtimes) and stays
+//! local; after a pull the first index run re-parses files but reuses every
+//! stored embedding whose chunk content is unchanged.
+//!
+//! ## Layout
+//!
+//! Files are split into fixed-size blocks stored content-addressed under
+//! `blocks/<sha256>`. `manifest.json` at the remote root lists each file's
+//! block hashes in order and is written last, so readers never observe a
+//! half-published index. Both directions are incremental: a push uploads
+//! only blocks the remote does not have yet, and a pull downloads only
+//! blocks missing from the local copy.
+//!
+//! Blocks a push drops from the manifest are not deleted right away: they
+//! are listed as retired and only deleted by the push after that, so a pull
+//! that read the previous manifest can still fetch every block it lists.
+//!
+//! ## Locations
+//!
+//! | URL | Backend | Credentials |
+//! |-----|---------|-------------|
+//! | `s3://bucket/prefix` | Amazon S3 | `AWS_*` environment / profile |
+//! | `gs://bucket/prefix`, `gcs://...` | Google Cloud Storage | `GOOGLE_APPLICATION_CREDENTIALS` |
+//! | `azblob://container/prefix` | Azure Blob Storage | `AZURE_STORAGE_ACCOUNT` (+ key or SAS) |
+//! | `file:///abs/path` | Local filesystem | -- |
+
+#![allow(clippy::doc_markdown, clippy::missing_errors_doc)]
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use opendal::{services, ErrorKind, Operator};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::error::{OmniError, OmniResult};
+use crate::types::IndexMetadata;
+
+/// Data-directory files published to and fetched from the remote.
+pub const SYNCED_FILES: &[&str] = &["index.db", "vectors.bin"];
+
+/// Name of the manifest object at the remote root.
+pub const MANIFEST_NAME: &str = "manifest.json";
+
+/// Size of one content-addressed block.
+const BLOCK_SIZE: usize = 4 * 1024 * 1024;
+
+/// Block hashes of every synced file.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RemoteManifest {
+    /// File name -> SHA-256 of each block, in file order.
+    pub files: BTreeMap<String, Vec<String>>,
+    /// Metadata of the published index, checked before a pull.
+    #[serde(default)]
+    pub metadata: IndexMetadata,
+    /// Blocks the previous manifest referenced and this one does not; the
+    /// next push deletes them unless they are live again.
+    #[serde(default)]
+    pub retired: Vec<String>,
+}
+
+impl RemoteManifest {
+    fn blocks(&self) -> HashSet<&str> {
+        self.files.values().flatten().map(String::as_str).collect()
+    }
+}
+
+/// Outcome of a push or pull.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncReport {
+    /// Number of files synced.
+    pub files: usize,
+    /// Total blocks across those files.
+    pub blocks_total: usize,
+    /// Blocks actually uploaded or downloaded.
+    pub blocks_transferred: usize,
+    /// Bytes actually uploaded or downloaded.
+    pub bytes_transferred: u64,
+}
+
+/// Open an operator for a remote location URL (see the module docs).
+pub fn open_location(url: &str) -> OmniResult<Operator> {
+    let invalid = |why: &str| OmniError::Config {
+        details: format!("invalid remote index location {url:?}: {why}"),
+    };
+    let (scheme, rest) = url
+        .split_once("://")
+        .ok_or_else(|| invalid("missing scheme"))?;
+    let (bucket, prefix) = rest.split_once('/').unwrap_or((rest, ""));
+    let root = format!("/{}", prefix.trim_matches('/'));
+    if scheme != "file" && bucket.is_empty() {
+        return Err(invalid("missing bucket or container name"));
+    }
+
+    let op = match scheme {
+        "s3" => Operator::new(services::S3::default().bucket(bucket).root(&root))?.finish(),
+        "gs" | "gcs" => {
+            Operator::new(services::Gcs::default().bucket(bucket).root(&root))?.finish()
+        }
+        "azblob" => {
+            let account = std::env::var("AZURE_STORAGE_ACCOUNT")
+                .map_err(|_| invalid("AZURE_STORAGE_ACCOUNT is not set"))?;
+            Operator::new(
+                services::Azblob::default()
+                    .container(bucket)
+                    .root(&root)
+                    .endpoint(&format!("https://{account}.blob.core.windows.net")),
+            )?
+            .finish()
+        }
+        "file" => {
+            if !rest.starts_with('/') {
+                return Err(invalid("file:// locations must be absolute"));
+            }
+            Operator::new(services::Fs::default().root(rest))?.finish()
+        }
+        other => return Err(invalid(&format!("unsupported scheme {other:?}"))),
+    };
+    Ok(op)
+}
+
+/// Fetch the remote manifest, or `None` if nothing has been pushed yet.
+pub async fn read_manifest(op: &Operator) -> OmniResult<Option<RemoteManifest>> {
+    match op.read(MANIFEST_NAME).await {
+        Ok(buf) => serde_json::from_slice(&buf.to_vec())
+            .map(Some)
+            .map_err(|e| OmniError::Serialization(format!("remote manifest: {e}"))),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Upload `files` (remote name, local path) to `op`, recording `metadata`
+/// in the manifest.
+///
+/// Blocks already referenced by the remote manifest are skipped. The new
+/// manifest replaces the old one and retires the blocks only the old one
+/// referenced; blocks the old manifest had already retired are deleted.
+pub async fn push(
+    op: &Operator,
+    files: &[(&str, PathBuf)],
+    metadata: &IndexMetadata,
+) -> OmniResult<SyncReport> {
+    let old = read_manifest(op).await?.unwrap_or_default();
+    let mut uploaded: HashSet<String> = old.blocks().into_iter().map(str::to_string).collect();
+    // Retired blocks may still be on the remote; re-uploading one that comes
+    // back is cheaper than tracking which of them a previous push deleted.
+    let mut manifest = RemoteManifest {
+        metadata: metadata.clone(),
+        ..RemoteManifest::default()
+    };
+    let mut report = SyncReport::default();
+
+    for (name, path) in files {
+        let mut file = std::fs::File::open(path)?;
+        let mut hashes = Vec::new();
+        while let Some(block) = read_block(&mut file)? {
+            let hash = block_hash(&block);
+            if uploaded.insert(hash.clone()) {
+                report.blocks_transferred += 1;
+                report.bytes_transferred += block.len() as u64;
+                op.write(&block_key(&hash), block).await?;
+            }
+            hashes.push(hash);
+        }
+        report.files += 1;
+        report.blocks_total += hashes.len();
+        manifest.files.insert((*name).to_string(), hashes);
+    }
+
+    let live = manifest.blocks();
+    let mut retired: Vec<String> = old
+        .blocks()
+        .difference(&live)
+        .map(|hash| (*hash).to_string())
+        .collect();
+    retired.sort_unstable();
+    let expired: Vec<String> = old
+        .retired
+        .iter()
+        .filter(|hash| !live.contains(hash.as_str()) && retired.binary_search(*hash).is_err())
+        .cloned()
+        .collect();
+    manifest.retired = retired;
+
+    let json = serde_json::to_vec_pretty(&manifest)
+        .map_err(|e| OmniError::Serialization(format!("remote manifest: {e}")))?;
+    op.write(MANIFEST_NAME, json).await?;
+
+    for stale in &expired {
+        if let Err(e) = op.delete(&block_key(stale)).await {
+            tracing::warn!(block = %stale, error = %e, "failed to delete stale remote block");
+        }
+    }
+    Ok(report)
+}
+
+/// Download every file in the remote manifest into `dest_dir`.
+///
+/// Blocks already present in the local copy of a file are reused. Each file
+/// is assembled next to its destination and renamed into place. SQLite's
+/// `-wal` and `-shm` files belong to the replaced database, so they are
+/// removed first; replaying the old log over the new snapshot would corrupt
+/// it.
+pub async fn pull(op: &Operator, dest_dir: &Path) -> OmniResult<SyncReport> {
+    let manifest = read_manifest(op)
+        .await?
+        .ok_or_else(|| OmniError::NotFound {
+            entity: format!("remote index manifest ({MANIFEST_NAME})"),
+        })?;
+    std::fs::create_dir_all(dest_dir)?;
+    let mut report = SyncReport::default();
+
+    for (name, hashes) in &manifest.files {
+        if !SYNCED_FILES.contains(&name.as_str()) {
+            return Err(OmniError::Serialization(format!(
+                "remote manifest lists unexpected file {name:?}"
+            )));
+        }
+        let dest = dest_dir.join(name);
+        let mut local = LocalBlocks::open(&dest)?;
+        let tmp = dest_dir.join(format!("{name}.pull"));
+        let mut out = std::fs::File::create(&tmp)?;
+
+        for hash in hashes {
+            let block = if let Some(block) = local.read(hash)? {
+                block
+            } else {
+                let block = op.read(&block_key(hash)).await?.to_vec();
+                if block_hash(&block) != *hash {
+                    return Err(OmniError::DatabaseCorruption {
+                        details: format!("remote block {hash} does not match its hash"),
+                    });
+                }
+                report.blocks_transferred += 1;
+                report.bytes_transferred += block.len() as u64;
+                block
+            };
+            out.write_all(&block)?;
+        }
+        out.sync_all()?;
+        drop(out);
+        drop(local);
+        for suffix in ["-wal", "-shm"] {
+            match std::fs::remove_file(dest_dir.join(format!("{name}{suffix}"))) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+                _ => {}
+            }
+        }
+        std::fs::rename(&tmp, &dest)?;
+
+        report.files += 1;
+        report.blocks_total += hashes.len();
+    }
+    Ok(report)
+}
+
+/// Blocks of an existing local file, readable by hash.
+struct LocalBlocks {
+    file: Option<std::fs::File>,
+    offsets: HashMap<String, u64>,
+}
+
+impl LocalBlocks {
+    fn open(path: &Path) -> OmniResult<Self> {
+        let mut offsets = HashMap::new();
+        let Ok(mut file) = std::fs::File::open(path) else {
+            return Ok(Self {
+                file: None,
+                offsets,
+            });
+        };
+        let mut offset = 0u64;
+        while let Some(block) = read_block(&mut file)? {
+            offsets.entry(block_hash(&block)).or_insert(offset);
+            offset += block.len() as u64;
+        }
+        Ok(Self {
+            file: Some(file),
+            offsets,
+        })
+    }
+
+    fn read(&mut self, hash: &str) -> OmniResult<Option<Vec<u8>>> {
+        let (Some(file), Some(&offset)) = (self.file.as_mut(), self.offsets.get(hash)) else {
+            return Ok(None);
+        };
+        file.seek(SeekFrom::Start(offset))?;
+        Ok(read_block(file)?.filter(|block| block_hash(block) == hash))
+    }
+}
+
+/// Read the next block, or `None` at end of file.
+fn read_block(reader: &mut impl Read) -> OmniResult<Option<Vec<u8>>> {
+    let mut block = Vec::with_capacity(BLOCK_SIZE);
+    reader.take(BLOCK_SIZE as u64).read_to_end(&mut block)?;
+    Ok((!block.is_empty()).then_some(block))
+}
+
+fn block_hash(block: &[u8]) -> String {
+    hex::encode(Sha256::digest(block))
+}
+
+fn block_key(hash: &str) -> String {
+    format!("blocks/{hash}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fs_operator(dir: &Path) -> Operator {
+        open_location(&format!("file://{}", dir.display())).expect("fs operator")
+    }
+
+    #[test]
+    fn test_open_location_rejects_bad_urls() {
+        for url in [
+            "bucket/prefix", "ftp://host/x", "s3:///prefix", "file://relative",
+        ] {
+            assert!(
+                matches!(open_location(url), Err(OmniError::Config { .. })),
+                "{url}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_push_and_pull_transfer_only_changed_blocks() {
+        let remote = tempfile::tempdir().expect("remote dir");
+        let src = tempfile::tempdir().expect("src dir");
+        let dst = tempfile::tempdir().expect("dst dir");
+        let op = fs_operator(remote.path());
+
+        #[allow(clippy::cast_possible_truncation)]
+        let mut index: Vec<u8> = (0..BLOCK_SIZE * 2 + 10).map(|i| (i % 251) as u8).collect();
+        std::fs::write(src.path().join("index.db"), &index).expect("write index");
+        std::fs::write(src.path().join("vectors.bin"), b"vectors").expect("write vectors");
+        let files = [
+            ("index.db", src.path().join("index.db")),
+            ("vectors.bin", src.path().join("vectors.bin")),
+        ];
+
+        let first = push(&op, &files, &IndexMetadata::default())
+            .await
+            .expect("push");
+        assert_eq!(first.files, 2);
+        assert_eq!(first.blocks_total, 4);
+        assert_eq!(first.blocks_transferred, 4);
+        assert_eq!(
+            push(&op, &files, &IndexMetadata::default())
+                .await
+                .expect("no-op push")
+                .blocks_transferred,
+            0
+        );
+
+        let pulled = pull(&op, dst.path()).await.expect("pull");
+        assert_eq!(pulled.blocks_transferred, 4);
+        assert_eq!(
+            std::fs::read(dst.path().join("index.db")).expect("read"),
+            index
+        );
+
+        // Touch only the tail block: one upload, one download.
+        *index.last_mut().expect("non-empty") = 9;
+        std::fs::write(src.path().join("index.db"), &index).expect("rewrite index");
+        let second = push(&op, &files, &IndexMetadata::default())
+            .await
+            .expect("push again");
+        assert_eq!(second.blocks_transferred, 1);
+        let repulled = pull(&op, dst.path()).await.expect("pull again");
+        assert_eq!(repulled.blocks_transferred, 1);
+        assert_eq!(
+            std::fs::read(dst.path().join("index.db")).expect("read"),
+            index
+        );
+
+        // The superseded tail block outlives one push for in-flight pulls,
+        // then the next push deletes it.
+        let count_blocks = || {
+            std::fs::read_dir(remote.path().join("blocks"))
+                .expect("blocks dir")
+                .count()
+        };
+        let manifest = read_manifest(&op)
+            .await
+            .expect("manifest")
+            .expect("present");
+        assert_eq!(manifest.retired.len(), 1);
+        assert_eq!(count_blocks(), manifest.blocks().len() + 1);
+        push(&op, &files, &IndexMetadata::default())
+            .await
+            .expect("third push");
+        let manifest = read_manifest(&op)
+            .await
+            .expect("manifest")
+            .expect("present");
+        assert!(manifest.retired.is_empty());
+        assert_eq!(count_blocks(), manifest.blocks().len());
+    }
+
+    #[tokio::test]
+    async fn test_pull_removes_stale_wal_files() {
+        let remote = tempfile::tempdir().expect("remote dir");
+        let src = tempfile::tempdir().expect("src dir");
+        let dst = tempfile::tempdir().expect("dst dir");
+        let op = fs_operator(remote.path());
+
+        std::fs::write(src.path().join("index.db"), b"snapshot").expect("write index");
+        push(
+            &op,
+            &[("index.db", src.path().join("index.db"))],
+            &IndexMetadata::default(),
+        )
+        .await
+        .expect("push");
+        std::fs::write(dst.path().join("index.db-wal"), b"old log").expect("write wal");
+        std::fs::write(dst.path().join("index.db-shm"), b"old shm").expect("write shm");
+
+        pull(&op, dst.path()).await.expect("pull");
+        assert!(!dst.path().join("index.db-wal").exists());
+        assert!(!dst.path().join("index.db-shm").exists());
+    }
+
+    #[tokio::test]
+    async fn test_pull_without_manifest_is_not_found() {
+        let remote = tempfile::tempdir().expect("remote dir");
+        let dst = tempfile::tempdir().expect("dst dir");
+        let err = pull(&fs_operator(remote.path()), dst.path())
+            .await
+            .expect_err("nothing pushed");
+        assert!(matches!(err, OmniError::NotFound { .. }));
+    }
+}
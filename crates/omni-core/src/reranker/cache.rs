@@ -0,0 +1,71 @@
+//! LRU cache of cross-encoder scores.
+//!
+//! An agent session reruns near-identical searches, so the same (query,
+//! chunk) pairs reach the reranker again and again. Scores are keyed by the
+//! hashes of the query and the document text: editing a chunk changes its
+//! key, so a stale score is never returned and simply ages out.
+
+use std::num::NonZeroUsize;
+
+use lru::LruCache;
+use parking_lot::Mutex;
+use xxhash_rust::xxh3::xxh3_64;
+
+/// Cached scores keyed by (query hash, document hash).
+pub(super) struct ScoreCache {
+    entries: Mutex<LruCache<(u64, u64), f32>>,
+}
+
+impl ScoreCache {
+    /// A cache holding up to `capacity` scores; `None` when `capacity` is 0.
+    pub(super) fn new(capacity: usize) -> Option<Self> {
+        Some(Self {
+            entries: Mutex::new(LruCache::new(NonZeroUsize::new(capacity)?)),
+        })
+    }
+
+    /// Cache key of `query` and `document`.
+    pub(super) fn key(query: &str, document: &str) -> (u64, u64) {
+        (xxh3_64(query.as_bytes()), xxh3_64(document.as_bytes()))
+    }
+
+    /// Cached score for each key, in order.
+    pub(super) fn get_many(&self, keys: &[(u64, u64)]) -> Vec<Option<f32>> {
+        let mut entries = self.entries.lock();
+        keys.iter().map(|key| entries.get(key).copied()).collect()
+    }
+
+    /// Remember `score` for `key`.
+    pub(super) fn insert(&self, key: (u64, u64), score: f32) {
+        self.entries.lock().put(key, score);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scores_keyed_by_query_and_content() {
+        assert!(ScoreCache::new(0).is_none());
+        let cache = ScoreCache::new(2).expect("cache");
+
+        let a = ScoreCache::key("parse config", "fn parse() {}");
+        let edited = ScoreCache::key("parse config", "fn parse() { todo!() }");
+        let other_query = ScoreCache::key("load config", "fn parse() {}");
+        cache.insert(a, 0.9);
+        assert_eq!(
+            cache.get_many(&[a, edited, other_query]),
+            vec![Some(0.9), None, None]
+        );
+
+        // Least recently used is evicted.
+        cache.insert(edited, 0.4);
+        cache.get_many(&[a]);
+        cache.insert(other_query, 0.1);
+        assert_eq!(
+            cache.get_many(&[a, edited, other_query]),
+            vec![Some(0.9), None, Some(0.1)]
+        );
+    }
+}
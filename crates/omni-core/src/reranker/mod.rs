@@ -28,11 +28,16 @@
     clippy::missing_errors_doc
 )]
 
+mod cache;
+
 use std::sync::Mutex;
 
 use ort::session::Session;
 
+use self::cache::ScoreCache;
+
 use crate::embedder::model_manager;
+use crate::embedder::shared::SharedModels;
 use crate::error::{OmniError, OmniResult};
 
 pub struct Reranker {
@@ -41,6 +46,10 @@ pub struct Reranker {
     max_seq_length: usize,
     #[allow(dead_code)]
     batch_size: usize,
+    /// Duration of the last rerank call in microseconds; 0 until one has run.
+    last_latency_us: std::sync::atomic::AtomicU64,
+    /// Scores of recently reranked pairs; `None` when `cache_size` is 0.
+    cache: Option<ScoreCache>,
 }
 
 impl Reranker {
@@ -103,6 +112,8 @@ impl Reranker {
             tokenizer,
             max_seq_length: config.max_seq_length,
             batch_size: config.batch_size,
+            last_latency_us: std::sync::atomic::AtomicU64::new(0),
+            cache: ScoreCache::new(config.cache_size),
         })
     }
 
@@ -112,6 +123,8 @@ impl Reranker {
             tokenizer: None,
             max_seq_length: config.max_seq_length,
             batch_size: config.batch_size,
+            last_latency_us: std::sync::atomic::AtomicU64::new(0),
+            cache: ScoreCache::new(config.cache_size),
         }
     }
 
@@ -172,15 +185,103 @@ impl Reranker {
         self.session.is_some() && self.tokenizer.is_some()
     }
 
+    /// The reranker for `config`, shared with every other engine in the
+    /// process. Only the settings the model is built with select the
+    /// instance; the scoring settings are passed per call.
+    pub fn shared(config: &crate::config::RerankerConfig) -> OmniResult<std::sync::Arc<Self>> {
+        static RERANKERS: SharedModels<Reranker> = SharedModels::new();
+        let key = (config.max_seq_length, config.batch_size, config.cache_size);
+        RERANKERS.get_or_load(&key, || Self::new(config), Self::is_available)
+    }
+
+    /// Score one throwaway pair so the first real rerank does not pay for
+    /// ONNX Runtime's lazy initialization.
+    ///
+    /// Not recorded as rerank latency. Returns whether the model answered.
+    pub fn warm_up(&self) -> bool {
+        let Some(session) = self.session.as_ref().filter(|_| self.is_available()) else {
+            return false;
+        };
+        let Ok(mut session) = session.lock() else {
+            return false;
+        };
+        self.run_inference(&mut session, "main", &["fn main() {}"])
+            .inspect_err(|e| tracing::warn!(error = %e, "reranker warm-up failed"))
+            .is_ok()
+    }
+
+    /// Load state, size on disk, and last rerank latency of the
+    /// cross-encoder.
+    pub fn health(&self) -> crate::types::ModelHealth {
+        let spec = &model_manager::RERANKER_MODEL;
+        let unavailable_reason = if self.is_available() {
+            None
+        } else if std::env::var("OMNI_DISABLE_RERANKER").is_ok() {
+            Some("disabled because OMNI_DISABLE_RERANKER is set".to_string())
+        } else if std::env::var("OMNI_SKIP_MODEL_DOWNLOAD").is_ok() {
+            Some("model loading skipped because OMNI_SKIP_MODEL_DOWNLOAD is set".to_string())
+        } else if !model_manager::is_model_ready(spec) {
+            Some(format!(
+                "model {} is not downloaded; run `omnicontext setup reranker-download`",
+                spec.name
+            ))
+        } else if self.tokenizer.is_none() {
+            Some("tokenizer failed to load; see the log for details".to_string())
+        } else {
+            Some("ONNX session failed to load; see the log for details".to_string())
+        };
+        crate::types::ModelHealth {
+            available: self.is_available(),
+            name: spec.name.to_string(),
+            revision: spec.revision().to_string(),
+            size_bytes: model_manager::installed_size(spec),
+            last_latency_ms: crate::embedder::last_latency_ms(&self.last_latency_us),
+            unavailable_reason,
+        }
+    }
+
     /// Rerank documents against a query using the cross-encoder.
     ///
     /// Returns a relevance score in [0, 1] for each document (sigmoid-activated).
-    /// Returns `None` for documents that fail to score.
+    /// Returns `None` for documents that fail to score. Scores of pairs seen
+    /// recently are served from the cache (`reranker.cache_size`).
     pub fn rerank(&self, query: &str, documents: &[&str]) -> Vec<Option<f32>> {
         if !self.is_available() {
             return vec![None; documents.len()];
         }
+        let Some(cache) = self.cache.as_ref() else {
+            return self.rerank_uncached(query, documents);
+        };
+
+        let keys: Vec<(u64, u64)> = documents
+            .iter()
+            .map(|document| ScoreCache::key(query, document))
+            .collect();
+        let mut scores = cache.get_many(&keys);
+        let misses: Vec<usize> = (0..documents.len())
+            .filter(|&i| scores[i].is_none())
+            .collect();
+        tracing::debug!(
+            hits = documents.len() - misses.len(),
+            misses = misses.len(),
+            "reranker cache"
+        );
+        if misses.is_empty() {
+            return scores;
+        }
+
+        let texts: Vec<&str> = misses.iter().map(|&i| documents[i]).collect();
+        for (&i, score) in misses.iter().zip(self.rerank_uncached(query, &texts)) {
+            if let Some(score) = score {
+                cache.insert(keys[i], score);
+            }
+            scores[i] = score;
+        }
+        scores
+    }
 
+    /// [`Self::rerank`] without the cache.
+    fn rerank_uncached(&self, query: &str, documents: &[&str]) -> Vec<Option<f32>> {
         let session_mutex = match self.session.as_ref() {
             Some(s) => s,
             None => return vec![None; documents.len()],
@@ -191,6 +292,7 @@ impl Reranker {
             Err(_) => return vec![None; documents.len()],
         };
 
+        let started = std::time::Instant::now();
         let mut scores = Vec::with_capacity(documents.len());
 
         for batch in documents.chunks(self.batch_size) {
@@ -209,6 +311,7 @@ impl Reranker {
             }
         }
 
+        crate::embedder::record_latency(&self.last_latency_us, started);
         scores
     }
 
@@ -238,6 +341,7 @@ impl Reranker {
             Err(_) => return vec![None; documents.len()],
         };
 
+        let started = std::time::Instant::now();
         let mut scores = Vec::with_capacity(documents.len());
         let mut should_continue = true;
 
@@ -283,6 +387,7 @@ impl Reranker {
             }
         }
 
+        crate::embedder::record_latency(&self.last_latency_us, started);
         scores
     }
 
@@ -0,0 +1,174 @@
+//! Pluggable query classification and keyword expansion.
+//!
+//! The [`SearchEngine`](super::SearchEngine) asks a [`QueryAnalyzer`] two
+//! things about every query:
+//!
+//! 1. its [`QueryType`], which sets the per-signal retrieval depths and
+//!    fusion weights;
+//! 2. the keyword (FTS5) expression to run in place of the raw query.
+//!
+//! [`DefaultQueryAnalyzer`] holds the built-in English heuristics. Library
+//! consumers whose queries don't fit them (other natural languages, ticket
+//! IDs, domain jargon) can install their own analyzer with
+//! [`Engine::set_query_analyzer`](crate::pipeline::Engine::set_query_analyzer),
+//! typically wrapping the default and overriding one method.
+
+use crate::config::SearchConfig;
+
+use super::{expand_query, stem_terms, synonyms, StopWords};
+
+/// Classification of a search query for routing to appropriate signals.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueryType {
+    /// Looks like a symbol name (e.g., "authenticate_user", "Config.new")
+    Symbol,
+    /// Short keyword search (1-2 words, no natural language structure)
+    Keyword,
+    /// Natural language question ("how does authentication work?")
+    NaturalLanguage,
+    /// Mixed -- could be either (e.g., "user authentication function")
+    Mixed,
+}
+
+/// Classifies queries and rewrites them for keyword search.
+pub trait QueryAnalyzer: Send + Sync {
+    /// Classify the query.
+    fn classify(&self, query: &str) -> QueryType;
+
+    /// FTS5 expression to run instead of `query`, or `None` to run the query
+    /// as written. The expression may use the syntax accepted by
+    /// [`fts_query::build`](crate::index::fts_query::build).
+    fn expand(&self, query: &str, query_type: QueryType) -> Option<String>;
+}
+
+/// The built-in analyzer: English question-word heuristics for
+/// classification, and stopword removal, identifier splitting, synonyms, and
+/// stemmed prefixes for expansion.
+#[derive(Debug, Clone)]
+pub struct DefaultQueryAnalyzer {
+    stop_words: StopWords,
+    stemming: bool,
+}
+
+impl DefaultQueryAnalyzer {
+    /// Build the default analyzer from `[search]` settings.
+    pub fn from_config(config: &SearchConfig) -> Self {
+        Self {
+            stop_words: StopWords::new(&config.stopwords),
+            stemming: config.stemming,
+        }
+    }
+}
+
+impl Default for DefaultQueryAnalyzer {
+    fn default() -> Self {
+        Self::from_config(&SearchConfig::default())
+    }
+}
+
+impl QueryAnalyzer for DefaultQueryAnalyzer {
+    fn classify(&self, query: &str) -> QueryType {
+        analyze_query(query)
+    }
+
+    fn expand(&self, query: &str, query_type: QueryType) -> Option<String> {
+        // Queries using explicit FTS syntax (phrases, AND/OR/NOT, prefix*)
+        // are passed through untouched.
+        if crate::index::fts_query::has_syntax(query)
+            || !matches!(query_type, QueryType::NaturalLanguage | QueryType::Mixed)
+        {
+            return None;
+        }
+
+        let mut expanded = expand_query(query, &self.stop_words);
+        // Add code vocabulary synonyms
+        let syns = synonyms::expand_with_synonyms(query);
+        if !syns.is_empty() {
+            tracing::debug!(synonyms = ?syns, "synonym expansion applied");
+            expanded.push(' ');
+            expanded.push_str(&syns.join(" "));
+        }
+        if self.stemming {
+            for stem in stem_terms(query, &self.stop_words) {
+                expanded.push_str(" OR ");
+                expanded.push_str(&stem);
+                expanded.push('*');
+            }
+        }
+        Some(expanded)
+    }
+}
+
+/// Analyze a query string to determine the best search strategy.
+pub(super) fn analyze_query(query: &str) -> QueryType {
+    let trimmed = query.trim();
+
+    if trimmed.is_empty() {
+        return QueryType::Keyword;
+    }
+
+    // Symbol-like: contains :: or . separators, or is camelCase/snake_case without spaces
+    if !trimmed.contains(' ') {
+        if trimmed.contains("::") || trimmed.contains('.') || trimmed.contains("__") {
+            return QueryType::Symbol;
+        }
+        // Single word -- check if it looks like an identifier
+        if trimmed.chars().all(|c| c.is_alphanumeric() || c == '_') {
+            return QueryType::Symbol;
+        }
+    }
+
+    // Natural language: starts with question words, ends with ?, or has many words
+    let lower = trimmed.to_lowercase();
+    let words: Vec<&str> = trimmed.split_whitespace().collect();
+
+    if lower.ends_with('?')
+        || lower.starts_with("how ")
+        || lower.starts_with("what ")
+        || lower.starts_with("where ")
+        || lower.starts_with("why ")
+        || lower.starts_with("when ")
+        || lower.starts_with("which ")
+        || lower.starts_with("find ")
+        || lower.starts_with("show ")
+    {
+        return QueryType::NaturalLanguage;
+    }
+
+    // Short queries (1-3 words) that aren't questions are mixed
+    if words.len() <= 3 {
+        return QueryType::Mixed;
+    }
+
+    // Longer queries are likely natural language
+    QueryType::NaturalLanguage
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_expand_skips_symbols_and_fts_syntax() {
+        let analyzer = DefaultQueryAnalyzer::default();
+        assert_eq!(analyzer.expand("Config::new", QueryType::Symbol), None);
+        assert_eq!(analyzer.expand("\"token refresh\"", QueryType::Mixed), None);
+        let expanded = analyzer
+            .expand("pooled connections", QueryType::Mixed)
+            .expect("expanded");
+        assert!(expanded.contains("connection*"), "{expanded}");
+    }
+
+    #[test]
+    fn test_default_expand_respects_stemming_toggle() {
+        let config = SearchConfig {
+            stemming: false,
+            ..SearchConfig::default()
+        };
+        let analyzer = DefaultQueryAnalyzer::from_config(&config);
+        let expanded = analyzer
+            .expand("pooled connections", QueryType::Mixed)
+            .expect("expanded");
+        assert!(!expanded.contains('*'), "{expanded}");
+    }
+}
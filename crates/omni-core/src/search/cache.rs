@@ -573,11 +573,16 @@ mod tests {
                 weight: 1.0,
                 vector_id: None,
                 is_summary: false,
+                is_generated: false,
+                language: None,
                 content_hash: 0,
+                complexity: crate::types::ComplexityMetrics::default(),
             },
             file_path: PathBuf::from("test.rs"),
             score,
+            relevance: score.min(1.0),
             score_breakdown: ScoreBreakdown::default(),
+            owners: Vec::new(),
         }
     }
 
@@ -0,0 +1,105 @@
+//! Score calibration to a 0–1 relevance scale.
+//!
+//! A result's `score` only orders results within one query: raw RRF sums sit
+//! around 0.01–0.05, blending in the reranker moves them onto another scale,
+//! and structural and graph boosts multiply them again. [`relevance`] instead
+//! maps the evidence behind a result to a value in `[0, 1]` that reads the
+//! same across queries:
+//!
+//! - Each retrieval signal (keyword, semantic, symbol, sparse) that ranked
+//!   the result contributes `10 / (9 + rank)`: 1.0 at rank 1, 0.5 at rank 11.
+//! - Signals combine as a noisy-OR, each at most [`SIGNAL_STRENGTH`]: a top
+//!   hit from one signal gives 0.6, top hits from two agreeing signals 0.84.
+//! - When the cross-encoder scored the result, its probability is blended in
+//!   at [`RERANKER_WEIGHT`]; it is the one signal that judges the query and
+//!   the code together.
+//!
+//! Boosts for structure, recency, and the current branch change what to read
+//! first, not whether it matches, so they are left out. A negative query's
+//! penalty does apply: the caller said the result is not what they want. A relevance below
+//! [`PROBABLY_IRRELEVANT`] means the result is probably irrelevant.
+
+use crate::types::ScoreBreakdown;
+
+/// Relevance below which a result is probably irrelevant: without the
+/// reranker, only one signal found it and ranked it below 11th.
+pub const PROBABLY_IRRELEVANT: f64 = 0.3;
+
+/// Largest relevance one retrieval signal can contribute on its own.
+pub const SIGNAL_STRENGTH: f64 = 0.6;
+
+/// Share of the cross-encoder probability in the relevance, when present.
+pub const RERANKER_WEIGHT: f64 = 0.7;
+
+/// Rank at which a signal's contribution halves, counted from rank 1.
+const RANK_HALF_LIFE: f64 = 10.0;
+
+/// Calibrated relevance in `[0, 1]` for a result with `breakdown`.
+pub fn relevance(breakdown: &ScoreBreakdown) -> f64 {
+    let miss = [
+        breakdown.keyword_rank,
+        breakdown.semantic_rank,
+        breakdown.symbol_rank,
+        breakdown.sparse_rank,
+    ]
+    .into_iter()
+    .flatten()
+    .map(|rank| {
+        let confidence = RANK_HALF_LIFE / (RANK_HALF_LIFE - 1.0 + f64::from(rank.max(1)));
+        1.0 - SIGNAL_STRENGTH * confidence
+    })
+    .product::<f64>();
+    let retrieval = 1.0 - miss;
+
+    let relevance = match breakdown.reranker_probability {
+        Some(p) => (1.0 - RERANKER_WEIGHT) * retrieval + RERANKER_WEIGHT * p,
+        None => retrieval,
+    };
+    let suppressed = relevance * (1.0 - breakdown.negative_penalty.unwrap_or(0.0));
+    suppressed.clamp(0.0, 1.0)
+}
+
+/// Whether `relevance` falls below [`PROBABLY_IRRELEVANT`].
+pub fn is_probably_irrelevant(relevance: f64) -> bool {
+    relevance < PROBABLY_IRRELEVANT
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ranks(keyword: Option<u32>, semantic: Option<u32>) -> ScoreBreakdown {
+        ScoreBreakdown {
+            keyword_rank: keyword,
+            semantic_rank: semantic,
+            ..ScoreBreakdown::default()
+        }
+    }
+
+    #[test]
+    fn test_relevance_from_signal_ranks() {
+        assert!(relevance(&ScoreBreakdown::default()).abs() < 1e-9);
+        assert!((relevance(&ranks(Some(1), None)) - 0.6).abs() < 1e-9);
+        assert!((relevance(&ranks(Some(1), Some(1))) - 0.84).abs() < 1e-9);
+        assert!((relevance(&ranks(Some(11), None)) - 0.3).abs() < 1e-9);
+
+        let deep = relevance(&ranks(Some(40), None));
+        assert!(is_probably_irrelevant(deep), "{deep}");
+        assert!(relevance(&ranks(Some(2), None)) < relevance(&ranks(Some(1), None)));
+    }
+
+    #[test]
+    fn test_reranker_probability_dominates() {
+        let confident = ScoreBreakdown {
+            reranker_probability: Some(0.95),
+            ..ranks(Some(30), None)
+        };
+        let rejected = ScoreBreakdown {
+            reranker_probability: Some(0.02),
+            ..ranks(Some(1), Some(1))
+        };
+        assert!(relevance(&confident) > relevance(&rejected));
+        assert!(is_probably_irrelevant(relevance(&rejected)));
+        assert!(relevance(&confident) <= 1.0);
+    }
+}
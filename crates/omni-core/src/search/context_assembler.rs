@@ -6,7 +6,9 @@
 
 use std::collections::HashMap;
 use std::path::PathBuf;
+use std::sync::Arc;
 
+use crate::chunker::token_counter::TokenCounter;
 use crate::graph::dependencies::FileDependencyGraph;
 use crate::search::intent::{ContextStrategy, QueryIntent};
 use crate::types::{Chunk, ChunkPriority, ContextEntry, ContextWindow, SearchResult};
@@ -15,12 +17,33 @@ use crate::types::{Chunk, ChunkPriority, ContextEntry, ContextWindow, SearchResu
 pub struct ContextAssembler {
     /// Token budget for the context window.
     token_budget: u32,
+    /// Tokenizer of the consuming model. When set, chunk sizes are
+    /// re-measured with it instead of trusting index-time counts.
+    counter: Option<Arc<dyn TokenCounter>>,
 }
 
 impl ContextAssembler {
     /// Create a new context assembler with the given token budget.
     pub fn new(token_budget: u32) -> Self {
-        Self { token_budget }
+        Self {
+            token_budget,
+            counter: None,
+        }
+    }
+
+    /// Measure the budget with `counter` (the consuming model's tokenizer).
+    #[must_use]
+    pub fn with_counter(mut self, counter: Arc<dyn TokenCounter>) -> Self {
+        self.counter = Some(counter);
+        self
+    }
+
+    /// Tokens in `content` under the assembler's counter.
+    fn count_tokens(&self, content: &str) -> u32 {
+        match &self.counter {
+            Some(counter) => counter.count(content),
+            None => (content.len() / 4).max(1) as u32,
+        }
     }
 
     /// Assemble a context window from search results.
@@ -128,6 +151,7 @@ impl ContextAssembler {
                     file_path: result.file_path,
                     chunk: result.chunk,
                     score: result.score,
+                    relevance: result.relevance,
                     is_graph_neighbor: false,
                     priority: Some(priority),
                     shadow_header: None,
@@ -198,6 +222,9 @@ impl ContextAssembler {
         let mut items: Vec<ContextEntry> = Vec::with_capacity(entries.len());
         for mut entry in entries {
             let priority = entry.priority.unwrap_or(ChunkPriority::Low);
+            if self.counter.is_some() {
+                entry.chunk.token_count = self.count_tokens(&entry.chunk.content);
+            }
 
             // Try compressed form if chunk is too large
             if entry.chunk.token_count > budget / 2 && priority != ChunkPriority::Critical {
@@ -388,8 +415,7 @@ impl ContextAssembler {
             }
         };
 
-        // Estimate new token count
-        let new_token_count = (compressed_content.len() / 4).max(1) as u32;
+        let new_token_count = self.count_tokens(&compressed_content);
 
         Chunk {
             id: chunk.id,
@@ -405,7 +431,10 @@ impl ContextAssembler {
             weight: chunk.weight,
             vector_id: chunk.vector_id,
             is_summary: chunk.is_summary,
+            is_generated: chunk.is_generated,
+            language: chunk.language,
             content_hash: chunk.content_hash,
+            complexity: chunk.complexity,
         }
     }
 }
@@ -456,16 +485,14 @@ pub fn apply_causal_ordering(
     // --- Step B: Determine cross-file ordering ---
     // Anchor = file of the highest-scored entry.  Entries may arrive in any
     // order (e.g. heap-popped), so scan all entries for the true maximum.
-    let anchor_file = entries
-        .iter()
-        .max_by(|a, b| {
-            a.score
-                .partial_cmp(&b.score)
-                .unwrap_or(std::cmp::Ordering::Equal)
-        })
-        .expect("non-empty vec guaranteed above")
-        .file_path
-        .clone();
+    let Some(anchor) = entries.iter().max_by(|a, b| {
+        a.score
+            .partial_cmp(&b.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    }) else {
+        return;
+    };
+    let anchor_file = anchor.file_path.clone();
 
     // Build distance map: file_path → hop distance from anchor
     let distance_map: HashMap<PathBuf, usize> = if let Some(graph) = dep_graph {
@@ -563,7 +590,10 @@ mod tests {
             weight: 0.85,
             vector_id: Some(1),
             is_summary: false,
+            is_generated: false,
+            language: None,
             content_hash: 0,
+            complexity: crate::types::ComplexityMetrics::default(),
         }
     }
 
@@ -572,7 +602,9 @@ mod tests {
             chunk,
             file_path: PathBuf::from("test.rs"),
             score,
+            relevance: score.min(1.0),
             score_breakdown: ScoreBreakdown::default(),
+            owners: Vec::new(),
         }
     }
 
@@ -646,6 +678,40 @@ mod tests {
         assert!(context.total_tokens <= 150);
     }
 
+    #[test]
+    fn test_counter_remeasures_index_time_counts() {
+        /// One token per byte: far more than the stored counts claim.
+        struct ByteCounter;
+        impl TokenCounter for ByteCounter {
+            fn count(&self, text: &str) -> u32 {
+                text.len() as u32
+            }
+            fn name(&self) -> &'static str {
+                "bytes"
+            }
+        }
+
+        let results = || {
+            vec![
+                make_test_result(make_test_chunk("fn a() { }", 1), 0.9),
+                make_test_result(
+                    make_test_chunk("fn bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb() { }", 1),
+                    0.8,
+                ),
+            ]
+        };
+
+        // Debug intent packs into 60% of the budget: 30 tokens.
+        let stored = ContextAssembler::new(50).assemble("fix the bug", results(), None, None);
+        assert_eq!(stored.entries.len(), 2);
+
+        let counted = ContextAssembler::new(50)
+            .with_counter(Arc::new(ByteCounter))
+            .assemble("fix the bug", results(), None, None);
+        assert_eq!(counted.entries.len(), 1);
+        assert_eq!(counted.total_tokens, 10);
+    }
+
     #[test]
     fn test_compress_high_priority() {
         let assembler = ContextAssembler::new(1000);
@@ -744,7 +810,7 @@ mod tests {
 
         // Create enough results to exceed budget (high scores -> High/Critical priority)
         let results: Vec<SearchResult> = (0..20)
-            .map(|i| make_test_result(make_test_chunk(&format!("fn f_{i}() {{ }}",), 80), 0.9))
+            .map(|i| make_test_result(make_test_chunk(&format!("fn f_{i}() {{ }}"), 80), 0.9))
             .collect();
 
         // Debug query -> 60% budget = 600 tokens
@@ -831,12 +897,16 @@ mod tests {
             weight: 1.0,
             vector_id: None,
             is_summary: false,
+            is_generated: false,
+            language: None,
             content_hash: 0,
+            complexity: crate::types::ComplexityMetrics::default(),
         };
         ContextEntry {
             file_path: PathBuf::from(file),
             chunk,
             score,
+            relevance: score.min(1.0),
             is_graph_neighbor: false,
             priority: Some(ChunkPriority::High),
             shadow_header: None,
@@ -931,7 +1001,7 @@ mod tests {
         // x.rs chunks should be sorted by line within the file group
         let x_entries: Vec<_> = entries
             .iter()
-            .filter(|e| e.file_path == PathBuf::from("src/x.rs"))
+            .filter(|e| e.file_path == std::path::Path::new("src/x.rs"))
             .collect();
         assert_eq!(x_entries[0].chunk.line_start, 20);
         assert_eq!(x_entries[1].chunk.line_start, 80);
@@ -939,7 +1009,7 @@ mod tests {
         // y.rs chunks should be sorted by line within the file group
         let y_entries: Vec<_> = entries
             .iter()
-            .filter(|e| e.file_path == PathBuf::from("src/y.rs"))
+            .filter(|e| e.file_path == std::path::Path::new("src/y.rs"))
             .collect();
         assert_eq!(y_entries[0].chunk.line_start, 5);
         assert_eq!(y_entries[1].chunk.line_start, 60);
@@ -6,6 +6,7 @@
 //! - **Structured XML** — Clear tags with metadata for tool-use-capable models
 //! - **Compact** — Minimal framing, maximum code density
 //! - **Annotated Markdown** — Rich fenced code blocks with metadata
+//! - **Compact JSON** — One-line JSON for agent frameworks that parse context
 //!
 //! The formatter also supports:
 //! - **File grouping** — chunks from the same file are merged under one header
@@ -53,14 +54,56 @@ pub enum ContextFormat {
     /// ````markdown
     /// ## src/auth.rs (lines 10-45, relevance: 0.95)
     /// ```rust
-    /// fn validate(...) { ... }
+    /// 10 | fn validate(...) { ... }
     /// ```
     /// ````
     AnnotatedMarkdown,
+
+    /// Single-line JSON, grouped by file.
+    /// Best for agent frameworks that post-process context programmatically.
+    ///
+    /// ```json
+    /// {"query":"...","tokens":3200,"budget":8000,"files":[{"path":"src/auth.rs",
+    ///  "language":"rust","chunks":[{"lines":[10,45],"symbol":"auth::validate",
+    ///  "score":0.95,"relevance":0.84,"content":"fn validate(...) { ... }"}]}]}
+    /// ```
+    Json,
+}
+
+impl ContextFormat {
+    /// All formats, in the order they are listed to users.
+    pub const ALL: [Self; 4] = [
+        Self::AnnotatedMarkdown,
+        Self::StructuredXml,
+        Self::Json,
+        Self::Compact,
+    ];
+
+    /// Name used on the command line and in tool parameters.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::StructuredXml => "xml",
+            Self::Compact => "compact",
+            Self::AnnotatedMarkdown => "markdown",
+            Self::Json => "json",
+        }
+    }
+
+    /// Parse a format name (case-insensitive; `md` is accepted for Markdown).
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "xml" => Some(Self::StructuredXml),
+            "compact" | "text" => Some(Self::Compact),
+            "markdown" | "md" => Some(Self::AnnotatedMarkdown),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
 }
 
 /// Options for context formatting.
 #[derive(Debug, Clone)]
+#[allow(clippy::struct_excessive_bools)] // independent rendering toggles
 pub struct FormatOptions {
     /// Output format.
     pub format: ContextFormat,
@@ -74,6 +117,8 @@ pub struct FormatOptions {
     pub query: String,
     /// Maximum path depth to show (e.g., 2 = "auth/middleware.rs" instead of full path).
     pub path_depth: usize,
+    /// Whether to prefix Markdown code lines with their source line numbers.
+    pub line_numbers: bool,
 }
 
 impl Default for FormatOptions {
@@ -85,6 +130,7 @@ impl Default for FormatOptions {
             priority_sections: true,
             query: String::new(),
             path_depth: 0, // 0 = show full path
+            line_numbers: true,
         }
     }
 }
@@ -99,6 +145,7 @@ impl ContextFormatter {
             ContextFormat::StructuredXml => Self::format_xml(window, options),
             ContextFormat::Compact => Self::format_compact(window, options),
             ContextFormat::AnnotatedMarkdown => Self::format_markdown(window, options),
+            ContextFormat::Json => Self::format_json(window, options),
         }
     }
 
@@ -197,7 +244,7 @@ impl ContextFormatter {
             ));
         }
         if options.show_scores {
-            out.push_str(&format!(" relevance=\"{:.2}\"", entry.score));
+            out.push_str(&format!(" relevance=\"{:.2}\"", entry.relevance));
         }
         if entry.is_graph_neighbor {
             out.push_str(" source=\"graph\"");
@@ -288,7 +335,7 @@ impl ContextFormatter {
                         meta_parts.push(format!("`{}`", entry.chunk.symbol_path));
                     }
                     if options.show_scores {
-                        meta_parts.push(format!("relevance: {:.2}", entry.score));
+                        meta_parts.push(format!("relevance: {:.2}", entry.relevance));
                     }
                     if entry.is_graph_neighbor {
                         meta_parts.push("via graph".to_string());
@@ -300,12 +347,7 @@ impl ContextFormatter {
                         out.push_str(&format!("> {header}\n"));
                     }
 
-                    out.push_str(&format!("```{lang}\n"));
-                    out.push_str(&entry.chunk.content);
-                    if !entry.chunk.content.ends_with('\n') {
-                        out.push('\n');
-                    }
-                    out.push_str("```\n\n");
+                    push_fenced_code(&mut out, entry, &lang, options.line_numbers);
                 }
             }
         } else {
@@ -319,17 +361,58 @@ impl ContextFormatter {
                     "_lines {}-{}_\n",
                     entry.chunk.line_start, entry.chunk.line_end
                 ));
-                out.push_str(&format!("```{lang}\n"));
-                out.push_str(&entry.chunk.content);
-                if !entry.chunk.content.ends_with('\n') {
-                    out.push('\n');
-                }
-                out.push_str("```\n\n");
+                push_fenced_code(&mut out, entry, &lang, options.line_numbers);
             }
         }
 
         out
     }
+
+    /// Compact JSON: one line, entries grouped by file in rank order.
+    fn format_json(window: &ContextWindow, options: &FormatOptions) -> String {
+        let entries: Vec<_> = window.entries.iter().collect();
+        let files: Vec<serde_json::Value> = group_entries_by_file(&entries)
+            .into_iter()
+            .map(|(file_path, file_entries)| {
+                let chunks: Vec<serde_json::Value> = file_entries
+                    .iter()
+                    .map(|entry| {
+                        let mut chunk = serde_json::json!({
+                            "lines": [entry.chunk.line_start, entry.chunk.line_end],
+                            "symbol": entry.chunk.symbol_path,
+                            "content": entry.chunk.content,
+                        });
+                        if options.show_scores {
+                            chunk["score"] = serde_json::json!(entry.score);
+                            chunk["relevance"] = serde_json::json!(entry.relevance);
+                        }
+                        if entry.is_graph_neighbor {
+                            chunk["via_graph"] = serde_json::json!(true);
+                        }
+                        if let Some(ref header) = entry.shadow_header {
+                            chunk["header"] = serde_json::json!(header);
+                        }
+                        chunk
+                    })
+                    .collect();
+                serde_json::json!({
+                    "path": truncate_path(&file_path, options.path_depth),
+                    "language": detect_language(&file_path),
+                    "chunks": chunks,
+                })
+            })
+            .collect();
+
+        let mut root = serde_json::json!({
+            "tokens": window.total_tokens,
+            "budget": window.token_budget,
+            "files": files,
+        });
+        if !options.query.is_empty() {
+            root["query"] = serde_json::json!(options.query);
+        }
+        root.to_string()
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -355,6 +438,31 @@ fn group_entries_by_file<'a>(entries: &[&'a ContextEntry]) -> Vec<(String, Vec<&
     groups
 }
 
+/// Append a fenced code block for `entry`, optionally with line numbers.
+fn push_fenced_code(out: &mut String, entry: &ContextEntry, lang: &str, line_numbers: bool) {
+    // Embedded code (a fenced block in Markdown) is fenced as its own language.
+    let lang = entry.chunk.language.map_or(lang, |l| l.as_str());
+    out.push_str(&format!("```{lang}\n"));
+    if line_numbers {
+        let width = entry
+            .chunk
+            .line_end
+            .max(entry.chunk.line_start)
+            .to_string()
+            .len();
+        for (offset, line) in entry.chunk.content.lines().enumerate() {
+            let number = entry.chunk.line_start as usize + offset;
+            out.push_str(&format!("{number:>width$} | {line}\n"));
+        }
+    } else {
+        out.push_str(&entry.chunk.content);
+        if !entry.chunk.content.ends_with('\n') {
+            out.push('\n');
+        }
+    }
+    out.push_str("```\n\n");
+}
+
 /// Truncate a file path to show only the last N components.
 /// If `depth` is 0, return the full path.
 fn truncate_path(path: &str, depth: usize) -> String {
@@ -440,7 +548,10 @@ mod tests {
             weight: 1.0,
             vector_id: None,
             is_summary: false,
+            is_generated: false,
+            language: None,
             content_hash: 0,
+            complexity: crate::types::ComplexityMetrics::default(),
         }
     }
 
@@ -455,6 +566,7 @@ mod tests {
             file_path: PathBuf::from(path),
             chunk: test_chunk(1, symbol, content, 10, 20),
             score,
+            relevance: score.min(1.0),
             is_graph_neighbor: false,
             priority,
             shadow_header: None,
@@ -574,6 +686,56 @@ mod tests {
         assert!(output.contains("relevance: 0.95"), "should show score");
     }
 
+    #[test]
+    fn test_markdown_line_numbers() {
+        let window = test_window();
+        let mut options = FormatOptions {
+            format: ContextFormat::AnnotatedMarkdown,
+            ..Default::default()
+        };
+        let output = ContextFormatter::format(&window, &options);
+        assert!(output.contains("10 | fn validate(token: &str) -> bool {\n11 |     true\n"));
+
+        options.line_numbers = false;
+        let output = ContextFormatter::format(&window, &options);
+        assert!(output.contains("```rust\nfn validate(token: &str) -> bool {\n"));
+    }
+
+    #[test]
+    fn test_json_format_groups_by_file() {
+        let window = test_window();
+        let options = FormatOptions {
+            format: ContextFormat::Json,
+            query: "auth".to_string(),
+            ..Default::default()
+        };
+        let output = ContextFormatter::format(&window, &options);
+        assert!(!output.contains('\n'), "compact JSON is a single line");
+
+        let value: serde_json::Value = serde_json::from_str(&output).expect("valid JSON");
+        assert_eq!(value["query"], "auth");
+        assert_eq!(value["budget"], 4000);
+        let files = value["files"].as_array().expect("files");
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0]["path"], "src/auth.rs");
+        assert_eq!(files[0]["language"], "rust");
+        assert_eq!(files[0]["chunks"].as_array().map(Vec::len), Some(2));
+        assert_eq!(files[0]["chunks"][0]["lines"], serde_json::json!([10, 20]));
+        assert_eq!(files[0]["chunks"][0]["symbol"], "auth::validate");
+    }
+
+    #[test]
+    fn test_format_names_round_trip() {
+        for format in ContextFormat::ALL {
+            assert_eq!(ContextFormat::parse(format.as_str()), Some(format));
+        }
+        assert_eq!(
+            ContextFormat::parse("MD"),
+            Some(ContextFormat::AnnotatedMarkdown)
+        );
+        assert_eq!(ContextFormat::parse("yaml"), None);
+    }
+
     #[test]
     fn test_compact_groups_same_file() {
         let window = test_window();
@@ -631,11 +793,7 @@ mod tests {
             total_tokens: 0,
             token_budget: 4000,
         };
-        for format in [
-            ContextFormat::StructuredXml,
-            ContextFormat::Compact,
-            ContextFormat::AnnotatedMarkdown,
-        ] {
+        for format in ContextFormat::ALL {
             let options = FormatOptions {
                 format,
                 ..Default::default()
@@ -0,0 +1,163 @@
+//! Why a search returned nothing.
+//!
+//! An empty result list gives an agent nothing to act on. When a search
+//! comes back empty, [`crate::pipeline::Engine::diagnose_no_results`]
+//! checks the usual causes (an empty index, a degraded embedder, filters or
+//! a score threshold that removed every match, a query with no searchable
+//! terms) and reports each with the fix to try.
+
+use serde::Serialize;
+
+/// One cause of an empty search.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "reason", rename_all = "snake_case")]
+pub enum NoResultsReason {
+    /// Nothing has been indexed.
+    IndexEmpty,
+    /// The query has no terms keyword search can match, e.g. it is only
+    /// operators or punctuation.
+    NoSearchableTerms,
+    /// The embedding model is not loaded, so only keyword search ran.
+    EmbedderDegraded,
+    /// Too few chunks have embeddings for semantic search to find much.
+    LowEmbeddingCoverage {
+        /// Share of embeddable chunks with a vector, in percent.
+        percent: f64,
+    },
+    /// The query matches, but the filters exclude every match.
+    FiltersTooStrict {
+        /// The active filters, as `name=value`.
+        filters: Vec<String>,
+    },
+    /// Only generated code matches, and it is excluded by default.
+    OnlyGeneratedMatches,
+    /// The query matches, but no match reaches the reranker threshold.
+    ThresholdTooHigh {
+        /// The minimum rerank score that was requested.
+        min_rerank_score: f32,
+    },
+    /// None of the above; nothing in the index matches the query.
+    NoMatches,
+}
+
+impl NoResultsReason {
+    /// What the caller can do about it.
+    pub fn suggestion(&self) -> String {
+        match self {
+            Self::IndexEmpty => "Run `omnicontext index .` to index the repository.".to_string(),
+            Self::NoSearchableTerms => {
+                "Add words or identifiers to the query; operators and punctuation alone match nothing."
+                    .to_string()
+            }
+            Self::EmbedderDegraded => {
+                "Run `omnicontext setup model-download` for semantic search, then rephrase with \
+                 identifiers or words that appear in the code."
+                    .to_string()
+            }
+            Self::LowEmbeddingCoverage { .. } => {
+                "Wait for embedding to finish, or run `omnicontext embed --retry-failed`."
+                    .to_string()
+            }
+            Self::FiltersTooStrict { filters } => {
+                format!("Loosen or remove the filters ({}).", filters.join(", "))
+            }
+            Self::OnlyGeneratedMatches => {
+                "Set include_generated to search generated code.".to_string()
+            }
+            Self::ThresholdTooHigh { min_rerank_score } => {
+                format!("Lower min_rerank_score (currently {min_rerank_score}) or omit it.")
+            }
+            Self::NoMatches => {
+                "Rephrase the query, try a symbol name, or use a broader term.".to_string()
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for NoResultsReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::IndexEmpty => f.write_str("the repository is not indexed"),
+            Self::NoSearchableTerms => f.write_str("the query has no searchable terms"),
+            Self::EmbedderDegraded => {
+                f.write_str("the embedding model is unavailable; only keyword search ran")
+            }
+            Self::LowEmbeddingCoverage { percent } => {
+                write!(f, "only {percent:.0}% of chunks have embeddings")
+            }
+            Self::FiltersTooStrict { .. } => f.write_str("the filters exclude every match"),
+            Self::OnlyGeneratedMatches => f.write_str("only generated code matches"),
+            Self::ThresholdTooHigh { .. } => {
+                f.write_str("no match reaches the reranker score threshold")
+            }
+            Self::NoMatches => f.write_str("nothing in the index matches the query"),
+        }
+    }
+}
+
+/// A cause with its explanation, as reported to callers.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Finding {
+    /// The cause.
+    #[serde(flatten)]
+    pub reason: NoResultsReason,
+    /// What it means.
+    pub message: String,
+    /// What to do about it.
+    pub suggestion: String,
+}
+
+/// Result of [`crate::pipeline::Engine::diagnose_no_results`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NoResultsDiagnostics {
+    /// Causes found, most actionable first. Never empty.
+    pub findings: Vec<Finding>,
+}
+
+impl NoResultsDiagnostics {
+    /// Diagnostics for `reasons`; [`NoResultsReason::NoMatches`] when
+    /// there are none.
+    pub fn new(mut reasons: Vec<NoResultsReason>) -> Self {
+        if reasons.is_empty() {
+            reasons.push(NoResultsReason::NoMatches);
+        }
+        Self {
+            findings: reasons
+                .into_iter()
+                .map(|reason| Finding {
+                    message: reason.to_string(),
+                    suggestion: reason.suggestion(),
+                    reason,
+                })
+                .collect(),
+        }
+    }
+
+    /// The findings as a short text report.
+    pub fn render(&self) -> String {
+        let mut out = String::from("No results:");
+        for finding in &self.findings {
+            out.push_str(&format!("\n- {}. {}", finding.message, finding.suggestion));
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_findings_serialize_with_reason_tag() {
+        let diagnostics = NoResultsDiagnostics::new(vec![NoResultsReason::FiltersTooStrict {
+            filters: vec!["language=go".to_string()],
+        }]);
+        let json = serde_json::to_value(&diagnostics).expect("serialize");
+        assert_eq!(json["findings"][0]["reason"], "filters_too_strict");
+        assert_eq!(json["findings"][0]["filters"][0], "language=go");
+        assert!(diagnostics.render().contains("language=go"));
+
+        let fallback = NoResultsDiagnostics::new(Vec::new());
+        assert_eq!(fallback.findings[0].reason, NoResultsReason::NoMatches);
+    }
+}
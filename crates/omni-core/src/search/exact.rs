@@ -0,0 +1,212 @@
+//! Exact-case and whole-word search.
+//!
+//! FTS5 folds case and stems terms, so a query for `Device` also matches
+//! `device`, `devices`, and `DEVICE`. This mode skips the hybrid pipeline and
+//! instead scans chunk bodies with a raw SQL substring pass
+//! ([`MetadataIndex::find_chunks_containing`]) and then verifies each
+//! occurrence here:
+//!
+//! - **case-sensitive**: byte-exact comparison
+//! - **whole-word**: the match must not be flanked by identifier characters
+//!   (`[A-Za-z0-9_]` or any other alphanumeric), so `Device` does not match
+//!   `DeviceManager` or `my_device`
+//!
+//! Results are ranked by occurrence count, with chunks that *define* the
+//! identifier (last `symbol_path` segment equals the needle) first.
+//!
+//! [`MetadataIndex::find_chunks_containing`]: crate::index::MetadataIndex::find_chunks_containing
+
+use std::path::PathBuf;
+
+use crate::types::{Chunk, ScoreBreakdown, SearchResult};
+
+/// Bonus added to the score of a chunk that defines the searched identifier.
+const DEFINITION_BONUS: f64 = 10.0;
+
+/// Matching options for exact search.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ExactMatchOptions {
+    /// Match case exactly (`Device` does not match `device`).
+    pub case_sensitive: bool,
+    /// Only match whole identifiers (`Device` does not match `DeviceManager`).
+    pub whole_word: bool,
+}
+
+impl ExactMatchOptions {
+    /// Whether any exact-matching behavior is requested.
+    pub fn is_active(&self) -> bool {
+        self.case_sensitive || self.whole_word
+    }
+}
+
+/// Count occurrences of `needle` in `haystack` under the given options.
+pub fn count_occurrences(haystack: &str, needle: &str, options: ExactMatchOptions) -> usize {
+    if needle.is_empty() {
+        return 0;
+    }
+
+    let (haystack, needle) = if options.case_sensitive {
+        (haystack.to_string(), needle.to_string())
+    } else {
+        (haystack.to_lowercase(), needle.to_lowercase())
+    };
+
+    haystack
+        .match_indices(needle.as_str())
+        .filter(|(start, matched)| {
+            !options.whole_word || is_word_bounded(&haystack, *start, start + matched.len())
+        })
+        .count()
+}
+
+/// Whether `haystack[start..end]` is not flanked by identifier characters.
+fn is_word_bounded(haystack: &str, start: usize, end: usize) -> bool {
+    let before = haystack[..start].chars().next_back();
+    let after = haystack[end..].chars().next();
+    !before.is_some_and(is_ident_char) && !after.is_some_and(is_ident_char)
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Whether the chunk's own symbol is the needle (e.g. `net::Device` for `Device`).
+fn defines(chunk: &Chunk, needle: &str, options: ExactMatchOptions) -> bool {
+    let name = chunk
+        .symbol_path
+        .rsplit(|c| c == ':' || c == '.')
+        .next()
+        .unwrap_or(&chunk.symbol_path);
+    if options.case_sensitive {
+        name == needle
+    } else {
+        name.eq_ignore_ascii_case(needle)
+    }
+}
+
+/// Verify and rank candidate chunks, returning at most `limit` results.
+pub fn rank(
+    candidates: Vec<(Chunk, PathBuf)>,
+    needle: &str,
+    options: ExactMatchOptions,
+    limit: usize,
+) -> Vec<SearchResult> {
+    let mut results: Vec<SearchResult> = candidates
+        .into_iter()
+        .filter_map(|(chunk, file_path)| {
+            let hits = count_occurrences(&chunk.content, needle, options);
+            if hits == 0 {
+                return None;
+            }
+            let bonus = if defines(&chunk, needle, options) {
+                DEFINITION_BONUS
+            } else {
+                0.0
+            };
+            let score = hits as f64 * chunk.weight + bonus;
+            let structural_weight = chunk.weight;
+            Some(SearchResult {
+                chunk,
+                file_path,
+                score,
+                // Every match contains the needle verbatim.
+                relevance: 1.0,
+                score_breakdown: ScoreBreakdown {
+                    structural_weight,
+                    ..ScoreBreakdown::default()
+                },
+                owners: Vec::new(),
+            })
+        })
+        .collect();
+
+    results.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| a.file_path.cmp(&b.file_path))
+            .then_with(|| a.chunk.line_start.cmp(&b.chunk.line_start))
+    });
+    results.truncate(limit);
+    for (rank, result) in results.iter_mut().enumerate() {
+        result.score_breakdown.keyword_rank = Some(rank as u32 + 1);
+    }
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CASE: ExactMatchOptions = ExactMatchOptions {
+        case_sensitive: true,
+        whole_word: false,
+    };
+    const WORD: ExactMatchOptions = ExactMatchOptions {
+        case_sensitive: false,
+        whole_word: true,
+    };
+    const BOTH: ExactMatchOptions = ExactMatchOptions {
+        case_sensitive: true,
+        whole_word: true,
+    };
+
+    #[test]
+    fn test_case_sensitive_counts() {
+        let text = "let device = Device::new(); DeviceManager::get(device);";
+        assert_eq!(count_occurrences(text, "Device", CASE), 2);
+        assert_eq!(count_occurrences(text, "device", CASE), 2);
+    }
+
+    #[test]
+    fn test_whole_word_counts() {
+        let text = "let device = Device::new(); DeviceManager::get(my_device);";
+        assert_eq!(count_occurrences(text, "device", WORD), 2);
+        assert_eq!(count_occurrences(text, "Device", BOTH), 1);
+        assert_eq!(
+            count_occurrences(text, "Device", ExactMatchOptions::default()),
+            4
+        );
+    }
+
+    #[test]
+    fn test_rank_puts_definitions_first() {
+        let chunk = |symbol: &str, content: &str| Chunk {
+            id: 0,
+            file_id: 1,
+            symbol_path: symbol.to_string(),
+            kind: crate::types::ChunkKind::Function,
+            visibility: crate::types::Visibility::Public,
+            line_start: 1,
+            line_end: 1,
+            content: content.to_string(),
+            doc_comment: None,
+            token_count: 1,
+            weight: 1.0,
+            vector_id: None,
+            is_summary: false,
+            is_generated: false,
+            language: None,
+            content_hash: 0,
+            complexity: crate::types::ComplexityMetrics::default(),
+        };
+        let candidates = vec![
+            (
+                chunk("net::connect", "Device Device device"),
+                PathBuf::from("a.rs"),
+            ),
+            (
+                chunk("net::Device", "struct Device;"),
+                PathBuf::from("b.rs"),
+            ),
+            (chunk("net::other", "device only"), PathBuf::from("c.rs")),
+        ];
+        let results = rank(candidates, "Device", BOTH, 10);
+        let symbols: Vec<&str> = results
+            .iter()
+            .map(|r| r.chunk.symbol_path.as_str())
+            .collect();
+        assert_eq!(symbols, vec!["net::Device", "net::connect"]);
+        assert_eq!(results[0].score_breakdown.keyword_rank, Some(1));
+        assert_eq!(results[1].provenance(), vec!["keyword#2"]);
+    }
+}
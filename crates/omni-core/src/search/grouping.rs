@@ -0,0 +1,151 @@
+//! Group-by-file search response shape.
+//!
+//! IDE panels and agents read results per file: one header per file, its
+//! best chunks underneath. [`group_by_file`] folds a ranked result list into
+//! that shape so every consumer does not have to.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::SearchResult;
+
+/// Chunks kept per file when the caller does not say.
+pub const DEFAULT_CHUNKS_PER_FILE: usize = 3;
+
+/// The results of one file, with aggregate scores.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileGroup {
+    /// File containing the chunks.
+    pub file_path: PathBuf,
+    /// Aggregate score: the file's chunk scores in rank order, the n-th
+    /// weighted by `1/n`. A file with several good matches outranks one with
+    /// a single match of the same strength, without many weak matches
+    /// swamping one strong one.
+    pub score: f64,
+    /// Highest calibrated relevance among the file's chunks.
+    pub relevance: f64,
+    /// Number of the file's chunks in the result list, including those
+    /// beyond `chunks`.
+    pub match_count: usize,
+    /// The file's top-scoring chunks, best first.
+    pub chunks: Vec<SearchResult>,
+}
+
+/// Group `results` by file, keeping the top `chunks_per_file` chunks of
+/// each. Groups are ordered by aggregate score, ties by first appearance.
+pub fn group_by_file(results: Vec<SearchResult>, chunks_per_file: usize) -> Vec<FileGroup> {
+    let mut groups: Vec<FileGroup> = Vec::new();
+    let mut index: HashMap<PathBuf, usize> = HashMap::new();
+    for result in results {
+        let slot = *index.entry(result.file_path.clone()).or_insert_with(|| {
+            groups.push(FileGroup {
+                file_path: result.file_path.clone(),
+                score: 0.0,
+                relevance: 0.0,
+                match_count: 0,
+                chunks: Vec::new(),
+            });
+            groups.len() - 1
+        });
+        groups[slot].chunks.push(result);
+    }
+
+    for group in &mut groups {
+        group.chunks.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        group.match_count = group.chunks.len();
+        group.score = group
+            .chunks
+            .iter()
+            .zip(1u32..)
+            .map(|(r, n)| r.score / f64::from(n))
+            .sum();
+        group.relevance = group.chunks.iter().map(|r| r.relevance).fold(0.0, f64::max);
+        group.chunks.truncate(chunks_per_file.max(1));
+    }
+
+    // Stable: equal scores keep first-appearance order.
+    groups.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Chunk, ChunkKind, ScoreBreakdown, Visibility};
+
+    fn result(file: &str, line: u32, score: f64) -> SearchResult {
+        SearchResult {
+            chunk: Chunk {
+                id: i64::from(line),
+                file_id: 1,
+                symbol_path: format!("{file}::{line}"),
+                kind: ChunkKind::Function,
+                visibility: Visibility::Public,
+                line_start: line,
+                line_end: line + 1,
+                content: String::new(),
+                doc_comment: None,
+                token_count: 10,
+                weight: 1.0,
+                vector_id: None,
+                is_summary: false,
+                is_generated: false,
+                language: None,
+                content_hash: 0,
+                complexity: crate::types::ComplexityMetrics::default(),
+            },
+            file_path: PathBuf::from(file),
+            score,
+            relevance: score,
+            score_breakdown: ScoreBreakdown::default(),
+            owners: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_group_by_file_aggregates_and_nests() {
+        let groups = group_by_file(
+            vec![
+                result("a.rs", 1, 0.9),
+                result("b.rs", 2, 0.7),
+                result("b.rs", 3, 0.6),
+                result("a.rs", 4, 0.2),
+                result("b.rs", 5, 0.3),
+                result("c.rs", 6, 0.1),
+            ],
+            2,
+        );
+
+        let files: Vec<_> = groups.iter().map(|g| g.file_path.clone()).collect();
+        assert_eq!(
+            files,
+            vec![
+                PathBuf::from("b.rs"),
+                PathBuf::from("a.rs"),
+                PathBuf::from("c.rs")
+            ]
+        );
+        let b = &groups[0];
+        assert!((b.score - (0.7 + 0.6 / 2.0 + 0.3 / 3.0)).abs() < 1e-9);
+        assert!((b.relevance - 0.7).abs() < 1e-9);
+        assert_eq!(b.match_count, 3);
+        assert_eq!(
+            b.chunks
+                .iter()
+                .map(|r| r.chunk.line_start)
+                .collect::<Vec<_>>(),
+            vec![2, 3]
+        );
+        assert_eq!(groups[1].match_count, 2);
+    }
+}
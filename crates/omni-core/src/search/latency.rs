@@ -0,0 +1,220 @@
+//! Per-query latency budget for the model-backed search signals.
+//!
+//! Query embedding and cross-encoder reranking are the only search steps
+//! whose cost is unbounded in practice: a cold ONNX session or a loaded
+//! machine can turn a 20ms embedding into 800ms. With `search.max_latency_ms`
+//! set, each signal's cost is tracked as an exponential moving average of
+//! observed timings, and a signal whose expected cost does not fit in what
+//! remains of the budget is skipped for that query.
+//!
+//! Before a signal has been timed, a conservative CPU prior stands in for
+//! its estimate, so the first query is held to the budget too; its first
+//! real timing replaces the prior.
+//!
+//! A skipped signal produces no new timing, so its estimate would never
+//! improve. Each skip halves the estimate instead; a model that has warmed
+//! up is retried after a few queries and its real timing takes over.
+
+use std::time::{Duration, Instant};
+
+/// Weight of the newest observation in the moving average.
+const EWMA_ALPHA: f64 = 0.3;
+
+/// Factor applied to a signal's estimate each time it is skipped.
+const SKIP_DECAY: f64 = 0.5;
+
+/// A search signal that runs a model at query time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModelSignal {
+    /// Query embedding for vector search.
+    Semantic,
+    /// Cross-encoder reranking; cost is per candidate.
+    Rerank,
+}
+
+impl ModelSignal {
+    /// Name reported in `ScoreBreakdown::skipped_signals`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Semantic => "semantic",
+            Self::Rerank => "reranker",
+        }
+    }
+
+    fn slot(self) -> usize {
+        match self {
+            Self::Semantic => 0,
+            Self::Rerank => 1,
+        }
+    }
+
+    /// Assumed milliseconds per unit before the signal has been timed: a
+    /// warm CPU query embedding, and one cross-encoder pair.
+    fn prior_ms(self) -> f64 {
+        match self {
+            Self::Semantic => 30.0,
+            Self::Rerank => 8.0,
+        }
+    }
+}
+
+/// Milliseconds per unit of work for one signal.
+#[derive(Debug, Clone, Copy)]
+enum Estimate {
+    /// Not timed yet; the signal's prior, decayed by skips.
+    Prior(f64),
+    /// Moving average of observed timings, decayed by skips.
+    Measured(f64),
+}
+
+impl Estimate {
+    fn per_unit(self) -> f64 {
+        match self {
+            Self::Prior(ms) | Self::Measured(ms) => ms,
+        }
+    }
+
+    fn scaled(self, factor: f64) -> Self {
+        match self {
+            Self::Prior(ms) => Self::Prior(ms * factor),
+            Self::Measured(ms) => Self::Measured(ms * factor),
+        }
+    }
+}
+
+/// Latency budget shared by every query of a `SearchEngine`.
+#[derive(Debug)]
+pub struct LatencyBudget {
+    max: Option<Duration>,
+    /// Estimated cost per [`ModelSignal`].
+    estimates: parking_lot::Mutex<[Estimate; 2]>,
+}
+
+impl Default for LatencyBudget {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
+
+impl LatencyBudget {
+    /// Budget of `max_ms` per query; `None` disables it.
+    pub fn new(max_ms: Option<u64>) -> Self {
+        Self {
+            max: max_ms.map(Duration::from_millis),
+            estimates: parking_lot::Mutex::new([
+                Estimate::Prior(ModelSignal::Semantic.prior_ms()),
+                Estimate::Prior(ModelSignal::Rerank.prior_ms()),
+            ]),
+        }
+    }
+
+    /// The per-query budget, if any.
+    pub fn max(&self) -> Option<Duration> {
+        self.max
+    }
+
+    /// Whether `signal` may run `units` of work (1 embedding, N rerank
+    /// candidates) for a query that began at `started`.
+    ///
+    /// Signals not timed yet are checked against their prior.
+    pub fn admit(&self, signal: ModelSignal, started: Instant, units: usize) -> bool {
+        let Some(max) = self.max else {
+            return true;
+        };
+        let mut estimates = self.estimates.lock();
+        let estimate = estimates[signal.slot()];
+        let per_unit = estimate.per_unit();
+        let remaining_ms = max.saturating_sub(started.elapsed()).as_secs_f64() * 1000.0;
+        if per_unit * units.max(1) as f64 <= remaining_ms {
+            return true;
+        }
+        estimates[signal.slot()] = estimate.scaled(SKIP_DECAY);
+        tracing::debug!(
+            signal = signal.as_str(),
+            expected_ms = per_unit * units.max(1) as f64,
+            remaining_ms,
+            "skipping signal to stay within search.max_latency_ms"
+        );
+        false
+    }
+
+    /// Record that `signal` took `elapsed` for `units` of work.
+    pub fn record(&self, signal: ModelSignal, elapsed: Duration, units: usize) {
+        let per_unit = elapsed.as_secs_f64() * 1000.0 / units.max(1) as f64;
+        let mut estimates = self.estimates.lock();
+        let slot = &mut estimates[signal.slot()];
+        *slot = Estimate::Measured(match *slot {
+            Estimate::Measured(previous) => previous + EWMA_ALPHA * (per_unit - previous),
+            Estimate::Prior(_) => per_unit,
+        });
+    }
+
+    /// Current per-unit estimate for `signal` in milliseconds, once it has
+    /// been timed.
+    pub fn estimate_ms(&self, signal: ModelSignal) -> Option<f64> {
+        match self.estimates.lock()[signal.slot()] {
+            Estimate::Measured(ms) => Some(ms),
+            Estimate::Prior(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unbounded_budget_admits_everything() {
+        let budget = LatencyBudget::new(None);
+        budget.record(ModelSignal::Semantic, Duration::from_secs(5), 1);
+        assert!(budget.admit(ModelSignal::Semantic, Instant::now(), 1));
+    }
+
+    #[test]
+    fn test_unmeasured_signal_checked_against_prior() {
+        let budget = LatencyBudget::new(Some(100));
+        assert!(budget.admit(ModelSignal::Semantic, Instant::now(), 1));
+        assert!(!budget.admit(ModelSignal::Rerank, Instant::now(), 50));
+
+        // A budget below the prior skips even the first, cold query; the
+        // decayed prior lets a later query run and measure the model.
+        let tight = LatencyBudget::new(Some(10));
+        assert!(!tight.admit(ModelSignal::Semantic, Instant::now(), 1));
+        assert!(!tight.admit(ModelSignal::Semantic, Instant::now(), 1));
+        assert!(tight.admit(ModelSignal::Semantic, Instant::now(), 1));
+        assert_eq!(tight.estimate_ms(ModelSignal::Semantic), None);
+        tight.record(ModelSignal::Semantic, Duration::from_millis(4), 1);
+        assert_eq!(tight.estimate_ms(ModelSignal::Semantic), Some(4.0));
+    }
+
+    #[test]
+    fn test_slow_signal_skipped_then_retried() {
+        let budget = LatencyBudget::new(Some(150));
+        budget.record(ModelSignal::Semantic, Duration::from_millis(800), 1);
+        assert!(!budget.admit(ModelSignal::Semantic, Instant::now(), 1));
+        assert_eq!(budget.estimate_ms(ModelSignal::Semantic), Some(400.0));
+        assert!(!budget.admit(ModelSignal::Semantic, Instant::now(), 1));
+        assert!(!budget.admit(ModelSignal::Semantic, Instant::now(), 1));
+        // 800 -> 400 -> 200 -> 100: back within the 150ms budget.
+        assert!(budget.admit(ModelSignal::Semantic, Instant::now(), 1));
+    }
+
+    #[test]
+    fn test_rerank_cost_scales_with_candidates() {
+        let budget = LatencyBudget::new(Some(100));
+        budget.record(ModelSignal::Rerank, Duration::from_millis(50), 10);
+        assert!(budget.admit(ModelSignal::Rerank, Instant::now(), 10));
+        assert!(!budget.admit(ModelSignal::Rerank, Instant::now(), 50));
+    }
+
+    #[test]
+    fn test_record_smooths_estimate() {
+        let budget = LatencyBudget::new(Some(100));
+        budget.record(ModelSignal::Semantic, Duration::from_millis(10), 1);
+        budget.record(ModelSignal::Semantic, Duration::from_millis(110), 1);
+        let estimate = budget
+            .estimate_ms(ModelSignal::Semantic)
+            .unwrap_or_default();
+        assert!((estimate - 40.0).abs() < 1e-6, "got {estimate}");
+    }
+}
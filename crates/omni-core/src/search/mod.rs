@@ -8,8 +8,11 @@
 //! 1. **Query Analysis** - Classify the query as symbol-like, keyword, or natural language
 //! 2. **Multi-Signal Retrieval** - Execute parallel retrievals
 //! 3. **RRF Fusion** - Combine rank lists using Reciprocal Rank Fusion
-//! 4. **Boosting** - Apply structural weight, dependency proximity, recency
-//! 5. **Context Building** - Assemble token-budget-aware context window
+//! 4. **Boosting** - Apply structural weight, dependency proximity, recency,
+//!    and suppress matches of an optional negative query (see [`negative`])
+//! 5. **Calibration** - Map each result's evidence to a 0–1 relevance
+//!    (see [`calibration`])
+//! 6. **Context Building** - Assemble token-budget-aware context window
 #![allow(
     clippy::cast_lossless,
     clippy::cast_possible_truncation,
@@ -31,14 +34,22 @@
     clippy::unused_self
 )]
 
+pub mod analyzer;
 pub mod cache;
+pub mod calibration;
 pub mod chunk_dedup;
 pub mod context_assembler;
 pub mod context_formatter;
+pub mod diagnostics;
+pub mod exact;
 pub mod feedback;
+pub mod grouping;
 pub mod hyde;
 pub mod intent;
+pub mod latency;
+pub mod negative;
 pub mod pack;
+pub mod stemmer;
 pub mod synonyms;
 
 use crate::embedder::Embedder;
@@ -48,14 +59,39 @@ use crate::graph::dependencies::FileDependencyGraph;
 use crate::graph::reasoning::ReasoningEngine;
 use crate::index::MetadataIndex;
 use crate::reranker::Reranker;
-use crate::types::{Chunk, ContextEntry, ContextWindow, ScoreBreakdown, SearchResult};
+use crate::types::{
+    Chunk, ComplexityMetrics, ContextEntry, ContextWindow, ScoreBreakdown, SearchResult,
+};
 use crate::vector::VectorIndex;
 
 // Re-export key types for convenience
+pub use analyzer::{DefaultQueryAnalyzer, QueryAnalyzer, QueryType};
 pub use cache::{CacheKey, CacheStats, TieredCacheStats, TieredQueryCache};
 pub use context_assembler::ContextAssembler;
 pub use context_formatter::{ContextFormat, ContextFormatter, FormatOptions};
+pub use grouping::{group_by_file, FileGroup};
 pub use intent::{ContextStrategy, QueryIntent};
+pub use latency::{LatencyBudget, ModelSignal};
+
+/// The chunks a filtered search may return, and its per-query limits.
+#[derive(Debug, Clone, Default)]
+pub struct SearchScope {
+    /// The filters, applied inside the keyword query, and the
+    /// `candidates` / `token_budget` overrides.
+    pub options: crate::types::SearchOptions,
+    /// Ids of the chunks matching `options`: the allowlist for the vector,
+    /// symbol, and sparse signals. `None` when no filter is set.
+    pub chunk_ids: Option<std::collections::HashSet<i64>>,
+}
+
+impl SearchScope {
+    /// Whether `chunk_id` may be returned.
+    pub fn contains(&self, chunk_id: i64) -> bool {
+        self.chunk_ids
+            .as_ref()
+            .map_or(true, |ids| ids.contains(&chunk_id))
+    }
+}
 
 /// Hybrid search engine that fuses multiple retrieval signals.
 pub struct SearchEngine {
@@ -100,6 +136,23 @@ pub struct SearchEngine {
     /// HyDE configuration — controls whether and how hypothetical documents
     /// are generated before embedding NL queries.
     hyde_config: Option<crate::config::HydeConfig>,
+
+    /// Per-column BM25 weights for the keyword signal.
+    bm25_weights: crate::config::Bm25Weights,
+
+    /// Query classification and keyword expansion.
+    analyzer: std::sync::Arc<dyn QueryAnalyzer>,
+
+    /// Path patterns of vendored-dependency trees and the score multiplier
+    /// applied to results inside them.
+    vendored_patterns: Vec<String>,
+    vendored_weight: f64,
+
+    /// Per-query latency budget for query embedding and reranking.
+    latency: std::sync::Arc<LatencyBudget>,
+
+    /// Whole-file and graph-neighbour expansion of context windows.
+    context_config: crate::config::ContextConfig,
 }
 
 impl SearchEngine {
@@ -128,6 +181,12 @@ impl SearchEngine {
                 std::collections::HashSet::new(),
             )),
             hyde_config: None,
+            bm25_weights: crate::config::Bm25Weights::default(),
+            analyzer: std::sync::Arc::new(DefaultQueryAnalyzer::default()),
+            vendored_patterns: Vec::new(),
+            vendored_weight: 1.0,
+            latency: std::sync::Arc::new(LatencyBudget::new(None)),
+            context_config: crate::config::ContextConfig::default(),
         }
     }
 
@@ -139,6 +198,55 @@ impl SearchEngine {
         self.hyde_config = Some(config);
     }
 
+    /// Classify a query with the configured analyzer.
+    pub fn classify(&self, query: &str) -> QueryType {
+        self.analyzer.classify(query)
+    }
+
+    /// Set the RRF constant k. Cached results are dropped.
+    pub fn set_rrf_k(&mut self, rrf_k: u32) {
+        self.rrf_k = rrf_k;
+        self.result_cache.clear();
+    }
+
+    /// Set the per-column BM25 weights used by keyword search.
+    pub fn set_bm25_weights(&mut self, weights: crate::config::Bm25Weights) {
+        self.bm25_weights = weights;
+    }
+
+    /// Scale the scores of results under `patterns` (vendored-dependency
+    /// trees) by `weight`. Cached results are dropped.
+    pub fn set_vendored(&mut self, patterns: Vec<String>, weight: f64) {
+        self.vendored_patterns = patterns;
+        self.vendored_weight = weight.clamp(0.0, 1.0);
+        self.result_cache.clear();
+    }
+
+    /// Limit each query to `max_ms` milliseconds: query embedding and
+    /// reranking are skipped when their tracked cost would exceed what is
+    /// left. `None` removes the limit.
+    pub fn set_max_latency_ms(&mut self, max_ms: Option<u64>) {
+        self.latency = std::sync::Arc::new(LatencyBudget::new(max_ms));
+    }
+
+    /// Set how [`Self::assemble_context_window`] expands results into
+    /// whole files and graph neighbours.
+    pub fn set_context_config(&mut self, config: crate::config::ContextConfig) {
+        self.context_config = config;
+    }
+
+    /// The per-query latency budget.
+    pub fn latency_budget(&self) -> &LatencyBudget {
+        &self.latency
+    }
+
+    /// Replace the query analyzer. Cached results are dropped since they
+    /// were produced under the previous analyzer.
+    pub fn set_query_analyzer(&mut self, analyzer: std::sync::Arc<dyn QueryAnalyzer>) {
+        self.analyzer = analyzer;
+        self.result_cache.clear();
+    }
+
     /// Get a reference to the tiered result cache for external invalidation.
     pub fn result_cache(&self) -> &TieredQueryCache {
         &self.result_cache
@@ -307,6 +415,44 @@ impl SearchEngine {
         sparse_results: &[(i64, f32)],
         file_dep_graph: Option<&FileDependencyGraph>,
     ) -> OmniResult<Vec<SearchResult>> {
+        self.search_with_semantic_hits(
+            query, limit, index, vector_index, embedder, dep_graph, reasoning, reranker,
+            reranker_config, open_files, sparse_results, None, file_dep_graph, None,
+        )
+    }
+
+    /// [`Self::search`] with the semantic signal supplied by the caller.
+    ///
+    /// `semantic_hits` must come from [`Self::semantic_query`]: the vector
+    /// index searched with the returned vector and limit. `None` runs the
+    /// semantic step here. Used to score several queries in one
+    /// [`VectorIndex::search_batch`] pass.
+    ///
+    /// `scope` restricts every signal to the matching chunks before fusion:
+    /// the keyword query applies its filters in SQL and the vector search
+    /// scores only its chunk ids. Its `candidates` and `token_budget` options
+    /// override the engine defaults. Scoped searches bypass the result cache.
+    pub fn search_with_semantic_hits(
+        &self,
+        query: &str,
+        limit: usize,
+        index: &MetadataIndex,
+        vector_index: &VectorIndex,
+        embedder: &Embedder,
+        dep_graph: Option<&crate::graph::DependencyGraph>,
+        reasoning: Option<&ReasoningEngine>,
+        reranker: Option<&Reranker>,
+        reranker_config: Option<&crate::config::RerankerConfig>,
+        open_files: &[std::path::PathBuf],
+        sparse_results: &[(i64, f32)],
+        semantic_hits: Option<&[(u64, f32)]>,
+        file_dep_graph: Option<&FileDependencyGraph>,
+        scope: Option<&SearchScope>,
+    ) -> OmniResult<Vec<SearchResult>> {
+        let started = std::time::Instant::now();
+        // Model-backed signals skipped to stay within `search.max_latency_ms`.
+        let mut skipped: Vec<ModelSignal> = Vec::new();
+
         // ---- Check tiered result cache ----
         let reranker_active = reranker.is_some_and(|r| r.is_available());
         let graph_available = dep_graph.is_some();
@@ -324,7 +470,11 @@ impl SearchEngine {
             graph_available || file_dep_graph.is_some(),
             reasoning_available,
         );
-        if let Some(cached) = self.result_cache.get(&cache_key) {
+        if let Some(cached) = scope
+            .is_none()
+            .then(|| self.result_cache.get(&cache_key))
+            .flatten()
+        {
             tracing::debug!(
                 query = query,
                 results = cached.len(),
@@ -333,49 +483,38 @@ impl SearchEngine {
             return Ok(cached);
         }
 
-        let query_type = analyze_query(query);
+        let query_type = self.analyzer.classify(query);
         let query_intent = QueryIntent::classify(query);
-        let limit = limit.min(self.retrieval_limit);
-
-        // Adaptive retrieval limits per signal source.
-        // Different query types benefit from different signal depths:
-        //   Symbol:  deep symbol + shallow semantic
-        //   NL:      deep semantic + shallow keyword (expanded)
-        //   Keyword: balanced
-        //   Mixed:   balanced with slight symbol boost
-        let base = self.retrieval_limit;
-        let (kw_limit, sem_limit, sym_limit) = match query_type {
-            QueryType::Symbol => (base / 2, base / 3, base),
-            QueryType::NaturalLanguage => (base * 2 / 3, base, base / 3),
-            QueryType::Keyword => (base, base * 2 / 3, base / 3),
-            QueryType::Mixed => (base, base, base * 2 / 3),
-        };
+        let no_filter = crate::types::SearchOptions::default();
+        let filter = scope.map_or(&no_filter, |s| &s.options);
+        let retrieval_limit = filter.candidates.unwrap_or(self.retrieval_limit).max(1);
+        let token_budget = filter.token_budget.unwrap_or(self.token_budget);
+        let limit = limit.min(retrieval_limit);
+
+        // Adaptive retrieval limits per signal source (semantic depth is
+        // applied by `semantic_query`).
+        let (kw_limit, _, sym_limit) = Self::signal_limits(query_type, retrieval_limit);
+        let in_scope = |chunk_id: i64| scope.map_or(true, |s| s.contains(chunk_id));
 
         // ---- Query expansion for NL queries ----
         // Extract meaningful tokens for better keyword matching
-        let expanded_query =
-            if query_type == QueryType::NaturalLanguage || query_type == QueryType::Mixed {
-                let mut expanded = expand_query(query);
-                // Add code vocabulary synonyms
-                let syns = synonyms::expand_with_synonyms(query);
-                if !syns.is_empty() {
-                    tracing::debug!(synonyms = ?syns, "synonym expansion applied");
-                    expanded.push(' ');
-                    expanded.push_str(&syns.join(" "));
-                }
-                expanded
-            } else {
-                query.to_string()
-            };
+        let expanded_query = self
+            .analyzer
+            .expand(query, query_type)
+            .unwrap_or_else(|| query.to_string());
 
         // ---- Signal 1: Keyword (FTS5) ----
-        let keyword_results = match index.keyword_search(&expanded_query, kw_limit) {
+        let keyword_results = match index
+            .keyword_search_filtered(&expanded_query, kw_limit, &self.bm25_weights, filter)
+        {
             Ok(results) => results,
             Err(e) => {
                 tracing::warn!(error = %e, "keyword search failed");
                 // Fallback: try original query if expansion failed
                 if expanded_query != query {
-                    index.keyword_search(query, kw_limit).unwrap_or_default()
+                    index
+                        .keyword_search_filtered(query, kw_limit, &self.bm25_weights, filter)
+                        .unwrap_or_default()
                 } else {
                     Vec::new()
                 }
@@ -383,59 +522,34 @@ impl SearchEngine {
         };
 
         // ---- Signal 2: Semantic (Vector) ----
-        let semantic_results = if embedder.is_available() && query_type != QueryType::Symbol {
-            // Determine the best text to embed for semantic search.
-            // For NL queries, HyDE generates a hypothetical code snippet whose
-            // embedding is closer in vector space to relevant code.
-            let embed_text = if query_type == QueryType::NaturalLanguage {
-                hyde::generate_hypothetical_document(query, query_intent, self.hyde_config.as_ref())
-                    .unwrap_or_else(|| query.to_string())
-            } else {
-                query.to_string()
-            };
-
-            // Check cache first
-            let cache_key = embed_text.clone();
-            let cached_embedding = {
-                if let Ok(mut cache) = self.query_cache.lock() {
-                    cache.get(&cache_key).cloned()
-                } else {
-                    None
-                }
-            };
-
-            let query_vec = if let Some(embedding) = cached_embedding {
-                embedding
-            } else {
-                match embedder.embed_query(&embed_text) {
-                    Ok(vec) => {
-                        // Store in cache
-                        if let Ok(mut cache) = self.query_cache.lock() {
-                            cache.put(cache_key, vec.clone());
+        let mut semantic_results = match semantic_hits {
+            Some(hits) => hits.to_vec(),
+            None => match self
+                .budgeted_semantic_query(query, embedder, retrieval_limit, started, &mut skipped)
+            {
+                Some((query_vec, sem_limit)) => {
+                    let results = match scope.and_then(|s| s.chunk_ids.as_ref()) {
+                        // Score only in-scope vectors: exact, and cheaper
+                        // than filtering a global top-k.
+                        Some(chunk_ids) => vector_index.search_among(
+                            &query_vec,
+                            sem_limit,
+                            chunk_ids.iter().filter_map(|&id| u64::try_from(id).ok()),
+                        ),
+                        None => vector_index.search(&query_vec, sem_limit),
+                    };
+                    match results {
+                        Ok(results) => results,
+                        Err(e) => {
+                            tracing::warn!(error = %e, "vector search failed");
+                            Vec::new()
                         }
-                        vec
-                    }
-                    Err(e) => {
-                        tracing::warn!(error = %e, "query embedding failed");
-                        Vec::new()
                     }
                 }
-            };
-
-            if !query_vec.is_empty() {
-                match vector_index.search(&query_vec, sem_limit) {
-                    Ok(results) => results,
-                    Err(e) => {
-                        tracing::warn!(error = %e, "vector search failed");
-                        Vec::new()
-                    }
-                }
-            } else {
-                Vec::new()
-            }
-        } else {
-            Vec::new()
+                None => Vec::new(),
+            },
         };
+        semantic_results.retain(|&(vector_id, _)| i64::try_from(vector_id).is_ok_and(&in_scope));
 
         // ---- Signal 3: Symbol lookup + semantic neighborhood expansion ----
         // For symbol/mixed queries: fetch direct symbol matches, then walk
@@ -508,10 +622,31 @@ impl SearchEngine {
             }
         }
 
+        symbol_results.retain(|&chunk_id| in_scope(chunk_id));
+        let scoped_sparse: Vec<(i64, f32)>;
+        let sparse_results = if scope.is_some_and(|s| s.chunk_ids.is_some()) {
+            scoped_sparse = sparse_results
+                .iter()
+                .copied()
+                .filter(|&(chunk_id, _)| in_scope(chunk_id))
+                .collect();
+            &scoped_sparse[..]
+        } else {
+            sparse_results
+        };
+
         // ---- RRF Fusion with query-type-adaptive weights ----
         let mut fused = self.fuse_results(
             query, &keyword_results, &semantic_results, &symbol_results, sparse_results, query_type,
         );
+        // Generated code only on request. The keyword signal already leaves
+        // it out; this catches symbol hits and any embedded generated chunks.
+        if !filter.include_generated {
+            let generated = index.generated_chunk_ids().unwrap_or_default();
+            if !generated.is_empty() {
+                fused.retain(|item| !generated.contains(&item.chunk_id));
+            }
+        }
 
         // ---- GNN Structural Attention Boost ----
         // Apply file-level architectural importance scores from the two-layer GCN.
@@ -554,9 +689,18 @@ impl SearchEngine {
                     }
                 }
 
-                if !candidates.is_empty() {
+                let admitted = candidates.is_empty()
+                    || self
+                        .latency
+                        .admit(ModelSignal::Rerank, started, candidates.len());
+                if !admitted {
+                    skipped.push(ModelSignal::Rerank);
+                } else if !candidates.is_empty() {
                     let texts: Vec<&str> = candidates.iter().map(|(_, c)| c.as_str()).collect();
+                    let rerank_started = std::time::Instant::now();
                     let scores = reranker.rerank(query, &texts);
+                    self.latency
+                        .record(ModelSignal::Rerank, rerank_started.elapsed(), texts.len());
 
                     let mut min_score = f32::INFINITY;
                     let mut max_score = f32::NEG_INFINITY;
@@ -579,12 +723,13 @@ impl SearchEngine {
                         for ((chunk_id, _), score) in candidates.iter().zip(scores.iter()) {
                             if let Some(score) = score {
                                 let norm = (*score - min_score) / denom;
-                                score_map.insert(*chunk_id, norm as f64);
+                                score_map.insert(*chunk_id, (norm as f64, f64::from(*score)));
                             }
                         }
                         for item in &mut fused {
-                            if let Some(&norm) = score_map.get(&item.chunk_id) {
+                            if let Some(&(norm, probability)) = score_map.get(&item.chunk_id) {
                                 item.breakdown.reranker_score = Some(norm);
+                                item.breakdown.reranker_probability = Some(probability);
                                 item.final_score =
                                     item.final_score * rrf_weight + norm * reranker_weight;
                             } else {
@@ -601,6 +746,14 @@ impl SearchEngine {
             }
         }
 
+        // ---- Negative query: push down what the caller does not want ----
+        if let Some(negative) = filter.negative_query.as_deref() {
+            self.suppress_negative(
+                negative, &mut fused, index, vector_index, embedder, filter, retrieval_limit,
+                started,
+            );
+        }
+
         // ---- Identify Anchor for Proximity Boosting ----
         // We find the 'best' matched chunk that maps to a symbol
         let anchor_symbol_id = if let Some(_graph) = dep_graph {
@@ -721,27 +874,25 @@ impl SearchEngine {
             if let Some(graph) = dep_graph {
                 if !chunk.symbol_path.is_empty() {
                     if let Ok(Some(sym)) = index.get_symbol_by_fqn(&chunk.symbol_path) {
-                        // Global Importance (In-degree): Highly depended upon modules get a slight score bump
-                        let indegree = graph.downstream(sym.id, 1).map(|v| v.len()).unwrap_or(0);
-                        graph_boost += 0.05 * ((indegree.min(20)) as f64);
+                        // Global Importance (In-degree): Highly depended upon modules get a slight score bump.
+                        // Dependents are weighted by edge confidence so name-only guesses count less.
+                        let indegree = graph.weighted_in_degree(sym.id);
+                        graph_boost += 0.05 * indegree.min(20.0);
 
-                        // Local Proximity: If this chunk is closely related to the anchor, give it a big boost
+                        // Local Proximity: If this chunk is closely related to the anchor, give it a big boost,
+                        // scaled by how confident the connecting edges are.
                         if let Some(anchor) = anchor_symbol_id {
                             if sym.id != anchor {
-                                // Shortest undirected / directed path surrogate: check both ways
-                                let dist_down = graph.distance(anchor, sym.id).ok().flatten();
-                                let dist_up = graph.distance(sym.id, anchor).ok().flatten();
-                                let dist = match (dist_down, dist_up) {
-                                    (Some(d1), Some(d2)) => std::cmp::min(d1, d2),
-                                    (Some(d), None) => d,
-                                    (None, Some(d)) => d,
-                                    (None, None) => usize::MAX,
-                                };
-
-                                if dist == 1 {
-                                    graph_boost += 0.3; // Very closely related!
-                                } else if dist == 2 {
-                                    graph_boost += 0.1; // Related
+                                match graph.proximity(anchor, sym.id, 2) {
+                                    Some((1, confidence)) => {
+                                        graph_boost += 0.3 * f64::from(confidence);
+                                        // Very closely related!
+                                    }
+                                    Some((2, confidence)) => {
+                                        graph_boost += 0.1 * f64::from(confidence);
+                                        // Related
+                                    }
+                                    _ => {}
                                 }
                             }
                         }
@@ -815,7 +966,7 @@ impl SearchEngine {
                 Self::apply_structural_boost(scored.final_score, &chunk, graph_boost);
 
             // Check token budget
-            if total_tokens + chunk.token_count > self.token_budget {
+            if total_tokens + chunk.token_count > token_budget {
                 break;
             }
             total_tokens += chunk.token_count;
@@ -825,16 +976,28 @@ impl SearchEngine {
                 .get_file_path_for_chunk(index, &chunk)
                 .unwrap_or_default();
 
+            // Vendored dependencies rank below first-party code.
+            let boosted_score = if crate::watcher::is_vendored(&file_path, &self.vendored_patterns)
+            {
+                boosted_score * self.vendored_weight
+            } else {
+                boosted_score
+            };
+
             let mut breakdown = scored.breakdown.clone();
             breakdown.structural_weight = struct_weight;
             breakdown.pagerank_boost = pagerank_pct;
             breakdown.recency_boost = freshness;
+            breakdown.graph_boost = graph_boost - 1.0;
+            breakdown.skipped_signals = skipped.iter().map(|s| s.as_str().to_string()).collect();
 
             results.push(SearchResult {
                 chunk,
                 file_path,
                 score: boosted_score,
+                relevance: calibration::relevance(&breakdown),
                 score_breakdown: breakdown,
+                owners: Vec::new(),
             });
         }
 
@@ -867,7 +1030,9 @@ impl SearchEngine {
         deduped.truncate(limit);
 
         // ---- Store in tiered result cache ----
-        if !deduped.is_empty() {
+        // Results missing a skipped signal are not cached: the next run of the
+        // same query may have the time to compute it.
+        if !deduped.is_empty() && skipped.is_empty() && scope.is_none() {
             self.result_cache.insert(cache_key, deduped.clone());
             tracing::debug!(
                 query = query,
@@ -879,6 +1044,181 @@ impl SearchEngine {
         Ok(deduped)
     }
 
+    /// Scale down the fused candidates matching `negative` (see
+    /// [`negative`]) and re-sort them. The negative query is embedded only
+    /// when the latency budget allows; otherwise paths and keywords decide.
+    #[allow(clippy::too_many_arguments)]
+    fn suppress_negative(
+        &self,
+        negative: &str,
+        fused: &mut [ScoredChunk],
+        index: &MetadataIndex,
+        vector_index: &VectorIndex,
+        embedder: &Embedder,
+        filter: &crate::types::SearchOptions,
+        retrieval_limit: usize,
+        started: std::time::Instant,
+    ) {
+        let terms = negative::terms(negative);
+        if fused.is_empty() || (terms.is_empty() && negative.trim().is_empty()) {
+            return;
+        }
+
+        let keyword_hits: std::collections::HashSet<i64> = negative::keyword_query(&terms)
+            .and_then(|q| {
+                index
+                    .keyword_search_filtered(&q, retrieval_limit, &self.bm25_weights, filter)
+                    .map_err(|e| tracing::debug!(error = %e, "negative keyword search failed"))
+                    .ok()
+            })
+            .map(|hits| hits.into_iter().map(|(id, _)| id).collect())
+            .unwrap_or_default();
+
+        let similarities: std::collections::HashMap<i64, f32> =
+            if embedder.is_available() && self.latency.admit(ModelSignal::Semantic, started, 1) {
+                embedder
+                    .embed_query(negative)
+                    .and_then(|vec| {
+                        vector_index.search_among(
+                            &vec,
+                            fused.len(),
+                            fused.iter().filter_map(|s| u64::try_from(s.chunk_id).ok()),
+                        )
+                    })
+                    .map(|hits| {
+                        hits.into_iter()
+                            .filter_map(|(id, sim)| i64::try_from(id).ok().map(|id| (id, sim)))
+                            .collect()
+                    })
+                    .unwrap_or_default()
+            } else {
+                std::collections::HashMap::new()
+            };
+
+        for scored in fused.iter_mut() {
+            let path_match = !terms.is_empty()
+                && self
+                    .get_file_path_for_chunk_id(index, scored.chunk_id)
+                    .is_some_and(|path| negative::path_matches(&path, &terms));
+            let strength = negative::strength(
+                path_match,
+                keyword_hits.contains(&scored.chunk_id),
+                similarities.get(&scored.chunk_id).copied(),
+            );
+            if strength > 0.0 {
+                let penalty = negative::penalty(strength);
+                scored.breakdown.negative_penalty = Some(penalty);
+                scored.final_score *= 1.0 - penalty;
+            }
+        }
+        fused.sort_by(|a, b| {
+            b.final_score
+                .partial_cmp(&a.final_score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+    }
+
+    /// Retrieval depth per signal `(keyword, semantic, symbol)`.
+    ///
+    /// Different query types benefit from different signal depths:
+    ///   Symbol:  deep symbol + shallow semantic
+    ///   NL:      deep semantic + shallow keyword (expanded)
+    ///   Keyword: balanced
+    ///   Mixed:   balanced with slight symbol boost
+    ///
+    /// Every signal keeps a depth of at least 1, however small `base` is.
+    fn signal_limits(query_type: QueryType, base: usize) -> (usize, usize, usize) {
+        let (keyword, semantic, symbol) = match query_type {
+            QueryType::Symbol => (base / 2, base / 3, base),
+            QueryType::NaturalLanguage => (base * 2 / 3, base, base / 3),
+            QueryType::Keyword => (base, base * 2 / 3, base / 3),
+            QueryType::Mixed => (base, base, base * 2 / 3),
+        };
+        (keyword.max(1), semantic.max(1), symbol.max(1))
+    }
+
+    /// Query vector and semantic retrieval depth for `query`, or `None` when
+    /// the query gets no semantic signal (symbol queries, no embedder, or a
+    /// failed embedding).
+    pub fn semantic_query(&self, query: &str, embedder: &Embedder) -> Option<(Vec<f32>, usize)> {
+        self.budgeted_semantic_query(
+            query,
+            embedder,
+            self.retrieval_limit,
+            std::time::Instant::now(),
+            &mut Vec::new(),
+        )
+    }
+
+    /// [`Self::semantic_query`] with `retrieval_limit` candidates for a query
+    /// that began at `started`. Pushes [`ModelSignal::Semantic`] onto
+    /// `skipped` when embedding the query would exceed the latency budget.
+    fn budgeted_semantic_query(
+        &self,
+        query: &str,
+        embedder: &Embedder,
+        retrieval_limit: usize,
+        started: std::time::Instant,
+        skipped: &mut Vec<ModelSignal>,
+    ) -> Option<(Vec<f32>, usize)> {
+        let query_type = self.analyzer.classify(query);
+        if !embedder.is_available() || query_type == QueryType::Symbol {
+            return None;
+        }
+        let (_, sem_limit, _) = Self::signal_limits(query_type, retrieval_limit);
+
+        // Determine the best text to embed for semantic search.
+        // For NL queries, HyDE generates a hypothetical code snippet whose
+        // embedding is closer in vector space to relevant code.
+        let embed_text = if query_type == QueryType::NaturalLanguage {
+            hyde::generate_hypothetical_document(
+                query,
+                QueryIntent::classify(query),
+                self.hyde_config.as_ref(),
+            )
+            .unwrap_or_else(|| query.to_string())
+        } else {
+            query.to_string()
+        };
+
+        // Check cache first
+        let cache_key = embed_text.clone();
+        let cached_embedding = {
+            if let Ok(mut cache) = self.query_cache.lock() {
+                cache.get(&cache_key).cloned()
+            } else {
+                None
+            }
+        };
+
+        let query_vec = if let Some(embedding) = cached_embedding {
+            embedding
+        } else if !self.latency.admit(ModelSignal::Semantic, started, 1) {
+            skipped.push(ModelSignal::Semantic);
+            return None;
+        } else {
+            let embed_started = std::time::Instant::now();
+            let embedded = embedder.embed_query(&embed_text);
+            self.latency
+                .record(ModelSignal::Semantic, embed_started.elapsed(), 1);
+            match embedded {
+                Ok(vec) => {
+                    // Store in cache
+                    if let Ok(mut cache) = self.query_cache.lock() {
+                        cache.put(cache_key, vec.clone());
+                    }
+                    vec
+                }
+                Err(e) => {
+                    tracing::warn!(error = %e, "query embedding failed");
+                    Vec::new()
+                }
+            }
+        };
+
+        (!query_vec.is_empty()).then_some((query_vec, sem_limit))
+    }
+
     /// Execute search and return both results AND the GAR neighbor map.
     ///
     /// The GAR neighbor map (`chunk_id → gar_score`) contains all chunks
@@ -900,9 +1240,33 @@ impl SearchEngine {
         sparse_results: &[(i64, f32)],
         file_dep_graph: Option<&FileDependencyGraph>,
     ) -> OmniResult<(Vec<SearchResult>, std::collections::HashMap<i64, f64>)> {
-        let results = self.search(
+        self.search_with_gar_scoped(
             query, limit, index, vector_index, embedder, dep_graph, reasoning, reranker,
-            reranker_config, open_files, sparse_results, file_dep_graph,
+            reranker_config, open_files, sparse_results, file_dep_graph, None,
+        )
+    }
+
+    /// [`Self::search_with_gar`] restricted by `scope`, as in
+    /// [`Self::search_with_semantic_hits`].
+    pub fn search_with_gar_scoped(
+        &self,
+        query: &str,
+        limit: usize,
+        index: &MetadataIndex,
+        vector_index: &VectorIndex,
+        embedder: &Embedder,
+        dep_graph: Option<&crate::graph::DependencyGraph>,
+        reasoning: Option<&ReasoningEngine>,
+        reranker: Option<&Reranker>,
+        reranker_config: Option<&crate::config::RerankerConfig>,
+        open_files: &[std::path::PathBuf],
+        sparse_results: &[(i64, f32)],
+        file_dep_graph: Option<&FileDependencyGraph>,
+        scope: Option<&SearchScope>,
+    ) -> OmniResult<(Vec<SearchResult>, std::collections::HashMap<i64, f64>)> {
+        let results = self.search_with_semantic_hits(
+            query, limit, index, vector_index, embedder, dep_graph, reasoning, reranker,
+            reranker_config, open_files, sparse_results, None, file_dep_graph, scope,
         )?;
 
         // Compute GAR neighbor map for context assembly
@@ -1047,6 +1411,7 @@ impl SearchEngine {
                 final_score: 0.0,
             });
             let rank_score = sym_weight / (f64::from(self.rrf_k) + (rank as f64) + 1.0);
+            entry.breakdown.symbol_rank = Some((rank + 1) as u32);
             entry.breakdown.rrf_score += rank_score;
         }
 
@@ -1079,8 +1444,12 @@ impl SearchEngine {
 
     /// Apply structural and graph boosts once we have the actual chunk data.
     /// Called during result assembly when chunks are fetched from the DB.
+    ///
+    /// The structural weight is the one stored at chunking time, so
+    /// `indexing.kind_weights`, `indexing.path_weights`, and complexity
+    /// weighting all carry through to ranking.
     fn apply_structural_boost(score: f64, chunk: &Chunk, graph_boost: f64) -> (f64, f64) {
-        let struct_weight = chunk.kind.default_weight() * chunk.visibility.weight_multiplier();
+        let struct_weight = chunk.weight;
         let boosted = score * (0.4 + 0.6 * struct_weight) * graph_boost;
         (boosted, struct_weight)
     }
@@ -1113,9 +1482,10 @@ impl SearchEngine {
         let conn = index.connection();
         conn.query_row(
             "SELECT id, file_id, symbol_path, kind, visibility,
-                    line_start, line_end, content, doc_comment,
-                    token_count, weight, vector_id
-             FROM chunks WHERE id = ?1",
+                    line_start, line_end, b.content, doc_comment,
+                    token_count, weight, vector_id, complexity, nesting_depth,
+                    generated, language
+             FROM chunks c JOIN chunk_blobs b ON b.hash = c.blob_hash WHERE id = ?1",
             rusqlite::params![chunk_id],
             |row| {
                 Ok(Chunk {
@@ -1132,7 +1502,15 @@ impl SearchEngine {
                     weight: row.get(10)?,
                     vector_id: row.get::<_, Option<i64>>(11)?.map(|v| v as u64),
                     is_summary: false,
+                    is_generated: row.get(14)?,
+                    language: row
+                        .get::<_, Option<String>>(15)?
+                        .map(|l| crate::types::Language::from_str_lossy(&l)),
                     content_hash: 0, // not needed for search results
+                    complexity: ComplexityMetrics {
+                        cyclomatic: row.get(12)?,
+                        nesting_depth: row.get(13)?,
+                    },
                 })
             },
         )
@@ -1196,6 +1574,10 @@ impl SearchEngine {
     /// 4. Falls back to 1-hop graph neighbors when no GAR data is available
     /// 5. Packs greedily by score until token budget is hit
     ///
+    /// When `counter` is given, chunk sizes are re-measured with it before
+    /// packing, so `token_budget` is in the consuming model's tokens rather
+    /// than the embedding tokenizer's.
+    ///
     /// Returns a structured context window with file grouping.
     pub fn assemble_context_window(
         &self,
@@ -1204,6 +1586,7 @@ impl SearchEngine {
         dep_graph: Option<&crate::graph::DependencyGraph>,
         gar_neighbors: &std::collections::HashMap<i64, f64>,
         token_budget: u32,
+        counter: Option<std::sync::Arc<dyn crate::chunker::token_counter::TokenCounter>>,
         file_dep_graph: Option<&crate::graph::dependencies::FileDependencyGraph>,
     ) -> ContextWindow {
         use std::cmp::Ordering;
@@ -1213,6 +1596,7 @@ impl SearchEngine {
         #[derive(Debug)]
         struct ScoredEntry {
             score: f64,
+            relevance: f64,
             chunk: Chunk,
             file_path: std::path::PathBuf,
             is_neighbor: bool,
@@ -1247,19 +1631,26 @@ impl SearchEngine {
                 .push(result);
         }
 
-        // Step 2: For files with 3+ matches, include ALL chunks from that file
+        let config = &self.context_config;
+
+        // Step 2: For files with `whole_file_min_matches`+ matches, include
+        // ALL chunks from that file
         for (&file_id, results) in &file_groups {
-            if results.len() >= 3 {
+            if config.whole_file_min_matches > 0 && results.len() >= config.whole_file_min_matches {
                 // This file is highly relevant -- include all its chunks
                 if let Ok(all_chunks) = index.get_chunks_for_file(file_id) {
                     let file_path = results[0].file_path.clone();
                     let avg_score =
                         results.iter().map(|r| r.score).sum::<f64>() / results.len() as f64;
+                    let avg_relevance =
+                        results.iter().map(|r| r.relevance).sum::<f64>() / results.len() as f64;
                     for chunk in all_chunks {
                         if !seen_chunk_ids.contains(&chunk.id) {
                             seen_chunk_ids.insert(chunk.id);
                             heap.push(ScoredEntry {
-                                score: avg_score * 0.9, // slight discount for non-matched chunks
+                                // slight discount for non-matched chunks
+                                score: avg_score * config.whole_file_discount,
+                                relevance: avg_relevance * config.whole_file_discount,
                                 chunk,
                                 file_path: file_path.clone(),
                                 is_neighbor: false,
@@ -1274,6 +1665,7 @@ impl SearchEngine {
                         seen_chunk_ids.insert(result.chunk.id);
                         heap.push(ScoredEntry {
                             score: result.score,
+                            relevance: result.relevance,
                             chunk: result.chunk.clone(),
                             file_path: result.file_path.clone(),
                             is_neighbor: false,
@@ -1288,9 +1680,13 @@ impl SearchEngine {
         // Use pre-computed GAR neighbor chunk_ids from search() to avoid
         // redundant graph walks. Falls back to simple 1-hop structural
         // neighbors when no GAR data is available.
-        if !gar_neighbors.is_empty() {
+        let pull_neighbors = config.pulls_neighbors(dep_graph.map_or(0, |g| g.node_count()));
+        if !pull_neighbors {
+            // Disabled, or the graph is too large to walk per query.
+        } else if !gar_neighbors.is_empty() {
             // GAR path: inject pre-computed semantic neighbors
             let base_score = search_results.first().map(|r| r.score).unwrap_or(1.0);
+            let base_relevance = search_results.first().map_or(0.0, |r| r.relevance);
             for (&chunk_id, &gar_score) in gar_neighbors {
                 if !seen_chunk_ids.contains(&chunk_id) {
                     if let Some(chunk) = self.get_chunk_by_id(index, chunk_id) {
@@ -1300,7 +1696,8 @@ impl SearchEngine {
                         seen_chunk_ids.insert(chunk_id);
                         // Score: top result score * GAR relevance (capped at 0.6)
                         heap.push(ScoredEntry {
-                            score: base_score * 0.5 * gar_score.min(1.0),
+                            score: base_score * config.gar_discount * gar_score.min(1.0),
+                            relevance: base_relevance * config.gar_discount * gar_score.min(1.0),
                             chunk,
                             file_path: fp,
                             is_neighbor: true,
@@ -1310,13 +1707,13 @@ impl SearchEngine {
             }
         } else if let Some(graph) = dep_graph {
             // Fallback: simple 1-hop structural neighbors (no GAR data)
-            for result in search_results.iter().take(3) {
+            for result in search_results.iter().take(config.neighbor_results) {
                 if result.chunk.symbol_path.is_empty() {
                     continue;
                 }
                 if let Ok(Some(sym)) = index.get_symbol_by_fqn(&result.chunk.symbol_path) {
                     // Get upstream dependencies (what this symbol depends on)
-                    if let Ok(upstream) = graph.upstream(sym.id, 1) {
+                    if let Ok(upstream) = graph.upstream(sym.id, config.neighbor_depth) {
                         for dep_id in upstream {
                             if let Ok(Some(dep_sym)) = index.get_symbol_by_id(dep_id) {
                                 if let Some(chunk_id) = dep_sym.chunk_id {
@@ -1327,7 +1724,9 @@ impl SearchEngine {
                                                 .unwrap_or_default();
                                             seen_chunk_ids.insert(chunk_id);
                                             heap.push(ScoredEntry {
-                                                score: result.score * 0.5,
+                                                score: result.score * config.upstream_discount,
+                                                relevance: result.relevance
+                                                    * config.upstream_discount,
                                                 chunk,
                                                 file_path: fp,
                                                 is_neighbor: true,
@@ -1339,7 +1738,7 @@ impl SearchEngine {
                         }
                     }
                     // Get downstream dependencies (what depends on this)
-                    if let Ok(downstream) = graph.downstream(sym.id, 1) {
+                    if let Ok(downstream) = graph.downstream(sym.id, config.neighbor_depth) {
                         for dep_id in downstream {
                             if let Ok(Some(dep_sym)) = index.get_symbol_by_id(dep_id) {
                                 if let Some(chunk_id) = dep_sym.chunk_id {
@@ -1350,7 +1749,9 @@ impl SearchEngine {
                                                 .unwrap_or_default();
                                             seen_chunk_ids.insert(chunk_id);
                                             heap.push(ScoredEntry {
-                                                score: result.score * 0.4,
+                                                score: result.score * config.downstream_discount,
+                                                relevance: result.relevance
+                                                    * config.downstream_discount,
                                                 chunk,
                                                 file_path: fp,
                                                 is_neighbor: true,
@@ -1389,6 +1790,7 @@ impl SearchEngine {
                 file_path: entry.file_path,
                 chunk: entry.chunk,
                 score: entry.score,
+                relevance: entry.relevance,
                 is_graph_neighbor: entry.is_neighbor,
                 priority: Some(priority),
                 shadow_header: None,
@@ -1406,75 +1808,26 @@ impl SearchEngine {
             })
         });
 
+        // Keep each file's best chunks up to the per-file cap.
+        if config.max_chunks_per_file > 0 {
+            let mut per_file: HashMap<std::path::PathBuf, usize> = HashMap::new();
+            candidate_entries.retain(|entry| {
+                let taken = per_file.entry(entry.file_path.clone()).or_default();
+                *taken += 1;
+                *taken <= config.max_chunks_per_file
+            });
+        }
+
         // Delegate to ContextAssembler's knapsack DP packer, then apply causal ordering
-        let assembler = context_assembler::ContextAssembler::new(token_budget);
+        let mut assembler = context_assembler::ContextAssembler::new(token_budget);
+        if let Some(counter) = counter {
+            assembler = assembler.with_counter(counter);
+        }
         assembler
             .pack_entries_with_strategy(candidate_entries, &strategy, token_budget, file_dep_graph)
     }
 }
 
-// ---------------------------------------------------------------------------
-// Query analysis
-// ---------------------------------------------------------------------------
-
-/// Classification of a search query for routing to appropriate signals.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum QueryType {
-    /// Looks like a symbol name (e.g., "authenticate_user", "Config.new")
-    Symbol,
-    /// Short keyword search (1-2 words, no natural language structure)
-    Keyword,
-    /// Natural language question ("how does authentication work?")
-    NaturalLanguage,
-    /// Mixed -- could be either (e.g., "user authentication function")
-    Mixed,
-}
-
-/// Analyze a query string to determine the best search strategy.
-fn analyze_query(query: &str) -> QueryType {
-    let trimmed = query.trim();
-
-    if trimmed.is_empty() {
-        return QueryType::Keyword;
-    }
-
-    // Symbol-like: contains :: or . separators, or is camelCase/snake_case without spaces
-    if !trimmed.contains(' ') {
-        if trimmed.contains("::") || trimmed.contains('.') || trimmed.contains("__") {
-            return QueryType::Symbol;
-        }
-        // Single word -- check if it looks like an identifier
-        if trimmed.chars().all(|c| c.is_alphanumeric() || c == '_') {
-            return QueryType::Symbol;
-        }
-    }
-
-    // Natural language: starts with question words, ends with ?, or has many words
-    let lower = trimmed.to_lowercase();
-    let words: Vec<&str> = trimmed.split_whitespace().collect();
-
-    if lower.ends_with('?')
-        || lower.starts_with("how ")
-        || lower.starts_with("what ")
-        || lower.starts_with("where ")
-        || lower.starts_with("why ")
-        || lower.starts_with("when ")
-        || lower.starts_with("which ")
-        || lower.starts_with("find ")
-        || lower.starts_with("show ")
-    {
-        return QueryType::NaturalLanguage;
-    }
-
-    // Short queries (1-3 words) that aren't questions are mixed
-    if words.len() <= 3 {
-        return QueryType::Mixed;
-    }
-
-    // Longer queries are likely natural language
-    QueryType::NaturalLanguage
-}
-
 // ---------------------------------------------------------------------------
 // Internal types
 // ---------------------------------------------------------------------------
@@ -1490,7 +1843,7 @@ struct ScoredChunk {
 // Query expansion
 // ---------------------------------------------------------------------------
 
-/// Stop words to strip from natural language queries for FTS5.
+/// Built-in stop words to strip from natural language queries for FTS5.
 const STOP_WORDS: &[&str] = &[
     "a", "an", "the", "is", "are", "was", "were", "be", "been", "being", "have", "has", "had",
     "do", "does", "did", "will", "would", "shall", "should", "may", "might", "can", "could",
@@ -1503,17 +1856,46 @@ const STOP_WORDS: &[&str] = &[
     "list", "explain", "describe",
 ];
 
+/// The effective stopword set: [`STOP_WORDS`] plus `[search.stopwords]`
+/// additions, minus removals. Matching is case-insensitive.
+#[derive(Debug, Clone)]
+struct StopWords(std::collections::HashSet<String>);
+
+impl StopWords {
+    fn new(config: &crate::config::StopwordConfig) -> Self {
+        let removed: std::collections::HashSet<String> =
+            config.remove.iter().map(|w| w.to_lowercase()).collect();
+        let words = STOP_WORDS
+            .iter()
+            .map(|w| (*w).to_string())
+            .chain(config.add.iter().map(|w| w.to_lowercase()))
+            .filter(|w| !removed.contains(w))
+            .collect();
+        Self(words)
+    }
+
+    fn contains(&self, word: &str) -> bool {
+        self.0.contains(&word.to_lowercase())
+    }
+}
+
+impl Default for StopWords {
+    fn default() -> Self {
+        Self::new(&crate::config::StopwordConfig::default())
+    }
+}
+
 /// Expand a natural language query into better FTS5 tokens.
 ///
 /// Strips stop words, splits code identifiers (snake_case, CamelCase,
 /// dot.paths, colon::paths), and preserves content-bearing tokens
 /// that are more likely to match code identifiers and documentation.
-fn expand_query(query: &str) -> String {
+fn expand_query(query: &str, stop_words: &StopWords) -> String {
     let tokens: Vec<&str> = query
         .split_whitespace()
         .map(|w| w.trim_matches(|c: char| !c.is_alphanumeric() && c != '_'))
         .filter(|w| !w.is_empty())
-        .filter(|w| !STOP_WORDS.contains(&w.to_lowercase().as_str()))
+        .filter(|w| !stop_words.contains(w))
         .collect();
 
     if tokens.is_empty() {
@@ -1531,8 +1913,8 @@ fn expand_query(query: &str) -> String {
         let sub_tokens = split_code_token(token);
         for sub in sub_tokens {
             let lower = sub.to_lowercase();
-            if lower.len() >= 2
-                && !STOP_WORDS.contains(&lower.as_str())
+            if lower.chars().count() >= 2
+                && !stop_words.contains(&lower)
                 && !expanded.contains(&lower)
             {
                 expanded.push(lower);
@@ -1548,6 +1930,24 @@ fn expand_query(query: &str) -> String {
     expanded.join(" OR ")
 }
 
+/// Stems of the content-bearing words in `query` that differ from the
+/// words themselves (`connections` → `connection`).
+fn stem_terms(query: &str, stop_words: &StopWords) -> Vec<String> {
+    let mut stems: Vec<String> = Vec::new();
+    for word in query.split_whitespace() {
+        let word = word.trim_matches(|c: char| !c.is_alphanumeric());
+        if stop_words.contains(word) {
+            continue;
+        }
+        if let Some(stem) = stemmer::stem_term(word) {
+            if !stems.contains(&stem) {
+                stems.push(stem);
+            }
+        }
+    }
+    stems
+}
+
 /// Split a code token into constituent sub-words.
 ///
 /// Handles:
@@ -1558,6 +1958,8 @@ fn expand_query(query: &str) -> String {
 /// - `package.Class` -> ["package", "Class"]
 /// - `kebab-case-name` -> ["kebab", "case", "name"]
 fn split_code_token(token: &str) -> Vec<&str> {
+    use crate::index::fts_query::is_cjk;
+
     let mut parts = Vec::new();
 
     // First split on clear separators: _ . :: - /
@@ -1566,26 +1968,29 @@ fn split_code_token(token: &str) -> Vec<&str> {
             continue;
         }
 
-        // Then split CamelCase within each segment
-        let bytes = segment.as_bytes();
+        // Then split CamelCase and CJK/non-CJK transitions within each segment.
+        // Iterate chars (not bytes) so multi-byte identifiers split on
+        // character boundaries.
+        let chars: Vec<(usize, char)> = segment.char_indices().collect();
         let mut start = 0;
-        for i in 1..bytes.len() {
-            let cur = bytes[i] as char;
-            let prev = bytes[i - 1] as char;
+        for i in 1..chars.len() {
+            let (pos, cur) = chars[i];
+            let prev = chars[i - 1].1;
 
             // Split on case transitions: lowercase->UPPERCASE or UPPERCASE->UPPERCASE+lowercase
             let boundary = (prev.is_lowercase() && cur.is_uppercase())
-                || (i + 1 < bytes.len()
+                || (i + 1 < chars.len()
                     && prev.is_uppercase()
                     && cur.is_uppercase()
-                    && (bytes[i + 1] as char).is_lowercase());
+                    && chars[i + 1].1.is_lowercase())
+                || is_cjk(prev) != is_cjk(cur);
 
             if boundary {
-                let part = &segment[start..i];
+                let part = &segment[start..pos];
                 if !part.is_empty() {
                     parts.push(part);
                 }
-                start = i;
+                start = pos;
             }
         }
         let tail = &segment[start..];
@@ -1611,8 +2016,104 @@ fn parse_vis(s: &str) -> crate::types::Visibility {
 
 #[cfg(test)]
 mod tests {
+    use super::analyzer::analyze_query;
     use super::*;
 
+    #[test]
+    fn test_context_config_controls_whole_file_expansion() {
+        use crate::types::{ChunkKind, ComplexityMetrics, FileInfo, Language, Visibility};
+
+        let dir = tempfile::tempdir().expect("tempdir");
+        let index = MetadataIndex::open(&dir.path().join("index.db")).expect("open db");
+        let file_id = index
+            .upsert_file(&FileInfo {
+                id: 0,
+                path: "billing.py".into(),
+                language: Language::Python,
+                content_hash: "h".into(),
+                size_bytes: 1,
+            })
+            .expect("upsert file");
+        let mut results = Vec::new();
+        for (i, name) in ["invoice_open", "invoice_close", "invoice_void", "helper"]
+            .iter()
+            .enumerate()
+        {
+            let mut chunk = Chunk {
+                id: 0,
+                file_id,
+                symbol_path: format!("billing.{name}"),
+                kind: ChunkKind::Function,
+                visibility: Visibility::Public,
+                line_start: i as u32 * 4 + 1,
+                line_end: i as u32 * 4 + 2,
+                content: format!("def {name}():\n    pass"),
+                doc_comment: None,
+                token_count: 8,
+                weight: 1.0,
+                vector_id: None,
+                is_summary: false,
+                is_generated: false,
+                language: None,
+                content_hash: 0,
+                complexity: ComplexityMetrics::default(),
+            };
+            chunk.id = index.insert_chunk(&chunk).expect("insert chunk");
+            if *name != "helper" {
+                results.push(SearchResult {
+                    chunk,
+                    file_path: "billing.py".into(),
+                    score: 1.0,
+                    relevance: 0.8,
+                    score_breakdown: ScoreBreakdown::default(),
+                    owners: Vec::new(),
+                });
+            }
+        }
+
+        let symbols = |config: crate::config::ContextConfig| {
+            let mut engine = SearchEngine::new(60, 4000);
+            engine.set_context_config(config);
+            let window = engine.assemble_context_window(
+                &results,
+                &index,
+                None,
+                &std::collections::HashMap::new(),
+                4000,
+                None,
+                None,
+            );
+            window
+                .entries
+                .iter()
+                .map(|e| e.chunk.symbol_path.clone())
+                .collect::<Vec<_>>()
+        };
+
+        let default = symbols(crate::config::ContextConfig::default());
+        assert_eq!(default.len(), 4, "three matches pull in the whole file");
+        assert!(default.contains(&"billing.helper".to_string()));
+
+        let matches_only = symbols(crate::config::ContextConfig {
+            whole_file_min_matches: 0,
+            ..crate::config::ContextConfig::default()
+        });
+        assert_eq!(matches_only.len(), 3);
+        assert!(!matches_only.contains(&"billing.helper".to_string()));
+
+        let capped = symbols(crate::config::ContextConfig {
+            max_chunks_per_file: 2,
+            ..crate::config::ContextConfig::default()
+        });
+        assert_eq!(capped.len(), 2);
+
+        assert!(!crate::config::ContextConfig {
+            max_graph_nodes: Some(10),
+            ..crate::config::ContextConfig::default()
+        }
+        .pulls_neighbors(11));
+    }
+
     #[test]
     fn test_rrf_score_both_signals() {
         let engine = SearchEngine::new(60, 4000);
@@ -1785,6 +2286,13 @@ mod tests {
         assert_eq!(parts, vec!["authenticate"]);
     }
 
+    #[test]
+    fn test_split_unicode_identifiers() {
+        assert_eq!(split_code_token("用户Login"), vec!["用户", "Login"]);
+        assert_eq!(split_code_token("getÉtat"), vec!["get", "État"]);
+        assert_eq!(split_code_token("验证_token"), vec!["验证", "token"]);
+    }
+
     // -- Line overlap ratio tests --
 
     #[test]
@@ -1810,17 +2318,43 @@ mod tests {
 
     #[test]
     fn test_expand_query_splits_identifier() {
-        let expanded = expand_query("processFileHash");
+        let expanded = expand_query("processFileHash", &StopWords::default());
         assert!(expanded.contains("process"), "should contain sub-word");
         assert!(expanded.contains("File"), "should contain sub-word");
     }
 
     #[test]
     fn test_expand_query_preserves_original() {
-        let expanded = expand_query("processFileHash");
+        let expanded = expand_query("processFileHash", &StopWords::default());
         assert!(expanded.contains("processFileHash"), "should keep original");
     }
 
+    #[test]
+    fn test_stem_terms_skip_stop_words_and_unchanged_words() {
+        assert_eq!(
+            stem_terms(
+                "how does it handle connections pooling?",
+                &StopWords::default()
+            ),
+            vec!["connection".to_string(), "pool".to_string()]
+        );
+        assert!(stem_terms("parse config", &StopWords::default()).is_empty());
+    }
+
+    #[test]
+    fn test_configured_stopwords() {
+        let config = crate::config::StopwordConfig {
+            add: vec!["Handler".to_string()],
+            remove: vec!["not".to_string()],
+        };
+        let stop_words = StopWords::new(&config);
+        let expanded = expand_query("retry not idempotent handler", &stop_words);
+        assert_eq!(expanded, "retry OR not OR idempotent");
+
+        let default = expand_query("retry not idempotent handler", &StopWords::default());
+        assert_eq!(default, "retry OR idempotent OR handler");
+    }
+
     // -----------------------------------------------------------------------
     // Temporal freshness scoring tests
     // -----------------------------------------------------------------------
@@ -0,0 +1,109 @@
+//! Negative ("X but not Y") queries.
+//!
+//! A negative query names areas the caller does not want, e.g. `tests
+//! protobuf` for "serialization, but not tests or protobuf". Each candidate
+//! gets a suppression strength in `[0, 1]` from three pieces of evidence,
+//! combined as a noisy-OR:
+//!
+//! - its file path contains a negative term (`tests/`, `protobuf_codec.rs`);
+//! - it matches a negative term in the keyword index;
+//! - its embedding is close to the negative query's embedding.
+//!
+//! The score and calibrated relevance are then scaled by
+//! `1 - NEGATIVE_WEIGHT * strength`, so suppressed results sink rather than
+//! vanish: a strong positive match still shows up when nothing else does.
+
+use std::path::Path;
+
+/// Largest share of the score a negative query can take away.
+pub const NEGATIVE_WEIGHT: f64 = 0.8;
+
+/// Suppression strength of a keyword-index match. Lower than a path match:
+/// a term mentioned in a comment does not make a chunk about that term.
+const KEYWORD_STRENGTH: f64 = 0.5;
+
+/// Cosine similarity to the negative query below which there is no
+/// suppression, and the width of the ramp up to full suppression.
+const SIMILARITY_FLOOR: f32 = 0.3;
+const SIMILARITY_RAMP: f32 = 0.4;
+
+/// Lowercased terms of `negative`, ignoring punctuation and one- or
+/// two-letter words.
+pub fn terms(negative: &str) -> Vec<String> {
+    negative
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|t| t.len() > 2)
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Keyword query matching any of `terms`, or `None` when there are none.
+pub fn keyword_query(terms: &[String]) -> Option<String> {
+    (!terms.is_empty()).then(|| terms.join(" OR "))
+}
+
+/// Whether a component of `path` contains one of `terms`, ignoring case
+/// and a plural `s` (`tests` matches `test_utils.rs`).
+pub fn path_matches(path: &Path, terms: &[String]) -> bool {
+    path.components().any(|component| {
+        let component = component.as_os_str().to_string_lossy().to_lowercase();
+        terms.iter().any(|term| {
+            let stem = term
+                .strip_suffix('s')
+                .filter(|s| s.len() > 2)
+                .unwrap_or(term);
+            component.contains(stem)
+        })
+    })
+}
+
+/// Suppression strength from the cosine similarity to the negative query.
+pub fn similarity_strength(similarity: f32) -> f64 {
+    f64::from(((similarity - SIMILARITY_FLOOR) / SIMILARITY_RAMP).clamp(0.0, 1.0))
+}
+
+/// Noisy-OR of the path, keyword, and similarity evidence.
+pub fn strength(path_match: bool, keyword_match: bool, similarity: Option<f32>) -> f64 {
+    let path = if path_match { 1.0 } else { 0.0 };
+    let keyword = if keyword_match { KEYWORD_STRENGTH } else { 0.0 };
+    let semantic = similarity.map_or(0.0, similarity_strength);
+    1.0 - (1.0 - path) * (1.0 - keyword) * (1.0 - semantic)
+}
+
+/// Share of the score removed at suppression `strength`.
+pub fn penalty(strength: f64) -> f64 {
+    NEGATIVE_WEIGHT * strength.clamp(0.0, 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_terms_and_keyword_query() {
+        let terms = terms("Tests, protobuf & io");
+        assert_eq!(terms, vec!["tests", "protobuf"]);
+        assert_eq!(keyword_query(&terms).as_deref(), Some("tests OR protobuf"));
+        assert_eq!(keyword_query(&[]), None);
+    }
+
+    #[test]
+    fn test_path_matches_components_and_plurals() {
+        let terms = vec!["tests".to_string(), "protobuf".to_string()];
+        assert!(path_matches(Path::new("src/tests/codec.rs"), &terms));
+        assert!(path_matches(Path::new("src/test_utils.rs"), &terms));
+        assert!(path_matches(Path::new("gen/Protobuf/msg.rs"), &terms));
+        assert!(!path_matches(Path::new("src/serde/json.rs"), &terms));
+    }
+
+    #[test]
+    fn test_strength_combines_evidence() {
+        assert!(strength(false, false, None).abs() < 1e-9);
+        assert!((strength(true, false, None) - 1.0).abs() < 1e-9);
+        assert!((strength(false, true, None) - 0.5).abs() < 1e-9);
+        assert!(strength(false, false, Some(0.2)).abs() < 1e-9);
+        assert!((strength(false, false, Some(0.9)) - 1.0).abs() < 1e-9);
+        assert!((strength(false, true, Some(0.5)) - 0.75).abs() < 1e-6);
+        assert!((penalty(1.0) - NEGATIVE_WEIGHT).abs() < 1e-9);
+    }
+}
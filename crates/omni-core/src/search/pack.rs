@@ -42,6 +42,9 @@ pub struct PackedContextEntry {
     pub token_count: u32,
     /// Maximum relevance score across all merged chunks.
     pub score: f64,
+    /// Maximum calibrated 0–1 relevance across all merged chunks.
+    #[serde(default)]
+    pub relevance: f64,
     /// Kind of the primary chunk.
     pub kind: ChunkKind,
 }
@@ -67,6 +70,7 @@ pub fn merge_adjacent(entries: Vec<PackedContextEntry>) -> Vec<PackedContextEntr
                 // Merge into previous entry.
                 prev.line_end = prev.line_end.max(entry.line_end);
                 prev.score = prev.score.max(entry.score);
+                prev.relevance = prev.relevance.max(entry.relevance);
                 prev.token_count += entry.token_count;
                 prev.content.push('\n');
                 prev.content.push_str(&entry.content);
@@ -121,6 +125,7 @@ mod tests {
             content: format!("// lines {line_start}-{line_end}"),
             token_count: tokens,
             score,
+            relevance: score,
             kind: ChunkKind::Function,
         }
     }
@@ -0,0 +1,121 @@
+//! Light English stemming for query expansion.
+//!
+//! `chunks_fts` already runs the Porter tokenizer, so `connections` matches
+//! the word `connection`. It cannot match identifiers, though: `unicode61`
+//! indexes `ConnectionPool` as the single token `connectionpool`. Query
+//! expansion therefore adds the stem of each natural-language word as a
+//! prefix term (`connection*`), which reaches those compound identifiers.
+//!
+//! Only Porter step 1 (plurals, `-ed`, `-ing`) is applied. Deeper steps
+//! (`-ation`, `-ness`, ...) over-conflate when the stem is used as a prefix.
+
+/// Minimum stem length worth emitting as a prefix term.
+const MIN_STEM_LEN: usize = 3;
+
+/// Strip plural and `-ed`/`-ing` suffixes from an ASCII word.
+///
+/// Returns the lowercased word unchanged when no rule applies, or when the
+/// word contains non-ASCII letters.
+pub fn light_stem(word: &str) -> String {
+    let word = word.to_lowercase();
+    if !word.chars().all(|c| c.is_ascii_alphabetic()) {
+        return word;
+    }
+
+    // Porter maps `-ies` to `-i`; `-y` keeps the stem a readable prefix.
+    if word.len() > 4 && word.ends_with("ies") {
+        return format!("{}y", &word[..word.len() - 3]);
+    }
+
+    strip_verb_suffix(strip_plural(&word)).to_string()
+}
+
+/// The stem of `word` as an expansion term, if stemming changed it.
+pub fn stem_term(word: &str) -> Option<String> {
+    let stem = light_stem(word);
+    (stem.len() >= MIN_STEM_LEN && stem != word.to_lowercase()).then_some(stem)
+}
+
+/// Porter step 1a (minus `-ies`, handled by the caller).
+fn strip_plural(word: &str) -> &str {
+    if word.ends_with("sses") {
+        return &word[..word.len() - 2];
+    }
+    for suffix in ["ches", "shes", "xes", "zes"] {
+        if word.ends_with(suffix) {
+            return &word[..word.len() - 2];
+        }
+    }
+    if word.len() > 3
+        && word.ends_with('s')
+        && !["ss", "us", "is"].iter().any(|s| word.ends_with(s))
+    {
+        return &word[..word.len() - 1];
+    }
+    word
+}
+
+/// Porter step 1b without the `-e` restoration (the stem is used as a
+/// prefix, so `mak*` still reaches `make`).
+fn strip_verb_suffix(word: &str) -> &str {
+    for suffix in ["ing", "ed"] {
+        if let Some(stem) = word.strip_suffix(suffix) {
+            if stem.len() >= MIN_STEM_LEN && stem.contains(is_vowel) {
+                return undouble(stem);
+            }
+        }
+    }
+    word
+}
+
+/// `running` → `runn` → `run`, but keep `ll`/`ss`/`zz` (`calling` → `call`).
+fn undouble(stem: &str) -> &str {
+    let bytes = stem.as_bytes();
+    let n = bytes.len();
+    if n >= 2
+        && bytes[n - 1] == bytes[n - 2]
+        && !matches!(bytes[n - 1], b'l' | b's' | b'z')
+        && !is_vowel(char::from(bytes[n - 1]))
+    {
+        return &stem[..n - 1];
+    }
+    stem
+}
+
+fn is_vowel(c: char) -> bool {
+    matches!(c, 'a' | 'e' | 'i' | 'o' | 'u')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_light_stem() {
+        assert_eq!(light_stem("connections"), "connection");
+        assert_eq!(light_stem("queries"), "query");
+        assert_eq!(light_stem("indexes"), "index");
+        assert_eq!(light_stem("classes"), "class");
+        assert_eq!(light_stem("pooling"), "pool");
+        assert_eq!(light_stem("running"), "run");
+        assert_eq!(light_stem("calling"), "call");
+        assert_eq!(light_stem("cached"), "cach");
+        assert_eq!(light_stem("Parsed"), "pars");
+    }
+
+    #[test]
+    fn test_light_stem_leaves_short_and_exempt_words() {
+        assert_eq!(light_stem("status"), "status");
+        assert_eq!(light_stem("analysis"), "analysis");
+        assert_eq!(light_stem("bed"), "bed");
+        assert_eq!(light_stem("sing"), "sing");
+        assert_eq!(light_stem("naïve"), "naïve");
+    }
+
+    #[test]
+    fn test_stem_term_only_when_changed() {
+        assert_eq!(stem_term("connections").as_deref(), Some("connection"));
+        assert_eq!(stem_term("pool"), None);
+        assert_eq!(stem_term("go"), None);
+    }
+}
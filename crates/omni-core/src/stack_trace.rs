@@ -0,0 +1,415 @@
+//! Stack trace parsing and resolution to indexed code.
+//!
+//! [`parse`] pulls frames out of free text containing Rust panics and
+//! backtraces, Python tracebacks, and JavaScript stacks (V8 and Firefox
+//! styles), in any mix. [`resolve`] maps each frame to the indexed chunk
+//! enclosing its line, matching paths by suffix so traces captured in a
+//! container or on another machine still resolve; frames without a usable
+//! location fall back to a symbol lookup by function name.
+//! [`crate::pipeline::Engine::resolve_stack_trace`] packs the resolved
+//! chunks into a context window.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::OmniResult;
+use crate::index::MetadataIndex;
+use crate::types::{Chunk, ContextWindow, FileInfo};
+
+/// One frame of a stack trace.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StackFrame {
+    /// Function name as printed, when the format gives one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub function: Option<String>,
+    /// File path as printed (absolute, relative, or a URL path).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    /// 1-based line number.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line: Option<u32>,
+}
+
+/// A frame and the indexed code it points at, if any.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResolvedFrame {
+    /// The parsed frame.
+    #[serde(flatten)]
+    pub frame: StackFrame,
+    /// Indexed file the frame is in, relative to the repository root.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_path: Option<PathBuf>,
+    /// Symbol of the chunk enclosing the frame's line.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub symbol_path: Option<String>,
+    /// The chunk enclosing the frame's line.
+    #[serde(skip)]
+    pub chunk: Option<Chunk>,
+}
+
+/// Result of [`crate::pipeline::Engine::resolve_stack_trace`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StackTraceContext {
+    /// Every parsed frame, innermost first, with its resolution.
+    pub frames: Vec<ResolvedFrame>,
+    /// The code of the resolved frames, innermost ranked highest.
+    pub window: ContextWindow,
+}
+
+/// Parse the stack frames in `text`, innermost (most recent call) first.
+///
+/// Lines that are not frames are skipped. Python tracebacks list the most
+/// recent call last, so their frames are reversed.
+pub fn parse(text: &str) -> Vec<StackFrame> {
+    let mut frames = Vec::new();
+    let mut python = Vec::new();
+    // A numbered Rust backtrace frame waiting for its `at file:line` line.
+    let mut pending: Option<StackFrame> = None;
+
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if let Some(frame) = parse_python_frame(trimmed) {
+            python.push(frame);
+            continue;
+        }
+        if let Some(location) = trimmed.strip_prefix("at ") {
+            if let Some(mut frame) = pending.take() {
+                if let Some((file, line)) = parse_location(location) {
+                    frame.file = Some(file);
+                    frame.line = Some(line);
+                }
+                frames.push(frame);
+            } else if let Some(frame) = parse_v8_frame(location) {
+                frames.push(frame);
+            }
+            continue;
+        }
+        frames.extend(pending.take());
+        if let Some(function) = parse_backtrace_function(trimmed) {
+            pending = Some(StackFrame {
+                function: Some(function),
+                ..StackFrame::default()
+            });
+        } else if let Some(frame) = parse_panic_location(trimmed) {
+            frames.push(frame);
+        } else if let Some(frame) = parse_firefox_frame(trimmed) {
+            frames.push(frame);
+        }
+    }
+    frames.extend(pending);
+    python.reverse();
+    frames.extend(python);
+    frames
+}
+
+/// `File "app/handlers.py", line 12, in handle`
+fn parse_python_frame(line: &str) -> Option<StackFrame> {
+    let rest = line.strip_prefix("File \"")?;
+    let (file, rest) = rest.split_once('"')?;
+    let rest = rest.trim_start().strip_prefix(',')?.trim_start();
+    let rest = rest.strip_prefix("line ")?;
+    let (number, function) = match rest.split_once(',') {
+        Some((number, tail)) => (number, tail.trim().strip_prefix("in ")),
+        None => (rest, None),
+    };
+    Some(StackFrame {
+        function: function
+            .map(str::trim)
+            .filter(|f| !f.starts_with('<'))
+            .map(str::to_string),
+        file: Some(file.to_string()),
+        line: Some(number.trim().parse().ok()?),
+    })
+}
+
+/// `  3: my_crate::parser::parse_header::h0123456789abcdef`
+fn parse_backtrace_function(line: &str) -> Option<String> {
+    let (index, function) = line.split_once(": ")?;
+    if index.is_empty() || !index.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let function = function.trim();
+    let function = match function.rsplit_once("::h") {
+        Some((head, hash)) if hash.len() == 16 && hash.bytes().all(|b| b.is_ascii_hexdigit()) => {
+            head
+        }
+        _ => function,
+    };
+    (!function.is_empty()).then(|| function.replace("::{{closure}}", ""))
+}
+
+/// `thread 'main' panicked at src/main.rs:10:5:` (and the older
+/// `panicked at 'message', src/main.rs:10:5`).
+fn parse_panic_location(line: &str) -> Option<StackFrame> {
+    let (_, rest) = line.split_once("panicked at ")?;
+    let location = rest.split_whitespace().last()?;
+    let (file, line) = parse_location(location.trim_end_matches([':', ',']))?;
+    Some(StackFrame {
+        function: None,
+        file: Some(file),
+        line: Some(line),
+    })
+}
+
+/// The part of a V8 frame after `at `: `handler (/app/src/server.js:10:15)`,
+/// `async run (file:///app/main.mjs:3:1)`, or a bare location.
+fn parse_v8_frame(rest: &str) -> Option<StackFrame> {
+    let (function, location) = match rest.strip_suffix(')').and_then(|r| r.rsplit_once(" (")) {
+        Some((function, location)) => {
+            let function = function.trim_start_matches("async ").trim();
+            (Some(function.to_string()), location)
+        }
+        None => (None, rest),
+    };
+    let (file, line) = parse_location(location)?;
+    Some(StackFrame {
+        function: function.filter(|f| !f.is_empty() && !f.starts_with('<')),
+        file: Some(file),
+        line: Some(line),
+    })
+}
+
+/// `handler@http://localhost:3000/src/server.js:10:15`
+fn parse_firefox_frame(line: &str) -> Option<StackFrame> {
+    if line.contains(char::is_whitespace) {
+        return None;
+    }
+    let (function, location) = line.split_once('@')?;
+    let (file, line) = parse_location(location)?;
+    Some(StackFrame {
+        function: (!function.is_empty()).then(|| function.to_string()),
+        file: Some(file),
+        line: Some(line),
+    })
+}
+
+/// Split `path:line[:column]` into the path and line. `file://` URLs become
+/// their path, and other URLs the path after their host.
+fn parse_location(location: &str) -> Option<(String, u32)> {
+    let mut parts = location.trim().rsplitn(3, ':');
+    let last = parts.next()?;
+    let second = parts.next()?;
+    let (file, line) = match (second.parse::<u32>(), last.parse::<u32>()) {
+        // path:line:column
+        (Ok(line), Ok(_)) => (parts.next()?, line),
+        // path:line
+        (Err(_), Ok(line)) => (location.trim().rsplit_once(':')?.0, line),
+        _ => return None,
+    };
+    let file = if let Some(path) = file.strip_prefix("file://") {
+        path
+    } else if let Some((_, rest)) = file.split_once("://") {
+        rest.find('/').map_or(rest, |slash| &rest[slash..])
+    } else {
+        file
+    };
+    (!file.is_empty() && line > 0).then(|| (file.to_string(), line))
+}
+
+/// Map `frames` to indexed chunks.
+///
+/// A frame's file is the indexed path it ends with (the longest, when
+/// several do), so `/app/src/server.js` finds `src/server.js`; its chunk is
+/// the smallest one enclosing the line. Frames without a resolvable file
+/// are looked up by function name.
+pub fn resolve(
+    index: &MetadataIndex,
+    repo_root: &Path,
+    frames: Vec<StackFrame>,
+) -> OmniResult<Vec<ResolvedFrame>> {
+    let files = index.get_all_files()?;
+    let mut resolved = Vec::with_capacity(frames.len());
+    for frame in frames {
+        let file = frame
+            .file
+            .as_deref()
+            .and_then(|path| match_indexed_file(&files, repo_root, path));
+        let chunk = match (file, frame.line) {
+            (Some(file), Some(line)) => index
+                .get_chunks_for_file(file.id)?
+                .into_iter()
+                .filter(|c| c.line_start <= line && line <= c.line_end)
+                .min_by_key(|c| c.line_end - c.line_start),
+            _ => None,
+        };
+        let chunk = match (chunk, file, frame.function.as_deref()) {
+            (None, None, Some(function)) => chunk_for_function(index, function)?,
+            (chunk, ..) => chunk,
+        };
+        let file_path = match (&chunk, file) {
+            (_, Some(file)) => Some(file.path.clone()),
+            (Some(chunk), None) => index.get_file_by_id(chunk.file_id)?.map(|f| f.path),
+            (None, None) => None,
+        };
+        resolved.push(ResolvedFrame {
+            frame,
+            file_path,
+            symbol_path: chunk.as_ref().map(|c| c.symbol_path.clone()),
+            chunk,
+        });
+    }
+    Ok(resolved)
+}
+
+/// The indexed file `path` (as printed in a trace) refers to.
+fn match_indexed_file<'a>(
+    files: &'a [FileInfo],
+    repo_root: &Path,
+    path: &str,
+) -> Option<&'a FileInfo> {
+    let relative = crate::paths::strip_root(Path::new(path), repo_root)
+        .map_or_else(|| path.to_string(), |rel| crate::paths::index_form(&rel));
+    let printed = relative.replace('\\', "/");
+    let printed = printed.trim_start_matches("./");
+    files
+        .iter()
+        .filter(|file| {
+            let indexed = crate::paths::index_form(&file.path);
+            printed == indexed || printed.ends_with(&format!("/{indexed}"))
+        })
+        .max_by_key(|file| file.path.as_os_str().len())
+}
+
+/// The chunk defining `function`, looked up by its last path segment and
+/// preferring the symbol whose qualified name shares the most segments.
+fn chunk_for_function(index: &MetadataIndex, function: &str) -> OmniResult<Option<Chunk>> {
+    let segments: Vec<&str> = function
+        .split(|c| c == ':' || c == '.')
+        .filter(|s| !s.is_empty())
+        .collect();
+    let Some(name) = segments.last() else {
+        return Ok(None);
+    };
+    let shared = |fqn: &str| {
+        fqn.split(|c| c == ':' || c == '.')
+            .filter(|s| !s.is_empty())
+            .rev()
+            .zip(segments.iter().rev())
+            .take_while(|(a, b)| a == *b)
+            .count()
+    };
+    let best = index
+        .search_symbols_by_fqn_suffix(name, 50)?
+        .into_iter()
+        .filter(|symbol| symbol.name == *name)
+        .filter_map(|symbol| Some((shared(&symbol.fqn), symbol.chunk_id?)))
+        .max_by_key(|(shared, _)| *shared);
+    match best {
+        Some((_, chunk_id)) => index.get_chunk_by_id(chunk_id),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(function: Option<&str>, file: &str, line: u32) -> StackFrame {
+        StackFrame {
+            function: function.map(str::to_string),
+            file: Some(file.to_string()),
+            line: Some(line),
+        }
+    }
+
+    #[test]
+    fn test_parse_rust_panic_and_backtrace() {
+        let text = "\
+thread 'main' panicked at src/parser.rs:42:9:
+index out of bounds
+stack backtrace:
+   0: rust_begin_unwind
+             at /rustc/abc/library/std/src/panicking.rs:652:5
+   1: my_crate::parser::parse_header::h0123456789abcdef
+             at ./src/parser.rs:42:9
+   2: my_crate::main::{{closure}}
+   3: my_crate::main
+             at ./src/main.rs:7:5";
+        assert_eq!(
+            parse(text),
+            vec![
+                frame(None, "src/parser.rs", 42),
+                frame(
+                    Some("rust_begin_unwind"),
+                    "/rustc/abc/library/std/src/panicking.rs",
+                    652
+                ),
+                frame(
+                    Some("my_crate::parser::parse_header"),
+                    "./src/parser.rs",
+                    42
+                ),
+                StackFrame {
+                    function: Some("my_crate::main".to_string()),
+                    ..StackFrame::default()
+                },
+                frame(Some("my_crate::main"), "./src/main.rs", 7),
+            ]
+        );
+
+        let old = "thread 'main' panicked at 'boom', src/lib.rs:3:1";
+        assert_eq!(parse(old), vec![frame(None, "src/lib.rs", 3)]);
+    }
+
+    #[test]
+    fn test_parse_python_traceback_innermost_first() {
+        let text = r#"Traceback (most recent call last):
+  File "/srv/app/main.py", line 10, in <module>
+    run()
+  File "/srv/app/handlers.py", line 22, in run
+    parse(data)
+ValueError: bad"#;
+        assert_eq!(
+            parse(text),
+            vec![
+                frame(Some("run"), "/srv/app/handlers.py", 22),
+                frame(None, "/srv/app/main.py", 10),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_javascript_stacks() {
+        let v8 = "\
+TypeError: Cannot read properties of undefined
+    at parseBody (/app/src/server.js:10:15)
+    at async handle (file:///app/src/router.mjs:3:1)
+    at /app/src/index.js:5:2
+    at node:internal/main:1:1";
+        assert_eq!(
+            parse(v8),
+            vec![
+                frame(Some("parseBody"), "/app/src/server.js", 10),
+                frame(Some("handle"), "/app/src/router.mjs", 3),
+                frame(None, "/app/src/index.js", 5),
+                frame(None, "node:internal/main", 1),
+            ]
+        );
+
+        let firefox = "parseBody@http://localhost:3000/src/server.js:10:15";
+        assert_eq!(
+            parse(firefox),
+            vec![frame(Some("parseBody"), "/src/server.js", 10)]
+        );
+    }
+
+    #[test]
+    fn test_match_indexed_file_by_suffix() {
+        let file = |id, path: &str| FileInfo {
+            id,
+            path: path.into(),
+            language: crate::types::Language::Python,
+            content_hash: String::new(),
+            size_bytes: 0,
+        };
+        let files = [file(1, "handlers.py"), file(2, "app/handlers.py")];
+        let root = Path::new("/repo");
+        let id = |path| match_indexed_file(&files, root, path).map(|f| f.id);
+        assert_eq!(id("/srv/app/handlers.py"), Some(2));
+        assert_eq!(id("/repo/handlers.py"), Some(1));
+        assert_eq!(id("./handlers.py"), Some(1));
+        assert_eq!(id("/srv/other.py"), None);
+        assert_eq!(id("/srv/myhandlers.py"), None);
+    }
+}
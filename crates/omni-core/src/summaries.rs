@@ -0,0 +1,348 @@
+//! Hierarchical file and directory summaries.
+//!
+//! A file summary is built from the file's chunks: counts per chunk kind, the
+//! first doc comment, and the highest-weight public symbols. A directory
+//! summary aggregates the stored file summaries beneath it, so refreshing a
+//! directory never re-reads chunk content.
+//!
+//! Summaries live in the `summaries` table and are refreshed for the changed
+//! files and their ancestor directories after each index pass. A file
+//! reindexed on its own has its summary refreshed at once; its directories
+//! are only marked dirty and refreshed in a batch (see [`refresh_dirs`]), so
+//! a burst of single-file updates aggregates the root once rather than once
+//! per file.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use crate::error::OmniResult;
+use crate::index::MetadataIndex;
+use crate::types::{FileInfo, ModuleSummary, SummaryKind, Visibility};
+
+/// Path of the repository root summary.
+pub const ROOT: &str = ".";
+
+/// Key symbols kept per summary.
+const KEY_SYMBOLS: usize = 8;
+
+/// Files named in a directory summary.
+const LARGEST_FILES: usize = 3;
+
+/// Normalise a user- or index-supplied path to summary form: relative,
+/// `/`-separated, no trailing slash, `.` for the root.
+pub fn normalize_path(path: &str) -> String {
+    let path = path.replace('\\', "/");
+    let path = path.trim_matches('/').trim_start_matches("./");
+    if path.is_empty() || path == "." {
+        ROOT.to_string()
+    } else {
+        path.to_string()
+    }
+}
+
+/// Build the summary of an indexed file from its chunks.
+pub fn summarize_file(index: &MetadataIndex, file: &FileInfo) -> OmniResult<ModuleSummary> {
+    let mut chunks = index.get_chunks_for_file(file.id)?;
+    chunks.retain(|c| !c.is_summary);
+    chunks.sort_by_key(|c| c.line_start);
+
+    let mut kinds: BTreeMap<String, usize> = BTreeMap::new();
+    for chunk in &chunks {
+        *kinds
+            .entry(format!("{:?}", chunk.kind).to_lowercase())
+            .or_insert(0) += 1;
+    }
+    let counts: Vec<String> = kinds
+        .iter()
+        .map(|(kind, n)| format!("{n} {kind}{}", if *n == 1 { "" } else { "s" }))
+        .collect();
+
+    let doc = chunks
+        .iter()
+        .filter_map(|c| c.doc_comment.as_deref())
+        .flat_map(str::lines)
+        .map(str::trim)
+        .find(|line| !line.is_empty());
+
+    let mut ranked: Vec<_> = chunks.iter().collect();
+    ranked.sort_by(|a, b| {
+        (b.visibility == Visibility::Public)
+            .cmp(&(a.visibility == Visibility::Public))
+            .then_with(|| {
+                b.weight
+                    .partial_cmp(&a.weight)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            })
+            .then_with(|| a.line_start.cmp(&b.line_start))
+    });
+    let mut key_symbols: Vec<String> = Vec::new();
+    for chunk in ranked {
+        if key_symbols.len() == KEY_SYMBOLS {
+            break;
+        }
+        if !key_symbols.contains(&chunk.symbol_path) {
+            key_symbols.push(chunk.symbol_path.clone());
+        }
+    }
+
+    let mut summary = if counts.is_empty() {
+        format!("{} file with no indexed symbols.", file.language.as_str())
+    } else {
+        format!(
+            "{} file with {}.",
+            file.language.as_str(),
+            counts.join(", ")
+        )
+    };
+    if let Some(doc) = doc {
+        summary.push(' ');
+        summary.push_str(doc);
+    }
+
+    Ok(ModuleSummary {
+        path: normalize_path(&file.path.to_string_lossy()),
+        kind: SummaryKind::File,
+        summary,
+        key_symbols,
+        file_count: 1,
+        chunk_count: chunks.len(),
+    })
+}
+
+/// Aggregate the file summaries beneath directory `dir`.
+pub fn summarize_dir(dir: &str, files: &[ModuleSummary]) -> ModuleSummary {
+    let chunk_count = files.iter().map(|f| f.chunk_count).sum();
+
+    let mut by_size: Vec<&ModuleSummary> = files.iter().collect();
+    by_size.sort_by(|a, b| {
+        b.chunk_count
+            .cmp(&a.chunk_count)
+            .then_with(|| a.path.cmp(&b.path))
+    });
+
+    // Round-robin over the largest files so one big file can't fill the list.
+    let mut key_symbols: Vec<String> = Vec::new();
+    let depth = by_size
+        .iter()
+        .map(|f| f.key_symbols.len())
+        .max()
+        .unwrap_or(0);
+    'outer: for i in 0..depth {
+        for file in &by_size {
+            if key_symbols.len() == KEY_SYMBOLS {
+                break 'outer;
+            }
+            if let Some(symbol) = file.key_symbols.get(i) {
+                if !key_symbols.contains(symbol) {
+                    key_symbols.push(symbol.clone());
+                }
+            }
+        }
+    }
+
+    let mut subdirs: BTreeSet<&str> = BTreeSet::new();
+    let prefix_len = if dir == ROOT { 0 } else { dir.len() + 1 };
+    for file in files {
+        if let Some((child, _)) = file.path[prefix_len..].split_once('/') {
+            subdirs.insert(child);
+        }
+    }
+
+    let mut summary = format!(
+        "{} file{} ({} chunks)",
+        files.len(),
+        if files.len() == 1 { "" } else { "s" },
+        chunk_count
+    );
+    if !subdirs.is_empty() {
+        let names: Vec<&str> = subdirs.into_iter().collect();
+        summary.push_str(&format!(" across subdirectories {}", names.join(", ")));
+    }
+    summary.push('.');
+    let largest: Vec<&str> = by_size
+        .iter()
+        .take(LARGEST_FILES)
+        .map(|f| f.path[prefix_len..].as_ref())
+        .collect();
+    if !largest.is_empty() {
+        summary.push_str(&format!(" Largest files: {}.", largest.join(", ")));
+    }
+
+    ModuleSummary {
+        path: dir.to_string(),
+        kind: SummaryKind::Directory,
+        summary,
+        key_symbols,
+        file_count: files.len(),
+        chunk_count,
+    }
+}
+
+/// Refresh the summaries of `paths` (relative file paths) and every
+/// directory above them. Files no longer in the index lose their summary,
+/// as do directories left empty. Returns the number of summaries written.
+pub fn refresh<P: AsRef<Path>>(index: &MetadataIndex, paths: &[P]) -> OmniResult<usize> {
+    let mut dirs = BTreeSet::new();
+    let written = refresh_files(index, paths, &mut dirs)?;
+    Ok(written + refresh_dirs(index, dirs)?)
+}
+
+/// Refresh the summaries of `paths` (relative file paths) alone, adding
+/// their ancestor directories to `dirs` for a later [`refresh_dirs`].
+/// Returns the number of summaries written.
+pub fn refresh_files<P: AsRef<Path>>(
+    index: &MetadataIndex,
+    paths: &[P],
+    dirs: &mut BTreeSet<String>,
+) -> OmniResult<usize> {
+    let mut written = 0;
+    for path in paths {
+        let path = path.as_ref();
+        let key = normalize_path(&path.to_string_lossy());
+        match index.get_file_by_path(path)? {
+            Some(file) => {
+                index.upsert_summary(&summarize_file(index, &file)?)?;
+                written += 1;
+            }
+            None => {
+                index.delete_summary(&key)?;
+            }
+        }
+        let mut dir = key.as_str();
+        while let Some((parent, _)) = dir.rsplit_once('/') {
+            dirs.insert(parent.to_string());
+            dir = parent;
+        }
+        dirs.insert(ROOT.to_string());
+    }
+    Ok(written)
+}
+
+/// Re-aggregate the directory summaries in `dirs` from the file summaries
+/// beneath them. Directories left empty lose their summary. Returns the
+/// number of summaries written.
+pub fn refresh_dirs(
+    index: &MetadataIndex,
+    dirs: impl IntoIterator<Item = String>,
+) -> OmniResult<usize> {
+    let mut written = 0;
+    for dir in dirs {
+        let files = index.file_summaries_under(&dir)?;
+        if files.is_empty() {
+            index.delete_summary(&dir)?;
+        } else {
+            index.upsert_summary(&summarize_dir(&dir, &files))?;
+            written += 1;
+        }
+    }
+
+    Ok(written)
+}
+
+/// Rebuild summaries for every indexed file and directory.
+pub fn refresh_all(index: &MetadataIndex) -> OmniResult<usize> {
+    let paths: Vec<_> = index.get_all_files()?.into_iter().map(|f| f.path).collect();
+    refresh(index, &paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Chunk, ChunkKind, ComplexityMetrics, Language};
+
+    fn add_file(index: &MetadataIndex, path: &str, symbols: &[(&str, Option<&str>)]) {
+        let file_id = index
+            .upsert_file(&FileInfo {
+                id: 0,
+                path: path.into(),
+                language: Language::Rust,
+                content_hash: path.to_string(),
+                size_bytes: 1,
+            })
+            .expect("upsert file");
+        for (i, (symbol, doc)) in symbols.iter().enumerate() {
+            index
+                .insert_chunk(&Chunk {
+                    id: 0,
+                    file_id,
+                    symbol_path: (*symbol).to_string(),
+                    kind: ChunkKind::Function,
+                    visibility: Visibility::Public,
+                    line_start: i as u32 * 10 + 1,
+                    line_end: i as u32 * 10 + 5,
+                    content: format!("fn {symbol}() {{}}"),
+                    doc_comment: doc.map(str::to_string),
+                    token_count: 4,
+                    weight: 1.0 - i as f64 * 0.1,
+                    vector_id: None,
+                    is_summary: false,
+                    is_generated: false,
+                    language: None,
+                    content_hash: 0,
+                    complexity: ComplexityMetrics::default(),
+                })
+                .expect("insert chunk");
+        }
+    }
+
+    #[test]
+    fn test_normalize_path() {
+        assert_eq!(normalize_path(""), ".");
+        assert_eq!(normalize_path("./"), ".");
+        assert_eq!(normalize_path("src\\auth\\"), "src/auth");
+        assert_eq!(normalize_path("./src/lib.rs"), "src/lib.rs");
+    }
+
+    #[test]
+    fn test_refresh_builds_and_prunes_hierarchy() {
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let index = MetadataIndex::open(&dir.path().join("index.db")).expect("open db");
+        add_file(
+            &index,
+            "src/auth/token.rs",
+            &[
+                ("auth::issue", Some("Issues access tokens.")),
+                ("auth::revoke", None),
+            ],
+        );
+        add_file(&index, "src/db.rs", &[("db::connect", None)]);
+
+        refresh_all(&index).expect("refresh");
+
+        let file = index
+            .get_summary("src/auth/token.rs")
+            .expect("get")
+            .expect("file summary");
+        assert_eq!(file.kind, SummaryKind::File);
+        assert!(file.summary.contains("2 functions"), "{}", file.summary);
+        assert!(
+            file.summary.contains("Issues access tokens."),
+            "{}",
+            file.summary
+        );
+        assert_eq!(file.key_symbols, vec!["auth::issue", "auth::revoke"]);
+
+        let src = index.get_summary("src").expect("get").expect("dir summary");
+        assert_eq!(src.kind, SummaryKind::Directory);
+        assert_eq!((src.file_count, src.chunk_count), (2, 3));
+        assert!(
+            src.summary.contains("subdirectories auth"),
+            "{}",
+            src.summary
+        );
+        assert_eq!(src.key_symbols[..2], ["auth::issue", "db::connect"]);
+        assert!(index.get_summary(ROOT).expect("get").is_some());
+
+        index
+            .delete_file(Path::new("src/auth/token.rs"))
+            .expect("delete");
+        refresh(&index, &["src/auth/token.rs"]).expect("refresh");
+        assert!(index
+            .get_summary("src/auth/token.rs")
+            .expect("get")
+            .is_none());
+        assert!(index.get_summary("src/auth").expect("get").is_none());
+        let src = index.get_summary("src").expect("get").expect("dir summary");
+        assert_eq!(src.file_count, 1);
+    }
+}
@@ -3,6 +3,7 @@
 //! These types form the API contract between modules. Changing them
 //! requires updating all consumers, so they should be stable and minimal.
 
+use serde::ser::SerializeStruct as _;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
@@ -63,6 +64,10 @@ pub enum Language {
     Kotlin,
     /// HTML (.html, .htm)
     Html,
+    /// Vue single-file components (.vue)
+    Vue,
+    /// Svelte components (.svelte)
+    Svelte,
     /// Shell / Bash (.sh, .bash, .zsh)
     Shell,
     /// Markdown (.md, .mdx)
@@ -73,6 +78,8 @@ pub enum Language {
     Yaml,
     /// JSON data (.json, .jsonc)
     Json,
+    /// Extension claimed by a WASM plugin analyzer (see [`crate::plugins`])
+    Plugin,
     /// Unknown / unsupported
     Unknown,
 }
@@ -96,6 +103,8 @@ impl Language {
             "swift" => Self::Swift,
             "kt" | "kts" => Self::Kotlin,
             "html" | "htm" => Self::Html,
+            "vue" => Self::Vue,
+            "svelte" => Self::Svelte,
             "sh" | "bash" | "zsh" => Self::Shell,
             "md" | "mdx" => Self::Markdown,
             "toml" => Self::Toml,
@@ -123,15 +132,70 @@ impl Language {
             Self::Swift => "swift",
             Self::Kotlin => "kotlin",
             Self::Html => "html",
+            Self::Vue => "vue",
+            Self::Svelte => "svelte",
             Self::Shell => "shell",
             Self::Markdown => "markdown",
             Self::Toml => "toml",
             Self::Yaml => "yaml",
             Self::Json => "json",
+            Self::Plugin => "plugin",
             Self::Unknown => "unknown",
         }
     }
 
+    /// Parse from database string (the [`Self::as_str`] form).
+    pub fn from_str_lossy(s: &str) -> Self {
+        match s {
+            "python" => Self::Python,
+            "typescript" => Self::TypeScript,
+            "javascript" => Self::JavaScript,
+            "rust" => Self::Rust,
+            "go" => Self::Go,
+            "java" => Self::Java,
+            "c" => Self::C,
+            "cpp" => Self::Cpp,
+            "csharp" => Self::CSharp,
+            "css" => Self::Css,
+            "ruby" => Self::Ruby,
+            "php" => Self::Php,
+            "swift" => Self::Swift,
+            "kotlin" => Self::Kotlin,
+            "html" => Self::Html,
+            "vue" => Self::Vue,
+            "svelte" => Self::Svelte,
+            "shell" => Self::Shell,
+            "markdown" => Self::Markdown,
+            "toml" => Self::Toml,
+            "yaml" => Self::Yaml,
+            "json" => Self::Json,
+            "plugin" => Self::Plugin,
+            _ => Self::Unknown,
+        }
+    }
+
+    /// Language named by a Markdown fence info string or an HTML `lang` /
+    /// `type` attribute: a name (`python`, `rust`), an extension (`py`,
+    /// `tsx`), or a common alias (`golang`, `c++`). Unknown for anything
+    /// else.
+    pub fn from_tag(tag: &str) -> Self {
+        let tag = tag.trim().to_ascii_lowercase();
+        let tag = tag
+            .strip_prefix("text/")
+            .or_else(|| tag.strip_prefix("application/"))
+            .unwrap_or(&tag);
+        match tag {
+            "golang" => Self::Go,
+            "c++" => Self::Cpp,
+            "c#" => Self::CSharp,
+            "ecmascript" | "module" => Self::JavaScript,
+            _ => match Self::from_str_lossy(tag) {
+                Self::Unknown | Self::Plugin => Self::from_extension(tag),
+                language => language,
+            },
+        }
+    }
+
     /// Returns true if this is an AST-parseable programming language.
     pub fn is_code(&self) -> bool {
         matches!(
@@ -300,6 +364,38 @@ impl Visibility {
     }
 }
 
+/// Structural complexity of a code element, computed from its AST.
+///
+/// A zero `cyclomatic` value means "not computed" (documents, legacy chunks);
+/// any analyzed code element has a cyclomatic complexity of at least 1.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ComplexityMetrics {
+    /// McCabe cyclomatic complexity: 1 + number of decision points
+    /// (branches, loops, case arms, catch clauses, short-circuit operators).
+    pub cyclomatic: u32,
+    /// Deepest nesting of control-flow constructs (0 = straight-line code).
+    pub nesting_depth: u32,
+}
+
+impl ComplexityMetrics {
+    /// Whether metrics were computed for this element.
+    pub fn is_computed(&self) -> bool {
+        self.cyclomatic > 0
+    }
+
+    /// Multiplier applied to structural weight when complexity weighting is on.
+    ///
+    /// Trivial straight-line code (getters, re-exports) is nudged down to 0.9x,
+    /// branchy logic up to 1.1x. Uncomputed metrics are neutral (1.0x).
+    pub fn weight_multiplier(&self) -> f64 {
+        if !self.is_computed() {
+            return 1.0;
+        }
+        let decisions = f64::from(self.cyclomatic - 1) + f64::from(self.nesting_depth);
+        0.9 + 0.2 * (decisions.min(10.0) / 10.0)
+    }
+}
+
 /// A semantically meaningful chunk of code extracted from a file.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Chunk {
@@ -330,12 +426,27 @@ pub struct Chunk {
     /// Whether this is a RAPTOR-style summary chunk (aggregated from leaf chunks).
     #[serde(default)]
     pub is_summary: bool,
+    /// Language of the code when it differs from the file's: a fenced block
+    /// in Markdown, or a `<script>` / `<style>` block in HTML, Vue, or
+    /// Svelte (see [`crate::parser::embedded`]). `None` means the file's
+    /// language.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<Language>,
+    /// Whether the chunk is generated code: its file carries a "DO NOT EDIT"
+    /// or `@generated` banner, or the chunk itself is marked generated.
+    /// Generated chunks are not embedded unless `indexing.embed_generated`,
+    /// and search leaves them out unless `include_generated` is set.
+    #[serde(default)]
+    pub is_generated: bool,
     /// xxHash3 of the chunk's content for chunk-level delta detection.
     ///
     /// A value of 0 means the hash was not computed (legacy chunks or summary
     /// chunks). Code treats hash == 0 as "not computed" and always re-embeds.
     #[serde(default)]
     pub content_hash: u64,
+    /// Structural complexity of the source element this chunk was cut from.
+    #[serde(default)]
+    pub complexity: ComplexityMetrics,
 }
 
 // ---------------------------------------------------------------------------
@@ -361,6 +472,79 @@ pub struct Symbol {
     pub chunk_id: Option<i64>,
 }
 
+/// Source-level definition of a symbol.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Definition {
+    /// Fully qualified name.
+    pub fqn: String,
+    /// What kind of symbol this is.
+    pub kind: ChunkKind,
+    /// File containing the definition (relative to the repo root).
+    pub file_path: PathBuf,
+    /// First line of the definition (1-based, inclusive).
+    pub line_start: u32,
+    /// Last line of the definition (1-based, inclusive).
+    pub line_end: u32,
+    /// Declaration line, without decorators or doc comments.
+    pub signature: String,
+    /// Doc comment, if any.
+    pub doc_comment: Option<String>,
+    /// Source text of `line_start..=line_end`.
+    pub source: String,
+}
+
+/// A chunk together with the source lines around it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExpandedContext {
+    /// The indexed chunk.
+    pub chunk: Chunk,
+    /// File containing the chunk (relative to the repo root).
+    pub file_path: PathBuf,
+    /// First line of `source` (1-based, inclusive).
+    pub line_start: u32,
+    /// Last line of `source` (1-based, inclusive).
+    pub line_end: u32,
+    /// Source text of `line_start..=line_end`.
+    pub source: String,
+    /// The file changed on disk since it was indexed (or cannot be read),
+    /// so `source` is the indexed chunk alone, without surrounding lines.
+    pub stale: bool,
+}
+
+/// An indexed file reassembled to fit a token budget.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssembledFile {
+    /// File path (relative to the repo root).
+    pub file_path: PathBuf,
+    /// Language of the file.
+    pub language: Language,
+    /// The file text, with each omitted range replaced by a marker line.
+    pub content: String,
+    /// Tokens in `content`.
+    pub token_count: u32,
+    /// Tokens in the whole file.
+    pub total_tokens: u32,
+    /// Line ranges dropped to fit the budget, in file order.
+    pub omitted: Vec<OmittedLines>,
+    /// Whether `content` was also cut off at the budget.
+    pub truncated: bool,
+    /// The file changed on disk since it was indexed, so its chunks no
+    /// longer line up and `content` is the current file cut at the budget
+    /// (or, when it cannot be read, its stored chunks in line order).
+    pub stale: bool,
+}
+
+/// A run of lines left out of an [`AssembledFile`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OmittedLines {
+    /// First omitted line (1-based, inclusive).
+    pub line_start: u32,
+    /// Last omitted line (1-based, inclusive).
+    pub line_end: u32,
+    /// Symbol paths of the chunks dropped from this range.
+    pub symbols: Vec<String>,
+}
+
 // ---------------------------------------------------------------------------
 // Dependency edge types
 // ---------------------------------------------------------------------------
@@ -408,6 +592,20 @@ pub enum DependencyKind {
     /// Two symbols that frequently change in the same commit.
     /// Higher weight = stronger correlation.
     HistoricalCoChange,
+    /// Test symbol A exercises production symbol B.
+    ///
+    /// Inferred from test bodies (references), test-file imports, and naming
+    /// conventions (`test_parse` → `parse`). Stored with the test as the
+    /// source so it points the same way as `Calls`: walking incoming edges
+    /// from a production symbol (blast radius) reaches the tests to run.
+    TestedBy,
+    /// Symbol A calls symbol B across a language boundary.
+    ///
+    /// Resolved from binding declarations: PyO3 and napi-rs exports called
+    /// from Python or TypeScript, and gRPC service methods called through
+    /// generated client stubs. Points from caller to implementation, like
+    /// `Calls`.
+    Binds,
 }
 
 impl DependencyKind {
@@ -425,6 +623,8 @@ impl DependencyKind {
             Self::ErrorFlow => "error_flow",
             Self::TypeFlow => "type_flow",
             Self::HistoricalCoChange => "historical_co_change",
+            Self::TestedBy => "tested_by",
+            Self::Binds => "binds",
         }
     }
 
@@ -442,6 +642,8 @@ impl DependencyKind {
             "error_flow" => Self::ErrorFlow,
             "type_flow" => Self::TypeFlow,
             "historical_co_change" => Self::HistoricalCoChange,
+            "tested_by" => Self::TestedBy,
+            "binds" => Self::Binds,
             _ => Self::Calls, // fallback
         }
     }
@@ -469,6 +671,25 @@ pub struct DependencyEdge {
     pub target_id: i64,
     /// Kind of dependency.
     pub kind: DependencyKind,
+    /// How sure resolution is that the edge points at the right symbol, in
+    /// `(0, 1]`: see [`DependencyEdge::EXACT`] and its siblings. Edges that
+    /// are extracted rather than resolved by name are exact.
+    #[serde(default = "DependencyEdge::exact_confidence")]
+    pub confidence: f32,
+}
+
+impl DependencyEdge {
+    /// Target found by fully qualified name, directly or through a
+    /// re-export alias.
+    pub const EXACT: f32 = 1.0;
+    /// Target found by a qualified-name suffix (`config::Config`).
+    pub const SUFFIX: f32 = 0.7;
+    /// Target guessed from a bare name; often wrong in large repositories.
+    pub const NAME_ONLY: f32 = 0.3;
+
+    fn exact_confidence() -> f32 {
+        Self::EXACT
+    }
 }
 
 /// An import statement extracted from source code.
@@ -487,21 +708,220 @@ pub struct ImportStatement {
     pub kind: DependencyKind,
 }
 
+/// A name a module re-exports from another module: a Rust `pub use`, a
+/// TypeScript `export { X } from`, or a Python package `__init__.py`
+/// importing from its submodules.
+///
+/// Both FQNs are in the form of symbol FQNs, with relative paths resolved
+/// against the re-exporting file. A glob re-export ends both in `*`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SymbolAlias {
+    /// FQN the name is visible under (e.g. `config::Config`).
+    pub alias_fqn: String,
+    /// FQN it stands for, which may itself be an alias
+    /// (e.g. `config::loader::Config`).
+    pub target_fqn: String,
+    /// Line of the re-export.
+    pub line: u32,
+}
+
+/// A TODO, FIXME, or HACK comment found while indexing (see
+/// [`crate::parser::todos`]).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TodoComment {
+    /// File the comment is in, relative to the repository root.
+    pub file_path: PathBuf,
+    /// Line of the marker (1-based).
+    pub line: u32,
+    /// The marker: `TODO`, `FIXME`, or `HACK`.
+    pub tag: String,
+    /// Name given with the marker, as in `TODO(alice):`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub assignee: Option<String>,
+    /// Comment text after the marker.
+    pub text: String,
+    /// Author of the line according to `git blame`; filled when listing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    /// Days since the line was last changed according to `git blame`;
+    /// filled when listing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub age_days: Option<u64>,
+}
+
 // ---------------------------------------------------------------------------
 // Search types
 // ---------------------------------------------------------------------------
 
 /// A search result with scoring details.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// Serializes with an extra `provenance` list derived from
+/// `score_breakdown` at that moment (see [`SearchResult::provenance`]), so
+/// it reflects reranking and other late score adjustments.
+#[derive(Debug, Clone, Deserialize)]
 pub struct SearchResult {
     /// The matched chunk.
     pub chunk: Chunk,
     /// File path of the matched chunk.
     pub file_path: PathBuf,
-    /// Overall relevance score (higher is better).
+    /// Overall relevance score (higher is better). Only comparable within
+    /// one query; see `relevance` for an absolute scale.
     pub score: f64,
+    /// Calibrated relevance in `[0, 1]`, comparable across queries. Below
+    /// [`crate::search::calibration::PROBABLY_IRRELEVANT`] the result is
+    /// probably irrelevant.
+    #[serde(default)]
+    pub relevance: f64,
     /// Breakdown of how the score was computed (for debugging).
     pub score_breakdown: ScoreBreakdown,
+    /// CODEOWNERS owners of the file (empty when unowned or no CODEOWNERS).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub owners: Vec<String>,
+}
+
+impl SearchResult {
+    /// Which signals found and lifted this result, e.g. `["keyword#3",
+    /// "semantic#7", "graph+0.30"]`; see [`ScoreBreakdown::provenance`].
+    pub fn provenance(&self) -> Vec<String> {
+        self.score_breakdown.provenance()
+    }
+}
+
+impl Serialize for SearchResult {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let provenance = self.provenance();
+        let mut s = serializer.serialize_struct("SearchResult", 7)?;
+        s.serialize_field("chunk", &self.chunk)?;
+        s.serialize_field("file_path", &self.file_path)?;
+        s.serialize_field("score", &self.score)?;
+        s.serialize_field("relevance", &self.relevance)?;
+        s.serialize_field("score_breakdown", &self.score_breakdown)?;
+        if self.owners.is_empty() {
+            s.skip_field("owners")?;
+        } else {
+            s.serialize_field("owners", &self.owners)?;
+        }
+        if provenance.is_empty() {
+            s.skip_field("provenance")?;
+        } else {
+            s.serialize_field("provenance", &provenance)?;
+        }
+        s.end()
+    }
+}
+
+/// File and chunk filters for a search. All set filters must hold.
+///
+/// Filters are resolved against the `files` and `chunks` tables before
+/// fusion, so every retrieval signal only ranks matching chunks.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchOptions {
+    /// Only files whose filesystem modification time, as of their last
+    /// indexing, is at or after this ISO 8601 date or datetime (e.g.
+    /// `2025-03-01` or `2025-03-01T09:00:00Z`; UTC if no offset is given).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified_after: Option<String>,
+    /// Only files whose modification time is before this date or datetime.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified_before: Option<String>,
+    /// Only files of at least this many bytes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_size_bytes: Option<u64>,
+    /// Only files of at most this many bytes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_size_bytes: Option<u64>,
+    /// Only these files, relative to the repository root.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub files: Vec<PathBuf>,
+    /// Only files in this language (e.g. `rust`, `python`; any case).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// Only chunks of this kind (e.g. `function`, `class`; any case).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+    /// Only files whose repository-relative path matches this glob
+    /// (e.g. `src/auth/**`; any case).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path_glob: Option<String>,
+    /// Candidates fetched from each retrieval signal before fusion, in place
+    /// of the engine default (100). Deeper retrieval finds more for broad
+    /// queries at the cost of latency.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub candidates: Option<usize>,
+    /// Token budget for this query's results and context window, in place
+    /// of `search.token_budget`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_budget: Option<u32>,
+    /// What the caller does not want, e.g. `tests protobuf`. Results whose
+    /// path, keywords, or embedding match it are pushed down the ranking.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub negative_query: Option<String>,
+    /// Also return generated code (see [`Chunk::is_generated`]), which is
+    /// left out by default.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub include_generated: bool,
+}
+
+impl SearchOptions {
+    /// Search only `files` (e.g. those named in a stack trace or diff),
+    /// relative to the repository root or absolute within it. Every
+    /// retrieval signal is restricted to their chunks before fusion, so the
+    /// results are the best matches among them rather than the global top
+    /// results that happen to be in them. Replaces [`Self::files`].
+    #[must_use]
+    pub fn restrict_to_files(mut self, files: Vec<PathBuf>) -> Self {
+        self.files = files;
+        self
+    }
+
+    /// Whether no filter or override is set.
+    pub fn is_empty(&self) -> bool {
+        !self.has_filters()
+            && self.candidates.is_none()
+            && self.token_budget.is_none()
+            && self.negative_query.is_none()
+            && !self.include_generated
+    }
+
+    /// Whether any filter restricting the searched chunks is set.
+    pub fn has_filters(&self) -> bool {
+        !(self.modified_after.is_none()
+            && self.modified_before.is_none()
+            && self.min_size_bytes.is_none()
+            && self.max_size_bytes.is_none()
+            && self.files.is_empty()
+            && self.language.is_none()
+            && self.kind.is_none()
+            && self.path_glob.is_none())
+    }
+
+    /// The filters that are set, as `name=value`.
+    pub fn active_filters(&self) -> Vec<String> {
+        let mut filters = Vec::new();
+        let mut push = |name: &str, value: Option<String>| {
+            if let Some(value) = value {
+                filters.push(format!("{name}={value}"));
+            }
+        };
+        push("modified_after", self.modified_after.clone());
+        push("modified_before", self.modified_before.clone());
+        push("min_size_bytes", self.min_size_bytes.map(|n| n.to_string()));
+        push("max_size_bytes", self.max_size_bytes.map(|n| n.to_string()));
+        push(
+            "files",
+            (!self.files.is_empty()).then(|| {
+                self.files
+                    .iter()
+                    .map(|f| f.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(",")
+            }),
+        );
+        push("language", self.language.clone());
+        push("kind", self.kind.clone());
+        push("path_glob", self.path_glob.clone());
+        filters
+    }
 }
 
 /// Detailed scoring breakdown for a search result.
@@ -511,14 +931,30 @@ pub struct ScoreBreakdown {
     pub semantic_rank: Option<u32>,
     /// Rank from keyword (FTS5) search (None if semantic-only match).
     pub keyword_rank: Option<u32>,
+    /// Rank from the symbol table lookup (None if not a symbol match).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub symbol_rank: Option<u32>,
     /// RRF fusion score (before reranking).
     pub rrf_score: f64,
-    /// Cross-encoder reranker score (None if not reranked).
+    /// Cross-encoder reranker score, min-max normalized over the reranked
+    /// candidates (None if not reranked).
     pub reranker_score: Option<f64>,
+    /// Cross-encoder probability that the chunk answers the query, before
+    /// normalization (None if not reranked).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reranker_probability: Option<f64>,
+    /// Share of the score removed by the negative query (None without one
+    /// or when the chunk does not match it).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub negative_penalty: Option<f64>,
     /// Structural importance weight applied.
     pub structural_weight: f64,
     /// Dependency proximity boost applied.
     pub dependency_boost: f64,
+    /// Combined lift from graph proximity, open files, bug history,
+    /// PageRank, freshness, and branch changes (0.3 = +30%).
+    #[serde(default)]
+    pub graph_boost: f64,
     /// Recency boost applied.
     pub recency_boost: f64,
     /// PageRank-based symbol importance boost (0.0–1.0 percentile).
@@ -527,6 +963,352 @@ pub struct ScoreBreakdown {
     /// Rank from BGE-M3 sparse (SPLADE-style) retrieval signal.
     /// `None` when `enable_sparse_retrieval = false` (default).
     pub sparse_rank: Option<u32>,
+    /// Model-backed signals (`semantic`, `reranker`) skipped for this query
+    /// to stay within `search.max_latency_ms`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped_signals: Vec<String>,
+}
+
+impl ScoreBreakdown {
+    /// Compact list of the signals behind a result: the rank from each
+    /// retrieval signal (`keyword#3`, `semantic#7`, `symbol#1`, `sparse#2`),
+    /// score lifts (`gar+0.20` for graph-augmented retrieval, `graph+0.30`),
+    /// the reranker's probability (`rerank=0.82`), a negative-query penalty
+    /// (`negative-0.40`), and signals skipped for latency (`skipped:semantic`).
+    pub fn provenance(&self) -> Vec<String> {
+        let mut out = Vec::new();
+        for (signal, rank) in [
+            ("keyword", self.keyword_rank),
+            ("semantic", self.semantic_rank),
+            ("symbol", self.symbol_rank),
+            ("sparse", self.sparse_rank),
+        ] {
+            if let Some(rank) = rank {
+                out.push(format!("{signal}#{rank}"));
+            }
+        }
+        if self.dependency_boost > 0.0 {
+            out.push(format!("gar+{:.2}", self.dependency_boost));
+        }
+        if self.graph_boost > 0.0 {
+            out.push(format!("graph+{:.2}", self.graph_boost));
+        }
+        if let Some(probability) = self.reranker_probability {
+            out.push(format!("rerank={probability:.2}"));
+        }
+        if let Some(penalty) = self.negative_penalty {
+            out.push(format!("negative-{penalty:.2}"));
+        }
+        out.extend(self.skipped_signals.iter().map(|s| format!("skipped:{s}")));
+        out
+    }
+}
+
+/// One row of the opt-in search telemetry log (`[search] log_queries`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchLogEntry {
+    /// When the search ran (`YYYY-MM-DD HH:MM:SS`, UTC).
+    pub logged_at: String,
+    /// Query text as received.
+    pub query: String,
+    /// Query classification (`Symbol`, `Keyword`, `NaturalLanguage`, `Mixed`).
+    pub query_type: String,
+    /// Wall-clock search latency in milliseconds.
+    pub latency_ms: f64,
+    /// How many returned results each retrieval signal contributed to.
+    pub signals: SignalCounts,
+    /// Returned chunk IDs, best first.
+    pub chunk_ids: Vec<i64>,
+    /// Error message if the search failed.
+    pub error: Option<String>,
+}
+
+/// Per-signal contribution counts for a result list.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignalCounts {
+    /// Results with a keyword (FTS5) rank.
+    pub keyword: usize,
+    /// Results with a semantic (vector) rank.
+    pub semantic: usize,
+    /// Results with a sparse (SPLADE) rank.
+    pub sparse: usize,
+    /// Results scored by the cross-encoder reranker.
+    pub reranked: usize,
+}
+
+impl SignalCounts {
+    /// Count signal contributions across `results`.
+    pub fn from_results(results: &[SearchResult]) -> Self {
+        let count = |f: fn(&ScoreBreakdown) -> bool| {
+            results.iter().filter(|r| f(&r.score_breakdown)).count()
+        };
+        Self {
+            keyword: count(|b| b.keyword_rank.is_some()),
+            semantic: count(|b| b.semantic_rank.is_some()),
+            sparse: count(|b| b.sparse_rank.is_some()),
+            reranked: count(|b| b.reranker_score.is_some()),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Embedding types
+// ---------------------------------------------------------------------------
+
+/// Identity of the embedding model that produced a set of vectors.
+///
+/// Recorded in both `index.db` and the vector file header so that a model
+/// swap with the same dimensions (and different semantics) is detected.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EmbeddingModelInfo {
+    /// Model name (e.g. `CodeRankEmbed`) or the ONNX file name for a
+    /// user-supplied model.
+    pub name: String,
+    /// Pinned digest or HuggingFace revision of the model weights.
+    pub revision: String,
+    /// Output embedding dimensions.
+    pub dimensions: usize,
+}
+
+/// Index-wide metadata recorded in `index.db` (see
+/// [`crate::index::meta_keys`]). Fields are `None` when not recorded, e.g.
+/// in databases created before the key existed.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexMetadata {
+    /// When the database was created (`YYYY-MM-DD HH:MM:SS`, UTC).
+    pub created_at: Option<String>,
+    /// Version of the engine that last indexed the repository.
+    pub engine_version: Option<String>,
+    /// Embedding model (and dimensions) of the stored vectors.
+    pub embedding_model: Option<EmbeddingModelInfo>,
+    /// Root commit of the indexed repository; see
+    /// [`crate::branch_diff::repo_fingerprint`].
+    pub repo_fingerprint: Option<String>,
+    /// `origin` remote URL of the indexed repository.
+    pub repo_origin: Option<String>,
+    /// When the last index run finished (`YYYY-MM-DD HH:MM:SS`, UTC).
+    pub last_full_index: Option<String>,
+}
+
+impl std::fmt::Display for EmbeddingModelInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}@{} ({}d)", self.name, self.revision, self.dimensions)
+    }
+}
+
+/// Load state and recent cost of an ONNX model (embedder or reranker).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ModelHealth {
+    /// Whether the model is loaded and serving requests.
+    pub available: bool,
+    /// Model name (e.g. `CodeRankEmbed`), or the ONNX file name for a
+    /// user-supplied model.
+    pub name: String,
+    /// Pinned digest or revision of the model weights.
+    pub revision: String,
+    /// Bytes the model occupies on disk; 0 when it is not downloaded.
+    pub size_bytes: u64,
+    /// Wall-clock time of the most recent query-time inference call, in
+    /// milliseconds. `None` until the model has served a query.
+    pub last_latency_ms: Option<f64>,
+    /// Why the model is not available, when it isn't.
+    pub unavailable_reason: Option<String>,
+}
+
+/// Approximate heap memory held by the engine's in-memory structures.
+///
+/// Estimates count element sizes and string lengths, not allocator slack,
+/// so treat them as a lower bound.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemoryUsage {
+    /// Resident vectors, the ANN structure, and spill bookkeeping.
+    pub vector_index_bytes: usize,
+    /// Vectors moved to disk to stay under `storage.memory_limit_mb`.
+    pub spilled_vectors: usize,
+    /// Symbol-level and file-level dependency graphs.
+    pub dependency_graph_bytes: usize,
+    /// Per-file caches: content hashes, mtimes, and the symbol → file index
+    /// used to resolve call edges.
+    pub parse_cache_bytes: usize,
+    /// Sum of the above.
+    pub total_bytes: usize,
+    /// `storage.memory_limit_mb` in bytes, when set. Applies to the vector
+    /// index only.
+    pub vector_limit_bytes: Option<usize>,
+}
+
+// ---------------------------------------------------------------------------
+// Module summary types
+// ---------------------------------------------------------------------------
+
+/// Whether a [`ModuleSummary`] describes a file or a directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SummaryKind {
+    /// A single source file.
+    File,
+    /// A directory, aggregated from the files beneath it.
+    Directory,
+}
+
+impl SummaryKind {
+    /// String form stored in the `summaries` table.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::File => "file",
+            Self::Directory => "directory",
+        }
+    }
+
+    /// Parse the stored string form.
+    pub fn from_str_lossy(s: &str) -> Self {
+        if s == "directory" {
+            Self::Directory
+        } else {
+            Self::File
+        }
+    }
+}
+
+/// Generated overview of a file or directory.
+///
+/// Paths are relative to the repository root with `/` separators; the root
+/// directory itself is `.`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModuleSummary {
+    /// File or directory path.
+    pub path: String,
+    /// File or directory.
+    pub kind: SummaryKind,
+    /// One-paragraph description built from symbols and doc comments.
+    pub summary: String,
+    /// Most important symbols, best first.
+    pub key_symbols: Vec<String>,
+    /// Files covered (1 for a file summary).
+    pub file_count: usize,
+    /// Chunks covered.
+    pub chunk_count: usize,
+}
+
+// ---------------------------------------------------------------------------
+// Change journal types
+// ---------------------------------------------------------------------------
+
+/// What happened to a file in a [`FileChange`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeOp {
+    /// The file was (re)indexed; its current rows replace any earlier ones.
+    Indexed,
+    /// The file was removed from the index.
+    Deleted,
+    /// The whole index was cleared; every earlier change is void. The
+    /// entry's `file` is empty.
+    Cleared,
+}
+
+impl ChangeOp {
+    /// String form stored in the `changes` table.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Indexed => "indexed",
+            Self::Deleted => "deleted",
+            Self::Cleared => "cleared",
+        }
+    }
+
+    /// Parse the stored string form.
+    pub fn from_str_lossy(s: &str) -> Self {
+        match s {
+            "deleted" => Self::Deleted,
+            "cleared" => Self::Cleared,
+            _ => Self::Indexed,
+        }
+    }
+}
+
+/// One entry of the append-only change journal.
+///
+/// Consumers remember the highest `seq` they have applied and ask for
+/// everything after it with [`crate::index::MetadataIndex::changes_since`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileChange {
+    /// Monotonic sequence number; never reused.
+    pub seq: i64,
+    /// File path relative to the repository root.
+    pub file: String,
+    /// What happened.
+    pub op: ChangeOp,
+    /// When it happened (`YYYY-MM-DD HH:MM:SS`, UTC).
+    pub timestamp: String,
+}
+
+// ---------------------------------------------------------------------------
+// Background jobs
+// ---------------------------------------------------------------------------
+
+/// Lifecycle of a [`BackgroundJob`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    /// Work remains; the job is picked up again after a restart.
+    Running,
+    /// Every unit of work completed.
+    Done,
+    /// The job stopped; `error` says why.
+    Failed,
+}
+
+impl JobState {
+    /// String form stored in the `jobs` table.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Running => "running",
+            Self::Done => "done",
+            Self::Failed => "failed",
+        }
+    }
+
+    /// Parse the stored string form.
+    pub fn from_str_lossy(s: &str) -> Self {
+        match s {
+            "done" => Self::Done,
+            "failed" => Self::Failed,
+            _ => Self::Running,
+        }
+    }
+}
+
+/// A resumable unit of low-priority work recorded in the index, such as
+/// re-embedding every chunk after an embedding model change.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackgroundJob {
+    /// Row id in the `jobs` table.
+    pub id: i64,
+    /// What the job does (e.g. `"reembed"`).
+    pub kind: String,
+    /// Current state.
+    pub state: JobState,
+    /// Units of work when the job started.
+    pub total: usize,
+    /// Units of work completed so far.
+    pub done: usize,
+    /// Why the job failed, if it did.
+    pub error: Option<String>,
+    /// When the job was created (`YYYY-MM-DD HH:MM:SS`, UTC).
+    pub created_at: String,
+    /// When progress was last recorded.
+    pub updated_at: String,
+}
+
+impl BackgroundJob {
+    /// Completed share of the work, 0-100.
+    pub fn percent(&self) -> f64 {
+        if self.total == 0 {
+            return 100.0;
+        }
+        (self.done.min(self.total) as f64 / self.total as f64) * 100.0
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -641,6 +1423,10 @@ pub struct ContextEntry {
     pub chunk: Chunk,
     /// Relevance score.
     pub score: f64,
+    /// Calibrated relevance in `[0, 1]` (see [`SearchResult::relevance`]).
+    /// Graph neighbours inherit a discounted share of their anchor's.
+    #[serde(default)]
+    pub relevance: f64,
     /// Whether this chunk was included via graph traversal (not direct search match).
     pub is_graph_neighbor: bool,
     /// Priority level for this chunk.
@@ -687,3 +1473,29 @@ impl ContextWindow {
         self.entries.is_empty()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_provenance_lists_contributing_signals() {
+        let breakdown = ScoreBreakdown {
+            keyword_rank: Some(3),
+            semantic_rank: Some(7),
+            symbol_rank: Some(1),
+            graph_boost: 0.3,
+            reranker_probability: Some(0.82),
+            skipped_signals: vec!["semantic".to_string()],
+            ..ScoreBreakdown::default()
+        };
+        assert_eq!(
+            breakdown.provenance(),
+            [
+                "keyword#3", "semantic#7", "symbol#1", "graph+0.30", "rerank=0.82",
+                "skipped:semantic"
+            ]
+        );
+        assert!(ScoreBreakdown::default().provenance().is_empty());
+    }
+}
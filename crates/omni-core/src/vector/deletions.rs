@@ -0,0 +1,119 @@
+//! Append-only log of vector removals next to `vectors.bin`.
+//!
+//! [`super::VectorIndex::remove`] appends the removed ids here instead of
+//! rewriting `vectors.bin`, so a delete costs a few bytes of I/O. On open the
+//! logged ids are applied as tombstones on top of the file; the next full
+//! save writes the tombstones into `vectors.bin` itself and deletes the log.
+//!
+//! The log starts with the save generation of the `vectors.bin` it applies
+//! to, followed by one id per record, all little-endian `u64`. A log whose
+//! generation does not match (the process died between a save and deleting
+//! the log, or `vectors.bin` was replaced) is stale and ignored: its ids may
+//! have been reused by vectors added since. A record torn by a crash
+//! mid-append is ignored too.
+
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use crate::error::OmniResult;
+
+const RECORD_BYTES: usize = 8;
+
+/// Removals logged against one generation of `vectors.bin`.
+pub(super) struct DeletionLog {
+    path: PathBuf,
+}
+
+impl DeletionLog {
+    pub(super) fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+        }
+    }
+
+    /// Append `ids`, starting a log for `generation` if there is none.
+    pub(super) fn append(&self, generation: u64, ids: &[u64]) -> OmniResult<()> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        // Drop a torn record, so new ones stay aligned.
+        let len = file.metadata()?.len();
+        let aligned = len - len % RECORD_BYTES as u64;
+        if aligned != len {
+            file.set_len(aligned)?;
+        }
+        let mut buf = Vec::with_capacity((ids.len() + 1) * RECORD_BYTES);
+        if aligned == 0 {
+            buf.extend_from_slice(&generation.to_le_bytes());
+        }
+        for id in ids {
+            buf.extend_from_slice(&id.to_le_bytes());
+        }
+        file.write_all(&buf)?;
+        file.sync_data()?;
+        Ok(())
+    }
+
+    /// Ids logged against `generation`. A stale log is deleted.
+    pub(super) fn read(&self, generation: u64) -> OmniResult<Vec<u64>> {
+        let mut data = Vec::new();
+        match File::open(&self.path) {
+            Ok(mut file) => file.read_to_end(&mut data)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut records = data
+            .chunks_exact(RECORD_BYTES)
+            .map(|record| u64::from_le_bytes(record.try_into().unwrap_or_default()));
+        match records.next() {
+            Some(logged) if logged == generation => Ok(records.collect()),
+            _ => {
+                tracing::debug!(path = %self.path.display(), "discarding stale vector deletion log");
+                self.remove()?;
+                Ok(Vec::new())
+            }
+        }
+    }
+
+    /// Delete the log, once a save has made it redundant.
+    pub(super) fn remove(&self) -> OmniResult<()> {
+        match std::fs::remove_file(&self.path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_round_trip_and_staleness() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let log = DeletionLog::new(&dir.path().join("vectors.del"));
+        assert!(log.read(7).expect("read").is_empty());
+
+        log.append(7, &[1, 2]).expect("append");
+        log.append(7, &[3]).expect("append");
+        assert_eq!(log.read(7).expect("read"), vec![1, 2, 3]);
+
+        // A torn trailing record is ignored.
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(dir.path().join("vectors.del"))
+            .expect("open");
+        file.write_all(&[9, 9, 9]).expect("write");
+        assert_eq!(log.read(7).expect("read"), vec![1, 2, 3]);
+        log.append(7, &[4]).expect("append");
+        assert_eq!(log.read(7).expect("read"), vec![1, 2, 3, 4]);
+
+        assert!(log.read(8).expect("read").is_empty());
+        assert!(!dir.path().join("vectors.del").exists());
+    }
+}
@@ -216,6 +216,25 @@ impl HnswIndex {
         self.nodes.is_empty()
     }
 
+    /// Approximate heap bytes held by node vectors and neighbor lists.
+    pub fn memory_usage_bytes(&self) -> usize {
+        self.nodes
+            .iter()
+            .map(|node| {
+                std::mem::size_of::<HnswNode>()
+                    + node.vector.capacity() * std::mem::size_of::<f32>()
+                    + node
+                        .neighbors
+                        .iter()
+                        .map(|layer| {
+                            std::mem::size_of::<Vec<usize>>()
+                                + layer.capacity() * std::mem::size_of::<usize>()
+                        })
+                        .sum::<usize>()
+            })
+            .sum()
+    }
+
     /// Insert a vector into the index.
     pub fn insert(&mut self, id: u64, vector: &[f32]) {
         assert_eq!(vector.len(), self.dimensions, "vector dimension mismatch");
@@ -19,25 +19,53 @@
 //! | Flat     | ~0.5ms      | ~5ms         | ~50ms      |
 //! | IVF      | ~0.3ms      | ~1ms         | ~5ms       |
 //! | HNSW     | ~0.1ms      | ~0.5ms       | ~1ms       |
+//!
+//! ## Memory limit
+//!
+//! With [`VectorIndex::set_memory_limit`], an index over its limit first
+//! drops its ANN structure (search falls back to flat), then moves vectors
+//! to an on-disk spill file (see [`spill`]) until it is back under 3/4 of
+//! the limit. Spilled vectors are still searched, from disk. An index
+//! opened with [`VectorIndex::open_with_memory_limit`] spills while it
+//! loads instead of reading the whole file into memory first.
+//!
+//! ## Deletions
+//!
+//! Removed ids are tombstoned in memory and appended to a deletion log next
+//! to `vectors.bin` (see [`deletions`]), so persisting a delete does not
+//! rewrite the file. Once more than `TOMBSTONE_GC_THRESHOLD` tombstones
+//! accumulate, the index compacts them away and saves.
 #![allow(
     clippy::manual_let_else,
     clippy::missing_errors_doc,
     clippy::must_use_candidate
 )]
 
+mod deletions;
 pub mod hnsw;
+mod spill;
 
 use std::collections::{HashMap, HashSet};
 use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
 
 use crate::error::{OmniError, OmniResult};
+use crate::index::MetadataIndex;
+use crate::types::EmbeddingModelInfo;
 
-/// Number of pending tombstones that triggers a compaction pass inside
-/// `build_optimal_index()`.  Chosen so that at typical embedding throughput
-/// (~800 chunks/s) GC runs at most once per ~37 minutes under heavy churn.
-#[allow(dead_code)]
+/// Number of pending tombstones past which a remove compacts the index and
+/// saves it (and `build_optimal_index()` compacts tombstones loaded from
+/// disk). Bounds both the dead vectors kept in memory and the deletion log.
 const TOMBSTONE_GC_THRESHOLD: usize = 500;
 
+/// Entries decoded between memory limit checks while loading `vectors.bin`.
+const LOAD_SPILL_INTERVAL: u64 = 1024;
+
+/// Fraction of the memory limit an over-limit index spills down to, so the
+/// next few additions do not immediately spill again.
+const SPILL_TARGET_NUMERATOR: usize = 3;
+const SPILL_TARGET_DENOMINATOR: usize = 4;
+
 /// Distance metric used for nearest neighbor search.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
 pub enum DistanceMetric {
@@ -64,11 +92,12 @@ pub enum DistanceMetric {
 /// ## Tombstone semantics
 ///
 /// `remove()` / `remove_batch()` add IDs to `tombstones` instead of
-/// immediately deleting from `vectors`.  This makes removal O(1) and
-/// prevents stale HNSW nodes from returning in search results — those IDs
-/// are filtered post-hoc in `search()` / `search_ivf()` / `search_hnsw()`.
-/// When `tombstone_count` reaches `TOMBSTONE_GC_THRESHOLD`, the next
-/// `build_optimal_index()` call drains tombstones and rebuilds the ANN index.
+/// immediately deleting from `vectors`, and append them to the deletion
+/// log.  This makes removal O(1) and prevents stale HNSW nodes from
+/// returning in search results — those IDs are filtered post-hoc in
+/// `search()` / `search_ivf()` / `search_hnsw()`.  When `tombstone_count`
+/// exceeds `TOMBSTONE_GC_THRESHOLD`, the remove drains tombstones, saves,
+/// and rebuilds the ANN index.
 pub struct VectorIndex {
     dimensions: usize,
     metric: DistanceMetric,
@@ -82,6 +111,18 @@ pub struct VectorIndex {
     tombstones: HashSet<u64>,
     /// Count of tombstoned IDs accumulated since the last GC.
     tombstone_count: usize,
+    /// Embedding model that produced the stored vectors (file header).
+    model: Option<EmbeddingModelInfo>,
+    /// Dimensions of an on-disk file that was not loaded because they differ
+    /// from the configured ones.
+    discarded_dimensions: Option<usize>,
+    /// Approximate heap budget; see [`Self::set_memory_limit`].
+    memory_limit: Option<usize>,
+    /// Vectors moved out of `vectors` to stay under `memory_limit`.
+    spill: Option<spill::SpillStore>,
+    /// Save generation of the on-disk file, which the deletion log is
+    /// written against.
+    generation: AtomicU64,
 }
 
 impl VectorIndex {
@@ -95,6 +136,33 @@ impl VectorIndex {
         index_path: &Path,
         dimensions: usize,
         metric: DistanceMetric,
+    ) -> OmniResult<Self> {
+        Self::open_limited(index_path, dimensions, metric, None)
+    }
+
+    /// Create or open a vector index capped at `memory_limit` bytes (see
+    /// [`Self::set_memory_limit`]).
+    ///
+    /// The limit already applies while `vectors.bin` loads, so vectors past
+    /// it go straight to the spill file.
+    pub fn open_with_memory_limit(
+        index_path: &Path,
+        dimensions: usize,
+        memory_limit: Option<usize>,
+    ) -> OmniResult<Self> {
+        Self::open_limited(
+            index_path,
+            dimensions,
+            DistanceMetric::default(),
+            memory_limit,
+        )
+    }
+
+    fn open_limited(
+        index_path: &Path,
+        dimensions: usize,
+        metric: DistanceMetric,
+        memory_limit: Option<usize>,
     ) -> OmniResult<Self> {
         let mut index = Self {
             dimensions,
@@ -105,6 +173,11 @@ impl VectorIndex {
             hnsw_index: None,
             tombstones: HashSet::new(),
             tombstone_count: 0,
+            model: None,
+            discarded_dimensions: None,
+            memory_limit,
+            spill: None,
+            generation: AtomicU64::new(0),
         };
 
         // Try loading existing index from disk
@@ -121,9 +194,14 @@ impl VectorIndex {
                 Err(e) => {
                     tracing::warn!(error = %e, "failed to load vector index, starting fresh");
                     index.vectors.clear();
+                    index.spill = None;
                 }
             }
         }
+        index.enforce_memory_limit()?;
+        if let Err(e) = index.replay_deletions() {
+            tracing::warn!(error = %e, "failed to read vector deletion log");
+        }
 
         Ok(index)
     }
@@ -139,6 +217,11 @@ impl VectorIndex {
             hnsw_index: None,
             tombstones: HashSet::new(),
             tombstone_count: 0,
+            model: None,
+            discarded_dimensions: None,
+            memory_limit: None,
+            spill: None,
+            generation: AtomicU64::new(0),
         }
     }
 
@@ -153,6 +236,11 @@ impl VectorIndex {
             hnsw_index: None,
             tombstones: HashSet::new(),
             tombstone_count: 0,
+            model: None,
+            discarded_dimensions: None,
+            memory_limit: None,
+            spill: None,
+            generation: AtomicU64::new(0),
         }
     }
 
@@ -176,9 +264,12 @@ impl VectorIndex {
         if self.tombstones.remove(&id) {
             self.tombstone_count = self.tombstone_count.saturating_sub(1);
         }
+        if let Some(spill) = &mut self.spill {
+            spill.remove(id);
+        }
 
         self.vectors.insert(id, vector.to_vec());
-        Ok(())
+        self.enforce_memory_limit()
     }
 
     /// Add multiple vectors in a batch.
@@ -206,7 +297,7 @@ impl VectorIndex {
             )));
         }
 
-        if self.vectors.is_empty() {
+        if self.is_empty() {
             return Ok(Vec::new());
         }
 
@@ -215,17 +306,15 @@ impl VectorIndex {
             .vectors
             .iter()
             .filter(|(&id, _)| !self.tombstones.contains(&id))
-            .map(|(&id, vec)| {
-                let score = match self.metric {
-                    DistanceMetric::Cosine | DistanceMetric::DotProduct => dot_product(query, vec),
-                    DistanceMetric::Euclidean => {
-                        // Negate so that smaller distance = higher score
-                        -euclidean_distance_sq(query, vec)
-                    }
-                };
-                (id, score)
-            })
+            .map(|(&id, vec)| (id, self.score(query, vec)))
             .collect();
+        if let Some(spill) = &self.spill {
+            spill.for_each(|id, vec| {
+                if !self.tombstones.contains(&id) {
+                    scores.push((id, self.score(query, vec)));
+                }
+            })?;
+        }
 
         // Sort by score descending (highest = most similar/closest)
         scores.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
@@ -235,6 +324,83 @@ impl VectorIndex {
         Ok(scores)
     }
 
+    /// Search for the K nearest neighbors of several query vectors at once.
+    ///
+    /// Equivalent to calling [`Self::search`] per query, but makes a single
+    /// pass over the stored vectors, scoring every query against each vector
+    /// while it is in cache. Returns one result list per query, in order.
+    pub fn search_batch(&self, queries: &[Vec<f32>], k: usize) -> OmniResult<Vec<Vec<(u64, f32)>>> {
+        if let Some(query) = queries.iter().find(|q| q.len() != self.dimensions) {
+            return Err(OmniError::Internal(format!(
+                "query dimension mismatch: expected {}, got {}",
+                self.dimensions,
+                query.len()
+            )));
+        }
+
+        let mut scores: Vec<Vec<(u64, f32)>> = queries.iter().map(|_| Vec::new()).collect();
+        let mut score_all = |id: u64, vec: &[f32]| {
+            if self.tombstones.contains(&id) {
+                return;
+            }
+            for (query, query_scores) in queries.iter().zip(scores.iter_mut()) {
+                query_scores.push((id, self.score(query, vec)));
+            }
+        };
+        for (&id, vec) in &self.vectors {
+            score_all(id, vec);
+        }
+        if let Some(spill) = &self.spill {
+            spill.for_each(score_all)?;
+        }
+
+        for query_scores in &mut scores {
+            query_scores.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+            query_scores.truncate(k);
+        }
+        Ok(scores)
+    }
+
+    /// [`Self::search`] restricted to the vectors with the given ids.
+    ///
+    /// Only those vectors are scored, so a search scoped to a few files stays
+    /// exact and cheap. Unknown and tombstoned ids are skipped.
+    pub fn search_among(
+        &self,
+        query: &[f32],
+        k: usize,
+        ids: impl IntoIterator<Item = u64>,
+    ) -> OmniResult<Vec<(u64, f32)>> {
+        if query.len() != self.dimensions {
+            return Err(OmniError::Internal(format!(
+                "query dimension mismatch: expected {}, got {}",
+                self.dimensions,
+                query.len()
+            )));
+        }
+
+        let mut scores: Vec<(u64, f32)> = Vec::new();
+        for id in ids {
+            if self.tombstones.contains(&id) {
+                continue;
+            }
+            if let Some(vec) = self.vectors.get(&id) {
+                scores.push((id, self.score(query, vec)));
+            } else if let Some(vec) = self
+                .spill
+                .as_ref()
+                .map(|s| s.get(id))
+                .transpose()?
+                .flatten()
+            {
+                scores.push((id, self.score(query, &vec)));
+            }
+        }
+        scores.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        scores.truncate(k);
+        Ok(scores)
+    }
+
     /// Remove a vector by ID.
     ///
     /// Uses soft-delete: the ID is added to the tombstone set and filtered from
@@ -244,11 +410,8 @@ impl VectorIndex {
     /// Returns `true` if the vector existed (or was already tombstoned),
     /// `false` if the ID is entirely unknown.
     pub fn remove(&mut self, id: u64) -> OmniResult<bool> {
-        let known = self.vectors.contains_key(&id) || self.tombstones.contains(&id);
-        if self.vectors.contains_key(&id) && !self.tombstones.contains(&id) {
-            self.tombstones.insert(id);
-            self.tombstone_count += 1;
-        }
+        let known = self.is_stored(id) || self.tombstones.contains(&id);
+        self.remove_batch(&[id])?;
         Ok(known)
     }
 
@@ -256,20 +419,76 @@ impl VectorIndex {
     ///
     /// Returns the number of IDs that were newly tombstoned.
     pub fn remove_batch(&mut self, ids: &[u64]) -> OmniResult<usize> {
-        let mut newly_tombstoned = 0;
+        let mut newly_tombstoned = Vec::new();
         for &id in ids {
-            if self.vectors.contains_key(&id) && !self.tombstones.contains(&id) {
+            if self.is_stored(id) && !self.tombstones.contains(&id) {
                 self.tombstones.insert(id);
                 self.tombstone_count += 1;
-                newly_tombstoned += 1;
+                newly_tombstoned.push(id);
+            }
+        }
+        self.record_removals(&newly_tombstoned)?;
+        Ok(newly_tombstoned.len())
+    }
+
+    /// Persist newly tombstoned `ids` to the deletion log, then compact and
+    /// save once tombstones exceed [`TOMBSTONE_GC_THRESHOLD`].
+    fn record_removals(&mut self, ids: &[u64]) -> OmniResult<()> {
+        if let Some(log) = self.deletion_log() {
+            log.append(self.generation.load(Ordering::Relaxed), ids)?;
+        }
+        if self.tombstone_count > TOMBSTONE_GC_THRESHOLD {
+            let had_ann = self.ivf.is_some() || self.hnsw_index.is_some();
+            self.compact();
+            self.save()?;
+            if had_ann {
+                self.build_optimal_index()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Tombstone the ids logged since the file was last saved.
+    fn replay_deletions(&mut self) -> OmniResult<()> {
+        let Some(log) = self.deletion_log() else {
+            return Ok(());
+        };
+        let logged = log.read(self.generation.load(Ordering::Relaxed))?;
+        for id in logged {
+            if self.is_stored(id) && self.tombstones.insert(id) {
+                self.tombstone_count += 1;
             }
         }
-        Ok(newly_tombstoned)
+        Ok(())
+    }
+
+    fn deletion_log(&self) -> Option<deletions::DeletionLog> {
+        self.index_path
+            .as_ref()
+            .map(|path| deletions::DeletionLog::new(&path.with_extension("del")))
     }
 
     /// Returns the number of live (non-tombstoned) vectors in the index.
     pub fn len(&self) -> usize {
-        self.vectors.len() - self.tombstones.len()
+        self.vectors.len() + self.spilled_len() - self.tombstones.len()
+    }
+
+    /// Number of vectors held in the spill file rather than in memory.
+    pub fn spilled_len(&self) -> usize {
+        self.spill.as_ref().map_or(0, spill::SpillStore::len)
+    }
+
+    /// Whether `id` has a stored vector, in memory or spilled (tombstoned or not).
+    fn is_stored(&self, id: u64) -> bool {
+        self.vectors.contains_key(&id) || self.spill.as_ref().is_some_and(|s| s.contains(id))
+    }
+
+    fn score(&self, query: &[f32], vec: &[f32]) -> f32 {
+        match self.metric {
+            DistanceMetric::Cosine | DistanceMetric::DotProduct => dot_product(query, vec),
+            // Negate so that smaller distance = higher score
+            DistanceMetric::Euclidean => -euclidean_distance_sq(query, vec),
+        }
     }
 
     /// Returns true if there are no live vectors.
@@ -277,6 +496,16 @@ impl VectorIndex {
         self.len() == 0
     }
 
+    /// Iterate over live (non-tombstoned) vectors in arbitrary order.
+    ///
+    /// Only vectors resident in memory are visited; see [`Self::spilled_len`].
+    pub fn iter(&self) -> impl Iterator<Item = (u64, &[f32])> {
+        self.vectors
+            .iter()
+            .filter(|(id, _)| !self.tombstones.contains(id))
+            .map(|(&id, vec)| (id, vec.as_slice()))
+    }
+
     /// Returns the number of pending tombstones awaiting compaction.
     pub fn tombstone_count(&self) -> usize {
         self.tombstone_count
@@ -287,17 +516,104 @@ impl VectorIndex {
         self.dimensions
     }
 
-    /// Estimate heap memory usage in bytes for stored vectors.
+    /// Estimate heap memory usage in bytes.
     ///
-    /// Does not include HashMap overhead or index structures (IVF/HNSW),
-    /// only the raw vector data.  Includes memory held by tombstoned vectors
-    /// that have not yet been compacted.
+    /// Covers resident vectors (including tombstoned ones not yet
+    /// compacted), the IVF or HNSW structure, and the bookkeeping for
+    /// spilled vectors.
     pub fn memory_usage_bytes(&self) -> usize {
+        self.vectors.len() * self.resident_entry_bytes()
+            + self.ann_memory_bytes()
+            + self
+                .spill
+                .as_ref()
+                .map_or(0, spill::SpillStore::memory_usage_bytes)
+    }
+
+    fn resident_entry_bytes(&self) -> usize {
         // Each vector: dimensions * sizeof(f32) + Vec overhead (~24 bytes on 64-bit)
         // HashMap entry overhead: ~64 bytes per entry (key + hash + pointers)
-        let per_vector = self.dimensions * std::mem::size_of::<f32>() + 24;
-        let per_entry = per_vector + 64;
-        self.vectors.len() * per_entry
+        self.dimensions * std::mem::size_of::<f32>() + 24 + 64
+    }
+
+    fn ann_memory_bytes(&self) -> usize {
+        let ivf = self.ivf.as_ref().map_or(0, |ivf| {
+            let vector = self.dimensions * std::mem::size_of::<f32>() + 24;
+            ivf.centroids.len() * vector
+                + ivf
+                    .buckets
+                    .iter()
+                    .map(|bucket| 24 + bucket.len() * (vector + 8))
+                    .sum::<usize>()
+        });
+        let hnsw = self
+            .hnsw_index
+            .as_ref()
+            .map_or(0, hnsw::HnswIndex::memory_usage_bytes);
+        ivf + hnsw
+    }
+
+    /// Cap [`Self::memory_usage_bytes`] at `limit_bytes` (`None` removes
+    /// the cap), enforcing it now and after every addition.
+    ///
+    /// An index without a backing file cannot spill; it only drops its ANN
+    /// structure.
+    pub fn set_memory_limit(&mut self, limit_bytes: Option<usize>) -> OmniResult<()> {
+        self.memory_limit = limit_bytes;
+        self.enforce_memory_limit()
+    }
+
+    /// The limit set with [`Self::set_memory_limit`].
+    pub fn memory_limit(&self) -> Option<usize> {
+        self.memory_limit
+    }
+
+    fn enforce_memory_limit(&mut self) -> OmniResult<()> {
+        let Some(limit) = self.memory_limit else {
+            return Ok(());
+        };
+        let usage = self.memory_usage_bytes();
+        if usage <= limit {
+            return Ok(());
+        }
+        if self.ivf.is_some() || self.hnsw_index.is_some() {
+            tracing::info!(
+                strategy = self.active_strategy(),
+                "dropping ANN index to stay within storage.memory_limit_mb"
+            );
+            self.ivf = None;
+            self.hnsw_index = None;
+            if self.memory_usage_bytes() <= limit {
+                return Ok(());
+            }
+        }
+        let Some(index_path) = &self.index_path else {
+            return Ok(());
+        };
+        if self.spill.is_none() {
+            self.spill = Some(spill::SpillStore::create_for(index_path, self.dimensions)?);
+        }
+
+        let target = limit / SPILL_TARGET_DENOMINATOR * SPILL_TARGET_NUMERATOR;
+        let excess = self.memory_usage_bytes().saturating_sub(target);
+        // Spilling frees the entry but adds spill bookkeeping for it.
+        let saved_per_entry = self.resident_entry_bytes() - spill::ENTRY_OVERHEAD_BYTES;
+        let count = excess.div_ceil(saved_per_entry).min(self.vectors.len());
+        let ids: Vec<u64> = self.vectors.keys().take(count).copied().collect();
+        let entries: Vec<(u64, Vec<f32>)> = ids
+            .into_iter()
+            .filter_map(|id| self.vectors.remove(&id).map(|vec| (id, vec)))
+            .collect();
+        if let Some(spill) = &mut self.spill {
+            spill.write(&entries)?;
+        }
+        self.vectors.shrink_to_fit();
+        tracing::debug!(
+            spilled = entries.len(),
+            total_spilled = self.spilled_len(),
+            "spilled vectors to disk to stay within storage.memory_limit_mb"
+        );
+        Ok(())
     }
 
     /// Compact the index by draining all tombstones.
@@ -306,12 +622,17 @@ impl VectorIndex {
     /// the ANN index pointers (`ivf`, `hnsw_index`) so they are rebuilt on
     /// the next `build_optimal_index()` call with the updated live set.
     ///
-    /// Called automatically by `build_optimal_index()` when `tombstone_count`
-    /// reaches `TOMBSTONE_GC_THRESHOLD`.
+    /// Called automatically when `tombstone_count` exceeds
+    /// `TOMBSTONE_GC_THRESHOLD`.
     fn compact(&mut self) {
         let drained: HashSet<u64> = std::mem::take(&mut self.tombstones);
         let removed = drained.len();
         self.vectors.retain(|id, _| !drained.contains(id));
+        if let Some(spill) = &mut self.spill {
+            for &id in &drained {
+                spill.remove(id);
+            }
+        }
         // Invalidate stale ANN indexes — they will be rebuilt by the caller.
         self.ivf = None;
         self.hnsw_index = None;
@@ -319,10 +640,76 @@ impl VectorIndex {
         tracing::info!(removed, "vector index GC compaction complete");
     }
 
+    /// Embedding model recorded in the file header, if any.
+    pub fn model_info(&self) -> Option<&EmbeddingModelInfo> {
+        self.model.as_ref()
+    }
+
+    /// Record the embedding model that produced the stored vectors.
+    /// Persisted on the next [`Self::save`].
+    pub fn set_model_info(&mut self, model: EmbeddingModelInfo) {
+        self.model = Some(model);
+    }
+
+    /// Cross-check the stored vectors against the chunk table.
+    ///
+    /// Reports vector ids that chunks reference but the index lacks
+    /// (`missing`), live vectors no chunk references (`orphaned`), and ids
+    /// shared by more than one chunk (`duplicated`). All lists are sorted.
+    pub fn verify(&self, index: &MetadataIndex) -> OmniResult<VectorIntegrityReport> {
+        let mut refs: HashMap<u64, usize> = HashMap::new();
+        for (_, vector_id) in index.chunk_vector_ids()? {
+            *refs.entry(vector_id).or_default() += 1;
+        }
+
+        let is_live = |id: &u64| self.is_stored(*id) && !self.tombstones.contains(id);
+        let spilled = self.spill.iter().flat_map(spill::SpillStore::ids);
+        let mut report = VectorIntegrityReport {
+            missing: refs.keys().copied().filter(|id| !is_live(id)).collect(),
+            orphaned: self
+                .vectors
+                .keys()
+                .copied()
+                .chain(spilled)
+                .filter(|id| is_live(id) && !refs.contains_key(id))
+                .collect(),
+            duplicated: refs
+                .iter()
+                .filter(|(_, &count)| count > 1)
+                .map(|(&id, _)| id)
+                .collect(),
+        };
+        report.missing.sort_unstable();
+        report.orphaned.sort_unstable();
+        report.duplicated.sort_unstable();
+        Ok(report)
+    }
+
+    /// Dimensions of the `vectors.bin` found at open, when it was ignored
+    /// because they differ from the configured dimensions. Cleared by
+    /// [`Self::clear`].
+    pub fn discarded_dimensions(&self) -> Option<usize> {
+        self.discarded_dimensions
+    }
+
+    /// Remove every vector, tombstone, and ANN structure.
+    pub fn clear(&mut self) {
+        self.discarded_dimensions = None;
+        // Dropping the store deletes its file.
+        self.spill = None;
+        self.vectors.clear();
+        self.tombstones.clear();
+        self.tombstone_count = 0;
+        self.ivf = None;
+        self.hnsw_index = None;
+    }
+
     /// Persist the index to disk atomically.
     ///
     /// Writes to a temporary file first, then renames to the target path.
     /// This prevents corruption if the process is interrupted mid-write.
+    /// The file includes the pending tombstones, so the deletion log is
+    /// deleted afterwards.
     pub fn save(&self) -> OmniResult<()> {
         let path = match &self.index_path {
             Some(p) => p,
@@ -334,71 +721,175 @@ impl VectorIndex {
             std::fs::create_dir_all(parent)?;
         }
 
-        let data = VectorData {
-            dimensions: self.dimensions,
-            entries: self
-                .vectors
-                .iter()
-                .map(|(&id, vec)| (id, vec.clone()))
-                .collect(),
-            tombstones: self.tombstones.iter().copied().collect(),
-        };
-
-        let encoded = bincode::serialize(&data)
-            .map_err(|e| OmniError::Internal(format!("failed to serialize vector index: {e}")))?;
-
         // Write to temp file alongside target, then atomic rename
         let tmp_path = path.with_extension("bin.tmp");
-        std::fs::write(&tmp_path, encoded)?;
+        let generation = next_generation(self.generation.load(Ordering::Relaxed));
+        self.write_vector_data(&tmp_path, generation)?;
         std::fs::rename(&tmp_path, path).map_err(|e| {
             // Clean up temp file on rename failure
             let _ = std::fs::remove_file(&tmp_path);
             OmniError::Io(e)
         })?;
+        self.generation.store(generation, Ordering::Relaxed);
+        if let Some(log) = self.deletion_log() {
+            log.remove()?;
+        }
 
         tracing::debug!(path = %path.display(), vectors = self.len(), "saved vector index (atomic)");
 
         Ok(())
     }
 
+    /// Write the `vectors.bin` encoding of this index to `path`: the
+    /// dimensions, the entry count, each `(id, vector)` entry, then a
+    /// [`VectorTail`].
+    ///
+    /// Entries are encoded one at a time, so spilled vectors are streamed
+    /// from disk instead of being loaded back into memory. bincode encodes a
+    /// sequence as a `u64` length followed by its items, so the bytes match
+    /// `bincode::serialize` of a struct with those fields in that order.
+    ///
+    /// Entries and tombstones are written in id order, so an unchanged index
+    /// saves to identical bytes and a remote push re-uploads only the blocks
+    /// that actually changed.
+    fn write_vector_data(&self, path: &Path, generation: u64) -> OmniResult<()> {
+        use std::io::Write;
+
+        let encode_err = |e: bincode::Error| {
+            OmniError::Internal(format!("failed to serialize vector index: {e}"))
+        };
+        let mut out = std::io::BufWriter::new(std::fs::File::create(path)?);
+        let entry_count = (self.vectors.len() + self.spilled_len()) as u64;
+        bincode::serialize_into(&mut out, &(self.dimensions, entry_count)).map_err(encode_err)?;
+        let mut ids: Vec<u64> = self.vectors.keys().copied().collect();
+        let mut spilled = None;
+        if let Some(spill) = &self.spill {
+            ids.extend(spill.ids());
+            spilled = Some((spill, spill.open_reader()?));
+        }
+        ids.sort_unstable();
+        for id in ids {
+            if let Some(vec) = self.vectors.get(&id) {
+                bincode::serialize_into(&mut out, &(id, vec)).map_err(encode_err)?;
+            } else if let Some((spill, reader)) = spilled.as_mut() {
+                if let Some(vec) = spill.read_with(reader, id)? {
+                    bincode::serialize_into(&mut out, &(id, vec)).map_err(encode_err)?;
+                }
+            }
+        }
+        let mut tombstones: Vec<u64> = self.tombstones.iter().copied().collect();
+        tombstones.sort_unstable();
+        bincode::serialize_into(&mut out, &(tombstones, &self.model, generation))
+            .map_err(encode_err)?;
+        out.flush()?;
+        Ok(())
+    }
+
     /// Load the index from disk.
+    ///
+    /// Entries are decoded one at a time and the memory limit is enforced
+    /// every [`LOAD_SPILL_INTERVAL`] entries, so an index larger than the
+    /// limit spills as it loads instead of being read into memory whole.
     fn load_from_disk(&mut self) -> OmniResult<()> {
+        use std::io::Read;
+
         let path = match &self.index_path {
             Some(p) => p.clone(),
             None => return Ok(()),
         };
 
-        let data = std::fs::read(&path)?;
-        let decoded: VectorData = bincode::deserialize(&data)
-            .map_err(|e| OmniError::Internal(format!("failed to deserialize vector index: {e}")))?;
-
-        if decoded.dimensions != self.dimensions {
+        let decode_err = |e: bincode::Error| {
+            OmniError::Internal(format!("failed to deserialize vector index: {e}"))
+        };
+        let mut reader = std::io::BufReader::new(std::fs::File::open(&path)?);
+        let (dimensions, count): (usize, u64) =
+            bincode::deserialize_from(&mut reader).map_err(decode_err)?;
+        if dimensions != self.dimensions {
+            self.discarded_dimensions = Some(dimensions);
             return Err(OmniError::Internal(format!(
                 "vector index dimension mismatch: file has {}, config expects {}",
-                decoded.dimensions, self.dimensions
+                dimensions, self.dimensions
             )));
         }
 
-        self.vectors = decoded.entries.into_iter().collect();
-        // Restore tombstones persisted in a previous run.  Uses serde default
-        // (empty vec) for binary files written before tombstone support was added.
-        self.tombstones = decoded.tombstones.into_iter().collect();
+        self.vectors = HashMap::new();
+        for loaded in 1..=count {
+            let (id, vector): (u64, Vec<f32>) =
+                bincode::deserialize_from(&mut reader).map_err(decode_err)?;
+            self.vectors.insert(id, vector);
+            if loaded % LOAD_SPILL_INTERVAL == 0 {
+                self.enforce_memory_limit()?;
+            }
+        }
+
+        let mut tail = Vec::new();
+        reader.read_to_end(&mut tail)?;
+        // bincode is not self-describing, so files written before the model
+        // header or the save generation existed need their own layouts.
+        let (tombstones, model, generation) = bincode::deserialize::<VectorTail>(&tail)
+            .or_else(|e| {
+                bincode::deserialize::<UnnumberedVectorTail>(&tail)
+                    .map(|(tombstones, model)| (tombstones, model, 0))
+                    .or_else(|_| {
+                        bincode::deserialize::<LegacyVectorTail>(&tail)
+                            .map(|tombstones| (tombstones, None, 0))
+                    })
+                    .map_err(|_| e)
+            })
+            .map_err(decode_err)?;
+
+        // Restore tombstones persisted in a previous run.
+        self.tombstones = tombstones.into_iter().collect();
         self.tombstone_count = self.tombstones.len();
+        self.model = model;
+        self.generation.store(generation, Ordering::Relaxed);
         Ok(())
     }
 }
 
-/// Serializable vector data for disk persistence.
-#[derive(serde::Serialize, serde::Deserialize)]
-struct VectorData {
-    dimensions: usize,
-    entries: Vec<(u64, Vec<f32>)>,
-    /// Pending soft-deleted IDs.  `#[serde(default)]` ensures older binary
-    /// files (without this field) deserialize cleanly as an empty vec.
-    #[serde(default)]
-    tombstones: Vec<u64>,
+/// A save generation different from `previous`. Clock-based rather than a
+/// counter, so a `vectors.bin` copied in from elsewhere is unlikely to share
+/// a generation with a local deletion log.
+fn next_generation(previous: u64) -> u64 {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos() as u64);
+    if now == previous {
+        now.wrapping_add(1)
+    } else {
+        now
+    }
+}
+
+/// Result of [`VectorIndex::verify`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize)]
+pub struct VectorIntegrityReport {
+    /// Vector ids referenced by chunks but absent from the vector index.
+    pub missing: Vec<u64>,
+    /// Live vectors that no chunk references.
+    pub orphaned: Vec<u64>,
+    /// Vector ids referenced by more than one chunk.
+    pub duplicated: Vec<u64>,
+}
+
+impl VectorIntegrityReport {
+    /// True when the vector index and the chunk table agree.
+    pub fn is_clean(&self) -> bool {
+        self.missing.is_empty() && self.orphaned.is_empty() && self.duplicated.is_empty()
+    }
 }
 
+/// Fields of `vectors.bin` after the entries: pending tombstones, the
+/// embedding model that produced the entries, and the save generation that
+/// the deletion log is matched against.
+type VectorTail = (Vec<u64>, Option<EmbeddingModelInfo>, u64);
+
+/// Tail written before the save generation was added.
+type UnnumberedVectorTail = (Vec<u64>, Option<EmbeddingModelInfo>);
+
+/// Tail written before the embedding model header was added.
+type LegacyVectorTail = Vec<u64>;
+
 // ---------------------------------------------------------------------------
 // Math
 // ---------------------------------------------------------------------------
@@ -498,6 +989,27 @@ mod tests {
         assert_eq!(results[1].0, 3);
     }
 
+    #[test]
+    fn test_search_among_scores_only_given_ids() {
+        let mut index = VectorIndex::in_memory(3);
+        let mut v1 = vec![1.0, 0.0, 0.0];
+        l2_normalize(&mut v1);
+        let mut v2 = vec![0.0, 1.0, 0.0];
+        l2_normalize(&mut v2);
+        let mut v3 = vec![0.9, 0.1, 0.0];
+        l2_normalize(&mut v3);
+        index.add(1, &v1).expect("add v1");
+        index.add(2, &v2).expect("add v2");
+        index.add(3, &v3).expect("add v3");
+        index.remove(3).expect("remove v3");
+
+        let results = index
+            .search_among(&v1, 5, [2, 3, 99])
+            .expect("search among");
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].0, 2);
+    }
+
     #[test]
     fn test_dimension_mismatch_rejected() {
         let mut index = VectorIndex::in_memory(3);
@@ -535,15 +1047,11 @@ mod tests {
     }
 
     #[test]
-    fn test_tombstone_gc_triggered_by_build_optimal() {
-        // Build an index large enough that build_optimal_index will be called.
-        // Then remove enough vectors to cross TOMBSTONE_GC_THRESHOLD (500),
-        // trigger build_optimal_index, and verify tombstone_count resets to 0.
+    fn test_tombstone_gc_triggered_past_threshold() {
+        // Removing past TOMBSTONE_GC_THRESHOLD (500) compacts right away.
         let dim = 4;
         let mut index = VectorIndex::in_memory(dim);
 
-        // We only need 501 removes to cross the GC threshold, so add 600 vectors
-        // using simple unit-like vectors.
         for i in 0u64..600 {
             let mut v = vec![0.0f32; dim];
             v[(i as usize) % dim] = 1.0;
@@ -551,17 +1059,53 @@ mod tests {
         }
         assert_eq!(index.len(), 600);
 
-        // Remove 501 to exceed TOMBSTONE_GC_THRESHOLD
-        for i in 0u64..501 {
+        for i in 0u64..500 {
             index.remove(i).expect("remove");
         }
-        assert_eq!(index.tombstone_count(), 501);
-        // build_optimal_index triggers compact() because tombstone_count >= 500
-        index.build_optimal_index().expect("build optimal");
+        assert_eq!(index.tombstone_count(), 500);
+        assert_eq!(index.vectors.len(), 600, "no GC at the threshold");
+
+        index.remove(500).expect("remove");
         assert_eq!(index.tombstone_count(), 0, "GC must reset tombstone_count");
+        assert_eq!(index.vectors.len(), 99, "dead vectors dropped by GC");
         assert_eq!(index.len(), 99, "only live vectors should remain after GC");
     }
 
+    #[test]
+    fn test_removals_persist_without_rewriting_file() {
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let path = dir.path().join("vectors.bin");
+        let log = dir.path().join("vectors.del");
+
+        let mut index = VectorIndex::open(&path, 3).expect("open");
+        index.add(1, &[1.0, 0.0, 0.0]).expect("add");
+        index.add(2, &[0.0, 1.0, 0.0]).expect("add");
+        index.add(3, &[0.0, 0.0, 1.0]).expect("add");
+        index.save().expect("save");
+        let saved = std::fs::read(&path).expect("read");
+
+        assert!(index.remove(2).expect("remove"));
+        assert_eq!(std::fs::read(&path).expect("read"), saved, "file rewritten");
+        assert!(log.exists());
+        drop(index);
+
+        let mut index = VectorIndex::open(&path, 3).expect("reopen");
+        assert_eq!(index.len(), 2);
+        let hits = index.search(&[0.0, 1.0, 0.0], 3).expect("search");
+        assert!(hits.iter().all(|(id, _)| *id != 2), "removed id returned");
+
+        // A log that outlived its file (crash between save and deleting
+        // the log) must not delete a vector re-added under the same id.
+        let stale = std::fs::read(&log).expect("read log");
+        index.add(2, &[0.0, 1.0, 0.0]).expect("re-add");
+        index.save().expect("save");
+        assert!(!log.exists());
+        std::fs::write(&log, stale).expect("restore stale log");
+        let index = VectorIndex::open(&path, 3).expect("reopen");
+        assert_eq!(index.len(), 3);
+        assert!(!log.exists());
+    }
+
     #[test]
     fn test_search_empty_index() {
         let index = VectorIndex::in_memory(3);
@@ -592,6 +1136,137 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_save_is_independent_of_insertion_order() {
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let mut saved = Vec::new();
+        for (name, ids) in [("a.bin", [1u64, 2, 3, 4]), ("b.bin", [4, 2, 3, 1])] {
+            let path = dir.path().join(name);
+            let mut index = VectorIndex::open(&path, 3).expect("open");
+            for id in ids {
+                #[allow(clippy::cast_precision_loss)]
+                index.add(id, &[id as f32, 1.0, 0.0]).expect("add");
+            }
+            index.remove(3).expect("remove");
+            index.save().expect("save");
+            let mut bytes = std::fs::read(&path).expect("read");
+            // The trailing save generation is a timestamp.
+            bytes.truncate(bytes.len() - 8);
+            saved.push(bytes);
+        }
+        assert_eq!(saved[0], saved[1]);
+    }
+
+    #[test]
+    fn test_model_header_round_trip_and_legacy_files() {
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let path = dir.path().join("vectors.bin");
+        let model = EmbeddingModelInfo {
+            name: "CodeRankEmbed".to_string(),
+            revision: "main".to_string(),
+            dimensions: 3,
+        };
+
+        {
+            let mut index = VectorIndex::open(&path, 3).expect("open");
+            index.add(1, &[1.0, 0.0, 0.0]).expect("add");
+            index.set_model_info(model.clone());
+            index.save().expect("save");
+        }
+        let index = VectorIndex::open(&path, 3).expect("open");
+        assert_eq!(index.model_info(), Some(&model));
+
+        // A file written before the header existed still loads.
+        let entries: Vec<(u64, Vec<f32>)> = vec![(7, vec![0.0, 1.0, 0.0])];
+        let legacy: (usize, _, LegacyVectorTail) = (3, entries, Vec::new());
+        std::fs::write(&path, bincode::serialize(&legacy).expect("encode")).expect("write");
+        let index = VectorIndex::open(&path, 3).expect("open");
+        assert_eq!(index.len(), 1);
+        assert_eq!(index.model_info(), None);
+    }
+
+    #[test]
+    fn test_search_batch_matches_single_search() {
+        let mut index = VectorIndex::in_memory(3);
+        index.add(1, &[1.0, 0.0, 0.0]).expect("add");
+        index.add(2, &[0.0, 1.0, 0.0]).expect("add");
+        index.add(3, &[0.6, 0.8, 0.0]).expect("add");
+        index.add(4, &[0.0, 0.0, 1.0]).expect("add");
+        index.remove(4).expect("remove");
+
+        let queries = vec![
+            vec![1.0, 0.0, 0.0],
+            vec![0.0, 1.0, 0.0],
+            vec![0.0, 0.0, 1.0],
+        ];
+        let batch = index.search_batch(&queries, 2).expect("batch");
+        assert_eq!(batch.len(), queries.len());
+        for (query, hits) in queries.iter().zip(&batch) {
+            assert_eq!(hits, &index.search(query, 2).expect("search"));
+        }
+        assert!(
+            batch[2].iter().all(|(id, _)| *id != 4),
+            "tombstoned id returned"
+        );
+
+        assert!(index.search_batch(&[vec![1.0, 0.0]], 2).is_err());
+    }
+
+    #[test]
+    fn test_verify_against_chunk_table() {
+        use crate::types::{Chunk, ChunkKind, ComplexityMetrics, FileInfo, Language, Visibility};
+
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let db = MetadataIndex::open(&dir.path().join("index.db")).expect("open db");
+        let file_id = db
+            .upsert_file(&FileInfo {
+                id: 0,
+                path: "src/lib.rs".into(),
+                language: Language::Rust,
+                content_hash: "h".to_string(),
+                size_bytes: 1,
+            })
+            .expect("upsert file");
+        for (i, vector_id) in [1_u64, 2, 2, 3].into_iter().enumerate() {
+            let chunk_id = db
+                .insert_chunk(&Chunk {
+                    id: 0,
+                    file_id,
+                    symbol_path: format!("lib::f{i}"),
+                    kind: ChunkKind::Function,
+                    visibility: Visibility::Public,
+                    line_start: i as u32 + 1,
+                    line_end: i as u32 + 1,
+                    content: format!("fn f{i}() {{}}"),
+                    doc_comment: None,
+                    token_count: 4,
+                    weight: 1.0,
+                    vector_id: None,
+                    is_summary: false,
+                    is_generated: false,
+                    language: None,
+                    content_hash: 0,
+                    complexity: ComplexityMetrics::default(),
+                })
+                .expect("insert chunk");
+            db.set_chunk_vector_id(chunk_id, vector_id)
+                .expect("set vector");
+        }
+
+        let mut index = VectorIndex::in_memory(3);
+        index.add(1, &[1.0, 0.0, 0.0]).expect("add");
+        index.add(2, &[0.0, 1.0, 0.0]).expect("add");
+        index.add(3, &[0.0, 0.0, 1.0]).expect("add");
+        index.add(9, &[1.0, 0.0, 0.0]).expect("add");
+        index.remove(3).expect("remove");
+
+        let report = index.verify(&db).expect("verify");
+        assert_eq!(report.missing, vec![3]);
+        assert_eq!(report.orphaned, vec![9]);
+        assert_eq!(report.duplicated, vec![2]);
+        assert!(!report.is_clean());
+    }
+
     #[test]
     fn test_l2_normalize() {
         let mut vec = vec![3.0, 4.0];
@@ -791,6 +1466,61 @@ mod tests {
         // Orthogonal should have score ~0.0
         assert!((results[1].1).abs() < 0.1, "orthogonal should be ~0.0");
     }
+
+    #[test]
+    fn test_kmeans_handles_empty_input_and_large_k() {
+        assert!(kmeans(&[], 4, 8).is_empty());
+        let a = make_random_vector(8, 1);
+        let b = make_random_vector(8, 2);
+        assert!(kmeans(&[a.as_slice(), b.as_slice()], 0, 8).is_empty());
+        let centroids = kmeans(&[a.as_slice(), b.as_slice()], 5, 8);
+        assert_eq!(centroids.len(), 2);
+        assert!(centroids.iter().all(|c| c.len() == 8));
+    }
+
+    #[test]
+    fn test_memory_limit_spills_to_disk() {
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let path = dir.path().join("vectors.bin");
+        let mut index = VectorIndex::open(&path, 8).expect("open");
+        for i in 0..200 {
+            index.add(i, &make_random_vector(8, i)).expect("add");
+        }
+        let unlimited = index.memory_usage_bytes();
+        index.set_memory_limit(Some(unlimited / 2)).expect("limit");
+
+        assert!(index.memory_usage_bytes() <= unlimited / 2);
+        assert!(index.spilled_len() > 0);
+        assert_eq!(index.len(), 200);
+
+        // Spilled vectors are still found, by exact and restricted search.
+        let spilled = index
+            .spill
+            .as_ref()
+            .and_then(|s| s.ids().next())
+            .expect("spilled id");
+        let query = make_random_vector(8, spilled);
+        assert_eq!(index.search(&query, 1).expect("search")[0].0, spilled);
+        let among = index
+            .search_among(&query, 1, [spilled])
+            .expect("search among");
+        assert_eq!(among[0].0, spilled);
+
+        index.remove(spilled).expect("remove");
+        assert_ne!(index.search(&query, 1).expect("search")[0].0, spilled);
+
+        index.save().expect("save");
+        let reopened = VectorIndex::open(&path, 8).expect("reopen");
+        assert_eq!(reopened.len(), 199);
+        assert_eq!(reopened.spilled_len(), 0);
+
+        // Opening with the limit spills during the load.
+        let limited =
+            VectorIndex::open_with_memory_limit(&path, 8, Some(unlimited / 2)).expect("reopen");
+        assert_eq!(limited.len(), 199);
+        assert!(limited.spilled_len() > 0);
+        assert!(limited.memory_usage_bytes() <= unlimited / 2);
+    }
 }
 
 /// Inverted File Index for sub-linear ANN search.
@@ -827,89 +1557,13 @@ impl VectorIndex {
             return Ok(());
         }
 
-        let dims = self.dimensions;
-        let all_vecs: Vec<(&u64, &Vec<f32>)> = self.vectors.iter().collect();
-
-        // Simple k-means++ initialization
-        let mut centroids = Vec::with_capacity(n_clusters);
-        // First centroid: pick the first vector
-        centroids.push(all_vecs[0].1.clone());
-
-        // Remaining centroids: pick vectors that are far from existing centroids
-        for _ in 1..n_clusters {
-            let mut best_dist = f32::NEG_INFINITY;
-            let mut best_idx = 0;
-            for (idx, (_, vec)) in all_vecs.iter().enumerate() {
-                let min_d = centroids
-                    .iter()
-                    .map(|c| cosine_sim(vec, c))
-                    .fold(f32::INFINITY, f32::min);
-                let neg_sim = -min_d; // want to maximize distance = minimize similarity
-                if neg_sim > best_dist {
-                    best_dist = neg_sim;
-                    best_idx = idx;
-                }
-            }
-            centroids.push(all_vecs[best_idx].1.clone());
-        }
-
-        // Run k-means iterations (10 iterations is sufficient for code embeddings)
-        for _ in 0..10 {
-            // Assign each vector to nearest centroid
-            let mut assignments: Vec<Vec<usize>> = vec![Vec::new(); n_clusters];
-            for (idx, (_, vec)) in all_vecs.iter().enumerate() {
-                let mut best_c = 0;
-                let mut best_sim = f32::NEG_INFINITY;
-                for (c, centroid) in centroids.iter().enumerate() {
-                    let sim = cosine_sim(vec, centroid);
-                    if sim > best_sim {
-                        best_sim = sim;
-                        best_c = c;
-                    }
-                }
-                assignments[best_c].push(idx);
-            }
-
-            // Recompute centroids as mean of assigned vectors
-            for (c, assigned) in assignments.iter().enumerate() {
-                if assigned.is_empty() {
-                    continue;
-                }
-                let mut new_centroid = vec![0.0f32; dims];
-                for &idx in assigned {
-                    for (d, val) in all_vecs[idx].1.iter().enumerate() {
-                        if d < dims {
-                            new_centroid[d] += val;
-                        }
-                    }
-                }
-                let n_assigned = assigned.len() as f32;
-                for val in &mut new_centroid {
-                    *val /= n_assigned;
-                }
-                // L2 normalize the centroid
-                let norm = new_centroid.iter().map(|x| x * x).sum::<f32>().sqrt();
-                if norm > 1e-10 {
-                    for val in &mut new_centroid {
-                        *val /= norm;
-                    }
-                }
-                centroids[c] = new_centroid;
-            }
-        }
+        let all_vecs: Vec<&[f32]> = self.vectors.values().map(Vec::as_slice).collect();
+        let centroids = kmeans(&all_vecs, n_clusters, self.dimensions);
 
         // Build final buckets
         let mut buckets: Vec<Vec<(u64, Vec<f32>)>> = vec![Vec::new(); n_clusters];
         for (&id, vec) in &self.vectors {
-            let mut best_c = 0;
-            let mut best_sim = f32::NEG_INFINITY;
-            for (c, centroid) in centroids.iter().enumerate() {
-                let sim = cosine_sim(vec, centroid);
-                if sim > best_sim {
-                    best_sim = sim;
-                    best_c = c;
-                }
-            }
+            let (best_c, _) = nearest_centroid(vec, &centroids);
             buckets[best_c].push((id, vec.clone()));
         }
 
@@ -1053,10 +1707,20 @@ impl VectorIndex {
         // Run GC compaction if tombstone accumulation has crossed the threshold.
         // This drains the tombstone set, removes dead entries from `vectors`, and
         // resets `ivf`/`hnsw_index` so they are rebuilt below with clean data.
-        if self.tombstone_count >= TOMBSTONE_GC_THRESHOLD {
+        if self.tombstone_count > TOMBSTONE_GC_THRESHOLD {
             self.compact();
         }
 
+        // An ANN structure over the resident vectors alone would miss the
+        // spilled ones; stay on flat search under memory pressure.
+        if self.spilled_len() > 0 {
+            tracing::debug!(
+                spilled = self.spilled_len(),
+                "vectors spilled to disk, skipping ANN index"
+            );
+            return Ok(());
+        }
+
         let n = self.vectors.len();
 
         if n < 5_000 {
@@ -1070,12 +1734,13 @@ impl VectorIndex {
             let n_clusters = (n as f64).sqrt() as usize;
             let n_probe = (n_clusters as f64).sqrt().max(3.0) as usize;
             tracing::info!(n, n_clusters, n_probe, "building IVF index");
-            return self.build_ivf(n_clusters, n_probe);
+            self.build_ivf(n_clusters, n_probe)?;
+        } else {
+            // Large index: HNSW
+            tracing::info!(n, "building HNSW index for large vector set");
+            self.build_hnsw(hnsw::HnswConfig::for_code_search())?;
         }
-
-        // Large index: HNSW
-        tracing::info!(n, "building HNSW index for large vector set");
-        self.build_hnsw(hnsw::HnswConfig::for_code_search())
+        self.enforce_memory_limit()
     }
 
     /// Returns the current search strategy as a string (for diagnostics).
@@ -1090,6 +1755,92 @@ impl VectorIndex {
     }
 }
 
+/// Cluster `vectors` into `k` groups with spherical k-means.
+///
+/// Farthest-point initialisation from the first vector, then 10 Lloyd
+/// iterations (sufficient for code embeddings). Returns L2-normalised
+/// centroids: at most `vectors.len()` of them, and none when `vectors` is
+/// empty or `k` is 0.
+pub fn kmeans(vectors: &[&[f32]], k: usize, dims: usize) -> Vec<Vec<f32>> {
+    let k = k.min(vectors.len());
+    if k == 0 {
+        return Vec::new();
+    }
+    // Simple k-means++ initialization
+    let mut centroids = Vec::with_capacity(k);
+    // First centroid: pick the first vector
+    centroids.push(vectors[0].to_vec());
+
+    // Remaining centroids: pick vectors that are far from existing centroids
+    for _ in 1..k {
+        let mut best_dist = f32::NEG_INFINITY;
+        let mut best_idx = 0;
+        for (idx, vec) in vectors.iter().enumerate() {
+            let min_d = centroids
+                .iter()
+                .map(|c| cosine_sim(vec, c))
+                .fold(f32::INFINITY, f32::min);
+            let neg_sim = -min_d; // want to maximize distance = minimize similarity
+            if neg_sim > best_dist {
+                best_dist = neg_sim;
+                best_idx = idx;
+            }
+        }
+        centroids.push(vectors[best_idx].to_vec());
+    }
+
+    for _ in 0..10 {
+        // Assign each vector to nearest centroid
+        let mut assignments: Vec<Vec<usize>> = vec![Vec::new(); k];
+        for (idx, vec) in vectors.iter().enumerate() {
+            assignments[nearest_centroid(vec, &centroids).0].push(idx);
+        }
+
+        // Recompute centroids as mean of assigned vectors
+        for (c, assigned) in assignments.iter().enumerate() {
+            if assigned.is_empty() {
+                continue;
+            }
+            let mut new_centroid = vec![0.0f32; dims];
+            for &idx in assigned {
+                for (d, val) in vectors[idx].iter().enumerate() {
+                    if d < dims {
+                        new_centroid[d] += val;
+                    }
+                }
+            }
+            let n_assigned = assigned.len() as f32;
+            for val in &mut new_centroid {
+                *val /= n_assigned;
+            }
+            // L2 normalize the centroid
+            let norm = new_centroid.iter().map(|x| x * x).sum::<f32>().sqrt();
+            if norm > 1e-10 {
+                for val in &mut new_centroid {
+                    *val /= norm;
+                }
+            }
+            centroids[c] = new_centroid;
+        }
+    }
+
+    centroids
+}
+
+/// Index of the centroid most similar to `vec`, and that cosine similarity.
+pub fn nearest_centroid(vec: &[f32], centroids: &[Vec<f32>]) -> (usize, f32) {
+    let mut best_c = 0;
+    let mut best_sim = f32::NEG_INFINITY;
+    for (c, centroid) in centroids.iter().enumerate() {
+        let sim = cosine_sim(vec, centroid);
+        if sim > best_sim {
+            best_sim = sim;
+            best_c = c;
+        }
+    }
+    (best_c, best_sim)
+}
+
 fn cosine_sim(a: &[f32], b: &[f32]) -> f32 {
     let mut na = a.to_vec();
     let mut nb = b.to_vec();
@@ -0,0 +1,211 @@
+//! On-disk overflow for vectors evicted from memory.
+//!
+//! With `storage.memory_limit_mb` set, [`super::VectorIndex`] moves vectors
+//! out of its in-memory map into a spill file next to `vectors.bin` once the
+//! limit is exceeded. Each record is the vector id followed by its
+//! components, all little-endian, so records have a fixed size and a vector
+//! is read back with one seek.
+//!
+//! The file is scratch space owned by one index instance: its name carries
+//! the process id and a per-process counter, so a CLI query and the daemon
+//! spilling for the same repository never share a file. It is created on
+//! first use and deleted when the index is dropped or cleared. `vectors.bin` stays
+//! the source of truth. Replaced and removed vectors leave dead records
+//! behind; those are skipped on reads and reclaimed with the file.
+
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::error::OmniResult;
+
+/// Bookkeeping per spilled vector: map key, offset, and hash table overhead.
+pub(super) const ENTRY_OVERHEAD_BYTES: usize = 32;
+
+/// Vectors spilled to disk, addressed by id.
+pub(super) struct SpillStore {
+    path: PathBuf,
+    dimensions: usize,
+    /// Vector id → byte offset of its live record.
+    offsets: HashMap<u64, u64>,
+    /// Size of the file, i.e. the offset of the next record.
+    end: u64,
+}
+
+/// Spill stores created by this process, for unique file names.
+static NEXT_SPILL_ID: AtomicU64 = AtomicU64::new(0);
+
+impl SpillStore {
+    /// A new spill store next to `index_path`, in a file no other index
+    /// instance uses.
+    pub(super) fn create_for(index_path: &Path, dimensions: usize) -> OmniResult<Self> {
+        let id = NEXT_SPILL_ID.fetch_add(1, Ordering::Relaxed);
+        let extension = format!("{}-{id}.spill", std::process::id());
+        Self::create(&index_path.with_extension(extension), dimensions)
+    }
+
+    /// A spill store at `path`, which must not exist yet.
+    fn create(path: &Path, dimensions: usize) -> OmniResult<Self> {
+        OpenOptions::new().write(true).create_new(true).open(path)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            dimensions,
+            offsets: HashMap::new(),
+            end: 0,
+        })
+    }
+
+    fn record_size(&self) -> usize {
+        8 + self.dimensions * 4
+    }
+
+    /// Number of live spilled vectors.
+    pub(super) fn len(&self) -> usize {
+        self.offsets.len()
+    }
+
+    pub(super) fn contains(&self, id: u64) -> bool {
+        self.offsets.contains_key(&id)
+    }
+
+    /// Spilled vector ids, in arbitrary order.
+    pub(super) fn ids(&self) -> impl Iterator<Item = u64> + '_ {
+        self.offsets.keys().copied()
+    }
+
+    /// Forget `id`; its record becomes dead space.
+    pub(super) fn remove(&mut self, id: u64) -> bool {
+        self.offsets.remove(&id).is_some()
+    }
+
+    /// Append `entries`, replacing earlier records for the same ids.
+    pub(super) fn write(&mut self, entries: &[(u64, Vec<f32>)]) -> OmniResult<()> {
+        let file = OpenOptions::new().append(true).open(&self.path)?;
+        let mut writer = BufWriter::new(file);
+        let mut record = Vec::with_capacity(self.record_size());
+        for (id, vector) in entries {
+            record.clear();
+            record.extend_from_slice(&id.to_le_bytes());
+            for x in vector {
+                record.extend_from_slice(&x.to_le_bytes());
+            }
+            writer.write_all(&record)?;
+            self.offsets.insert(*id, self.end);
+            self.end += record.len() as u64;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Read one spilled vector.
+    pub(super) fn get(&self, id: u64) -> OmniResult<Option<Vec<f32>>> {
+        if !self.offsets.contains_key(&id) {
+            return Ok(None);
+        }
+        self.read_with(&mut self.open_reader()?, id)
+    }
+
+    /// A handle for [`Self::read_with`], so a run of reads opens the file once.
+    pub(super) fn open_reader(&self) -> OmniResult<File> {
+        Ok(File::open(&self.path)?)
+    }
+
+    /// Read one spilled vector through a handle from [`Self::open_reader`].
+    pub(super) fn read_with(&self, file: &mut File, id: u64) -> OmniResult<Option<Vec<f32>>> {
+        let Some(&offset) = self.offsets.get(&id) else {
+            return Ok(None);
+        };
+        file.seek(SeekFrom::Start(offset))?;
+        let mut record = vec![0u8; self.record_size()];
+        file.read_exact(&mut record)?;
+        Ok(Some(decode(&record[8..])))
+    }
+
+    /// Visit every live spilled vector with one sequential pass over the file.
+    pub(super) fn for_each(&self, mut f: impl FnMut(u64, &[f32])) -> OmniResult<()> {
+        if self.offsets.is_empty() {
+            return Ok(());
+        }
+        let mut reader = BufReader::new(File::open(&self.path)?);
+        let mut record = vec![0u8; self.record_size()];
+        let mut vector = vec![0f32; self.dimensions];
+        let mut offset = 0;
+        while offset < self.end {
+            reader.read_exact(&mut record)?;
+            let mut id = [0u8; 8];
+            id.copy_from_slice(&record[..8]);
+            let id = u64::from_le_bytes(id);
+            if self.offsets.get(&id) == Some(&offset) {
+                for (x, bytes) in vector.iter_mut().zip(record[8..].chunks_exact(4)) {
+                    *x = f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+                }
+                f(id, &vector);
+            }
+            offset += record.len() as u64;
+        }
+        Ok(())
+    }
+
+    /// Heap bytes held for the spilled vectors (not the file itself).
+    pub(super) fn memory_usage_bytes(&self) -> usize {
+        self.offsets.len() * ENTRY_OVERHEAD_BYTES
+    }
+}
+
+impl Drop for SpillStore {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+fn decode(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spilled_vectors_round_trip() {
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let mut store = SpillStore::create(&dir.path().join("vectors.spill"), 2).expect("create");
+        store
+            .write(&[(1, vec![1.0, 2.0]), (2, vec![3.0, 4.0])])
+            .expect("write");
+        store.write(&[(1, vec![5.0, 6.0])]).expect("replace");
+        store.remove(2);
+
+        assert_eq!(store.len(), 1);
+        assert_eq!(store.get(1).expect("get"), Some(vec![5.0, 6.0]));
+        assert_eq!(store.get(2).expect("get"), None);
+
+        let mut seen = Vec::new();
+        store
+            .for_each(|id, v| seen.push((id, v.to_vec())))
+            .expect("scan");
+        assert_eq!(seen, vec![(1, vec![5.0, 6.0])]);
+
+        let path = store.path.clone();
+        drop(store);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_spill_files_are_not_shared() {
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let index_path = dir.path().join("vectors.bin");
+        let mut first = SpillStore::create_for(&index_path, 2).expect("create");
+        let second = SpillStore::create_for(&index_path, 2).expect("create");
+        assert_ne!(first.path, second.path);
+
+        first.write(&[(1, vec![1.0, 2.0])]).expect("write");
+        drop(second);
+        assert_eq!(first.get(1).expect("get"), Some(vec![1.0, 2.0]));
+    }
+}
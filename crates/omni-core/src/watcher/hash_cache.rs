@@ -21,8 +21,15 @@
 //! cache is in-memory only and is not persisted; it is rebuilt from successful index
 //! runs as the engine runs.
 //!
+//! Once [`FileHashCache::set_repo_root`] is called, entries are keyed by
+//! repo-relative path, so the cache survives the repository moving. Callers
+//! keep passing whatever path they have; paths under the root are
+//! relativized on the way in. Caches written before that (version 2) keyed
+//! by absolute path and are converted on load.
+//!
 //! Atomic writes use temp-file + rename to prevent corruption.
 
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -31,7 +38,9 @@ use std::time::SystemTime;
 use serde::{Deserialize, Serialize};
 use xxhash_rust::xxh3::xxh3_64;
 
+use crate::encoding::SourceText;
 use crate::error::{OmniError, OmniResult};
+use crate::paths;
 
 // ---------------------------------------------------------------------------
 // Public API
@@ -49,6 +58,8 @@ pub struct FileHashCache {
     mtime_cache: HashMap<PathBuf, SystemTime>,
     /// Path to the on-disk JSON cache.
     cache_file: PathBuf,
+    /// Repository root that keys are relative to; see [`Self::set_repo_root`].
+    root: Option<PathBuf>,
     /// Whether the hash map has been modified since last save.
     dirty: bool,
 }
@@ -60,6 +71,7 @@ impl FileHashCache {
             hashes: HashMap::new(),
             mtime_cache: HashMap::new(),
             cache_file: index_dir.join("file_hashes.json"),
+            root: None,
             dirty: false,
         }
     }
@@ -76,6 +88,7 @@ impl FileHashCache {
                 hashes: HashMap::new(),
                 mtime_cache: HashMap::new(),
                 cache_file,
+                root: None,
                 dirty: false,
             });
         }
@@ -94,6 +107,7 @@ impl FileHashCache {
                     hashes: cache_data.hashes,
                     mtime_cache: HashMap::new(), // always rebuilt in memory
                     cache_file,
+                    root: None,
                     dirty: false,
                 })
             }
@@ -103,6 +117,7 @@ impl FileHashCache {
                     hashes: HashMap::new(),
                     mtime_cache: HashMap::new(),
                     cache_file,
+                    root: None,
                     dirty: false,
                 })
             }
@@ -113,6 +128,49 @@ impl FileHashCache {
     // Primary API
     // -----------------------------------------------------------------------
 
+    /// Key entries by path relative to `repo_path` from now on, converting
+    /// absolute keys already under it.
+    pub fn set_repo_root(&mut self, repo_path: &Path) {
+        let absolute: Vec<(PathBuf, PathBuf)> = self
+            .hashes
+            .keys()
+            .filter(|p| p.is_absolute())
+            .filter_map(|p| Some((p.clone(), paths::strip_root(p, repo_path)?)))
+            .collect();
+        for (path, rel) in &absolute {
+            if let Some(hash) = self.hashes.remove(path) {
+                self.hashes.insert(rel.clone(), hash);
+            }
+            if let Some(mtime) = self.mtime_cache.remove(path) {
+                self.mtime_cache.insert(rel.clone(), mtime);
+            }
+        }
+        if !absolute.is_empty() {
+            tracing::info!(
+                converted = absolute.len(),
+                "hash cache keys converted to repo-relative paths"
+            );
+            self.dirty = true;
+        }
+        self.root = Some(repo_path.to_path_buf());
+    }
+
+    /// Map key for `path`: relative to the repo root when under it.
+    fn key<'a>(&self, path: &'a Path) -> Cow<'a, Path> {
+        match &self.root {
+            Some(root) => paths::relative_to(path, root),
+            None => Cow::Borrowed(path),
+        }
+    }
+
+    /// Filesystem path for a map key.
+    fn resolve(&self, key: &Path) -> PathBuf {
+        match &self.root {
+            Some(root) if key.is_relative() => root.join(key),
+            _ => key.to_path_buf(),
+        }
+    }
+
     /// Pre-populate the in-memory mtime cache from the filesystem for all
     /// files currently in the hash cache.
     ///
@@ -169,54 +227,60 @@ impl FileHashCache {
     ///
     /// Returns:
     /// - `Ok((false, None))` — file is unchanged; caller should skip re-indexing.
-    /// - `Ok((true, Some(content)))` — file changed; content already in memory
-    ///   so the caller need not read it again.
+    /// - `Ok((true, Some(source)))` — file changed; content already in memory
+    ///   (decoded to UTF-8, see [`crate::encoding`]) so the caller need not
+    ///   read it again.
     /// - `Err(_)` — I/O failure.
     ///
     /// Internally runs the three-tier strategy:
     /// - Tier 1: mtime check (in-memory, ~1 µs) — most files stop here on warm runs.
     /// - Tier 2: xxHash3 (~40 ns/KB) — catches `touch` without edit.
     /// - Tier 3: returns content directly — no double-read.
-    pub fn check_and_read(&mut self, path: &Path) -> OmniResult<(bool, Option<String>)> {
+    pub fn check_and_read(&mut self, path: &Path) -> OmniResult<(bool, Option<SourceText>)> {
         // --- Tier 1: mtime ---
         let mtime = fs::metadata(path).and_then(|m| m.modified()).ok();
 
+        let key = self.key(path);
         if let Some(mtime) = mtime {
-            if self.mtime_cache.get(path) == Some(&mtime) {
+            if self.mtime_cache.get(&*key) == Some(&mtime) {
                 // mtime identical → file cannot have changed
                 return Ok((false, None));
             }
         }
 
         // --- Tier 2 / 3: read content + hash ---
-        let content = match fs::read_to_string(path) {
-            Ok(c) => c,
-            Err(e) => {
-                return Err(OmniError::Io(e));
-            }
-        };
+        // Hash the decoded text: it is what the pipeline hashes on commit.
+        let source = crate::encoding::read_source(path).map_err(OmniError::Io)?;
 
-        let hash = xxh3_64(content.as_bytes());
+        let hash = xxh3_64(source.text.as_bytes());
 
         // Tier 2: hash matches → content is identical despite mtime change
-        if self.hashes.get(path) == Some(&hash) {
+        if self.hashes.get(&*key) == Some(&hash) {
             // Sync mtime so future stat checks short-circuit
             if let Some(mtime) = mtime {
-                self.mtime_cache.insert(path.to_path_buf(), mtime);
+                self.mtime_cache.insert(key.into_owned(), mtime);
             }
             return Ok((false, None));
         }
 
         // Tier 3: content changed → return it directly
-        Ok((true, Some(content)))
+        Ok((true, Some(source)))
     }
 
     /// Record that a file was successfully indexed with the given content hash and mtime.
     ///
     /// Updates both the persistent hash store (dirty) and the in-memory mtime cache.
     pub fn update_from_read(&mut self, path: PathBuf, hash: u64, mtime: SystemTime) {
-        self.hashes.insert(path.clone(), hash);
-        self.mtime_cache.insert(path, mtime);
+        let key = match self
+            .root
+            .as_deref()
+            .and_then(|root| paths::strip_root(&path, root))
+        {
+            Some(rel) => rel,
+            None => path,
+        };
+        self.hashes.insert(key.clone(), hash);
+        self.mtime_cache.insert(key, mtime);
         self.dirty = true;
     }
 
@@ -272,28 +336,30 @@ impl FileHashCache {
             self.update_from_read(path, hash, mtime);
         } else {
             // File unreadable at commit time — remove stale entry if any
-            self.hashes.remove(&path);
-            self.mtime_cache.remove(&path);
+            self.remove(&path);
             self.dirty = true;
         }
     }
 
     /// Get the stored xxHash3 for a file, or `None` if not cached.
     pub fn get_hash_u64(&self, path: &Path) -> Option<u64> {
-        self.hashes.get(path).copied()
+        self.hashes.get(&*self.key(path)).copied()
     }
 
     /// Get the cached hash as a hex string (for backward compatibility).
     ///
     /// Returns `None` if the file is not in the cache.
     pub fn get_hash(&self, path: &Path) -> Option<String> {
-        self.hashes.get(path).map(|h| format!("{h:016x}"))
+        self.hashes
+            .get(&*self.key(path))
+            .map(|h| format!("{h:016x}"))
     }
 
     /// Remove a file from the cache (called when a file is deleted).
     pub fn remove(&mut self, path: &Path) -> bool {
-        let removed_hash = self.hashes.remove(path).is_some();
-        self.mtime_cache.remove(path);
+        let key = self.key(path);
+        let removed_hash = self.hashes.remove(&*key).is_some();
+        self.mtime_cache.remove(&*key);
         if removed_hash {
             self.dirty = true;
         }
@@ -313,7 +379,7 @@ impl FileHashCache {
         }
 
         let cache_data = CacheData {
-            version: 2,
+            version: if self.root.is_some() { 3 } else { 2 },
             hashes: self.hashes.clone(),
         };
 
@@ -338,6 +404,23 @@ impl FileHashCache {
         Ok(())
     }
 
+    /// Approximate heap bytes held by the hash and mtime maps.
+    pub fn memory_usage_bytes(&self) -> usize {
+        // PathBuf header + hash table control bytes and padding
+        const ENTRY_OVERHEAD: usize = std::mem::size_of::<PathBuf>() + 8;
+        let hashes: usize = self
+            .hashes
+            .keys()
+            .map(|p| p.as_os_str().len() + ENTRY_OVERHEAD + std::mem::size_of::<u64>())
+            .sum();
+        let mtimes: usize = self
+            .mtime_cache
+            .keys()
+            .map(|p| p.as_os_str().len() + ENTRY_OVERHEAD + std::mem::size_of::<SystemTime>())
+            .sum();
+        hashes + mtimes
+    }
+
     /// Number of entries in the persistent hash store.
     pub fn len(&self) -> usize {
         self.hashes.len()
@@ -370,8 +453,17 @@ impl FileHashCache {
     /// Returns the number of entries removed.
     pub fn prune_missing_files(&mut self) -> usize {
         let before = self.hashes.len();
-        self.hashes.retain(|path, _| path.exists());
-        self.mtime_cache.retain(|path, _| path.exists());
+        let missing: Vec<PathBuf> = self
+            .hashes
+            .keys()
+            .chain(self.mtime_cache.keys())
+            .filter(|key| !self.resolve(key).exists())
+            .cloned()
+            .collect();
+        for key in &missing {
+            self.hashes.remove(key);
+            self.mtime_cache.remove(key);
+        }
         let removed = before - self.hashes.len();
 
         if removed > 0 {
@@ -390,7 +482,8 @@ impl FileHashCache {
 /// Serializable cache data.
 #[derive(Debug, Serialize, Deserialize)]
 struct CacheData {
-    /// Cache format version. Version 2 stores u64 xxHash3 values.
+    /// Cache format version. Version 2 stores u64 xxHash3 values; version 3
+    /// keys them by repo-relative path.
     version: u32,
     /// Map from file path to xxHash3 (stored as JSON integers).
     hashes: HashMap<PathBuf, u64>,
@@ -469,7 +562,7 @@ mod tests {
 
         let (changed, content) = cache.check_and_read(&path).expect("check_and_read");
         assert!(changed, "new file must be reported as changed");
-        assert_eq!(content.as_deref(), Some("new content"));
+        assert_eq!(content.map(|s| s.text).as_deref(), Some("new content"));
     }
 
     #[test]
@@ -482,7 +575,7 @@ mod tests {
         // First call: file is new → changed=true
         let (changed, content) = cache.check_and_read(&path).expect("first read");
         assert!(changed);
-        let content = content.expect("content on first read");
+        let content = content.expect("content on first read").text;
 
         // Record it as indexed
         let hash = xxh3_64(content.as_bytes());
@@ -530,7 +623,7 @@ mod tests {
         // we fall through to the Tier-2 hash comparison every time.
         let (changed, content) = cache.check_and_read(&path).expect("first");
         assert!(changed);
-        let hash = xxh3_64(content.expect("content").as_bytes());
+        let hash = xxh3_64(content.expect("content").text.as_bytes());
         cache.update_from_read(path.clone(), hash, std::time::UNIX_EPOCH);
 
         // Overwrite file content
@@ -543,7 +636,10 @@ mod tests {
         // check_and_read must detect the change via hash (Tier 2 / 3)
         let (changed2, content2) = cache.check_and_read(&path).expect("second");
         assert!(changed2, "modified file must be reported as changed");
-        assert_eq!(content2.as_deref(), Some("modified content"));
+        assert_eq!(
+            content2.map(|s| s.text).as_deref(),
+            Some("modified content")
+        );
     }
 
     // -----------------------------------------------------------------------
@@ -688,6 +784,31 @@ mod tests {
         assert!(cache.get_hash_u64(&missing).is_none());
     }
 
+    #[test]
+    fn test_repo_root_keys_relative_paths() {
+        let temp = TempDir::new().expect("temp dir");
+        let repo = temp.path().join("repo");
+        fs::create_dir_all(&repo).expect("create repo");
+        let file = create_test_file(&repo, "a.rs", "fn a() {}");
+
+        let mut cache = FileHashCache::new(temp.path());
+        cache.update_from_read(file.clone(), 7_u64, SystemTime::now());
+        cache.set_repo_root(&repo);
+        assert_eq!(cache.get_hash_u64(Path::new("a.rs")), Some(7));
+        assert_eq!(cache.get_hash_u64(&file), Some(7));
+        assert_eq!(cache.prune_missing_files(), 0);
+        cache.save().expect("save");
+
+        // The repository moves; the cache follows it.
+        let moved = temp.path().join("moved");
+        fs::rename(&repo, &moved).expect("move repo");
+        let mut reloaded = FileHashCache::load(temp.path()).expect("load");
+        reloaded.set_repo_root(&moved);
+        assert_eq!(reloaded.get_hash_u64(&moved.join("a.rs")), Some(7));
+        assert!(reloaded.remove(&moved.join("a.rs")));
+        assert!(reloaded.is_empty());
+    }
+
     #[test]
     fn test_statistics() {
         let temp = TempDir::new().expect("temp dir");
@@ -9,19 +9,30 @@
 //! - `full_scan` walks the directory tree synchronously and emits events
 //! - `watch` uses notify's debounced watcher for live FS monitoring
 //! - Exclude patterns are checked against path components (not full globs)
+//! - With `follow_symlinks`, walks track canonical directories and file
+//!   identity, so symlink loops terminate and shared targets are emitted once
+//! - Editor temp files (`WatcherConfig::ignore_patterns`) never produce events,
+//!   and a delete followed by a re-create within one debounce window (an
+//!   atomic save) collapses into a single `FileChanged`
 //! - Language detection uses file extension via `Language::from_extension`
+//! - `notify` backends miss changes on network filesystems (NFS, SMB, 9p,
+//!   Docker and VM shares), so `watch` polls there instead, rescanning every
+//!   `poll_interval_secs` and comparing content hashes. Native watching
+//!   rescans on the same interval to catch dropped events.
 
 pub mod hash_cache;
 
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime};
 
 use notify::RecursiveMode;
 use notify_debouncer_mini::{new_debouncer, DebouncedEventKind};
 use tokio::sync::mpsc;
 
-use crate::config::{IndexingConfig, WatcherConfig};
+use crate::config::{IndexingConfig, WatchMode, WatcherConfig};
 use crate::error::{OmniError, OmniResult};
+use crate::privacy::PrivacyPolicy;
 use crate::types::{Language, PipelineEvent};
 
 /// File system watcher that emits pipeline events.
@@ -29,6 +40,8 @@ use crate::types::{Language, PipelineEvent};
 pub struct FileWatcher {
     watcher_config: WatcherConfig,
     indexing_config: IndexingConfig,
+    privacy: PrivacyPolicy,
+    extra_extensions: Vec<String>,
     root: PathBuf,
 }
 
@@ -43,27 +56,76 @@ impl FileWatcher {
         Self {
             watcher_config: watcher_config.clone(),
             indexing_config: indexing_config.clone(),
-            root: root.to_path_buf(),
+            privacy: PrivacyPolicy::default(),
+            extra_extensions: Vec::new(),
+            root: crate::paths::simplify(root).into_owned(),
         }
     }
 
+    /// Never emit events for paths the privacy policy excludes.
+    #[must_use]
+    pub fn with_privacy(mut self, privacy: PrivacyPolicy) -> Self {
+        self.privacy = privacy;
+        self
+    }
+
+    /// Also treat files with these lowercase extensions as source files
+    /// (extensions claimed by plugin analyzers).
+    #[must_use]
+    pub fn with_extra_extensions(mut self, extensions: Vec<String>) -> Self {
+        self.extra_extensions = extensions;
+        self
+    }
+
     /// Perform a full directory scan and emit FileChanged for all source files.
     ///
     /// Returns the number of files discovered.
     pub fn full_scan(&self, tx: &mpsc::Sender<PipelineEvent>) -> OmniResult<usize> {
-        let mut count = 0usize;
-        self.walk_dir(&self.root, tx, &mut count)?;
-        tracing::info!(files = count, root = %self.root.display(), "full scan complete");
-        Ok(count)
+        let mut state = self.walk_state();
+        self.walk_dir(&self.root, &mut |path| emit_changed(tx, path), &mut state)?;
+        tracing::info!(files = state.count, root = %self.root.display(), "full scan complete");
+        Ok(state.count)
+    }
+
+    /// Collect the source files a full scan would emit, without a channel.
+    pub fn list_files(&self) -> OmniResult<Vec<PathBuf>> {
+        let mut files = Vec::new();
+        let mut state = self.walk_state();
+        self.walk_dir(
+            &self.root,
+            &mut |path| {
+                files.push(path);
+                true
+            },
+            &mut state,
+        )?;
+        Ok(files)
     }
 
-    /// Recursively walk a directory, emitting FileChanged events for source files.
+    /// Fresh walk state, with the root already marked visited when symlinks
+    /// are followed so a link back to it is not walked again.
+    fn walk_state(&self) -> WalkState {
+        let mut state = WalkState::default();
+        if self.indexing_config.follow_symlinks {
+            state.enter_dir(&self.root);
+        }
+        state
+    }
+
+    /// Recursively walk a directory, passing source files to `emit` until it
+    /// returns `false`.
+    ///
+    /// With `follow_symlinks`, every directory and file is checked against
+    /// `state` first, so symlink loops terminate and a file reachable through
+    /// several links is emitted once.
     fn walk_dir(
         &self,
         dir: &Path,
-        tx: &mpsc::Sender<PipelineEvent>,
-        count: &mut usize,
+        emit: &mut dyn FnMut(PathBuf) -> bool,
+        state: &mut WalkState,
     ) -> OmniResult<()> {
+        let follow = self.indexing_config.follow_symlinks;
+
         let entries = std::fs::read_dir(dir).map_err(|e| {
             OmniError::Internal(format!("failed to read directory {}: {e}", dir.display()))
         })?;
@@ -94,10 +156,14 @@ impl FileWatcher {
             };
 
             if file_type.is_dir() {
-                self.walk_dir(&path, tx, count)?;
+                if follow && !state.enter_dir(&path) {
+                    tracing::debug!(path = %path.display(), "directory already walked");
+                    continue;
+                }
+                self.walk_dir(&path, emit, state)?;
             } else if file_type.is_file() {
                 // Check if this is a supported source file
-                if !is_source_file_static(&path) {
+                if !is_source_file(&path, &self.extra_extensions) {
                     continue;
                 }
 
@@ -114,25 +180,43 @@ impl FileWatcher {
                     }
                 }
 
-                // Emit event using blocking send to apply backpressure instead of dropping
-                let event = PipelineEvent::FileChanged { path };
-                if tx.blocking_send(event).is_err() {
-                    tracing::warn!("pipeline channel closed, stopping scan");
+                if follow && !state.claim_file(&path) {
+                    tracing::debug!(path = %path.display(), "file already emitted");
+                    continue;
+                }
+
+                if !emit(path) {
                     break;
                 }
-                *count += 1;
-            } else if file_type.is_symlink() && self.indexing_config.follow_symlinks {
+                state.count += 1;
+            } else if file_type.is_symlink() && follow {
                 // Follow symlinks if configured
-                if let Ok(resolved) = std::fs::canonicalize(&path) {
+                if let Ok(resolved) = crate::paths::canonicalize(&path) {
                     if resolved.is_dir() {
-                        self.walk_dir(&resolved, tx, count)?;
-                    } else if resolved.is_file() && is_source_file_static(&resolved) {
-                        let event = PipelineEvent::FileChanged { path: resolved };
-                        if tx.blocking_send(event).is_err() {
-                            tracing::warn!("pipeline channel closed, stopping scan");
+                        if !state.enter_dir(&resolved) {
+                            tracing::debug!(
+                                path = %path.display(),
+                                target = %resolved.display(),
+                                "symlink cycle or duplicate directory, skipping"
+                            );
+                            continue;
+                        }
+                        self.walk_dir(&resolved, emit, state)?;
+                    } else if resolved.is_file()
+                        && is_source_file(&resolved, &self.extra_extensions)
+                    {
+                        if !state.claim_file(&resolved) {
+                            tracing::debug!(
+                                path = %path.display(),
+                                target = %resolved.display(),
+                                "symlink to already emitted file, skipping"
+                            );
+                            continue;
+                        }
+                        if !emit(resolved) {
                             break;
                         }
-                        *count += 1;
+                        state.count += 1;
                     }
                 }
             }
@@ -144,13 +228,33 @@ impl FileWatcher {
     /// Start watching for file changes.
     ///
     /// Sends `PipelineEvent` messages through the provided channel.
-    /// Blocks until a shutdown signal is received or an error occurs.
+    /// Blocks until the channel or the native watcher closes, or an error
+    /// occurs. See [`WatchMode`] for when changes are polled instead.
     pub async fn watch(&self, tx: mpsc::Sender<PipelineEvent>) -> OmniResult<()> {
-        let debounce_ms = self.watcher_config.debounce_ms;
-        let root = self.root.clone();
+        let poll = match self.watcher_config.mode {
+            WatchMode::Native => false,
+            WatchMode::Poll => true,
+            WatchMode::Auto => {
+                let network = is_network_fs(&self.root);
+                if network {
+                    tracing::info!(
+                        root = %self.root.display(),
+                        "repository is on a network filesystem, polling for changes"
+                    );
+                }
+                network
+            }
+        };
+        let this = self.clone();
+        if poll {
+            return tokio::task::spawn_blocking(move || this.poll(&tx))
+                .await
+                .map_err(|e| OmniError::Internal(format!("watcher task panicked: {e}")))?;
+        }
 
+        let debounce_ms = self.watcher_config.debounce_ms;
         tracing::info!(
-            root = %root.display(),
+            root = %self.root.display(),
             debounce_ms,
             "starting file watcher"
         );
@@ -158,58 +262,66 @@ impl FileWatcher {
         // Create a channel for notify events
         let (notify_tx, notify_rx) = std::sync::mpsc::channel();
 
-        // Create debounced watcher
-        let mut debouncer = new_debouncer(Duration::from_millis(debounce_ms), notify_tx)
-            .map_err(|e| OmniError::Internal(format!("failed to create file watcher: {e}")))?;
-
-        // Start watching
-        debouncer
-            .watcher()
-            .watch(&root, RecursiveMode::Recursive)
-            .map_err(|e| OmniError::Internal(format!("failed to watch directory: {e}")))?;
+        // Create debounced watcher and start watching
+        let debouncer = new_debouncer(Duration::from_millis(debounce_ms), notify_tx)
+            .map_err(|e| OmniError::Internal(format!("failed to create file watcher: {e}")))
+            .and_then(|mut debouncer| {
+                debouncer
+                    .watcher()
+                    .watch(&self.root, RecursiveMode::Recursive)
+                    .map_err(|e| OmniError::Internal(format!("failed to watch directory: {e}")))?;
+                Ok(debouncer)
+            });
+        let _debouncer = match debouncer {
+            Ok(debouncer) => debouncer,
+            Err(e) if self.watcher_config.mode == WatchMode::Auto => {
+                tracing::warn!(error = %e, "native file watching unavailable, polling for changes");
+                return tokio::task::spawn_blocking(move || this.poll(&tx))
+                    .await
+                    .map_err(|e| OmniError::Internal(format!("watcher task panicked: {e}")))?;
+            }
+            Err(e) => return Err(e),
+        };
 
         // Process events in a blocking task
-        let indexing_config = self.indexing_config.clone();
-        let max_file_size = self.indexing_config.max_file_size;
+        let grace = Duration::from_millis(debounce_ms);
 
         tokio::task::spawn_blocking(move || {
+            // Deletions wait one debounce window in case the file is re-created.
+            let mut pending = PendingDeletes::new(grace);
+            let mut rescan = this.rescan_interval().map(|every| Rescan {
+                every,
+                next: Instant::now() + every,
+                snapshot: Snapshot::new(this.list_files().unwrap_or_default()),
+            });
             loop {
-                match notify_rx.recv() {
+                let deadline = pending
+                    .next_deadline()
+                    .into_iter()
+                    .chain(rescan.as_ref().map(|r| r.next))
+                    .min();
+                let received = match deadline {
+                    Some(deadline) => {
+                        notify_rx.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                    }
+                    None => notify_rx
+                        .recv()
+                        .map_err(|_| std::sync::mpsc::RecvTimeoutError::Disconnected),
+                };
+                match received {
                     Ok(Ok(events)) => {
+                        let mut seen = HashSet::new();
                         for event in events {
-                            let path = event.path;
-
                             // Filter by event kind
-                            match event.kind {
-                                DebouncedEventKind::Any => {}
-                                _ => continue,
-                            }
-
-                            // Skip excluded and non-source files
-                            if is_excluded_static(&path, &indexing_config.exclude_patterns) {
+                            if event.kind != DebouncedEventKind::Any
+                                || !seen.insert(event.path.clone())
+                            {
                                 continue;
                             }
-
-                            if path.is_file() {
-                                // Check if it's a recognized source file
-                                if !is_source_file_static(&path) {
-                                    continue;
-                                }
-
-                                // Check file size
-                                if let Ok(meta) = std::fs::metadata(&path) {
-                                    if meta.len() > max_file_size {
-                                        continue;
-                                    }
-                                }
-
-                                let event = PipelineEvent::FileChanged { path };
-                                if tx.blocking_send(event).is_err() {
-                                    tracing::warn!("pipeline channel closed");
+                            if let Some(event) = this.classify(event.path, &mut pending) {
+                                if let Some(rescan) = &mut rescan {
+                                    rescan.snapshot.refresh(&event);
                                 }
-                            } else if !path.exists() {
-                                // File was deleted
-                                let event = PipelineEvent::FileDeleted { path };
                                 if tx.blocking_send(event).is_err() {
                                     tracing::warn!("pipeline channel closed");
                                 }
@@ -219,12 +331,41 @@ impl FileWatcher {
                     Ok(Err(e)) => {
                         tracing::warn!(error = %e, "file watcher error");
                     }
-                    Err(_) => {
+                    Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {}
+                    Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => {
                         // Channel closed -- watcher was dropped
                         tracing::info!("file watcher channel closed, stopping");
                         break;
                     }
                 }
+
+                for path in pending.due(Instant::now()) {
+                    let event = if path.is_file() {
+                        // Renamed back into place after the grace window opened.
+                        this.changed_event(path)
+                    } else {
+                        Some(PipelineEvent::FileDeleted { path })
+                    };
+                    if let Some(event) = event {
+                        if let Some(rescan) = &mut rescan {
+                            rescan.snapshot.refresh(&event);
+                        }
+                        if tx.blocking_send(event).is_err() {
+                            tracing::warn!("pipeline channel closed");
+                        }
+                    }
+                }
+
+                if let Some(rescan) = rescan.as_mut().filter(|r| r.next <= Instant::now()) {
+                    rescan.next = Instant::now() + rescan.every;
+                    if !this.rescan(&mut rescan.snapshot, &tx) {
+                        break;
+                    }
+                }
+                if tx.is_closed() {
+                    tracing::info!("pipeline channel closed, stopping file watcher");
+                    break;
+                }
             }
         })
         .await
@@ -233,9 +374,97 @@ impl FileWatcher {
         Ok(())
     }
 
-    /// Check if a path should be excluded based on configured patterns.
-    fn is_excluded(&self, path: &Path) -> bool {
-        is_excluded_static(path, &self.indexing_config.exclude_patterns)
+    /// Time between rescans, `None` when `poll_interval_secs` is 0.
+    fn rescan_interval(&self) -> Option<Duration> {
+        let secs = self.watcher_config.poll_interval_secs;
+        (secs > 0).then(|| Duration::from_secs(secs))
+    }
+
+    /// Poll mode: rescan every `poll_interval_secs` until the channel closes.
+    fn poll(&self, tx: &mpsc::Sender<PipelineEvent>) -> OmniResult<()> {
+        let Some(every) = self.rescan_interval() else {
+            tracing::warn!("watcher.poll_interval_secs is 0, not polling for changes");
+            return Ok(());
+        };
+        tracing::info!(
+            root = %self.root.display(),
+            interval_secs = every.as_secs(),
+            "polling for file changes"
+        );
+        let mut snapshot = Snapshot::new(self.list_files()?);
+        loop {
+            std::thread::sleep(every);
+            if tx.is_closed() || !self.rescan(&mut snapshot, tx) {
+                tracing::info!("pipeline channel closed, stopping file watcher");
+                return Ok(());
+            }
+        }
+    }
+
+    /// Walk the tree and send the changes since `snapshot`. A walk that
+    /// fails (an unreachable mount) reports nothing rather than deleting
+    /// every file. Returns `false` once the pipeline channel is closed.
+    fn rescan(&self, snapshot: &mut Snapshot, tx: &mpsc::Sender<PipelineEvent>) -> bool {
+        let files = match self.list_files() {
+            Ok(files) => files,
+            Err(e) => {
+                tracing::warn!(error = %e, "rescan failed, keeping previous snapshot");
+                return true;
+            }
+        };
+        let events = snapshot.update(files);
+        if !events.is_empty() {
+            tracing::debug!(changes = events.len(), "rescan found changes");
+        }
+        events
+            .into_iter()
+            .all(|event| tx.blocking_send(event).is_ok())
+    }
+
+    /// Turn a debounced path into a pipeline event, deferring deletions to
+    /// `pending`. Returns `None` for ignored paths and deferred deletions.
+    fn classify(&self, path: PathBuf, pending: &mut PendingDeletes) -> Option<PipelineEvent> {
+        // Events for a verbatim-prefixed watch root carry the prefix too.
+        let path = crate::paths::into_simplified(path);
+        if self.is_excluded(&path) {
+            return None;
+        }
+        if path.is_file() {
+            // Re-created before its deletion was reported: one change, not two.
+            pending.cancel(&path);
+            self.changed_event(path)
+        } else {
+            if !path.exists() {
+                pending.defer(path, Instant::now());
+            }
+            None
+        }
+    }
+
+    /// `FileChanged` for `path` if it is a source file within the size limit.
+    fn changed_event(&self, path: PathBuf) -> Option<PipelineEvent> {
+        if !is_source_file(&path, &self.extra_extensions) {
+            return None;
+        }
+        if let Ok(meta) = std::fs::metadata(&path) {
+            if meta.len() > self.indexing_config.max_file_size {
+                return None;
+            }
+        }
+        Some(PipelineEvent::FileChanged { path })
+    }
+
+    /// Check if a path should be excluded based on configured patterns,
+    /// editor temp-file patterns, or the privacy policy.
+    pub fn is_excluded(&self, path: &Path) -> bool {
+        is_excluded_static(
+            path,
+            &self.indexing_config.exclude_patterns,
+            &self.indexing_config.vendored_patterns,
+        ) || is_temp_file(path, &self.watcher_config.ignore_patterns)
+            || self
+                .privacy
+                .is_excluded(&crate::paths::relative_to(path, &self.root))
     }
 }
 
@@ -245,20 +474,320 @@ impl FileWatcher {
 /// - "node_modules" matches any path containing a "node_modules" directory
 /// - "*.lock" matches files ending in .lock
 /// - ".git" matches the .git directory
-fn is_excluded_static(path: &Path, exclude_patterns: &[String]) -> bool {
-    for component in path.components() {
+///
+/// Components matching a vendored pattern are never excluded, so listing
+/// `node_modules` there indexes it despite the default exclude.
+fn is_excluded_static(
+    path: &Path,
+    exclude_patterns: &[String],
+    vendored_patterns: &[String],
+) -> bool {
+    path.components().any(|component| {
         let name = component.as_os_str().to_string_lossy();
-        for pattern in exclude_patterns {
-            if let Some(suffix) = pattern.strip_prefix('*') {
-                if name.ends_with(suffix) {
-                    return true;
+        component_matches(&name, exclude_patterns) && !component_matches(&name, vendored_patterns)
+    })
+}
+
+/// Check if a path lies in a vendored-dependency tree, i.e. one of its
+/// components matches `IndexingConfig::vendored_patterns`.
+pub fn is_vendored(path: &Path, vendored_patterns: &[String]) -> bool {
+    !vendored_patterns.is_empty()
+        && path
+            .components()
+            .any(|c| component_matches(&c.as_os_str().to_string_lossy(), vendored_patterns))
+}
+
+/// Check a single path component against exclude-style patterns.
+fn component_matches(name: &str, patterns: &[String]) -> bool {
+    patterns
+        .iter()
+        .any(|pattern| match pattern.strip_prefix('*') {
+            Some(suffix) => name.ends_with(suffix),
+            None => name == pattern.as_str(),
+        })
+}
+
+/// Check if a path's file name matches an editor temp-file pattern.
+///
+/// A pattern is an exact name or holds a single `*` wildcard, so `*.swp`
+/// matches a suffix, `.#*` a prefix, and `#*#` both ends.
+fn is_temp_file(path: &Path, patterns: &[String]) -> bool {
+    let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+        return false;
+    };
+    patterns
+        .iter()
+        .any(|pattern| match pattern.split_once('*') {
+            Some((prefix, suffix)) => {
+                name.len() >= prefix.len() + suffix.len()
+                    && name.starts_with(prefix)
+                    && name.ends_with(suffix)
+            }
+            None => name == pattern,
+        })
+}
+
+/// Bookkeeping for one directory walk.
+#[derive(Default)]
+struct WalkState {
+    /// Source files emitted so far.
+    count: usize,
+    /// Canonical paths of directories already entered.
+    dirs: HashSet<PathBuf>,
+    /// Identities of files already emitted.
+    files: HashSet<FileId>,
+}
+
+impl WalkState {
+    /// Mark a directory as entered; returns `false` if it already was (or
+    /// cannot be resolved), in which case it must not be walked again.
+    fn enter_dir(&mut self, dir: &Path) -> bool {
+        match crate::paths::canonicalize(dir) {
+            Ok(canonical) => self.dirs.insert(canonical),
+            Err(e) => {
+                tracing::warn!(path = %dir.display(), error = %e, "cannot resolve directory");
+                false
+            }
+        }
+    }
+
+    /// Mark a file as emitted; returns `false` if the same underlying file
+    /// was already emitted under another path. Files whose identity cannot
+    /// be read are always emitted.
+    fn claim_file(&mut self, path: &Path) -> bool {
+        match FileId::of(path) {
+            Some(id) => self.files.insert(id),
+            None => true,
+        }
+    }
+}
+
+/// Identity of a file independent of the path used to reach it.
+///
+/// Device and inode on Unix, so hard links also collapse; the canonical
+/// path elsewhere.
+#[derive(PartialEq, Eq, Hash)]
+enum FileId {
+    #[cfg(unix)]
+    Inode { dev: u64, ino: u64 },
+    #[cfg(not(unix))]
+    Path(PathBuf),
+}
+
+impl FileId {
+    #[cfg(unix)]
+    fn of(path: &Path) -> Option<Self> {
+        use std::os::unix::fs::MetadataExt;
+        let meta = std::fs::metadata(path).ok()?;
+        Some(Self::Inode {
+            dev: meta.dev(),
+            ino: meta.ino(),
+        })
+    }
+
+    #[cfg(not(unix))]
+    fn of(path: &Path) -> Option<Self> {
+        crate::paths::canonicalize(path).ok().map(Self::Path)
+    }
+}
+
+/// Deletions held back for one debounce window.
+///
+/// Atomic saves (Vim's rename-to-backup, write-temp-then-rename) briefly
+/// remove the real file. Reporting that as `FileDeleted` would purge the
+/// file and then reindex it from scratch a moment later; holding the
+/// deletion lets a re-create cancel it.
+struct PendingDeletes {
+    grace: Duration,
+    deadlines: HashMap<PathBuf, Instant>,
+}
+
+impl PendingDeletes {
+    fn new(grace: Duration) -> Self {
+        Self {
+            grace,
+            deadlines: HashMap::new(),
+        }
+    }
+
+    /// Hold a deletion of `path` observed at `now`.
+    fn defer(&mut self, path: PathBuf, now: Instant) {
+        self.deadlines.entry(path).or_insert(now + self.grace);
+    }
+
+    /// Drop a held deletion; returns whether one was pending.
+    fn cancel(&mut self, path: &Path) -> bool {
+        self.deadlines.remove(path).is_some()
+    }
+
+    /// The earliest time a held deletion becomes due.
+    fn next_deadline(&self) -> Option<Instant> {
+        self.deadlines.values().min().copied()
+    }
+
+    /// Remove and return the deletions whose grace window has passed.
+    fn due(&mut self, now: Instant) -> Vec<PathBuf> {
+        let mut due = Vec::new();
+        self.deadlines.retain(|path, deadline| {
+            if *deadline <= now {
+                due.push(path.clone());
+                false
+            } else {
+                true
+            }
+        });
+        due
+    }
+}
+
+/// Periodic rescans alongside native events.
+struct Rescan {
+    every: Duration,
+    next: Instant,
+    snapshot: Snapshot,
+}
+
+/// The source files of the tree as of the last rescan.
+///
+/// Size and mtime decide which files are read; the content hash decides
+/// whether a change is reported, so a `touch` or a network filesystem's
+/// coarse timestamps do not cause a reindex. Files are hashed the first
+/// time they are seen to change, so a change before that is always
+/// reported.
+#[derive(Default)]
+struct Snapshot {
+    files: HashMap<PathBuf, FileStamp>,
+}
+
+#[derive(Clone, Copy)]
+struct FileStamp {
+    len: u64,
+    modified: Option<SystemTime>,
+    hash: Option<u64>,
+}
+
+impl FileStamp {
+    fn of(path: &Path) -> Option<Self> {
+        let meta = std::fs::metadata(path).ok()?;
+        Some(Self {
+            len: meta.len(),
+            modified: meta.modified().ok(),
+            hash: None,
+        })
+    }
+}
+
+impl Snapshot {
+    /// Record `files` without reading them.
+    fn new(files: Vec<PathBuf>) -> Self {
+        let files = files
+            .into_iter()
+            .filter_map(|path| Some((path.clone(), FileStamp::of(&path)?)))
+            .collect();
+        Self { files }
+    }
+
+    /// Apply an event reported natively, so the next rescan does not
+    /// report it again.
+    fn refresh(&mut self, event: &PipelineEvent) {
+        match event {
+            PipelineEvent::FileChanged { path } => match FileStamp::of(path) {
+                Some(stamp) => {
+                    self.files.insert(path.clone(), stamp);
                 }
-            } else if name == pattern.as_str() {
-                return true;
+                None => {
+                    self.files.remove(path);
+                }
+            },
+            PipelineEvent::FileDeleted { path } => {
+                self.files.remove(path);
             }
+            PipelineEvent::FullScan | PipelineEvent::Shutdown => {}
         }
     }
-    false
+
+    /// Replace the snapshot with a fresh listing and return the changes:
+    /// `FileChanged` for new and modified files, `FileDeleted` for files
+    /// no longer listed.
+    fn update(&mut self, files: Vec<PathBuf>) -> Vec<PipelineEvent> {
+        let mut previous = std::mem::take(&mut self.files);
+        let mut events = Vec::new();
+        for path in files {
+            let Some(mut stamp) = FileStamp::of(&path) else {
+                continue;
+            };
+            match previous.remove(&path) {
+                Some(old) if old.len == stamp.len && old.modified == stamp.modified => {
+                    stamp.hash = old.hash;
+                }
+                Some(old) => {
+                    stamp.hash = hash_cache::FileHashCache::compute_hash_u64(&path).ok();
+                    if old.hash.is_none() || old.hash != stamp.hash {
+                        events.push(PipelineEvent::FileChanged { path: path.clone() });
+                    }
+                }
+                None => events.push(PipelineEvent::FileChanged { path: path.clone() }),
+            }
+            self.files.insert(path, stamp);
+        }
+        events.extend(
+            previous
+                .into_keys()
+                .map(|path| PipelineEvent::FileDeleted { path }),
+        );
+        events
+    }
+}
+
+/// Filesystem types whose change notifications miss edits made elsewhere:
+/// on another machine, or on the host of a container or VM share.
+const NETWORK_FILESYSTEMS: &[&str] = &[
+    "nfs", "nfs4", "cifs", "smb3", "smbfs", "afs", "ceph", "glusterfs", "9p", "virtiofs",
+    "fuse.sshfs", "fuse.grpcfuse", "fuse.osxfs", "fuse.rclone",
+];
+
+/// Whether `path` is on a network or host-shared filesystem. Read from
+/// `/proc/self/mountinfo` on Linux; always `false` elsewhere.
+pub fn is_network_fs(path: &Path) -> bool {
+    #[cfg(target_os = "linux")]
+    {
+        let Ok(path) = crate::paths::canonicalize(path) else {
+            return false;
+        };
+        std::fs::read_to_string("/proc/self/mountinfo")
+            .ok()
+            .and_then(|info| mount_fstype(&info, &path).map(str::to_owned))
+            .is_some_and(|fstype| NETWORK_FILESYSTEMS.contains(&fstype.as_str()))
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = path;
+        false
+    }
+}
+
+/// Filesystem type of the mount holding `path`, from `mountinfo` contents:
+/// that of the longest mount point containing it, the last one listed if a
+/// point is mounted over.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn mount_fstype<'a>(mountinfo: &'a str, path: &Path) -> Option<&'a str> {
+    mountinfo
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split(' ').collect();
+            let separator = fields.iter().position(|f| *f == "-")?;
+            // Spaces and other specials in mount points are octal-escaped.
+            let mount_point = fields
+                .get(4)?
+                .replace("\\040", " ")
+                .replace("\\011", "\t")
+                .replace("\\012", "\n")
+                .replace("\\134", "\\");
+            Some((PathBuf::from(mount_point), *fields.get(separator + 1)?))
+        })
+        .filter(|(mount_point, _)| path.starts_with(mount_point))
+        .max_by_key(|(mount_point, _)| mount_point.components().count())
+        .map(|(_, fstype)| fstype)
 }
 
 /// Check if a file has a recognized source file extension.
@@ -274,6 +803,28 @@ fn is_source_file_static(path: &Path) -> bool {
     !matches!(Language::from_extension(&ext_lower), Language::Unknown)
 }
 
+/// [`is_source_file_static`], plus files whose extension is in `extra`.
+fn is_source_file(path: &Path, extra: &[String]) -> bool {
+    is_source_file_static(path)
+        || path
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|ext| extra.iter().any(|e| e.eq_ignore_ascii_case(ext)))
+}
+
+/// Send a `FileChanged` event, blocking to apply backpressure instead of
+/// dropping. Returns `false` once the pipeline channel is closed.
+fn emit_changed(tx: &mpsc::Sender<PipelineEvent>, path: PathBuf) -> bool {
+    if tx
+        .blocking_send(PipelineEvent::FileChanged { path })
+        .is_err()
+    {
+        tracing::warn!("pipeline channel closed, stopping scan");
+        return false;
+    }
+    true
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -281,27 +832,63 @@ mod tests {
     #[test]
     fn test_is_excluded_directory() {
         let excludes = vec![".git".into(), "node_modules".into(), "target".into()];
-        assert!(is_excluded_static(Path::new("/repo/.git/HEAD"), &excludes));
+        assert!(is_excluded_static(
+            Path::new("/repo/.git/HEAD"),
+            &excludes,
+            &[]
+        ));
         assert!(is_excluded_static(
             Path::new("/repo/node_modules/foo/bar.js"),
-            &excludes
+            &excludes,
+            &[]
         ));
         assert!(is_excluded_static(
             Path::new("/repo/target/debug/bin"),
-            &excludes
+            &excludes,
+            &[]
         ));
         assert!(!is_excluded_static(
             Path::new("/repo/src/main.rs"),
-            &excludes
+            &excludes,
+            &[]
         ));
     }
 
     #[test]
     fn test_is_excluded_glob() {
         let excludes = vec!["*.lock".into(), "*.min.js".into()];
-        assert!(is_excluded_static(Path::new("/repo/Cargo.lock"), &excludes));
-        assert!(is_excluded_static(Path::new("/repo/app.min.js"), &excludes));
-        assert!(!is_excluded_static(Path::new("/repo/app.js"), &excludes));
+        assert!(is_excluded_static(
+            Path::new("/repo/Cargo.lock"),
+            &excludes,
+            &[]
+        ));
+        assert!(is_excluded_static(
+            Path::new("/repo/app.min.js"),
+            &excludes,
+            &[]
+        ));
+        assert!(!is_excluded_static(
+            Path::new("/repo/app.js"),
+            &excludes,
+            &[]
+        ));
+    }
+
+    #[test]
+    fn test_vendored_patterns_override_excludes() {
+        let excludes = vec!["node_modules".into(), ".git".into()];
+        let vendored = vec!["node_modules".into()];
+        let lib = Path::new("/repo/node_modules/left-pad/index.js");
+        assert!(!is_excluded_static(lib, &excludes, &vendored));
+        assert!(is_vendored(lib, &vendored));
+        // Other excludes still apply inside a vendored tree.
+        assert!(is_excluded_static(
+            Path::new("/repo/node_modules/left-pad/.git/HEAD"),
+            &excludes,
+            &vendored
+        ));
+        assert!(!is_vendored(Path::new("src/main.rs"), &vendored));
+        assert!(!is_vendored(lib, &[]));
     }
 
     #[test]
@@ -436,4 +1023,244 @@ mod tests {
         let count = watcher.full_scan(&tx).expect("scan");
         assert_eq!(count, 2);
     }
+
+    #[test]
+    fn test_full_scan_skips_privacy_excluded_paths() {
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let root = dir.path();
+
+        std::fs::create_dir_all(root.join("app/secrets")).expect("create dirs");
+        std::fs::write(root.join("app/main.py"), "def main(): pass").expect("write");
+        std::fs::write(root.join("app/secrets/keys.py"), "KEY = 1").expect("write");
+
+        let privacy = PrivacyPolicy::new(&crate::config::PrivacyConfig::default()).expect("policy");
+        let watcher = FileWatcher::new(root, &WatcherConfig::default(), &IndexingConfig::default())
+            .with_privacy(privacy);
+
+        let (tx, _rx) = mpsc::channel(100);
+        let count = watcher.full_scan(&tx).expect("scan");
+        assert_eq!(count, 1, "secrets/ must never be scanned");
+    }
+
+    #[test]
+    fn test_is_temp_file_default_patterns() {
+        let patterns = WatcherConfig::default().ignore_patterns;
+        for name in [
+            ".main.rs.swp",
+            ".main.rs.swx",
+            "4913",
+            "main.rs~",
+            ".#main.rs",
+            "#main.rs#",
+            "main.rs.crswap",
+            "main.rs___jb_tmp___",
+            "main.rs.tmp",
+        ] {
+            assert!(
+                is_temp_file(&Path::new("/repo/src").join(name), &patterns),
+                "{name} should be ignored"
+            );
+        }
+        assert!(!is_temp_file(Path::new("/repo/src/main.rs"), &patterns));
+        assert!(!is_temp_file(Path::new("/repo/src/49131.rs"), &patterns));
+        // `#*#` needs two distinct characters
+        assert!(!is_temp_file(Path::new("/repo/#"), &patterns));
+    }
+
+    #[test]
+    fn test_full_scan_skips_editor_temp_files() {
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let root = dir.path();
+        std::fs::write(root.join("main.rs"), "fn main() {}").expect("write");
+        std::fs::write(root.join(".#main.rs"), "fn main() {}").expect("write");
+
+        let watcher = FileWatcher::new(root, &WatcherConfig::default(), &IndexingConfig::default());
+        let files = watcher.list_files().expect("list");
+        assert_eq!(files, vec![root.join("main.rs")]);
+    }
+
+    #[test]
+    fn test_atomic_save_collapses_into_one_change() {
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let root = dir.path();
+        let file = root.join("main.rs");
+        let watcher = FileWatcher::new(root, &WatcherConfig::default(), &IndexingConfig::default());
+        let mut pending = PendingDeletes::new(Duration::from_millis(100));
+
+        // Vim: main.rs -> main.rs~, the watcher sees main.rs vanish...
+        assert!(watcher.classify(file.clone(), &mut pending).is_none());
+        assert!(watcher
+            .classify(root.join("main.rs~"), &mut pending)
+            .is_none());
+        assert!(pending.next_deadline().is_some());
+
+        // ...then the new main.rs appears before the grace window ends.
+        std::fs::write(&file, "fn main() {}").expect("write");
+        assert!(matches!(
+            watcher.classify(file.clone(), &mut pending),
+            Some(PipelineEvent::FileChanged { path }) if path == file
+        ));
+        assert!(pending
+            .due(Instant::now() + Duration::from_secs(1))
+            .is_empty());
+    }
+
+    #[test]
+    fn test_pending_deletes_become_due_after_grace() {
+        let mut pending = PendingDeletes::new(Duration::from_millis(100));
+        let now = Instant::now();
+        pending.defer(PathBuf::from("/repo/a.rs"), now);
+        pending.defer(PathBuf::from("/repo/b.rs"), now + Duration::from_millis(50));
+        // A repeated deletion keeps its original deadline.
+        pending.defer(PathBuf::from("/repo/a.rs"), now + Duration::from_millis(90));
+
+        assert_eq!(
+            pending.next_deadline(),
+            Some(now + Duration::from_millis(100))
+        );
+        assert!(pending.due(now + Duration::from_millis(99)).is_empty());
+        assert_eq!(
+            pending.due(now + Duration::from_millis(100)),
+            vec![PathBuf::from("/repo/a.rs")]
+        );
+        assert!(pending.cancel(Path::new("/repo/b.rs")));
+        assert!(pending.next_deadline().is_none());
+    }
+
+    #[test]
+    fn test_snapshot_reports_changes_by_content() {
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let root = dir.path();
+        let (a, b, c) = (root.join("a.rs"), root.join("b.rs"), root.join("c.rs"));
+        std::fs::write(&a, "fn a() {}").expect("write");
+        std::fs::write(&b, "fn b() {}").expect("write");
+        let mut snapshot = Snapshot::new(vec![a.clone(), b.clone()]);
+
+        let touch = |path: &Path, secs: u64| {
+            std::fs::File::options()
+                .write(true)
+                .open(path)
+                .and_then(|f| f.set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(secs)))
+                .expect("set mtime");
+        };
+        std::fs::write(&a, "fn a() { changed() }").expect("write");
+        std::fs::remove_file(&b).expect("remove");
+        std::fs::write(&c, "fn c() {}").expect("write");
+        let events = snapshot.update(vec![a.clone(), c.clone()]);
+        let changed: HashSet<_> = events
+            .iter()
+            .filter_map(|e| match e {
+                PipelineEvent::FileChanged { path } => Some(path.clone()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(changed, HashSet::from([a.clone(), c.clone()]));
+        assert!(events
+            .iter()
+            .any(|e| matches!(e, PipelineEvent::FileDeleted { path } if *path == b)));
+
+        // A new timestamp alone is not a change once the content is hashed.
+        touch(&a, 1_000);
+        assert!(snapshot.update(vec![a.clone(), c.clone()]).is_empty());
+        touch(&a, 2_000);
+        assert!(snapshot.update(vec![a.clone(), c.clone()]).is_empty());
+        std::fs::write(&a, "fn a() { again() }").expect("write");
+        touch(&a, 3_000);
+        assert!(matches!(
+            snapshot.update(vec![a.clone(), c]).as_slice(),
+            [PipelineEvent::FileChanged { path }] if *path == a
+        ));
+    }
+
+    #[test]
+    fn test_mount_fstype_picks_innermost_mount() {
+        let mountinfo = "\
+22 1 8:1 / / rw,relatime shared:1 - ext4 /dev/sda1 rw
+40 22 0:35 / /mnt/src rw,relatime shared:20 - nfs4 server:/export rw
+41 40 0:36 / /mnt/src/local\\040disk rw shared:21 - ext4 /dev/sdb1 rw
+50 22 0:40 / /workspace rw - 9p drvfs rw
+51 50 0:41 / /workspace rw - ext4 /dev/sdc1 rw";
+        let fstype = |path: &str| mount_fstype(mountinfo, Path::new(path));
+        assert_eq!(fstype("/home/dev/repo"), Some("ext4"));
+        assert_eq!(fstype("/mnt/src/repo"), Some("nfs4"));
+        assert_eq!(fstype("/mnt/src/local disk/repo"), Some("ext4"));
+        assert_eq!(fstype("/mnt/srcfoo"), Some("ext4"));
+        // Mounted over: the later entry wins.
+        assert_eq!(fstype("/workspace/repo"), Some("ext4"));
+        assert!(NETWORK_FILESYSTEMS.contains(&"nfs4"));
+    }
+
+    #[tokio::test]
+    async fn test_poll_mode_reports_changes() {
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let root = dir.path();
+        std::fs::write(root.join("main.rs"), "fn main() {}").expect("write");
+        let watcher_config = WatcherConfig {
+            mode: WatchMode::Poll,
+            poll_interval_secs: 1,
+            ..WatcherConfig::default()
+        };
+        let watcher = FileWatcher::new(root, &watcher_config, &IndexingConfig::default());
+        let (tx, mut rx) = mpsc::channel(16);
+        let handle = tokio::spawn(async move { watcher.watch(tx).await });
+
+        // Let the first snapshot be taken, then add a file.
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        let added = crate::paths::simplify(root).join("lib.rs");
+        std::fs::write(&added, "pub fn lib() {}").expect("write");
+        let event = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .expect("rescan within the interval");
+        assert!(
+            matches!(&event, Some(PipelineEvent::FileChanged { path }) if *path == added),
+            "{event:?}"
+        );
+
+        drop(rx);
+        tokio::time::timeout(Duration::from_secs(5), handle)
+            .await
+            .expect("watcher stops once the channel closes")
+            .expect("join")
+            .expect("watch");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_full_scan_breaks_symlink_cycles() {
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("src/nested")).expect("mkdir");
+        std::fs::write(root.join("src/lib.rs"), "pub fn lib() {}").expect("write");
+        // src/nested/up -> src, and loop -> the root itself
+        std::os::unix::fs::symlink(root.join("src"), root.join("src/nested/up")).expect("symlink");
+        std::os::unix::fs::symlink(root, root.join("loop")).expect("symlink");
+
+        let indexing = IndexingConfig {
+            follow_symlinks: true,
+            ..IndexingConfig::default()
+        };
+        let watcher = FileWatcher::new(root, &WatcherConfig::default(), &indexing);
+        let files = watcher.list_files().expect("list terminates");
+        assert_eq!(files.len(), 1, "lib.rs reached once: {files:?}");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_full_scan_dedupes_symlinked_targets() {
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let root = dir.path();
+        std::fs::create_dir(root.join("real")).expect("mkdir");
+        std::fs::write(root.join("real/a.rs"), "fn a() {}").expect("write");
+        std::os::unix::fs::symlink(root.join("real"), root.join("alias")).expect("symlink");
+        std::os::unix::fs::symlink(root.join("real/a.rs"), root.join("b.rs")).expect("symlink");
+        std::fs::hard_link(root.join("real/a.rs"), root.join("c.rs")).expect("hard link");
+
+        let indexing = IndexingConfig {
+            follow_symlinks: true,
+            ..IndexingConfig::default()
+        };
+        let watcher = FileWatcher::new(root, &WatcherConfig::default(), &indexing);
+        let (tx, _rx) = mpsc::channel(100);
+        assert_eq!(watcher.full_scan(&tx).expect("scan"), 1);
+    }
 }
@@ -51,6 +51,57 @@ pub struct WorkspaceConfig {
     pub repos: Vec<LinkedRepo>,
 }
 
+/// One merged result from a multi-repo search.
+#[derive(Debug, Clone)]
+pub struct WorkspaceSearchResult {
+    /// Repos holding this exact file, the one whose copy scored highest
+    /// first.
+    pub repos: Vec<PathBuf>,
+    /// The highest-scoring copy of the result.
+    pub result: SearchResult,
+}
+
+impl WorkspaceSearchResult {
+    /// Repo whose copy of the result scored highest.
+    #[must_use]
+    pub fn repo(&self) -> &Path {
+        &self.repos[0]
+    }
+}
+
+/// Sort results by score and merge copies of the same chunk from identical
+/// files in different repos. Results whose file hash is unknown are never
+/// merged.
+fn collapse_duplicates(
+    mut results: Vec<(PathBuf, Option<String>, SearchResult)>,
+) -> Vec<WorkspaceSearchResult> {
+    results.sort_by(|a, b| {
+        b.2.score
+            .partial_cmp(&a.2.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    let mut merged: Vec<WorkspaceSearchResult> = Vec::with_capacity(results.len());
+    let mut seen: HashMap<(String, u32, u32), usize> = HashMap::new();
+    for (repo, file_hash, result) in results {
+        if let Some(hash) = file_hash {
+            let key = (hash, result.chunk.line_start, result.chunk.line_end);
+            if let Some(&i) = seen.get(&key) {
+                if !merged[i].repos.contains(&repo) {
+                    merged[i].repos.push(repo);
+                }
+                continue;
+            }
+            seen.insert(key, merged.len());
+        }
+        merged.push(WorkspaceSearchResult {
+            repos: vec![repo],
+            result,
+        });
+    }
+    merged
+}
+
 impl Workspace {
     /// Create or open a workspace at the given config path.
     pub fn open(config_path: &Path) -> OmniResult<Self> {
@@ -184,9 +235,13 @@ impl Workspace {
     }
 
     /// Search across all linked repositories, boosting results from higher-priority repos.
-    pub fn search(&self, query: &str, limit: usize) -> OmniResult<Vec<(PathBuf, SearchResult)>> {
+    ///
+    /// A file vendored into several repos (same content hash) is returned
+    /// once, listing every repo that holds a copy, so shared library code
+    /// does not crowd out everything else.
+    pub fn search(&self, query: &str, limit: usize) -> OmniResult<Vec<WorkspaceSearchResult>> {
         let per_repo_limit = limit * 2; // fetch more per repo, then merge + truncate
-        let mut all_results: Vec<(PathBuf, SearchResult)> = Vec::new();
+        let mut all_results: Vec<(PathBuf, Option<String>, SearchResult)> = Vec::new();
 
         for (repo_path, engine) in &self.engines {
             let boost = self
@@ -200,7 +255,12 @@ impl Workspace {
                         // Scale score by priority weight so higher-priority repos
                         // surface above lower-priority ones for equal relevance.
                         r.score *= f64::from(boost);
-                        all_results.push((repo_path.clone(), r));
+                        let file_hash = engine
+                            .metadata_index()
+                            .get_file_hash(&r.file_path)
+                            .ok()
+                            .flatten();
+                        all_results.push((repo_path.clone(), file_hash, r));
                     }
                 }
                 Err(e) => {
@@ -213,15 +273,10 @@ impl Workspace {
             }
         }
 
-        // Sort by boosted score descending and truncate.
-        all_results.sort_by(|a, b| {
-            b.1.score
-                .partial_cmp(&a.1.score)
-                .unwrap_or(std::cmp::Ordering::Equal)
-        });
-        all_results.truncate(limit);
+        let mut merged = collapse_duplicates(all_results);
+        merged.truncate(limit);
 
-        Ok(all_results)
+        Ok(merged)
     }
 
     /// Get the number of linked repos.
@@ -310,6 +365,58 @@ mod tests {
         assert!((deserialized.repos[0].priority - 0.8).abs() < f32::EPSILON);
     }
 
+    #[test]
+    fn test_collapse_duplicates_merges_identical_files() {
+        let result = |path: &str, line: u32, score: f64| SearchResult {
+            chunk: crate::types::Chunk {
+                id: i64::from(line),
+                file_id: 1,
+                symbol_path: format!("shared::f{line}"),
+                kind: crate::types::ChunkKind::Function,
+                visibility: crate::types::Visibility::Public,
+                line_start: line,
+                line_end: line + 5,
+                content: String::new(),
+                doc_comment: None,
+                token_count: 10,
+                weight: 1.0,
+                vector_id: None,
+                is_summary: false,
+                is_generated: false,
+                language: None,
+                content_hash: 0,
+                complexity: crate::types::ComplexityMetrics::default(),
+            },
+            file_path: PathBuf::from(path),
+            score,
+            relevance: score,
+            score_breakdown: crate::types::ScoreBreakdown::default(),
+            owners: Vec::new(),
+        };
+        let hash = Some("abc".to_string());
+        let merged = collapse_duplicates(vec![
+            (
+                PathBuf::from("/a"),
+                hash.clone(),
+                result("lib/util.rs", 1, 0.4),
+            ),
+            (
+                PathBuf::from("/b"),
+                hash.clone(),
+                result("vendor/util.rs", 1, 0.6),
+            ),
+            (PathBuf::from("/b"), hash, result("vendor/util.rs", 20, 0.5)),
+            (PathBuf::from("/c"), None, result("lib/util.rs", 1, 0.3)),
+        ]);
+
+        assert_eq!(merged.len(), 3);
+        assert_eq!(merged[0].repos, [PathBuf::from("/b"), PathBuf::from("/a")]);
+        assert_eq!(merged[0].result.file_path, PathBuf::from("vendor/util.rs"));
+        assert_eq!(merged[1].repo(), Path::new("/b"));
+        assert_eq!(merged[1].result.chunk.line_start, 20);
+        assert_eq!(merged[2].repos, [PathBuf::from("/c")]);
+    }
+
     #[test]
     fn test_workspace_open_nonexistent() {
         let dir = tempfile::tempdir().expect("tmp");
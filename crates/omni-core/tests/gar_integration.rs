@@ -38,7 +38,8 @@ use omni_core::graph::DependencyGraph;
 use omni_core::index::MetadataIndex;
 use omni_core::search::SearchEngine;
 use omni_core::types::{
-    Chunk, ChunkKind, DependencyEdge, DependencyKind, FileInfo, Language, Symbol, Visibility,
+    Chunk, ChunkKind, ComplexityMetrics, DependencyEdge, DependencyKind, FileInfo, Language,
+    Symbol, Visibility,
 };
 use omni_core::vector::VectorIndex;
 
@@ -77,6 +78,7 @@ impl Fixture {
             enable_sparse_retrieval: false,
             cloud_api_key: None,
             quantization_mode: omni_core::embedder::quantization::QuantizationMode::None,
+            reembed_on_model_change: false,
         };
         let embedder = Embedder::degraded(&embed_cfg);
 
@@ -125,7 +127,10 @@ fn insert_symbol_with_chunk(index: &MetadataIndex, fqn: &str, content: &str) ->
         weight: 1.0,
         vector_id: None,
         is_summary: false,
+        is_generated: false,
+        language: None,
         content_hash: 0,
+        complexity: ComplexityMetrics::default(),
     };
     let chunk_id = index.insert_chunk(&chunk).expect("insert chunk");
 
@@ -180,6 +185,7 @@ fn gar_neighbours_included_when_edge_connects_anchor_to_chunk() {
             source_id: anchor_sym_id,
             target_id: neighbour_sym_id,
             kind: DependencyKind::Calls,
+            confidence: DependencyEdge::EXACT,
         })
         .expect("add Calls edge");
 
@@ -257,6 +263,7 @@ fn gar_hop_decay_score_decreases_with_distance() {
                 source_id: src,
                 target_id: tgt,
                 kind: DependencyKind::Calls,
+                confidence: DependencyEdge::EXACT,
             })
             .expect("add edge");
     }
@@ -334,6 +341,7 @@ fn gar_remove_edges_changes_neighbourhood() {
                 source_id: anchor_sym_id,
                 target_id: tgt,
                 kind: DependencyKind::Calls,
+                confidence: DependencyEdge::EXACT,
             })
             .expect("add edge");
     }
@@ -426,6 +434,7 @@ fn gar_depth_limit_excludes_deep_results() {
                 source_id: w[0],
                 target_id: w[1],
                 kind: DependencyKind::Calls,
+                confidence: DependencyEdge::EXACT,
             })
             .expect("add chain edge");
     }
@@ -488,6 +497,7 @@ fn gar_edge_type_weights_order_scores_correctly() {
             source_id: 100,
             target_id: 200,
             kind: DependencyKind::DataFlow,
+            confidence: DependencyEdge::EXACT,
         })
         .unwrap();
     // anchor --[Calls]--> sym_300
@@ -496,6 +506,7 @@ fn gar_edge_type_weights_order_scores_correctly() {
             source_id: 100,
             target_id: 300,
             kind: DependencyKind::Calls,
+            confidence: DependencyEdge::EXACT,
         })
         .unwrap();
 
@@ -556,6 +567,7 @@ fn gar_partial_edge_removal_leaves_unrelated_edges_intact() {
             source_id: 10,
             target_id: 20,
             kind: DependencyKind::Calls,
+            confidence: DependencyEdge::EXACT,
         })
         .unwrap();
     graph
@@ -563,6 +575,7 @@ fn gar_partial_edge_removal_leaves_unrelated_edges_intact() {
             source_id: 30,
             target_id: 40,
             kind: DependencyKind::DataFlow,
+            confidence: DependencyEdge::EXACT,
         })
         .unwrap();
 
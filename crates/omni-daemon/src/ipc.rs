@@ -7,12 +7,14 @@
 //!
 //! Each message is a complete JSON object terminated by `\n`.
 //! The client sends `Request` objects, the server responds with `Response` objects.
+//! Between responses the server may push `Notification` objects (no `id`),
+//! such as `fileIndexed` when a file finishes reindexing.
 
 use std::path::Path;
 use std::sync::Arc;
 
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
-use tokio::sync::Mutex;
+use tokio::sync::{broadcast, Mutex, RwLock};
 use tokio_util::sync::CancellationToken;
 
 use omni_core::Engine;
@@ -55,56 +57,201 @@ impl RepoRegistry {
     }
 }
 
-/// Derive a deterministic pipe/socket name from the repository path.
-///
-/// Normalization must match the extension's `derivePipeName()`:
-///   1. Strip `\\?\` prefix
-///   2. Backslash -> forward slash
-///   3. Lowercase
-///   4. Strip trailing separator(s)
+/// Derive a deterministic pipe/socket name from the repository path
+/// (see [`omni_core::config::daemon_pipe_name`]).
 pub fn default_pipe_name(repo_path: &Path) -> String {
-    use sha2::{Digest, Sha256};
-    let mut normalized = repo_path
-        .to_string_lossy()
-        .replace(r"\\?\", "")
-        .replace('\\', "/")
-        .to_lowercase();
-
-    // Strip trailing separator to match extension behavior
-    while normalized.ends_with('/') {
-        normalized.pop();
-    }
+    omni_core::config::daemon_pipe_name(repo_path)
+}
 
-    let mut hasher = Sha256::new();
-    hasher.update(normalized.as_bytes());
-    let hash = hex::encode(&hasher.finalize()[..6]);
+/// Notifications buffered per client before the oldest are dropped.
+const NOTIFICATION_CAPACITY: usize = 256;
 
-    #[cfg(windows)]
-    {
-        format!(r"\\.\pipe\omnicontext-{hash}")
-    }
+/// Serialized notification lines (newline-terminated) fanned out to clients.
+type NotificationSender = broadcast::Sender<Arc<str>>;
 
-    #[cfg(not(windows))]
-    {
-        let runtime_dir = std::env::var("XDG_RUNTIME_DIR").unwrap_or_else(|_| "/tmp".to_string());
-        format!("{runtime_dir}/omnicontext-{hash}.sock")
-    }
+/// Forward engine events that clients care about as JSON-RPC notifications
+/// until shutdown.
+fn spawn_notification_forwarder(
+    mut events: broadcast::Receiver<omni_core::EngineEvent>,
+    repo_path: std::path::PathBuf,
+    notifications: NotificationSender,
+    shutdown_token: CancellationToken,
+) {
+    tokio::spawn(async move {
+        loop {
+            let event = tokio::select! {
+                event = events.recv() => event,
+                () = shutdown_token.cancelled() => break,
+            };
+            let (method, params) = match event {
+                Ok(omni_core::EngineEvent::FileIndexed {
+                    path,
+                    chunks,
+                    elapsed_ms,
+                    ..
+                }) => (
+                    protocol::notifications::FILE_INDEXED,
+                    serde_json::to_value(protocol::FileIndexedParams {
+                        path: repo_path.join(path).display().to_string(),
+                        chunks,
+                        duration_ms: elapsed_ms,
+                    }),
+                ),
+                Ok(_) => continue,
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::debug!(
+                        skipped,
+                        "notification forwarder lagged behind engine events"
+                    );
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+            let line = params.and_then(|params| {
+                serde_json::to_string(&protocol::Notification::new(method, params))
+            });
+            match line {
+                // Only fails when no client is connected.
+                Ok(line) => {
+                    let _ = notifications.send(format!("{line}\n").into());
+                }
+                Err(e) => tracing::warn!(error = %e, "failed to serialize notification"),
+            }
+        }
+    });
+}
+
+/// Chunks embedded per background job step. Small enough that the write
+/// lock is released again within a few hundred milliseconds.
+const BACKGROUND_JOB_STEP: usize = 40;
+
+/// Pause between background job steps while a job is making progress.
+const BACKGROUND_JOB_PAUSE: std::time::Duration = std::time::Duration::from_millis(200);
+
+/// How often to look for background work when there is none (or when the
+/// running job cannot progress, e.g. the model is still missing).
+const BACKGROUND_JOB_IDLE: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Drive resumable background jobs (re-embedding after a model change) at
+/// low priority until shutdown.
+///
+/// Each step uses `try_write`, so it never waits in line with client
+/// requests: while anyone holds the engine the step is skipped, and keyword
+/// and semantic searches keep being served between steps.
+fn spawn_background_job_runner(engine: Arc<RwLock<Engine>>, shutdown_token: CancellationToken) {
+    tokio::spawn(async move {
+        let mut delay = BACKGROUND_JOB_PAUSE;
+        let mut last_progress = None;
+        loop {
+            tokio::select! {
+                () = tokio::time::sleep(delay) => {}
+                () = shutdown_token.cancelled() => break,
+            }
+            let Ok(mut eng) = engine.try_write() else {
+                delay = BACKGROUND_JOB_PAUSE;
+                continue;
+            };
+            let step = eng.run_background_jobs(BACKGROUND_JOB_STEP);
+            drop(eng);
+            delay = match step {
+                Ok(Some(job)) if job.state == omni_core::types::JobState::Running => {
+                    let progress = Some((job.id, job.done));
+                    let advanced = progress != last_progress;
+                    last_progress = progress;
+                    if advanced {
+                        BACKGROUND_JOB_PAUSE
+                    } else {
+                        BACKGROUND_JOB_IDLE
+                    }
+                }
+                Ok(_) => BACKGROUND_JOB_IDLE,
+                Err(e) => {
+                    tracing::warn!(error = %e, "background job step failed");
+                    BACKGROUND_JOB_IDLE
+                }
+            };
+        }
+    });
+}
+
+/// Reindex files the watcher reports changed or deleted: native events,
+/// or hash-based rescans on network filesystems (see `[watcher]`).
+fn spawn_file_watcher(
+    watcher: omni_core::watcher::FileWatcher,
+    engine: Arc<RwLock<Engine>>,
+    prefetch_cache: Arc<crate::prefetch::PrefetchCache>,
+    shutdown_token: CancellationToken,
+) {
+    let (tx, mut rx) = tokio::sync::mpsc::channel(256);
+    tokio::spawn(async move {
+        if let Err(e) = watcher.watch(tx).await {
+            tracing::warn!(error = %e, "file watcher stopped");
+        }
+    });
+    tokio::spawn(async move {
+        loop {
+            let event = tokio::select! {
+                event = rx.recv() => event,
+                () = shutdown_token.cancelled() => break,
+            };
+            let path = match event {
+                Some(
+                    omni_core::types::PipelineEvent::FileChanged { path }
+                    | omni_core::types::PipelineEvent::FileDeleted { path },
+                ) => path,
+                Some(_) => continue,
+                None => break,
+            };
+            match engine.write().await.reindex_single_file(&path) {
+                Ok((_, true, _)) => prefetch_cache.invalidate_file(&path),
+                Ok(_) => {}
+                Err(e) => {
+                    tracing::warn!(file = %path.display(), error = %e, "watcher reindex failed");
+                }
+            }
+        }
+    });
+}
+
+/// Wait for one of the engine's search slots
+/// (`search.max_concurrent_searches`). Searches share the engine through a
+/// read lock, so without this every client could start an ONNX inference at
+/// once and oversubscribe the CPU. The permit is held until dropped.
+async fn acquire_search_permit(
+    engine: &RwLock<Engine>,
+) -> Option<tokio::sync::OwnedSemaphorePermit> {
+    let slots = engine.read().await.search_slots();
+    slots.acquire_owned().await.ok()
 }
 
 /// Start the IPC server and listen for client connections.
+///
+/// Requests that only read the index (searches, graph and history queries,
+/// status) share the engine through a read lock, so a slow search does not
+/// block other clients; indexing and configuration changes take the write
+/// lock. At most `search.max_concurrent_searches` searches run at once.
 pub async fn serve(engine: Engine, pipe_name: &str) -> anyhow::Result<()> {
     // Derive the primary repo path from the engine config so the registry
     // config file lands in the same data directory as the engine index.
     let repo_path = engine.repo_path().to_path_buf();
     let repo_registry = RepoRegistry::open(&repo_path);
+    let (notifications, _) = broadcast::channel(NOTIFICATION_CAPACITY);
+    let events = engine.subscribe();
+    let file_watcher = engine.file_watcher();
 
-    let engine = Arc::new(Mutex::new(engine));
+    let engine = Arc::new(RwLock::new(engine));
     let prefetch_cache = Arc::new(crate::prefetch::PrefetchCache::default());
     let daemon_start_time = Arc::new(std::time::Instant::now());
     let performance_metrics = Arc::new(crate::metrics::PerformanceMetrics::default());
     let event_dedup = Arc::new(crate::event_dedup::EventDeduplicator::new());
     let backpressure = Arc::new(crate::backpressure::BackpressureMonitor::new(100)); // max 100 concurrent requests
     let shutdown_token = CancellationToken::new();
+    spawn_notification_forwarder(
+        events,
+        repo_path,
+        notifications.clone(),
+        shutdown_token.clone(),
+    );
 
     // Spawn periodic maintenance task — prunes expired cache entries every 60s
     {
@@ -115,11 +262,16 @@ pub async fn serve(engine: Engine, pipe_name: &str) -> anyhow::Result<()> {
             loop {
                 tokio::select! {
                     _ = interval.tick() => {
-                        let eng_guard = eng.lock().await;
+                        let eng_guard = eng.read().await;
                         let pruned = eng_guard.search_engine().result_cache().prune_expired();
                         if pruned > 0 {
                             tracing::debug!(pruned = pruned, "periodic cache maintenance: pruned expired entries");
                         }
+                        // Truncate the WAL once the [storage] interval or size
+                        // threshold is reached; edits alone never shrink it.
+                        if let Err(e) = eng_guard.metadata_index().maybe_checkpoint() {
+                            tracing::warn!(error = %e, "periodic WAL checkpoint failed");
+                        }
                         drop(eng_guard);
                     }
                     () = token.cancelled() => {
@@ -131,11 +283,26 @@ pub async fn serve(engine: Engine, pipe_name: &str) -> anyhow::Result<()> {
         });
     }
 
+    // Warm up off the accept path so clients can connect immediately;
+    // early searches share the read lock and only writes wait for it.
+    {
+        let eng = engine.clone();
+        tokio::task::spawn_blocking(move || eng.blocking_read().warm_up());
+    }
+
+    spawn_background_job_runner(engine.clone(), shutdown_token.clone());
+    spawn_file_watcher(
+        file_watcher,
+        engine.clone(),
+        prefetch_cache.clone(),
+        shutdown_token.clone(),
+    );
+
     #[cfg(windows)]
     {
         serve_named_pipe(
             engine, repo_registry, prefetch_cache, daemon_start_time, performance_metrics,
-            event_dedup, backpressure, pipe_name, shutdown_token,
+            event_dedup, backpressure, notifications, pipe_name, shutdown_token,
         )
         .await
     }
@@ -144,7 +311,7 @@ pub async fn serve(engine: Engine, pipe_name: &str) -> anyhow::Result<()> {
     {
         serve_unix_socket(
             engine, repo_registry, prefetch_cache, daemon_start_time, performance_metrics,
-            event_dedup, backpressure, pipe_name, shutdown_token,
+            event_dedup, backpressure, notifications, pipe_name, shutdown_token,
         )
         .await
     }
@@ -156,13 +323,14 @@ pub async fn serve(engine: Engine, pipe_name: &str) -> anyhow::Result<()> {
 
 #[cfg(windows)]
 async fn serve_named_pipe(
-    engine: Arc<Mutex<Engine>>,
+    engine: Arc<RwLock<Engine>>,
     repo_registry: RepoRegistry,
     prefetch_cache: Arc<crate::prefetch::PrefetchCache>,
     daemon_start_time: Arc<std::time::Instant>,
     performance_metrics: Arc<crate::metrics::PerformanceMetrics>,
     event_dedup: Arc<crate::event_dedup::EventDeduplicator>,
     backpressure: Arc<crate::backpressure::BackpressureMonitor>,
+    notifications: NotificationSender,
     pipe_name: &str,
     shutdown_token: CancellationToken,
 ) -> anyhow::Result<()> {
@@ -195,10 +363,12 @@ async fn serve_named_pipe(
         let dedup = event_dedup.clone();
         let bp = backpressure.clone();
         let token = shutdown_token.clone();
+        let notify = notifications.subscribe();
         tokio::spawn(async move {
             let (reader, writer) = tokio::io::split(server);
             if let Err(e) = handle_client(
-                engine, registry, cache, start_time, metrics, dedup, bp, token, reader, writer,
+                engine, registry, cache, start_time, metrics, dedup, bp, token, notify, reader,
+                writer,
             )
             .await
             {
@@ -215,13 +385,14 @@ async fn serve_named_pipe(
 
 #[cfg(not(windows))]
 async fn serve_unix_socket(
-    engine: Arc<Mutex<Engine>>,
+    engine: Arc<RwLock<Engine>>,
     repo_registry: RepoRegistry,
     prefetch_cache: Arc<crate::prefetch::PrefetchCache>,
     daemon_start_time: Arc<std::time::Instant>,
     performance_metrics: Arc<crate::metrics::PerformanceMetrics>,
     event_dedup: Arc<crate::event_dedup::EventDeduplicator>,
     backpressure: Arc<crate::backpressure::BackpressureMonitor>,
+    notifications: NotificationSender,
     socket_path: &str,
     shutdown_token: CancellationToken,
 ) -> anyhow::Result<()> {
@@ -247,9 +418,10 @@ async fn serve_unix_socket(
                 let dedup = event_dedup.clone();
                 let bp = backpressure.clone();
                 let token = shutdown_token.clone();
+                let notify = notifications.subscribe();
                 tokio::spawn(async move {
                     let (reader, writer) = tokio::io::split(stream);
-                    if let Err(e) = handle_client(engine, registry, cache, start_time, metrics, dedup, bp, token, reader, writer).await
+                    if let Err(e) = handle_client(engine, registry, cache, start_time, metrics, dedup, bp, token, notify, reader, writer).await
                     {
                         tracing::warn!(error = %e, "client handler error");
                     }
@@ -272,9 +444,10 @@ async fn serve_unix_socket(
 ///
 /// Reads newline-delimited JSON-RPC requests (optionally compressed),
 /// dispatches them to the engine, and writes JSON-RPC responses back
-/// (with compression for large responses).
+/// (with compression for large responses). Notifications received while
+/// idle are written as they arrive.
 async fn handle_client<R, W>(
-    engine: Arc<Mutex<Engine>>,
+    engine: Arc<RwLock<Engine>>,
     repo_registry: RepoRegistry,
     prefetch_cache: Arc<crate::prefetch::PrefetchCache>,
     daemon_start_time: Arc<std::time::Instant>,
@@ -282,6 +455,7 @@ async fn handle_client<R, W>(
     event_dedup: Arc<crate::event_dedup::EventDeduplicator>,
     backpressure: Arc<crate::backpressure::BackpressureMonitor>,
     shutdown_token: CancellationToken,
+    notifications: broadcast::Receiver<Arc<str>>,
     reader: R,
     mut writer: W,
 ) -> anyhow::Result<()>
@@ -293,8 +467,28 @@ where
     const MAX_LINE_LEN: usize = 10 * 1024 * 1024;
     let buf_reader = BufReader::new(reader);
     let mut lines = buf_reader.lines();
+    let mut notifications = Some(notifications);
 
-    while let Some(line_result) = lines.next_line().await.transpose() {
+    loop {
+        let line_result = tokio::select! {
+            line = lines.next_line() => match line.transpose() {
+                Some(line) => line,
+                None => break,
+            },
+            notification = recv_notification(&mut notifications) => {
+                match notification {
+                    Ok(line) => {
+                        writer.write_all(line.as_bytes()).await?;
+                        writer.flush().await?;
+                    }
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        tracing::debug!(skipped, "client lagged behind notifications");
+                    }
+                    Err(broadcast::error::RecvError::Closed) => notifications = None,
+                }
+                continue;
+            }
+        };
         let line = match line_result {
             Ok(l) => l,
             Err(e) => {
@@ -360,9 +554,19 @@ where
     Ok(())
 }
 
+/// Next notification line, or never once the channel has closed.
+async fn recv_notification(
+    notifications: &mut Option<broadcast::Receiver<Arc<str>>>,
+) -> Result<Arc<str>, broadcast::error::RecvError> {
+    match notifications {
+        Some(receiver) => receiver.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
 /// Dispatch a JSON-RPC request to the appropriate handler.
 async fn dispatch(
-    engine: Arc<Mutex<Engine>>,
+    engine: Arc<RwLock<Engine>>,
     repo_registry: RepoRegistry,
     prefetch_cache: Arc<crate::prefetch::PrefetchCache>,
     daemon_start_time: Arc<std::time::Instant>,
@@ -574,6 +778,17 @@ async fn dispatch(
             handle_remove_repo(repo_registry.clone(), params).await
         }
 
+        "workspace/search" => {
+            let params: protocol::WorkspaceSearchParams = match parse_params(&req) {
+                Ok(p) => p,
+                Err(r) => return r,
+            };
+            let result =
+                handle_workspace_search(engine.clone(), repo_registry.clone(), params).await;
+            performance_metrics.record_search_latency(start.elapsed());
+            result
+        }
+
         // Performance Controls
         "embedder/get_metrics" => {
             handle_embedder_metrics(engine.clone(), daemon_start_time.clone()).await
@@ -646,18 +861,18 @@ fn parse_params<T: serde::de::DeserializeOwned>(req: &protocol::Request) -> Resu
 // Handler implementations
 // ---------------------------------------------------------------------------
 
-async fn handle_status(engine: Arc<Mutex<Engine>>) -> Result<serde_json::Value, (i32, String)> {
-    let eng = engine.lock().await;
+async fn handle_status(engine: Arc<RwLock<Engine>>) -> Result<serde_json::Value, (i32, String)> {
+    let eng = engine.read().await;
     eng.status()
         .map(|s| serde_json::to_value(s).unwrap_or_default())
         .map_err(|e| (error_codes::ENGINE_ERROR, format!("status failed: {e}")))
 }
 
 async fn handle_system_status(
-    engine: Arc<Mutex<Engine>>,
+    engine: Arc<RwLock<Engine>>,
     daemon_start_time: Arc<std::time::Instant>,
 ) -> Result<serde_json::Value, (i32, String)> {
-    let eng = engine.lock().await;
+    let eng = engine.read().await;
 
     // Get engine status for file/chunk counts
     let status = eng
@@ -693,6 +908,7 @@ async fn handle_system_status(
         daemon_uptime_seconds,
         files_indexed: status.files_indexed,
         chunks_indexed: status.chunks_indexed,
+        degraded_reasons: status.degraded_reasons,
     };
 
     serde_json::to_value(response).map_err(|e| {
@@ -704,10 +920,10 @@ async fn handle_system_status(
 }
 
 async fn handle_performance_metrics(
-    engine: Arc<Mutex<Engine>>,
+    engine: Arc<RwLock<Engine>>,
     performance_metrics: Arc<crate::metrics::PerformanceMetrics>,
 ) -> Result<serde_json::Value, (i32, String)> {
-    let eng = engine.lock().await;
+    let eng = engine.read().await;
 
     // Get engine status for embedding coverage
     let status = eng
@@ -727,9 +943,9 @@ async fn handle_performance_metrics(
     let search_latency_p95_ms = performance_metrics.get_latency_percentile(0.95);
     let search_latency_p99_ms = performance_metrics.get_latency_percentile(0.99);
 
-    // Use vector index memory as a concrete, non-placeholder memory signal.
+    // Engine-estimated heap across vectors, graphs, and parse caches.
     #[allow(clippy::cast_possible_truncation)]
-    let memory_usage_bytes = status.vector_memory_bytes as u64;
+    let memory_usage_bytes = status.memory.total_bytes as u64;
     performance_metrics.update_memory_usage(memory_usage_bytes);
     let peak_memory_usage_bytes = performance_metrics.get_peak_memory_bytes();
 
@@ -755,7 +971,7 @@ async fn handle_performance_metrics(
 }
 
 async fn handle_search(
-    engine: Arc<Mutex<Engine>>,
+    engine: Arc<RwLock<Engine>>,
     params: protocol::SearchParams,
 ) -> Result<serde_json::Value, (i32, String)> {
     // Validate query
@@ -772,23 +988,45 @@ async fn handle_search(
         ));
     }
     let limit = params.limit.clamp(1, 200); // Cap at 200, minimum 1
-    let eng = engine.lock().await;
+    let _permit = acquire_search_permit(&engine).await;
+    let eng = engine.read().await;
     eng.search_with_active_content(&params.query, limit, params.active_file_content.as_deref())
         .map(|results| {
-            let entries: Vec<serde_json::Value> = results
-                .iter()
-                .map(|r| {
-                    serde_json::json!({
-                        "file": r.file_path.display().to_string(),
-                        "symbol": r.chunk.symbol_path,
-                        "kind": format!("{:?}", r.chunk.kind),
-                        "score": r.score,
-                        "line_start": r.chunk.line_start,
-                        "line_end": r.chunk.line_end,
-                        "content": r.chunk.content,
-                    })
+            let record = |r: &omni_core::types::SearchResult| {
+                serde_json::json!({
+                    "file": r.file_path.display().to_string(),
+                    "symbol": r.chunk.symbol_path,
+                    "kind": format!("{:?}", r.chunk.kind),
+                    "score": r.score,
+                    "relevance": r.relevance,
+                    "line_start": r.chunk.line_start,
+                    "line_end": r.chunk.line_end,
+                    "content": r.chunk.content,
                 })
-                .collect();
+            };
+            if params.group_by_file {
+                let groups = omni_core::search::group_by_file(
+                    results,
+                    omni_core::search::grouping::DEFAULT_CHUNKS_PER_FILE,
+                );
+                let files: Vec<serde_json::Value> = groups
+                    .iter()
+                    .map(|g| {
+                        serde_json::json!({
+                            "file": g.file_path.display().to_string(),
+                            "score": g.score,
+                            "relevance": g.relevance,
+                            "matches": g.match_count,
+                            "chunks": g.chunks.iter().map(record).collect::<Vec<_>>(),
+                        })
+                    })
+                    .collect();
+                return serde_json::json!({
+                    "count": files.len(),
+                    "files": files,
+                });
+            }
+            let entries: Vec<serde_json::Value> = results.iter().map(record).collect();
             serde_json::json!({
                 "count": entries.len(),
                 "results": entries,
@@ -798,7 +1036,7 @@ async fn handle_search(
 }
 
 async fn handle_context_window(
-    engine: Arc<Mutex<Engine>>,
+    engine: Arc<RwLock<Engine>>,
     params: protocol::ContextWindowParams,
 ) -> Result<serde_json::Value, (i32, String)> {
     // Validate query
@@ -815,7 +1053,8 @@ async fn handle_context_window(
         ));
     }
     let limit = params.limit.clamp(1, 200); // Cap at 200, minimum 1
-    let eng = engine.lock().await;
+    let _permit = acquire_search_permit(&engine).await;
+    let eng = engine.read().await;
     eng.search_context_window(&params.query, limit, params.token_budget)
         .map(|ctx| {
             serde_json::json!({
@@ -835,7 +1074,7 @@ async fn handle_context_window(
 
 #[allow(clippy::too_many_lines)]
 async fn handle_preflight(
-    engine: Arc<Mutex<Engine>>,
+    engine: Arc<RwLock<Engine>>,
     prefetch_cache: Arc<crate::prefetch::PrefetchCache>,
     params: protocol::PreflightParams,
     start: std::time::Instant,
@@ -881,7 +1120,8 @@ async fn handle_preflight(
     }
 
     // Cache miss or no active_file: perform fresh search
-    let eng = engine.lock().await;
+    let _permit = acquire_search_permit(&engine).await;
+    let eng = engine.read().await;
 
     // Build the context window from the user's prompt
     let ctx = eng
@@ -998,10 +1238,10 @@ fn parse_cached_meta(context: &str) -> (usize, u32) {
 }
 
 async fn handle_module_map(
-    engine: Arc<Mutex<Engine>>,
+    engine: Arc<RwLock<Engine>>,
     _params: protocol::ModuleMapParams,
 ) -> Result<serde_json::Value, (i32, String)> {
-    let eng = engine.lock().await;
+    let eng = engine.read().await;
     let index = eng.metadata_index();
 
     // Build module map from indexed files
@@ -1056,9 +1296,9 @@ async fn handle_module_map(
 
 /// IDX-1 fix: Spawn indexing in background so the mutex is not held for minutes.
 /// Returns immediately with a "started" acknowledgment.
-async fn handle_index(engine: Arc<Mutex<Engine>>) -> Result<serde_json::Value, (i32, String)> {
+async fn handle_index(engine: Arc<RwLock<Engine>>) -> Result<serde_json::Value, (i32, String)> {
     // Quick check: if we can't even lock the engine, another index is running
-    let eng = engine.try_lock();
+    let eng = engine.try_write();
     if eng.is_err() {
         return Err((
             error_codes::ENGINE_ERROR,
@@ -1070,7 +1310,7 @@ async fn handle_index(engine: Arc<Mutex<Engine>>) -> Result<serde_json::Value, (
     // Spawn the actual indexing in background so this handler returns immediately
     let engine_bg = engine.clone();
     tokio::spawn(async move {
-        let mut eng = engine_bg.lock().await;
+        let mut eng = engine_bg.write().await;
         let start = std::time::Instant::now();
         match eng.run_index(false).await {
             Ok(result) => {
@@ -1133,7 +1373,7 @@ fn validate_ide_file_path(file_path: &str, engine: &Engine) -> Result<(), (i32,
 /// On `text_edited` events, also triggers incremental re-indexing of the changed file.
 #[allow(clippy::unused_async)]
 async fn handle_ide_event(
-    engine: Arc<Mutex<Engine>>,
+    engine: Arc<RwLock<Engine>>,
     prefetch_cache: Arc<crate::prefetch::PrefetchCache>,
     event_dedup: Arc<crate::event_dedup::EventDeduplicator>,
     params: protocol::IdeEventParams,
@@ -1146,7 +1386,7 @@ async fn handle_ide_event(
 
     // IDE-1: Validate file path is inside repo root
     {
-        let eng_guard = engine.lock().await;
+        let eng_guard = engine.read().await;
         validate_ide_file_path(&params.file_path, &eng_guard)?;
     }
 
@@ -1221,7 +1461,7 @@ async fn handle_ide_event(
                 // Re-index the changed file, then invalidate the cache
                 let abs_path = std::path::PathBuf::from(&file_path);
                 {
-                    let mut engine_guard = eng.lock().await;
+                    let mut engine_guard = eng.write().await;
                     match engine_guard.reindex_single_file(&abs_path) {
                         Ok((stats, changed, delta)) => {
                             if changed {
@@ -1270,14 +1510,15 @@ async fn handle_ide_event(
 /// Background pre-fetch: search for context relevant to the given file
 /// and store it in the cache so subsequent preflight requests hit the cache.
 async fn prefetch_file_context(
-    engine: Arc<Mutex<Engine>>,
+    engine: Arc<RwLock<Engine>>,
     cache: Arc<crate::prefetch::PrefetchCache>,
     file_path: &str,
 ) {
     use std::fmt::Write;
     let start = std::time::Instant::now();
 
-    let eng = engine.lock().await;
+    let _permit = acquire_search_permit(&engine).await;
+    let eng = engine.read().await;
     let query = format!("file:{file_path}");
     match eng.search_context_window(&query, 10, Some(4096)) {
         Ok(ctx) => {
@@ -1311,14 +1552,15 @@ async fn prefetch_file_context(
 
 /// Background pre-fetch: search for context relevant to a specific symbol.
 async fn prefetch_symbol_context(
-    engine: Arc<Mutex<Engine>>,
+    engine: Arc<RwLock<Engine>>,
     cache: Arc<crate::prefetch::PrefetchCache>,
     file_path: &str,
     symbol: &str,
 ) {
     let start = std::time::Instant::now();
 
-    let eng = engine.lock().await;
+    let _permit = acquire_search_permit(&engine).await;
+    let eng = engine.read().await;
     match eng.search_context_window(symbol, 10, Some(4096)) {
         Ok(ctx) => {
             let rendered = ctx.render();
@@ -1410,7 +1652,7 @@ async fn handle_update_config(
 /// Handle request to clear the index.
 /// CI-1 fix: requires confirmation token "CONFIRM_CLEAR" to proceed.
 async fn handle_clear_index(
-    engine: Arc<Mutex<Engine>>,
+    engine: Arc<RwLock<Engine>>,
     params: protocol::ClearIndexParams,
 ) -> Result<serde_json::Value, (i32, String)> {
     // CI-1: Require confirmation token for destructive operation
@@ -1424,7 +1666,7 @@ async fn handle_clear_index(
         }
     }
 
-    let mut eng = engine.lock().await;
+    let mut eng = engine.write().await;
 
     // Clear the index
     eng.clear_index().map_err(|e| {
@@ -1448,9 +1690,9 @@ async fn handle_clear_index(
 
 /// Handle request for reranker metrics.
 async fn handle_reranker_metrics(
-    engine: Arc<Mutex<Engine>>,
+    engine: Arc<RwLock<Engine>>,
 ) -> Result<serde_json::Value, (i32, String)> {
-    let eng = engine.lock().await;
+    let eng = engine.read().await;
 
     let reranker = eng.reranker();
     let enabled = reranker.is_available();
@@ -1486,9 +1728,9 @@ async fn handle_reranker_metrics(
 
 /// Handle request for graph metrics.
 async fn handle_graph_metrics(
-    engine: Arc<Mutex<Engine>>,
+    engine: Arc<RwLock<Engine>>,
 ) -> Result<serde_json::Value, (i32, String)> {
-    let eng = engine.lock().await;
+    let eng = engine.read().await;
 
     let status = eng.status().map_err(|e| {
         (
@@ -1531,7 +1773,7 @@ async fn handle_graph_metrics(
 
 /// Handle request for search intent classification.
 async fn handle_search_intent(
-    _engine: Arc<Mutex<Engine>>,
+    _engine: Arc<RwLock<Engine>>,
     params: protocol::SearchIntentParams,
 ) -> Result<serde_json::Value, (i32, String)> {
     // Use the real QueryIntent classifier from omni-core
@@ -1560,11 +1802,11 @@ async fn handle_search_intent(
 
 /// Handle request for resilience status (circuit breakers, health, dedup, backpressure).
 async fn handle_resilience_status(
-    engine: Arc<Mutex<Engine>>,
+    engine: Arc<RwLock<Engine>>,
     event_dedup: Arc<crate::event_dedup::EventDeduplicator>,
     backpressure: Arc<crate::backpressure::BackpressureMonitor>,
 ) -> Result<serde_json::Value, (i32, String)> {
-    let eng = engine.lock().await;
+    let eng = engine.read().await;
 
     // Get circuit breaker states
     let embedder_cb = eng.embedder_breaker();
@@ -1670,10 +1912,10 @@ async fn handle_resilience_status(
 
 /// Handle request to reset circuit breakers.
 async fn handle_reset_circuit_breaker(
-    engine: Arc<Mutex<Engine>>,
+    engine: Arc<RwLock<Engine>>,
     params: protocol::ResetCircuitBreakerParams,
 ) -> Result<serde_json::Value, (i32, String)> {
-    let eng = engine.lock().await;
+    let eng = engine.read().await;
 
     match params.subsystem.as_str() {
         "embedder" => {
@@ -1719,7 +1961,7 @@ async fn handle_reset_circuit_breaker(
 
 /// Handle request for commit context for a file.
 async fn handle_commit_context(
-    engine: Arc<Mutex<Engine>>,
+    engine: Arc<RwLock<Engine>>,
     params: protocol::CommitContextParams,
 ) -> Result<serde_json::Value, (i32, String)> {
     // Validate file_path
@@ -1729,7 +1971,7 @@ async fn handle_commit_context(
             "file_path must not be empty".to_string(),
         ));
     }
-    let eng = engine.lock().await;
+    let eng = engine.read().await;
 
     let limit = params.limit.clamp(1, 100); // Cap commit count, minimum 1
 
@@ -1774,9 +2016,9 @@ async fn handle_commit_context(
 
 /// Handle request to index commit history.
 async fn handle_index_commits(
-    engine: Arc<Mutex<Engine>>,
+    engine: Arc<RwLock<Engine>>,
 ) -> Result<serde_json::Value, (i32, String)> {
-    let eng = engine.lock().await;
+    let eng = engine.write().await;
 
     let commits_indexed = eng.index_commit_history().map_err(|e| {
         (
@@ -1799,10 +2041,10 @@ async fn handle_index_commits(
 
 /// Handle request for architectural context (N-hop neighborhood).
 async fn handle_architectural_context(
-    engine: Arc<Mutex<Engine>>,
+    engine: Arc<RwLock<Engine>>,
     params: protocol::ArchitecturalContextParams,
 ) -> Result<serde_json::Value, (i32, String)> {
-    let eng = engine.lock().await;
+    let eng = engine.read().await;
 
     let file_path = std::path::PathBuf::from(&params.file_path);
 
@@ -1847,9 +2089,9 @@ async fn handle_architectural_context(
 
 /// Handle request to find circular dependencies.
 async fn handle_find_cycles(
-    engine: Arc<Mutex<Engine>>,
+    engine: Arc<RwLock<Engine>>,
 ) -> Result<serde_json::Value, (i32, String)> {
-    let eng = engine.lock().await;
+    let eng = engine.read().await;
 
     // Use symbol-level graph for cycle detection
     let cycles = eng.dep_graph().find_cycles().map_err(|e| {
@@ -1873,12 +2115,12 @@ async fn handle_find_cycles(
 /// Returns every repo in the workspace registry — not just the primary repo
 /// the daemon was launched with. Repos are sorted by priority descending.
 async fn handle_list_repos(
-    engine: Arc<Mutex<Engine>>,
+    engine: Arc<RwLock<Engine>>,
     repo_registry: RepoRegistry,
 ) -> Result<serde_json::Value, (i32, String)> {
     // Get the primary repo path from the live engine for the "active" flag.
     let primary_path = {
-        let eng = engine.lock().await;
+        let eng = engine.read().await;
         eng.repo_path().to_path_buf()
     };
 
@@ -1899,7 +2141,7 @@ async fn handle_list_repos(
     // If the registry is empty (first run before any workspace/add_repo call),
     // surface the primary repo so clients always get at least one entry.
     if repos.is_empty() {
-        let eng = engine.lock().await;
+        let eng = engine.read().await;
         let status = eng.status().map_err(|e| {
             (
                 error_codes::ENGINE_ERROR,
@@ -2023,15 +2265,67 @@ async fn handle_remove_repo(
     }
 }
 
+/// Handle a search across every repository in the workspace registry.
+///
+/// Results are priority-weighted and merged by
+/// [`omni_core::workspace::Workspace::search`]; a file vendored into
+/// several repos comes back once, with every repo holding a copy in
+/// `repos`.
+async fn handle_workspace_search(
+    engine: Arc<RwLock<Engine>>,
+    repo_registry: RepoRegistry,
+    params: protocol::WorkspaceSearchParams,
+) -> Result<serde_json::Value, (i32, String)> {
+    if params.query.trim().is_empty() {
+        return Err((
+            error_codes::INVALID_PARAMS,
+            "query must not be empty".to_string(),
+        ));
+    }
+    if params.query.len() > 10_000 {
+        return Err((
+            error_codes::INVALID_PARAMS,
+            "query exceeds maximum length of 10000 characters".to_string(),
+        ));
+    }
+    let limit = params.limit.clamp(1, 200);
+    let _permit = acquire_search_permit(&engine).await;
+    let ws = repo_registry.0.lock().await;
+    let results = ws.search(&params.query, limit).map_err(|e| {
+        (
+            error_codes::ENGINE_ERROR,
+            format!("workspace search failed: {e}"),
+        )
+    })?;
+    let results: Vec<serde_json::Value> = results
+        .iter()
+        .map(|r| {
+            serde_json::json!({
+                "repo": r.repo().to_string_lossy(),
+                "repos": r.repos.iter().map(|p| p.to_string_lossy()).collect::<Vec<_>>(),
+                "file": r.result.file_path.display().to_string(),
+                "symbol": r.result.chunk.symbol_path,
+                "kind": format!("{:?}", r.result.chunk.kind),
+                "score": r.result.score,
+                "relevance": r.result.relevance,
+                "line_start": r.result.chunk.line_start,
+                "line_end": r.result.chunk.line_end,
+                "content": r.result.chunk.content,
+            })
+        })
+        .collect();
+    Ok(serde_json::json!({ "results": results }))
+}
+
 // Performance control handlers
 // ---------------------------------------------------------------------------
 
 /// Handle request for embedder metrics.
 async fn handle_embedder_metrics(
-    engine: Arc<Mutex<Engine>>,
+    engine: Arc<RwLock<Engine>>,
     daemon_start_time: Arc<std::time::Instant>,
 ) -> Result<serde_json::Value, (i32, String)> {
-    let eng = engine.lock().await;
+    let eng = engine.read().await;
 
     let embedder = eng.embedder();
     let breaker_stats = eng.embedder_breaker().stats();
@@ -2082,14 +2376,14 @@ async fn handle_embedder_metrics(
 /// `quantization_mode` and `batch_timeout_ms` are acknowledged but have no runtime effect
 /// — quantization requires model reload and timeout is fixed at the session pool level.
 async fn handle_configure_embedder(
-    engine: Arc<Mutex<Engine>>,
+    engine: Arc<RwLock<Engine>>,
     params: protocol::ConfigureEmbedderParams,
 ) -> Result<serde_json::Value, (i32, String)> {
     // Design: only batch_size can be mutated at runtime because it is read
     // per-flush from config.embedding.batch_size.  Quantization mode and
     // batch_timeout_ms require a model reload — document them as pending
     // and return the current effective values so callers can verify.
-    let mut eng = engine.lock().await;
+    let mut eng = engine.write().await;
 
     let mut applied = serde_json::Map::new();
     let mut pending = serde_json::Map::new();
@@ -2143,11 +2437,11 @@ async fn handle_configure_embedder(
 
 /// Handle request for index pool metrics.
 async fn handle_index_pool_metrics(
-    engine: Arc<Mutex<Engine>>,
+    engine: Arc<RwLock<Engine>>,
     backpressure: Arc<crate::backpressure::BackpressureMonitor>,
     performance_metrics: Arc<crate::metrics::PerformanceMetrics>,
 ) -> Result<serde_json::Value, (i32, String)> {
-    let eng = engine.lock().await;
+    let eng = engine.read().await;
     let breaker_stats = eng.index_breaker().stats();
     let bp_stats = backpressure.stats();
     let status = eng.status().map_err(|e| {
@@ -2179,9 +2473,9 @@ async fn handle_index_pool_metrics(
 
 /// Handle request for compression statistics.
 async fn handle_compression_stats(
-    engine: Arc<Mutex<Engine>>,
+    engine: Arc<RwLock<Engine>>,
 ) -> Result<serde_json::Value, (i32, String)> {
-    let eng = engine.lock().await;
+    let eng = engine.read().await;
     let status = eng.status().map_err(|e| {
         (
             error_codes::ENGINE_ERROR,
@@ -2203,10 +2497,10 @@ async fn handle_compression_stats(
 // ---------------------------------------------------------------------------
 
 async fn handle_co_changes(
-    engine: Arc<Mutex<Engine>>,
+    engine: Arc<RwLock<Engine>>,
     params: protocol::CoChangeParams,
 ) -> Result<serde_json::Value, (i32, String)> {
-    let eng = engine.lock().await;
+    let eng = engine.read().await;
     let index = eng.metadata_index();
     // Clamp min_frequency: protocol uses 0.0–1.0 scale, convert to count (×10, min 1, max 100)
     let min_freq = ((params.min_frequency * 10.0).clamp(1.0, 100.0)) as usize;
@@ -2239,7 +2533,7 @@ async fn handle_co_changes(
 }
 
 async fn handle_audit_plan(
-    engine: Arc<Mutex<Engine>>,
+    engine: Arc<RwLock<Engine>>,
     params: protocol::AuditPlanParams,
 ) -> Result<serde_json::Value, (i32, String)> {
     // Validate plan text
@@ -2255,7 +2549,7 @@ async fn handle_audit_plan(
             "plan exceeds maximum length of 500000 characters".to_string(),
         ));
     }
-    let eng = engine.lock().await;
+    let eng = engine.read().await;
     let auditor = omni_core::plan_auditor::PlanAuditor::new(&eng);
     let max_depth = params.max_depth.unwrap_or(3).clamp(1, 20);
 
@@ -2279,7 +2573,7 @@ async fn handle_audit_plan(
 /// query_id→intent mapping table is not yet wired; a follow-up can
 /// look up the originating search call and pass the real intent.
 async fn handle_search_feedback(
-    engine: Arc<Mutex<Engine>>,
+    engine: Arc<RwLock<Engine>>,
     params: protocol::SearchFeedbackParams,
 ) -> Result<serde_json::Value, (i32, String)> {
     use omni_core::search::feedback::FeedbackEvent;
@@ -2293,7 +2587,7 @@ async fn handle_search_feedback(
         "search feedback received"
     );
 
-    let eng = engine.lock().await;
+    let eng = engine.read().await;
     eng.feedback_collector().record_feedback(&FeedbackEvent {
         intent: QueryIntent::Unknown,
         result_position: params.rank,
@@ -2333,9 +2627,58 @@ mod tests {
         Engine::new(&temp_dir).unwrap()
     }
 
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_file_indexed_notification_reaches_client() {
+        let engine = create_test_engine();
+        let repo_path = engine.repo_path().to_path_buf();
+        let events = engine.subscribe();
+        let engine = Arc::new(RwLock::new(engine));
+        let (notifications, _) = broadcast::channel(NOTIFICATION_CAPACITY);
+        let token = CancellationToken::new();
+        spawn_notification_forwarder(
+            events,
+            repo_path.clone(),
+            notifications.clone(),
+            token.clone(),
+        );
+
+        let (client, server) = tokio::io::duplex(64 * 1024);
+        let (reader, writer) = tokio::io::split(server);
+        tokio::spawn(handle_client(
+            engine.clone(),
+            RepoRegistry::open(&repo_path),
+            Arc::new(crate::prefetch::PrefetchCache::default()),
+            Arc::new(std::time::Instant::now()),
+            Arc::new(crate::metrics::PerformanceMetrics::default()),
+            Arc::new(crate::event_dedup::EventDeduplicator::new()),
+            Arc::new(crate::backpressure::BackpressureMonitor::new(100)),
+            token.clone(),
+            notifications.subscribe(),
+            reader,
+            writer,
+        ));
+
+        let file = repo_path.join("test.rs");
+        engine.write().await.reindex_single_file(&file).unwrap();
+
+        let mut lines = BufReader::new(client).lines();
+        let line = tokio::time::timeout(Duration::from_secs(10), lines.next_line())
+            .await
+            .expect("notification within 10s")
+            .unwrap()
+            .unwrap();
+        let notification: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(notification["method"], "fileIndexed");
+        assert!(notification.get("id").is_none(), "{notification}");
+        assert_eq!(notification["params"]["path"], file.display().to_string());
+        assert!(notification["params"]["chunks"].as_u64() > Some(0));
+        assert!(notification["params"]["durationMs"].is_u64());
+        token.cancel();
+    }
+
     #[tokio::test]
     async fn test_preflight_cache_miss() {
-        let engine = Arc::new(Mutex::new(create_test_engine()));
+        let engine = Arc::new(RwLock::new(create_test_engine()));
         let cache = Arc::new(crate::prefetch::PrefetchCache::default());
 
         let params = protocol::PreflightParams {
@@ -2366,7 +2709,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_preflight_cache_hit() {
-        let engine = Arc::new(Mutex::new(create_test_engine()));
+        let engine = Arc::new(RwLock::new(create_test_engine()));
         let cache = Arc::new(crate::prefetch::PrefetchCache::default());
 
         let params = protocol::PreflightParams {
@@ -2403,7 +2746,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_preflight_no_active_file() {
-        let engine = Arc::new(Mutex::new(create_test_engine()));
+        let engine = Arc::new(RwLock::new(create_test_engine()));
         let cache = Arc::new(crate::prefetch::PrefetchCache::default());
 
         let params = protocol::PreflightParams {
@@ -2432,7 +2775,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_preflight_cache_expiry() {
-        let engine = Arc::new(Mutex::new(create_test_engine()));
+        let engine = Arc::new(RwLock::new(create_test_engine()));
         // Create cache with very short TTL (10ms)
         let cache = Arc::new(crate::prefetch::PrefetchCache::new(
             100,
@@ -2472,7 +2815,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_preflight_different_files() {
-        let engine = Arc::new(Mutex::new(create_test_engine()));
+        let engine = Arc::new(RwLock::new(create_test_engine()));
         let cache = Arc::new(crate::prefetch::PrefetchCache::default());
 
         let params1 = protocol::PreflightParams {
@@ -2699,9 +3042,49 @@ mod tests {
         assert!(matches!(copy, protocol::FeedbackAction::Copy));
     }
 
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_workspace_search_collapses_shared_files() {
+        let engine = Arc::new(RwLock::new(create_test_engine()));
+        let dir = tempfile::tempdir().expect("tempdir");
+        let shared = "def rotate_signing_keys(store):\n    return store\n";
+        let mut repos = Vec::new();
+        for name in ["api", "worker"] {
+            let repo = dir.path().join(name);
+            std::fs::create_dir_all(repo.join("vendor")).expect("create repo");
+            std::fs::write(repo.join("vendor/keys.py"), shared).expect("write shared");
+            let mut indexer = Engine::new(&repo).expect("engine");
+            indexer.run_index(false).await.expect("index");
+            repos.push(repo);
+        }
+        let registry = RepoRegistry::open(&dir.path().join("primary"));
+        for repo in &repos {
+            registry
+                .0
+                .lock()
+                .await
+                .link_repo(repo, None, 0.5)
+                .expect("link");
+        }
+
+        let params = protocol::WorkspaceSearchParams {
+            query: "rotate_signing_keys".to_string(),
+            limit: 10,
+        };
+        let value = handle_workspace_search(engine, registry, params)
+            .await
+            .expect("workspace search");
+        let results = value["results"].as_array().expect("results");
+        let shared_hits: Vec<_> = results
+            .iter()
+            .filter(|r| r["file"] == "vendor/keys.py")
+            .collect();
+        assert_eq!(shared_hits.len(), 1, "{value}");
+        assert_eq!(shared_hits[0]["repos"].as_array().map(Vec::len), Some(2));
+    }
+
     #[tokio::test]
     async fn test_feedback_handler_returns_empty_object() {
-        let engine = Arc::new(Mutex::new(create_test_engine()));
+        let engine = Arc::new(RwLock::new(create_test_engine()));
 
         let params = protocol::SearchFeedbackParams {
             query_id: "test-query-001".to_string(),
@@ -7,7 +7,9 @@
 //! ## Architecture
 //!
 //! The daemon owns a single `Engine` instance and multiplexes client
-//! requests through a `tokio::sync::Mutex`. Each connected client
+//! requests through a `tokio::sync::RwLock`: searches and other queries run
+//! concurrently under the read lock, indexing takes the write lock, and a
+//! semaphore bounds concurrent model inference. Each connected client
 //! (typically the VS Code extension) sends JSON-RPC requests and
 //! receives JSON-RPC responses over the pipe.
 //!
@@ -78,6 +80,11 @@ struct Args {
     /// Named pipe/socket name override.
     #[arg(long)]
     pipe_name: Option<String>,
+
+    /// Searches allowed to run model inference at once (overrides
+    /// `search.max_concurrent_searches`).
+    #[arg(long)]
+    max_concurrent_searches: Option<usize>,
 }
 
 #[tokio::main]
@@ -119,6 +126,19 @@ async fn main() -> Result<()> {
     // Initialize the core engine
     let mut engine = omni_core::Engine::new(&repo_path)?;
 
+    // Hold the index writer lock for the daemon's lifetime so a concurrent
+    // `omnicontext index` is refused instead of racing us on vectors.bin.
+    // If one is already running, let it finish first.
+    engine.hold_writer_lock(omni_core::index_lock::WriterKind::Daemon, true)?;
+
+    if let Some(max) = args.max_concurrent_searches {
+        let search = omni_core::config::SearchConfig {
+            max_concurrent_searches: max,
+            ..engine.config().search.clone()
+        };
+        engine.apply_search_config(search)?;
+    }
+
     // Auto-index if needed
     if !args.no_auto_index {
         let status = engine.status()?;
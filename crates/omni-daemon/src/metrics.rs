@@ -107,18 +107,12 @@ impl PerformanceMetrics {
 
     /// Get peak memory usage in bytes.
     pub fn get_peak_memory_bytes(&self) -> u64 {
-        self.inner
-            .lock()
-            .map(|inner| inner.peak_memory_bytes)
-            .unwrap_or(0)
+        self.inner.lock().map_or(0, |inner| inner.peak_memory_bytes)
     }
 
     /// Get total number of searches performed.
     pub fn get_total_searches(&self) -> u64 {
-        self.inner
-            .lock()
-            .map(|inner| inner.total_searches)
-            .unwrap_or(0)
+        self.inner.lock().map_or(0, |inner| inner.total_searches)
     }
 
     /// Reset all metrics.
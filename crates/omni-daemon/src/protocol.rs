@@ -76,6 +76,47 @@ impl Response {
     }
 }
 
+/// A JSON-RPC 2.0 notification pushed from the daemon to every connected
+/// client. Notifications carry no `id` and expect no reply.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Notification {
+    /// Protocol version, always "2.0".
+    pub jsonrpc: String,
+    /// Notification name (see [`notifications`]).
+    pub method: String,
+    /// Notification payload.
+    pub params: serde_json::Value,
+}
+
+impl Notification {
+    /// Create a notification.
+    pub fn new(method: impl Into<String>, params: serde_json::Value) -> Self {
+        Self {
+            jsonrpc: "2.0".into(),
+            method: method.into(),
+            params,
+        }
+    }
+}
+
+/// Notification methods pushed by the daemon.
+pub mod notifications {
+    /// A file finished (re)indexing; params are [`super::FileIndexedParams`].
+    pub const FILE_INDEXED: &str = "fileIndexed";
+}
+
+/// Parameters of the `fileIndexed` notification.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileIndexedParams {
+    /// Absolute path of the indexed file.
+    pub path: String,
+    /// Chunks stored for the file.
+    pub chunks: usize,
+    /// Time spent indexing the file in milliseconds.
+    pub duration_ms: u64,
+}
+
 // ---------------------------------------------------------------------------
 // Method-specific parameter types
 // ---------------------------------------------------------------------------
@@ -95,6 +136,10 @@ pub struct SearchParams {
     /// The content is never persisted to SQLite.
     #[serde(default)]
     pub active_file_content: Option<String>,
+    /// Return results grouped by file (`files`, each with aggregate scores
+    /// and its top chunks) instead of a flat `results` list.
+    #[serde(default)]
+    pub group_by_file: bool,
 }
 
 /// Parameters for the `context_window` method.
@@ -237,6 +282,9 @@ pub struct SystemStatusResponse {
     pub files_indexed: usize,
     /// Number of chunks indexed.
     pub chunks_indexed: usize,
+    /// Why the engine is running below full capability; empty when healthy.
+    #[serde(default)]
+    pub degraded_reasons: Vec<String>,
 }
 
 /// Performance metrics response.
@@ -603,6 +651,16 @@ pub struct RemoveRepoParams {
     pub path: String,
 }
 
+/// Parameters for searching every repository in the workspace.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkspaceSearchParams {
+    /// The search query.
+    pub query: String,
+    /// Maximum results.
+    #[serde(default = "default_limit")]
+    pub limit: usize,
+}
+
 // ---------------------------------------------------------------------------
 // Performance control types
 // ---------------------------------------------------------------------------
@@ -0,0 +1,81 @@
+/*
+ * OmniContext C API.
+ *
+ * Stable C ABI over the OmniContext engine, for JetBrains plugins and other
+ * native hosts. Link against omnicontext.dll / libomnicontext.so /
+ * libomnicontext.dylib (built from crates/omni-ffi).
+ *
+ * Conventions:
+ *   - Input strings are caller-owned, null-terminated UTF-8.
+ *   - Returned `char *` strings are JSON, allocated by the library; release
+ *     them with omni_free(). Never free them with free().
+ *   - On failure, functions return NULL (or 0) and record a message that
+ *     omni_last_error() returns on the same thread.
+ *   - An engine may be used from one thread at a time.
+ *
+ * Versioning: additions keep OMNI_ABI_VERSION; removing a function or
+ * changing a signature bumps it. Compare omni_abi_version() against
+ * OMNI_ABI_VERSION after loading the library.
+ */
+
+#ifndef OMNICONTEXT_H
+#define OMNICONTEXT_H
+
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define OMNI_ABI_VERSION 1
+
+/* Opaque engine handle. */
+typedef void OmniEngine;
+
+/* ABI version implemented by the loaded library. */
+uint32_t omni_abi_version(void);
+
+/* Library version, e.g. "1.5.0". Static; do not free. */
+const char *omni_version(void);
+
+/* Last error on this thread, or NULL. Free with omni_free(). */
+char *omni_last_error(void);
+
+/* Free a string returned by the library. NULL is a no-op. */
+void omni_free(char *ptr);
+
+/* Open (or create) the index for the repository at `repo_path`. */
+OmniEngine *omni_engine_new(const char *repo_path);
+
+/* Release an engine. NULL is a no-op. */
+void omni_engine_free(OmniEngine *engine);
+
+/* Hybrid search. JSON array of
+ * {file, score, symbol, kind, line_start, line_end, content}. */
+char *omni_search(const OmniEngine *engine, const char *query, uint32_t limit);
+
+/* Index statistics as a JSON object. */
+char *omni_status(const OmniEngine *engine);
+
+/* Token-budgeted context window as JSON
+ * {total_tokens, token_budget, entries_count, rendered}.
+ * A `token_budget` of 0 uses the engine default. */
+char *omni_context_window(const OmniEngine *engine, const char *query,
+                          uint32_t limit, uint32_t token_budget);
+
+/* Critique of a free-text change plan, as JSON. */
+char *omni_audit_plan(const OmniEngine *engine, const char *plan);
+
+/* Symbols affected by changing `symbol`, as a JSON array of
+ * {symbol, distance, kind}. */
+char *omni_blast_radius(const OmniEngine *engine, const char *symbol,
+                        uint32_t max_depth);
+
+/* 1 if the repository's index database can be opened for writing, else 0. */
+int32_t omni_ensure_health(const char *repo_path);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* OMNICONTEXT_H */
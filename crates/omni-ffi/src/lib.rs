@@ -1,8 +1,9 @@
 //! C-ABI Foreign Function Interface for `OmniContext`.
 //!
 //! Produces `omnicontext.dll` (Windows) / `libomnicontext.so` (Linux) /
-//! `libomnicontext.dylib` (macOS) for Python, Node.js, and other language
-//! agents to call at native speed without IPC overhead.
+//! `libomnicontext.dylib` (macOS) for `JetBrains` plugins, Python, Node.js, and
+//! other native hosts to call at native speed without IPC overhead. The C
+//! declarations live in `include/omnicontext.h`.
 //!
 //! ## Pattern
 //!
@@ -11,11 +12,23 @@
 //! - Strings out: `*mut c_char` (callee-allocated via `CString`, caller frees with `omni_free`)
 //! - Engine: opaque `*mut c_void` pointer to a boxed `EngineWrapper`
 //!
+//! ## Stability
+//!
+//! The exported symbols, their signatures, and the JSON fields they return
+//! form a stable ABI versioned by [`OMNI_ABI_VERSION`]. Additions keep the
+//! version; any removal or signature change bumps it. Hosts should check
+//! `omni_abi_version()` after loading the library.
+//!
+//! Failures return null (or `0`) and record a message retrievable with
+//! `omni_last_error()` on the same thread. Panics never unwind into the host:
+//! they are caught and reported as errors.
+//!
 //! ## Usage (Python)
 //!
 //! ```python
 //! import ctypes
 //! lib = ctypes.CDLL("./omnicontext.dll")
+//! assert lib.omni_abi_version() == 1
 //! engine = lib.omni_engine_new(b".")
 //! result = lib.omni_search(engine, b"embedding pipeline", 5)
 //! # ... use result ...
@@ -27,9 +40,19 @@
 #![allow(unsafe_code)]
 #![allow(missing_docs, clippy::missing_errors_doc, clippy::missing_panics_doc)]
 
+use std::cell::RefCell;
 use std::ffi::{c_char, c_void, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
 use std::path::Path;
 
+/// Version of the C ABI described by `include/omnicontext.h`.
+pub const OMNI_ABI_VERSION: u32 = 1;
+
+thread_local! {
+    /// Message of the last failed call on this thread.
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
 /// Wrapper around Engine + Tokio runtime for async method dispatch.
 struct EngineWrapper {
     engine: omni_core::Engine,
@@ -37,15 +60,55 @@ struct EngineWrapper {
     runtime: tokio::runtime::Runtime,
 }
 
-/// Helper: convert a `*const c_char` to a Rust `&str`.
+/// Record `message` as this thread's last error.
+fn set_last_error(message: impl Into<String>) {
+    let message = message.into().replace('\0', " ");
+    LAST_ERROR.with(|slot| *slot.borrow_mut() = CString::new(message).ok());
+}
+
+/// Run `body`, turning an error or a panic into `on_error` plus a recorded
+/// last error, so neither crosses the C boundary.
+fn guard<T>(on_error: T, body: impl FnOnce() -> Result<T, String>) -> T {
+    match catch_unwind(AssertUnwindSafe(body)) {
+        Ok(Ok(value)) => value,
+        Ok(Err(message)) => {
+            set_last_error(message);
+            on_error
+        }
+        Err(panic) => {
+            let detail = panic
+                .downcast_ref::<&str>()
+                .map(ToString::to_string)
+                .or_else(|| panic.downcast_ref::<String>().cloned())
+                .unwrap_or_default();
+            set_last_error(format!("internal panic: {detail}"));
+            on_error
+        }
+    }
+}
+
+/// Helper: convert a `*const c_char` argument to a Rust `&str`.
 ///
 /// # Safety
-/// Caller must ensure `ptr` is a valid null-terminated UTF-8 string.
-unsafe fn cstr_to_str<'a>(ptr: *const c_char) -> Option<&'a str> {
+/// Caller must ensure `ptr` is null or a valid null-terminated string.
+unsafe fn arg_str<'a>(ptr: *const c_char, name: &str) -> Result<&'a str, String> {
     if ptr.is_null() {
-        return None;
+        return Err(format!("{name} is null"));
     }
-    CStr::from_ptr(ptr).to_str().ok()
+    CStr::from_ptr(ptr)
+        .to_str()
+        .map_err(|_| format!("{name} is not valid UTF-8"))
+}
+
+/// Helper: dereference an engine pointer.
+///
+/// # Safety
+/// Caller must ensure `engine` is null or a live pointer from `omni_engine_new`.
+unsafe fn engine_ref<'a>(engine: *const c_void) -> Result<&'a EngineWrapper, String> {
+    engine
+        .cast::<EngineWrapper>()
+        .as_ref()
+        .ok_or_else(|| "engine is null".to_string())
 }
 
 /// Helper: convert a Rust `String` to a heap-allocated `*mut c_char`.
@@ -56,6 +119,37 @@ fn string_to_cstring(s: String) -> *mut c_char {
     }
 }
 
+/// Helper: serialize `value` into a callee-allocated JSON C string.
+fn to_json<T: serde::Serialize + ?Sized>(value: &T) -> Result<*mut c_char, String> {
+    serde_json::to_string(value)
+        .map(string_to_cstring)
+        .map_err(|e| format!("serialization failed: {e}"))
+}
+
+/// ABI version of this library; see [`OMNI_ABI_VERSION`].
+#[no_mangle]
+pub extern "C" fn omni_abi_version() -> u32 {
+    OMNI_ABI_VERSION
+}
+
+/// Library version string (for example `"1.5.0"`). Static; do not free.
+#[no_mangle]
+pub extern "C" fn omni_version() -> *const c_char {
+    concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr().cast()
+}
+
+/// Message describing the last failed `omni_*` call on this thread, or null
+/// if none has failed. Only meaningful right after a call returned null (or
+/// `0`). Free the result with `omni_free`.
+#[no_mangle]
+pub extern "C" fn omni_last_error() -> *mut c_char {
+    LAST_ERROR.with(|slot| {
+        slot.borrow()
+            .as_ref()
+            .map_or(std::ptr::null_mut(), |message| message.clone().into_raw())
+    })
+}
+
 /// Create a new `OmniContext` engine for the given repository path.
 ///
 /// Returns an opaque engine pointer, or null on failure.
@@ -64,20 +158,19 @@ fn string_to_cstring(s: String) -> *mut c_char {
 /// `repo_path` must be a valid null-terminated UTF-8 C string.
 #[no_mangle]
 pub unsafe extern "C" fn omni_engine_new(repo_path: *const c_char) -> *mut c_void {
-    let Some(path_str) = cstr_to_str(repo_path) else {
-        return std::ptr::null_mut();
-    };
-
-    let Ok(runtime) = tokio::runtime::Runtime::new() else {
-        return std::ptr::null_mut();
-    };
-
-    let Ok(engine) = omni_core::Engine::new(Path::new(path_str)) else {
-        return std::ptr::null_mut();
-    };
-
-    let wrapper = Box::new(EngineWrapper { engine, runtime });
-    Box::into_raw(wrapper).cast::<c_void>()
+    guard(std::ptr::null_mut(), || {
+        let path_str = arg_str(repo_path, "repo_path")?;
+        // The index location is keyed by path; canonicalize as the CLI does.
+        let path = Path::new(path_str);
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+
+        let runtime =
+            tokio::runtime::Runtime::new().map_err(|e| format!("failed to start runtime: {e}"))?;
+        let engine = omni_core::Engine::new(&path).map_err(|e| e.to_string())?;
+
+        let wrapper = Box::new(EngineWrapper { engine, runtime });
+        Ok(Box::into_raw(wrapper).cast::<c_void>())
+    })
 }
 
 /// Free an engine created by `omni_engine_new`.
@@ -87,7 +180,10 @@ pub unsafe extern "C" fn omni_engine_new(repo_path: *const c_char) -> *mut c_voi
 #[no_mangle]
 pub unsafe extern "C" fn omni_engine_free(engine: *mut c_void) {
     if !engine.is_null() {
-        drop(Box::from_raw(engine.cast::<EngineWrapper>()));
+        guard((), || {
+            drop(Box::from_raw(engine.cast::<EngineWrapper>()));
+            Ok(())
+        });
     }
 }
 
@@ -113,34 +209,31 @@ pub unsafe extern "C" fn omni_search(
     query: *const c_char,
     limit: u32,
 ) -> *mut c_char {
-    let Some(wrapper) = engine.cast::<EngineWrapper>().as_ref() else {
-        return std::ptr::null_mut();
-    };
-    let Some(query_str) = cstr_to_str(query) else {
-        return std::ptr::null_mut();
-    };
-
-    match wrapper.engine.search(query_str, limit as usize) {
-        Ok(results) => {
-            let json_results: Vec<serde_json::Value> = results
-                .iter()
-                .map(|r| {
-                    serde_json::json!({
-                        "file": r.file_path.display().to_string(),
-                        "score": r.score,
-                        "symbol": r.chunk.symbol_path,
-                        "kind": r.chunk.kind.as_str(),
-                        "line_start": r.chunk.line_start,
-                        "line_end": r.chunk.line_end,
-                        "content": r.chunk.content,
-                    })
+    guard(std::ptr::null_mut(), || {
+        let wrapper = engine_ref(engine)?;
+        let query_str = arg_str(query, "query")?;
+
+        let results = wrapper
+            .engine
+            .search(query_str, limit as usize)
+            .map_err(|e| e.to_string())?;
+        let json_results: Vec<serde_json::Value> = results
+            .iter()
+            .map(|r| {
+                serde_json::json!({
+                    "file": r.file_path.display().to_string(),
+                    "score": r.score,
+                    "relevance": r.relevance,
+                    "symbol": r.chunk.symbol_path,
+                    "kind": r.chunk.kind.as_str(),
+                    "line_start": r.chunk.line_start,
+                    "line_end": r.chunk.line_end,
+                    "content": r.chunk.content,
                 })
-                .collect();
-            let json = serde_json::to_string(&json_results).unwrap_or_default();
-            string_to_cstring(json)
-        }
-        Err(_) => std::ptr::null_mut(),
-    }
+            })
+            .collect();
+        to_json(&json_results)
+    })
 }
 
 /// Get engine status as JSON. Returns null on error.
@@ -149,17 +242,10 @@ pub unsafe extern "C" fn omni_search(
 /// `engine` must be a valid engine pointer from `omni_engine_new`.
 #[no_mangle]
 pub unsafe extern "C" fn omni_status(engine: *const c_void) -> *mut c_char {
-    let Some(wrapper) = engine.cast::<EngineWrapper>().as_ref() else {
-        return std::ptr::null_mut();
-    };
-
-    match wrapper.engine.status() {
-        Ok(status) => {
-            let json = serde_json::to_string(&status).unwrap_or_default();
-            string_to_cstring(json)
-        }
-        Err(_) => std::ptr::null_mut(),
-    }
+    guard(std::ptr::null_mut(), || {
+        let wrapper = engine_ref(engine)?;
+        to_json(&wrapper.engine.status().map_err(|e| e.to_string())?)
+    })
 }
 
 /// Assemble a token-budget-aware context window as JSON. Returns null on error.
@@ -174,34 +260,27 @@ pub unsafe extern "C" fn omni_context_window(
     limit: u32,
     token_budget: u32,
 ) -> *mut c_char {
-    let Some(wrapper) = engine.cast::<EngineWrapper>().as_ref() else {
-        return std::ptr::null_mut();
-    };
-    let Some(query_str) = cstr_to_str(query) else {
-        return std::ptr::null_mut();
-    };
-
-    let budget = if token_budget == 0 {
-        None
-    } else {
-        Some(token_budget)
-    };
-
-    match wrapper
-        .engine
-        .search_context_window(query_str, limit as usize, budget)
-    {
-        Ok(ctx) => {
-            let json = serde_json::json!({
-                "total_tokens": ctx.total_tokens,
-                "token_budget": ctx.token_budget,
-                "entries_count": ctx.entries.len(),
-                "rendered": ctx.render(),
-            });
-            string_to_cstring(json.to_string())
-        }
-        Err(_) => std::ptr::null_mut(),
-    }
+    guard(std::ptr::null_mut(), || {
+        let wrapper = engine_ref(engine)?;
+        let query_str = arg_str(query, "query")?;
+
+        let budget = if token_budget == 0 {
+            None
+        } else {
+            Some(token_budget)
+        };
+
+        let ctx = wrapper
+            .engine
+            .search_context_window(query_str, limit as usize, budget)
+            .map_err(|e| e.to_string())?;
+        to_json(&serde_json::json!({
+            "total_tokens": ctx.total_tokens,
+            "token_budget": ctx.token_budget,
+            "entries_count": ctx.entries.len(),
+            "rendered": ctx.render(),
+        }))
+    })
 }
 
 /// Audit a plan and return JSON critique. Returns null on error.
@@ -214,21 +293,13 @@ pub unsafe extern "C" fn omni_audit_plan(
     engine: *const c_void,
     plan: *const c_char,
 ) -> *mut c_char {
-    let Some(wrapper) = engine.cast::<EngineWrapper>().as_ref() else {
-        return std::ptr::null_mut();
-    };
-    let Some(plan_str) = cstr_to_str(plan) else {
-        return std::ptr::null_mut();
-    };
-
-    let auditor = omni_core::plan_auditor::PlanAuditor::new(&wrapper.engine);
-    match auditor.audit(plan_str, 3) {
-        Ok(critique) => {
-            let json = serde_json::to_string(&critique).unwrap_or_default();
-            string_to_cstring(json)
-        }
-        Err(_) => std::ptr::null_mut(),
-    }
+    guard(std::ptr::null_mut(), || {
+        let wrapper = engine_ref(engine)?;
+        let plan_str = arg_str(plan, "plan")?;
+
+        let auditor = omni_core::plan_auditor::PlanAuditor::new(&wrapper.engine);
+        to_json(&auditor.audit(plan_str, 3).map_err(|e| e.to_string())?)
+    })
 }
 
 /// Compute blast radius for a symbol. Returns JSON array, or null on error.
@@ -249,60 +320,46 @@ pub unsafe extern "C" fn omni_blast_radius(
     symbol: *const c_char,
     max_depth: u32,
 ) -> *mut c_char {
-    // Guard: null engine
-    let Some(wrapper) = engine.cast::<EngineWrapper>().as_ref() else {
-        return std::ptr::null_mut();
-    };
-
-    // Guard: null symbol pointer
-    if symbol.is_null() {
-        return std::ptr::null_mut();
-    }
-
-    // Guard: invalid UTF-8 in symbol string
-    let Ok(sym_str) = CStr::from_ptr(symbol).to_str() else {
-        return std::ptr::null_mut();
-    };
+    guard(std::ptr::null_mut(), || {
+        let wrapper = engine_ref(engine)?;
+        let sym_str = arg_str(symbol, "symbol")?;
 
-    // Guard: empty symbol
-    if sym_str.trim().is_empty() {
-        return std::ptr::null_mut();
-    }
+        // Guard: empty symbol
+        if sym_str.trim().is_empty() {
+            return Err("symbol is empty".to_string());
+        }
 
-    let index = wrapper.engine.metadata_index();
-    let graph = wrapper.engine.dep_graph();
-
-    // Look up symbol — try FQN first, fall back to name search
-    let sym = match index.get_symbol_by_fqn(sym_str) {
-        Ok(Some(s)) => s,
-        _ => match index.search_symbols_by_name(sym_str, 1) {
-            Ok(syms) => match syms.into_iter().next() {
-                Some(s) => s,
-                None => return std::ptr::null_mut(),
-            },
-            Err(_) => return std::ptr::null_mut(),
-        },
-    };
-
-    match graph.blast_radius(sym.id, max_depth as usize) {
-        Ok(radius) => {
-            let results: Vec<serde_json::Value> = radius
-                .iter()
-                .filter_map(|(id, dist)| {
-                    index.get_symbol_by_id(*id).ok().flatten().map(|s| {
-                        serde_json::json!({
-                            "symbol": s.fqn,
-                            "distance": dist,
-                            "kind": s.kind.as_str(),
-                        })
+        let index = wrapper.engine.metadata_index();
+        let graph = wrapper.engine.dep_graph();
+
+        // Look up symbol — try FQN first, fall back to name search
+        let sym = match index.get_symbol_by_fqn(sym_str) {
+            Ok(Some(s)) => s,
+            _ => index
+                .search_symbols_by_name(sym_str, 1)
+                .map_err(|e| e.to_string())?
+                .into_iter()
+                .next()
+                .ok_or_else(|| format!("symbol not found: {sym_str}"))?,
+        };
+
+        let radius = graph
+            .blast_radius(sym.id, max_depth as usize)
+            .map_err(|e| e.to_string())?;
+        let results: Vec<serde_json::Value> = radius
+            .iter()
+            .filter_map(|(id, dist)| {
+                index.get_symbol_by_id(*id).ok().flatten().map(|s| {
+                    serde_json::json!({
+                        "symbol": s.fqn,
+                        "distance": dist,
+                        "kind": s.kind.as_str(),
                     })
                 })
-                .collect();
-            let json = serde_json::to_string(&results).unwrap_or_default();
-            string_to_cstring(json)
-        }
-        Err(_) => std::ptr::null_mut(),
-    }
+            })
+            .collect();
+        to_json(&results)
+    })
 }
 
 /// Verify that the `OmniContext` engine is healthy for the given repo path.
@@ -321,43 +378,40 @@ pub unsafe extern "C" fn omni_blast_radius(
 /// Returns `0` (unhealthy) on null input.
 #[no_mangle]
 pub unsafe extern "C" fn omni_ensure_health(repo_path: *const c_char) -> i32 {
-    // Guard: null path
-    if repo_path.is_null() {
-        return 0;
-    }
-
-    // Guard: invalid UTF-8
-    let path_str = match CStr::from_ptr(repo_path).to_str() {
-        Ok(s) if !s.trim().is_empty() => s,
-        _ => return 0,
-    };
-
-    let path = std::path::Path::new(path_str);
+    guard(0, || {
+        let path_str = arg_str(repo_path, "repo_path")?;
+        if path_str.trim().is_empty() {
+            return Err("repo_path is empty".to_string());
+        }
 
-    // Derive the data directory the same way omni-core does:
-    // Uses Config::defaults to get the same hash-derived path.
-    let data_dir = omni_core::Config::defaults(path).data_dir();
-    let db_path = data_dir.join("omnicontext.db");
+        let path = Path::new(path_str);
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
 
-    // If DB doesn't exist yet there's nothing to lock — healthy.
-    if !db_path.exists() {
-        return 1;
-    }
+        // Resolve the index the same way the engine does, honoring any
+        // data directory set in the repository's config.
+        let db_path = omni_core::Config::load(&path)
+            .unwrap_or_else(|_| omni_core::Config::defaults(&path))
+            .index_db_path();
 
-    // Attempt to open with WAL and an immediate EXCLUSIVE lock probe.
-    // rusqlite is bundled — always available.
-    match rusqlite::Connection::open_with_flags(
-        &db_path,
-        rusqlite::OpenFlags::SQLITE_OPEN_READ_WRITE | rusqlite::OpenFlags::SQLITE_OPEN_NO_MUTEX,
-    ) {
-        Ok(conn) => {
-            // Try a BEGIN IMMEDIATE to test for exclusive lock contention.
-            let locked = conn.execute_batch("BEGIN IMMEDIATE; ROLLBACK;").is_err();
-            i32::from(!locked)
+        // If DB doesn't exist yet there's nothing to lock — healthy.
+        if !db_path.exists() {
+            return Ok(1);
         }
+
+        // Attempt to open with WAL and an immediate EXCLUSIVE lock probe.
+        // rusqlite is bundled — always available.
+        let conn = rusqlite::Connection::open_with_flags(
+            &db_path,
+            rusqlite::OpenFlags::SQLITE_OPEN_READ_WRITE | rusqlite::OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )
         // Can't open → locked or permissions issue
-        Err(_) => 0,
-    }
+        .map_err(|e| format!("cannot open {}: {e}", db_path.display()))?;
+
+        // Try a BEGIN IMMEDIATE to test for exclusive lock contention.
+        conn.execute_batch("BEGIN IMMEDIATE; ROLLBACK;")
+            .map_err(|e| format!("index database is locked: {e}"))?;
+        Ok(1)
+    })
 }
 
 #[cfg(test)]
@@ -594,4 +648,107 @@ mod tests {
         // SAFETY: engine was returned by omni_engine_new and has not been freed.
         unsafe { omni_engine_free(engine) };
     }
+
+    // ------------------------------------------------------------ ABI surface
+
+    /// Every exported function must be declared in the C header, and the
+    /// header's ABI version must match the library's.
+    #[test]
+    fn test_header_declares_every_export() {
+        let header = include_str!("../include/omnicontext.h");
+        let source = include_str!("lib.rs");
+        let exports: Vec<&str> = source
+            .split("extern \"C\" fn ")
+            .skip(1)
+            .filter_map(|rest| rest.split('(').next())
+            .collect();
+        assert!(exports.len() >= 12, "{exports:?}");
+        for name in exports {
+            assert!(
+                header.contains(&format!(" {name}(")) || header.contains(&format!("*{name}(")),
+                "{name} is exported but missing from include/omnicontext.h"
+            );
+        }
+        assert!(header.contains(&format!("#define OMNI_ABI_VERSION {OMNI_ABI_VERSION}\n")));
+        assert_eq!(omni_abi_version(), OMNI_ABI_VERSION);
+
+        // SAFETY: omni_version returns a static null-terminated string.
+        let version = unsafe { CStr::from_ptr(omni_version()) };
+        assert_eq!(version.to_str(), Ok(env!("CARGO_PKG_VERSION")));
+    }
+
+    /// Failures record a message for `omni_last_error` on the calling thread.
+    #[test]
+    fn test_last_error_reports_failure_reason() {
+        let query = cs("anything");
+        // SAFETY: intentionally passing null engine — the function must guard.
+        let result = unsafe { omni_search(std::ptr::null(), query.as_ptr(), 5) };
+        assert!(result.is_null());
+
+        let error = omni_last_error();
+        assert!(!error.is_null(), "failure should set the last error");
+        // SAFETY: error is non-null and was returned by omni_last_error.
+        assert_eq!(unsafe { read_cstr(error) }, "engine is null");
+        // SAFETY: error was returned by omni_last_error and has not been freed.
+        unsafe { omni_free(error) };
+
+        // SAFETY: intentionally passing invalid UTF-8.
+        let health = unsafe { omni_ensure_health(c"\xff".as_ptr()) };
+        assert_eq!(health, 0);
+        let error = omni_last_error();
+        // SAFETY: error is non-null and was returned by omni_last_error.
+        assert_eq!(unsafe { read_cstr(error) }, "repo_path is not valid UTF-8");
+        // SAFETY: error was returned by omni_last_error and has not been freed.
+        unsafe { omni_free(error) };
+
+        // Other threads keep their own error state.
+        std::thread::spawn(|| assert!(omni_last_error().is_null()))
+            .join()
+            .expect("thread");
+    }
+
+    /// Panics inside the engine are reported as errors instead of unwinding
+    /// into the host.
+    #[test]
+    fn test_panics_do_not_cross_the_boundary() {
+        let result = guard(std::ptr::null_mut::<c_char>(), || panic!("boom"));
+        assert!(result.is_null());
+        let error = omni_last_error();
+        // SAFETY: error is non-null and was returned by omni_last_error.
+        assert_eq!(unsafe { read_cstr(error) }, "internal panic: boom");
+        // SAFETY: error was returned by omni_last_error and has not been freed.
+        unsafe { omni_free(error) };
+    }
+
+    /// Full round trip without the embedding model: open, index, search, free.
+    #[test]
+    fn test_search_round_trip_without_model() {
+        std::env::set_var("OMNI_SKIP_MODEL_DOWNLOAD", "1");
+        std::env::set_var("OMNI_DISABLE_RERANKER", "1");
+        let (_dir, engine) = make_engine_with_tempdir();
+        assert!(!engine.is_null(), "engine creation failed");
+
+        // SAFETY: engine is valid and non-null.
+        let wrapper = unsafe { &mut *engine.cast::<EngineWrapper>() };
+        wrapper
+            .runtime
+            .block_on(wrapper.engine.run_index(false))
+            .expect("index");
+
+        let query = cs("hello");
+        // SAFETY: engine is valid; query is a valid null-terminated UTF-8 string.
+        let result_ptr = unsafe { omni_search(engine.cast_const(), query.as_ptr(), 5) };
+        assert!(!result_ptr.is_null(), "omni_search returned null");
+        // SAFETY: result_ptr is non-null and was returned by omni_search.
+        let parsed: serde_json::Value =
+            serde_json::from_str(&unsafe { read_cstr(result_ptr) }).expect("json");
+        assert!(parsed[0]["symbol"]
+            .as_str()
+            .is_some_and(|s| s.contains("hello")));
+
+        // SAFETY: result_ptr was returned by omni_search and has not been freed.
+        unsafe { omni_free(result_ptr) };
+        // SAFETY: engine was returned by omni_engine_new and has not been freed.
+        unsafe { omni_engine_free(engine) };
+    }
 }
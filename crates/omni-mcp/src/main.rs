@@ -103,6 +103,13 @@ struct Args {
     #[arg(long)]
     no_auto_index: bool,
 
+    /// Serve an existing index read-only (implies `--no-auto-index`).
+    ///
+    /// For stateless search replicas: the index is opened immutable and
+    /// never written, migrated, or re-indexed.
+    #[arg(long)]
+    read_only: bool,
+
     /// Transport backend.
     ///
     /// - `stdio` (default): JSON-RPC over stdin/stdout. Used by Claude Desktop,
@@ -232,7 +239,8 @@ async fn main() -> Result<()> {
 
     // Initialize the core engine with a timeout and degraded fallback.
     // This prevents MCP startup from hanging indefinitely during model init.
-    let (mut engine, degraded_mode) = initialize_engine_with_fallback(repo_path.clone()).await?;
+    let (mut engine, degraded_mode) =
+        initialize_engine_with_fallback(repo_path.clone(), args.read_only).await?;
     if degraded_mode {
         tracing::warn!(
             "MCP started in degraded mode (OMNI_SKIP_MODEL_DOWNLOAD=1). Semantic embeddings and reranking are temporarily disabled."
@@ -242,7 +250,7 @@ async fn main() -> Result<()> {
     // Auto-index: if the index is empty and auto-index is not disabled,
     // run a full index before starting the MCP server.
     // This ensures AI agents always connect to a ready engine.
-    if !args.no_auto_index {
+    if !args.no_auto_index && !args.read_only {
         let status = engine.status()?;
         if status.files_indexed == 0 {
             tracing::info!("no existing index found, running auto-index...");
@@ -278,6 +286,10 @@ async fn main() -> Result<()> {
         }
     }
 
+    // Load models and prime caches now so the agent's first search is not
+    // the one that pays for it.
+    engine.warm_up();
+
     tracing::info!("engine ready, starting MCP server");
 
     match args.transport {
@@ -286,6 +298,9 @@ async fn main() -> Result<()> {
 
             // Create and start the MCP server
             let server = tools::OmniContextServer::new(engine);
+            if !args.read_only {
+                server.spawn_background_jobs();
+            }
             let service = server
                 .serve(rmcp::transport::stdio())
                 .await
@@ -314,6 +329,9 @@ async fn main() -> Result<()> {
             );
 
             let engine_arc = Arc::new(tokio::sync::Mutex::new(engine));
+            if !args.read_only {
+                tools::spawn_background_job_runner(Arc::clone(&engine_arc));
+            }
             transport::sse::serve(sse_config, engine_arc).await?;
 
             tracing::info!("SSE MCP server shut down");
@@ -323,11 +341,24 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
+/// Open the engine, as an immutable replica when `read_only` is set.
+fn open_engine(
+    repo_path: &std::path::Path,
+    read_only: bool,
+) -> omni_core::error::OmniResult<omni_core::Engine> {
+    if read_only {
+        omni_core::Engine::open_read_only(omni_core::Config::load(repo_path)?)
+    } else {
+        omni_core::Engine::new(repo_path)
+    }
+}
+
 async fn initialize_engine_with_fallback(
     repo_path: std::path::PathBuf,
+    read_only: bool,
 ) -> Result<(omni_core::Engine, bool)> {
     let normal_repo = repo_path.clone();
-    let normal_init = tokio::task::spawn_blocking(move || omni_core::Engine::new(&normal_repo));
+    let normal_init = tokio::task::spawn_blocking(move || open_engine(&normal_repo, read_only));
 
     match tokio::time::timeout(Duration::from_secs(90), normal_init).await {
         Ok(joined) => match joined {
@@ -363,7 +394,7 @@ async fn initialize_engine_with_fallback(
 
     std::env::set_var("OMNI_SKIP_MODEL_DOWNLOAD", "1");
     let degraded_repo = repo_path.clone();
-    let degraded_init = tokio::task::spawn_blocking(move || omni_core::Engine::new(&degraded_repo));
+    let degraded_init = tokio::task::spawn_blocking(move || open_engine(&degraded_repo, read_only));
     let degraded = tokio::time::timeout(Duration::from_secs(30), degraded_init)
         .await
         .map_err(|_| anyhow::anyhow!("degraded engine init timed out after 30s"))?
@@ -20,6 +20,7 @@ use rmcp::{
 use serde::Deserialize;
 use tokio::sync::Mutex;
 
+use omni_core::search::{ContextFormat, ContextFormatter, FormatOptions};
 use omni_core::Engine;
 
 // -----------------------------------------------------------------------
@@ -36,6 +37,12 @@ const MAX_GRAPH_DEPTH: usize = 20;
 const MAX_COMMIT_COUNT: usize = 100;
 /// Maximum plan text length for `audit_plan`.
 const MAX_PLAN_LEN: usize = 500_000;
+/// Maximum per-signal retrieval depth (`candidates`).
+const MAX_CANDIDATES: usize = 1000;
+/// Maximum stack trace length for `resolve_stack_trace`.
+const MAX_TRACE_LEN: usize = 200_000;
+/// Maximum unified diff length for `context_for_diff`.
+pub(crate) const MAX_DIFF_LEN: usize = 2_000_000;
 
 /// Clamp a limit value to a safe range.
 fn clamp_limit(limit: Option<usize>, default: usize) -> usize {
@@ -61,6 +68,21 @@ fn validate_query(query: &str) -> Result<(), McpError> {
     Ok(())
 }
 
+/// Why a search came back empty, with the fix to try.
+fn no_results_text(
+    engine: &Engine,
+    query: &str,
+    options: &omni_core::types::SearchOptions,
+    min_score: Option<f32>,
+) -> String {
+    engine
+        .diagnose_no_results(query, options, min_score)
+        .map_or_else(
+            |e| format!("No results found (diagnostics unavailable: {e})."),
+            |diagnostics| diagnostics.render(),
+        )
+}
+
 /// Validate a path is safe (no parent traversal, no absolute paths pointing outside the repo).
 fn validate_relative_path(path: &str) -> Result<(), McpError> {
     let p = std::path::Path::new(path);
@@ -88,6 +110,50 @@ fn clamp_rerank_score(score: Option<f32>) -> Option<f32> {
     score.map(|s| s.clamp(0.0, 1.0))
 }
 
+/// Markdown for `search_code` results grouped by file: a section per file
+/// with its aggregate scores, then its top chunks.
+fn write_file_groups(output: &mut String, groups: &[omni_core::search::FileGroup]) {
+    use std::fmt::Write;
+
+    for (i, group) in groups.iter().enumerate() {
+        writeln!(
+            output,
+            "## {}. {} ({}, {} match{})",
+            i + 1,
+            group.file_path.display(),
+            score_label(group.score, group.relevance),
+            group.match_count,
+            if group.match_count == 1 { "" } else { "es" },
+        )
+        .ok();
+        for result in &group.chunks {
+            write!(
+                output,
+                "### {} ({:?}, L{}-{}, {})\n```\n{}\n```\n",
+                result.chunk.symbol_path,
+                result.chunk.kind,
+                result.chunk.line_start,
+                result.chunk.line_end,
+                score_label(result.score, result.relevance),
+                result.chunk.content,
+            )
+            .ok();
+        }
+        output.push('\n');
+    }
+}
+
+/// "relevance: 0.84, score: 0.0312", flagging results below the
+/// calibrated "probably irrelevant" threshold.
+fn score_label(score: f64, relevance: f64) -> String {
+    let note = if omni_core::search::calibration::is_probably_irrelevant(relevance) {
+        ", probably irrelevant"
+    } else {
+        ""
+    };
+    format!("relevance: {relevance:.2}, score: {score:.4}{note}")
+}
+
 // -----------------------------------------------------------------------
 // Parameter structs for each tool
 // -----------------------------------------------------------------------
@@ -95,7 +161,8 @@ fn clamp_rerank_score(score: Option<f32>) -> Option<f32> {
 /// Parameters for `search_code` tool.
 #[derive(Debug, Deserialize, schemars::JsonSchema)]
 pub struct SearchCodeParams {
-    /// Search query -- natural language or symbol name.
+    /// Search query -- natural language or symbol name. `owner:@team` terms
+    /// keep only files owned by that CODEOWNERS owner.
     pub query: String,
     /// Maximum number of results to return (default: 10).
     pub limit: Option<usize>,
@@ -103,6 +170,23 @@ pub struct SearchCodeParams {
     /// this threshold are demoted. Higher values produce fewer, more precise
     /// results. Default: no threshold (all results returned).
     pub min_rerank_score: Option<f32>,
+    /// Match the query string with exact case (e.g. `Device` but not `device`).
+    /// Switches to a literal scan instead of hybrid retrieval. Default: false.
+    pub case_sensitive: Option<bool>,
+    /// Only match the query as a whole identifier (e.g. `Device` but not
+    /// `DeviceManager`). Switches to a literal scan. Default: false.
+    pub whole_word: Option<bool>,
+    /// Group results by file, with per-file aggregate scores and each
+    /// file's top chunks nested. Default: false.
+    pub group_by_file: Option<bool>,
+    /// Chunks shown per file when grouping by file (default: 3).
+    pub chunks_per_file: Option<usize>,
+    /// What you do not want, e.g. "tests protobuf". Results whose path,
+    /// keywords, or meaning match it are pushed down the ranking.
+    pub exclude: Option<String>,
+    /// Also return generated code (files marked "DO NOT EDIT" or
+    /// `@generated`), which is left out by default.
+    pub include_generated: Option<bool>,
 }
 
 /// Parameters for `get_symbol` tool.
@@ -121,6 +205,23 @@ pub struct GetFileSummaryParams {
     pub path: String,
 }
 
+/// Parameters for `get_file` tool.
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct GetFileParams {
+    /// File path relative to repository root.
+    pub path: String,
+    /// Token budget for the returned content (default: 8000).
+    pub max_tokens: Option<u32>,
+}
+
+/// Parameters for `get_module_summary` tool.
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct GetModuleSummaryParams {
+    /// File or directory path relative to repository root (empty or "." for the root).
+    #[serde(default)]
+    pub path: String,
+}
+
 /// Parameters for `get_dependencies` tool.
 #[derive(Debug, Deserialize, schemars::JsonSchema)]
 pub struct GetDependenciesParams {
@@ -130,6 +231,21 @@ pub struct GetDependenciesParams {
     pub direction: Option<String>,
 }
 
+/// Parameters for `get_package_dependencies` tool.
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct GetPackageDependenciesParams {
+    /// Package (crate, npm package, Go module, or Python project) name.
+    /// Omit to list every package in the repository with its internal
+    /// dependencies.
+    pub package: Option<String>,
+    /// Direction: 'dependencies' (what it depends on), 'dependents' (which
+    /// packages in this repository depend on it), or 'both' (default: 'both').
+    pub direction: Option<String>,
+    /// Include indirect dependents, e.g. a crate that depends on one that
+    /// depends on the package (default: false).
+    pub transitive: Option<bool>,
+}
+
 /// Parameters for `find_patterns` tool.
 #[derive(Debug, Deserialize, schemars::JsonSchema)]
 pub struct FindPatternsParams {
@@ -148,12 +264,23 @@ pub struct ContextWindowParams {
     pub limit: Option<usize>,
     /// Token budget for the context window (default: engine config).
     pub token_budget: Option<u32>,
+    /// Candidates retrieved from each search signal before ranking (default:
+    /// 100). Raise it for broad questions that need deep retrieval.
+    pub candidates: Option<usize>,
     /// Minimum cross-encoder reranker score threshold (0.0-1.0). Chunks below
     /// this threshold are demoted. Higher values produce fewer, more precise
     /// results. Default: no threshold.
     pub min_rerank_score: Option<f32>,
     /// Whether to include architectural shadow headers on each chunk (default: from config).
     pub shadow_headers: Option<bool>,
+    /// LLM that will read the context (e.g. "gpt-4o"). The token budget is
+    /// counted with that model's tokenizer and defaults to its configured
+    /// budget. Default: `search.target_model` from config.
+    pub model: Option<String>,
+    /// Output format: "markdown" (file headers and line numbers), "xml"
+    /// (tagged sections), "json" (compact, one line), or "compact" (raw code
+    /// with file dividers). Default: the standard Markdown summary.
+    pub format: Option<String>,
 }
 
 /// Parameters for `get_module_map` tool.
@@ -257,7 +384,7 @@ fn default_manifest_format() -> String {
 /// Parameters for `search_with_filter` tool.
 #[derive(Debug, Deserialize, schemars::JsonSchema)]
 pub struct SearchWithFilterParams {
-    /// Search query.
+    /// Search query. `owner:@team` terms filter by CODEOWNERS owner.
     pub query: String,
     /// Maximum results (default: 10).
     pub limit: Option<usize>,
@@ -267,10 +394,25 @@ pub struct SearchWithFilterParams {
     pub language: Option<String>,
     /// Glob pattern matched against file paths, e.g. "src/auth/**".
     pub path_glob: Option<String>,
-    /// Only include files indexed after this ISO 8601 datetime.
+    /// Only include files whose content changed at or after this ISO 8601 date or datetime (UTC).
     pub modified_after: Option<String>,
-    /// Symbol type filter, e.g. "function", "class", "struct", "method".
+    /// Only include files whose content changed before this ISO 8601 date or datetime (UTC).
+    pub modified_before: Option<String>,
+    /// Only include files of at least this many bytes.
+    pub min_size_bytes: Option<u64>,
+    /// Only include files of at most this many bytes.
+    pub max_size_bytes: Option<u64>,
+    /// Only search these files (paths relative to the repo root).
+    pub files: Option<Vec<String>>,
+    /// Chunk kind filter, e.g. "function", "class", "trait", "impl", "test".
     pub symbol_type: Option<String>,
+    /// Candidates retrieved from each search signal before ranking (default: 100).
+    pub candidates: Option<usize>,
+    /// What you do not want, e.g. "tests protobuf". Matching results are
+    /// pushed down the ranking.
+    pub exclude: Option<String>,
+    /// Also return generated code, which is left out by default.
+    pub include_generated: Option<bool>,
 }
 
 /// Parameters for `explain_symbol` tool.
@@ -359,6 +501,46 @@ pub struct GetMemoryParams {
 #[derive(Debug, Deserialize, schemars::JsonSchema)]
 pub struct ListMemoryParams {}
 
+/// Parameters for `resolve_stack_trace` tool.
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct ResolveStackTraceParams {
+    /// The stack trace or error output, as printed: a Rust panic or
+    /// backtrace, a Python traceback, or a JavaScript stack.
+    pub trace: String,
+    /// Token budget for the returned code (default: engine config).
+    pub token_budget: Option<u32>,
+}
+
+/// Parameters for `context_for_diff` tool.
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct ContextForDiffParams {
+    /// Unified diff, as printed by `git diff` or `diff -u`.
+    pub diff: String,
+    /// Token budget for the returned code (default: engine config).
+    pub token_budget: Option<u32>,
+}
+
+/// Parameters for `recent_changes` tool.
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct RecentChangesParams {
+    /// Number of recent commits to cover (default: 10, or up to 100 when
+    /// `days` is set).
+    pub commits: Option<usize>,
+    /// Only cover commits from the last this many days.
+    pub days: Option<u32>,
+}
+
+/// Parameters for `get_todos` tool.
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct GetTodosParams {
+    /// Only list comments in this file or directory (relative path).
+    pub path: Option<String>,
+    /// Only list one marker: `TODO`, `FIXME`, or `HACK`.
+    pub tag: Option<String>,
+    /// Maximum number of comments to return (default: 50).
+    pub limit: Option<usize>,
+}
+
 // -----------------------------------------------------------------------
 // MCP Server
 // -----------------------------------------------------------------------
@@ -372,6 +554,64 @@ pub struct OmniContextServer {
     tool_router: ToolRouter<Self>,
 }
 
+/// Chunks embedded per background job step; small enough that tool calls
+/// wait at most a few hundred milliseconds for the engine.
+const BACKGROUND_JOB_STEP: usize = 40;
+
+/// Pause between background job steps while a job is making progress.
+const BACKGROUND_JOB_PAUSE: std::time::Duration = std::time::Duration::from_millis(200);
+
+/// How often to look for background work when there is none, or when the
+/// job cannot progress (no model, or the daemon holds the index).
+const BACKGROUND_JOB_IDLE: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Drive resumable background jobs (re-embedding after a model change)
+/// between tool calls, as the daemon does for its clients.
+///
+/// Steps use `try_lock`, so a tool call never waits behind more than one
+/// step. While the daemon holds the index it drives the job and the steps
+/// here are refused.
+pub fn spawn_background_job_runner(engine: Arc<Mutex<Engine>>) {
+    tokio::spawn(async move {
+        let mut delay = BACKGROUND_JOB_PAUSE;
+        let mut last_progress = None;
+        loop {
+            tokio::time::sleep(delay).await;
+            let Ok(mut eng) = engine.try_lock() else {
+                delay = BACKGROUND_JOB_PAUSE;
+                continue;
+            };
+            let step = eng.run_background_jobs(BACKGROUND_JOB_STEP);
+            drop(eng);
+            delay = match step {
+                Ok(Some(job)) if job.state == omni_core::types::JobState::Running => {
+                    let progress = Some((job.id, job.done));
+                    let advanced = progress != last_progress;
+                    last_progress = progress;
+                    if advanced {
+                        BACKGROUND_JOB_PAUSE
+                    } else {
+                        BACKGROUND_JOB_IDLE
+                    }
+                }
+                Ok(_) | Err(omni_core::error::OmniError::IndexLocked { .. }) => BACKGROUND_JOB_IDLE,
+                Err(e) => {
+                    tracing::warn!(error = %e, "background job step failed");
+                    BACKGROUND_JOB_IDLE
+                }
+            };
+        }
+    });
+}
+
+impl OmniContextServer {
+    /// Start driving the engine's background jobs; see
+    /// [`spawn_background_job_runner`].
+    pub fn spawn_background_jobs(&self) {
+        spawn_background_job_runner(Arc::clone(&self.engine));
+    }
+}
+
 #[tool_router]
 impl OmniContextServer {
     /// Create a new MCP server backed by the given engine.
@@ -384,7 +624,7 @@ impl OmniContextServer {
 
     #[tool(
         name = "search_code",
-        description = "Search the codebase using hybrid retrieval (keyword + semantic). Returns ranked code chunks with file paths, scores, and source code. Use natural language queries like 'authentication middleware' or symbol names like 'validate_token'."
+        description = "Search the codebase using hybrid retrieval (keyword + semantic). Returns ranked code chunks with file paths, scores, and source code. Use natural language queries like 'authentication middleware' or symbol names like 'validate_token'. Keyword syntax supports \"quoted phrases\", AND/OR/NOT, and prefix* wildcards. Set case_sensitive and/or whole_word for literal identifier matching. Set group_by_file to get one section per file with its top chunks nested. Set exclude (e.g. 'tests protobuf') to push down results about what you do not want. Generated code (DO NOT EDIT / @generated) is left out unless include_generated is set."
     )]
     async fn search_code(
         &self,
@@ -396,37 +636,80 @@ impl OmniContextServer {
         let limit = clamp_limit(params.0.limit, 10);
         let min_score = clamp_rerank_score(params.0.min_rerank_score);
         let query = &params.0.query;
+        let exact = omni_core::search::exact::ExactMatchOptions {
+            case_sensitive: params.0.case_sensitive.unwrap_or(false),
+            whole_word: params.0.whole_word.unwrap_or(false),
+        };
         let engine = self.engine.lock().await;
 
-        match engine.search_with_rerank_threshold(query, limit, min_score) {
+        let options = omni_core::types::SearchOptions {
+            negative_query: params.0.exclude.clone().filter(|q| !q.trim().is_empty()),
+            include_generated: params.0.include_generated.unwrap_or(false),
+            ..omni_core::types::SearchOptions::default()
+        };
+        let results = if exact.is_active() {
+            engine.search_exact(query, limit, exact)
+        } else {
+            engine.search_with_options_and_threshold(query, limit, min_score, &options)
+        };
+
+        match results {
             Ok(results) => {
                 if results.is_empty() {
-                    let hint = if let Ok(status) = engine.status() {
-                        if status.files_indexed == 0 {
-                            "Repository not indexed. Run `omnicontext index .` first."
-                        } else if status.embedding_coverage_percent < 0.1 {
-                            "No results. Embedding coverage is 0% -- semantic search unavailable.\n\
-                             Run `omnicontext setup model-download` then `omnicontext index . --force`."
-                        } else {
-                            "No results found for this query."
-                        }
+                    let hint = if exact.is_active() {
+                        "No exact matches found for this query.".to_string()
                     } else {
-                        "No results found. Make sure the repository has been indexed with `omnicontext index .`"
+                        no_results_text(&engine, query, &options, min_score)
                     };
                     return Ok(CallToolResult::success(vec![Content::text(hint)]));
                 }
 
                 let mut output = String::new();
+                let skipped = &results[0].score_breakdown.skipped_signals;
+                if !skipped.is_empty() {
+                    writeln!(
+                        output,
+                        "_Skipped to stay within search.max_latency_ms: {}_\n",
+                        skipped.join(", ")
+                    )
+                    .ok();
+                }
+                if params.0.group_by_file == Some(true) {
+                    let per_file = params
+                        .0
+                        .chunks_per_file
+                        .unwrap_or(omni_core::search::grouping::DEFAULT_CHUNKS_PER_FILE)
+                        .clamp(1, MAX_LIMIT);
+                    let groups = omni_core::search::group_by_file(results, per_file);
+                    write_file_groups(&mut output, &groups);
+                    return Ok(CallToolResult::success(vec![Content::text(output)]));
+                }
                 for (i, result) in results.iter().enumerate() {
                     write!(
                         output,
-                        "## Result {} (score: {:.4})\n**File**: {}\n**Symbol**: {} ({:?})\n**Lines**: {}-{}\n",
-                        i + 1, result.score,
+                        "## Result {} ({})\n**File**: {}\n**Symbol**: {} ({:?})\n**Lines**: {}-{}\n",
+                        i + 1, score_label(result.score, result.relevance),
                         result.file_path.display(),
                         result.chunk.symbol_path, result.chunk.kind,
                         result.chunk.line_start, result.chunk.line_end,
                     )
                     .ok();
+                    let complexity = result.chunk.complexity;
+                    if complexity.is_computed() {
+                        writeln!(
+                            output,
+                            "**Complexity**: cyclomatic {}, nesting {}",
+                            complexity.cyclomatic, complexity.nesting_depth,
+                        )
+                        .ok();
+                    }
+                    if !result.owners.is_empty() {
+                        writeln!(output, "**Owners**: {}", result.owners.join(", ")).ok();
+                    }
+                    let provenance = result.provenance();
+                    if !provenance.is_empty() {
+                        writeln!(output, "**Via**: {}", provenance.join(", ")).ok();
+                    }
                     if let Some(ref doc) = result.chunk.doc_comment {
                         writeln!(output, "**Doc**: {doc}").ok();
                     }
@@ -457,16 +740,42 @@ impl OmniContextServer {
         let min_score = clamp_rerank_score(params.0.min_rerank_score);
         let query = &params.0.query;
         let want_shadow = params.0.shadow_headers;
+        let format = match params.0.format.as_deref() {
+            None => None,
+            Some(name) => Some(ContextFormat::parse(name).ok_or_else(|| {
+                McpError::invalid_params(
+                    format!(
+                        "format must be 'markdown', 'xml', 'json', or 'compact', got: '{name}'"
+                    ),
+                    None,
+                )
+            })?),
+        };
         let mut engine = self.engine.lock().await;
         let rules_prefix = engine.load_rules_prefix();
         let memory_prefix = engine.memory_prefix();
 
-        match engine.search_context_window_with_rerank_threshold(
-            query,
-            limit,
-            params.0.token_budget,
-            min_score,
-        ) {
+        let window = match params.0.candidates {
+            Some(candidates) => engine.search_context_window_with_options(
+                query,
+                limit,
+                min_score,
+                params.0.model.as_deref(),
+                &omni_core::types::SearchOptions {
+                    candidates: Some(candidates.clamp(1, MAX_CANDIDATES)),
+                    token_budget: params.0.token_budget,
+                    ..omni_core::types::SearchOptions::default()
+                },
+            ),
+            None => engine.search_context_window_for_model(
+                query,
+                limit,
+                params.0.token_budget,
+                min_score,
+                params.0.model.as_deref(),
+            ),
+        };
+        match window {
             Ok(mut ctx) => {
                 // Enrich with shadow headers if explicitly requested (overrides config)
                 if want_shadow == Some(true) {
@@ -474,11 +783,31 @@ impl OmniContextServer {
                 }
 
                 if ctx.is_empty() {
+                    let options = omni_core::types::SearchOptions {
+                        candidates: params.0.candidates.map(|c| c.clamp(1, MAX_CANDIDATES)),
+                        ..omni_core::types::SearchOptions::default()
+                    };
                     return Ok(CallToolResult::success(vec![Content::text(
-                        "No results found. Make sure the repository has been indexed.",
+                        no_results_text(&engine, query, &options, min_score),
                     )]));
                 }
 
+                if let Some(format) = format {
+                    let options = FormatOptions {
+                        format,
+                        query: query.clone(),
+                        ..FormatOptions::default()
+                    };
+                    let body = ContextFormatter::format(&ctx, &options);
+                    // Keep JSON parseable: rules and memory only frame text formats.
+                    let final_output = if format == ContextFormat::Json {
+                        body
+                    } else {
+                        format!("{rules_prefix}{memory_prefix}{body}")
+                    };
+                    return Ok(CallToolResult::success(vec![Content::text(final_output)]));
+                }
+
                 let mut output = format!(
                     "# Context Window ({} entries, {}/{} tokens used)\n\n",
                     ctx.len(),
@@ -506,10 +835,10 @@ impl OmniContextServer {
 
                     writeln!(
                         output,
-                        "### {} ({:?}, score: {:.4}){}",
+                        "### {} ({:?}, {}){}",
                         entry.chunk.symbol_path,
                         entry.chunk.kind,
-                        entry.score,
+                        score_label(entry.score, entry.relevance),
                         if entry.is_graph_neighbor {
                             " [via graph]"
                         } else {
@@ -561,32 +890,21 @@ impl OmniContextServer {
         let engine = self.engine.lock().await;
         let index = engine.metadata_index();
 
-        match index.get_symbol_by_fqn(name) {
-            Ok(Some(symbol)) => {
-                // Resolve file path from file_id
-                let file_path_str = index
-                    .get_file_by_id(symbol.file_id)
-                    .ok()
-                    .flatten()
-                    .map_or_else(
-                        || format!("file#{}", symbol.file_id),
-                        |f| f.path.display().to_string(),
-                    );
-
+        match engine.get_definition(name) {
+            Ok(Some(def)) => {
                 let mut output = format!(
-                    "## {} ({:?})\n**File**: {}\n**Line**: {}\n",
-                    symbol.fqn, symbol.kind, file_path_str, symbol.line
+                    "## {} ({:?})\n**File**: {}\n**Lines**: {}-{}\n",
+                    def.fqn,
+                    def.kind,
+                    def.file_path.display(),
+                    def.line_start,
+                    def.line_end
                 );
-
-                if let Some(chunk_id) = symbol.chunk_id {
-                    if let Ok(chunks) = index.get_chunks_for_file(symbol.file_id) {
-                        if let Some(chunk) = chunks.iter().find(|c| c.id == chunk_id) {
-                            if let Some(ref doc) = chunk.doc_comment {
-                                writeln!(output, "**Doc**: {doc}").ok();
-                            }
-                            write!(output, "```\n{}\n```\n", chunk.content).ok();
-                        }
-                    }
+                if let Some(ref doc) = def.doc_comment {
+                    writeln!(output, "**Doc**: {doc}").ok();
+                }
+                if !def.source.is_empty() {
+                    write!(output, "```\n{}\n```\n", def.source).ok();
                 }
                 Ok(CallToolResult::success(vec![Content::text(output)]))
             }
@@ -631,11 +949,6 @@ impl OmniContextServer {
     ) -> Result<CallToolResult, McpError> {
         use std::fmt::Write;
 
-        // Helper: strip Windows UNC prefix for consistent comparison
-        fn normalize_path_str(s: &str) -> &str {
-            s.strip_prefix(r"\\?\").unwrap_or(s)
-        }
-
         validate_relative_path(&params.0.path)?;
 
         let path_str = &params.0.path;
@@ -643,19 +956,21 @@ impl OmniContextServer {
         let index = engine.metadata_index();
         let repo_root = engine.repo_path();
 
-        // Build candidate paths to try:
-        // 1. As given (relative path)
-        // 2. Joined with repo root (absolute)
-        // 3. Canonicalized versions of both
+        // The index stores repo-relative paths and normalizes verbatim
+        // prefixes itself. Try the path as given, then relative to the repo
+        // root (as spelled and canonicalized), which also covers absolute
+        // paths from editors.
         let file_path = std::path::Path::new(path_str);
-        let absolute_path = if file_path.is_relative() {
-            repo_root.join(file_path)
-        } else {
-            file_path.to_path_buf()
-        };
-
-        // Try exact match first, then normalized absolute path
-        let candidates = [file_path.to_path_buf(), absolute_path.clone()];
+        let absolute_path = repo_root.join(file_path);
+        let mut candidates = vec![file_path.to_path_buf()];
+        candidates.extend(omni_core::paths::strip_root(&absolute_path, repo_root));
+        if let Ok(canonical) = omni_core::paths::canonicalize(&absolute_path) {
+            let root = omni_core::paths::canonicalize(repo_root);
+            candidates.extend(
+                root.ok()
+                    .and_then(|root| omni_core::paths::strip_root(&canonical, &root)),
+            );
+        }
 
         let mut file_info = None;
         for candidate in &candidates {
@@ -663,31 +978,6 @@ impl OmniContextServer {
                 file_info = Some(info);
                 break;
             }
-            let candidate_str = candidate.to_string_lossy();
-            let normalized = normalize_path_str(&candidate_str);
-            let norm_path = std::path::Path::new(normalized);
-            if norm_path != candidate.as_path() {
-                if let Ok(Some(info)) = index.get_file_by_path(norm_path) {
-                    file_info = Some(info);
-                    break;
-                }
-            }
-        }
-
-        // Last resort: try canonicalization
-        if file_info.is_none() {
-            if let Ok(canonical) = absolute_path.canonicalize() {
-                if let Ok(Some(info)) = index.get_file_by_path(&canonical) {
-                    file_info = Some(info);
-                } else {
-                    let canon_str = canonical.to_string_lossy();
-                    let norm = normalize_path_str(&canon_str);
-                    let norm_path = std::path::Path::new(norm);
-                    if let Ok(Some(info)) = index.get_file_by_path(norm_path) {
-                        file_info = Some(info);
-                    }
-                }
-            }
         }
 
         // Final fallback: normalize separators and do a suffix search.
@@ -744,6 +1034,210 @@ impl OmniContextServer {
         }
     }
 
+    #[tool(
+        name = "get_file",
+        description = "Read an indexed file within a token budget. The file is reassembled from its chunks in order; \
+                       when it does not fit, the least important chunks are replaced by one-line markers naming the \
+                       omitted symbols, while imports and other top-level lines are kept. Prefer this over reading \
+                       large files raw; use search_code or get_symbol to fetch an omitted symbol."
+    )]
+    async fn get_file(
+        &self,
+        params: Parameters<GetFileParams>,
+    ) -> Result<CallToolResult, McpError> {
+        use std::fmt::Write;
+
+        validate_relative_path(&params.0.path)?;
+        let max_tokens = params.0.max_tokens.unwrap_or(8_000).clamp(100, 200_000);
+        let engine = self.engine.lock().await;
+        let file = engine
+            .get_file(std::path::Path::new(&params.0.path), max_tokens)
+            .map_err(|e| McpError::internal_error(format!("get_file failed: {e}"), None))?;
+
+        let Some(file) = file else {
+            return Ok(CallToolResult::success(vec![Content::text(format!(
+                "File not found in index: '{}'. Try using relative path from repo root or ensure the file has been indexed.",
+                params.0.path
+            ))]));
+        };
+        let mut output = format!(
+            "## File: {}\n**Language**: {}\n**Tokens**: {} of {}\n",
+            file.file_path.display(),
+            file.language.as_str(),
+            file.token_count,
+            file.total_tokens
+        );
+        if file.stale {
+            output
+                .push_str("\n> The file changed since it was indexed; showing its current text.\n");
+        }
+        if !file.omitted.is_empty() {
+            writeln!(
+                output,
+                "\n{} range(s) omitted to fit {max_tokens} tokens:",
+                file.omitted.len()
+            )
+            .ok();
+            for range in &file.omitted {
+                writeln!(
+                    output,
+                    "- L{}-L{}: {}",
+                    range.line_start,
+                    range.line_end,
+                    range.symbols.join(", ")
+                )
+                .ok();
+            }
+        }
+        if file.truncated {
+            writeln!(output, "\nContent cut off at {max_tokens} tokens.").ok();
+        }
+        write!(
+            output,
+            "\n```{}\n{}\n```\n",
+            file.language.as_str(),
+            file.content
+        )
+        .ok();
+        Ok(CallToolResult::success(vec![Content::text(output)]))
+    }
+
+    #[tool(
+        name = "get_repo_overview",
+        description = "Get an orientation overview of the repository: language distribution, module map, top-level directory summaries, entry points, and the symbols the rest of the code depends on most. Call this first when dropped into an unfamiliar repository."
+    )]
+    async fn get_repo_overview(&self) -> Result<CallToolResult, McpError> {
+        use std::fmt::Write;
+
+        let engine = self.engine.lock().await;
+        let overview = engine
+            .repo_overview()
+            .map_err(|e| McpError::internal_error(format!("overview failed: {e}"), None))?;
+
+        if overview.files == 0 {
+            return Ok(CallToolResult::success(vec![Content::text(
+                "No files indexed. Run `omnicontext index .` first.",
+            )]));
+        }
+
+        let mut output = format!(
+            "## Repository Overview\n\n**Root**: {}\n**Files**: {} | **Symbols**: {}\n\n",
+            engine.repo_path().display(),
+            overview.files,
+            overview.symbols,
+        );
+        if let Some(ref summary) = overview.summary {
+            writeln!(output, "{summary}\n").ok();
+        }
+
+        writeln!(output, "### Languages\n").ok();
+        writeln!(output, "| Language | Files | Share |").ok();
+        writeln!(output, "|----------|-------|-------|").ok();
+        for lang in &overview.languages {
+            writeln!(
+                output,
+                "| {} | {} | {:.1}% |",
+                lang.language, lang.files, lang.percent
+            )
+            .ok();
+        }
+        output.push('\n');
+
+        if !overview.entry_points.is_empty() {
+            writeln!(output, "### Entry Points\n").ok();
+            for entry in &overview.entry_points {
+                writeln!(output, "- `{}` ({})", entry.path, entry.role).ok();
+            }
+            output.push('\n');
+        }
+
+        if !overview.directories.is_empty() {
+            writeln!(output, "### Top-Level Directories\n").ok();
+            for dir in &overview.directories {
+                writeln!(output, "- `{}/` -- {}", dir.path, dir.summary).ok();
+            }
+            output.push('\n');
+        }
+
+        if !overview.modules.is_empty() {
+            writeln!(output, "### Module Map\n").ok();
+            for module in &overview.modules {
+                let indent = "  ".repeat(module.path.matches('/').count());
+                writeln!(
+                    output,
+                    "{indent}- `{}` -- {} files ({})",
+                    module.path,
+                    module.files,
+                    module.languages.join(", ")
+                )
+                .ok();
+            }
+            output.push('\n');
+        }
+
+        if !overview.key_symbols.is_empty() {
+            writeln!(output, "### Key Symbols\n").ok();
+            for symbol in &overview.key_symbols {
+                writeln!(
+                    output,
+                    "- `{}` ({}) in `{}` -- {} dependents",
+                    symbol.fqn,
+                    symbol.kind.as_str(),
+                    symbol.file,
+                    symbol.dependents
+                )
+                .ok();
+            }
+        }
+
+        Ok(CallToolResult::success(vec![Content::text(output)]))
+    }
+
+    #[tool(
+        name = "get_module_summary",
+        description = "Get a generated summary of a directory or file: what it contains, its key symbols, and (for directories) its largest files and subdirectories. Start at the repository root (empty path) and drill down to orient in an unfamiliar codebase."
+    )]
+    async fn get_module_summary(
+        &self,
+        params: Parameters<GetModuleSummaryParams>,
+    ) -> Result<CallToolResult, McpError> {
+        use std::fmt::Write;
+
+        let path = params.0.path.trim();
+        if !path.is_empty() {
+            validate_relative_path(path)?;
+        }
+
+        let engine = self.engine.lock().await;
+        let summary = engine
+            .summarize(path)
+            .map_err(|e| McpError::internal_error(format!("summary lookup failed: {e}"), None))?;
+
+        match summary {
+            Some(summary) => {
+                let mut output = format!(
+                    "## {} `{}`\n\n{}\n",
+                    match summary.kind {
+                        omni_core::types::SummaryKind::File => "File",
+                        omni_core::types::SummaryKind::Directory => "Directory",
+                    },
+                    summary.path,
+                    summary.summary
+                );
+                if !summary.key_symbols.is_empty() {
+                    output.push_str("\n### Key symbols\n\n");
+                    for symbol in &summary.key_symbols {
+                        writeln!(output, "- `{symbol}`").ok();
+                    }
+                }
+                Ok(CallToolResult::success(vec![Content::text(output)]))
+            }
+            None => Ok(CallToolResult::success(vec![Content::text(format!(
+                "No summary for '{path}'. Check the path is relative to the repo root and has been indexed."
+            ))])),
+        }
+    }
+
     #[tool(
         name = "get_status",
         description = "Get the current status of the OmniContext engine: indexed files, chunks, symbols, vectors, and search mode."
@@ -783,6 +1277,60 @@ impl OmniContextServer {
                     s.graph_edges,
                 );
 
+                writeln!(
+                    output,
+                    "\n### Models\n\n\
+                     | Model | Name | Available | Size | Last latency |\n\
+                     |-------|------|-----------|------|--------------|"
+                )
+                .ok();
+                for (role, health) in [("Embedder", &s.embedder), ("Reranker", &s.reranker)] {
+                    #[allow(clippy::cast_precision_loss)]
+                    let size_mb = health.size_bytes as f64 / (1024.0 * 1024.0);
+                    writeln!(
+                        output,
+                        "| {role} | {}@{} | {} | {:.1} MB | {} |",
+                        health.name,
+                        health.revision,
+                        if health.available { "yes" } else { "no" },
+                        size_mb,
+                        health
+                            .last_latency_ms
+                            .map_or_else(|| "-".to_string(), |ms| format!("{ms:.1} ms")),
+                    )
+                    .ok();
+                }
+                #[allow(clippy::cast_precision_loss)]
+                let mb = |bytes: usize| bytes as f64 / (1024.0 * 1024.0);
+                let m = &s.memory;
+                writeln!(
+                    output,
+                    "\n### Memory (approx.)\n\n\
+                     | Component | Size |\n|-----------|------|\n\
+                     | Vector index | {:.2} MB |\n| Dependency graphs | {:.2} MB |\n\
+                     | Parse caches | {:.2} MB |\n| Total | {:.2} MB |",
+                    mb(m.vector_index_bytes),
+                    mb(m.dependency_graph_bytes),
+                    mb(m.parse_cache_bytes),
+                    mb(m.total_bytes),
+                )
+                .ok();
+                if let Some(limit) = m.vector_limit_bytes {
+                    writeln!(
+                        output,
+                        "\nVector memory limit: {:.0} MB ({} vectors spilled to disk)",
+                        mb(limit),
+                        m.spilled_vectors
+                    )
+                    .ok();
+                }
+                if !s.degraded_reasons.is_empty() {
+                    writeln!(output, "\n### Degraded\n").ok();
+                    for reason in &s.degraded_reasons {
+                        writeln!(output, "- {reason}").ok();
+                    }
+                }
+
                 // Language distribution
                 if !s.language_distribution.is_empty() {
                     writeln!(output, "\n### Language Distribution\n").ok();
@@ -790,9 +1338,44 @@ impl OmniContextServer {
                         writeln!(output, "- **{lang}**: {count} files").ok();
                     }
                 }
+                if !s.encoding_distribution.is_empty() {
+                    writeln!(output, "\n### Non-UTF-8 Files\n").ok();
+                    for (encoding, count) in &s.encoding_distribution {
+                        writeln!(output, "- **{encoding}**: {count} files").ok();
+                    }
+                }
+
+                let reembedding = s
+                    .background_jobs
+                    .iter()
+                    .any(|job| job.state == omni_core::types::JobState::Running);
+                if !s.background_jobs.is_empty() {
+                    writeln!(output, "\n### Background Jobs\n").ok();
+                    for job in &s.background_jobs {
+                        writeln!(
+                            output,
+                            "- **{}** ({}): {}/{} ({:.1}%){}",
+                            job.kind,
+                            job.state.as_str(),
+                            job.done,
+                            job.total,
+                            job.percent(),
+                            job.error
+                                .as_deref()
+                                .map(|e| format!(" - {e}"))
+                                .unwrap_or_default(),
+                        )
+                        .ok();
+                    }
+                }
 
                 // Diagnostic hints for embedding coverage
-                if s.embedding_coverage_percent < 0.1 && s.chunks_indexed > 0 {
+                if reembedding {
+                    output.push_str(
+                        "\n> Re-embedding in the background after a model change. Keyword search \
+                         is unaffected; semantic results fill in as it progresses.\n",
+                    );
+                } else if s.embedding_coverage_percent < 0.1 && s.chunks_indexed > 0 {
                     output.push_str(
                         "\n> **CRITICAL**: Embedding coverage is 0%. Semantic search is DISABLED.\n\
                          > Run `omnicontext setup model-download` then `omnicontext index . --force`.\n",
@@ -817,6 +1400,126 @@ impl OmniContextServer {
         }
     }
 
+    #[tool(
+        name = "get_package_dependencies",
+        description = "Get package-level dependencies parsed from Cargo.toml, package.json, go.mod, and pyproject.toml: which packages in this repository depend on a given crate/package/module, and what it depends on. Answers questions like 'which internal crates depend on omni-core' without walking symbols."
+    )]
+    async fn get_package_dependencies(
+        &self,
+        params: Parameters<GetPackageDependenciesParams>,
+    ) -> Result<CallToolResult, McpError> {
+        use std::fmt::Write;
+
+        let direction = params.0.direction.as_deref().unwrap_or("both");
+        if !matches!(direction, "dependencies" | "dependents" | "both") {
+            return Err(McpError::invalid_params(
+                format!(
+                    "direction must be 'dependencies', 'dependents', or 'both', got: '{direction}'"
+                ),
+                None,
+            ));
+        }
+        let transitive = params.0.transitive.unwrap_or(false);
+
+        let engine = self.engine.lock().await;
+        let graph = engine
+            .package_graph()
+            .map_err(|e| McpError::internal_error(format!("package graph failed: {e}"), None))?;
+
+        if graph.packages.is_empty() {
+            return Ok(CallToolResult::success(vec![Content::text(
+                "No package manifests (Cargo.toml, package.json, go.mod, pyproject.toml) found.",
+            )]));
+        }
+
+        let Some(name) = params.0.package.as_deref() else {
+            let mut output = format!(
+                "## Packages\n\n{} packages, {} internal dependency edges\n\n",
+                graph.packages.len(),
+                graph.internal_edge_count()
+            );
+            for package in &graph.packages {
+                let internal: Vec<&str> = package
+                    .dependencies
+                    .iter()
+                    .filter(|d| d.internal)
+                    .map(|d| d.name.as_str())
+                    .collect();
+                write!(
+                    output,
+                    "- `{}` ({}, `{}`)",
+                    package.name,
+                    package.ecosystem.as_str(),
+                    package.manifest
+                )
+                .ok();
+                if internal.is_empty() {
+                    output.push('\n');
+                } else {
+                    writeln!(output, " -> {}", internal.join(", ")).ok();
+                }
+            }
+            return Ok(CallToolResult::success(vec![Content::text(output)]));
+        };
+
+        let matches = graph.find(name);
+        let mut output = format!("## Package dependencies for `{name}`\n\n");
+
+        if direction == "dependencies" || direction == "both" {
+            output.push_str("### Depends on\n\n");
+            if matches.is_empty() {
+                output.push_str("_Not a package declared in this repository._\n\n");
+            }
+            for package in &matches {
+                writeln!(
+                    output,
+                    "From `{}` ({}):\n",
+                    package.manifest,
+                    package.ecosystem.as_str()
+                )
+                .ok();
+                if package.dependencies.is_empty() {
+                    output.push_str("_No dependencies declared._\n");
+                }
+                for dep in &package.dependencies {
+                    write!(output, "- `{}`", dep.name).ok();
+                    if let Some(ref req) = dep.requirement {
+                        write!(output, " {req}").ok();
+                    }
+                    let mut tags = Vec::new();
+                    if dep.scope != omni_core::graph::packages::DependencyScope::Normal {
+                        tags.push(dep.scope.as_str());
+                    }
+                    if dep.internal {
+                        tags.push("internal");
+                    }
+                    if !tags.is_empty() {
+                        write!(output, " [{}]", tags.join(", ")).ok();
+                    }
+                    output.push('\n');
+                }
+                output.push('\n');
+            }
+        }
+
+        if direction == "dependents" || direction == "both" {
+            output.push_str("### Depended on by\n\n");
+            let dependents = graph.dependents(name, transitive);
+            if dependents.is_empty() {
+                output.push_str("_No package in this repository depends on it._\n");
+            }
+            for (package, depth) in dependents {
+                write!(output, "- `{}` (`{}`)", package.name, package.manifest).ok();
+                if depth > 1 {
+                    write!(output, " -- indirect, {depth} hops").ok();
+                }
+                output.push('\n');
+            }
+        }
+
+        Ok(CallToolResult::success(vec![Content::text(output)]))
+    }
+
     #[tool(
         name = "get_dependencies",
         description = "Get dependency relationships for a symbol: upstream (what it depends on) and downstream (what depends on it). Uses the dependency graph built during indexing."
@@ -1001,10 +1704,14 @@ impl OmniContextServer {
                 for (i, result) in results.iter().enumerate() {
                     write!(
                         output,
-                        "### Example {} -- {} (score: {:.4})\n**{:?}** `{}` (L{}-L{})\n```\n{}\n```\n\n",
-                        i + 1, result.file_path.display(), result.score,
-                        result.chunk.kind, result.chunk.symbol_path,
-                        result.chunk.line_start, result.chunk.line_end,
+                        "### Example {} -- {} ({})\n**{:?}** `{}` (L{}-L{})\n```\n{}\n```\n\n",
+                        i + 1,
+                        result.file_path.display(),
+                        score_label(result.score, result.relevance),
+                        result.chunk.kind,
+                        result.chunk.symbol_path,
+                        result.chunk.line_start,
+                        result.chunk.line_end,
                         result.chunk.content,
                     )
                     .ok();
@@ -1166,6 +1873,11 @@ impl OmniContextServer {
             "- `get_symbol` -- exact symbol lookup with source code"
         )
         .ok();
+        writeln!(
+            output,
+            "- `get_repo_overview` -- languages, entry points, and key symbols at a glance"
+        )
+        .ok();
         writeln!(output, "- `get_file_summary` -- file structure breakdown").ok();
         writeln!(output, "- `get_module_map` -- project module hierarchy").ok();
         writeln!(
@@ -1174,6 +1886,11 @@ impl OmniContextServer {
         )
         .ok();
         writeln!(output, "- `get_dependencies` -- symbol dependency analysis").ok();
+        writeln!(
+            output,
+            "- `get_package_dependencies` -- crate/package/module dependency graph"
+        )
+        .ok();
         writeln!(output, "- `get_blast_radius` -- change impact analysis").ok();
         writeln!(
             output,
@@ -1350,8 +2067,14 @@ impl OmniContextServer {
                     // Fall back to original query (without synonym expansion)
                     match engine.search(query, limit) {
                         Ok(results) if results.is_empty() => {
+                            let diagnostics = no_results_text(
+                                &engine,
+                                query,
+                                &omni_core::types::SearchOptions::default(),
+                                None,
+                            );
                             return Ok(CallToolResult::success(vec![Content::text(format!(
-                                "No results found for: '{query}'\n\n\
+                                "{diagnostics}\n\n\
                                  **Intent**: {intent:?}\n\
                                  **Expanded to**: '{expanded_query}'\n\
                                  **Strategy**: graph_depth={}, include_tests={}, include_architecture={}",
@@ -1375,13 +2098,13 @@ impl OmniContextServer {
                                 let breakdown = &r.score_breakdown;
                                 write!(
                                     output,
-                                    "### {} (score: {:.4})\n\
+                                    "### {} ({})\n\
                                      **File**: {}\n\
                                      **Symbol**: {} ({:?})\n\
                                      **Score breakdown**: semantic_rank={}, keyword_rank={}, rrf={:.3}, \
                                      reranker={:.3}, struct_w={:.2}, dep_boost={:.2}, recency={:.2}\n\
                                      ```\n{}\n```\n\n",
-                                    i + 1, r.score, r.file_path.display(),
+                                    i + 1, score_label(r.score, r.relevance), r.file_path.display(),
                                     r.chunk.symbol_path, r.chunk.kind,
                                     breakdown.semantic_rank.map_or("N/A".to_string(), |r| r.to_string()),
                                     breakdown.keyword_rank.map_or("N/A".to_string(), |r| r.to_string()),
@@ -1539,7 +2262,7 @@ impl OmniContextServer {
 
     #[tool(
         name = "get_blast_radius",
-        description = "Analyze the impact of changing a symbol. Returns all code that would be transitively affected if the given symbol is modified -- answers 'what breaks if I change this?'. Results are sorted by proximity (closest affected first)."
+        description = "Analyze the impact of changing a symbol. Returns all code that would be transitively affected if the given symbol is modified -- answers 'what breaks if I change this?'. Results are sorted by proximity (closest affected first), followed by the tests linked to the affected code."
     )]
     async fn get_blast_radius(
         &self,
@@ -1581,8 +2304,11 @@ impl OmniContextServer {
         let affected = graph
             .blast_radius(symbol.id, max_depth)
             .map_err(|e| McpError::internal_error(format!("blast radius failed: {e}"), None))?;
+        let tests = graph
+            .tests_for(symbol.id, max_depth)
+            .map_err(|e| McpError::internal_error(format!("test lookup failed: {e}"), None))?;
 
-        if affected.is_empty() {
+        if affected.is_empty() && tests.is_empty() {
             return Ok(CallToolResult::success(vec![Content::text(format!(
                 "## Blast Radius: {}\nNo downstream dependents found. This symbol can be safely modified in isolation.",
                 symbol.fqn
@@ -1629,6 +2355,30 @@ impl OmniContextServer {
             }
         }
 
+        if !tests.is_empty() {
+            writeln!(output, "\n### Tests to run ({})", tests.len()).ok();
+            for (test_id, distance) in &tests {
+                let Ok(Some(sym)) = index.get_symbol_by_id(*test_id) else {
+                    continue;
+                };
+                let file = index
+                    .get_file_by_id(sym.file_id)
+                    .ok()
+                    .flatten()
+                    .map(|f| f.path.display().to_string())
+                    .unwrap_or_default();
+                let via = if *distance == 0 {
+                    "direct".to_string()
+                } else {
+                    format!(
+                        "via {distance} hop{}",
+                        if *distance == 1 { "" } else { "s" }
+                    )
+                };
+                writeln!(output, "- **{}** ({file}:{}) -- {via}", sym.fqn, sym.line).ok();
+            }
+        }
+
         Ok(CallToolResult::success(vec![Content::text(output)]))
     }
 
@@ -2206,7 +2956,8 @@ impl OmniContextServer {
     // -----------------------------------------------------------------------
     #[tool(
         name = "search_with_filter",
-        description = "Search the codebase with post-filter criteria: language, path glob, indexed-after datetime, and symbol type. \
+        description = "Search the codebase with filter criteria: language, path glob, symbol type, modification time \
+                       (modified_after / modified_before), file size (min_size_bytes / max_size_bytes), and an explicit file list. \
                        All filters are optional and ANDed. Example: find auth logic in src/backend/ written in Rust, \
                        returning only function-level chunks. Use this for scoped, precise searches within large codebases."
     )]
@@ -2220,44 +2971,75 @@ impl OmniContextServer {
         let limit = clamp_limit(params.0.limit, 10);
         let min_score = clamp_rerank_score(params.0.min_rerank_score);
         let p = &params.0;
+        let options = omni_core::types::SearchOptions {
+            modified_after: p.modified_after.clone(),
+            modified_before: p.modified_before.clone(),
+            min_size_bytes: p.min_size_bytes,
+            max_size_bytes: p.max_size_bytes,
+            files: p
+                .files
+                .iter()
+                .flatten()
+                .map(std::path::PathBuf::from)
+                .collect(),
+            language: p.language.clone(),
+            kind: p.symbol_type.clone(),
+            path_glob: p.path_glob.clone(),
+            candidates: p.candidates.map(|c| c.clamp(1, MAX_CANDIDATES)),
+            token_budget: None,
+            negative_query: p.exclude.clone().filter(|q| !q.trim().is_empty()),
+            include_generated: p.include_generated.unwrap_or(false),
+        };
         let engine = self.engine.lock().await;
 
-        match engine.search_filtered(
-            &p.query,
-            limit,
-            min_score,
-            p.language.as_deref(),
-            p.path_glob.as_deref(),
-            p.modified_after.as_deref(),
-            p.symbol_type.as_deref(),
-        ) {
+        match engine.search_with_options_and_threshold(&p.query, limit, min_score, &options) {
             Ok(results) => {
                 if results.is_empty() {
                     return Ok(CallToolResult::success(vec![Content::text(
-                        "No results matched the query and filters. \
-                         Try relaxing the language/path/symbol_type filters, or run `omnicontext index .` first.",
+                        no_results_text(&engine, &p.query, &options, min_score),
                     )]));
                 }
 
                 let mut output = format!(
                     "## Filtered Search Results ({} results)\n\
                      **Query**: `{}`\n\
-                     **Filters**: language={} path={} symbol_type={}\n\n",
+                     **Filters**: language={} path={} symbol_type={}\n",
                     results.len(),
                     p.query,
                     p.language.as_deref().unwrap_or("any"),
                     p.path_glob.as_deref().unwrap_or("*"),
                     p.symbol_type.as_deref().unwrap_or("any"),
                 );
+                if !options.is_empty() {
+                    writeln!(
+                        output,
+                        "**File filters**: modified_after={} modified_before={} size={}..{} files={}",
+                        options.modified_after.as_deref().unwrap_or("any"),
+                        options.modified_before.as_deref().unwrap_or("any"),
+                        options.min_size_bytes.map_or("0".into(), |n| n.to_string()),
+                        options.max_size_bytes.map_or("any".into(), |n| n.to_string()),
+                        options.files.len(),
+                    )
+                    .ok();
+                }
+                output.push('\n');
 
                 for (i, r) in results.iter().enumerate() {
                     write!(
                         output,
-                        "### {} (score: {:.4})\n**File**: {}\n**Symbol**: `{}` ({:?})\n**Lines**: {}-{}\n",
-                        i + 1, r.score, r.file_path.display(),
-                        r.chunk.symbol_path, r.chunk.kind,
-                        r.chunk.line_start, r.chunk.line_end,
-                    ).ok();
+                        "### {} ({})\n**File**: {}\n**Symbol**: `{}` ({:?})\n**Lines**: {}-{}\n",
+                        i + 1,
+                        score_label(r.score, r.relevance),
+                        r.file_path.display(),
+                        r.chunk.symbol_path,
+                        r.chunk.kind,
+                        r.chunk.line_start,
+                        r.chunk.line_end,
+                    )
+                    .ok();
+                    if !r.owners.is_empty() {
+                        writeln!(output, "**Owners**: {}", r.owners.join(", ")).ok();
+                    }
                     if let Some(ref doc) = r.chunk.doc_comment {
                         writeln!(output, "**Doc**: {}", doc.lines().next().unwrap_or("")).ok();
                     }
@@ -2266,6 +3048,10 @@ impl OmniContextServer {
 
                 Ok(CallToolResult::success(vec![Content::text(output)]))
             }
+            Err(e @ omni_core::error::OmniError::Config { .. }) => Err(McpError::invalid_params(
+                format!("search_with_filter failed: {e}"),
+                None,
+            )),
             Err(e) => Err(McpError::internal_error(
                 format!("search_with_filter failed: {e}"),
                 None,
@@ -2539,6 +3325,7 @@ impl OmniContextServer {
                                 "line_start": e.line_start,
                                 "line_end": e.line_end,
                                 "score": e.score,
+                                "relevance": e.relevance,
                                 "token_count": e.token_count,
                                 "content": e.content,
                             })
@@ -2620,10 +3407,10 @@ impl OmniContextServer {
                         }
                         writeln!(
                             output,
-                            "### `{}` ({:?}, {:.4}, {} tokens){}",
+                            "### `{}` ({:?}, {}, {} tokens){}",
                             entry.chunk.symbol_path,
                             entry.chunk.kind,
-                            entry.score,
+                            score_label(entry.score, entry.relevance),
                             entry.chunk.token_count,
                             if entry.is_graph_neighbor {
                                 " [graph]"
@@ -2692,9 +3479,9 @@ impl OmniContextServer {
                 for (i, r) in results.iter().enumerate() {
                     write!(
                         output,
-                        "### {} (score: {:.4})\n**File**: `{}`\n**Symbol**: `{}` ({:?})\n",
+                        "### {} ({})\n**File**: `{}`\n**Symbol**: `{}` ({:?})\n",
                         i + 1,
-                        r.score,
+                        score_label(r.score, r.relevance),
                         r.file_path.display(),
                         r.chunk.symbol_path,
                         r.chunk.kind,
@@ -2808,6 +3595,352 @@ impl OmniContextServer {
 
         Ok(CallToolResult::success(vec![Content::text(output)]))
     }
+
+    // -----------------------------------------------------------------------
+    // Tool 30 — resolve_stack_trace
+    // -----------------------------------------------------------------------
+    #[tool(
+        name = "resolve_stack_trace",
+        description = "Resolve a stack trace to the code it passes through. Paste a Rust panic or backtrace, \
+                       a Python traceback, or a JavaScript stack as printed; each frame is mapped to the \
+                       indexed function enclosing its line (paths from containers or other machines match \
+                       by suffix), and those functions are returned innermost first with their graph \
+                       neighbours, packed within a token budget. Use this first when debugging an error."
+    )]
+    async fn resolve_stack_trace(
+        &self,
+        params: Parameters<ResolveStackTraceParams>,
+    ) -> Result<CallToolResult, McpError> {
+        use std::fmt::Write;
+
+        let trace = &params.0.trace;
+        if trace.trim().is_empty() {
+            return Err(McpError::invalid_params("trace must not be empty", None));
+        }
+        if trace.len() > MAX_TRACE_LEN {
+            return Err(McpError::invalid_params(
+                format!("trace exceeds maximum length of {MAX_TRACE_LEN} characters"),
+                None,
+            ));
+        }
+        let engine = self.engine.lock().await;
+        let context = engine
+            .resolve_stack_trace(trace, params.0.token_budget)
+            .map_err(|e| {
+                McpError::internal_error(format!("resolve_stack_trace failed: {e}"), None)
+            })?;
+
+        if context.frames.is_empty() {
+            return Ok(CallToolResult::success(vec![Content::text(
+                "No stack frames found. Expected a Rust panic or backtrace, a Python traceback, \
+                 or a JavaScript stack.",
+            )]));
+        }
+
+        let resolved = context
+            .frames
+            .iter()
+            .filter(|f| f.symbol_path.is_some())
+            .count();
+        let mut output = format!(
+            "# Stack Trace Context ({resolved} of {} frames resolved, {}/{} tokens used)\n\n## Frames\n",
+            context.frames.len(),
+            context.window.total_tokens,
+            context.window.token_budget
+        );
+        for (i, resolved) in context.frames.iter().enumerate() {
+            let frame = &resolved.frame;
+            let function = frame.function.as_deref().unwrap_or("?");
+            let location = match (&frame.file, frame.line) {
+                (Some(file), Some(line)) => format!(" at {file}:{line}"),
+                _ => String::new(),
+            };
+            let target = match (&resolved.symbol_path, &resolved.file_path) {
+                (Some(symbol), Some(path)) => format!("`{symbol}` in {}", path.display()),
+                (None, Some(path)) => format!("{} (no enclosing chunk)", path.display()),
+                _ => "not indexed".to_string(),
+            };
+            writeln!(output, "{}. `{function}`{location} → {target}", i + 1).ok();
+        }
+
+        let mut current_file: Option<&std::path::Path> = None;
+        for entry in &context.window.entries {
+            if current_file != Some(&entry.file_path) {
+                write!(output, "\n## {}\n", entry.file_path.display()).ok();
+                current_file = Some(&entry.file_path);
+            }
+            writeln!(
+                output,
+                "### {} (L{}-L{}){}",
+                entry.chunk.symbol_path,
+                entry.chunk.line_start,
+                entry.chunk.line_end,
+                if entry.is_graph_neighbor {
+                    " [via graph]"
+                } else {
+                    ""
+                },
+            )
+            .ok();
+            write!(output, "```\n{}\n```\n\n", entry.chunk.content).ok();
+        }
+
+        Ok(CallToolResult::success(vec![Content::text(output)]))
+    }
+
+    // -----------------------------------------------------------------------
+    // Tool 31 — context_for_diff
+    // -----------------------------------------------------------------------
+    #[tool(
+        name = "context_for_diff",
+        description = "Build review context for a unified diff. Paste `git diff` output (or a PR's patch); \
+                       each hunk is mapped to the indexed symbols it changes, and their definitions plus \
+                       1-hop graph neighbours (callers, callees) are returned within a token budget, most \
+                       changed symbols first. Use this when reviewing a change."
+    )]
+    async fn context_for_diff(
+        &self,
+        params: Parameters<ContextForDiffParams>,
+    ) -> Result<CallToolResult, McpError> {
+        use std::fmt::Write;
+
+        let diff = &params.0.diff;
+        if diff.trim().is_empty() {
+            return Err(McpError::invalid_params("diff must not be empty", None));
+        }
+        if diff.len() > MAX_DIFF_LEN {
+            return Err(McpError::invalid_params(
+                format!("diff exceeds maximum length of {MAX_DIFF_LEN} characters"),
+                None,
+            ));
+        }
+        let engine = self.engine.lock().await;
+        let context = engine
+            .context_for_diff(diff, params.0.token_budget)
+            .map_err(|e| McpError::internal_error(format!("context_for_diff failed: {e}"), None))?;
+
+        if context.files.is_empty() {
+            return Ok(CallToolResult::success(vec![Content::text(
+                "No file changes found. Expected a unified diff (`git diff` or `diff -u` output).",
+            )]));
+        }
+
+        let mut output = format!(
+            "# Diff Context ({} files, {} changed symbols, {}/{} tokens used)\n\n## Changed symbols\n",
+            context.files.len(),
+            context.symbols.len(),
+            context.window.total_tokens,
+            context.window.token_budget
+        );
+        for symbol in &context.symbols {
+            writeln!(
+                output,
+                "- `{}` ({}) in {} L{}-L{}: {} lines changed",
+                symbol.symbol_path,
+                symbol.kind,
+                symbol.file_path.display(),
+                symbol.line_start,
+                symbol.line_end,
+                symbol.changed_lines
+            )
+            .ok();
+        }
+        if !context.unindexed_files.is_empty() {
+            writeln!(
+                output,
+                "\nNot indexed (deleted, new, or not source): {}",
+                context.unindexed_files.join(", ")
+            )
+            .ok();
+        }
+
+        let mut current_file: Option<&std::path::Path> = None;
+        for entry in &context.window.entries {
+            if current_file != Some(&entry.file_path) {
+                write!(output, "\n## {}\n", entry.file_path.display()).ok();
+                current_file = Some(&entry.file_path);
+            }
+            writeln!(
+                output,
+                "### {} (L{}-L{}){}",
+                entry.chunk.symbol_path,
+                entry.chunk.line_start,
+                entry.chunk.line_end,
+                if entry.is_graph_neighbor {
+                    " [via graph]"
+                } else {
+                    ""
+                },
+            )
+            .ok();
+            write!(output, "```\n{}\n```\n\n", entry.chunk.content).ok();
+        }
+
+        Ok(CallToolResult::success(vec![Content::text(output)]))
+    }
+
+    // -----------------------------------------------------------------------
+    // Tool 32 — recent_changes
+    // -----------------------------------------------------------------------
+    #[tool(
+        name = "recent_changes",
+        description = "Orient on in-flight work before editing: the files and indexed symbols changed by \
+                       the last N commits (default 10) or the last N days, with the commit messages that \
+                       touched each file. Symbols are resolved against the current index, most changed first."
+    )]
+    async fn recent_changes(
+        &self,
+        params: Parameters<RecentChangesParams>,
+    ) -> Result<CallToolResult, McpError> {
+        use std::fmt::Write;
+
+        let days = params.0.days;
+        let default_commits = if days.is_some() { MAX_COMMIT_COUNT } else { 10 };
+        let commits = params
+            .0
+            .commits
+            .unwrap_or(default_commits)
+            .clamp(1, MAX_COMMIT_COUNT);
+        let engine = self.engine.lock().await;
+        let changes = engine
+            .recent_changes(commits, days)
+            .map_err(|e| McpError::internal_error(format!("recent_changes failed: {e}"), None))?;
+
+        if changes.commits.is_empty() {
+            return Ok(CallToolResult::success(vec![Content::text(
+                "No recent commits found. The repository may have no history in that window, \
+                 or may not be a git repository.",
+            )]));
+        }
+
+        let span = match days {
+            Some(days) => format!("last {days} days"),
+            None => format!("last {} commits", changes.commits.len()),
+        };
+        let mut output = format!(
+            "# Recent Changes ({span}: {} commits, {} files, {} symbols)\n\n## Commits\n",
+            changes.commits.len(),
+            changes.files.len(),
+            changes.symbols.len()
+        );
+        for commit in &changes.commits {
+            let hash_short = &commit.hash[..8.min(commit.hash.len())];
+            writeln!(
+                output,
+                "- `{hash_short}` {} ({}, {})",
+                commit.message, commit.author, commit.timestamp
+            )
+            .ok();
+        }
+
+        output.push_str("\n## Files\n");
+        for file in changes.files.iter().take(50) {
+            writeln!(
+                output,
+                "- `{}` ({} commit{}): {}",
+                file.path,
+                file.commits,
+                if file.commits == 1 { "" } else { "s" },
+                file.messages.join("; ")
+            )
+            .ok();
+        }
+        if changes.files.len() > 50 {
+            writeln!(output, "- ... and {} more files", changes.files.len() - 50).ok();
+        }
+
+        if !changes.symbols.is_empty() {
+            output.push_str("\n## Changed symbols\n");
+            for symbol in changes.symbols.iter().take(50) {
+                writeln!(
+                    output,
+                    "- `{}` ({}) in {} L{}-L{}: {} lines changed",
+                    symbol.symbol_path,
+                    symbol.kind,
+                    symbol.file_path.display(),
+                    symbol.line_start,
+                    symbol.line_end,
+                    symbol.changed_lines
+                )
+                .ok();
+            }
+            if changes.symbols.len() > 50 {
+                writeln!(
+                    output,
+                    "- ... and {} more symbols",
+                    changes.symbols.len() - 50
+                )
+                .ok();
+            }
+        }
+
+        Ok(CallToolResult::success(vec![Content::text(output)]))
+    }
+
+    // -----------------------------------------------------------------------
+    // Tool 33 — get_todos
+    // -----------------------------------------------------------------------
+    #[tool(
+        name = "get_todos",
+        description = "List TODO, FIXME, and HACK comments recorded at index time, with file:line, \
+                       the assignee from `TODO(name):`, and the author and age of the line from git blame. \
+                       Scope to the file or directory you are about to edit to surface the tech debt there."
+    )]
+    async fn get_todos(
+        &self,
+        params: Parameters<GetTodosParams>,
+    ) -> Result<CallToolResult, McpError> {
+        use std::fmt::Write;
+
+        let tag = params.0.tag.as_deref().map(str::to_ascii_uppercase);
+        if let Some(ref tag) = tag {
+            if !omni_core::parser::todos::MARKERS.contains(&tag.as_str()) {
+                return Err(McpError::invalid_params(
+                    format!("tag must be one of {:?}", omni_core::parser::todos::MARKERS),
+                    None,
+                ));
+            }
+        }
+        let limit = clamp_limit(params.0.limit, 50);
+        let engine = self.engine.lock().await;
+        let todos = engine
+            .todos(params.0.path.as_deref(), tag.as_deref(), limit)
+            .map_err(|e| McpError::internal_error(format!("get_todos failed: {e}"), None))?;
+
+        if todos.is_empty() {
+            return Ok(CallToolResult::success(vec![Content::text(
+                "No TODO, FIXME, or HACK comments found.",
+            )]));
+        }
+
+        let mut output = format!("# Tech-debt comments ({})\n\n", todos.len());
+        for todo in &todos {
+            write!(
+                output,
+                "- **{}** `{}:{}`",
+                todo.tag,
+                todo.file_path.display(),
+                todo.line
+            )
+            .ok();
+            if let Some(ref assignee) = todo.assignee {
+                write!(output, " ({assignee})").ok();
+            }
+            write!(output, " {}", todo.text).ok();
+            match (&todo.author, todo.age_days) {
+                (Some(author), Some(days)) => {
+                    write!(output, " -- {author}, {days} days old").ok();
+                }
+                (Some(author), None) => {
+                    write!(output, " -- {author}").ok();
+                }
+                _ => {}
+            }
+            output.push('\n');
+        }
+
+        Ok(CallToolResult::success(vec![Content::text(output)]))
+    }
 }
 
 /// SSE transport bridge — only compiled when the `sse` feature is enabled.
@@ -2828,6 +3961,12 @@ impl OmniContextServer {
         }
     }
 
+    /// The shared engine, for HTTP endpoints that return engine results
+    /// as JSON rather than as tool output.
+    pub(crate) fn engine(&self) -> &Arc<Mutex<Engine>> {
+        &self.engine
+    }
+
     /// Invoke a tool by name with JSON arguments and return the serialised result.
     ///
     /// Used by the SSE transport dispatcher to call tools without going through
@@ -2880,6 +4019,9 @@ impl OmniContextServer {
             "get_symbol" => call_with_params!(GetSymbolParams, get_symbol),
             "get_module_map" => call_with_params!(GetModuleMapParams, get_module_map),
             "get_dependencies" => call_with_params!(GetDependenciesParams, get_dependencies),
+            "get_package_dependencies" => {
+                call_with_params!(GetPackageDependenciesParams, get_package_dependencies)
+            }
             "get_blast_radius" => call_with_params!(GetBlastRadiusParams, get_blast_radius),
             "get_call_graph" => call_with_params!(GetCallGraphParams, get_call_graph),
             "get_recent_changes" => call_with_params!(GetRecentChangesParams, get_recent_changes),
@@ -2905,6 +4047,12 @@ impl OmniContextServer {
             "save_memory" => call_with_params!(SaveMemoryParams, save_memory),
             "get_memory" => call_with_params!(GetMemoryParams, get_memory),
             "list_memory" => call_with_params!(ListMemoryParams, list_memory),
+            "resolve_stack_trace" => {
+                call_with_params!(ResolveStackTraceParams, resolve_stack_trace)
+            }
+            "context_for_diff" => call_with_params!(ContextForDiffParams, context_for_diff),
+            "recent_changes" => call_with_params!(RecentChangesParams, recent_changes),
+            "get_todos" => call_with_params!(GetTodosParams, get_todos),
             "audit_plan" => call_with_params!(AuditPlanParams, audit_plan),
             "generate_manifest" => call_with_params!(GenerateManifestParams, generate_manifest),
             _ => Err(format!("unknown tool: {name}")),
@@ -2956,7 +4104,11 @@ impl ServerHandler for OmniContextServer {
                  multi_repo_search for cross-repository searches, \
                  save_memory to persist key-value pairs across sessions, \
                  get_memory to retrieve a stored value by key, \
-                 and list_memory to enumerate all stored memory keys with timestamps."
+                 list_memory to enumerate all stored memory keys with timestamps, \
+                 resolve_stack_trace to turn a panic, traceback, or JS stack into the code it passes through, \
+                 context_for_diff to gather the symbols a diff changes and their neighbours for review, \
+                 recent_changes to see the files and symbols changed by recent commits before editing, \
+                 and get_todos to list TODO/FIXME/HACK comments with their author and age."
                     .into(),
             ),
             capabilities: ServerCapabilities::builder().enable_tools().build(),
@@ -22,9 +22,16 @@
 //! [`crate::tools::OmniContextServer`] instance, bypassing the rmcp stdio
 //! transport layer.
 //!
+//! ## REST endpoints
+//!
+//! `POST /context/diff` takes `{"diff": "<unified diff>", "token_budget": n}`
+//! and returns [`omni_core::diff_context::DiffContext`] as JSON, for PR bots
+//! that want review context without speaking MCP.
+//!
 //! ## Authentication
 //!
-//! When `OMNI_SERVER_TOKEN` is set, both `/sse` and `/message` require an
+//! When `OMNI_SERVER_TOKEN` is set, `/sse`, `/message`, and the REST
+//! endpoints require an
 //! `Authorization: Bearer <token>` header. Without it, requests are rejected
 //! with `401 Unauthorized`. When the env var is absent, no auth is enforced
 //! (localhost-only assumption).
@@ -49,7 +56,7 @@ use tokio::sync::{mpsc, Mutex};
 use tokio_stream::wrappers::ReceiverStream;
 use uuid::Uuid;
 
-use crate::tools::OmniContextServer;
+use crate::tools::{OmniContextServer, MAX_DIFF_LEN};
 
 // ---------------------------------------------------------------------------
 // Configuration
@@ -490,6 +497,41 @@ async fn message_handler(
     StatusCode::ACCEPTED
 }
 
+/// Body of `POST /context/diff`.
+#[derive(Debug, Deserialize)]
+struct DiffContextRequest {
+    /// Unified diff, as printed by `git diff` or `diff -u`.
+    diff: String,
+    /// Token budget for the returned code (default: engine config).
+    token_budget: Option<u32>,
+}
+
+/// `POST /context/diff` — review context for a unified diff.
+///
+/// Returns the changed files and symbols and the packed context window as
+/// JSON. `400 Bad Request` for an empty or oversized diff.
+async fn diff_context_handler(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    axum::Json(request): axum::Json<DiffContextRequest>,
+) -> Result<axum::Json<omni_core::diff_context::DiffContext>, (StatusCode, String)> {
+    check_auth(&headers, &state.config).map_err(|code| (code, String::new()))?;
+    if request.diff.trim().is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "diff must not be empty".into()));
+    }
+    if request.diff.len() > MAX_DIFF_LEN {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("diff exceeds maximum length of {MAX_DIFF_LEN} characters"),
+        ));
+    }
+    let engine = state.server.engine().lock().await;
+    engine
+        .context_for_diff(&request.diff, request.token_budget)
+        .map(axum::Json)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
 /// `GET /health` — liveness probe.
 ///
 /// Returns `200 OK` with a JSON body. Authentication is not required;
@@ -541,6 +583,7 @@ async fn add_cors_headers(
 ///
 /// - `GET  /sse`     — SSE event stream (MCP transport channel)
 /// - `POST /message` — MCP JSON-RPC request intake
+/// - `POST /context/diff` — review context for a unified diff (JSON)
 /// - `GET  /health`  — liveness probe (no auth)
 ///
 /// The `engine` is shared (via `Arc<Mutex>`) across all active SSE sessions.
@@ -561,6 +604,7 @@ pub async fn serve(config: SseConfig, engine: Arc<Mutex<omni_core::Engine>>) ->
     let app = Router::new()
         .route("/sse", get(sse_handler))
         .route("/message", post(message_handler))
+        .route("/context/diff", post(diff_context_handler))
         .route("/health", get(health_handler))
         .layer(axum::middleware::from_fn(add_cors_headers))
         .with_state(state);
@@ -782,6 +826,7 @@ mod tests {
         Router::new()
             .route("/sse", get(sse_handler))
             .route("/message", post(message_handler))
+            .route("/context/diff", post(diff_context_handler))
             .route("/health", get(health_handler))
             .layer(axum::middleware::from_fn(add_cors_headers))
             .with_state(state)
@@ -948,6 +993,59 @@ mod tests {
         );
     }
 
+    // -----------------------------------------------------------------------
+    // /context/diff endpoint tests
+    // -----------------------------------------------------------------------
+
+    fn diff_request(body: &str, token: Option<&str>) -> Request<Body> {
+        let mut builder = Request::builder()
+            .method("POST")
+            .uri("/context/diff")
+            .header("content-type", "application/json");
+        if let Some(token) = token {
+            builder = builder.header("authorization", format!("Bearer {token}"));
+        }
+        builder.body(Body::from(body.to_string())).expect("request")
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_diff_context_returns_json() {
+        use axum::body::to_bytes;
+
+        let app = make_test_app(Some("tok"));
+        let body = serde_json::json!({
+            "diff": "--- a/src/new.rs\n+++ b/src/new.rs\n@@ -0,0 +1 @@\n+fn f() {}\n"
+        });
+        let response = app
+            .oneshot(diff_request(&body.to_string(), Some("tok")))
+            .await
+            .expect("response");
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = to_bytes(response.into_body(), 1 << 20)
+            .await
+            .expect("body bytes");
+        let body: serde_json::Value = serde_json::from_slice(&bytes).expect("json");
+        assert_eq!(body["files"][0]["path"], "src/new.rs");
+        assert_eq!(body["unindexed_files"][0], "src/new.rs");
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_diff_context_rejects_empty_diff_and_missing_auth() {
+        let app = make_test_app(Some("tok"));
+        let response = app
+            .clone()
+            .oneshot(diff_request(r#"{"diff": "  "}"#, Some("tok")))
+            .await
+            .expect("response");
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = app
+            .oneshot(diff_request(r#"{"diff": "x"}"#, None))
+            .await
+            .expect("response");
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
     // -----------------------------------------------------------------------
     // Dispatcher unit tests (no axum layer)
     // -----------------------------------------------------------------------
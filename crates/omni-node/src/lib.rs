@@ -0,0 +1,252 @@
+//! Node.js bindings for `OmniContext`.
+//!
+//! Builds a napi-rs addon so the VS Code extension and Node agents can run
+//! read-only queries in-process instead of paying the daemon IPC round trip:
+//!
+//! ```typescript
+//! import { Engine } from '@omnicontext/native';
+//!
+//! const engine = new Engine('/path/to/repo');
+//! const hits = await engine.search('token refresh', 5);
+//! const pack = await engine.getContextPack('token refresh', { tokenBudget: 8000 });
+//! const status = await engine.status();
+//! ```
+//!
+//! Only queries are exposed: indexing stays with the daemon (or the CLI),
+//! which owns the index writer lock. Queries run on the libuv thread pool
+//! and resolve to plain objects with the same fields as the MCP server's
+//! JSON output, so they never block the event loop.
+
+// `#[napi]` generates undocumented glue items; napi passes JS arguments by value.
+#![allow(
+    missing_docs,
+    clippy::missing_errors_doc,
+    clippy::needless_pass_by_value
+)]
+
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use napi::bindgen_prelude::AsyncTask;
+use napi::{Env, Error, JsUnknown, Result, Task};
+use napi_derive::napi;
+use serde_json::Value;
+
+/// Default number of search results.
+const DEFAULT_LIMIT: u32 = 10;
+/// Default number of results considered for a context pack.
+const DEFAULT_PACK_LIMIT: u32 = 50;
+/// Default context pack token budget (matches the MCP `context_window_pack` tool).
+const DEFAULT_TOKEN_BUDGET: u32 = 100_000;
+/// Largest result count a caller may request.
+const MAX_LIMIT: u32 = 200;
+
+/// Options for [`Engine::get_context_pack`].
+#[napi(object)]
+pub struct ContextPackOptions {
+    /// Search results considered before packing (default 50).
+    pub limit: Option<u32>,
+    /// Token budget for the pack (default 100000, clamped to 1000..=500000).
+    pub token_budget: Option<u32>,
+    /// Drop results the reranker scores below this.
+    pub min_rerank_score: Option<f64>,
+}
+
+/// A read-only handle on one repository's index.
+#[napi]
+pub struct Engine {
+    inner: Arc<Mutex<omni_core::Engine>>,
+}
+
+#[napi]
+impl Engine {
+    /// Open the index for `repoPath` read-only. The repository must already
+    /// be indexed. Loading the embedding model makes this slow; open one
+    /// engine per repository and reuse it.
+    #[napi(constructor)]
+    pub fn new(repo_path: String) -> Result<Self> {
+        // The index location is keyed by path; canonicalize as the CLI does.
+        let repo_path = Path::new(&repo_path);
+        let repo_path = repo_path
+            .canonicalize()
+            .unwrap_or_else(|_| repo_path.to_path_buf());
+        let engine = omni_core::Config::load(&repo_path)
+            .and_then(omni_core::Engine::open_for_queries)
+            .map_err(|e| Error::from_reason(e.to_string()))?;
+        Ok(Self {
+            inner: Arc::new(Mutex::new(engine)),
+        })
+    }
+
+    /// Hybrid search; resolves to results ordered best first.
+    #[napi(ts_return_type = "Promise<Array<Record<string, unknown>>>")]
+    #[must_use]
+    pub fn search(&self, query: String, limit: Option<u32>) -> AsyncTask<Query> {
+        self.query(Request::Search {
+            query,
+            limit: limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT),
+        })
+    }
+
+    /// Index statistics and health.
+    #[napi(ts_return_type = "Promise<Record<string, unknown>>")]
+    #[must_use]
+    pub fn status(&self) -> AsyncTask<Query> {
+        self.query(Request::Status)
+    }
+
+    /// Search, merge adjacent chunks, and greedily pack the best ones into a
+    /// token budget.
+    #[napi(
+        js_name = "getContextPack",
+        ts_return_type = "Promise<{ query: string, token_budget: number, total_tokens: number, entries: Array<Record<string, unknown>> }>"
+    )]
+    #[must_use]
+    pub fn get_context_pack(
+        &self,
+        query: String,
+        options: Option<ContextPackOptions>,
+    ) -> AsyncTask<Query> {
+        let options = options.unwrap_or(ContextPackOptions {
+            limit: None,
+            token_budget: None,
+            min_rerank_score: None,
+        });
+        self.query(Request::ContextPack {
+            query,
+            limit: options.limit.unwrap_or(DEFAULT_PACK_LIMIT).clamp(1, MAX_LIMIT),
+            token_budget: options
+                .token_budget
+                .unwrap_or(DEFAULT_TOKEN_BUDGET)
+                .clamp(1_000, 500_000),
+            #[allow(clippy::cast_possible_truncation)] // rerank scores are f32
+            min_rerank_score: options.min_rerank_score.map(|s| s as f32),
+        })
+    }
+}
+
+impl Engine {
+    fn query(&self, request: Request) -> AsyncTask<Query> {
+        AsyncTask::new(Query {
+            engine: Arc::clone(&self.inner),
+            request,
+        })
+    }
+}
+
+/// A read-only query.
+enum Request {
+    Search {
+        query: String,
+        limit: u32,
+    },
+    Status,
+    ContextPack {
+        query: String,
+        limit: u32,
+        token_budget: u32,
+        min_rerank_score: Option<f32>,
+    },
+}
+
+/// A query running on the libuv thread pool.
+pub struct Query {
+    engine: Arc<Mutex<omni_core::Engine>>,
+    request: Request,
+}
+
+impl Task for Query {
+    type Output = Value;
+    type JsValue = JsUnknown;
+
+    fn compute(&mut self) -> Result<Value> {
+        let engine = self
+            .engine
+            .lock()
+            .map_err(|_| Error::from_reason("engine poisoned by an earlier panic"))?;
+        run(&engine, &self.request).map_err(|e| Error::from_reason(e.to_string()))
+    }
+
+    fn resolve(&mut self, env: Env, output: Value) -> Result<JsUnknown> {
+        env.to_js_value(&output)
+    }
+}
+
+/// Execute `request` and shape its result as JSON.
+fn run(engine: &omni_core::Engine, request: &Request) -> omni_core::error::OmniResult<Value> {
+    let json = |value: serde_json::Result<Value>| {
+        value.map_err(|e| omni_core::error::OmniError::Serialization(e.to_string()))
+    };
+    match request {
+        Request::Search { query, limit } => {
+            json(serde_json::to_value(engine.search(query, *limit as usize)?))
+        }
+        Request::Status => json(serde_json::to_value(engine.status()?)),
+        Request::ContextPack {
+            query,
+            limit,
+            token_budget,
+            min_rerank_score,
+        } => {
+            let (entries, total_tokens) = engine
+                .pack_context_window(query, *limit as usize, *token_budget, *min_rerank_score)?;
+            Ok(serde_json::json!({
+                "query": query,
+                "token_budget": token_budget,
+                "total_tokens": total_tokens,
+                "entries": json(serde_json::to_value(entries))?,
+            }))
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_queries_return_json_shaped_for_js() {
+        std::env::set_var("OMNI_SKIP_MODEL_DOWNLOAD", "1");
+        std::env::set_var("OMNI_DISABLE_RERANKER", "1");
+        let dir = tempfile::tempdir().expect("tempdir");
+        std::fs::write(
+            dir.path().join("session.ts"),
+            "export function refreshSessionToken(token: string): string {\n  return token;\n}\n",
+        )
+        .expect("write");
+        let mut writer = omni_core::Engine::new(dir.path()).expect("engine");
+        writer.run_index(false).await.expect("index");
+        let config = omni_core::Config::load(dir.path()).expect("config");
+        let engine = omni_core::Engine::open_for_queries(config).expect("reader");
+
+        let hits = run(
+            &engine,
+            &Request::Search {
+                query: "refreshSessionToken".into(),
+                limit: 5,
+            },
+        )
+        .expect("search");
+        assert!(hits[0]["chunk"]["symbol_path"]
+            .as_str()
+            .is_some_and(|s| s.contains("refreshSessionToken")));
+
+        let pack = run(
+            &engine,
+            &Request::ContextPack {
+                query: "refreshSessionToken".into(),
+                limit: 10,
+                token_budget: 1_000,
+                min_rerank_score: None,
+            },
+        )
+        .expect("pack");
+        assert_eq!(pack["token_budget"], 1_000);
+        assert!(pack["total_tokens"].as_u64().is_some_and(|t| t > 0));
+        assert!(!pack["entries"].as_array().expect("entries").is_empty());
+
+        let status = run(&engine, &Request::Status).expect("status");
+        assert_eq!(status["files_indexed"], 1);
+    }
+}
@@ -0,0 +1,26 @@
+"""Type stubs for the `omnicontext` extension module."""
+
+from os import PathLike
+from types import TracebackType
+from typing import Any, Optional, Type, Union
+
+__version__: str
+
+class OmniContextError(Exception):
+    """Raised when an OmniContext engine operation fails."""
+
+class Engine:
+    """An OmniContext engine for one repository."""
+
+    def __init__(self, repo_path: Union[str, PathLike[str]]) -> None: ...
+    def index(self, force: bool = False) -> dict[str, Any]: ...
+    def search(self, query: str, limit: int = 10) -> list[dict[str, Any]]: ...
+    def status(self) -> dict[str, Any]: ...
+    def close(self) -> None: ...
+    def __enter__(self) -> "Engine": ...
+    def __exit__(
+        self,
+        exc_type: Optional[Type[BaseException]],
+        exc: Optional[BaseException],
+        tb: Optional[TracebackType],
+    ) -> bool: ...
@@ -0,0 +1,243 @@
+//! Python bindings for `OmniContext`.
+//!
+//! Builds the `omnicontext` extension module (via maturin, see
+//! `pyproject.toml`) so evaluation scripts and Python agent frameworks can
+//! drive the engine in-process instead of shelling out to the CLI.
+//!
+//! ## Usage
+//!
+//! ```python
+//! import omnicontext
+//!
+//! with omnicontext.Engine(".") as engine:
+//!     engine.index()
+//!     for hit in engine.search("embedding pipeline", limit=5):
+//!         print(hit["file_path"], hit["chunk"]["symbol_path"], hit["score"])
+//!     print(engine.status()["chunks_indexed"])
+//! ```
+//!
+//! Results are plain `dict`/`list` values with the same shape as the CLI's
+//! `--json` output. Failures raise `omnicontext.OmniContextError`. The GIL
+//! is released while the engine works, so other Python threads keep running.
+
+#![allow(clippy::missing_errors_doc)]
+
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard};
+
+use pyo3::create_exception;
+use pyo3::exceptions::PyException;
+use pyo3::prelude::*;
+
+create_exception!(
+    omnicontext,
+    OmniContextError,
+    PyException,
+    "Raised when an OmniContext engine operation fails."
+);
+
+/// An engine plus the runtime its async methods run on.
+struct Session {
+    engine: omni_core::Engine,
+    runtime: tokio::runtime::Runtime,
+}
+
+/// An `OmniContext` engine for one repository.
+///
+/// The engine is guarded by a mutex, so one `Engine` may be shared between
+/// Python threads; calls are serialized.
+#[pyclass(name = "Engine", module = "omnicontext")]
+struct PyEngine {
+    session: Mutex<Option<Session>>,
+}
+
+#[pymethods]
+impl PyEngine {
+    /// Open (or create) the index for the repository at `repo_path`.
+    #[new]
+    #[allow(clippy::needless_pass_by_value)] // pyo3 extracts arguments by value
+    fn new(py: Python<'_>, repo_path: PathBuf) -> PyResult<Self> {
+        let session = py.detach(|| -> Result<Session, String> {
+            let runtime = tokio::runtime::Builder::new_multi_thread()
+                .enable_all()
+                .build()
+                .map_err(|e| format!("failed to start runtime: {e}"))?;
+            // The index location is keyed by path; canonicalize as the CLI does.
+            let repo_path = repo_path.canonicalize().unwrap_or(repo_path);
+            let engine = omni_core::Engine::new(&repo_path).map_err(|e| e.to_string())?;
+            Ok(Session { engine, runtime })
+        });
+        Ok(Self {
+            session: Mutex::new(Some(session.map_err(OmniContextError::new_err)?)),
+        })
+    }
+
+    /// Index the repository; `force` clears existing state first.
+    ///
+    /// Returns a dict of counts (`files_processed`, `chunks_created`, ...).
+    #[pyo3(signature = (force = false))]
+    fn index(&self, py: Python<'_>, force: bool) -> PyResult<Py<PyAny>> {
+        let json = py.detach(|| {
+            let mut guard = self.lock()?;
+            let session = open_session(&mut guard)?;
+            let result = session
+                .runtime
+                .block_on(session.engine.run_index(force))
+                .map_err(|e| e.to_string())?;
+            to_json(&result)
+        });
+        from_json(py, &json.map_err(OmniContextError::new_err)?)
+    }
+
+    /// Hybrid search over the index. Returns a list of result dicts, best
+    /// first.
+    #[pyo3(signature = (query, limit = 10))]
+    fn search(&self, py: Python<'_>, query: &str, limit: usize) -> PyResult<Py<PyAny>> {
+        let json = py.detach(|| {
+            let mut guard = self.lock()?;
+            let session = open_session(&mut guard)?;
+            let results = session
+                .engine
+                .search(query, limit)
+                .map_err(|e| e.to_string())?;
+            to_json(&results)
+        });
+        from_json(py, &json.map_err(OmniContextError::new_err)?)
+    }
+
+    /// Index statistics and health as a dict.
+    fn status(&self, py: Python<'_>) -> PyResult<Py<PyAny>> {
+        let json = py.detach(|| {
+            let mut guard = self.lock()?;
+            let session = open_session(&mut guard)?;
+            let status = session.engine.status().map_err(|e| e.to_string())?;
+            to_json(&status)
+        });
+        from_json(py, &json.map_err(OmniContextError::new_err)?)
+    }
+
+    /// Persist pending state and release the index. Further calls raise
+    /// `OmniContextError`; closing twice is a no-op.
+    fn close(&self, py: Python<'_>) -> PyResult<()> {
+        py.detach(|| {
+            let mut guard = self.lock()?;
+            match guard.take() {
+                Some(mut session) => session.engine.shutdown().map_err(|e| e.to_string()),
+                None => Ok(()),
+            }
+        })
+        .map_err(OmniContextError::new_err)
+    }
+
+    fn __enter__(slf: Py<Self>) -> Py<Self> {
+        slf
+    }
+
+    #[pyo3(signature = (*_args))]
+    fn __exit__(&self, py: Python<'_>, _args: &Bound<'_, pyo3::types::PyTuple>) -> PyResult<bool> {
+        self.close(py)?;
+        Ok(false)
+    }
+}
+
+impl PyEngine {
+    fn lock(&self) -> Result<MutexGuard<'_, Option<Session>>, String> {
+        self.session
+            .lock()
+            .map_err(|_| "engine poisoned by an earlier panic".to_string())
+    }
+}
+
+fn open_session<'a>(
+    guard: &'a mut MutexGuard<'_, Option<Session>>,
+) -> Result<&'a mut Session, String> {
+    guard.as_mut().ok_or_else(|| "engine is closed".to_string())
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> Result<String, String> {
+    serde_json::to_string(value).map_err(|e| format!("serialization failed: {e}"))
+}
+
+/// Convert engine JSON into native Python objects.
+fn from_json(py: Python<'_>, json: &str) -> PyResult<Py<PyAny>> {
+    Ok(py.import("json")?.call_method1("loads", (json,))?.unbind())
+}
+
+/// The `omnicontext` Python module.
+#[pymodule]
+fn omnicontext(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyEngine>()?;
+    m.add("OmniContextError", m.py().get_type::<OmniContextError>())?;
+    m.add("__version__", env!("CARGO_PKG_VERSION"))?;
+    Ok(())
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    fn engine_for<'py>(py: Python<'py>, dir: &std::path::Path) -> Bound<'py, PyEngine> {
+        std::env::set_var("OMNI_SKIP_MODEL_DOWNLOAD", "1");
+        std::env::set_var("OMNI_DISABLE_RERANKER", "1");
+        Bound::new(py, PyEngine::new(py, dir.to_path_buf()).expect("engine")).expect("bind")
+    }
+
+    #[test]
+    fn test_index_search_and_status_return_python_objects() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        std::fs::write(
+            dir.path().join("ledger.py"),
+            "def reconcile_ledger(entries):\n    return sum(entries)\n",
+        )
+        .expect("write");
+
+        Python::initialize();
+        Python::attach(|py| {
+            let engine = engine_for(py, dir.path());
+
+            let result = engine.call_method0("index").expect("index");
+            let processed: usize = result
+                .get_item("files_processed")
+                .and_then(|v| v.extract())
+                .expect("files_processed");
+            assert_eq!(processed, 1);
+
+            let hits = engine
+                .call_method1("search", ("reconcile_ledger",))
+                .expect("search");
+            let first = hits.get_item(0).expect("at least one hit");
+            let symbol: String = first
+                .get_item("chunk")
+                .and_then(|c| c.get_item("symbol_path"))
+                .and_then(|s| s.extract())
+                .expect("symbol_path");
+            assert!(symbol.contains("reconcile_ledger"), "{symbol}");
+
+            let status = engine.call_method0("status").expect("status");
+            let files: usize = status
+                .get_item("files_indexed")
+                .and_then(|v| v.extract())
+                .expect("files_indexed");
+            assert_eq!(files, 1);
+        });
+    }
+
+    #[test]
+    fn test_closed_engine_raises_omnicontext_error() {
+        let dir = tempfile::tempdir().expect("tempdir");
+
+        Python::initialize();
+        Python::attach(|py| {
+            let engine = engine_for(py, dir.path());
+            engine.call_method0("close").expect("close");
+            engine
+                .call_method0("close")
+                .expect("second close is a no-op");
+
+            let err = engine.call_method0("status").expect_err("closed");
+            assert!(err.is_instance_of::<OmniContextError>(py), "{err}");
+            assert!(err.to_string().contains("engine is closed"), "{err}");
+        });
+    }
+}
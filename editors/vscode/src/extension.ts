@@ -1180,6 +1180,14 @@ async function connectIpc(repoRoot: string): Promise<void> {
         if (!line.trim()) continue;
         try {
           const response = JSON.parse(line);
+          if (response.method === "fileIndexed" && response.params) {
+            // Daemon push notification (no id): a file finished reindexing.
+            const { path, chunks, durationMs } = response.params;
+            outputChannel.appendLine(
+              `[ipc] indexed ${path} (${chunks} chunks, ${durationMs}ms)`,
+            );
+            continue;
+          }
           const pending = pendingRequests.get(response.id);
           if (pending) {
             pendingRequests.delete(response.id);
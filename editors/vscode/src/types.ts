@@ -57,6 +57,31 @@ export interface PreflightResponse {
   from_cache?: boolean;
 }
 
+/**
+ * Load state and recent cost of an ONNX model (embedder or reranker).
+ */
+export interface ModelHealth {
+  available: boolean;
+  name: string;
+  revision: string;
+  size_bytes: number;
+  last_latency_ms: number | null;
+  unavailable_reason: string | null;
+}
+
+/**
+ * Approximate heap memory held by the engine, by component.
+ */
+export interface MemoryUsage {
+  vector_index_bytes: number;
+  spilled_vectors: number;
+  dependency_graph_bytes: number;
+  parse_cache_bytes: number;
+  total_bytes: number;
+  /** `storage.memory_limit_mb` in bytes, when set. */
+  vector_limit_bytes: number | null;
+}
+
 /**
  * Engine status from daemon or CLI.
  */
@@ -72,6 +97,7 @@ export interface EngineStatus {
   graph_edges: number;
   has_cycles: boolean;
   language_distribution?: Record<string, number>;
+  encoding_distribution?: [string, number][];
 
   // Reranking metrics
   reranker_enabled?: boolean;
@@ -92,6 +118,15 @@ export interface EngineStatus {
   quantization_mode?: 'fp32' | 'fp16' | 'int8';
   memory_usage_mb?: number;
   throughput_chunks_per_sec?: number;
+
+  // Model health
+  embedder?: ModelHealth;
+  reranker?: ModelHealth;
+  /** Why the engine is running below full capability; empty when healthy. */
+  degraded_reasons?: string[];
+
+  // Memory
+  memory?: MemoryUsage;
 }
 
 /**